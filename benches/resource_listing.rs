@@ -0,0 +1,50 @@
+//! 比较每次列出资源时重新构建并序列化资源列表和使用缓存快照的耗时
+//!
+//! ```bash
+//! cargo bench --bench resource_listing
+//! ```
+
+use rustmcp::server::resources::ResourceManager;
+use rustmcp::FunctionResource;
+use serde_json::json;
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const RESOURCES: usize = 5_000;
+const ITERATIONS: u32 = 200;
+
+fn manager() -> ResourceManager {
+    let manager = ResourceManager::new();
+    for i in 0..RESOURCES {
+        let meta = HashMap::from([("owner".to_string(), json!({ "team": "docs", "tags": ["a", "b", "c"] }))]);
+        manager.add_resource(
+            FunctionResource::simple(format!("file:///docs/{}.md", i), || Ok(json!("")))
+                .with_title(format!("Document {}", i))
+                .with_meta(meta),
+        );
+    }
+    manager
+}
+
+fn measure(name: &str, mut run: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    let elapsed = start.elapsed();
+    println!("{:<16} {:>10.2?} per listing", name, elapsed / ITERATIONS);
+    elapsed
+}
+
+fn main() {
+    let manager = manager();
+
+    let rebuilt = measure("rebuild", || {
+        black_box(serde_json::to_value(black_box(&manager).list_resources()).unwrap());
+    });
+    let snapshot = measure("snapshot", || {
+        black_box(black_box(&manager).resource_listing().unwrap());
+    });
+    println!("speedup          {:>10.1}x", rebuilt.as_secs_f64() / snapshot.as_secs_f64());
+}
@@ -0,0 +1,71 @@
+//! 比较每次调用时编译模式和使用注册时编译的模式的参数校验耗时
+//!
+//! ```bash
+//! cargo bench --bench schema_validation
+//! ```
+
+use rustmcp::server::validation::CompiledSchema;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 20_000;
+
+/// 一个字段较多、带嵌套对象和数组的输入模式
+fn schema() -> Value {
+    let mut properties = serde_json::Map::new();
+    for i in 0..40 {
+        properties.insert(format!("field_{}", i), json!({ "type": "string", "description": "A text field" }));
+    }
+    properties.insert(
+        "filters".to_string(),
+        json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "column": { "type": "string" },
+                    "op": { "type": "string", "enum": ["eq", "ne", "lt", "gt", "like"] },
+                    "value": { "type": "number" }
+                },
+                "required": ["column", "op"]
+            }
+        }),
+    );
+    properties.insert("limit".to_string(), json!({ "type": "integer" }));
+    json!({ "type": "object", "properties": properties, "required": ["field_0", "filters"] })
+}
+
+fn arguments() -> HashMap<String, Value> {
+    let mut arguments: HashMap<String, Value> = (0..40).map(|i| (format!("field_{}", i), json!("value"))).collect();
+    let filters: Vec<Value> = (0..10).map(|i| json!({ "column": format!("c{}", i), "op": "eq", "value": i })).collect();
+    arguments.insert("filters".to_string(), Value::Array(filters));
+    arguments.insert("limit".to_string(), json!(50));
+    arguments
+}
+
+fn measure(name: &str, mut run: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    let elapsed = start.elapsed();
+    println!("{:<16} {:>10.2?} per call", name, elapsed / ITERATIONS);
+    elapsed
+}
+
+fn main() {
+    let schema = schema();
+    let arguments = arguments();
+
+    let per_call = measure("compile per call", || {
+        let compiled = CompiledSchema::compile(black_box(&schema)).unwrap();
+        compiled.validate_arguments(Some(black_box(&arguments))).unwrap();
+    });
+    let compiled = CompiledSchema::compile(&schema).unwrap();
+    let cached = measure("cached", || {
+        compiled.validate_arguments(Some(black_box(&arguments))).unwrap();
+    });
+    println!("speedup          {:>10.1}x", per_call.as_secs_f64() / cached.as_secs_f64());
+}
@@ -3,10 +3,11 @@
 //! This example demonstrates how to create a complete RustMCP server with tools, resources, and prompts.
 //! The server supports both HTTP and WebSocket connections for full MCP protocol compatibility.
 
-use rustmcp::{RustMCP, FunctionTool, FunctionResource, FunctionPrompt, create_app, ToolAnnotations};
+use rustmcp::{RustMCP, FunctionTool, FunctionResource, FunctionPrompt, SafeCommandPolicy, create_app, serve, ToolAnnotations};
 use rustmcp::{ToolDuplicateBehavior, ResourceDuplicateBehavior, PromptDuplicateBehavior};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::time::Duration;
 
 #[tokio::main]
 async fn main() {
@@ -14,7 +15,7 @@ async fn main() {
     env_logger::init();
     
     // 创建RustMCP实例，设置重复行为
-    let mut rustmcp = RustMCP::with_behavior(
+    let rustmcp = RustMCP::with_behavior(
         ToolDuplicateBehavior::Warn,
         ResourceDuplicateBehavior::Warn,
         PromptDuplicateBehavior::Warn,
@@ -52,73 +53,21 @@ async fn main() {
     );
     rustmcp.add_tool(echo_tool);
     
-    // 添加执行shell命令的工具
-    let shell_tool = FunctionTool::from_function(
-        |_args: Option<HashMap<String, Value>>| -> Result<Value, String> {
-            let command = _args
-                .as_ref()
-                .and_then(|m| m.get("command"))
-                .and_then(|v| v.as_str())
-                .ok_or("Missing 'command' argument")?;
-            
-            // 安全检查：限制只能执行特定的安全命令
-            let allowed_commands = ["ls", "pwd", "date", "echo", "cat", "which"];
-            let cmd_parts: Vec<&str> = command.split_whitespace().collect();
-            if cmd_parts.is_empty() {
-                return Err("Empty command not allowed".to_string());
-            }
-            
-            if !allowed_commands.contains(&cmd_parts[0]) {
-                return Err(format!("Command '{}' not allowed", cmd_parts[0]));
-            }
-            
-            // 防止命令注入攻击
-            if cmd_parts.iter().any(|&arg| arg.contains(|c| "!\"#$&'()*+,;<=>?@[\\]^`{|}~".contains(c))) {
-                return Err("Invalid characters in command arguments".to_string());
-            }
-            
-            // 执行命令
-            match std::process::Command::new("sh")
-                .arg("-c")
-                .arg(command)
-                .output() 
-            {
-                Ok(output) => {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    let result = format!("stdout:\n{}\nstderr:\n{}\nexit_code: {}", 
-                                         stdout, stderr, output.status.code().unwrap_or(-1));
-                    Ok(Value::String(result))
-                }
-                Err(e) => Err(format!("Failed to execute command: {}", e)),
-            }
-        },
-        Some("shell".to_string()),
-        Some("Shell Tool".to_string()), // title
-        Some("Execute safe shell commands".to_string()),
-        Some(serde_json::json!({
-            "type": "object",
-            "properties": {
-                "command": {
-                    "type": "string",
-                    "description": "The shell command to execute (limited to safe commands)"
-                }
-            },
-            "required": ["command"]
-        })),
-        None, // output_schema
-        Some(ToolAnnotations {
-            title: Some("Shell Tool".to_string()),
-            read_only_hint: Some(false),
-            destructive_hint: Some(false),
-            idempotent_hint: Some(false),
-            open_world_hint: Some(true),
-        }), // annotations
-        Some(vec!["system".to_string()]),
-        None,
-    );
-    
-    rustmcp.add_tool(shell_tool);
+    // 添加执行客户端命令行的工具：只允许当前目录中的只读命令，不经过shell，5秒后超时
+    let policy = SafeCommandPolicy::read_only(".")
+        .allow_arg("du", "-[sh]+")
+        .max_output_bytes(64 * 1024)
+        .timeout(Duration::from_secs(5));
+    let mut command_tool = FunctionTool::from_command_policy("run_command", "Runs a read-only command in the working directory", policy);
+    command_tool.annotations = Some(ToolAnnotations {
+        title: Some("Run Command".to_string()),
+        read_only_hint: Some(true),
+        destructive_hint: Some(false),
+        idempotent_hint: Some(true),
+        open_world_hint: Some(false),
+    });
+    command_tool.tags = Some(vec!["system".to_string()]);
+    rustmcp.add_tool(command_tool);
     
     // 添加示例资源 - Hello资源
     // 该资源返回一个简单的问候消息
@@ -145,11 +94,7 @@ async fn main() {
                 .and_then(|v| v.as_str())
                 .unwrap_or("World");
             
-            Ok(vec![rustmcp::PromptMessage {
-                role: "user".to_string(),
-                content: format!("Hello, {}!", name),
-                name: None,
-            }])
+            Ok(vec![rustmcp::PromptMessage::user(format!("Hello, {}!", name))])
         },
         "greeting".to_string(),
         Some("A simple greeting prompt".to_string()),
@@ -168,5 +113,5 @@ async fn main() {
     println!("MCP JSON-RPC endpoint available at http://localhost:3001/mcp");
     
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3001").await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    serve(listener, app).await.unwrap();
 }
\ No newline at end of file
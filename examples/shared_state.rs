@@ -0,0 +1,51 @@
+//! 工具之间共享应用状态
+//!
+//! `increment`工具修改计数器，`read_counter`工具读取计数器。
+//!
+//! ```bash
+//! cargo run --example shared_state
+//! ```
+
+use rustmcp::{Context, FunctionTool, RustMCP};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 所有工具共享的计数器
+#[derive(Default)]
+struct Counter(AtomicU64);
+
+#[tokio::main]
+async fn main() {
+    let mut rustmcp = RustMCP::new();
+    rustmcp.insert_state(Counter::default());
+
+    let increment = FunctionTool::builder()
+        .name("increment")
+        .description("Increments the shared counter")
+        .handler_with_context(|ctx: &Context, _args: Option<HashMap<String, Value>>| {
+            let counter = ctx.state::<Counter>().ok_or("Counter state is not registered")?;
+            Ok(Value::from(counter.0.fetch_add(1, Ordering::SeqCst) + 1))
+        })
+        .build();
+    rustmcp.add_tool(increment);
+
+    let read_counter = FunctionTool::builder()
+        .name("read_counter")
+        .description("Reads the shared counter")
+        .handler_with_context(|ctx: &Context, _args: Option<HashMap<String, Value>>| {
+            let counter = ctx.state::<Counter>().ok_or("Counter state is not registered")?;
+            Ok(Value::from(counter.0.load(Ordering::SeqCst)))
+        })
+        .build();
+    rustmcp.add_tool(read_counter);
+
+    for _ in 0..3 {
+        rustmcp.mcp_call_tool("increment", None).await.unwrap();
+    }
+    let value = rustmcp.mcp_call_tool("read_counter", None).await.unwrap();
+    println!("Counter value: {}", value);
+
+    // 状态在create_app包装为Arc<RustMCP>后仍然可用
+    let _app = rustmcp::create_app(rustmcp);
+}
@@ -0,0 +1,27 @@
+;; 示例WASM工具：原样返回收到的JSON参数
+;;
+;; 遵循rustmcp的内存ABI：导出memory、alloc和工具函数，
+;; 工具函数返回 (结果地址 << 32) | 结果长度。
+(module
+  (memory (export "memory") 1)
+
+  ;; 简单的递增分配器，每次调用都在新的实例中运行，不需要释放
+  (global $next (mut i32) (i32.const 1024))
+
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $len)))
+    ;; 内存不足时扩容
+    (if (i32.gt_u (global.get $next) (i32.mul (memory.size) (i32.const 65536)))
+      (then
+        (drop (memory.grow
+          (i32.add
+            (i32.div_u (i32.sub (global.get $next) (i32.mul (memory.size) (i32.const 65536))) (i32.const 65536))
+            (i32.const 1))))))
+    (local.get $ptr))
+
+  (func (export "echo") (param $ptr i32) (param $len i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+      (i64.extend_i32_u (local.get $len)))))
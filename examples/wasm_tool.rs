@@ -0,0 +1,31 @@
+//! 在WASM沙箱中运行工具
+//!
+//! ```bash
+//! cargo run --example wasm_tool --features wasm
+//! ```
+
+use rustmcp::{FunctionTool, RustMCP, WasmLimits};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() {
+    let rustmcp = RustMCP::new();
+
+    let limits = WasmLimits::new()
+        .with_fuel(Some(1_000_000))
+        .with_max_memory(4 * 1024 * 1024)
+        .with_timeout(Some(Duration::from_secs(1)));
+    let mut echo = FunctionTool::from_wasm_module(include_bytes!("wasm/echo.wat"), "echo", limits)
+        .expect("echo.wat is a valid module");
+    echo.description = "Echoes the arguments back from a sandboxed WASM module".to_string();
+    rustmcp.add_tool(echo);
+
+    let mut args = HashMap::new();
+    args.insert("message".to_string(), Value::String("Hello from WASM!".to_string()));
+    match rustmcp.mcp_call_tool("echo", Some(args)).await {
+        Ok(result) => println!("Tool result: {}", result),
+        Err(e) => println!("Error calling tool: {}", e),
+    }
+}
@@ -0,0 +1,458 @@
+//! 声明式服务器配置
+//!
+//! 支持TOML（`.toml`）和YAML（`.yaml`/`.yml`）。解析和校验错误都带有文件、行号和出错的键。
+
+use rustmcp::{HttpClientConfig, LifecyclePolicy, MethodPolicy, ResultFormat, SecretTemplate, SerializationPolicy, WsConfig};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// 配置错误
+#[derive(Debug)]
+pub struct ConfigError {
+    /// 配置文件路径
+    pub file: PathBuf,
+    /// 出错的行号（从1开始，未知时为空）
+    pub line: Option<usize>,
+    /// 出错的键，例如`tools[0].command`
+    pub key: String,
+    /// 错误描述
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}: {}", self.file.display(), line, self.key, self.message),
+            None => write!(f, "{}: {}: {}", self.file.display(), self.key, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// 服务器配置
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerConfig {
+    /// 服务器设置
+    #[serde(default)]
+    pub server: ServerSection,
+    /// 工具
+    #[serde(default)]
+    pub tools: Vec<ToolConfig>,
+    /// 资源
+    #[serde(default)]
+    pub resources: Vec<ResourceConfig>,
+    /// 提示
+    #[serde(default)]
+    pub prompts: Vec<PromptConfig>,
+}
+
+/// 服务器设置
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerSection {
+    /// HTTP监听地址
+    #[serde(default = "default_host")]
+    pub host: String,
+    /// HTTP监听端口
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// 工具可以执行的命令
+    #[serde(default)]
+    pub allowed_commands: Vec<String>,
+    /// 在`initialize`之前到达的请求的处理策略：`strict`、`lenient`或`auto`
+    #[serde(default)]
+    pub lifecycle_policy: LifecyclePolicy,
+    /// 只读模式，拒绝可能修改状态的工具
+    #[serde(default)]
+    pub read_only: bool,
+    /// 输出键按字典序排列的规范化JSON
+    #[serde(default)]
+    pub canonical_json: bool,
+    /// `tools/call`结果的格式：`legacy`或`spec`，不设置时按会话协商的协议版本选择
+    #[serde(default)]
+    pub result_format: Option<ResultFormat>,
+    /// JSON-RPC方法的允许或拒绝列表，例如`{ deny = ["resources/*"] }`
+    #[serde(default)]
+    pub method_policy: Option<MethodPolicy>,
+    /// `tools/call`、`resources/read`和`prompts/get`的最长执行时间（毫秒）
+    #[serde(default)]
+    pub request_timeout_ms: Option<u64>,
+    /// 同时保留的HTTP会话数上限，达到上限时淘汰最久没有请求的会话，不设置时为10000
+    #[serde(default)]
+    pub max_sessions: Option<usize>,
+    /// 按`Accept-Encoding`压缩HTTP响应，SSE通知流除外
+    #[serde(default)]
+    pub compression: bool,
+    /// 压缩的最小响应字节数，不设置时为1024
+    #[serde(default)]
+    pub compression_min_bytes: Option<u16>,
+    /// `prompts/list`每页的提示数，不设置时不分页
+    #[serde(default)]
+    pub prompt_page_size: Option<usize>,
+    /// `tools/list`中工具列表的最大字节数，不设置时不限制
+    #[serde(default)]
+    pub max_tools_list_bytes: Option<usize>,
+    /// 列表中的项序列化失败时的处理方式：`fail_closed`（默认）或`skip_bad`
+    #[serde(default)]
+    pub serialization_policy: SerializationPolicy,
+    /// WebSocket消息的最大字节数，不设置时为64 MiB
+    #[serde(default)]
+    pub ws_max_message_bytes: Option<usize>,
+    /// WebSocket的permessage-deflate压缩
+    #[serde(default)]
+    pub ws: WsConfig,
+    /// WebSocket断开后保留未送达响应的毫秒数，不设置时不保留
+    #[serde(default)]
+    pub pending_results_ttl_ms: Option<u64>,
+    /// 出站HTTP客户端（超时、代理、额外的根证书）
+    #[serde(default)]
+    pub http_client: HttpClientConfig,
+    /// 同时处理的JSON-RPC请求数上限，不设置时不限制
+    #[serde(default)]
+    pub max_in_flight: Option<usize>,
+    /// 每个会话同时处理的请求数上限，不设置时不限制
+    #[serde(default)]
+    pub max_in_flight_per_session: Option<usize>,
+    /// 不计入并发请求数的方法
+    #[serde(default)]
+    pub load_shedding_exempt: Vec<String>,
+    /// 因过载拒绝请求时建议的重试等待毫秒数，不设置时为1000
+    #[serde(default)]
+    pub overload_retry_after_ms: Option<u64>,
+    /// HTTP通知流空闲时发送保活注释的间隔毫秒数，不设置时为25000，为0时不发送
+    #[serde(default)]
+    pub sse_keep_alive_ms: Option<u64>,
+    /// HTTP通知流`retry:`字段建议的重新连接等待毫秒数，不设置时为3000，为0时不发送
+    #[serde(default)]
+    pub sse_retry_ms: Option<u64>,
+    /// `POST /mcp`和`POST /mcp/call-tool`请求体的最大字节数，不设置时为2 MiB
+    #[serde(default)]
+    pub max_body_bytes: Option<usize>,
+    /// 工具错误文本的最大字节数，不设置时为4096，为0时不限制
+    #[serde(default)]
+    pub max_error_bytes: Option<usize>,
+    /// 从工具错误文本中去掉的片段的正则表达式
+    #[serde(default)]
+    pub error_scrubbers: Vec<String>,
+}
+
+impl Default for ServerSection {
+    fn default() -> Self {
+        Self {
+            host: default_host(),
+            port: default_port(),
+            allowed_commands: Vec::new(),
+            lifecycle_policy: LifecyclePolicy::default(),
+            read_only: false,
+            canonical_json: false,
+            result_format: None,
+            method_policy: None,
+            request_timeout_ms: None,
+            max_sessions: None,
+            compression: false,
+            compression_min_bytes: None,
+            prompt_page_size: None,
+            max_tools_list_bytes: None,
+            serialization_policy: SerializationPolicy::default(),
+            ws_max_message_bytes: None,
+            ws: WsConfig::default(),
+            pending_results_ttl_ms: None,
+            http_client: HttpClientConfig::default(),
+            max_in_flight: None,
+            max_in_flight_per_session: None,
+            load_shedding_exempt: Vec::new(),
+            overload_retry_after_ms: None,
+            sse_keep_alive_ms: None,
+            sse_retry_ms: None,
+            max_body_bytes: None,
+            max_error_bytes: None,
+            error_scrubbers: Vec::new(),
+        }
+    }
+}
+
+fn default_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_port() -> u16 {
+    3000
+}
+
+/// 命令工具
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolConfig {
+    /// 工具名称
+    pub name: String,
+    /// 工具描述
+    #[serde(default)]
+    pub description: String,
+    /// 输入模式，设置后调用前会校验参数
+    #[serde(default)]
+    pub schema: Option<Value>,
+    /// 命令模板，第一个元素为程序名，`{arg}`会被替换为参数值；
+    /// 参数可以引用密钥，`${env:NAME}`在加载时读取，`${file:/path}`在每次调用时读取
+    pub command: Vec<String>,
+    /// 校验时解析出的命令模板
+    #[serde(skip)]
+    pub templates: Vec<SecretTemplate>,
+}
+
+/// 资源
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceConfig {
+    /// 资源URI
+    pub uri: String,
+    /// 资源名称
+    #[serde(default)]
+    pub name: Option<String>,
+    /// 资源描述
+    #[serde(default)]
+    pub description: Option<String>,
+    /// MIME类型，未设置时按文件扩展名推断
+    #[serde(default)]
+    pub mime_type: Option<String>,
+    /// 文件路径，相对路径以配置文件所在目录为基准
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// 内联文本
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+/// 模板提示
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptConfig {
+    /// 提示名称
+    pub name: String,
+    /// 提示描述
+    #[serde(default)]
+    pub description: Option<String>,
+    /// 消息角色
+    #[serde(default = "default_role")]
+    pub role: String,
+    /// 提示模板，`{arg}`会被替换为参数值
+    pub template: String,
+    /// 提示参数
+    #[serde(default)]
+    pub arguments: Vec<PromptArgumentConfig>,
+}
+
+fn default_role() -> String {
+    "user".to_string()
+}
+
+/// 提示参数
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptArgumentConfig {
+    /// 参数名称
+    pub name: String,
+    /// 参数描述
+    #[serde(default)]
+    pub description: String,
+    /// 是否必填
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// 读取并校验配置文件
+pub fn load(path: &Path) -> Result<ServerConfig, ConfigError> {
+    let source = std::fs::read_to_string(path).map_err(|e| ConfigError {
+        file: path.to_path_buf(),
+        line: None,
+        key: "<file>".to_string(),
+        message: e.to_string(),
+    })?;
+    let mut config = parse(path, &source)?;
+    validate(path, &source, &mut config)?;
+    Ok(config)
+}
+
+/// 按扩展名解析配置
+fn parse(path: &Path, source: &str) -> Result<ServerConfig, ConfigError> {
+    let error = |key: String, line: Option<usize>, message: String| ConfigError {
+        file: path.to_path_buf(),
+        line,
+        key,
+        message,
+    };
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => {
+            let deserializer = toml::Deserializer::new(source);
+            serde_path_to_error::deserialize(deserializer).map_err(|e| {
+                let line = e.inner().span().map(|span| line_of_offset(source, span.start));
+                error(e.path().to_string(), line, e.inner().message().to_string())
+            })
+        }
+        Some("yaml") | Some("yml") => {
+            let deserializer = serde_yaml::Deserializer::from_str(source);
+            serde_path_to_error::deserialize(deserializer).map_err(|e| {
+                let line = e.inner().location().map(|location| location.line());
+                // serde_yaml的消息中已包含路径和位置，去掉后与TOML的格式保持一致
+                let message = e.inner().to_string();
+                let message = message.rsplit_once(" at line ").map_or(message.as_str(), |(m, _)| m);
+                let message = message.split_once(": ").filter(|(p, _)| !p.contains(' ')).map_or(message, |(_, m)| m);
+                error(e.path().to_string(), line, message.to_string())
+            })
+        }
+        _ => Err(error(
+            "<file>".to_string(),
+            None,
+            "unsupported config format, expected .toml, .yaml or .yml".to_string(),
+        )),
+    }
+}
+
+/// 校验解析后的配置，并把资源的相对路径解析为绝对路径
+fn validate(path: &Path, source: &str, config: &mut ServerConfig) -> Result<(), ConfigError> {
+    let error = |key: String, anchor: &str, message: String| ConfigError {
+        file: path.to_path_buf(),
+        line: find_line(source, anchor),
+        key,
+        message,
+    };
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    let mut names = HashSet::new();
+    for (i, tool) in config.tools.iter_mut().enumerate() {
+        if !names.insert(tool.name.clone()) {
+            return Err(error(format!("tools[{}].name", i), &tool.name, format!("duplicate tool '{}'", tool.name)));
+        }
+        let program = match tool.command.first() {
+            Some(program) => program,
+            None => return Err(error(format!("tools[{}].command", i), &tool.name, "command must not be empty".to_string())),
+        };
+        if program.contains('{') {
+            return Err(error(format!("tools[{}].command", i), &tool.name, "program name cannot be a template".to_string()));
+        }
+        if !config.server.allowed_commands.contains(program) {
+            return Err(error(
+                format!("tools[{}].command", i),
+                &tool.name,
+                format!("command '{}' is not listed in server.allowed_commands", program),
+            ));
+        }
+        if let Some(properties) = tool.schema.as_ref().and_then(|s| s.get("properties")).and_then(|p| p.as_object()) {
+            for placeholder in tool.command.iter().flat_map(|part| placeholders(part)) {
+                if !properties.contains_key(&placeholder) {
+                    return Err(error(
+                        format!("tools[{}].command", i),
+                        &tool.name,
+                        format!("placeholder '{{{}}}' is not declared in schema.properties", placeholder),
+                    ));
+                }
+            }
+        }
+        tool.templates = tool
+            .command
+            .iter()
+            .map(|part| SecretTemplate::parse(part))
+            .collect::<Result<_, _>>()
+            .map_err(|e| error(format!("tools[{}].command", i), &tool.name, e.to_string()))?;
+    }
+
+    let mut uris = HashSet::new();
+    for (i, resource) in config.resources.iter_mut().enumerate() {
+        if !uris.insert(resource.uri.clone()) {
+            return Err(error(format!("resources[{}].uri", i), &resource.uri, format!("duplicate resource '{}'", resource.uri)));
+        }
+        match (&resource.path, &resource.text) {
+            (Some(_), Some(_)) | (None, None) => {
+                return Err(error(
+                    format!("resources[{}]", i),
+                    &resource.uri,
+                    "exactly one of 'path' or 'text' must be set".to_string(),
+                ));
+            }
+            (Some(file), None) => {
+                let resolved = base_dir.join(file);
+                if !resolved.is_file() {
+                    return Err(error(
+                        format!("resources[{}].path", i),
+                        &resource.uri,
+                        format!("file not found: {}", resolved.display()),
+                    ));
+                }
+                resource.path = Some(resolved);
+            }
+            (None, Some(_)) => {}
+        }
+    }
+
+    let mut names = HashSet::new();
+    for (i, prompt) in config.prompts.iter().enumerate() {
+        if !names.insert(prompt.name.as_str()) {
+            return Err(error(format!("prompts[{}].name", i), &prompt.name, format!("duplicate prompt '{}'", prompt.name)));
+        }
+        for placeholder in placeholders(&prompt.template) {
+            if !prompt.arguments.iter().any(|arg| arg.name == placeholder) {
+                return Err(error(
+                    format!("prompts[{}].template", i),
+                    &prompt.name,
+                    format!("placeholder '{{{}}}' is not declared in arguments", placeholder),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 提取模板中的`{name}`占位符
+pub fn placeholders(template: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    interpolate(template, |name| {
+        result.push(name.to_string());
+        None
+    });
+    result
+}
+
+/// 把模板中的占位符替换为参数值，缺失的参数替换为空字符串
+///
+/// 只扫描一遍模板，参数值中的`{...}`不会被再次替换。
+pub fn interpolate(template: &str, mut lookup: impl FnMut(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) if is_placeholder_name(&after[..end]) => {
+                output.push_str(&lookup(&after[..end]).unwrap_or_default());
+                rest = &after[end + 1..];
+            }
+            _ => {
+                output.push('{');
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+fn is_placeholder_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// 字节偏移所在的行号
+fn line_of_offset(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+/// 查找定义某个条目的行：包含该名称且带有`name`或`uri`键的第一行
+fn find_line(source: &str, value: &str) -> Option<usize> {
+    source
+        .lines()
+        .position(|line| line.contains(value) && (line.contains("name") || line.contains("uri")))
+        .map(|index| index + 1)
+}
@@ -0,0 +1,289 @@
+//! rustmcp-serve: 按配置文件运行MCP服务器
+//!
+//! ```text
+//! rustmcp-serve --config server.toml [--transport http|stdio] [--host HOST] [--port PORT]
+//! ```
+//!
+//! 设置环境变量`RUSTMCP_TRACE=<文件>`时开启[协议跟踪](rustmcp::server::trace)，
+//! `RUSTMCP_TRACE_PER_SESSION=1`时按会话拆分文件。
+
+mod config;
+
+use rustmcp::server::secrets;
+use rustmcp::{run, serve_stdio, Content, FunctionPrompt, FunctionResource, FunctionTool, PromptMessage, RustMCP, SecretTemplate, Settings, TraceConfig};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::{Command, ExitCode};
+use std::sync::Arc;
+
+use config::{interpolate, PromptConfig, ResourceConfig, ServerConfig, ToolConfig};
+
+const USAGE: &str = "Usage: rustmcp-serve --config <FILE> [--transport http|stdio] [--host HOST] [--port PORT]";
+
+/// 传输方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Transport {
+    Http,
+    Stdio,
+}
+
+/// 命令行参数
+#[derive(Debug)]
+struct Args {
+    config: PathBuf,
+    transport: Transport,
+    host: Option<String>,
+    port: Option<u16>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut config = None;
+    let mut transport = Transport::Http;
+    let mut host = None;
+    let mut port = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().ok_or_else(|| format!("{} requires a value", flag));
+        match arg.as_str() {
+            "-c" | "--config" => config = Some(PathBuf::from(value(&arg)?)),
+            "-t" | "--transport" => {
+                transport = match value(&arg)?.as_str() {
+                    "http" => Transport::Http,
+                    "stdio" => Transport::Stdio,
+                    other => return Err(format!("unknown transport '{}', expected http or stdio", other)),
+                }
+            }
+            "--host" => host = Some(value(&arg)?),
+            "--port" => port = Some(value(&arg)?.parse().map_err(|e| format!("invalid --port: {}", e))?),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            other => return Err(format!("unexpected argument '{}'\n{}", other, USAGE)),
+        }
+    }
+
+    Ok(Args {
+        config: config.ok_or_else(|| format!("--config is required\n{}", USAGE))?,
+        transport,
+        host,
+        port,
+    })
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    // 日志写到标准错误，标准输出留给stdio传输
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .target(env_logger::Target::Stderr)
+        .init();
+
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::from(2);
+        }
+    };
+
+    let config = match config::load(&args.config) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let rustmcp = build_server(&config);
+    let result = match args.transport {
+        Transport::Stdio => serve_stdio(Arc::new(rustmcp)).await,
+        Transport::Http => {
+            let host = args.host.unwrap_or_else(|| config.server.host.clone());
+            let port = args.port.unwrap_or(config.server.port);
+            match tokio::net::TcpListener::bind((host.as_str(), port)).await {
+                Ok(listener) => run(rustmcp, listener).await,
+                Err(e) => Err(e),
+            }
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// 根据配置创建服务器
+fn build_server(config: &ServerConfig) -> RustMCP {
+    let mut settings = Settings::new()
+        .with_lifecycle_policy(config.server.lifecycle_policy)
+        .with_read_only(config.server.read_only)
+        .with_canonical_json(config.server.canonical_json);
+    settings.result_format = config.server.result_format;
+    settings.method_policy = config.server.method_policy.clone();
+    settings.request_timeout_ms = config.server.request_timeout_ms;
+    if let Some(max_sessions) = config.server.max_sessions {
+        settings.max_sessions = Some(max_sessions);
+    }
+    settings.prompt_page_size = config.server.prompt_page_size;
+    settings.max_tools_list_bytes = config.server.max_tools_list_bytes;
+    settings.serialization_policy = config.server.serialization_policy;
+    settings.pending_results_ttl_ms = config.server.pending_results_ttl_ms;
+    settings.http_client = config.server.http_client.clone();
+    settings.ws = config.server.ws.clone();
+    settings = settings
+        .with_in_flight_limits(config.server.max_in_flight, config.server.max_in_flight_per_session)
+        .with_load_shedding_exempt(config.server.load_shedding_exempt.iter().cloned());
+    if let Some(retry_after_ms) = config.server.overload_retry_after_ms {
+        settings.overload_retry_after_ms = retry_after_ms;
+    }
+    if let Some(keep_alive_ms) = config.server.sse_keep_alive_ms {
+        settings.sse_keep_alive_ms = keep_alive_ms;
+    }
+    if let Some(retry_ms) = config.server.sse_retry_ms {
+        settings.sse_retry_ms = retry_ms;
+    }
+    if let Some(bytes) = config.server.max_body_bytes {
+        settings = settings.with_max_body_bytes(bytes);
+    }
+    if let Some(bytes) = config.server.max_error_bytes {
+        settings = settings.with_max_error_bytes(bytes);
+    }
+    for pattern in &config.server.error_scrubbers {
+        settings = settings.with_error_scrubber(pattern.clone());
+    }
+    if let Some(bytes) = config.server.ws_max_message_bytes {
+        settings = settings.with_ws_max_message_bytes(bytes);
+    }
+    if config.server.compression {
+        let min_bytes = config.server.compression_min_bytes.unwrap_or(settings.compression_min_bytes);
+        settings = settings.with_compression(min_bytes);
+    }
+    let rustmcp = RustMCP::new().with_settings(settings);
+    for tool in &config.tools {
+        rustmcp.add_tool(command_tool(tool));
+    }
+    for resource in &config.resources {
+        rustmcp.add_resource(config_resource(resource));
+    }
+    for prompt in &config.prompts {
+        rustmcp.add_prompt(template_prompt(prompt));
+    }
+    if let Some(trace) = TraceConfig::from_env() {
+        if let Err(e) = rustmcp.enable_trace(trace.clone()) {
+            log::warn!("Cannot open trace file {}: {}", trace.path.display(), e);
+        }
+    }
+    rustmcp
+}
+
+/// 参数值转换为命令行参数或模板文本
+fn argument_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// 创建执行命令的工具
+///
+/// 命令不经过shell，模板的每个元素单独替换后作为一个参数传给程序。
+/// 密钥的值不参与参数替换，错误中的密钥值会被脱敏。
+fn command_tool(config: &ToolConfig) -> FunctionTool {
+    let command = config.templates.clone();
+    let mut builder = FunctionTool::builder()
+        .name(config.name.clone())
+        .description(config.description.clone())
+        .handler(move |args: Option<HashMap<String, Value>>| {
+            let args = args.unwrap_or_default();
+            run_command(&command, &args).map_err(|e| secrets::redact(&e))
+        });
+    if let Some(schema) = &config.schema {
+        builder = builder.input_schema(schema.clone()).validate_input(true);
+    }
+    builder.build()
+}
+
+/// 解析命令模板并执行
+fn run_command(command: &[SecretTemplate], args: &HashMap<String, Value>) -> Result<Value, String> {
+    let argv = command
+        .iter()
+        .map(|part| part.render(|text| interpolate(text, |name| args.get(name).map(argument_text))))
+        .collect::<Result<Vec<String>, _>>()?;
+    let output = Command::new(&argv[0])
+        .args(&argv[1..])
+        .output()
+        .map_err(|e| format!("Failed to run '{}': {}", argv[0], e))?;
+    if output.status.success() {
+        Ok(Value::String(String::from_utf8_lossy(&output.stdout).into_owned()))
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!("'{}' exited with {}: {}", argv[0], output.status, stderr.trim()))
+    }
+}
+
+/// 按文件扩展名推断MIME类型
+fn guess_mime_type(path: &std::path::Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("md") => "text/markdown",
+        Some("json") => "application/json",
+        Some("html") | Some("htm") => "text/html",
+        Some("csv") => "text/csv",
+        _ => "text/plain",
+    }
+}
+
+/// 创建文件或内联文本资源
+fn config_resource(config: &ResourceConfig) -> FunctionResource {
+    let mime_type = config.mime_type.clone().or_else(|| {
+        config.path.as_deref().map(|path| guess_mime_type(path).to_string())
+    });
+    let path = config.path.clone();
+    let text = config.text.clone();
+    FunctionResource::from_function(
+        move || match (&path, &text) {
+            (Some(path), _) => std::fs::read_to_string(path)
+                .map(Value::String)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e)),
+            (None, Some(text)) => Ok(Value::String(text.clone())),
+            (None, None) => Err("Resource has no content".to_string()),
+        },
+        config.uri.clone(),
+        config.name.clone(),
+        config.description.clone(),
+        mime_type.or_else(|| Some("text/plain".to_string())),
+        None,
+        None,
+        None,
+    )
+}
+
+/// 创建模板提示
+fn template_prompt(config: &PromptConfig) -> FunctionPrompt {
+    let template = config.template.clone();
+    let role = config.role.clone();
+    let required: Vec<String> = config.arguments.iter().filter(|a| a.required).map(|a| a.name.clone()).collect();
+    let arguments: HashMap<String, String> = config
+        .arguments
+        .iter()
+        .map(|arg| (arg.name.clone(), arg.description.clone()))
+        .collect();
+    FunctionPrompt::from_function(
+        move |args: Option<HashMap<String, Value>>| {
+            let args = args.unwrap_or_default();
+            if let Some(missing) = required.iter().find(|name| !args.contains_key(*name)) {
+                return Err(format!("Missing required argument '{}'", missing));
+            }
+            let text = interpolate(&template, |name| args.get(name).map(argument_text));
+            Ok(vec![PromptMessage::new(role.clone(), vec![Content::text(text)])])
+        },
+        config.name.clone(),
+        config.description.clone(),
+        None,
+        Some(arguments).filter(|a| !a.is_empty()),
+        None,
+    )
+    .with_template(config.template.clone())
+}
@@ -13,73 +13,53 @@
 //!
 //! ## 快速开始
 //!
+//! 常用类型都可以从[`prelude`]一次导入：`use rustmcp::prelude::*;`。
+//!
 //! ```rust
 //! use rustmcp::{RustMCP, FunctionTool};
+//! use serde_json::Value;
 //! use std::collections::HashMap;
 //!
 //! #[tokio::main]
 //! async fn main() {
 //!     // 创建RustMCP实例
-//!     let mut rustmcp = RustMCP::new();
-//!     
-//!     // 定义一个简单的工具函数
-//!     fn greet_tool(args: Option<HashMap<String, serde_json::Value>>) -> Result<serde_json::Value, String> {
+//!     let rustmcp = RustMCP::new();
+//!
+//!     // 创建并注册一个简单的工具
+//!     let greet_tool = FunctionTool::simple("greet", "Greets a person by name", |args| {
 //!         let name = args
-//!             .and_then(|map| map.get("name").cloned())
-//!             .unwrap_or(serde_json::Value::String("World".to_string()));
-//!         
-//!         let greeting = format!("Hello, {}!", name.as_str().unwrap_or("World"));
-//!         Ok(serde_json::Value::String(greeting))
-//!     }
-//!     
-//!     // 创建并注册工具
-//!     let greet_function_tool = FunctionTool::from_function(
-//!         greet_tool,
-//!         Some("greet".to_string()),
-//!         Some("Greets a person by name".to_string()),
-//!         Some(vec!["greeting".to_string()]),
-//!         None,
-//!         None,
-//!     );
-//!     
-//!     rustmcp.add_tool(greet_function_tool);
-//!     
-//!     // 测试调用工具
+//!             .as_ref()
+//!             .and_then(|map| map.get("name"))
+//!             .and_then(|v| v.as_str())
+//!             .unwrap_or("World");
+//!         Ok(Value::String(format!("Hello, {}!", name)))
+//!     });
+//!     rustmcp.add_tool(greet_tool);
+//!
+//!     // 调用工具
 //!     let mut args = HashMap::new();
-//!     args.insert("name".to_string(), serde_json::Value::String("RustMCP".to_string()));
-//!     
-//!     match rustmcp.mcp_call_tool("greet".to_string(), Some(args)).await {
-//!         Ok(result) => println!("Tool result: {}", result.as_str().unwrap_or("Unknown")),
-//!         Err(e) => println!("Error calling tool: {}", e),
-//!     }
+//!     args.insert("name".to_string(), Value::String("RustMCP".to_string()));
+//!
+//!     let result = rustmcp.mcp_call_tool("greet", Some(args)).await.unwrap();
+//!     assert_eq!(result, Value::String("Hello, RustMCP!".to_string()));
 //! }
 //! ```
-
+//!
 //! ## 创建完整服务器
 //!
-//! ```rust
-//! use rustmcp::{RustMCP, FunctionTool, create_app};
+//! ```rust,no_run
+//! use rustmcp::{RustMCP, FunctionTool, FunctionResource, FunctionPrompt, PromptMessage, create_app, serve};
 //! use serde_json::Value;
-//! use std::collections::HashMap;
 //!
 //! #[tokio::main]
 //! async fn main() {
-//!     let mut rustmcp = RustMCP::new();
-//!     
-//!     // 添加工具
-//!     let echo_tool = FunctionTool::from_function(
-//!         |_args: Option<HashMap<String, Value>>| -> Result<Value, String> {
-//!             let message = _args
-//!                 .as_ref()
-//!                 .and_then(|m| m.get("message"))
-//!                 .and_then(|v| v.as_str())
-//!                 .unwrap_or("Hello, World!");
-//!             Ok(Value::String(message.to_string()))
-//!         },
-//!         Some("echo".to_string()),
-//!         Some("Echoes back the provided message".to_string()),
-//!         Some(vec!["utility".to_string()]),
-//!         Some(serde_json::json!({
+//!     let rustmcp = RustMCP::new();
+//!
+//!     // 添加带输入模式的工具
+//!     let echo_tool = FunctionTool::with_schema(
+//!         "echo",
+//!         "Echoes back the provided message",
+//!         serde_json::json!({
 //!             "type": "object",
 //!             "properties": {
 //!                 "message": {
@@ -88,18 +68,30 @@
 //!                 }
 //!             },
 //!             "required": ["message"]
-//!         })),
-//!         None,
-//!         None,
+//!         }),
+//!         |args| {
+//!             let message = args
+//!                 .as_ref()
+//!                 .and_then(|m| m.get("message"))
+//!                 .and_then(|v| v.as_str())
+//!                 .unwrap_or("Hello, World!");
+//!             Ok(Value::String(message.to_string()))
+//!         },
 //!     );
 //!     rustmcp.add_tool(echo_tool);
-//!     
-//!     // 创建服务器应用
+//!
+//!     // 添加资源和提示
+//!     rustmcp.add_resource(FunctionResource::simple("resource://hello", || {
+//!         Ok(Value::String("Hello from resource!".to_string()))
+//!     }));
+//!     rustmcp.add_prompt(FunctionPrompt::simple("greeting", |_args| {
+//!         Ok(vec![PromptMessage::user("Hello!")])
+//!     }));
+//!
+//!     // 创建服务器应用并启动
 //!     let app = create_app(rustmcp);
-//!     
-//!     // 启动服务器
 //!     let listener = tokio::net::TcpListener::bind("0.0.0.0:3001").await.unwrap();
-//!     axum::serve(listener, app).await.unwrap();
+//!     serve(listener, app).await.unwrap();
 //! }
 //! ```
 
@@ -110,14 +102,56 @@ pub const MINOR_VERSION: u32 = 1;
 /// 修订版本号
 pub const PATCH_VERSION: u32 = 0;
 
+pub mod prelude;
+pub mod protocol;
 pub mod server;
 mod settings;
 
-pub use server::{RustMCP, Context};
-pub use server::tools::{FunctionTool, ToolAnnotations, DuplicateBehavior as ToolDuplicateBehavior};
-pub use server::resources::{FunctionResource, Resource, DuplicateBehavior as ResourceDuplicateBehavior};
-pub use server::prompts::{FunctionPrompt, Prompt, PromptMessage, DuplicateBehavior as PromptDuplicateBehavior};
-pub use server::{create_app};
+pub use server::{RustMCP, Context, CancellationToken, LanguageTag, Localizations, StateMap, McpError, RequestContext, TransportKind, Identity, Delivery, SessionChannel};
+pub use server::{SessionStore, SessionLimits, SessionError, SessionInfo, SessionEviction, SessionMetrics};
+pub use server::{CommandTool, MethodPolicy};
+pub use server::{ParsedCommand, PolicyError, SafeCommandPolicy};
+pub use server::{Backoff, RetryPolicy};
+pub use server::{Lifecycle, LifecyclePolicy, LifecycleState};
+pub use server::{ItemKind, Severity, ValidationIssue};
+pub use server::{Capture, CaptureConfig};
+pub use server::{Divergence, MirrorConfig, MirrorStats, MirrorTarget};
+pub use server::TraceConfig;
+pub use server::{AuditEntry, AuditOutcome, AuditSink, JsonlAuditSink, MemoryAuditSink};
+pub use server::{IdempotencyStore, MemoryIdempotencyStore, StoredResult};
+pub use server::{DependencyState, DependencyStatus, HealthCheck};
+pub use server::{HookState, ToolHookStatus, ToolStartupError};
+pub use server::{SecretError, SecretTemplate};
+pub use server::tools::{ToolManager, FunctionTool, FunctionToolBuilder, Tool, ToolAnnotations, ToolBinding, ToolExample, ToolGroup, ToolPage, DuplicateBehavior as ToolDuplicateBehavior};
+pub use server::tools::{sanitize_tool_name, unique_tool_name};
+pub use server::resolvers::{ResourceResolver, ResourceTemplate};
+pub use server::loader::{ResourceLoader, ResourcePage};
+pub use server::features::{Feature, ProtocolVersion, VersionGate};
+pub use server::proxy::ProxyTarget;
+pub use server::truncation::TruncationPolicy;
+pub use server::serialization::SerializationPolicy;
+pub use server::quota::{Quota, QuotaStatus};
+pub use server::tags::TagInfo;
+pub use server::admission::LoadMetrics;
+pub use server::http_client::HttpClientConfig;
+pub use server::ws::WsConfig;
+#[cfg(feature = "http-client")]
+pub use server::http_client::HttpClient;
+pub use server::testing;
+pub use server::resources::{ResourceManager, FunctionResource, Resource, ResourceContents, ResourceStream, ResourceStreamFunction, ResourceAnnotations, Role, DuplicateBehavior as ResourceDuplicateBehavior};
+pub use server::prompts::{PromptManager, FunctionPrompt, Prompt, PromptArgument, PromptBuilder, PromptContent, PromptExample, PromptPage, PromptMessage, PromptStream, PromptStreamFunction, TurnPolicy, DuplicateBehavior as PromptDuplicateBehavior};
+pub use server::content::{Content, ContentError, ResultFormat};
+pub use server::schema::{ToolArgs, ArgKind, ArgSpec};
+pub use server::{create_app, try_create_app, create_multi_app, try_create_multi_app, serve, run, serve_stdio};
+#[cfg(feature = "openapi")]
+pub use server::openapi::{OpenApiError, OpenApiOptions};
+#[cfg(feature = "wasm")]
+pub use server::wasm::{WasmError, WasmLimits};
+#[cfg(feature = "watch")]
+pub use server::watch::ManifestWatcher;
+#[cfg(feature = "admin")]
+pub use server::admin::{create_admin_router, AdminAuth, AdminValidator};
+pub use settings::{Settings, RateLimit, RateLimitSettings};
 
 /// 获取库版本
 pub fn version() -> String {
@@ -0,0 +1,86 @@
+//! 常用类型的统一导入
+//!
+//! 编写服务器通常需要的类型都在这里：核心类和设置、工具/资源/提示及其管理器和构建器、
+//! 内容类型、错误类型、上下文，以及启动服务器的函数。`protocol`模块也一并导出，
+//! 需要JSON-RPC消息或`initialize`类型时写`protocol::InitializeParams`即可。
+//!
+//! ```rust
+//! use rustmcp::prelude::*;
+//! use serde_json::json;
+//!
+//! let rustmcp = RustMCP::new().with_settings(Settings::new().with_canonical_json(true));
+//! rustmcp.add_tool(
+//!     FunctionTool::builder()
+//!         .name("greet")
+//!         .args(ToolArgs::new().string("name", "Who to greet", true))
+//!         .handler(|_| Ok(json!("Hello!")))
+//!         .build(),
+//! );
+//! let params: protocol::InitializeParams = serde_json::from_value(json!({ "clientInfo": { "name": "demo" } })).unwrap();
+//! assert_eq!(params.client_info.name, "demo");
+//! ```
+
+pub use crate::protocol;
+pub use crate::server::{create_app, run, serve, serve_stdio, try_create_app};
+pub use crate::server::{Context, McpError, RequestContext, RustMCP, TransportKind};
+pub use crate::server::content::{Content, ContentError, ResultFormat};
+pub use crate::server::tools::ToolAnnotations;
+pub use crate::server::{FunctionTool, FunctionToolBuilder, ToolArgs, ToolDuplicateBehavior, ToolExample, ToolManager};
+pub use crate::server::{FunctionResource, ResourceContents, ResourceDuplicateBehavior, ResourceManager};
+pub use crate::server::{FunctionPrompt, PromptBuilder, PromptDuplicateBehavior, PromptExample, PromptManager, PromptMessage};
+pub use crate::Settings;
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use crate::prelude::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn prelude_covers_a_complete_server() {
+        let rustmcp = RustMCP::with_behavior(
+            ToolDuplicateBehavior::Replace,
+            ResourceDuplicateBehavior::Replace,
+            PromptDuplicateBehavior::Replace,
+        )
+        .with_settings(Settings::new().with_canonical_json(true));
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("greet")
+                .description("Greets a person by name")
+                .args(ToolArgs::new().string("name", "Who to greet", true))
+                .validate_input(true)
+                .handler(|args| {
+                    let name = args.and_then(|args| args.get("name").cloned()).unwrap_or_default();
+                    Ok(json!(format!("Hello, {}!", name.as_str().unwrap_or("world"))))
+                })
+                .build(),
+        );
+        rustmcp.add_resource(FunctionResource::simple("resource://motd", || Ok(json!("Welcome"))));
+        rustmcp.add_prompt(FunctionPrompt::simple("hello", |_| {
+            Ok(vec![PromptMessage::user("Hello!")])
+        }));
+
+        // 直接调用
+        let result = rustmcp.mcp_call_tool("greet", Some([("name".to_string(), json!("Ada"))].into())).await.unwrap();
+        assert_eq!(result, json!("Hello, Ada!"));
+        let error: McpError = rustmcp.mcp_call_tool("greet", None).await.unwrap_err();
+        assert_eq!(error.code(), -32602);
+
+        // 启动HTTP和WebSocket服务器，用一个原始的HTTP请求确认它在运行
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(run(rustmcp, listener));
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"GET /mcp/resources HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("resource://motd"));
+
+        // protocol模块的类型同样可用
+        let params: protocol::InitializeParams = serde_json::from_value(json!({ "clientInfo": { "name": "demo", "version": "1" } })).unwrap();
+        assert_eq!(params.client_info.name, "demo");
+    }
+}
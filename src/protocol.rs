@@ -0,0 +1,682 @@
+//! JSON-RPC协议类型
+//!
+//! 服务器的所有传输（HTTP、WebSocket、标准输入输出）都使用这里的类型读写消息。
+//! 嵌入方可以用它们构造或检查协议消息，例如编写测试、代理或自定义传输。
+//!
+//! ```rust
+//! use rustmcp::protocol::{JsonRpcRequest, JsonRpcResponse, RequestId, INVALID_PARAMS};
+//!
+//! let request = JsonRpcRequest::new(1, "tools/list", None);
+//! let text = serde_json::to_string(&request).unwrap();
+//! assert_eq!(text, r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#);
+//!
+//! let response: JsonRpcResponse = serde_json::from_str(
+//!     r#"{"jsonrpc":"2.0","id":"a-1","error":{"code":-32602,"message":"Invalid params"}}"#,
+//! ).unwrap();
+//! assert_eq!(response.id, RequestId::from("a-1"));
+//! assert_eq!(response.error.unwrap().code, INVALID_PARAMS);
+//! ```
+//!
+//! 请求id可以是整数、字符串或`null`。响应总是带有`id`字段，无法确定请求id时为`null`：
+//!
+//! ```rust
+//! use rustmcp::protocol::{JsonRpcRequest, RequestId, INVALID_REQUEST, PARSE_ERROR};
+//!
+//! let request = JsonRpcRequest::parse(r#"{"jsonrpc":"2.0","id":9007199254740993,"method":"ping"}"#).unwrap();
+//! assert_eq!(request.id, Some(RequestId::Number(9007199254740993)));
+//!
+//! let request = JsonRpcRequest::parse(r#"{"jsonrpc":"2.0","id":null,"method":"ping"}"#).unwrap();
+//! assert_eq!(request.id, Some(RequestId::Null));
+//!
+//! let response = JsonRpcRequest::parse("{not json").unwrap_err();
+//! assert_eq!(response.error.as_ref().unwrap().code, PARSE_ERROR);
+//! assert_eq!(
+//!     serde_json::to_string(&response.id).unwrap(),
+//!     "null",
+//! );
+//!
+//! let response = JsonRpcRequest::parse(r#"{"jsonrpc":"2.0","id":1.5,"method":"ping"}"#).unwrap_err();
+//! assert_eq!(response.error.unwrap().code, INVALID_REQUEST);
+//! ```
+//!
+//! `initialize`的参数和结果有对应的类型，字段名与规范相同：
+//!
+//! ```rust
+//! use rustmcp::protocol::{InitializeParams, JsonRpcRequest};
+//!
+//! let request = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05","capabilities":{},"clientInfo":{"name":"ExampleClient","version":"1.0.0"}}}"#;
+//! let request = JsonRpcRequest::parse(request).unwrap();
+//! let params: InitializeParams = serde_json::from_value(request.params.unwrap()).unwrap();
+//! assert_eq!(params.client_info.name, "ExampleClient");
+//! ```
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+/// JSON-RPC版本
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// 默认的MCP协议版本，客户端没有请求受支持的版本时使用
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// 服务器支持的MCP协议版本，从新到旧排列
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", PROTOCOL_VERSION];
+
+/// 无法解析的JSON
+pub const PARSE_ERROR: i32 = -32700;
+/// 不是有效的请求对象
+pub const INVALID_REQUEST: i32 = -32600;
+/// 方法不存在
+pub const METHOD_NOT_FOUND: i32 = -32601;
+/// 参数无效
+pub const INVALID_PARAMS: i32 = -32602;
+/// 服务器内部错误
+pub const INTERNAL_ERROR: i32 = -32603;
+/// 通用的服务器错误（工具、资源或提示执行失败等）
+pub const SERVER_ERROR: i32 = -32000;
+/// 请求超过截止时间
+pub const REQUEST_TIMEOUT: i32 = -32001;
+/// 请求被限流
+pub const RATE_LIMITED: i32 = -32005;
+/// 会话还没有完成`initialize`
+pub const SERVER_NOT_INITIALIZED: i32 = -32002;
+/// 服务器处于只读模式，拒绝可能修改状态的工具
+pub const READ_ONLY_MODE: i32 = -32006;
+/// 工具或资源超出用量配额
+pub const QUOTA_EXCEEDED: i32 = -32007;
+/// 服务器正在关闭，不再接受新的工具调用
+pub const SERVER_SHUTTING_DOWN: i32 = -32008;
+/// 服务器过载，拒绝新的请求，客户端在`retryAfterMs`后重试
+pub const SERVER_OVERLOADED: i32 = -32009;
+/// 调用方无权执行请求
+pub const UNAUTHORIZED: i32 = -32010;
+
+/// 服务器开始关闭时发给每个会话的通知，参数为`{"reason": ..., "graceMs": ...}`，
+/// 参见[`shutdown`](crate::server::shutdown)
+pub const SHUTDOWN_NOTIFICATION: &str = "notifications/x-rustmcp/shutdown";
+
+/// JSON-RPC请求id
+///
+/// 数字id只接受整数，按`i64`保存，不经过浮点数，因此大整数不会丢失精度；
+/// 带小数的id被视为无效请求。
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum RequestId {
+    /// 整数id
+    Number(i64),
+    /// 字符串id
+    String(String),
+    /// `null`，也用于无法确定请求id的错误响应
+    #[default]
+    Null,
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RequestId::Number(n) => write!(f, "{}", n),
+            RequestId::String(s) => write!(f, "{:?}", s),
+            RequestId::Null => write!(f, "null"),
+        }
+    }
+}
+
+impl From<i64> for RequestId {
+    fn from(id: i64) -> Self {
+        RequestId::Number(id)
+    }
+}
+
+impl From<String> for RequestId {
+    fn from(id: String) -> Self {
+        RequestId::String(id)
+    }
+}
+
+impl From<&str> for RequestId {
+    fn from(id: &str) -> Self {
+        RequestId::String(id.to_string())
+    }
+}
+
+impl Serialize for RequestId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            RequestId::Number(n) => serializer.serialize_i64(*n),
+            RequestId::String(s) => serializer.serialize_str(s),
+            RequestId::Null => serializer.serialize_unit(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RequestId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RequestIdVisitor;
+
+        impl<'de> Visitor<'de> for RequestIdVisitor {
+            type Value = RequestId;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("an integer, a string or null")
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<RequestId, E> {
+                Ok(RequestId::Number(v))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<RequestId, E> {
+                i64::try_from(v)
+                    .map(RequestId::Number)
+                    .map_err(|_| E::custom(format!("request id {} is out of range", v)))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<RequestId, E> {
+                Err(E::custom(format!("request id must be an integer, got {}", v)))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<RequestId, E> {
+                Ok(RequestId::String(v.to_string()))
+            }
+
+            fn visit_string<E: de::Error>(self, v: String) -> Result<RequestId, E> {
+                Ok(RequestId::String(v))
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<RequestId, E> {
+                Ok(RequestId::Null)
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<RequestId, E> {
+                Ok(RequestId::Null)
+            }
+        }
+
+        deserializer.deserialize_any(RequestIdVisitor)
+    }
+}
+
+/// 反序列化存在的id字段，`null`为`Some(RequestId::Null)`，缺少字段时由`default`得到`None`
+fn deserialize_present_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<RequestId>, D::Error> {
+    RequestId::deserialize(deserializer).map(Some)
+}
+
+/// JSON-RPC请求结构
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JsonRpcRequest {
+    pub jsonrpc: String,
+    /// 请求id，为`None`时是通知
+    #[serde(default, deserialize_with = "deserialize_present_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<RequestId>,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+}
+
+impl JsonRpcRequest {
+    /// 创建请求
+    pub fn new(id: impl Into<RequestId>, method: impl Into<String>, params: Option<Value>) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id: Some(id.into()),
+            method: method.into(),
+            params,
+        }
+    }
+
+    /// 解析一条请求消息
+    ///
+    /// 不是合法JSON时返回`id`为`null`的`-32700`错误响应；是JSON但不是有效请求（例如id带小数）时
+    /// 返回`-32600`错误响应，能读出有效id时使用该id，否则为`null`。
+    pub fn parse(text: &str) -> Result<Self, Box<JsonRpcResponse>> {
+        let value: Value = serde_json::from_str(text)
+            .map_err(|e| Box::new(JsonRpcResponse::error(RequestId::Null, PARSE_ERROR, format!("Parse error: {}", e))))?;
+        let id = value
+            .get("id")
+            .and_then(|id| RequestId::deserialize(id).ok())
+            .unwrap_or_default();
+        serde_json::from_value(value)
+            .map_err(|e| Box::new(JsonRpcResponse::error(id, INVALID_REQUEST, format!("Invalid request: {}", e))))
+    }
+
+    /// 是否为通知（没有id）
+    pub fn is_notification(&self) -> bool {
+        self.id.is_none()
+    }
+}
+
+/// JSON-RPC响应结构
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JsonRpcResponse {
+    pub jsonrpc: String,
+    /// 对应请求的id，总是会被序列化，无法确定时为`null`
+    #[serde(default)]
+    pub id: RequestId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonRpcError>,
+}
+
+impl JsonRpcResponse {
+    /// 创建成功响应
+    pub fn success(id: impl Into<RequestId>, result: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id: id.into(),
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    /// 创建错误响应
+    pub fn error(id: impl Into<RequestId>, code: i32, message: impl Into<String>) -> Self {
+        Self::from_error(id, JsonRpcError::new(code, message))
+    }
+
+    /// 用已有的错误对象创建错误响应
+    pub fn from_error(id: impl Into<RequestId>, error: JsonRpcError) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id: id.into(),
+            result: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// JSON-RPC通知结构
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JsonRpcNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+}
+
+impl JsonRpcNotification {
+    /// 创建通知
+    pub fn new(method: impl Into<String>, params: Option<Value>) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            method: method.into(),
+            params,
+        }
+    }
+}
+
+/// JSON-RPC错误结构
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JsonRpcError {
+    pub code: i32,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl JsonRpcError {
+    /// 创建错误
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// 附加错误数据
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
+
+/// 实现的名称和版本，用于`initialize`的`clientInfo`和`serverInfo`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Implementation {
+    /// 名称
+    #[serde(default)]
+    pub name: String,
+    /// 人类可读的显示名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// 版本
+    #[serde(default)]
+    pub version: String,
+}
+
+/// 只有`listChanged`一个选项的能力（`roots`、`prompts`、`tools`）
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ListChangedCapability {
+    /// 列表变化时是否发送通知
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub list_changed: Option<bool>,
+}
+
+/// 服务器的资源能力
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourcesCapability {
+    /// 是否支持订阅单个资源的更新
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subscribe: Option<bool>,
+    /// 列表变化时是否发送通知
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub list_changed: Option<bool>,
+}
+
+/// 服务器向客户端发送日志的能力，规范没有定义选项
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct LoggingCapability {}
+
+/// 服务器支持参数补全的能力，规范没有定义选项
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CompletionsCapability {}
+
+/// 客户端在`initialize`中声明的能力
+///
+/// ```rust
+/// use rustmcp::protocol::ClientCapabilities;
+/// use serde_json::json;
+///
+/// let capabilities = ClientCapabilities::builder()
+///     .roots(true)
+///     .sampling()
+///     .experimental("acme/trace", json!({ "format": "w3c" }))
+///     .build();
+/// let expected = json!({
+///     "experimental": { "acme/trace": { "format": "w3c" } },
+///     "roots": { "listChanged": true },
+///     "sampling": {}
+/// });
+/// assert_eq!(serde_json::to_value(&capabilities).unwrap(), expected);
+/// assert_eq!(serde_json::from_value::<ClientCapabilities>(expected).unwrap(), capabilities);
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientCapabilities {
+    /// 非标准的实验性能力
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experimental: Option<serde_json::Map<String, Value>>,
+    /// 客户端提供根目录列表
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roots: Option<ListChangedCapability>,
+    /// 客户端支持服务器发起的采样请求
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<serde_json::Map<String, Value>>,
+    /// 客户端支持服务器向用户询问信息
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elicitation: Option<serde_json::Map<String, Value>>,
+}
+
+impl ClientCapabilities {
+    /// 创建构建器，例如在测试或代理中构造`initialize`参数
+    pub fn builder() -> ClientCapabilitiesBuilder {
+        ClientCapabilitiesBuilder::default()
+    }
+}
+
+/// [`ClientCapabilities`]的构建器
+#[derive(Debug, Clone, Default)]
+pub struct ClientCapabilitiesBuilder {
+    capabilities: ClientCapabilities,
+}
+
+impl ClientCapabilitiesBuilder {
+    /// 声明提供根目录列表，`list_changed`表示列表变化时发送通知
+    pub fn roots(mut self, list_changed: bool) -> Self {
+        self.capabilities.roots = Some(ListChangedCapability { list_changed: Some(list_changed) });
+        self
+    }
+
+    /// 声明支持服务器发起的采样请求
+    pub fn sampling(mut self) -> Self {
+        self.capabilities.sampling = Some(serde_json::Map::new());
+        self
+    }
+
+    /// 声明支持服务器向用户询问信息
+    pub fn elicitation(mut self) -> Self {
+        self.capabilities.elicitation = Some(serde_json::Map::new());
+        self
+    }
+
+    /// 添加一项实验性能力，同名的能力被替换
+    pub fn experimental(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.capabilities.experimental.get_or_insert_with(Default::default).insert(key.into(), value);
+        self
+    }
+
+    /// 构建能力
+    pub fn build(self) -> ClientCapabilities {
+        self.capabilities
+    }
+}
+
+/// 服务器在`initialize`结果中声明的能力
+///
+/// 用[`ServerCapabilities::builder`]组合，字段名与规范相同：
+///
+/// ```rust
+/// use rustmcp::protocol::ServerCapabilities;
+/// use serde_json::json;
+///
+/// let capabilities = ServerCapabilities::builder()
+///     .tools(true)
+///     .resources(true, true)
+///     .prompts(false)
+///     .logging()
+///     .completions()
+///     .experimental("acme/batching", json!({ "maxCalls": 16 }))
+///     .build();
+/// let expected = json!({
+///     "experimental": { "acme/batching": { "maxCalls": 16 } },
+///     "logging": {},
+///     "completions": {},
+///     "prompts": { "listChanged": false },
+///     "resources": { "subscribe": true, "listChanged": true },
+///     "tools": { "listChanged": true }
+/// });
+/// assert_eq!(serde_json::to_value(&capabilities).unwrap(), expected);
+/// assert_eq!(serde_json::from_value::<ServerCapabilities>(expected).unwrap(), capabilities);
+/// assert_eq!(serde_json::to_value(ServerCapabilities::builder().build()).unwrap(), json!({}));
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerCapabilities {
+    /// 非标准的实验性能力
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experimental: Option<serde_json::Map<String, Value>>,
+    /// 服务器向客户端发送日志
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logging: Option<LoggingCapability>,
+    /// 服务器支持参数补全
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completions: Option<CompletionsCapability>,
+    /// 服务器提供提示
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompts: Option<ListChangedCapability>,
+    /// 服务器提供资源
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourcesCapability>,
+    /// 服务器提供工具
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<ListChangedCapability>,
+}
+
+impl ServerCapabilities {
+    /// 创建构建器，没有声明的能力不出现在结果中
+    pub fn builder() -> ServerCapabilitiesBuilder {
+        ServerCapabilitiesBuilder::default()
+    }
+}
+
+/// [`ServerCapabilities`]的构建器
+#[derive(Debug, Clone, Default)]
+pub struct ServerCapabilitiesBuilder {
+    capabilities: ServerCapabilities,
+}
+
+impl ServerCapabilitiesBuilder {
+    /// 声明提供工具，`list_changed`表示列表变化时发送通知
+    pub fn tools(mut self, list_changed: bool) -> Self {
+        self.capabilities.tools = Some(ListChangedCapability { list_changed: Some(list_changed) });
+        self
+    }
+
+    /// 声明提供资源，`subscribe`表示支持订阅单个资源的更新
+    pub fn resources(mut self, subscribe: bool, list_changed: bool) -> Self {
+        self.capabilities.resources = Some(ResourcesCapability {
+            subscribe: Some(subscribe),
+            list_changed: Some(list_changed),
+        });
+        self
+    }
+
+    /// 声明提供提示
+    pub fn prompts(mut self, list_changed: bool) -> Self {
+        self.capabilities.prompts = Some(ListChangedCapability { list_changed: Some(list_changed) });
+        self
+    }
+
+    /// 声明向客户端发送日志
+    pub fn logging(mut self) -> Self {
+        self.capabilities.logging = Some(LoggingCapability {});
+        self
+    }
+
+    /// 声明支持参数补全
+    pub fn completions(mut self) -> Self {
+        self.capabilities.completions = Some(CompletionsCapability {});
+        self
+    }
+
+    /// 添加一项实验性能力，同名的能力被替换
+    pub fn experimental(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.capabilities.experimental.get_or_insert_with(Default::default).insert(key.into(), value);
+        self
+    }
+
+    /// 构建能力
+    pub fn build(self) -> ServerCapabilities {
+        self.capabilities
+    }
+}
+
+/// `initialize`请求的参数
+///
+/// 服务器接受不完整的参数：缺少的字段取默认值，缺少`protocolVersion`时使用默认的协议版本。
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InitializeParams {
+    /// 客户端请求的协议版本
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol_version: Option<String>,
+    /// 客户端声明的能力
+    #[serde(default)]
+    pub capabilities: ClientCapabilities,
+    /// 客户端信息
+    #[serde(default)]
+    pub client_info: Implementation,
+}
+
+impl InitializeParams {
+    /// 从请求的`params`读取，无法解析时返回默认值
+    pub fn from_params(params: Option<&Value>) -> Self {
+        params
+            .and_then(|params| serde_json::from_value(params.clone()).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// `initialize`请求的结果
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InitializeResult {
+    /// 服务器选择的协议版本
+    pub protocol_version: String,
+    /// 服务器声明的能力
+    pub capabilities: ServerCapabilities,
+    /// 服务器信息
+    pub server_info: Implementation,
+    /// 给客户端（通常是模型）的使用说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+    /// 附加的元数据，例如`readOnly`和`warnings`
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<serde_json::Map<String, Value>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initialize_messages_from_the_specification_round_trip() {
+        // 消息取自MCP规范的生命周期一节
+        let request = r#"{
+          "jsonrpc": "2.0",
+          "id": 1,
+          "method": "initialize",
+          "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {
+              "roots": {
+                "listChanged": true
+              },
+              "sampling": {},
+              "elicitation": {}
+            },
+            "clientInfo": {
+              "name": "ExampleClient",
+              "title": "Example Client Display Name",
+              "version": "1.0.0"
+            }
+          }
+        }"#;
+        let request = JsonRpcRequest::parse(request).unwrap();
+        let params: InitializeParams = serde_json::from_value(request.params.clone().unwrap()).unwrap();
+        assert_eq!(params.protocol_version.as_deref(), Some("2024-11-05"));
+        assert_eq!(params.capabilities.roots.as_ref().unwrap().list_changed, Some(true));
+        assert!(params.capabilities.sampling.is_some());
+        assert_eq!(params.client_info.title.as_deref(), Some("Example Client Display Name"));
+        assert_eq!(serde_json::to_value(&params).unwrap(), request.params.unwrap());
+
+        let response = r#"{
+          "jsonrpc": "2.0",
+          "id": 1,
+          "result": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {
+              "logging": {},
+              "prompts": {
+                "listChanged": true
+              },
+              "resources": {
+                "subscribe": true,
+                "listChanged": true
+              },
+              "tools": {
+                "listChanged": true
+              }
+            },
+            "serverInfo": {
+              "name": "ExampleServer",
+              "title": "Example Server Display Name",
+              "version": "1.0.0"
+            },
+            "instructions": "Optional instructions for the client"
+          }
+        }"#;
+        let response: JsonRpcResponse = serde_json::from_str(response).unwrap();
+        let result: InitializeResult = serde_json::from_value(response.result.clone().unwrap()).unwrap();
+        assert_eq!(result.capabilities.resources.as_ref().unwrap().subscribe, Some(true));
+        assert_eq!(result.server_info.name, "ExampleServer");
+        assert_eq!(result.instructions.as_deref(), Some("Optional instructions for the client"));
+        assert_eq!(serde_json::to_value(&result).unwrap(), response.result.unwrap());
+
+        // 服务器容忍不完整的参数
+        let params = InitializeParams::from_params(Some(&serde_json::json!({ "clientInfo": { "name": "bare" } })));
+        assert_eq!((params.protocol_version, params.client_info.name.as_str()), (None, "bare"));
+    }
+}
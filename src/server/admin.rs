@@ -0,0 +1,686 @@
+//! 运行时管理接口
+//!
+//! [`create_admin_router`]返回一个可以嵌套到任意路径下的路由，用于在服务器运行时注册、删除、
+//! 启用和禁用工具、资源与提示。也可以设置[`Settings::admin_token`](crate::Settings::admin_token)，
+//! 让[`create_app`](crate::create_app)在`/admin`下挂载，并使用Bearer令牌认证。
+//!
+//! | 方法 | 路径 | 说明 |
+//! | --- | --- | --- |
+//! | `POST` | `/tools` | 按清单注册工具，后端为命令模板或HTTP转发目标 |
+//! | `DELETE` | `/tools/{name}` | 删除工具 |
+//! | `POST` | `/tools/{name}/disable` | 禁用工具 |
+//! | `POST` | `/tools/{name}/enable` | 启用工具 |
+//! | `POST` | `/resources` | 注册内联文本或文件资源 |
+//! | `DELETE` | `/resources/{uri}` | 删除资源 |
+//! | `POST` | `/resources/{uri}/disable` | 停用资源，不再列出，读取时返回错误 |
+//! | `POST` | `/resources/{uri}/enable` | 启用资源 |
+//! | `POST` | `/prompts` | 注册模板提示 |
+//! | `DELETE` | `/prompts/{name}` | 删除提示 |
+//! | `POST` | `/prompts/{name}/disable` | 停用提示 |
+//! | `POST` | `/prompts/{name}/enable` | 启用提示 |
+//! | `GET` | `/diagnostics/capture` | 当前的内容采集设置 |
+//! | `PUT` | `/diagnostics/capture` | 按[`CaptureConfig`]开启内容采集 |
+//! | `DELETE` | `/diagnostics/capture` | 关闭内容采集 |
+//! | `GET` | `/diagnostics/captures` | 最近的采集 |
+//! | `GET` | `/mirror/divergences` | [请求镜像](crate::server::mirror)的计数和最近的不一致记录 |
+//! | `DELETE` | `/mirror/divergences` | 清空不一致记录 |
+//! | `GET` | `/trace` | 当前的[协议跟踪](crate::server::trace)设置和丢弃的条目数 |
+//! | `PUT` | `/trace` | 按[`TraceConfig`]开启协议跟踪，例如`{"path": "/var/log/mcp-trace.jsonl"}` |
+//! | `DELETE` | `/trace` | 关闭协议跟踪 |
+//! | `GET` | `/trace/tail?lines=N` | 最近写出的N条跟踪记录，默认100条 |
+//! | `POST` | `/diagnostics/probe-resources` | [探测](crate::server::probe)每个资源能否读取，请求体可以是`{"concurrency": 8}` |
+//! | `GET` | `/sessions` | 当前的会话，参见[`connections`](crate::server::connections) |
+//! | `DELETE` | `/sessions/{id}` | 强制结束会话 |
+//! | `GET` | `/quotas` | 工具和资源的配额用量，参见[`quota`](crate::server::quota) |
+//! | `GET` | `/method-policy` | 当前的[方法策略](crate::server::policy) |
+//! | `PUT` | `/method-policy` | 按[`MethodPolicy`]更换方法策略，例如`{"deny": ["resources/*"]}` |
+//! | `DELETE` | `/method-policy` | 取消方法策略，允许所有方法 |
+//!
+//! 每个请求都必须通过[`AdminAuth`]的校验，否则返回401；每次操作（包括被拒绝的请求）都会在
+//! `rustmcp::admin`目标下记录一条审计日志。清单无法解析、模式有错误，或重复行为为`Error`时
+//! 注册同名项，返回400和错误详情。工具清单的格式与清单目录热加载相同，也支持`proxy`后端。
+//!
+//! 手动停用的资源与[探测](crate::server::probe)停用的资源一样不出现在列表中，但探测成功不会恢复它，
+//! 只能通过`enable`启用。重新注册同一个资源或提示时恢复为启用状态。
+//!
+//! ```rust
+//! use rustmcp::{create_admin_router, create_app, AdminAuth, RustMCP};
+//!
+//! let rustmcp = RustMCP::new();
+//! let admin = create_admin_router(rustmcp.clone(), AdminAuth::bearer_token("s3cret"));
+//! let app = create_app(rustmcp).nest("/internal/admin", admin);
+//! ```
+//!
+//! 注册的命令工具可以执行任意程序，管理接口只应暴露给受信任的调用方。
+
+use axum::{
+    body::Bytes,
+    extract::{Extension, Path, Query, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Json, Response},
+    routing::{delete, get, post},
+    Router,
+};
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::server::capture::CaptureConfig;
+use crate::server::trace::TraceConfig;
+use crate::server::context::Identity;
+use crate::server::diagnostics::{check_item, Item, Severity, ValidationIssue};
+use crate::server::command::interpolate;
+use crate::server::content::Content;
+use crate::server::manifest::ToolManifest;
+use crate::server::policy::MethodPolicy;
+use crate::server::prompts::{FunctionPrompt, PromptMessage};
+use crate::server::resources::FunctionResource;
+use crate::server::{constant_time_eq, RustMCP};
+
+/// 审计日志的目标
+const AUDIT_TARGET: &str = "rustmcp::admin";
+
+/// 校验管理请求的函数，通过时返回调用方身份
+pub type AdminValidator = Arc<dyn Fn(&HeaderMap) -> Option<Identity> + Send + Sync>;
+
+/// 管理接口的认证方式
+#[derive(Clone)]
+pub struct AdminAuth {
+    validator: AdminValidator,
+}
+
+impl AdminAuth {
+    /// 使用自定义函数校验请求头，返回`None`时拒绝请求
+    pub fn new<F>(validator: F) -> Self
+    where
+        F: Fn(&HeaderMap) -> Option<Identity> + Send + Sync + 'static,
+    {
+        Self {
+            validator: Arc::new(validator),
+        }
+    }
+
+    /// 要求`Authorization: Bearer <token>`请求头，通过时调用方身份为`admin`
+    pub fn bearer_token(token: impl Into<String>) -> Self {
+        let token = token.into();
+        Self::new(move |headers| {
+            let provided = headers
+                .get("authorization")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))?;
+            constant_time_eq(provided.as_bytes(), token.as_bytes()).then(|| Identity::new("admin"))
+        })
+    }
+}
+
+impl std::fmt::Debug for AdminAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminAuth").finish_non_exhaustive()
+    }
+}
+
+/// 资源清单
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ResourceManifest {
+    /// 资源URI
+    uri: String,
+    /// 资源名称，默认为URI
+    #[serde(default)]
+    name: Option<String>,
+    /// 资源标题
+    #[serde(default)]
+    title: Option<String>,
+    /// 资源描述
+    #[serde(default)]
+    description: Option<String>,
+    /// MIME类型，默认为`text/plain`
+    #[serde(default)]
+    mime_type: Option<String>,
+    /// 每次读取时读取的文件
+    #[serde(default)]
+    path: Option<PathBuf>,
+    /// 内联文本
+    #[serde(default)]
+    text: Option<String>,
+}
+
+/// 提示清单
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PromptManifest {
+    /// 提示名称
+    name: String,
+    /// 提示标题
+    #[serde(default)]
+    title: Option<String>,
+    /// 提示描述
+    #[serde(default)]
+    description: Option<String>,
+    /// 消息角色
+    #[serde(default = "default_role")]
+    role: String,
+    /// 提示模板，`{arg}`会被替换为参数值
+    template: String,
+    /// 提示参数
+    #[serde(default)]
+    arguments: Vec<PromptArgumentManifest>,
+}
+
+fn default_role() -> String {
+    "user".to_string()
+}
+
+/// 提示参数
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PromptArgumentManifest {
+    /// 参数名称
+    name: String,
+    /// 参数描述
+    #[serde(default)]
+    description: String,
+    /// 是否必填
+    #[serde(default)]
+    required: bool,
+}
+
+/// 创建管理接口路由
+///
+/// `rustmcp`应为提供服务的实例的克隆，克隆共享同一份注册表和通知广播器，
+/// 通过管理接口做的修改会立即对客户端可见，并发送对应的列表变更通知。
+pub fn create_admin_router(rustmcp: RustMCP, auth: AdminAuth) -> Router {
+    Router::new()
+        .route("/tools", post(register_tool))
+        .route("/tools/:name", delete(remove_tool))
+        .route("/tools/:name/disable", post(disable_tool))
+        .route("/tools/:name/enable", post(enable_tool))
+        .route("/resources", post(register_resource))
+        .route("/resources/*uri", delete(remove_resource).post(toggle_resource))
+        .route("/prompts", post(register_prompt))
+        .route("/prompts/:name", delete(remove_prompt))
+        .route("/prompts/:name/disable", post(disable_prompt))
+        .route("/prompts/:name/enable", post(enable_prompt))
+        .route("/diagnostics/capture", get(capture_config).put(enable_capture).delete(disable_capture))
+        .route("/diagnostics/captures", get(list_captures))
+        .route("/mirror/divergences", get(list_divergences).delete(clear_divergences))
+        .route("/trace", get(trace_config).put(enable_trace).delete(disable_trace))
+        .route("/trace/tail", get(trace_tail))
+        .route("/diagnostics/probe-resources", post(probe_resources))
+        .route("/sessions", get(list_sessions))
+        .route("/sessions/:id", delete(disconnect_session))
+        .route("/quotas", get(list_quotas))
+        .route("/method-policy", get(method_policy).put(set_method_policy).delete(clear_method_policy))
+        .route_layer(middleware::from_fn_with_state(auth, authenticate))
+        .with_state(Arc::new(rustmcp))
+}
+
+/// 校验请求，通过时把调用方身份放入请求扩展
+async fn authenticate(State(auth): State<AdminAuth>, mut request: Request, next: Next) -> Response {
+    match (auth.validator)(request.headers()) {
+        Some(identity) => {
+            request.extensions_mut().insert(identity);
+            next.run(request).await
+        }
+        None => {
+            warn!(target: AUDIT_TARGET, "rejected unauthenticated {} {}", request.method(), request.uri().path());
+            error_response(StatusCode::UNAUTHORIZED, "missing or invalid credentials".to_string(), &[])
+        }
+    }
+}
+
+type AdminState = State<Arc<RustMCP>>;
+
+async fn register_tool(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, body: Bytes) -> Response {
+    let tool = match parse::<ToolManifest>(&body).and_then(ToolManifest::into_tool) {
+        Ok(tool) => tool,
+        Err(message) => return rejected(&identity, "register tool", "?", message, &[]),
+    };
+    let name = tool.name.clone();
+    let issues = check_item(Item::Tool(&tool));
+    register(&identity, "tool", &name, issues, || rustmcp.try_add_tool(tool))
+}
+
+async fn register_resource(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, body: Bytes) -> Response {
+    let resource = match parse::<ResourceManifest>(&body).and_then(manifest_resource) {
+        Ok(resource) => resource,
+        Err(message) => return rejected(&identity, "register resource", "?", message, &[]),
+    };
+    let uri = resource.uri.clone();
+    let issues = check_item(Item::Resource(&resource.to_resource()));
+    register(&identity, "resource", &uri, issues, || rustmcp.try_add_resource(resource))
+}
+
+async fn register_prompt(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, body: Bytes) -> Response {
+    let prompt = match parse::<PromptManifest>(&body).and_then(manifest_prompt) {
+        Ok(prompt) => prompt,
+        Err(message) => return rejected(&identity, "register prompt", "?", message, &[]),
+    };
+    let name = prompt.name.clone();
+    let issues = check_item(Item::Prompt(&prompt));
+    register(&identity, "prompt", &name, issues, || rustmcp.try_add_prompt(prompt))
+}
+
+async fn remove_tool(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, Path(name): Path<String>) -> Response {
+    removed(&identity, "tool", &name, rustmcp.remove_tool(&name))
+}
+
+async fn remove_resource(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, Path(uri): Path<String>) -> Response {
+    removed(&identity, "resource", &uri, rustmcp.remove_resource(&uri))
+}
+
+async fn remove_prompt(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, Path(name): Path<String>) -> Response {
+    removed(&identity, "prompt", &name, rustmcp.remove_prompt(&name))
+}
+
+async fn disable_tool(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, Path(name): Path<String>) -> Response {
+    toggled(&identity, "tool", &name, false, rustmcp.disable_tool(&name))
+}
+
+async fn enable_tool(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, Path(name): Path<String>) -> Response {
+    toggled(&identity, "tool", &name, true, rustmcp.enable_tool(&name))
+}
+
+/// 资源URI可以包含`/`，路由只能以通配段结尾，动作从路径末尾拆出
+async fn toggle_resource(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, Path(path): Path<String>) -> Response {
+    if let Some(uri) = path.strip_suffix("/disable") {
+        toggled(&identity, "resource", uri, false, rustmcp.disable_resource(uri))
+    } else if let Some(uri) = path.strip_suffix("/enable") {
+        toggled(&identity, "resource", uri, true, rustmcp.enable_resource(uri))
+    } else {
+        error_response(StatusCode::NOT_FOUND, format!("unknown resource action: '{}'", path), &[])
+    }
+}
+
+async fn disable_prompt(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, Path(name): Path<String>) -> Response {
+    toggled(&identity, "prompt", &name, false, rustmcp.disable_prompt(&name))
+}
+
+async fn enable_prompt(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, Path(name): Path<String>) -> Response {
+    toggled(&identity, "prompt", &name, true, rustmcp.enable_prompt(&name))
+}
+
+async fn capture_config(State(rustmcp): AdminState) -> Json<Value> {
+    Json(json!({ "capture": rustmcp.capture_config() }))
+}
+
+async fn enable_capture(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, body: Bytes) -> Response {
+    let config: CaptureConfig = match serde_json::from_slice(&body) {
+        Ok(config) => config,
+        Err(e) => return rejected(&identity, "enable", "capture", format!("invalid capture config: {}", e), &[]),
+    };
+    info!(
+        target: AUDIT_TARGET,
+        "{} enable 'capture': method={:?} tool={:?} sample_rate={}",
+        identity.subject, config.method, config.tool, config.sample_rate
+    );
+    rustmcp.enable_capture(config.clone());
+    Json(json!({ "capture": config })).into_response()
+}
+
+async fn disable_capture(State(rustmcp): AdminState, Extension(identity): Extension<Identity>) -> StatusCode {
+    info!(target: AUDIT_TARGET, "{} disable 'capture': ok", identity.subject);
+    rustmcp.disable_capture();
+    StatusCode::NO_CONTENT
+}
+
+async fn list_captures(State(rustmcp): AdminState, Extension(identity): Extension<Identity>) -> Json<Value> {
+    let captures = rustmcp.captures();
+    info!(target: AUDIT_TARGET, "{} read 'captures': {} entries", identity.subject, captures.len());
+    Json(json!({ "captures": captures }))
+}
+
+async fn list_divergences(State(rustmcp): AdminState, Extension(identity): Extension<Identity>) -> Json<Value> {
+    let divergences = rustmcp.divergences();
+    info!(target: AUDIT_TARGET, "{} read 'divergences': {} entries", identity.subject, divergences.len());
+    Json(json!({ "stats": rustmcp.mirror_stats(), "divergences": divergences }))
+}
+
+async fn clear_divergences(State(rustmcp): AdminState, Extension(identity): Extension<Identity>) -> StatusCode {
+    info!(target: AUDIT_TARGET, "{} clear 'divergences': ok", identity.subject);
+    rustmcp.clear_divergences();
+    StatusCode::NO_CONTENT
+}
+
+async fn trace_config(State(rustmcp): AdminState) -> Json<Value> {
+    Json(json!({ "trace": rustmcp.trace_config(), "dropped": rustmcp.dropped_trace_entries() }))
+}
+
+async fn enable_trace(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, body: Bytes) -> Response {
+    let config: TraceConfig = match serde_json::from_slice(&body) {
+        Ok(config) => config,
+        Err(e) => return rejected(&identity, "enable", "trace", format!("invalid trace config: {}", e), &[]),
+    };
+    if let Err(e) = rustmcp.enable_trace(config.clone()) {
+        return rejected(&identity, "enable", "trace", format!("cannot open {}: {}", config.path.display(), e), &[]);
+    }
+    info!(
+        target: AUDIT_TARGET,
+        "{} enable 'trace': path={} per_session={}",
+        identity.subject, config.path.display(), config.per_session
+    );
+    Json(json!({ "trace": config })).into_response()
+}
+
+async fn disable_trace(State(rustmcp): AdminState, Extension(identity): Extension<Identity>) -> StatusCode {
+    info!(target: AUDIT_TARGET, "{} disable 'trace': ok", identity.subject);
+    rustmcp.disable_trace();
+    StatusCode::NO_CONTENT
+}
+
+/// `/trace/tail`的查询参数
+#[derive(Deserialize)]
+struct TailQuery {
+    #[serde(default = "default_tail_lines")]
+    lines: usize,
+}
+
+fn default_tail_lines() -> usize {
+    100
+}
+
+async fn trace_tail(
+    State(rustmcp): AdminState,
+    Extension(identity): Extension<Identity>,
+    Query(query): Query<TailQuery>,
+) -> Json<Value> {
+    let entries = rustmcp.trace_tail(query.lines);
+    info!(target: AUDIT_TARGET, "{} read 'trace': {} entries", identity.subject, entries.len());
+    Json(json!({ "entries": entries }))
+}
+
+/// 探测请求，同时探测的资源数默认为8
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProbeRequest {
+    #[serde(default = "default_probe_concurrency")]
+    concurrency: usize,
+}
+
+fn default_probe_concurrency() -> usize {
+    8
+}
+
+async fn probe_resources(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, body: Bytes) -> Response {
+    let request = if body.is_empty() {
+        ProbeRequest { concurrency: default_probe_concurrency() }
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(request) => request,
+            Err(e) => return rejected(&identity, "probe", "resources", format!("invalid probe request: {}", e), &[]),
+        }
+    };
+    let results = rustmcp.probe_resources(request.concurrency).await;
+    let failed = results.iter().filter(|(_, result)| result.is_err()).count();
+    info!(target: AUDIT_TARGET, "{} probe 'resources': {} probed, {} failed", identity.subject, results.len(), failed);
+    let results: Vec<Value> = results
+        .into_iter()
+        .map(|(uri, result)| match result {
+            Ok(()) => json!({ "uri": uri, "ok": true }),
+            Err(error) => json!({ "uri": uri, "ok": false, "error": error }),
+        })
+        .collect();
+    Json(json!({ "results": results, "disabled": rustmcp.disabled_resources() })).into_response()
+}
+
+async fn list_sessions(State(rustmcp): AdminState, Extension(identity): Extension<Identity>) -> Json<Value> {
+    let sessions = rustmcp.sessions();
+    info!(target: AUDIT_TARGET, "{} read 'sessions': {} entries", identity.subject, sessions.len());
+    Json(json!({ "sessions": sessions }))
+}
+
+async fn list_quotas(State(rustmcp): AdminState, Extension(identity): Extension<Identity>) -> Json<Value> {
+    let quotas = rustmcp.quota_status();
+    info!(target: AUDIT_TARGET, "{} read 'quotas': {} entries", identity.subject, quotas.len());
+    Json(json!({ "quotas": quotas }))
+}
+
+async fn disconnect_session(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, Path(id): Path<String>) -> Response {
+    if rustmcp.disconnect_session(&id) {
+        info!(target: AUDIT_TARGET, "{} disconnect session '{}': disconnected", identity.subject, id);
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        not_found(&identity, "disconnect session", "session", &id)
+    }
+}
+
+async fn method_policy(State(rustmcp): AdminState) -> Json<Value> {
+    Json(json!({ "methodPolicy": rustmcp.method_policy() }))
+}
+
+async fn set_method_policy(State(rustmcp): AdminState, Extension(identity): Extension<Identity>, body: Bytes) -> Response {
+    let policy: MethodPolicy = match serde_json::from_slice(&body) {
+        Ok(policy) => policy,
+        Err(e) => return rejected(&identity, "set", "method-policy", format!("invalid method policy: {}", e), &[]),
+    };
+    info!(target: AUDIT_TARGET, "{} set 'method-policy': {:?}", identity.subject, policy);
+    rustmcp.set_method_policy(Some(policy.clone()));
+    Json(json!({ "methodPolicy": policy })).into_response()
+}
+
+async fn clear_method_policy(State(rustmcp): AdminState, Extension(identity): Extension<Identity>) -> StatusCode {
+    info!(target: AUDIT_TARGET, "{} clear 'method-policy': ok", identity.subject);
+    rustmcp.set_method_policy(None);
+    StatusCode::NO_CONTENT
+}
+
+/// 解析请求体中的清单
+fn parse<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<T, String> {
+    serde_json::from_slice(body).map_err(|e| format!("invalid manifest: {}", e))
+}
+
+/// 校验没有错误时注册，返回201；重复行为为`Ignore`且已存在时返回200
+fn register(
+    identity: &Identity,
+    kind: &str,
+    item: &str,
+    issues: Vec<ValidationIssue>,
+    add: impl FnOnce() -> Result<bool, String>,
+) -> Response {
+    let action = format!("register {}", kind);
+    if issues.iter().any(|issue| issue.severity == Severity::Error) {
+        return rejected(identity, &action, item, format!("{} '{}' failed validation", kind, item), &issues);
+    }
+    let warnings: Vec<String> = issues.iter().map(|issue| issue.to_string()).collect();
+    match add() {
+        Ok(true) => {
+            info!(target: AUDIT_TARGET, "{} {} '{}': registered", identity.subject, action, item);
+            (StatusCode::CREATED, Json(json!({ "registered": item, "warnings": warnings }))).into_response()
+        }
+        Ok(false) => {
+            info!(target: AUDIT_TARGET, "{} {} '{}': ignored, already exists", identity.subject, action, item);
+            Json(json!({ "registered": Value::Null, "ignored": item, "warnings": warnings })).into_response()
+        }
+        Err(message) => rejected(identity, &action, item, message, &[]),
+    }
+}
+
+fn removed(identity: &Identity, kind: &str, item: &str, existed: bool) -> Response {
+    if existed {
+        info!(target: AUDIT_TARGET, "{} remove {} '{}': removed", identity.subject, kind, item);
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        not_found(identity, &format!("remove {}", kind), kind, item)
+    }
+}
+
+fn toggled(identity: &Identity, kind: &str, item: &str, enabled: bool, existed: bool) -> Response {
+    let action = format!("{} {}", if enabled { "enable" } else { "disable" }, kind);
+    if existed {
+        info!(target: AUDIT_TARGET, "{} {} '{}': ok", identity.subject, action, item);
+        let key = if kind == "resource" { "uri" } else { "name" };
+        Json(json!({ key: item, "enabled": enabled })).into_response()
+    } else {
+        not_found(identity, &action, kind, item)
+    }
+}
+
+fn not_found(identity: &Identity, action: &str, kind: &str, item: &str) -> Response {
+    info!(target: AUDIT_TARGET, "{} {} '{}': not found", identity.subject, action, item);
+    error_response(StatusCode::NOT_FOUND, format!("{} '{}' not found", kind, item), &[])
+}
+
+/// 记录失败的操作并返回400
+fn rejected(identity: &Identity, action: &str, item: &str, message: String, issues: &[ValidationIssue]) -> Response {
+    warn!(target: AUDIT_TARGET, "{} {} '{}': rejected: {}", identity.subject, action, item, message);
+    error_response(StatusCode::BAD_REQUEST, message, issues)
+}
+
+fn error_response(status: StatusCode, message: String, issues: &[ValidationIssue]) -> Response {
+    let issues: Vec<Value> = issues
+        .iter()
+        .map(|issue| {
+            json!({
+                "severity": match issue.severity {
+                    Severity::Warning => "warning",
+                    Severity::Error => "error",
+                },
+                "item": issue.item,
+                "message": issue.message,
+            })
+        })
+        .collect();
+    let mut body = json!({ "error": message });
+    if !issues.is_empty() {
+        body["issues"] = json!(issues);
+    }
+    (status, Json(body)).into_response()
+}
+
+/// 按清单创建资源，内容来自内联文本或文件
+fn manifest_resource(manifest: ResourceManifest) -> Result<FunctionResource, String> {
+    let content = match (manifest.path, manifest.text) {
+        (Some(path), None) => ResourceContent::File(path),
+        (None, Some(text)) => ResourceContent::Text(text),
+        (Some(_), Some(_)) => return Err("only one of 'path' and 'text' may be set".to_string()),
+        (None, None) => return Err("one of 'path' or 'text' is required".to_string()),
+    };
+    let name = manifest.name.unwrap_or_else(|| manifest.uri.clone());
+    let mut resource = FunctionResource::from_function(
+        move || match &content {
+            ResourceContent::File(path) => std::fs::read_to_string(path)
+                .map(Value::String)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e)),
+            ResourceContent::Text(text) => Ok(Value::String(text.clone())),
+        },
+        manifest.uri,
+        Some(name),
+        manifest.description,
+        manifest.mime_type,
+        None,
+        None,
+        None,
+    );
+    if let Some(title) = manifest.title {
+        resource = resource.with_title(title);
+    }
+    Ok(resource)
+}
+
+enum ResourceContent {
+    File(PathBuf),
+    Text(String),
+}
+
+/// 按清单创建模板提示
+fn manifest_prompt(manifest: PromptManifest) -> Result<FunctionPrompt, String> {
+    if manifest.name.is_empty() {
+        return Err("name must not be empty".to_string());
+    }
+    let template = manifest.template.clone();
+    let role = manifest.role;
+    let required: Vec<String> = manifest.arguments.iter().filter(|a| a.required).map(|a| a.name.clone()).collect();
+    let arguments: HashMap<String, String> = manifest
+        .arguments
+        .into_iter()
+        .map(|arg| (arg.name, arg.description))
+        .collect();
+    let mut prompt = FunctionPrompt::from_function(
+        move |args: Option<HashMap<String, Value>>| {
+            let args = args.unwrap_or_default();
+            if let Some(missing) = required.iter().find(|name| !args.contains_key(*name)) {
+                return Err(format!("Missing required argument '{}'", missing));
+            }
+            Ok(vec![PromptMessage::new(role.clone(), vec![Content::text(interpolate(&template, &args))])])
+        },
+        manifest.name,
+        manifest.description,
+        None,
+        Some(arguments).filter(|a| !a.is_empty()),
+        None,
+    )
+    .with_template(manifest.template);
+    if let Some(title) = manifest.title {
+        prompt = prompt.with_title(title);
+    }
+    Ok(prompt)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::server::create_app;
+    use crate::server::test_support::{call, post};
+
+    const AUTH: [(&str, &str); 1] = [("authorization", "Bearer s3cret")];
+
+    fn server() -> (RustMCP, axum::Router) {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_resource(FunctionResource::simple("memo://notes", || Ok(json!("notes"))));
+        rustmcp.add_prompt(FunctionPrompt::simple("summarize", |_| Ok(Vec::new())));
+        let app = create_app(rustmcp.clone()).nest("/admin", create_admin_router(rustmcp.clone(), AdminAuth::bearer_token("s3cret")));
+        (rustmcp, app)
+    }
+
+    #[tokio::test]
+    async fn resources_can_be_disabled_and_stay_disabled_after_a_good_probe() {
+        let (rustmcp, app) = server();
+        let (status, _, body) = post(&app, "/admin/resources/memo://notes/disable", &AUTH, &Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "uri": "memo://notes", "enabled": false }));
+        assert_eq!(call(&app, "resources/list", json!({})).await["result"]["resources"], json!([]));
+        let response = call(&app, "resources/read", json!({ "uri": "memo://notes" })).await;
+        assert_eq!(response["error"]["data"], json!({ "uri": "memo://notes", "kind": "resource_disabled" }));
+
+        // 探测成功只恢复因探测失败而停用的资源
+        assert!(rustmcp.probe_resources(1).await[0].1.is_ok());
+        assert_eq!(rustmcp.disabled_resources(), ["memo://notes"]);
+
+        let (status, _, body) = post(&app, "/admin/resources/memo://notes/enable", &AUTH, &Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "uri": "memo://notes", "enabled": true }));
+        let response = call(&app, "resources/read", json!({ "uri": "memo://notes" })).await;
+        assert_eq!(response["result"]["contents"][0]["text"], "notes");
+    }
+
+    #[tokio::test]
+    async fn prompts_can_be_disabled_and_enabled() {
+        let (_, app) = server();
+        let (status, _, body) = post(&app, "/admin/prompts/summarize/disable", &AUTH, &Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "name": "summarize", "enabled": false }));
+        assert_eq!(call(&app, "prompts/list", json!({})).await["result"]["prompts"], json!([]));
+        let response = call(&app, "prompts/get", json!({ "name": "summarize" })).await;
+        assert_eq!(response["error"]["data"], json!({ "name": "summarize", "kind": "prompt_disabled" }));
+
+        let (status, _, _) = post(&app, "/admin/prompts/summarize/enable", &AUTH, &Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(call(&app, "prompts/get", json!({ "name": "summarize" })).await["result"].is_object());
+    }
+
+    #[tokio::test]
+    async fn toggling_unknown_items_or_without_a_token_is_refused() {
+        let (_, app) = server();
+        for path in ["/admin/prompts/missing/disable", "/admin/resources/memo://missing/enable", "/admin/resources/memo://notes"] {
+            assert_eq!(post(&app, path, &AUTH, &Value::Null).await.0, StatusCode::NOT_FOUND, "{}", path);
+        }
+        assert_eq!(post(&app, "/admin/prompts/summarize/disable", &[], &Value::Null).await.0, StatusCode::UNAUTHORIZED);
+    }
+}
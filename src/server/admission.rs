@@ -0,0 +1,250 @@
+//! 并发请求数统计和过载保护
+//!
+//! 负载突增时服务器原本接受任意多的并发请求，所有客户端的延迟一起变坏。分发器统计正在处理的
+//! JSON-RPC请求数，全局的和每个会话（没有会话时按远端IP）的，WebSocket连接按顺序处理请求，每个连接最多占用一个。
+//! REST接口的工具调用（`/mcp/call-tool`和`/mcp/tools/{name}/call`）按`tools/call`计数。超过执行时限的请求
+//! 已经返回超时错误，但处理函数仍在后台运行，计数持续到它结束。设置了
+//! [`Settings::with_in_flight_limits`](crate::Settings::with_in_flight_limits)后：
+//! - 达到全局上限或会话上限时，新的请求立即返回`-32009`错误，`data.kind`为`overloaded`，
+//!   `data`中有`scope`（`server`或`session`）、`limit`和建议的`retryAfterMs`
+//!   （[`Settings::with_overload_retry_after`](crate::Settings::with_overload_retry_after)，默认1秒）；
+//! - [`Settings::with_load_shedding_exempt`](crate::Settings::with_load_shedding_exempt)列出的方法
+//!   （例如开销很小的列表方法）不计数，也不会被拒绝；
+//! - 达到全局上限期间，以及因全局上限拒绝请求之后的`retryAfterMs`内，服务器处于降级状态，
+//!   `/health`的`degraded`为`true`。会话上限只限制单个客户端，不影响降级状态。
+//!
+//! [`RustMCP::load_metrics`](crate::RustMCP::load_metrics)和`/health`的`load`报告正在处理的请求数和拒绝的请求数。
+//!
+//! ```rust
+//! use rustmcp::{RustMCP, Settings};
+//! use std::time::Duration;
+//!
+//! // 全局最多64个、每个会话最多4个请求，列表方法不计数
+//! let settings = Settings::new()
+//!     .with_in_flight_limits(Some(64), Some(4))
+//!     .with_load_shedding_exempt(["tools/list", "resources/list", "prompts/list"])
+//!     .with_overload_retry_after(Duration::from_millis(500));
+//! let rustmcp = RustMCP::new().with_settings(settings);
+//! assert_eq!(rustmcp.load_metrics().max_in_flight, Some(64));
+//! ```
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::server::error::McpError;
+
+/// 并发请求数上限
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AdmissionLimits {
+    pub(crate) max_in_flight: Option<usize>,
+    pub(crate) max_in_flight_per_session: Option<usize>,
+    pub(crate) retry_after: Duration,
+}
+
+/// 正在处理的请求数和拒绝的请求数
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadMetrics {
+    /// 正在处理的请求数，不包括豁免的方法
+    pub in_flight: usize,
+    /// 全局上限，没有设置时为`None`
+    pub max_in_flight: Option<usize>,
+    /// 因全局上限拒绝的请求数
+    pub shed: u64,
+    /// 因会话上限拒绝的请求数
+    pub shed_per_session: u64,
+    /// 服务器是否处于降级状态
+    pub degraded: bool,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    in_flight: usize,
+    /// 每个会话正在处理的请求数，没有请求的会话不保留
+    sessions: HashMap<String, usize>,
+    shed: u64,
+    shed_per_session: u64,
+    /// 最近一次因全局上限拒绝请求的时间
+    last_shed: Option<Instant>,
+}
+
+/// 并发请求数统计
+#[derive(Debug, Default)]
+pub(crate) struct Admission {
+    counters: Mutex<Counters>,
+}
+
+/// 正在处理的请求，释放时计数减一
+#[derive(Debug)]
+pub(crate) struct Admitted {
+    admission: Arc<Admission>,
+    key: String,
+}
+
+impl Admission {
+    /// 登记`key`（会话ID或远端IP）的一个请求；达到上限时返回[`McpError::Overloaded`]
+    pub(crate) fn admit(self: &Arc<Self>, key: &str, limits: AdmissionLimits) -> Result<Admitted, McpError> {
+        let mut counters = self.counters.lock();
+        let overloaded = |scope: &str, limit: usize| McpError::Overloaded {
+            scope: scope.to_string(),
+            limit,
+            retry_after_ms: u64::try_from(limits.retry_after.as_millis()).unwrap_or(u64::MAX),
+        };
+        if let Some(limit) = limits.max_in_flight.filter(|limit| counters.in_flight >= *limit) {
+            counters.shed += 1;
+            counters.last_shed = Some(Instant::now());
+            return Err(overloaded("server", limit));
+        }
+        let session = counters.sessions.get(key).copied().unwrap_or(0);
+        if let Some(limit) = limits.max_in_flight_per_session.filter(|limit| session >= *limit) {
+            counters.shed_per_session += 1;
+            return Err(overloaded("session", limit));
+        }
+        counters.in_flight += 1;
+        *counters.sessions.entry(key.to_string()).or_default() += 1;
+        Ok(Admitted {
+            admission: self.clone(),
+            key: key.to_string(),
+        })
+    }
+
+    /// 当前的统计
+    pub(crate) fn metrics(&self, limits: AdmissionLimits) -> LoadMetrics {
+        let counters = self.counters.lock();
+        let saturated = limits.max_in_flight.is_some_and(|limit| counters.in_flight >= limit);
+        let recently_shed = counters.last_shed.is_some_and(|at| at.elapsed() < limits.retry_after);
+        LoadMetrics {
+            in_flight: counters.in_flight,
+            max_in_flight: limits.max_in_flight,
+            shed: counters.shed,
+            shed_per_session: counters.shed_per_session,
+            degraded: saturated || recently_shed,
+        }
+    }
+}
+
+impl Drop for Admitted {
+    fn drop(&mut self) {
+        let mut counters = self.admission.counters.lock();
+        counters.in_flight = counters.in_flight.saturating_sub(1);
+        if let Some(count) = counters.sessions.get_mut(&self.key) {
+            *count -= 1;
+            if *count == 0 {
+                counters.sessions.remove(&self.key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use axum::Router;
+    use serde_json::{json, Value};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::server::test_support::{get, initialize, post, request};
+    use crate::{create_app, FunctionTool, RustMCP, Settings};
+
+    async fn call(app: &Router, session: &str, id: u64, method: &str, params: Value) -> Value {
+        post(app, "/mcp", &[("mcp-session-id", session)], &request(id, method, params)).await.2
+    }
+
+    async fn health(app: &Router) -> Value {
+        get(app, "/health", &[]).await.2
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn requests_over_the_limits_are_shed_until_load_drops() {
+        let settings = Settings::new()
+            .with_in_flight_limits(Some(2), Some(1))
+            .with_load_shedding_exempt(["tools/list"])
+            .with_overload_retry_after(Duration::from_millis(200));
+        let rustmcp = RustMCP::new().with_settings(settings);
+        rustmcp.add_tool(FunctionTool::simple("slow", "Takes a while", |_| {
+            std::thread::sleep(Duration::from_millis(400));
+            Ok(json!("done"))
+        }));
+        let app = create_app(rustmcp.clone());
+        let (a, b, c) = (initialize(&app, json!({})).await, initialize(&app, json!({})).await, initialize(&app, json!({})).await);
+        let slow = || json!({ "name": "slow" });
+        let spawn = |session: &str| {
+            let (app, session) = (app.clone(), session.to_string());
+            tokio::spawn(async move { call(&app, &session, 1, "tools/call", json!({ "name": "slow" })).await })
+        };
+
+        // 每个会话最多一个请求
+        let first = spawn(&a);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let shed = call(&app, &a, 2, "tools/call", slow()).await;
+        assert_eq!(shed["error"]["code"], -32009);
+        assert_eq!(shed["error"]["data"], json!({ "kind": "overloaded", "scope": "session", "limit": 1, "retryAfterMs": 200 }));
+        assert_eq!(health(&app).await["degraded"], false);
+
+        // 全局最多两个请求，豁免的方法照常处理
+        let second = spawn(&b);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(call(&app, &c, 1, "tools/call", slow()).await["error"]["data"]["scope"], "server");
+        assert_eq!(call(&app, &c, 2, "tools/list", json!({})).await["result"]["tools"][0]["name"], "slow");
+
+        let report = health(&app).await;
+        assert_eq!(report["degraded"], true);
+        assert_eq!(report["load"], json!({ "inFlight": 2, "maxInFlight": 2, "shed": 1, "shedPerSession": 1, "degraded": true }));
+
+        // 调用完成、重试时间过去后恢复
+        assert!(first.await.unwrap()["result"]["content"][0]["text"].as_str().unwrap().contains("done"));
+        assert!(second.await.unwrap()["result"].is_object());
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(rustmcp.load_metrics().in_flight, 0);
+        assert_eq!(health(&app).await["degraded"], false);
+        assert!(call(&app, &c, 3, "tools/call", slow()).await["result"].is_object());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn rest_tool_calls_share_the_limits() {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_in_flight_limits(Some(1), None));
+        rustmcp.add_tool(FunctionTool::simple("slow", "Takes a while", |_| {
+            std::thread::sleep(Duration::from_millis(300));
+            Ok(json!("done"))
+        }));
+        let app = create_app(rustmcp.clone());
+        let running = {
+            let app = app.clone();
+            tokio::spawn(async move { post(&app, "/mcp/tools/slow/call", &[], &json!({})).await })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(rustmcp.load_metrics().in_flight, 1);
+        let (status, _, _) = post(&app, "/mcp/call-tool", &[], &json!({ "name": "slow" })).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        let (status, _, body) = post(&app, "/mcp/call-tool?format=mcp", &[], &json!({ "name": "slow" })).await;
+        assert_eq!((status, body["error"]["data"]["scope"].clone()), (StatusCode::SERVICE_UNAVAILABLE, json!("server")));
+        assert_eq!(running.await.unwrap().0, StatusCode::OK);
+        assert_eq!(rustmcp.load_metrics(), LoadMetrics { max_in_flight: Some(1), shed: 2, degraded: true, ..LoadMetrics::default() });
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn timed_out_calls_count_until_the_handler_returns() {
+        let settings = Settings::new()
+            .with_in_flight_limits(Some(1), None)
+            .with_request_timeout(Duration::from_millis(100))
+            .with_min_timeout_hint(Duration::from_millis(1));
+        let rustmcp = RustMCP::new().with_settings(settings);
+        rustmcp.add_tool(FunctionTool::simple("slow", "Ignores cancellation", |_| {
+            std::thread::sleep(Duration::from_millis(400));
+            Ok(json!("done"))
+        }));
+        let app = create_app(rustmcp.clone());
+        let (a, b) = (initialize(&app, json!({})).await, initialize(&app, json!({})).await);
+        let timed_out = call(&app, &a, 1, "tools/call", json!({ "name": "slow" })).await;
+        assert_eq!(timed_out["error"]["data"]["timeoutMs"], 100, "{}", timed_out);
+        // 处理函数仍在运行，新的调用被拒绝
+        assert_eq!(rustmcp.load_metrics().in_flight, 1);
+        assert_eq!(call(&app, &b, 2, "tools/call", json!({ "name": "slow" })).await["error"]["code"], -32009);
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(rustmcp.load_metrics().in_flight, 0);
+    }
+}
@@ -0,0 +1,573 @@
+//! REST接口的OpenAPI文档
+//!
+//! 描述[`create_app`](crate::create_app)提供的REST端点，并为每个已注册的工具生成
+//! `POST /mcp/tools/{name}/call`操作，请求体模式为工具的`inputSchema`，
+//! 响应模式为工具的`outputSchema`（如果有）。
+
+use serde_json::{Map, Value};
+
+use crate::server::RustMCP;
+
+/// OpenAPI版本
+const OPENAPI_VERSION: &str = "3.1.0";
+
+/// 生成OpenAPI文档
+pub(crate) fn build(rustmcp: &RustMCP) -> Value {
+    let settings = rustmcp.settings();
+    let mut paths = rest_paths();
+
+    let mut tools = rustmcp.mcp_list_tools();
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    for tool in tools {
+        let mut operation = serde_json::json!({
+            "operationId": format!("call_{}", operation_id_suffix(&tool.name)),
+            "summary": tool.title.clone().unwrap_or_else(|| format!("Call tool '{}'", tool.name)),
+            "tags": ["tools"],
+            "requestBody": {
+                "required": true,
+                "content": {
+                    "application/json": {
+                        "schema": tool.input_schema.clone().unwrap_or_else(|| serde_json::json!({ "type": "object" }))
+                    }
+                }
+            },
+            "responses": {
+                "200": {
+                    "description": "Tool result",
+                    "content": {
+                        "application/json": {
+                            "schema": tool.output_schema.clone().unwrap_or_else(|| serde_json::json!({}))
+                        }
+                    }
+                },
+                "400": text_response("Invalid arguments"),
+                "404": text_response("Tool not found or disabled"),
+                "500": text_response("Tool execution failed")
+            }
+        });
+        if !tool.description.is_empty() {
+            operation["description"] = Value::String(tool.description.clone());
+        }
+        paths.insert(
+            format!("/mcp/tools/{}/call", encode_path_segment(&tool.name)),
+            serde_json::json!({ "post": operation }),
+        );
+    }
+
+    serde_json::json!({
+        "openapi": OPENAPI_VERSION,
+        "info": {
+            "title": settings.server_name,
+            "version": settings.server_version,
+            "description": "REST interface of a Model Context Protocol server"
+        },
+        "tags": [
+            { "name": "mcp", "description": "MCP listing and protocol endpoints" },
+            { "name": "tools", "description": "Registered tools" }
+        ],
+        "paths": paths,
+        "components": {
+            "schemas": component_schemas()
+        }
+    })
+}
+
+/// 固定的REST端点
+fn rest_paths() -> Map<String, Value> {
+    let mut paths = Map::new();
+    paths.insert("/health".to_string(), serde_json::json!({
+        "get": {
+            "operationId": "health",
+            "summary": "Health check",
+            "tags": ["mcp"],
+            "responses": {
+                "200": {
+                    "description": "Server is healthy",
+                    "content": { "application/json": { "schema": {
+                        "type": "object",
+                        "properties": {
+                            "status": { "type": "string" },
+                            "degraded": { "type": "boolean", "description": "Whether the in-flight request limit is reached or was recently hit" },
+                            "load": {
+                                "type": "object",
+                                "description": "In-flight JSON-RPC requests and requests shed by the in-flight limits",
+                                "properties": {
+                                    "inFlight": { "type": "integer" },
+                                    "maxInFlight": { "type": "integer", "description": "Server-wide limit, null when unlimited" },
+                                    "shed": { "type": "integer", "description": "Requests refused by the server-wide limit" },
+                                    "shedPerSession": { "type": "integer", "description": "Requests refused by the per-session limit" },
+                                    "degraded": { "type": "boolean" }
+                                }
+                            },
+                            "readOnly": { "type": "boolean", "description": "Whether mutating tools are refused" },
+                            "droppedNotifications": { "type": "integer", "description": "Notifications dropped because a connection fell behind" },
+                            "droppedAuditEntries": { "type": "integer", "description": "Audit entries dropped because the audit queue was full" },
+                            "sessions": {
+                                "type": "object",
+                                "description": "HTTP session counts",
+                                "properties": {
+                                    "active": { "type": "integer" },
+                                    "created": { "type": "integer" },
+                                    "reaped": { "type": "integer", "description": "Sessions dropped after the idle timeout" },
+                                    "evicted": { "type": "integer", "description": "Sessions dropped because the session limit was reached" },
+                                    "reset": { "type": "integer", "description": "Sessions reset by a repeated initialize" }
+                                }
+                            },
+                            "dependencies": {
+                                "type": "object",
+                                "description": "Latest result of each registered health check",
+                                "additionalProperties": {
+                                    "type": "object",
+                                    "properties": {
+                                        "status": { "type": "string", "enum": ["pending", "up", "down"] },
+                                        "critical": { "type": "boolean" },
+                                        "lastError": { "type": "string" },
+                                        "lastSuccessMs": { "type": "integer" },
+                                        "lastCheckedMs": { "type": "integer" }
+                                    }
+                                }
+                            }
+                        }
+                    } } }
+                }
+            }
+        }
+    }));
+    let readiness = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "ready": { "type": "boolean" },
+            "failing": { "type": "array", "items": { "type": "string" } },
+            "toolHooks": { "type": "object", "additionalProperties": { "type": "object" } }
+        }
+    });
+    paths.insert("/ready".to_string(), serde_json::json!({
+        "get": {
+            "operationId": "ready",
+            "summary": "Readiness check",
+            "description": "Fails while any critical health check or tool startup hook has not succeeded",
+            "tags": ["mcp"],
+            "responses": {
+                "200": {
+                    "description": "All critical dependencies are up",
+                    "content": { "application/json": { "schema": readiness } }
+                },
+                "503": {
+                    "description": "A critical dependency is down or not yet checked",
+                    "content": { "application/json": { "schema": readiness } }
+                }
+            }
+        }
+    }));
+    paths.insert("/mcp/tools".to_string(), serde_json::json!({
+        "get": {
+            "operationId": "list_tools",
+            "summary": "List enabled tools",
+            "tags": ["mcp"],
+            "parameters": [{
+                "name": "group",
+                "in": "query",
+                "required": false,
+                "description": "Only list tools in this group",
+                "schema": { "type": "string" }
+            }],
+            "responses": { "200": json_array_response("Tools", "Tool") }
+        }
+    }));
+    paths.insert("/mcp/resources".to_string(), serde_json::json!({
+        "get": {
+            "operationId": "list_resources",
+            "summary": "List resources",
+            "tags": ["mcp"],
+            "responses": { "200": json_array_response("Resources", "Resource") }
+        }
+    }));
+    paths.insert("/mcp/resources/read".to_string(), serde_json::json!({
+        "get": {
+            "operationId": "read_resource",
+            "summary": "Read a resource",
+            "description": "Returns the resource contents with the resource's MIME type. Streamed resources are sent as a chunked body as the client reads it.",
+            "tags": ["mcp"],
+            "parameters": [{
+                "name": "uri",
+                "in": "query",
+                "required": true,
+                "description": "Resource URI",
+                "schema": { "type": "string" }
+            }, {
+                "name": "mimeType",
+                "in": "query",
+                "required": false,
+                "description": "MIME type of the representation to read. Defaults to the first registered representation.",
+                "schema": { "type": "string" }
+            }],
+            "responses": {
+                "200": { "description": "Resource contents" },
+                "400": text_response("The resource has no representation with the requested MIME type"),
+                "404": text_response("Resource not found")
+            }
+        }
+    }));
+    paths.insert("/mcp/prompts".to_string(), serde_json::json!({
+        "get": {
+            "operationId": "list_prompts",
+            "summary": "List prompts",
+            "tags": ["mcp"],
+            "responses": { "200": json_array_response("Prompts", "Prompt") }
+        }
+    }));
+    paths.insert("/mcp/tags".to_string(), serde_json::json!({
+        "get": {
+            "operationId": "list_tags",
+            "summary": "List defined and used tags with usage counts",
+            "tags": ["mcp"],
+            "responses": { "200": json_array_response("Tags", "Tag") }
+        }
+    }));
+    paths.insert("/mcp/call-tool".to_string(), serde_json::json!({
+        "post": {
+            "operationId": "call_tool",
+            "summary": "Call a tool by name",
+            "description": "Returns the tool's raw return value by default. With format=mcp, returns the same content/isError result as the JSON-RPC tools/call method.",
+            "tags": ["mcp"],
+            "parameters": [{
+                "name": "format",
+                "in": "query",
+                "required": false,
+                "schema": { "type": "string", "enum": ["raw", "mcp"], "default": "raw" }
+            }],
+            "requestBody": {
+                "required": true,
+                "content": {
+                    "application/json": { "schema": { "$ref": "#/components/schemas/CallToolRequest" } }
+                }
+            },
+            "responses": {
+                "200": {
+                    "description": "Tool result",
+                    "content": { "application/json": { "schema": {} } }
+                },
+                "400": text_response("Invalid request body or arguments"),
+                "404": text_response("Tool not found (format=mcp)"),
+                "500": text_response("Tool call failed")
+            }
+        }
+    }));
+    paths.insert("/mcp".to_string(), serde_json::json!({
+        "post": {
+            "operationId": "jsonrpc",
+            "summary": "MCP JSON-RPC endpoint",
+            "tags": ["mcp"],
+            "parameters": [
+                { "name": "Mcp-Session-Id", "in": "header", "required": false, "schema": { "type": "string" } }
+            ],
+            "requestBody": {
+                "required": true,
+                "content": {
+                    "application/json": { "schema": { "$ref": "#/components/schemas/JsonRpcRequest" } }
+                }
+            },
+            "responses": {
+                "200": {
+                    "description": "JSON-RPC response; the response to initialize carries the new session ID",
+                    "headers": {
+                        "Mcp-Session-Id": { "schema": { "type": "string" } }
+                    },
+                    "content": {
+                        "application/json": { "schema": { "$ref": "#/components/schemas/JsonRpcResponse" } }
+                    }
+                },
+                "404": {
+                    "description": "Unknown or expired session",
+                    "content": {
+                        "application/json": { "schema": { "$ref": "#/components/schemas/JsonRpcResponse" } }
+                    }
+                }
+            }
+        },
+        "get": {
+            "operationId": "event_stream",
+            "summary": "Server-sent notification stream of a session, resumable with Last-Event-ID",
+            "tags": ["mcp"],
+            "parameters": [
+                { "name": "Mcp-Session-Id", "in": "header", "required": true, "schema": { "type": "string" } },
+                { "name": "Last-Event-ID", "in": "header", "required": false, "schema": { "type": "string" } }
+            ],
+            "responses": {
+                "200": {
+                    "description": "Notification stream",
+                    "content": { "text/event-stream": { "schema": { "type": "string" } } }
+                },
+                "400": { "description": "Missing Mcp-Session-Id header" },
+                "404": { "description": "Unknown or expired session" }
+            }
+        },
+        "delete": {
+            "operationId": "terminate_session",
+            "summary": "Terminate a session and drop its session storage",
+            "tags": ["mcp"],
+            "parameters": [{
+                "name": "Mcp-Session-Id",
+                "in": "header",
+                "required": true,
+                "schema": { "type": "string" }
+            }],
+            "responses": {
+                "204": { "description": "Session terminated" },
+                "400": { "description": "Missing Mcp-Session-Id header" },
+                "404": { "description": "Unknown session" }
+            }
+        }
+    }));
+    paths.insert("/mcp/openapi.json".to_string(), serde_json::json!({
+        "get": {
+            "operationId": "openapi",
+            "summary": "This OpenAPI document",
+            "tags": ["mcp"],
+            "responses": {
+                "200": {
+                    "description": "OpenAPI document",
+                    "content": { "application/json": { "schema": { "type": "object" } } }
+                }
+            }
+        }
+    }));
+    paths.insert("/mcp/info".to_string(), serde_json::json!({
+        "get": {
+            "operationId": "info",
+            "summary": "Server version, capabilities, routes and enabled features",
+            "tags": ["mcp"],
+            "responses": {
+                "200": {
+                    "description": "Server information",
+                    "content": { "application/json": { "schema": { "type": "object" } } }
+                },
+                "401": { "description": "protect_info is set and the admin token is missing or wrong" }
+            }
+        }
+    }));
+    paths
+}
+
+/// 公共数据模式
+fn component_schemas() -> Value {
+    serde_json::json!({
+        "Tool": {
+            "type": "object",
+            "required": ["name", "description"],
+            "properties": {
+                "name": { "type": "string" },
+                "title": { "type": "string" },
+                "description": { "type": "string" },
+                "inputSchema": { "type": "object" },
+                "outputSchema": { "type": "object" },
+                "annotations": { "type": "object" },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "_meta": { "type": "object" }
+            }
+        },
+        "Resource": {
+            "type": "object",
+            "required": ["uri"],
+            "properties": {
+                "uri": { "type": "string" },
+                "name": { "type": "string" },
+                "title": { "type": "string" },
+                "description": { "type": "string" },
+                "mimeType": { "type": "string" },
+                "annotations": {
+                    "type": "object",
+                    "properties": {
+                        "audience": { "type": "array", "items": { "type": "string", "enum": ["user", "assistant"] } },
+                        "priority": { "type": "number", "minimum": 0, "maximum": 1 },
+                        "lastModified": { "type": "string" }
+                    }
+                },
+                "_meta": { "type": "object" }
+            }
+        },
+        "Prompt": {
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string" },
+                "title": { "type": "string" },
+                "description": { "type": "string" },
+                "_meta": { "type": "object" }
+            }
+        },
+        "Tag": {
+            "type": "object",
+            "required": ["name", "defined", "tools", "resources", "prompts"],
+            "properties": {
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "defined": { "type": "boolean" },
+                "tools": { "type": "integer" },
+                "resources": { "type": "integer" },
+                "prompts": { "type": "integer" }
+            }
+        },
+        "CallToolRequest": {
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string" },
+                "arguments": { "type": "object" }
+            }
+        },
+        "JsonRpcRequest": {
+            "type": "object",
+            "required": ["jsonrpc", "method"],
+            "properties": {
+                "jsonrpc": { "const": "2.0" },
+                "id": { "type": ["string", "integer", "null"] },
+                "method": { "type": "string" },
+                "params": {}
+            }
+        },
+        "JsonRpcResponse": {
+            "type": "object",
+            "required": ["jsonrpc"],
+            "properties": {
+                "jsonrpc": { "const": "2.0" },
+                "id": { "type": ["string", "integer", "null"] },
+                "result": {},
+                "error": {
+                    "type": "object",
+                    "required": ["code", "message"],
+                    "properties": {
+                        "code": { "type": "integer" },
+                        "message": { "type": "string" },
+                        "data": {}
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn text_response(description: &str) -> Value {
+    serde_json::json!({
+        "description": description,
+        "content": { "text/plain": { "schema": { "type": "string" } } }
+    })
+}
+
+fn json_array_response(description: &str, component: &str) -> Value {
+    serde_json::json!({
+        "description": description,
+        "content": {
+            "application/json": {
+                "schema": {
+                    "type": "array",
+                    "items": { "$ref": format!("#/components/schemas/{}", component) }
+                }
+            }
+        }
+    })
+}
+
+/// 工具名称转换为合法的operationId后缀
+fn operation_id_suffix(name: &str) -> String {
+    name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect()
+}
+
+/// 对路径段进行百分号编码
+pub(crate) fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::server::test_support::get;
+    use crate::server::{create_app, FunctionTool};
+    use crate::settings::Settings;
+
+    fn server() -> RustMCP {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_server_info("pets-gateway", "2.1.0"));
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("add")
+                .title("Add numbers")
+                .description("Adds two numbers")
+                .input_schema(json!({ "type": "object", "properties": { "a": { "type": "number" }, "b": { "type": "number" } }, "required": ["a", "b"] }))
+                .output_schema(json!({ "type": "object", "properties": { "sum": { "type": "number" } } }))
+                .handler(|_| Ok(json!({ "sum": 0 })))
+                .build(),
+        );
+        rustmcp.add_tool(FunctionTool::simple("ping", "", |_| Ok(json!("pong"))));
+        rustmcp
+    }
+
+    /// 检查文档结构：OpenAPI 3.1的必需字段、唯一的operationId、每个操作都有响应，所有本地引用都能解析
+    fn validate(document: &Value) {
+        assert_eq!(document["openapi"], OPENAPI_VERSION);
+        assert!(document["info"]["title"].is_string() && document["info"]["version"].is_string());
+        let mut operation_ids = HashSet::new();
+        for (path, item) in document["paths"].as_object().unwrap() {
+            assert!(path.starts_with('/'), "{}", path);
+            for (method, operation) in item.as_object().unwrap() {
+                assert!(["get", "post", "put", "delete", "patch"].contains(&method.as_str()), "{} {}", method, path);
+                let id = operation["operationId"].as_str().unwrap_or_else(|| panic!("{} {} has no operationId", method, path));
+                assert!(operation_ids.insert(id.to_string()), "duplicate operationId {}", id);
+                let responses = operation["responses"].as_object().unwrap();
+                assert!(!responses.is_empty() && responses.values().all(|response| response["description"].is_string()), "{} {}", method, path);
+            }
+        }
+        check_refs(document, document);
+    }
+
+    fn check_refs(document: &Value, value: &Value) {
+        match value {
+            Value::Object(map) => {
+                if let Some(reference) = map.get("$ref").and_then(Value::as_str) {
+                    let pointer = reference.strip_prefix('#').unwrap_or_else(|| panic!("non-local $ref {}", reference));
+                    assert!(document.pointer(pointer).is_some(), "unresolved $ref {}", reference);
+                }
+                map.values().for_each(|value| check_refs(document, value));
+            }
+            Value::Array(items) => items.iter().for_each(|value| check_refs(document, value)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn document_matches_golden_tool_operations() {
+        let document = server().openapi_spec();
+        validate(&document);
+
+        let golden: Value = serde_json::from_str(include_str!("testdata/openapi_tools.json")).unwrap();
+        let paths = document["paths"].as_object().unwrap();
+        let tools: Map<String, Value> = paths.iter().filter(|(path, _)| path.starts_with("/mcp/tools/")).map(|(path, item)| (path.clone(), item.clone())).collect();
+        assert_eq!(json!({ "info": document["info"], "paths": tools }), golden);
+        for path in ["/health", "/mcp", "/mcp/tools", "/mcp/call-tool", "/mcp/resources", "/mcp/prompts"] {
+            assert!(paths.contains_key(path), "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn document_is_served_and_tracks_registered_tools() {
+        let rustmcp = server();
+        let app = create_app(rustmcp.clone());
+        let (status, _, document) = get(&app, "/mcp/openapi.json", &[]).await;
+        assert_eq!(status, 200);
+        assert_eq!(document, rustmcp.openapi_spec());
+
+        rustmcp.add_tool(FunctionTool::simple("search docs", "Searches", |_| Ok(json!([]))));
+        let (_, _, document) = get(&app, "/mcp/openapi.json", &[]).await;
+        validate(&document);
+        assert_eq!(document["paths"]["/mcp/tools/search%20docs/call"]["post"]["operationId"], "call_search_docs");
+    }
+}
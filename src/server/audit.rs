@@ -0,0 +1,402 @@
+//! 工具调用审计日志
+//!
+//! 通过[`RustMCP::with_audit_sink`](crate::RustMCP::with_audit_sink)设置[`AuditSink`]后，
+//! 每次工具调用（包括被钩子否决、只读模式拒绝和工具不存在的调用）都会生成一条[`AuditEntry`]：
+//! 时间、会话、调用方身份、工具名、参数摘要、耗时和结果。
+//!
+//! - 参数只记录摘要：名称像密码、令牌的字段先替换为`[REDACTED]`，再对键排序后的JSON计算SHA-256；
+//! - 记录不会阻塞调用：条目放入有界队列（[`Settings::audit_queue_depth`](crate::Settings::audit_queue_depth)），
+//!   由后台任务交给接收端，队列满时丢弃并计数（[`RustMCP::dropped_audit_entries`](crate::RustMCP::dropped_audit_entries)）；
+//! - 后台任务需要tokio运行时，在设置接收端或第一次调用时启动；
+//!   关闭前调用[`RustMCP::flush_audit`](crate::RustMCP::flush_audit)等待队列中的条目写完。
+//!
+//! 提供两种接收端：按大小轮换的JSONL文件[`JsonlAuditSink`]，以及保存在内存中的[`MemoryAuditSink`]。
+//!
+//! ```rust,no_run
+//! use rustmcp::{JsonlAuditSink, RustMCP};
+//!
+//! # fn main() -> std::io::Result<()> {
+//! let sink = JsonlAuditSink::open("/var/log/rustmcp/audit.jsonl", 10 * 1024 * 1024)?.keep(3);
+//! let rustmcp = RustMCP::new().with_audit_sink(sink);
+//! # Ok(())
+//! # }
+//! ```
+
+use futures::future::BoxFuture;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::server::capture::Redacted;
+use crate::server::error::McpError;
+use crate::server::Context;
+
+/// 默认保留的轮换文件数
+const DEFAULT_KEEP_FILES: usize = 5;
+
+/// 工具调用的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    /// 调用成功
+    Ok,
+    /// 调用失败，类型见[`AuditEntry::error_kind`]
+    Error,
+}
+
+/// 一次工具调用的审计记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// 调用开始的时间（Unix毫秒）
+    pub timestamp_ms: u64,
+    /// 会话ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// 已认证的调用方身份
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    /// 工具名称
+    pub tool: String,
+    /// 脱敏后参数的SHA-256摘要，格式为`sha256:<hex>`
+    pub arguments_digest: String,
+    /// 耗时（毫秒）
+    pub duration_ms: u64,
+    /// 结果
+    pub outcome: AuditOutcome,
+    /// 失败时的错误类型，与JSON-RPC错误的`data.kind`相同
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<String>,
+}
+
+impl AuditEntry {
+    /// 根据调用和结果创建记录
+    pub(crate) fn new(
+        tool: &str,
+        ctx: &Context,
+        arguments_digest: String,
+        started: SystemTime,
+        duration: Duration,
+        result: &Result<Value, McpError>,
+    ) -> Self {
+        let request = ctx.request();
+        Self {
+            timestamp_ms: started.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0),
+            session_id: request.session_id.clone(),
+            identity: request.identity.as_ref().map(|identity| identity.subject.clone()),
+            tool: tool.to_string(),
+            arguments_digest,
+            duration_ms: duration.as_millis() as u64,
+            outcome: if result.is_ok() { AuditOutcome::Ok } else { AuditOutcome::Error },
+            error_kind: result.as_ref().err().map(|e| e.kind().to_string()),
+        }
+    }
+}
+
+/// 审计记录的接收端
+///
+/// 记录由后台任务按顺序交给接收端，接收端自行处理写入失败（例如记录日志）。
+pub trait AuditSink: Send + Sync {
+    /// 保存一条记录
+    fn record(&self, entry: AuditEntry) -> BoxFuture<'_, ()>;
+}
+
+/// 保存在内存中的最近记录，克隆的实例共享同一个缓冲区
+#[derive(Debug, Clone)]
+pub struct MemoryAuditSink {
+    capacity: usize,
+    entries: Arc<Mutex<VecDeque<AuditEntry>>>,
+}
+
+impl MemoryAuditSink {
+    /// 创建最多保存`capacity`条记录的接收端，超出时丢弃最早的记录
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: Arc::default(),
+        }
+    }
+
+    /// 按时间顺序返回保存的记录
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.lock().iter().cloned().collect()
+    }
+}
+
+impl AuditSink for MemoryAuditSink {
+    fn record(&self, entry: AuditEntry) -> BoxFuture<'_, ()> {
+        let mut entries = self.entries.lock();
+        while entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+        Box::pin(async {})
+    }
+}
+
+/// 每行一条JSON记录的文件，超过大小时轮换
+///
+/// 轮换时`audit.jsonl`重命名为`audit.jsonl.1`，已有的`.1`重命名为`.2`，依此类推，
+/// 超出保留数量的最旧文件被删除。
+#[derive(Debug, Clone)]
+pub struct JsonlAuditSink {
+    file: Arc<Mutex<JsonlFile>>,
+}
+
+#[derive(Debug)]
+struct JsonlFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl JsonlAuditSink {
+    /// 以追加方式打开文件，文件超过`max_bytes`字节时轮换
+    pub fn open(path: impl Into<PathBuf>, max_bytes: u64) -> std::io::Result<Self> {
+        let path = path.into();
+        let file = open_append(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            file: Arc::new(Mutex::new(JsonlFile {
+                path,
+                file,
+                size,
+                max_bytes,
+                keep: DEFAULT_KEEP_FILES,
+            })),
+        })
+    }
+
+    /// 设置保留的轮换文件数（默认5），为0时轮换直接删除旧文件
+    pub fn keep(self, files: usize) -> Self {
+        self.file.lock().keep = files;
+        self
+    }
+}
+
+impl AuditSink for JsonlAuditSink {
+    fn record(&self, entry: AuditEntry) -> BoxFuture<'_, ()> {
+        let file = self.file.clone();
+        Box::pin(async move {
+            let mut line = match serde_json::to_string(&entry) {
+                Ok(line) => line,
+                Err(e) => return log::warn!("Failed to serialize audit entry: {}", e),
+            };
+            line.push('\n');
+            let written = tokio::task::spawn_blocking(move || file.lock().append(line.as_bytes())).await;
+            match written {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => log::warn!("Failed to write audit entry: {}", e),
+                Err(e) => log::warn!("Audit writer failed: {}", e),
+            }
+        })
+    }
+}
+
+impl JsonlFile {
+    /// 追加一行，写入后会超过上限时先轮换
+    fn append(&mut self, line: &[u8]) -> std::io::Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let rotated = |n: usize| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{}", n));
+            PathBuf::from(name)
+        };
+        if self.keep == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(rotated(self.keep));
+            for n in (1..self.keep).rev() {
+                let from = rotated(n);
+                if from.exists() {
+                    std::fs::rename(&from, rotated(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, rotated(1))?;
+        }
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// 计算脱敏后参数的摘要
+pub(crate) fn digest(arguments: Option<&HashMap<String, Value>>) -> String {
+    // 按键排序后直接写入哈希，不复制参数；写入哈希不会失败
+    let mut hasher = Sha256::new();
+    let _ = match arguments {
+        Some(arguments) => {
+            let sorted: BTreeMap<&str, Redacted> =
+                arguments.iter().map(|(key, value)| (key.as_str(), Redacted::entry(key, value))).collect();
+            serde_json::to_writer(&mut hasher, &sorted)
+        }
+        None => serde_json::to_writer(&mut hasher, &Value::Null),
+    };
+    let hash = hasher.finalize();
+    let hex: String = hash.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("sha256:{}", hex)
+}
+
+/// 发给后台任务的消息
+enum Message {
+    Entry(AuditEntry),
+    Flush(oneshot::Sender<()>),
+}
+
+/// 等待后台任务启动的队列接收端和审计接收端
+type Pending = (mpsc::Receiver<Message>, Arc<dyn AuditSink>);
+
+/// 审计队列和后台任务
+pub(crate) struct AuditLog {
+    sender: mpsc::Sender<Message>,
+    pending: Mutex<Option<Pending>>,
+    dropped: AtomicU64,
+}
+
+impl AuditLog {
+    /// 创建队列，有tokio运行时时立即启动后台任务
+    pub fn new(sink: Arc<dyn AuditSink>, depth: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(depth.max(1));
+        let log = Self {
+            sender,
+            pending: Mutex::new(Some((receiver, sink))),
+            dropped: AtomicU64::new(0),
+        };
+        log.start();
+        log
+    }
+
+    /// 还没有启动时启动后台任务，返回任务是否在运行
+    fn start(&self) -> bool {
+        let mut pending = self.pending.lock();
+        if pending.is_none() {
+            return true;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return false;
+        };
+        if let Some((mut receiver, sink)) = pending.take() {
+            runtime.spawn(async move {
+                while let Some(message) = receiver.recv().await {
+                    match message {
+                        Message::Entry(entry) => sink.record(entry).await,
+                        Message::Flush(done) => {
+                            let _ = done.send(());
+                        }
+                    }
+                }
+            });
+        }
+        true
+    }
+
+    /// 放入队列，队列满时丢弃
+    pub fn record(&self, entry: AuditEntry) {
+        self.start();
+        if self.sender.try_send(Message::Entry(entry)).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed);
+            if dropped.is_multiple_of(1000) {
+                log::warn!("Audit queue is full; {} entries dropped so far", dropped + 1);
+            }
+        }
+    }
+
+    /// 等待之前放入队列的记录都交给接收端，后台任务没有运行时立即返回
+    pub async fn flush(&self) {
+        if !self.start() {
+            return;
+        }
+        let (done, wait) = oneshot::channel();
+        if self.sender.send(Message::Flush(done)).await.is_ok() {
+            let _ = wait.await;
+        }
+    }
+
+    /// 因队列满而丢弃的记录数
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog").field("dropped", &self.dropped()).finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AuditOutcome, Context, FunctionTool, Identity, McpError, MemoryAuditSink, RequestContext, RustMCP};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn calls_are_audited_with_identity_outcome_and_digest() {
+        let audit = MemoryAuditSink::new(100);
+        let mut rustmcp = RustMCP::new().with_audit_sink(audit.clone());
+        rustmcp.add_tool(FunctionTool::simple("divide", "Divides a by b", |args| {
+            let args = args.unwrap_or_default();
+            let a = args["a"].as_f64().unwrap_or_default();
+            let b = args["b"].as_f64().unwrap_or_default();
+            if b == 0.0 { Err("division by zero".to_string()) } else { Ok(json!(a / b)) }
+        }));
+        // 没有身份的调用方被拒绝
+        rustmcp.on_before_tool_call(|_, _, ctx| match ctx.request().identity {
+            Some(_) => Ok(()),
+            None => Err(McpError::Unauthorized("sign in first".to_string())),
+        });
+
+        let alice = Context::new().with_request(Arc::new(RequestContext {
+            identity: Some(Identity::new("alice")),
+            session_id: Some("session-1".to_string()),
+            ..RequestContext::default()
+        }));
+        let args = |b: f64| Some(HashMap::from([("a".to_string(), json!(1.0)), ("b".to_string(), json!(b)), ("password".to_string(), json!("hunter2"))]));
+        assert!(rustmcp.mcp_call_tool_with_context("divide", args(2.0), &alice).await.is_ok());
+        assert!(rustmcp.mcp_call_tool_with_context("divide", args(0.0), &alice).await.is_err());
+        assert_eq!(rustmcp.mcp_call_tool("divide", args(2.0)).await.unwrap_err().code(), -32010);
+        rustmcp.flush_audit().await;
+
+        let entries = audit.entries();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].identity.as_deref(), Some("alice"));
+        assert_eq!(entries[0].session_id.as_deref(), Some("session-1"));
+        assert_eq!(entries[0].outcome, AuditOutcome::Ok);
+        assert_eq!(entries[1].outcome, AuditOutcome::Error);
+        assert_eq!(entries[1].error_kind.as_deref(), Some("tool_execution_failed"));
+        assert_eq!(entries[2].identity, None);
+        assert_eq!(entries[2].error_kind.as_deref(), Some("unauthorized"));
+
+        // 相同的参数得到相同的摘要，摘要中不包含明文
+        assert_eq!(entries[0].arguments_digest, entries[2].arguments_digest);
+        assert_ne!(entries[0].arguments_digest, entries[1].arguments_digest);
+        let line = serde_json::to_string(&entries[0]).unwrap();
+        assert!(line.contains(r#""argumentsDigest":"sha256:"#));
+        assert!(!line.contains("hunter2"));
+        assert_eq!(rustmcp.dropped_audit_entries(), 0);
+    }
+}
@@ -0,0 +1,199 @@
+//! 批量调用工具：`x-rustmcp/tools/call_batch`
+//!
+//! 用不同的参数多次调用同一个工具时（例如每个文档调用一次），逐个发送`tools/call`需要很多次往返。
+//! 这个扩展方法在一次请求中完成所有调用：
+//!
+//! ```json
+//! {"jsonrpc": "2.0", "id": 1, "method": "x-rustmcp/tools/call_batch",
+//!  "params": {"name": "summarize", "calls": [{"id": "a", "arguments": {"doc": 1}}, {"id": "b", "arguments": {"doc": 2}}]}}
+//! ```
+//!
+//! - 结果为`{"results": [{"id", "content", "isError"}]}`，顺序和`id`与请求中的`calls`一致，`id`可以是任意JSON值；
+//! - 同时执行的调用数不超过[`Settings::batch_concurrency`](crate::Settings::batch_concurrency)（默认8），
+//!   工具设置的[`max_concurrency`](crate::FunctionTool::with_max_concurrency)同样生效；
+//! - 每次调用都经过钩子、只读模式、参数校验和审计，与单独的`tools/call`相同；
+//! - 单次调用失败只让对应的结果为`isError: true`，不影响其他调用；工具不存在时整个请求失败；
+//! - 方法策略拒绝`tools/call`时这个方法也不可用。
+//!
+//! ```rust
+//! use rustmcp::{RustMCP, Settings};
+//!
+//! let rustmcp = RustMCP::new().with_settings(Settings::new().with_batch_concurrency(3));
+//! ```
+
+use futures::StreamExt;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::server::content::Content;
+use crate::server::dispatch::tool_call_result;
+use crate::server::error::{suggest, McpError};
+use crate::server::watchdog;
+use crate::server::{Context, RustMCP};
+
+/// 批量调用工具的方法名
+pub const METHOD: &str = "x-rustmcp/tools/call_batch";
+
+/// 批量调用中的一项
+struct Call {
+    id: Value,
+    arguments: Option<HashMap<String, Value>>,
+}
+
+/// 执行`x-rustmcp/tools/call_batch`
+pub(crate) async fn call_batch(rustmcp: RustMCP, params: Option<Value>, ctx: Context) -> Result<Value, McpError> {
+    if !rustmcp.is_method_allowed("tools/call") {
+        return Err(McpError::MethodNotFound(METHOD.to_string()));
+    }
+    let (name, calls) = parse_params(params)?;
+    if rustmcp.tool_manager.get_tool(&name).is_none() {
+        let tools = rustmcp.tool_manager.list_tools();
+        return Err(McpError::ToolNotFound {
+            suggestions: suggest(&name, tools.iter().map(|tool| &tool.name)),
+            name,
+        });
+    }
+
+    let limit = rustmcp.settings().batch_concurrency.max(1);
+    let results: Vec<Value> = futures::stream::iter(calls)
+        .map(|call| run_call(&rustmcp, &name, &ctx, call))
+        .buffered(limit)
+        .collect()
+        .await;
+    Ok(serde_json::json!({ "results": results }))
+}
+
+/// 执行一次调用，返回带调用方`id`的结果
+///
+/// 工具函数是同步的，放到阻塞线程池执行，同一批中的调用才能真正并行。
+async fn run_call(rustmcp: &RustMCP, name: &str, ctx: &Context, call: Call) -> Value {
+    let ctx = ctx.for_call();
+    let work = {
+        let (rustmcp, name, ctx) = (rustmcp.clone(), name.to_string(), ctx.clone());
+        async move { rustmcp.mcp_call_tool_with_context(&name, call.arguments, &ctx).await }
+    };
+    let handle = tokio::runtime::Handle::current();
+    let result = match tokio::task::spawn_blocking(move || watchdog::offloaded(|| handle.block_on(work))).await {
+        Ok(result) => result,
+        Err(e) => Err(McpError::Internal(format!("Tool '{}' failed: {}", name, e))),
+    };
+    let mut entry = tool_call_result(rustmcp, name, result, &ctx).unwrap_or_else(|e| {
+        serde_json::json!({
+            "content": [Content::text(e.to_string())],
+            "isError": true
+        })
+    });
+    if let Value::Object(map) = &mut entry {
+        map.insert("id".to_string(), call.id);
+    }
+    entry
+}
+
+/// 解析`{name, calls: [{id, arguments}]}`
+fn parse_params(params: Option<Value>) -> Result<(String, Vec<Call>), McpError> {
+    let mut params = match params {
+        Some(Value::Object(params)) => params,
+        _ => return Err(McpError::invalid_params("params must be an object", "params", "object")),
+    };
+    let name = match params.remove("name") {
+        Some(Value::String(name)) if !name.is_empty() => name,
+        _ => return Err(McpError::invalid_params("'name' must be a non-empty string", "name", "string")),
+    };
+    let calls = match params.remove("calls") {
+        Some(Value::Array(calls)) => calls,
+        _ => return Err(McpError::invalid_params("'calls' must be an array", "calls", "array")),
+    };
+    let calls = calls
+        .into_iter()
+        .enumerate()
+        .map(|(index, call)| {
+            let param = format!("calls[{}]", index);
+            let mut call = match call {
+                Value::Object(call) => call,
+                _ => return Err(McpError::invalid_params(format!("'{}' must be an object", param), param, "object")),
+            };
+            let arguments = match call.remove("arguments") {
+                None | Some(Value::Null) => None,
+                Some(Value::Object(arguments)) => Some(arguments.into_iter().collect()),
+                Some(_) => {
+                    let param = format!("{}.arguments", param);
+                    return Err(McpError::invalid_params(format!("'{}' must be an object", param), param, "object"));
+                }
+            };
+            Ok(Call {
+                id: call.remove("id").unwrap_or(Value::Null),
+                arguments,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((name, calls))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use crate::server::test_support::{request, stdio};
+    use crate::{FunctionTool, RustMCP, Settings};
+
+    static RUNNING: AtomicUsize = AtomicUsize::new(0);
+    static HIGH_WATER: AtomicUsize = AtomicUsize::new(0);
+
+    fn work(args: Option<std::collections::HashMap<String, Value>>) -> Result<Value, String> {
+        let n = args.and_then(|args| args.get("n").and_then(Value::as_u64)).unwrap_or(0);
+        let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+        HIGH_WATER.fetch_max(running, Ordering::SeqCst);
+        // 排在前面的调用更慢，结果仍按请求顺序返回
+        std::thread::sleep(Duration::from_millis(60 - 5 * n));
+        RUNNING.fetch_sub(1, Ordering::SeqCst);
+        if n % 4 == 3 {
+            return Err(format!("document {} is corrupt", n));
+        }
+        Ok(json!(n * 10))
+    }
+
+    async fn call_batch(rustmcp: &RustMCP, name: &str) -> Value {
+        let calls: Vec<Value> = (0..12).map(|n| json!({ "id": format!("doc-{}", n), "arguments": { "n": n } })).collect();
+        let batch = request(2, "x-rustmcp/tools/call_batch", json!({ "name": name, "calls": calls }));
+        stdio(rustmcp, &[request(1, "initialize", json!({})), batch]).await.remove(1)
+    }
+
+    #[tokio::test]
+    async fn batch_results_keep_request_order_within_concurrency_limits() {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_batch_concurrency(3));
+        rustmcp.add_tool(FunctionTool::simple("work", "Processes a document", work));
+        rustmcp.add_tool(FunctionTool::simple("serial", "Processes a document", work).with_max_concurrency(1));
+
+        let response = call_batch(&rustmcp, "work").await;
+        let results = response["result"]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 12);
+        for (n, result) in results.iter().enumerate() {
+            assert_eq!(result["id"], format!("doc-{}", n));
+            if n % 4 == 3 {
+                assert_eq!(result["isError"], true);
+                assert_eq!(result["content"][0]["text"], format!("document {} is corrupt", n));
+            } else {
+                assert_eq!(result["isError"], false);
+                assert_eq!(result["content"][0]["text"], (n * 10).to_string());
+            }
+        }
+        assert!(HIGH_WATER.load(Ordering::SeqCst) <= 3);
+
+        // 工具自己的并发上限更小
+        HIGH_WATER.store(0, Ordering::SeqCst);
+        let response = call_batch(&rustmcp, "serial").await;
+        assert_eq!(response["result"]["results"][11]["id"], "doc-11");
+        assert_eq!(HIGH_WATER.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn unknown_tool_fails_the_whole_batch() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::simple("work", "Processes a document", |_| Ok(json!("done"))));
+        let response = call_batch(&rustmcp, "wrok").await;
+        assert_eq!(response["error"]["code"], -32602);
+        assert_eq!(response["error"]["data"]["suggestions"], json!(["work"]));
+    }
+}
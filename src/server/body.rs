@@ -0,0 +1,194 @@
+//! JSON-RPC请求体的提取
+//!
+//! `POST /mcp`和`POST /mcp/call-tool`的请求体由同一个提取器读取：
+//! - `Content-Type`可以是`application/json`、`application/json-rpc`或者不带，`charset`只能是`utf-8`；
+//!   其他类型返回`415`和`-32600`错误，错误信息列出接受的类型；
+//! - 请求体开头的UTF-8 BOM被去掉，不是有效UTF-8的请求体返回`400`和`-32700`错误；
+//! - 请求体超过[`Settings::with_max_body_bytes`](crate::Settings::with_max_body_bytes)（默认2 MiB）时
+//!   返回`413`和`-32600`错误（`Request too large`），不会先把整个请求体读进内存。
+//!
+//! 被拒绝的请求还没有读出`id`，错误响应的`id`为`null`。
+//!
+//! ```rust
+//! use rustmcp::{RustMCP, Settings};
+//!
+//! // 接受最大16 MiB的请求体，例如带有大段文本参数的工具调用
+//! let rustmcp = RustMCP::new().with_settings(Settings::new().with_max_body_bytes(16 * 1024 * 1024));
+//! ```
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, Request},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+
+use crate::protocol::{JsonRpcResponse, RequestId, INVALID_REQUEST, PARSE_ERROR};
+use crate::server::RustMCP;
+
+/// 默认的请求体上限，与axum的默认值相同
+pub const DEFAULT_MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// 接受的`Content-Type`，用于错误信息
+const ACCEPTED_TYPES: &str = "application/json, application/json-rpc or no content type";
+
+/// UTF-8 BOM
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// 读取并解码为文本的JSON-RPC请求体
+pub(crate) struct JsonRpcBody(pub(crate) String);
+
+/// 被拒绝的请求体，响应为`id`为`null`的JSON-RPC错误
+pub(crate) struct BodyRejection {
+    status: StatusCode,
+    code: i32,
+    message: String,
+}
+
+impl IntoResponse for BodyRejection {
+    fn into_response(self) -> Response {
+        let response = JsonRpcResponse::error(RequestId::Null, self.code, self.message);
+        (self.status, Json(response)).into_response()
+    }
+}
+
+#[async_trait]
+impl FromRequest<Arc<RustMCP>> for JsonRpcBody {
+    type Rejection = BodyRejection;
+
+    async fn from_request(request: Request, rustmcp: &Arc<RustMCP>) -> Result<Self, Self::Rejection> {
+        if let Some(value) = request.headers().get(header::CONTENT_TYPE) {
+            let value = value.to_str().unwrap_or_default();
+            if !is_accepted(value) {
+                return Err(BodyRejection {
+                    status: StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    code: INVALID_REQUEST,
+                    message: format!("Unsupported content type '{}', expected {}", value, ACCEPTED_TYPES),
+                });
+            }
+        }
+        let limit = rustmcp.settings.max_body_bytes;
+        let too_large = || BodyRejection {
+            status: StatusCode::PAYLOAD_TOO_LARGE,
+            code: INVALID_REQUEST,
+            message: format!("Request too large (limit {} bytes)", limit),
+        };
+        let declared = request
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<usize>().ok());
+        if declared.is_some_and(|length| length > limit) {
+            return Err(too_large());
+        }
+        let bytes = axum::body::to_bytes(request.into_body(), limit).await.map_err(|_| too_large())?;
+        let bytes = bytes.strip_prefix(BOM).unwrap_or(&bytes);
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Ok(Self(text.to_string())),
+            Err(e) => Err(BodyRejection {
+                status: StatusCode::BAD_REQUEST,
+                code: PARSE_ERROR,
+                message: format!("Parse error: request body is not valid UTF-8: {}", e),
+            }),
+        }
+    }
+}
+
+/// `Content-Type`是否为接受的JSON类型，带`charset`时只能是`utf-8`
+fn is_accepted(value: &str) -> bool {
+    let mut parts = value.split(';').map(str::trim);
+    let media_type = parts.next().unwrap_or_default();
+    if !media_type.eq_ignore_ascii_case("application/json") && !media_type.eq_ignore_ascii_case("application/json-rpc") {
+        return false;
+    }
+    parts.all(|parameter| match parameter.split_once('=') {
+        Some((name, charset)) if name.trim().eq_ignore_ascii_case("charset") => {
+            charset.trim().trim_matches('"').eq_ignore_ascii_case("utf-8")
+        }
+        _ => true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::Router;
+    use serde_json::{json, Value};
+
+    use crate::server::test_support::send;
+    use crate::{create_app, FunctionTool, RustMCP, Settings};
+
+    async fn post(app: &Router, path: &str, content_type: Option<&str>, body: Vec<u8>) -> (StatusCode, Value) {
+        let mut request = Request::post(path);
+        if let Some(content_type) = content_type {
+            request = request.header("content-type", content_type);
+        }
+        let (status, _, body) = send(app, request.body(Body::from(body)).unwrap()).await;
+        (status, body)
+    }
+
+    fn app() -> Router {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_max_body_bytes(1024));
+        rustmcp.add_tool(FunctionTool::simple("add", "Adds two numbers", |args| {
+            let args = args.unwrap_or_default();
+            Ok(json!(args["a"].as_i64().unwrap_or(0) + args["b"].as_i64().unwrap_or(0)))
+        }));
+        create_app(rustmcp)
+    }
+
+    fn list() -> Vec<u8> {
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }).to_string().into_bytes()
+    }
+
+    #[tokio::test]
+    async fn json_types_and_bom_are_accepted() {
+        let app = app();
+        // 接受的类型，以及不带Content-Type的请求
+        for content_type in [Some("application/json"), Some("application/json-rpc"), Some("application/json; charset=UTF-8"), None] {
+            let (status, response) = post(&app, "/mcp", content_type, list()).await;
+            assert_eq!(status, StatusCode::OK, "{:?}", content_type);
+            assert_eq!(response["id"], 1);
+        }
+
+        // 带BOM的请求体
+        let with_bom = [b"\xEF\xBB\xBF".as_slice(), &list()].concat();
+        let (status, response) = post(&app, "/mcp", Some("application/json"), with_bom).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(response["result"]["tools"][0]["name"], "add");
+    }
+
+    #[tokio::test]
+    async fn other_types_and_oversized_bodies_are_rejected() {
+        let app = app();
+        // 其他类型被拒绝，错误信息列出接受的类型
+        let (status, response) = post(&app, "/mcp", Some("text/plain"), list()).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(response["error"]["code"], -32600);
+        assert_eq!(response["id"], Value::Null);
+        assert!(response["error"]["message"].as_str().unwrap().contains("application/json-rpc"));
+        let (status, _) = post(&app, "/mcp", Some("application/json; charset=latin1"), list()).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        // 超过上限的请求体
+        let padding = "x".repeat(2048);
+        let oversized = json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list", "params": { "padding": padding } });
+        let (status, response) = post(&app, "/mcp", Some("application/json"), oversized.to_string().into_bytes()).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(response["error"]["code"], -32600);
+        assert!(response["error"]["message"].as_str().unwrap().starts_with("Request too large"));
+    }
+
+    #[tokio::test]
+    async fn call_tool_uses_the_same_extractor() {
+        let app = app();
+        let call = json!({ "name": "add", "arguments": { "a": 2, "b": 3 } }).to_string().into_bytes();
+        let (status, response) = post(&app, "/mcp/call-tool", Some("application/json-rpc"), call.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(response, json!(5));
+        let (status, _) = post(&app, "/mcp/call-tool", Some("text/plain"), call).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+}
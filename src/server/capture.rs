@@ -0,0 +1,321 @@
+//! 按需的请求/响应内容采集
+//!
+//! 排查某个客户端的问题时，可以在运行时为指定的JSON-RPC方法和/或工具名开启采集，
+//! 按采样率记录请求参数和响应，保存在有界的环形缓冲区中，通过
+//! [`RustMCP::captures`](crate::RustMCP::captures)或管理接口的`GET /diagnostics/captures`读取。
+//!
+//! - 参数中名称像密码、令牌、密钥的字段会被替换为`[REDACTED]`，声明式工具读取过的
+//!   [密钥](crate::server::secrets)的值无论出现在哪里也会被替换；
+//! - 请求和响应分别截断到`max_bytes`字节；
+//! - 采集内容只保存在内存中，不会写到标准输出或日志。
+//!
+//! ```rust
+//! use rustmcp::{CaptureConfig, RustMCP};
+//!
+//! let rustmcp = RustMCP::new();
+//! rustmcp.enable_capture(CaptureConfig::new().method("tools/call").max_bytes(80));
+//! // ……处理请求之后
+//! for capture in rustmcp.captures() {
+//!     println!("{} {:?} {}", capture.method, capture.tool, capture.request);
+//! }
+//! ```
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::server::secrets;
+
+/// 名称中包含这些片段（不区分大小写）的参数会被脱敏
+const SENSITIVE_KEYS: &[&str] = &[
+    "password", "passwd", "secret", "token", "api_key", "apikey", "authorization", "cookie", "credential", "private_key",
+];
+
+/// 脱敏后的占位值
+const REDACTED: &str = "[REDACTED]";
+
+/// 采集设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CaptureConfig {
+    /// 只采集这个JSON-RPC方法，为`None`时不限方法
+    #[serde(default)]
+    pub method: Option<String>,
+    /// 只采集调用这个工具的`tools/call`请求，为`None`时不限工具
+    #[serde(default)]
+    pub tool: Option<String>,
+    /// 采样率：每N个匹配的请求采集1个
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: u64,
+    /// 请求和响应各自保留的最大字节数
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: usize,
+    /// 环形缓冲区保存的采集条数
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+}
+
+fn default_sample_rate() -> u64 {
+    1
+}
+
+fn default_max_bytes() -> usize {
+    4096
+}
+
+fn default_capacity() -> usize {
+    100
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CaptureConfig {
+    /// 采集所有请求，不采样
+    pub fn new() -> Self {
+        Self {
+            method: None,
+            tool: None,
+            sample_rate: default_sample_rate(),
+            max_bytes: default_max_bytes(),
+            capacity: default_capacity(),
+        }
+    }
+
+    /// 只采集指定方法
+    pub fn method(mut self, method: impl Into<String>) -> Self {
+        self.method = Some(method.into());
+        self
+    }
+
+    /// 只采集调用指定工具的请求
+    pub fn tool(mut self, tool: impl Into<String>) -> Self {
+        self.tool = Some(tool.into());
+        self
+    }
+
+    /// 每`rate`个匹配的请求采集1个，0按1处理
+    pub fn sample_rate(mut self, rate: u64) -> Self {
+        self.sample_rate = rate;
+        self
+    }
+
+    /// 设置请求和响应各自保留的最大字节数
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// 设置缓冲区保存的采集条数
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    fn matches(&self, method: &str, tool: Option<&str>) -> bool {
+        self.method.as_deref().is_none_or(|m| m == method) && self.tool.as_deref().is_none_or(|t| Some(t) == tool)
+    }
+}
+
+/// 一次采集的请求和响应
+#[derive(Debug, Clone, Serialize)]
+pub struct Capture {
+    /// 采集时间（Unix毫秒）
+    pub timestamp_ms: u64,
+    /// JSON-RPC方法
+    pub method: String,
+    /// `tools/call`调用的工具
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// 会话ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// 脱敏后的请求参数（JSON文本，可能被截断）
+    pub request: String,
+    /// 响应（JSON文本，可能被截断）
+    pub response: String,
+    /// 请求或响应是否被截断
+    pub truncated: bool,
+    /// 处理耗时（毫秒）
+    pub duration_ms: u64,
+}
+
+/// 采集开关和缓冲区，克隆的[`RustMCP`](crate::RustMCP)共享同一份
+#[derive(Debug, Default)]
+pub(crate) struct CaptureBuffer {
+    /// 快速判断是否开启，关闭时请求路径上只有一次原子读取
+    enabled: AtomicBool,
+    config: RwLock<Option<CaptureConfig>>,
+    /// 匹配的请求计数，用于采样
+    seen: AtomicU64,
+    captures: Mutex<VecDeque<Capture>>,
+}
+
+impl CaptureBuffer {
+    /// 开启采集并替换设置，清空之前的采集
+    pub fn enable(&self, config: CaptureConfig) {
+        *self.config.write() = Some(config);
+        self.seen.store(0, Ordering::Relaxed);
+        self.captures.lock().clear();
+        self.enabled.store(true, Ordering::Release);
+    }
+
+    /// 关闭采集，已有的采集保留
+    pub fn disable(&self) {
+        self.enabled.store(false, Ordering::Release);
+        *self.config.write() = None;
+    }
+
+    /// 当前设置，关闭时为`None`
+    pub fn config(&self) -> Option<CaptureConfig> {
+        self.config.read().clone()
+    }
+
+    /// 按时间顺序返回缓冲区中的采集
+    pub fn captures(&self) -> Vec<Capture> {
+        self.captures.lock().iter().cloned().collect()
+    }
+
+    /// 请求是否需要采集，匹配的请求计入采样
+    pub fn should_capture(&self, method: &str, tool: Option<&str>) -> bool {
+        if !self.enabled.load(Ordering::Acquire) {
+            return false;
+        }
+        let config = self.config.read();
+        let Some(config) = config.as_ref().filter(|config| config.matches(method, tool)) else {
+            return false;
+        };
+        self.seen.fetch_add(1, Ordering::Relaxed).is_multiple_of(config.sample_rate.max(1))
+    }
+
+    /// 脱敏并截断请求参数
+    ///
+    /// 在处理请求之前调用，只保留截断后的文本，处理期间不再持有参数的副本。
+    pub fn request_text(&self, params: Option<&Value>) -> (String, bool) {
+        let Some(config) = self.config() else {
+            return (String::new(), false);
+        };
+        let params = params.map_or(Redacted::Value(&Value::Null), Redacted::Value);
+        truncate(secrets::redact(&serde_json::to_string(&params).unwrap_or_default()), config.max_bytes)
+    }
+
+    /// 记录一次采集，`request`为[`request_text`](Self::request_text)的结果，响应会先脱敏再截断
+    pub fn record(
+        &self,
+        method: &str,
+        tool: Option<&str>,
+        session_id: Option<&str>,
+        request: (String, bool),
+        response: &impl Serialize,
+        duration: std::time::Duration,
+    ) {
+        let Some(config) = self.config() else {
+            return;
+        };
+        let (request, request_truncated) = request;
+        let response = secrets::redact(&serde_json::to_string(response).unwrap_or_default());
+        let (response, response_truncated) = truncate(response, config.max_bytes);
+        let capture = Capture {
+            timestamp_ms: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0),
+            method: method.to_string(),
+            tool: tool.map(str::to_string),
+            session_id: session_id.map(str::to_string),
+            request,
+            response,
+            truncated: request_truncated || response_truncated,
+            duration_ms: duration.as_millis() as u64,
+        };
+        let mut captures = self.captures.lock();
+        while captures.len() >= config.capacity.max(1) {
+            captures.pop_front();
+        }
+        captures.push_back(capture);
+    }
+}
+
+/// 序列化时把名称像敏感信息的字段替换为占位值，不复制原来的值
+pub(crate) enum Redacted<'a> {
+    /// 原样序列化，其中的对象逐个字段检查
+    Value(&'a Value),
+    /// 占位值
+    Hidden,
+}
+
+impl<'a> Redacted<'a> {
+    /// 对象中的一个字段
+    pub(crate) fn entry(key: &str, value: &'a Value) -> Self {
+        let lower = key.to_ascii_lowercase();
+        if SENSITIVE_KEYS.iter().any(|sensitive| lower.contains(sensitive)) {
+            Redacted::Hidden
+        } else {
+            Redacted::Value(value)
+        }
+    }
+}
+
+impl Serialize for Redacted<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Redacted::Hidden => serializer.serialize_str(REDACTED),
+            Redacted::Value(Value::Object(map)) => {
+                serializer.collect_map(map.iter().map(|(key, value)| (key, Redacted::entry(key, value))))
+            }
+            Redacted::Value(Value::Array(items)) => serializer.collect_seq(items.iter().map(Redacted::Value)),
+            Redacted::Value(value) => value.serialize(serializer),
+        }
+    }
+}
+
+/// 在字符边界截断到最多`max_bytes`字节
+fn truncate(mut text: String, max_bytes: usize) -> (String, bool) {
+    if text.len() <= max_bytes {
+        return (text, false);
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    (text, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CaptureConfig, FunctionTool, RustMCP};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn tool_calls_are_captured_redacted_and_truncated() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::simple("echo", "Echoes its arguments", |args| {
+            Ok(serde_json::json!(args))
+        }));
+        rustmcp.enable_capture(CaptureConfig::new().method("tools/call").max_bytes(80));
+
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"echo","arguments":{"api_key":"hunter2","text":"a very long message that will not fit in eighty bytes"}}}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"resources/list"}"#,
+        ]
+        .join("\n");
+        crate::server::stdio::serve_lines(Arc::new(rustmcp.clone()), input.as_bytes(), tokio::io::sink())
+            .await
+            .unwrap();
+
+        let captures = rustmcp.captures();
+        assert_eq!(captures.len(), 1);
+        assert_eq!(captures[0].method, "tools/call");
+        assert_eq!(captures[0].tool.as_deref(), Some("echo"));
+        assert!(captures[0].request.contains("[REDACTED]"));
+        assert!(!captures[0].request.contains("hunter2"));
+        assert!(captures[0].truncated);
+        assert!(captures[0].request.len() <= 80);
+    }
+}
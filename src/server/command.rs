@@ -0,0 +1,606 @@
+//! 执行外部程序的工具
+//!
+//! [`CommandTool`]描述要执行的程序和参数模板，[`FunctionTool::from_command`]据此创建工具：
+//! - 默认不经过shell，程序和每个参数模板单独替换`{参数名}`后作为一个参数传给程序，
+//!   参数值中的空格、引号和`;`、`$()`等都不会被解释；
+//! - 设置[`shell`](CommandTool::shell)后把程序和参数拼成一行命令交给`sh -c`（Windows上为`cmd /C`），
+//!   替换进去的参数值会被加上引号；
+//! - 子进程的环境变量被清空，只传入[`allow_env`](CommandTool::allow_env)列出的变量（默认只有`PATH`）
+//!   和[`env`](CommandTool::env)设置的变量；
+//! - stdout和stderr分别只保留前若干字节，超出的部分被丢弃并在结果中注明；
+//! - 超过[`timeout`](CommandTool::timeout)后结束整个进程组（Windows上为进程树），工具返回超时错误；
+//!   程序正常退出时同样结束它留在后台的进程，读取输出最多等到超时，至少等到退出后的一小段时间，
+//!   脱离了进程组、仍然持有管道的进程不会让调用一直等待；
+//! - 退出码不为0时返回工具错误，可以用[`exit_code`](CommandTool::exit_code)为退出码指定说明。
+//!
+//! 成功时工具返回stdout的文本。
+//!
+//! ```rust
+//! use rustmcp::{CommandTool, FunctionTool, RustMCP};
+//! use std::time::Duration;
+//!
+//! let rustmcp = RustMCP::new();
+//! let grep = CommandTool::new("grep").args(["-n", "{pattern}", "/var/log/app.log"]).timeout(Duration::from_secs(5));
+//! rustmcp.add_tool(FunctionTool::from_command("grep_logs", "Searches the application log", grep));
+//! ```
+
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use crate::server::tools::FunctionTool;
+
+/// 默认传入子进程的环境变量
+#[cfg(not(windows))]
+const DEFAULT_ENV: &[&str] = &["PATH"];
+#[cfg(windows)]
+const DEFAULT_ENV: &[&str] = &["PATH", "PATHEXT", "SYSTEMROOT"];
+
+/// stdout默认保留的字节数
+const DEFAULT_MAX_STDOUT: usize = 1024 * 1024;
+
+/// stderr默认保留的字节数
+const DEFAULT_MAX_STDERR: usize = 64 * 1024;
+
+/// 默认超时
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 检查子进程是否退出的最长间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// 子进程退出后至少等待读完输出的时间
+const OUTPUT_GRACE: Duration = Duration::from_millis(500);
+
+/// 外部程序工具的定义
+#[derive(Debug, Clone)]
+pub struct CommandTool {
+    /// 程序模板，shell模式下为命令行的开头
+    pub program: String,
+    /// 参数模板
+    pub args: Vec<String>,
+    /// 经过`sh -c`（Windows上为`cmd /C`）执行
+    pub shell: bool,
+    /// 从服务器进程传入子进程的环境变量
+    pub allowed_env: Vec<String>,
+    /// 为子进程设置的环境变量
+    pub env: HashMap<String, String>,
+    /// 工作目录，为`None`时使用服务器的工作目录
+    pub working_dir: Option<PathBuf>,
+    /// stdout保留的最大字节数
+    pub max_stdout_bytes: usize,
+    /// stderr保留的最大字节数
+    pub max_stderr_bytes: usize,
+    /// 最长执行时间，为`None`时不限制
+    pub timeout: Option<Duration>,
+    /// 非0退出码的说明
+    pub exit_codes: HashMap<i32, String>,
+}
+
+impl CommandTool {
+    /// 执行`program`，不带参数
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            shell: false,
+            allowed_env: DEFAULT_ENV.iter().map(|name| name.to_string()).collect(),
+            env: HashMap::new(),
+            working_dir: None,
+            max_stdout_bytes: DEFAULT_MAX_STDOUT,
+            max_stderr_bytes: DEFAULT_MAX_STDERR,
+            timeout: Some(DEFAULT_TIMEOUT),
+            exit_codes: HashMap::new(),
+        }
+    }
+
+    /// 添加一个参数模板
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// 添加多个参数模板
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// 设置是否经过shell执行
+    pub fn shell(mut self, shell: bool) -> Self {
+        self.shell = shell;
+        self
+    }
+
+    /// 把服务器进程的环境变量`name`传入子进程
+    pub fn allow_env(mut self, name: impl Into<String>) -> Self {
+        self.allowed_env.push(name.into());
+        self
+    }
+
+    /// 为子进程设置环境变量
+    pub fn env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(name.into(), value.into());
+        self
+    }
+
+    /// 设置工作目录
+    pub fn working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    /// 设置stdout保留的最大字节数
+    pub fn max_stdout_bytes(mut self, bytes: usize) -> Self {
+        self.max_stdout_bytes = bytes;
+        self
+    }
+
+    /// 设置stderr保留的最大字节数
+    pub fn max_stderr_bytes(mut self, bytes: usize) -> Self {
+        self.max_stderr_bytes = bytes;
+        self
+    }
+
+    /// 设置最长执行时间
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// 不限制执行时间
+    pub fn no_timeout(mut self) -> Self {
+        self.timeout = None;
+        self
+    }
+
+    /// 为非0退出码指定错误说明
+    pub fn exit_code(mut self, code: i32, message: impl Into<String>) -> Self {
+        self.exit_codes.insert(code, message.into());
+        self
+    }
+
+    /// 用参数替换模板，构造要执行的命令；`args`为`None`时程序和参数按原样使用
+    fn command(&self, args: Option<&HashMap<String, Value>>) -> Command {
+        let mut command = match args {
+            Some(args) if self.shell => {
+                let line = std::iter::once(&self.program)
+                    .chain(&self.args)
+                    .map(|template| interpolate_with(template, |name| args.get(name).map(|value| quote(&argument_text(value)))))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                shell_command(line)
+            }
+            Some(args) => {
+                let mut command = Command::new(interpolate(&self.program, args));
+                command.args(self.args.iter().map(|template| interpolate(template, args)));
+                command
+            }
+            None => {
+                let mut command = Command::new(&self.program);
+                command.args(&self.args);
+                command
+            }
+        };
+        command.env_clear();
+        for name in &self.allowed_env {
+            if let Some(value) = std::env::var_os(name) {
+                command.env(name, value);
+            }
+        }
+        command.envs(&self.env);
+        if let Some(dir) = &self.working_dir {
+            command.current_dir(dir);
+        }
+        command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+        new_process_group(&mut command);
+        command
+    }
+
+    /// 执行命令并把结果转换为工具的返回值，`args`为`None`时不替换模板
+    pub(crate) fn run(&self, args: Option<&HashMap<String, Value>>) -> Result<Value, String> {
+        let child = self
+            .command(args)
+            .spawn()
+            .map_err(|e| format!("Failed to run '{}': {}", self.program, e))?;
+        let mut process = ProcessGroup(child);
+        let stdout = capture(process.0.stdout.take(), self.max_stdout_bytes);
+        let stderr = capture(process.0.stderr.take(), self.max_stderr_bytes);
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        let status = match wait(&mut process.0, deadline)? {
+            Some(status) => status,
+            None => {
+                let timeout = self.timeout.unwrap_or_default();
+                return Err(format!("Command timed out after {}ms and was killed", timeout.as_millis()));
+            }
+        };
+        // 结束留在后台的进程，它们持有的管道关闭后读取才会结束
+        drop(process);
+        let output_deadline = deadline.unwrap_or_else(Instant::now).max(Instant::now() + OUTPUT_GRACE);
+        let stdout = collect(stdout, output_deadline);
+        let stderr = collect(stderr, output_deadline);
+
+        if status.success() {
+            return Ok(Value::String(stdout.text()));
+        }
+        let stderr = stderr.text();
+        let message = match status.code() {
+            Some(code) => {
+                let reason = self
+                    .exit_codes
+                    .get(&code)
+                    .cloned()
+                    .unwrap_or_else(|| format!("'{}' failed", self.program));
+                format!("{} (exit code {})", reason, code)
+            }
+            None => format!("'{}' was terminated by a signal", self.program),
+        };
+        if stderr.trim().is_empty() {
+            Err(message)
+        } else {
+            Err(format!("{}: {}", message, stderr.trim()))
+        }
+    }
+}
+
+impl FunctionTool {
+    /// 创建执行外部程序的工具，参见[`command`](crate::server::command)
+    ///
+    /// 没有设置输入模式，需要时在返回后设置`input_schema`。
+    pub fn from_command(name: impl Into<String>, description: impl Into<String>, command: CommandTool) -> Self {
+        FunctionTool::builder()
+            .name(name)
+            .description(description)
+            .handler(move |args: Option<HashMap<String, Value>>| command.run(Some(&args.unwrap_or_default())))
+            .build()
+    }
+}
+
+/// 在新进程组中运行的子进程，离开作用域时（包括出错和超时）结束整个进程组并回收子进程
+struct ProcessGroup(Child);
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        kill_process_group(&mut self.0);
+        let _ = self.0.wait();
+    }
+}
+
+/// 等待子进程退出，到达`deadline`时返回`None`
+fn wait(child: &mut Child, deadline: Option<Instant>) -> Result<Option<ExitStatus>, String> {
+    let Some(deadline) = deadline else {
+        return child.wait().map(Some).map_err(|e| format!("Failed to wait for the command: {}", e));
+    };
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(Some(status)),
+            Ok(None) => {}
+            Err(e) => return Err(format!("Failed to wait for the command: {}", e)),
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(None);
+        }
+        std::thread::sleep(POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+/// 读到的输出，只保留前`limit`字节
+#[derive(Debug, Default)]
+struct Captured {
+    bytes: Vec<u8>,
+    total: usize,
+}
+
+impl Captured {
+    /// 输出的文本，截断时注明总字节数
+    fn text(&self) -> String {
+        let text = String::from_utf8_lossy(&self.bytes).into_owned();
+        if self.total > self.bytes.len() {
+            format!("{}\n[output truncated: {} bytes, showing the first {}]", text, self.total, self.bytes.len())
+        } else {
+            text
+        }
+    }
+}
+
+/// 在后台线程读取的管道
+///
+/// 读到的内容随时写入`captured`，等待超时后返回已经读到的部分；线程结束时`done`断开。
+struct Reader {
+    captured: Arc<Mutex<Captured>>,
+    done: mpsc::Receiver<()>,
+}
+
+/// 在后台线程读完管道，避免子进程因管道写满而阻塞
+fn capture(pipe: Option<impl Read + Send + 'static>, limit: usize) -> Option<Reader> {
+    let mut pipe = pipe?;
+    let captured = Arc::new(Mutex::new(Captured::default()));
+    let (done, finished) = mpsc::channel();
+    let output = captured.clone();
+    std::thread::spawn(move || {
+        let _done = done;
+        let mut buffer = [0u8; 8192];
+        while let Ok(n) = pipe.read(&mut buffer) {
+            if n == 0 {
+                break;
+            }
+            let mut captured = output.lock();
+            let keep = n.min(limit.saturating_sub(captured.bytes.len()));
+            captured.bytes.extend_from_slice(&buffer[..keep]);
+            captured.total += n;
+        }
+    });
+    Some(Reader { captured, done: finished })
+}
+
+/// 等待读取结束，最多等到`deadline`，返回读到的内容
+fn collect(reader: Option<Reader>, deadline: Instant) -> Captured {
+    let Some(reader) = reader else {
+        return Captured::default();
+    };
+    let _ = reader.done.recv_timeout(deadline.saturating_duration_since(Instant::now()));
+    let captured = std::mem::take(&mut *reader.captured.lock());
+    captured
+}
+
+#[cfg(not(windows))]
+fn shell_command(line: String) -> Command {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(line);
+    command
+}
+
+#[cfg(windows)]
+fn shell_command(line: String) -> Command {
+    use std::os::windows::process::CommandExt;
+    let mut command = Command::new("cmd");
+    command.arg("/C").raw_arg(line);
+    command
+}
+
+/// 让子进程成为新进程组的组长，超时时可以结束它启动的所有进程
+#[cfg(unix)]
+fn new_process_group(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+}
+
+#[cfg(not(unix))]
+fn new_process_group(_command: &mut Command) {}
+
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) {
+    // 进程组ID等于子进程的PID
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+}
+
+#[cfg(windows)]
+fn kill_process_group(child: &mut Child) {
+    let _ = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &child.id().to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    let _ = child.kill();
+}
+
+#[cfg(not(any(unix, windows)))]
+fn kill_process_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// 给shell命令行中的参数值加引号
+#[cfg(not(windows))]
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(windows)]
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// 参数值替换进模板时的文本，字符串不带引号
+fn argument_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// 把模板中的`{name}`替换为参数值，缺失的参数替换为空字符串；参数值中的`{...}`不会被再次替换
+pub(crate) fn interpolate(template: &str, args: &HashMap<String, Value>) -> String {
+    interpolate_with(template, |name| args.get(name).map(argument_text))
+}
+
+/// 把模板中的`{name}`替换为`value(name)`，返回`None`时替换为空字符串
+fn interpolate_with(template: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) if is_placeholder_name(&after[..end]) => {
+                if let Some(value) = value(&after[..end]) {
+                    output.push_str(&value);
+                }
+                rest = &after[end + 1..];
+            }
+            _ => {
+                output.push('{');
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+fn is_placeholder_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{McpError, RustMCP};
+
+    fn args(text: &str) -> Option<HashMap<String, Value>> {
+        Some(HashMap::from([("text".to_string(), json!(text))]))
+    }
+
+    fn execution_error(error: McpError) -> String {
+        match error {
+            McpError::ToolExecution { message, .. } => message,
+            other => panic!("expected a tool execution error, got {:?}", other),
+        }
+    }
+
+    /// 进程是否还在运行（僵尸进程按已结束处理）
+    fn alive(pid: &str) -> bool {
+        let state = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()));
+        match state {
+            Ok(state) => !state.split(") ").nth(1).is_some_and(|rest| rest.starts_with('Z')),
+            Err(_) if cfg!(target_os = "linux") => false,
+            Err(_) => unsafe { libc::kill(pid.trim().parse().unwrap(), 0) == 0 },
+        }
+    }
+
+    #[tokio::test]
+    async fn arguments_are_passed_verbatim() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::from_command("echo", "Echoes text", CommandTool::new("echo").arg("{text}")));
+        let output = rustmcp.mcp_call_tool("echo", args("$(whoami); rm -rf /tmp/x")).await.unwrap();
+        assert_eq!(output, "$(whoami); rm -rf /tmp/x\n");
+    }
+
+    #[tokio::test]
+    async fn shell_mode_quotes_arguments() {
+        let rustmcp = RustMCP::new();
+        let shell = CommandTool::new("echo {text} | tr a-z A-Z").shell(true);
+        rustmcp.add_tool(FunctionTool::from_command("shout", "Shouts text", shell));
+        let output = rustmcp.mcp_call_tool("shout", args("it's $HOME")).await.unwrap();
+        assert_eq!(output, "IT'S $HOME\n");
+    }
+
+    #[test]
+    fn environment_is_cleared_except_allowed_variables() {
+        std::env::set_var("RUSTMCP_COMMAND_TEST_SECRET", "leaked");
+        let command = CommandTool::new("sh")
+            .args(["-c", "echo \"$RUSTMCP_COMMAND_TEST_SECRET|$GREETING\""])
+            .env("GREETING", "hello");
+        assert_eq!(command.run(None).unwrap(), json!("|hello\n"));
+        let allowed = command.allow_env("RUSTMCP_COMMAND_TEST_SECRET");
+        assert_eq!(allowed.run(None).unwrap(), json!("leaked|hello\n"));
+    }
+
+    #[test]
+    fn timeout_kills_the_process_group() {
+        let dir = std::env::temp_dir().join(format!("rustmcp-command-timeout-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pid_file = dir.join("pid");
+        let script = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+        let command = CommandTool::new("sh").args(["-c", script.as_str()]).timeout(Duration::from_millis(200));
+
+        let started = Instant::now();
+        let error = command.run(None).unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(error, "Command timed out after 200ms and was killed");
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while alive(&pid) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!alive(&pid), "background process {} survived the timeout", pid.trim());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn background_processes_do_not_hold_the_result() {
+        let dir = std::env::temp_dir().join(format!("rustmcp-command-background-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pid_file = dir.join("pid");
+        let script = format!("sleep 1000 & echo $! > {}; echo hi", pid_file.display());
+        let command = CommandTool::new("sh").args(["-c", script.as_str()]).no_timeout();
+
+        let started = Instant::now();
+        assert_eq!(command.run(None).unwrap(), json!("hi\n"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // 程序退出后留在后台的进程同样被结束
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while alive(&pid) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!alive(&pid), "background process {} survived the command", pid.trim());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn escaped_processes_holding_the_pipes_are_not_waited_for() {
+        if Command::new("setsid").arg("true").status().is_err() {
+            return;
+        }
+        // setsid让后台进程离开进程组，结束进程组不会关闭它持有的stdout
+        let command = CommandTool::new("sh")
+            .args(["-c", "setsid sleep 3 & echo hi"])
+            .timeout(Duration::from_millis(200));
+        let started = Instant::now();
+        assert_eq!(command.run(None).unwrap(), json!("hi\n"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn output_is_truncated() {
+        let rustmcp = RustMCP::new();
+        let seq = CommandTool::new("seq").args(["1", "100000"]).max_stdout_bytes(12);
+        rustmcp.add_tool(FunctionTool::from_command("seq", "Counts", seq));
+        let output = rustmcp.mcp_call_tool("seq", None).await.unwrap();
+        assert_eq!(output, "1\n2\n3\n4\n5\n6\n\n[output truncated: 588895 bytes, showing the first 12]");
+    }
+
+    #[tokio::test]
+    async fn exit_codes_are_described() {
+        let rustmcp = RustMCP::new();
+        let fail = CommandTool::new("sh").args(["-c", "echo 'no such user' >&2; exit 3"]).exit_code(3, "User not found");
+        rustmcp.add_tool(FunctionTool::from_command("lookup", "Looks up a user", fail));
+        let error = rustmcp.mcp_call_tool("lookup", None).await.unwrap_err();
+        assert_eq!(execution_error(error), "User not found (exit code 3): no such user");
+
+        let unknown = CommandTool::new("sh").args(["-c", "exit 4"]);
+        assert_eq!(unknown.run(None).unwrap_err(), "'sh' failed (exit code 4)");
+        let missing = CommandTool::new("rustmcp-no-such-program");
+        assert!(missing.run(None).unwrap_err().starts_with("Failed to run 'rustmcp-no-such-program': "));
+    }
+
+    #[test]
+    fn placeholders_are_replaced_once() {
+        let args = HashMap::from([
+            ("name".to_string(), json!("{other}")),
+            ("count".to_string(), json!(3)),
+            ("other".to_string(), json!("x")),
+        ]);
+        assert_eq!(interpolate("{name}-{count}-{missing}", &args), "{other}-3-");
+        // 不是参数名的花括号原样保留
+        assert_eq!(interpolate("{not a name} {}", &args), "{not a name} {}");
+    }
+}
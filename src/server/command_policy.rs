@@ -0,0 +1,435 @@
+//! 执行客户端给出的命令行时的安全策略
+//!
+//! [`CommandTool`]执行的是服务器写好的命令模板。需要让客户端自己给出整行命令时（例如
+//! `ls -la docs`），用[`SafeCommandPolicy`]描述允许什么，再用[`FunctionTool::from_command_policy`]创建工具：
+//! - 命令行按shell的规则拆分成单词（空白分隔，支持单引号、双引号和反斜杠转义），
+//!   没有加引号的`;`、`|`、`&`、`>`、`$`、反引号、通配符等shell语法直接拒绝；
+//! - 第一个单词必须是允许的程序名，不能带路径；
+//! - 每个参数必须完整匹配默认的参数模式或该程序的参数模式。默认模式只允许普通的文件名和单词，
+//!   不允许以`-`开头，选项需要为每个程序单独允许；
+//! - 设置了工作目录时，参数不能是绝对路径、不能以`~`开头，也不能包含`..`，命令在该目录中执行；
+//! - 命令不经过shell，按拆分后的程序和参数直接执行，环境变量、超时和输出截断与[`CommandTool`]相同。
+//!
+//! 工作目录的检查只看参数文本，目录中指向外部的符号链接不受限制。
+//!
+//! ```rust
+//! use rustmcp::{FunctionTool, RustMCP, SafeCommandPolicy};
+//!
+//! let policy = SafeCommandPolicy::read_only("/srv/docs");
+//! assert!(policy.validate("ls -la guides").is_ok());
+//! assert!(policy.validate("ls; rm -rf /").is_err());
+//!
+//! let rustmcp = RustMCP::new();
+//! rustmcp.add_tool(FunctionTool::from_command_policy("run", "Runs a read-only command", policy));
+//! ```
+
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::server::command::CommandTool;
+use crate::server::tools::FunctionTool;
+
+/// 默认的参数模式：不以`-`开头的普通单词和相对路径
+const DEFAULT_ARG_PATTERN: &str = r"[A-Za-z0-9_./][A-Za-z0-9_.,:=@%+/-]*";
+
+/// 默认保留的输出字节数
+const DEFAULT_MAX_OUTPUT: usize = 64 * 1024;
+
+/// 默认的参数个数上限
+const DEFAULT_MAX_ARGS: usize = 64;
+
+/// 没有加引号时不允许出现的shell语法字符
+const SHELL_SYNTAX: &[char] = &[
+    ';', '|', '&', '<', '>', '`', '$', '(', ')', '{', '}', '*', '?', '[', ']', '~', '#', '!',
+];
+
+/// 命令行不符合策略的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolicyError {
+    /// 命令行为空
+    Empty,
+    /// 无法拆分或包含shell语法
+    Syntax(String),
+    /// 程序不在允许列表中
+    ProgramNotAllowed(String),
+    /// 参数不匹配任何允许的模式
+    ArgumentNotAllowed {
+        /// 程序名
+        program: String,
+        /// 被拒绝的参数
+        argument: String,
+    },
+    /// 参数指向工作目录之外
+    PathEscapesJail(String),
+    /// 参数太多
+    TooManyArguments {
+        /// 参数个数
+        count: usize,
+        /// 上限
+        limit: usize,
+    },
+}
+
+impl std::fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolicyError::Empty => write!(f, "Command is empty"),
+            PolicyError::Syntax(message) => write!(f, "Invalid command: {}", message),
+            PolicyError::ProgramNotAllowed(program) => write!(f, "Program '{}' is not allowed", program),
+            PolicyError::ArgumentNotAllowed { program, argument } => {
+                write!(f, "Argument '{}' is not allowed for '{}'", argument, program)
+            }
+            PolicyError::PathEscapesJail(argument) => {
+                write!(f, "Argument '{}' refers to a path outside the working directory", argument)
+            }
+            PolicyError::TooManyArguments { count, limit } => {
+                write!(f, "Too many arguments ({}, limit {})", count, limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
+impl From<PolicyError> for String {
+    fn from(e: PolicyError) -> Self {
+        e.to_string()
+    }
+}
+
+/// 通过检查的命令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedCommand {
+    /// 程序名
+    pub program: String,
+    /// 参数
+    pub args: Vec<String>,
+}
+
+/// 命令行的安全策略，参见[模块文档](self)
+#[derive(Debug, Clone)]
+pub struct SafeCommandPolicy {
+    /// 允许的程序和各自额外允许的参数模式
+    programs: BTreeMap<String, Vec<Regex>>,
+    /// 所有程序都允许的参数模式
+    default_args: Vec<Regex>,
+    max_args: usize,
+    max_output_bytes: usize,
+    working_dir: Option<PathBuf>,
+    timeout: Option<Duration>,
+}
+
+impl Default for SafeCommandPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SafeCommandPolicy {
+    /// 创建不允许任何程序的策略，参数使用默认模式，输出保留64 KiB
+    pub fn new() -> Self {
+        Self {
+            programs: BTreeMap::new(),
+            default_args: vec![anchored(DEFAULT_ARG_PATTERN)],
+            max_args: DEFAULT_MAX_ARGS,
+            max_output_bytes: DEFAULT_MAX_OUTPUT,
+            working_dir: None,
+            timeout: None,
+        }
+    }
+
+    /// 在`working_dir`中执行只读命令的常用策略
+    ///
+    /// 允许`ls`（`-l`、`-a`、`-h`、`-R`、`-1`的组合）、`cat`、`head`和`tail`（`-n`）、
+    /// `wc`（`-l`、`-w`、`-c`的组合）、`pwd`和`date`。
+    pub fn read_only(working_dir: impl Into<PathBuf>) -> Self {
+        Self::new()
+            .working_dir(working_dir)
+            .allow_arg("ls", "-[lahR1]+")
+            .allow_program("cat")
+            .allow_arg("head", "-n")
+            .allow_arg("tail", "-n")
+            .allow_arg("wc", "-[lwc]+")
+            .allow_program("pwd")
+            .allow_program("date")
+    }
+
+    /// 允许程序，参数使用默认模式
+    pub fn allow_program(mut self, program: impl Into<String>) -> Self {
+        self.programs.entry(program.into()).or_default();
+        self
+    }
+
+    /// 允许程序，并允许它使用完整匹配`pattern`的参数
+    ///
+    /// # Panics
+    ///
+    /// `pattern`不是有效的正则表达式时panic。
+    pub fn allow_arg(mut self, program: impl Into<String>, pattern: &str) -> Self {
+        self.programs.entry(program.into()).or_default().push(anchored(pattern));
+        self
+    }
+
+    /// 所有程序都允许完整匹配`pattern`的参数
+    ///
+    /// # Panics
+    ///
+    /// `pattern`不是有效的正则表达式时panic。
+    pub fn allow_any_arg(mut self, pattern: &str) -> Self {
+        self.default_args.push(anchored(pattern));
+        self
+    }
+
+    /// 设置参数个数上限（默认64）
+    pub fn max_args(mut self, limit: usize) -> Self {
+        self.max_args = limit;
+        self
+    }
+
+    /// 设置stdout和stderr各自保留的最大字节数
+    pub fn max_output_bytes(mut self, bytes: usize) -> Self {
+        self.max_output_bytes = bytes;
+        self
+    }
+
+    /// 设置工作目录，命令在其中执行，参数不能指向它之外
+    pub fn working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    /// 设置最长执行时间，不设置时使用[`CommandTool`]的默认值
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// 允许的程序名
+    pub fn programs(&self) -> impl Iterator<Item = &str> {
+        self.programs.keys().map(String::as_str)
+    }
+
+    /// 拆分命令行并检查是否符合策略
+    pub fn validate(&self, command: &str) -> Result<ParsedCommand, PolicyError> {
+        let mut words = tokenize(command)?.into_iter();
+        let program = words.next().ok_or(PolicyError::Empty)?;
+        let Some(patterns) = self.programs.get(&program) else {
+            return Err(PolicyError::ProgramNotAllowed(program));
+        };
+        let args: Vec<String> = words.collect();
+        if args.len() > self.max_args {
+            return Err(PolicyError::TooManyArguments {
+                count: args.len(),
+                limit: self.max_args,
+            });
+        }
+        for argument in &args {
+            if !self.default_args.iter().chain(patterns).any(|pattern| pattern.is_match(argument)) {
+                return Err(PolicyError::ArgumentNotAllowed {
+                    program,
+                    argument: argument.clone(),
+                });
+            }
+            if self.working_dir.is_some() && escapes(argument) {
+                return Err(PolicyError::PathEscapesJail(argument.clone()));
+            }
+        }
+        Ok(ParsedCommand { program, args })
+    }
+
+    /// 检查并执行命令，成功时返回stdout的文本
+    pub fn run(&self, command: &str) -> Result<Value, String> {
+        let parsed = self.validate(command)?;
+        let mut tool = CommandTool::new(parsed.program)
+            .args(parsed.args)
+            .max_stdout_bytes(self.max_output_bytes)
+            .max_stderr_bytes(self.max_output_bytes);
+        if let Some(dir) = &self.working_dir {
+            tool = tool.working_dir(dir.clone());
+        }
+        if let Some(timeout) = self.timeout {
+            tool = tool.timeout(timeout);
+        }
+        tool.run(None)
+    }
+}
+
+impl FunctionTool {
+    /// 创建执行客户端给出的命令行的工具，参数为`{"command": "..."}`，参见[`command_policy`](crate::server::command_policy)
+    pub fn from_command_policy(name: impl Into<String>, description: impl Into<String>, policy: SafeCommandPolicy) -> Self {
+        let programs: Vec<&str> = policy.programs().collect();
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "command": {
+                    "type": "string",
+                    "description": format!("Command line to run; allowed programs: {}", programs.join(", "))
+                }
+            },
+            "required": ["command"]
+        });
+        FunctionTool::builder()
+            .name(name)
+            .description(description)
+            .input_schema(schema)
+            .handler(move |args: Option<HashMap<String, Value>>| {
+                match args.as_ref().and_then(|args| args.get("command")).and_then(Value::as_str) {
+                    Some(command) => policy.run(command),
+                    None => Err("'command' must be a string".to_string()),
+                }
+            })
+            .build()
+    }
+}
+
+/// 编译完整匹配的模式
+fn anchored(pattern: &str) -> Regex {
+    match Regex::new(&format!("^(?:{})$", pattern)) {
+        Ok(regex) => regex,
+        Err(e) => panic!("Invalid argument pattern '{}': {}", pattern, e),
+    }
+}
+
+/// 参数（或`--option=value`中的值）是否指向工作目录之外
+fn escapes(argument: &str) -> bool {
+    argument.split('=').any(|part| {
+        part.starts_with('/') || part.starts_with('~') || part.split('/').any(|component| component == "..")
+    })
+}
+
+/// 按shell的规则把命令行拆分成单词，拒绝没有加引号的shell语法
+fn tokenize(command: &str) -> Result<Vec<String>, PolicyError> {
+    let syntax = |message: String| Err(PolicyError::Syntax(message));
+    let mut words = Vec::new();
+    // 正在读取的单词，引号中的空字符串也是一个单词
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) if c.is_control() => return syntax("control characters are not allowed".to_string()),
+                        Some(c) => word.push(c),
+                        None => return syntax("unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c @ ('$' | '`')) => return syntax(format!("'{}' is not allowed", c)),
+                            Some(c) if !c.is_control() => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            _ => return syntax("unterminated double quote".to_string()),
+                        },
+                        Some(c @ ('$' | '`')) => return syntax(format!("'{}' is not allowed", c)),
+                        Some(c) if c.is_control() => return syntax("control characters are not allowed".to_string()),
+                        Some(c) => word.push(c),
+                        None => return syntax("unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) if !c.is_control() => word.get_or_insert_with(String::new).push(c),
+                _ => return syntax("dangling backslash".to_string()),
+            },
+            c if c.is_control() => return syntax("control characters are not allowed".to_string()),
+            c if SHELL_SYNTAX.contains(&c) => return syntax(format!("'{}' is not allowed", c)),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{FunctionTool, PolicyError, RustMCP, SafeCommandPolicy};
+
+    /// 含`docs/`和`notes.txt`的临时目录
+    fn jail(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rustmcp-policy-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("notes.txt"), "one\ntwo\nthree\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn injection_and_escape_attempts_are_rejected() {
+        let dir = jail("validate");
+        let policy = SafeCommandPolicy::read_only(&dir);
+
+        // 允许的命令
+        let parsed = policy.validate(r#"head -n 2 "notes.txt""#).unwrap();
+        assert_eq!(parsed.program, "head");
+        assert_eq!(parsed.args, ["-n", "2", "notes.txt"]);
+        assert!(policy.validate("ls -la docs").is_ok());
+        assert!(policy.validate("wc -l notes.txt docs/../notes.txt").is_err());
+
+        // 注入和越权尝试
+        let attempts = [
+            "ls; rm -rf /",
+            "ls && whoami",
+            "ls || whoami",
+            "ls | sh",
+            "ls & whoami",
+            "ls `whoami`",
+            "ls $(whoami)",
+            r#"ls "$(whoami)""#,
+            "ls $HOME",
+            "ls > /tmp/owned",
+            "cat < /etc/passwd",
+            "ls\nwhoami",
+            "ls *",
+            "ls ~",
+            "ls 'unterminated",
+            "cat /etc/passwd",
+            "cat ../secret",
+            "cat docs/../../secret",
+            "cat 'a;b'",
+            "ls --output=/tmp/owned",
+            "tail -f notes.txt",
+            "/bin/ls",
+            "../bin/ls",
+            "sh -c ls",
+            "rm -rf .",
+            "find . -exec rm {} ;",
+            "env",
+            "",
+        ];
+        for attempt in attempts {
+            assert!(policy.validate(attempt).is_err(), "accepted {:?}", attempt);
+        }
+        assert_eq!(policy.validate("rm -rf .").unwrap_err(), PolicyError::ProgramNotAllowed("rm".to_string()));
+        assert!(matches!(policy.validate("cat /etc/passwd"), Err(PolicyError::PathEscapesJail(_))));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn policy_tool_runs_validated_commands_in_the_jail() {
+        let dir = jail("tool");
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::from_command_policy("run", "Runs a read-only command", SafeCommandPolicy::read_only(&dir)));
+        let args = |command: &str| Some([("command".to_string(), serde_json::json!(command))].into());
+        assert_eq!(rustmcp.mcp_call_tool("run", args("head -n 1 notes.txt")).await.unwrap(), "one\n");
+        let error = rustmcp.mcp_call_tool("run", args("cat notes.txt; id")).await.unwrap_err();
+        assert!(error.to_string().contains("';' is not allowed"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,95 @@
+//! HTTP响应压缩
+//!
+//! `tools/list`和`resources/list`的响应在注册项很多时有几百KB，慢速链路上传输很慢。
+//! 设置[`Settings::with_compression`](crate::Settings::with_compression)后，客户端的`Accept-Encoding`
+//! 包含`gzip`或`deflate`时，HTTP响应按对应的编码压缩：
+//! - 作用于REST接口、`POST /mcp`的JSON响应和管理接口；
+//! - 小于最小字节数（默认1024）的响应原样发送；
+//! - `GET /mcp`的SSE通知流从不压缩，压缩会缓冲事件，客户端要等到缓冲区满才能收到通知；
+//! - WebSocket和stdio不受影响。
+//!
+//! 配置文件中对应`compression`和`compression_min_bytes`两项，默认关闭。
+//!
+//! ```rust
+//! use rustmcp::{create_app, RustMCP, Settings};
+//!
+//! // 压缩不小于2 KiB的响应
+//! let app = create_app(RustMCP::new().with_settings(Settings::new().with_compression(2048)));
+//! ```
+
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+
+/// 默认的最小压缩字节数
+pub(crate) const DEFAULT_MIN_BYTES: u16 = 1024;
+
+/// 压缩不小于`min_bytes`的响应，跳过SSE流、图片和gRPC
+pub(crate) fn layer(min_bytes: u16) -> CompressionLayer<impl Predicate> {
+    let predicate = SizeAbove::new(min_bytes)
+        .and(NotForContentType::SSE)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::GRPC);
+    CompressionLayer::new().gzip(true).deflate(true).compress_when(predicate)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{HeaderMap, Request};
+    use serde_json::{json, Value};
+    use tower::Service;
+
+    use crate::server::test_support::{get, request};
+    use crate::{create_app, FunctionTool, RustMCP, Settings};
+
+    /// 响应头和未解码的响应体
+    async fn raw(app: &axum::Router, body: &Value, encoding: Option<&str>) -> (HeaderMap, Vec<u8>) {
+        let mut request = Request::post("/mcp").header("content-type", "application/json");
+        if let Some(encoding) = encoding {
+            request = request.header("accept-encoding", encoding);
+        }
+        let response = app.clone().call(request.body(Body::from(body.to_string())).unwrap()).await.unwrap();
+        let headers = response.headers().clone();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (headers, body.to_vec())
+    }
+
+    #[tokio::test]
+    async fn large_responses_are_compressed_except_the_event_stream() {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_compression(1024));
+        for i in 0..200 {
+            rustmcp.add_tool(FunctionTool::simple(format!("tool_{}", i), "Does one of many things", |_| Ok(json!("ok"))));
+        }
+        let app = create_app(rustmcp);
+        let list = request(2, "tools/list", json!({}));
+
+        // 小于最小字节数的响应不压缩
+        let initialize = request(1, "initialize", json!({ "protocolVersion": "2025-06-18" }));
+        let (headers, _) = raw(&app, &initialize, Some("gzip")).await;
+        assert!(headers.get("content-encoding").is_none());
+        let session = headers["mcp-session-id"].clone();
+
+        // 大的列表按客户端接受的编码压缩，gzip数据以1f 8b开头
+        let (headers, body) = raw(&app, &list, Some("gzip")).await;
+        assert_eq!(headers["content-encoding"], "gzip");
+        assert_eq!(&body[..2], &[0x1f, 0x8b]);
+        let (headers, _) = raw(&app, &list, Some("deflate")).await;
+        assert_eq!(headers["content-encoding"], "deflate");
+
+        // 没有Accept-Encoding时原样发送
+        let (headers, body) = raw(&app, &list, None).await;
+        assert!(headers.get("content-encoding").is_none());
+        let response: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response["result"]["tools"].as_array().unwrap().len(), 200);
+
+        // REST接口同样压缩
+        let (_, headers, _) = get(&app, "/mcp/tools", &[("accept-encoding", "gzip")]).await;
+        assert_eq!(headers["content-encoding"], "gzip");
+
+        // SSE通知流从不压缩；流不会结束，只检查响应头
+        let request = Request::get("/mcp").header("mcp-session-id", session).header("accept-encoding", "gzip").body(Body::empty()).unwrap();
+        let response = app.clone().call(request).await.unwrap();
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        assert!(response.headers().get("content-encoding").is_none());
+    }
+}
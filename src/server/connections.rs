@@ -0,0 +1,314 @@
+//! 连接和会话查询
+//!
+//! [`RustMCP::sessions`](crate::RustMCP::sessions)列出当前的会话，用于调试线上服务器：
+//! - WebSocket和标准输入输出：每个连接一个会话，连接建立时出现，断开时消失；
+//! - HTTP：不带`Mcp-Session-Id`的`initialize`创建会话，超时、被淘汰或`DELETE /mcp`终止时消失。
+//!
+//! [`RustMCP::disconnect_session`](crate::RustMCP::disconnect_session)强制结束会话：WebSocket连接
+//! 收到关闭帧（`1000`）后断开，标准输入输出停止服务，HTTP会话与`DELETE /mcp`相同，丢弃会话存储。
+//! 开启`admin`特性后，管理接口提供`GET /sessions`和`DELETE /sessions/{id}`。
+//!
+//! 服务器没有实现`resources/subscribe`，会话信息中没有订阅列表。
+//!
+//! ```rust
+//! use rustmcp::RustMCP;
+//!
+//! let rustmcp = RustMCP::new();
+//! for session in rustmcp.sessions() {
+//!     println!("{} {:?} {} requests", session.session_id, session.transport, session.requests);
+//! }
+//! ```
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::server::context::TransportKind;
+use crate::server::notifications::Outbox;
+use crate::server::resources::ResourceRegistrar;
+use crate::server::sse::{EventLog, ReplayLimits};
+
+/// 会话信息
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    /// 会话ID
+    pub session_id: String,
+    /// 传输类型
+    pub transport: TransportKind,
+    /// 连接建立（HTTP为第一次请求）的Unix时间戳，毫秒
+    pub connected_at_ms: u64,
+    /// 协商的协议版本，还没有完成`initialize`时为`None`
+    pub protocol_version: Option<String>,
+    /// 客户端在`initialize`中发送的`clientInfo`
+    pub client_info: Option<Value>,
+    /// 已处理的请求数
+    pub requests: u64,
+}
+
+/// `initialize`协商的结果
+#[derive(Debug, Clone, Default)]
+struct Negotiated {
+    protocol_version: Option<String>,
+    client_info: Option<Value>,
+    capabilities: Option<Value>,
+}
+
+/// 单个会话的连接信息，由传输层创建，同一会话的所有请求共享
+#[derive(Debug)]
+pub struct Connection {
+    session_id: String,
+    transport: TransportKind,
+    connected_at_ms: u64,
+    /// `initialize`协商的结果
+    client: Mutex<Negotiated>,
+    requests: AtomicU64,
+    /// WebSocket和标准输入输出连接的发送队列
+    outbox: Option<Arc<Outbox>>,
+    /// HTTP会话的通知记录，参见[`sse`](crate::server::sse)
+    events: Option<Arc<EventLog>>,
+    disconnected: AtomicBool,
+    /// 服务器正在关闭，连接发完队列中的消息后以`1001`关闭
+    going_away: AtomicBool,
+    /// 会话范围的资源，会话结束时删除
+    scoped_resources: Mutex<Vec<(String, ResourceRegistrar)>>,
+}
+
+impl Connection {
+    pub(crate) fn new(session_id: &str, transport: TransportKind, outbox: Option<Arc<Outbox>>) -> Arc<Self> {
+        Arc::new(Self::build(session_id, transport, outbox, None))
+    }
+
+    /// 创建HTTP会话的连接信息，带有通知记录
+    pub(crate) fn http(session_id: &str, replay: ReplayLimits) -> Arc<Self> {
+        Arc::new(Self::build(session_id, TransportKind::Http, None, Some(EventLog::new(replay))))
+    }
+
+    fn build(session_id: &str, transport: TransportKind, outbox: Option<Arc<Outbox>>, events: Option<Arc<EventLog>>) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            transport,
+            connected_at_ms: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0),
+            client: Mutex::default(),
+            requests: AtomicU64::new(0),
+            outbox,
+            events,
+            disconnected: AtomicBool::new(false),
+            going_away: AtomicBool::new(false),
+            scoped_resources: Mutex::default(),
+        }
+    }
+
+    /// HTTP会话的通知记录
+    pub(crate) fn events(&self) -> Option<&Arc<EventLog>> {
+        self.events.as_ref()
+    }
+
+    /// 记录一个请求
+    pub(crate) fn record_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 记录`initialize`协商的结果
+    pub(crate) fn record_initialize(&self, protocol_version: Option<&str>, client_info: Option<&Value>, capabilities: Option<&Value>) {
+        *self.client.lock() = Negotiated {
+            protocol_version: protocol_version.map(str::to_string),
+            client_info: client_info.cloned(),
+            capabilities: capabilities.cloned(),
+        };
+    }
+
+    /// `initialize`协商的协议版本
+    pub(crate) fn protocol_version(&self) -> Option<String> {
+        self.client.lock().protocol_version.clone()
+    }
+
+    /// 客户端在`initialize`中发送的`clientInfo`
+    pub(crate) fn client_info(&self) -> Option<Value> {
+        self.client.lock().client_info.clone()
+    }
+
+    /// 客户端在`initialize`中声明的能力
+    pub(crate) fn client_capabilities(&self) -> Option<Value> {
+        self.client.lock().capabilities.clone()
+    }
+
+    /// 是否被服务器强制断开
+    pub(crate) fn is_disconnected(&self) -> bool {
+        self.disconnected.load(Ordering::Acquire)
+    }
+
+    /// 服务器是否正在关闭
+    pub(crate) fn is_going_away(&self) -> bool {
+        self.going_away.load(Ordering::Acquire)
+    }
+
+    /// 服务器关闭时结束连接，与强制断开不同，队列中的消息会先发出
+    fn go_away(&self) {
+        self.going_away.store(true, Ordering::Release);
+        if let Some(outbox) = &self.outbox {
+            outbox.finish();
+        }
+    }
+
+    /// 强制断开，关闭发送队列；传输层据此结束连接
+    fn disconnect(&self) {
+        self.disconnected.store(true, Ordering::Release);
+        if let Some(outbox) = &self.outbox {
+            outbox.close();
+        }
+    }
+
+    /// 会话结束时删除资源
+    pub(crate) fn scope_resource(&self, uri: String, registrar: ResourceRegistrar) {
+        self.scoped_resources.lock().push((uri, registrar));
+    }
+
+    /// 当前的会话信息
+    pub fn info(&self) -> SessionInfo {
+        let Negotiated { protocol_version, client_info, .. } = self.client.lock().clone();
+        SessionInfo {
+            session_id: self.session_id.clone(),
+            transport: self.transport,
+            connected_at_ms: self.connected_at_ms,
+            protocol_version,
+            client_info,
+            requests: self.requests.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for Connection {
+    /// 最后一个引用释放即会话结束：WebSocket或标准输入输出连接关闭，或HTTP会话被删除、过期或淘汰
+    fn drop(&mut self) {
+        for (uri, registrar) in self.scoped_resources.get_mut().drain(..) {
+            registrar.remove(&uri);
+        }
+    }
+}
+
+/// WebSocket和标准输入输出的连接
+#[derive(Debug, Default)]
+pub(crate) struct ConnectionRegistry {
+    connections: Mutex<HashMap<String, Arc<Connection>>>,
+}
+
+impl ConnectionRegistry {
+    /// 登记连接，返回的句柄释放时移除
+    pub fn register(self: &Arc<Self>, session_id: &str, transport: TransportKind, outbox: Arc<Outbox>) -> ConnectionGuard {
+        let connection = Connection::new(session_id, transport, Some(outbox));
+        self.connections.lock().insert(session_id.to_string(), connection.clone());
+        ConnectionGuard {
+            registry: self.clone(),
+            connection,
+        }
+    }
+
+    /// 当前连接的信息
+    pub fn list(&self) -> Vec<SessionInfo> {
+        self.connections.lock().values().map(|connection| connection.info()).collect()
+    }
+
+    /// 服务器关闭时让WebSocket连接发完队列中的消息后以`1001`关闭
+    pub fn go_away(&self) {
+        for connection in self.connections.lock().values() {
+            if connection.transport == TransportKind::Ws {
+                connection.go_away();
+            }
+        }
+    }
+
+    /// 会话当前登记的另一个连接的发送队列，用于把旧连接未送达的响应交给重新连接的客户端
+    pub(crate) fn other_outbox(&self, session_id: &str, except: Option<&Arc<Connection>>) -> Option<Arc<Outbox>> {
+        let connections = self.connections.lock();
+        let connection = connections.get(session_id)?;
+        if except.is_some_and(|except| Arc::ptr_eq(connection, except)) || connection.is_disconnected() {
+            return None;
+        }
+        connection.outbox.clone()
+    }
+
+    /// 强制断开连接，连接存在时返回`true`
+    pub fn disconnect(&self, session_id: &str) -> bool {
+        match self.connections.lock().get(session_id) {
+            Some(connection) => {
+                connection.disconnect();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// 已登记的连接，释放时从登记表中移除
+#[derive(Debug)]
+pub(crate) struct ConnectionGuard {
+    registry: Arc<ConnectionRegistry>,
+    connection: Arc<Connection>,
+}
+
+impl ConnectionGuard {
+    pub fn connection(&self) -> Arc<Connection> {
+        self.connection.clone()
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let mut connections = self.registry.connections.lock();
+        // 只移除自己，不影响之后以同一ID登记的连接
+        if connections.get(&self.connection.session_id).is_some_and(|c| Arc::ptr_eq(c, &self.connection)) {
+            connections.remove(&self.connection.session_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{SinkExt, StreamExt};
+    use std::time::Duration;
+    use tokio_tungstenite::tungstenite::Message;
+
+    use crate::server::test_support::spawn_server;
+    use crate::{RustMCP, TransportKind};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn sessions_are_listed_and_disconnected() {
+        let rustmcp = RustMCP::new();
+        let url = format!("ws://{}/mcp/ws", spawn_server(&rustmcp).await);
+
+        let mut clients = Vec::new();
+        for name in ["alpha", "beta"] {
+            let (mut client, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+            let initialize = format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"initialize","params":{{"clientInfo":{{"name":"{}","version":"1.0"}}}}}}"#,
+                name
+            );
+            client.send(Message::text(initialize)).await.unwrap();
+            client.next().await.unwrap().unwrap();
+            clients.push(client);
+        }
+
+        let sessions = rustmcp.sessions();
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.iter().all(|s| s.transport == TransportKind::Ws && s.requests == 1));
+        assert!(sessions.iter().all(|s| s.protocol_version.as_deref() == Some("2024-11-05")));
+        let beta = sessions.iter().find(|s| s.client_info.as_ref().unwrap()["name"] == "beta").unwrap();
+
+        // 强制断开beta，客户端收到关闭帧
+        assert!(rustmcp.disconnect_session(&beta.session_id));
+        match clients[1].next().await {
+            Some(Ok(Message::Close(Some(frame)))) => assert_eq!(u16::from(frame.code), 1000),
+            other => panic!("expected a close frame, got {:?}", other),
+        }
+        while rustmcp.sessions().len() != 1 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(rustmcp.sessions()[0].client_info.as_ref().unwrap()["name"], "alpha");
+        assert!(!rustmcp.disconnect_session(&beta.session_id));
+    }
+}
@@ -0,0 +1,384 @@
+//! 内容块定义
+//!
+//! 工具结果中的`content`数组由内容块组成。工具函数可以直接返回一个内容块
+//! （或内容块数组）序列化后的`Value`，分发器会原样放入结果中；
+//! 其他返回值仍然作为单个文本块返回。协商2024-11-05的会话默认收到合并后的单个文本块，
+//! 参见[`ResultFormat`]。
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+use crate::server::resources::FunctionResource;
+
+/// 默认的媒体内容最大编码大小（base64编码后，4 MB）
+pub const DEFAULT_MAX_ENCODED_SIZE: usize = 4 * 1024 * 1024;
+
+/// 允许的图片MIME类型
+pub const IMAGE_MIME_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+/// 允许的音频MIME类型
+pub const AUDIO_MIME_TYPES: &[&str] = &["audio/wav", "audio/mpeg", "audio/ogg", "audio/flac", "audio/webm"];
+
+/// 内容块
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Content {
+    /// 文本内容
+    Text {
+        text: String,
+    },
+    /// 图片内容（base64编码）
+    Image {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    /// 音频内容（base64编码）
+    Audio {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    /// 指向资源的链接，客户端可以用`resources/read`读取
+    ResourceLink {
+        uri: String,
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+        mime_type: Option<String>,
+    },
+}
+
+/// `tools/call`结果的格式
+///
+/// 没有设置[`Settings::result_format`](crate::Settings::result_format)时按会话在`initialize`中协商的
+/// 协议版本选择：2024-11-05的会话使用`Legacy`，更新的版本以及没有协商的请求（无会话的HTTP请求、REST接口）
+/// 使用`Spec`。同一服务器上的不同会话可以收到不同格式。
+///
+/// ```rust
+/// use rustmcp::{ResultFormat, RustMCP, Settings};
+///
+/// // 不按会话协商，总是使用规范格式
+/// let rustmcp = RustMCP::new().with_settings(Settings::new().with_result_format(ResultFormat::Spec));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultFormat {
+    /// 旧格式：`content`只有一个文本块，不带`structuredContent`
+    Legacy,
+    /// 规范格式：工具返回的内容块原样放入`content`，声明了`outputSchema`时带`structuredContent`
+    Spec,
+}
+
+impl ResultFormat {
+    /// 按协商的协议版本选择格式，没有协商时为`Spec`
+    pub fn for_protocol_version(protocol_version: Option<&str>) -> Self {
+        match protocol_version {
+            Some("2024-11-05") => ResultFormat::Legacy,
+            _ => ResultFormat::Spec,
+        }
+    }
+}
+
+/// 内容构造错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentError {
+    /// 不支持的MIME类型
+    UnsupportedMimeType(String),
+    /// 编码后的大小超过限制
+    TooLarge { size: usize, max: usize },
+    /// 无法从文件内容识别类型
+    UnknownFormat,
+    /// 读取文件失败
+    Io(String),
+}
+
+impl std::fmt::Display for ContentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContentError::UnsupportedMimeType(mime) => write!(f, "Unsupported MIME type: {}", mime),
+            ContentError::TooLarge { size, max } => {
+                write!(f, "Encoded content size {} exceeds the maximum of {} bytes", size, max)
+            }
+            ContentError::UnknownFormat => write!(f, "Unable to detect content type from file contents"),
+            ContentError::Io(e) => write!(f, "Failed to read file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ContentError {}
+
+impl From<ContentError> for String {
+    fn from(e: ContentError) -> Self {
+        e.to_string()
+    }
+}
+
+impl Content {
+    /// 创建文本内容
+    pub fn text(text: impl Into<String>) -> Self {
+        Content::Text { text: text.into() }
+    }
+
+    /// 指向资源的链接，带有资源的URI、名称、标题、描述和MIME类型
+    ///
+    /// 工具在调用中生成的资源可以用[`Context::register_resource`](crate::Context::register_resource)注册后返回链接。
+    pub fn link_to(resource: &FunctionResource) -> Self {
+        let non_empty = |text: &str| if text.is_empty() { None } else { Some(text.to_string()) };
+        Content::ResourceLink {
+            uri: resource.uri.clone(),
+            name: resource.name.clone(),
+            title: resource.title.clone(),
+            description: non_empty(&resource.description),
+            mime_type: non_empty(&resource.mime_type),
+        }
+    }
+
+    /// 从字节创建图片内容，使用默认大小限制
+    pub fn image_from_bytes(bytes: &[u8], mime: &str) -> Result<Self, ContentError> {
+        Self::image_from_bytes_with_limit(bytes, mime, DEFAULT_MAX_ENCODED_SIZE)
+    }
+
+    /// 从字节创建图片内容，使用指定的最大编码大小
+    pub fn image_from_bytes_with_limit(bytes: &[u8], mime: &str, max_encoded_size: usize) -> Result<Self, ContentError> {
+        let mime = check_mime(mime, IMAGE_MIME_TYPES)?;
+        let data = encode(bytes, max_encoded_size)?;
+        Ok(Content::Image { data, mime_type: mime })
+    }
+
+    /// 从字节创建音频内容，使用默认大小限制
+    pub fn audio_from_bytes(bytes: &[u8], mime: &str) -> Result<Self, ContentError> {
+        Self::audio_from_bytes_with_limit(bytes, mime, DEFAULT_MAX_ENCODED_SIZE)
+    }
+
+    /// 从字节创建音频内容，使用指定的最大编码大小
+    pub fn audio_from_bytes_with_limit(bytes: &[u8], mime: &str, max_encoded_size: usize) -> Result<Self, ContentError> {
+        let mime = check_mime(mime, AUDIO_MIME_TYPES)?;
+        let data = encode(bytes, max_encoded_size)?;
+        Ok(Content::Audio { data, mime_type: mime })
+    }
+
+    /// 从文件创建图片内容，根据文件头识别图片类型
+    pub fn image_from_path(path: impl AsRef<Path>) -> Result<Self, ContentError> {
+        let path = path.as_ref();
+        // 在读取整个文件之前先检查大小，避免读取超大文件
+        let metadata = std::fs::metadata(path).map_err(|e| ContentError::Io(e.to_string()))?;
+        let size = encoded_len(metadata.len() as usize);
+        if size > DEFAULT_MAX_ENCODED_SIZE {
+            return Err(ContentError::TooLarge { size, max: DEFAULT_MAX_ENCODED_SIZE });
+        }
+        let bytes = std::fs::read(path).map_err(|e| ContentError::Io(e.to_string()))?;
+        let mime = sniff_image_mime(&bytes).ok_or(ContentError::UnknownFormat)?;
+        Self::image_from_bytes(&bytes, mime)
+    }
+
+    /// 把内容块合并为一个文本块，文本以换行连接，媒体内容用`[image: image/png]`这样的占位文本代替
+    pub fn flatten(content: &[Content]) -> Content {
+        let parts: Vec<String> = content
+            .iter()
+            .map(|block| match block {
+                Content::Text { text } => text.clone(),
+                Content::Image { mime_type, .. } => format!("[image: {}]", mime_type),
+                Content::Audio { mime_type, .. } => format!("[audio: {}]", mime_type),
+                Content::ResourceLink { uri, .. } => format!("[resource: {}]", uri),
+            })
+            .collect();
+        Content::text(parts.join("\n"))
+    }
+
+    /// 尝试将工具返回值解析为内容块列表
+    ///
+    /// 返回值为单个内容块对象或内容块数组时返回`Some`，否则返回`None`。
+    pub fn from_tool_value(value: &Value) -> Option<Vec<Content>> {
+        match value {
+            Value::Object(map) if map.contains_key("type") => {
+                serde_json::from_value::<Content>(value.clone()).ok().map(|c| vec![c])
+            }
+            Value::Array(items) if !items.is_empty() => items
+                .iter()
+                .map(|item| match item {
+                    Value::Object(map) if map.contains_key("type") => {
+                        serde_json::from_value::<Content>(item.clone()).ok()
+                    }
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+}
+
+impl From<Content> for Value {
+    fn from(content: Content) -> Self {
+        serde_json::to_value(content).unwrap_or(Value::Null)
+    }
+}
+
+/// 根据文件头识别图片MIME类型
+pub fn sniff_image_mime(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+fn check_mime(mime: &str, allowed: &[&str]) -> Result<String, ContentError> {
+    let normalized = mime.trim().to_ascii_lowercase();
+    if allowed.contains(&normalized.as_str()) {
+        Ok(normalized)
+    } else {
+        Err(ContentError::UnsupportedMimeType(mime.to_string()))
+    }
+}
+
+fn encoded_len(len: usize) -> usize {
+    len.div_ceil(3) * 4
+}
+
+fn encode(bytes: &[u8], max_encoded_size: usize) -> Result<String, ContentError> {
+    // 编码前计算大小，超限时不分配编码缓冲区
+    let size = encoded_len(bytes.len());
+    if size > max_encoded_size {
+        return Err(ContentError::TooLarge { size, max: max_encoded_size });
+    }
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use crate::server::test_support::{post, request};
+    use crate::{create_app, FunctionTool, RustMCP};
+
+    const PNG_HEADER: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+    #[test]
+    fn image_from_bytes_encodes_base64() {
+        let content = Content::image_from_bytes(b"hello", "image/png").unwrap();
+        assert_eq!(content, Content::Image { data: "aGVsbG8=".to_string(), mime_type: "image/png".to_string() });
+        assert_eq!(
+            serde_json::to_value(&content).unwrap(),
+            json!({ "type": "image", "data": "aGVsbG8=", "mimeType": "image/png" })
+        );
+    }
+
+    #[test]
+    fn audio_from_bytes_encodes_base64_and_normalizes_mime() {
+        let content = Content::audio_from_bytes(&[0, 1, 2, 254, 255], " Audio/WAV ").unwrap();
+        assert_eq!(content, Content::Audio { data: "AAEC/v8=".to_string(), mime_type: "audio/wav".to_string() });
+    }
+
+    #[test]
+    fn size_guard_rejects_before_encoding() {
+        // 6字节编码后为8字节
+        assert!(Content::image_from_bytes_with_limit(&[0; 6], "image/gif", 8).is_ok());
+        assert_eq!(
+            Content::image_from_bytes_with_limit(&[0; 7], "image/gif", 8),
+            Err(ContentError::TooLarge { size: 12, max: 8 })
+        );
+        assert_eq!(
+            Content::audio_from_bytes_with_limit(&[0; 10], "audio/ogg", 4),
+            Err(ContentError::TooLarge { size: 16, max: 4 })
+        );
+        let oversized = vec![0u8; DEFAULT_MAX_ENCODED_SIZE];
+        assert!(matches!(Content::image_from_bytes(&oversized, "image/png"), Err(ContentError::TooLarge { .. })));
+    }
+
+    #[test]
+    fn unsupported_mime_types_are_rejected() {
+        assert_eq!(
+            Content::image_from_bytes(b"x", "image/svg+xml"),
+            Err(ContentError::UnsupportedMimeType("image/svg+xml".to_string()))
+        );
+        // 音频类型不能用作图片，反之亦然
+        assert!(matches!(Content::image_from_bytes(b"x", "audio/wav"), Err(ContentError::UnsupportedMimeType(_))));
+        assert!(matches!(Content::audio_from_bytes(b"x", "image/png"), Err(ContentError::UnsupportedMimeType(_))));
+    }
+
+    #[test]
+    fn image_from_path_sniffs_the_type() {
+        let dir = std::env::temp_dir().join(format!("rustmcp-content-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let png = dir.join("pixel.bin");
+        std::fs::write(&png, [PNG_HEADER, b"data"].concat()).unwrap();
+        match Content::image_from_path(&png).unwrap() {
+            Content::Image { mime_type, data } => {
+                assert_eq!(mime_type, "image/png");
+                assert_eq!(base64::engine::general_purpose::STANDARD.decode(data).unwrap(), [PNG_HEADER, b"data"].concat());
+            }
+            other => panic!("expected an image, got {:?}", other),
+        }
+
+        let text = dir.join("notes.txt");
+        std::fs::write(&text, "not an image").unwrap();
+        assert_eq!(Content::image_from_path(&text), Err(ContentError::UnknownFormat));
+        assert!(matches!(Content::image_from_path(dir.join("missing.png")), Err(ContentError::Io(_))));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sniffs_known_image_headers() {
+        assert_eq!(sniff_image_mime(&[0xFF, 0xD8, 0xFF, 0xE0]), Some("image/jpeg"));
+        assert_eq!(sniff_image_mime(b"GIF89a...."), Some("image/gif"));
+        assert_eq!(sniff_image_mime(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff_image_mime(b"RIFF\0\0\0\0WAVE"), None);
+    }
+
+    #[tokio::test]
+    async fn result_format_follows_the_negotiated_version() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("forecast")
+                .description("Returns the forecast")
+                .output_schema(json!({ "type": "object" }))
+                .handler(|_| Ok(json!({ "temp": 21 })))
+                .build(),
+        );
+        rustmcp.add_tool(FunctionTool::simple("report", "Returns two blocks", |_| {
+            Ok(json!([Content::text("Sunny"), Content::text("Light wind")]))
+        }));
+        let app = create_app(rustmcp);
+
+        let mut sessions = Vec::new();
+        for version in ["2024-11-05", "2025-06-18"] {
+            let initialize = request(1, "initialize", json!({ "protocolVersion": version }));
+            let (_, headers, response) = post(&app, "/mcp", &[], &initialize).await;
+            assert_eq!(response["result"]["protocolVersion"], version);
+            sessions.push(headers["mcp-session-id"].to_str().unwrap().to_string());
+        }
+        let (old, new) = (sessions[0].as_str(), sessions[1].as_str());
+        let call = |session: &str, name: &str| {
+            let (app, session) = (app.clone(), session.to_string());
+            let body = request(2, "tools/call", json!({ "name": name }));
+            async move { post(&app, "/mcp", &[("mcp-session-id", &session)], &body).await.2["result"].clone() }
+        };
+
+        // 2024-11-05：单个文本块
+        let result = call(old, "forecast").await;
+        assert_eq!(result["content"], json!([{ "type": "text", "text": "{\"temp\":21}" }]));
+        assert!(result.get("structuredContent").is_none());
+        let result = call(old, "report").await;
+        assert_eq!(result["content"], json!([{ "type": "text", "text": "Sunny\nLight wind" }]));
+
+        // 2025-06-18：内容块数组和structuredContent
+        let result = call(new, "forecast").await;
+        assert_eq!(result["structuredContent"], json!({ "temp": 21 }));
+        let result = call(new, "report").await;
+        assert_eq!(result["content"].as_array().unwrap().len(), 2);
+    }
+}
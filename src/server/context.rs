@@ -0,0 +1,639 @@
+//! 请求上下文
+//!
+//! [`Context`]在每个请求中创建，传递给工具、资源、提示和钩子；
+//! 其中的[`RequestContext`]由传输层填充，描述调用方是谁以及通过什么方式调用。
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::server::connections::Connection;
+use crate::server::error::McpError;
+use crate::server::features::{Feature, ProtocolVersion};
+use crate::server::lifecycle::{Lifecycle, LifecyclePolicy, LifecycleState};
+use crate::server::locale::{self, LanguageTag};
+use crate::server::methods::is_spec_reserved;
+use crate::server::notifications::{Delivery, SessionChannel};
+use crate::server::resources::{FunctionResource, ResourceRegistrar};
+use crate::server::session::{SessionLimits, SessionStore};
+use crate::server::state::StateMap;
+use crate::protocol::{Implementation, JsonRpcNotification};
+
+/// 不会被复制到请求上下文中的敏感请求头
+const SENSITIVE_HEADERS: &[&str] = &["authorization", "proxy-authorization", "cookie", "set-cookie"];
+
+/// 传输类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportKind {
+    /// HTTP请求
+    Http,
+    /// WebSocket连接
+    Ws,
+    /// 标准输入输出
+    Stdio,
+    /// 在Rust代码中直接调用，不经过传输层
+    #[default]
+    Direct,
+}
+
+/// 已认证的调用方身份
+///
+/// 认证中间件可以把`Identity`放入请求扩展（`request.extensions_mut().insert(identity)`），
+/// HTTP和WebSocket传输会把它带入请求上下文。
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Identity {
+    /// 身份标识（用户名、客户端ID等）
+    pub subject: String,
+    /// 附加的身份信息
+    pub claims: HashMap<String, Value>,
+}
+
+impl Identity {
+    /// 创建新的身份
+    pub fn new(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            claims: HashMap::new(),
+        }
+    }
+}
+
+/// 传输层提供的请求信息
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    /// 传输类型
+    pub transport: TransportKind,
+    /// 远端地址（已知时）
+    pub remote_addr: Option<SocketAddr>,
+    /// 请求头（名称为小写，不包含认证相关的敏感请求头）
+    pub headers: HashMap<String, String>,
+    /// 会话ID
+    pub session_id: Option<String>,
+    /// 已认证的调用方身份
+    pub identity: Option<Identity>,
+    /// 会话的回传通道，只有WebSocket和标准输入输出这类双向传输才有
+    pub back_channel: Option<SessionChannel>,
+    /// 会话存储，没有会话的请求为临时存储
+    pub session: Option<SessionStore>,
+    /// 会话的初始化状态，同一会话的请求共享
+    pub lifecycle: Lifecycle,
+    /// 在`initialize`之前到达的请求的处理策略，`Auto`会按传输解析
+    pub lifecycle_policy: LifecyclePolicy,
+    /// 会话的连接信息，用于[`RustMCP::sessions`](crate::RustMCP::sessions)
+    pub connection: Option<Arc<Connection>>,
+}
+
+impl RequestContext {
+    /// 创建指定传输类型的请求上下文
+    pub fn new(transport: TransportKind) -> Self {
+        Self {
+            transport,
+            ..Self::default()
+        }
+    }
+
+    /// 从HTTP请求头构造上下文使用的请求头表，过滤敏感请求头
+    pub fn collect_headers(headers: &axum::http::HeaderMap) -> HashMap<String, String> {
+        headers
+            .iter()
+            .filter(|(name, _)| !SENSITIVE_HEADERS.contains(&name.as_str()))
+            .filter_map(|(name, value)| value.to_str().ok().map(|v| (name.as_str().to_string(), v.to_string())))
+            .collect()
+    }
+
+    /// 获取请求头
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_ascii_lowercase()).map(|v| v.as_str())
+    }
+
+    /// 按传输解析后的生命周期策略，只会是`Strict`或`Lenient`
+    pub fn effective_lifecycle_policy(&self) -> LifecyclePolicy {
+        self.lifecycle_policy.resolve(self.transport, self.session_id.is_some())
+    }
+
+    /// 用于区分调用方的键：优先使用会话ID，其次使用远端IP
+    pub fn caller_key(&self) -> String {
+        self.session_id
+            .clone()
+            .or_else(|| self.remote_addr.map(|addr| addr.ip().to_string()))
+            .unwrap_or_else(|| "anonymous".to_string())
+    }
+}
+
+/// 请求的取消标记，克隆共享同一个状态
+///
+/// 请求超过截止时间后分发器会触发它；同步执行的工具可以在耗时的步骤之间检查
+/// [`Context::is_cancelled`]并提前返回。
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// 创建未触发的标记
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 触发取消
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    /// 是否已触发取消
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// 请求的处理状态，drop时把请求标记为已完成
+///
+/// 分发器在生成响应前持有它，[`Context::is_request_active`]读取同一个标记。
+#[derive(Debug, Default)]
+pub(crate) struct RequestGuard(Arc<AtomicBool>);
+
+impl RequestGuard {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+/// RustMCP上下文
+///
+/// 每个请求都会创建一个新的上下文，并传递给工具、资源和提示的处理函数。
+///
+/// 上下文是廉价的克隆句柄：请求信息、会话的回传通道、状态表、注册资源的入口和取消标记都放在`Arc`中，
+/// 克隆共享同一份状态，不借用请求的任何数据，可以移入`tokio::spawn`的后台任务，在请求完成后继续使用。
+/// 请求完成后（[`is_request_active`](Self::is_request_active)返回`false`）：
+/// - [`notify`](Self::notify)只在会话仍然连接时送达，连接关闭后返回[`Delivery::NotDelivered`]；
+/// - [`set_result_meta`](Self::set_result_meta)不再影响已经发送的结果；
+/// - 注册资源照常生效。
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    /// 传输层提供的请求信息
+    request: Arc<RequestContext>,
+    /// 客户端在请求`params._meta`中携带的元数据
+    request_meta: Option<Value>,
+    /// 处理函数设置的结果元数据
+    result_meta: Arc<Mutex<Option<Value>>>,
+    /// 共享的应用状态
+    state: Arc<StateMap>,
+    /// 请求信息中没有会话存储时使用的临时存储
+    ephemeral_session: Arc<OnceLock<SessionStore>>,
+    /// 请求的截止时间
+    deadline: Option<Instant>,
+    /// 请求的取消标记
+    cancellation: CancellationToken,
+    /// 注册资源的入口，经过[`RustMCP`](crate::RustMCP)调用时设置
+    resources: Option<ResourceRegistrar>,
+    /// 请求是否已经完成，由分发器的[`RequestGuard`]设置
+    completed: Arc<AtomicBool>,
+}
+
+impl Context {
+    /// 创建新的空上下文
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置请求信息
+    pub fn with_request(mut self, request: Arc<RequestContext>) -> Self {
+        self.request = request;
+        self
+    }
+
+    /// 设置请求元数据
+    pub fn with_request_meta(mut self, meta: Value) -> Self {
+        self.request_meta = Some(meta);
+        self
+    }
+
+    /// 设置共享的应用状态
+    ///
+    /// 经过[`RustMCP`](crate::RustMCP)调用时会自动设置为服务器的状态表；
+    /// 直接测试处理函数时可以用它注入状态。
+    pub fn with_state(mut self, state: Arc<StateMap>) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// 设置注册资源的入口
+    pub(crate) fn with_resources(mut self, resources: ResourceRegistrar) -> Self {
+        self.resources = Some(resources);
+        self
+    }
+
+    /// 关联分发器的请求状态
+    pub(crate) fn with_request_guard(mut self, guard: &RequestGuard) -> Self {
+        self.completed = guard.0.clone();
+        self
+    }
+
+    /// 发起这个上下文的请求是否仍在处理中
+    ///
+    /// 分发器生成响应后返回`false`，此后发出的通知和进度只是尽力送达。
+    /// 不经过传输层直接调用（例如[`RustMCP::mcp_call_tool`](crate::RustMCP::mcp_call_tool)）时始终返回`true`。
+    ///
+    /// ```rust
+    /// use rustmcp::FunctionTool;
+    /// use serde_json::json;
+    /// use std::time::Duration;
+    ///
+    /// let tool = FunctionTool::builder().name("export").handler_with_context(|ctx, _| {
+    ///     let ctx = ctx.clone();
+    ///     tokio::spawn(async move {
+    ///         while ctx.is_request_active() {
+    ///             tokio::time::sleep(Duration::from_millis(20)).await;
+    ///         }
+    ///         // 响应已经发出，通知只是尽力送达
+    ///         let _ = ctx.notify("acme/export_ready", json!({ "file": "export.csv" }));
+    ///     });
+    ///     Ok(json!({ "started": true }))
+    /// }).build();
+    /// ```
+    pub fn is_request_active(&self) -> bool {
+        !self.completed.load(Ordering::Acquire)
+    }
+
+    /// 获取共享的应用状态，没有注册该类型时返回`None`
+    pub fn state<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.state.get::<T>()
+    }
+
+    /// 获取传输层提供的请求信息
+    pub fn request(&self) -> &RequestContext {
+        &self.request
+    }
+
+    /// 获取客户端在请求`params._meta`中携带的元数据
+    pub fn request_meta(&self) -> Option<&Value> {
+        self.request_meta.as_ref()
+    }
+
+    /// 客户端在`initialize`中发送的`clientInfo`，没有会话、还没有协商或格式不对时为`None`
+    pub fn client_info(&self) -> Option<Implementation> {
+        let client_info = self.request.connection.as_ref()?.client_info()?;
+        serde_json::from_value(client_info).ok()
+    }
+
+    /// 调用方的语言偏好，按优先级排列
+    ///
+    /// 请求`params._meta.locale`优先，其次是HTTP请求头`Accept-Language`，格式相同；
+    /// 都没有时为空。参见[`locale`](crate::server::locale)。
+    pub fn locale(&self) -> Vec<LanguageTag> {
+        locale::preferences(self.request_meta.as_ref(), self.request.header("accept-language"))
+    }
+
+    /// 设置结果的`_meta`，该值会原样返回给客户端
+    pub fn set_result_meta(&self, meta: Value) {
+        *self.result_meta.lock().unwrap() = Some(meta);
+    }
+
+    /// 在结果元数据中加入一项，保留处理函数设置的其他项
+    pub(crate) fn insert_result_meta(&self, key: &str, value: Value) {
+        let mut meta = self.result_meta.lock().unwrap();
+        match meta.get_or_insert_with(|| Value::Object(Default::default())) {
+            Value::Object(map) => {
+                map.insert(key.to_string(), value);
+            }
+            _ => log::warn!("Result metadata is not an object, dropping '{}'", key),
+        }
+    }
+
+    /// 获取处理函数设置的结果元数据
+    pub fn result_meta(&self) -> Option<Value> {
+        self.result_meta.lock().unwrap().clone()
+    }
+
+    /// 获取调用方会话的键值存储
+    ///
+    /// 同一会话的多次调用共享同一个存储。没有会话时（无会话ID的HTTP请求、直接调用）
+    /// 返回只在本次调用内有效的临时存储，使用时会记录警告。参见[`crate::server::session`]。
+    pub fn session(&self) -> SessionStore {
+        match &self.request.session {
+            Some(session) => session.clone(),
+            None => self
+                .ephemeral_session
+                .get_or_init(|| SessionStore::ephemeral(SessionLimits::default()))
+                .clone(),
+        }
+    }
+
+    /// 当前会话使用的生命周期策略（已按传输解析为`Strict`或`Lenient`）
+    pub fn lifecycle_policy(&self) -> LifecyclePolicy {
+        self.request.effective_lifecycle_policy()
+    }
+
+    /// 当前会话的初始化状态
+    pub fn lifecycle_state(&self) -> LifecycleState {
+        self.request.lifecycle.state()
+    }
+
+    /// 当前会话是否由客户端通过`initialize`完成了协商，宽松策略下合成的会话返回`false`
+    pub fn is_negotiated(&self) -> bool {
+        self.lifecycle_state() == LifecycleState::Negotiated
+    }
+
+    /// 当前会话协商的协议版本，没有会话或还没有协商时为最旧的版本，参见[`features`](crate::server::features)
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.request
+            .connection
+            .as_ref()
+            .and_then(|connection| connection.protocol_version())
+            .and_then(|version| ProtocolVersion::parse(&version))
+            .unwrap_or(ProtocolVersion::OLDEST)
+    }
+
+    /// 当前会话是否支持某个功能，参见[`features`](crate::server::features)
+    pub fn supports(&self, feature: Feature) -> bool {
+        let capabilities = self.request.connection.as_ref().and_then(|connection| connection.client_capabilities());
+        feature.is_supported(self.protocol_version(), capabilities.as_ref())
+    }
+
+    /// 在调用期间注册资源，返回资源URI，注册后发送资源列表变更通知
+    ///
+    /// 适合工具生成报告等文件后注册为资源，再用[`Content::link_to`](crate::Content::link_to)返回指向它的链接。
+    /// 与[`RustMCP::try_add_resource`](crate::RustMCP::try_add_resource)相同，重复行为为`Error`且资源已存在时返回错误；
+    /// 上下文不是经过[`RustMCP`](crate::RustMCP)调用得到的（例如直接测试处理函数）时也返回错误。
+    ///
+    /// ```rust
+    /// use rustmcp::{Content, FunctionResource, FunctionTool};
+    /// use serde_json::json;
+    ///
+    /// let tool = FunctionTool::builder()
+    ///     .name("report")
+    ///     .handler_with_context(|ctx, _args| {
+    ///         let resource = FunctionResource::simple("report://latest", || Ok(json!("3 rows"))).with_title("Summary");
+    ///         let link = Content::link_to(&resource);
+    ///         ctx.register_resource(resource)?;
+    ///         Ok(json!([Content::text("Report ready"), link]))
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn register_resource(&self, resource: FunctionResource) -> Result<String, String> {
+        self.registrar()?.add(resource)
+    }
+
+    /// 注册只在当前会话期间存在的资源，会话结束时删除并发送资源列表变更通知
+    ///
+    /// 会话结束指WebSocket或标准输入输出连接关闭，或HTTP会话被删除、过期或淘汰。资源在会话期间对所有客户端可见，
+    /// 只是生命周期跟随会话。没有会话的请求（无会话ID的HTTP请求、直接调用）返回错误。
+    /// 资源函数不应持有`Context`，否则会话的连接信息无法释放，资源也不会被删除。
+    pub fn register_session_resource(&self, resource: FunctionResource) -> Result<String, String> {
+        let Some(connection) = &self.request.connection else {
+            return Err("session-scoped resources need a session".to_string());
+        };
+        let registrar = self.registrar()?;
+        let uri = registrar.add(resource)?;
+        connection.scope_resource(uri.clone(), registrar.clone());
+        Ok(uri)
+    }
+
+    fn registrar(&self) -> Result<&ResourceRegistrar, String> {
+        self.resources
+            .as_ref()
+            .ok_or_else(|| "resources can only be registered while handling a request".to_string())
+    }
+
+    /// 为同一请求中的一次调用创建上下文（例如批量调用中的每一项）
+    ///
+    /// 共享请求信息、元数据、状态、截止时间和取消标记，结果元数据各自独立。
+    pub(crate) fn for_call(&self) -> Context {
+        Context {
+            result_meta: Arc::default(),
+            ..self.clone()
+        }
+    }
+
+    /// 设置请求的截止时间
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// 请求的截止时间：客户端`_meta.timeoutMs`和服务器`request_timeout_ms`中较早的一个，
+    /// 都没有时为`None`
+    ///
+    /// 超过截止时间的请求返回`-32001`，同时触发[`cancellation_token`](Self::cancellation_token)。
+    ///
+    /// ```rust
+    /// use rustmcp::{Context, FunctionTool};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let tool = FunctionTool::builder().name("wait").handler_with_context(|ctx, _| {
+    ///     while ctx.remaining().is_some_and(|remaining| remaining > Duration::ZERO) && !ctx.is_cancelled() {
+    ///         std::thread::sleep(Duration::from_millis(5));
+    ///     }
+    ///     Ok(serde_json::json!("stopped"))
+    /// }).build();
+    /// let ctx = Context::new().with_deadline(Instant::now() + Duration::from_millis(20));
+    /// assert_eq!(tool.call(&ctx, None).unwrap(), "stopped");
+    /// ```
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// 距离截止时间的剩余时间，已经过期时为0，没有截止时间时为`None`
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// 请求的取消标记
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// 请求是否已被取消（例如超过了截止时间）
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// 通过调用方会话的回传通道发送自定义JSON-RPC通知
+    ///
+    /// 方法名不能是MCP规范保留的方法（例如`tools/call`），以`notifications/`开头的除外，
+    /// 否则返回[`McpError::ReservedMethod`]。传输没有回传通道时不发送，返回
+    /// [`Delivery::NotDelivered`]。同一次调用发出的通知按顺序送达，并且都在调用结果之前；
+    /// 请求完成后从后台任务发出的通知在结果之后送达，会话已经关闭时返回[`Delivery::NotDelivered`]。
+    pub fn notify(&self, method: &str, params: Value) -> Result<Delivery, McpError> {
+        if !method.starts_with("notifications/") && is_spec_reserved(method) {
+            return Err(McpError::ReservedMethod(method.to_string()));
+        }
+        let channel = match &self.request.back_channel {
+            Some(channel) => channel,
+            None => return Ok(Delivery::NotDelivered),
+        };
+        Ok(channel.send(&JsonRpcNotification::new(method, Some(params))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use serde_json::json;
+
+    use super::*;
+    use crate::server::test_support::{initialize, post, post_tcp, request, send, spawn_server, stdio, WsClient};
+    use crate::server::{create_app, FunctionResource, FunctionTool, RustMCP};
+    use crate::{Content, Settings};
+
+    /// 返回工具看到的请求信息
+    fn whoami_server() -> RustMCP {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("whoami")
+                .handler_with_context(|ctx, _| {
+                    let request = ctx.request();
+                    Ok(Value::String(format!(
+                        "{:?} {} {}",
+                        request.transport,
+                        request.remote_addr.map(|addr| addr.ip().to_string()).unwrap_or_default(),
+                        request.header("X-Caller").unwrap_or("-"),
+                    )))
+                })
+                .build(),
+        );
+        rustmcp
+    }
+
+    #[tokio::test]
+    async fn http_tools_see_headers_and_peer_address() {
+        let addr = spawn_server(&whoami_server()).await;
+        let body = request(1, "tools/call", json!({ "name": "whoami" }));
+        let (status, response) = post_tcp(addr, "/mcp", &[("x-caller", "billing"), ("authorization", "Bearer t")], &body).await;
+        assert_eq!(status, 200);
+        assert_eq!(response["result"]["content"][0]["text"], "\"Http 127.0.0.1 billing\"", "{}", response);
+    }
+
+    #[tokio::test]
+    async fn ws_tools_see_the_transport_kind() {
+        let addr = spawn_server(&whoami_server()).await;
+        let mut client = WsClient::initialized(addr).await;
+        let response = client.call(1, "tools/call", json!({ "name": "whoami" })).await;
+        assert_eq!(response["result"]["content"][0]["text"], "\"Ws 127.0.0.1 -\"", "{}", response);
+    }
+
+    #[test]
+    fn sensitive_headers_are_not_collected() {
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("authorization", "Bearer secret".parse().unwrap());
+        headers.insert("x-caller", "billing".parse().unwrap());
+        let context = RequestContext {
+            headers: RequestContext::collect_headers(&headers),
+            ..RequestContext::new(TransportKind::Http)
+        };
+        assert_eq!(context.header("X-Caller"), Some("billing"));
+        assert_eq!(context.header("authorization"), None);
+    }
+
+    #[tokio::test]
+    async fn deadline_is_the_earlier_of_client_and_server_limits() {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_request_timeout(Duration::from_millis(300)));
+        rustmcp.add_tool(FunctionTool::builder().name("wait").handler_with_context(|ctx, args| {
+            let ms = args.and_then(|args| args.get("ms").and_then(Value::as_u64)).unwrap_or(0);
+            let until = Instant::now() + Duration::from_millis(ms);
+            while Instant::now() < until && !ctx.is_cancelled() {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            Ok(json!(ctx.remaining().unwrap().as_millis() as u64))
+        }).build());
+
+        let wait = |id: u64, arguments: Value, timeout_ms: i64| {
+            request(id, "tools/call", json!({ "name": "wait", "arguments": arguments, "_meta": { "timeoutMs": timeout_ms } }))
+        };
+        let responses = stdio(&rustmcp, &[
+            request(1, "initialize", json!({})),
+            // 客户端的时限比服务器短
+            wait(2, json!({ "ms": 5000 }), 100),
+            // 客户端的时限比服务器长，使用服务器的时限
+            wait(3, json!({ "ms": 5000 }), 60000),
+            // 低于下限的时限被提高到50ms
+            wait(4, json!({}), 1),
+            wait(5, json!({}), -1),
+        ])
+        .await;
+        let response = |id: u64| responses.iter().find(|response| response["id"] == id).unwrap();
+
+        assert_eq!(response(2)["error"]["code"], -32001);
+        assert_eq!(response(2)["error"]["data"]["timeoutMs"], 100);
+        assert_eq!(response(3)["error"]["code"], -32001);
+        assert_eq!(response(3)["error"]["data"]["timeoutMs"], 300);
+        assert_eq!(response(4)["result"]["isError"], false);
+        assert!(response(4)["result"]["content"][0]["text"].as_str().unwrap().parse::<u64>().unwrap() <= 50);
+        assert!(response(4)["result"]["_meta"]["warnings"][0].as_str().unwrap().contains("below the minimum"));
+        assert_eq!(response(5)["error"]["code"], -32602);
+    }
+
+    #[tokio::test]
+    async fn session_resources_are_linked_read_and_removed_with_the_session() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::builder().name("report").handler_with_context(|ctx, _args| {
+            let session = ctx.request().session_id.clone().unwrap_or_default();
+            let resource = FunctionResource::simple(format!("report://{}/summary", session), || Ok(json!("3 rows"))).with_title("Summary");
+            let link = Content::link_to(&resource);
+            ctx.register_session_resource(resource)?;
+            Ok(json!([Content::text("Report ready"), link]))
+        }).build());
+        let app = create_app(rustmcp.clone());
+        let id = initialize(&app, json!({ "protocolVersion": "2025-06-18", "capabilities": {} })).await;
+        let session = [("mcp-session-id", id.as_str())];
+
+        let call = post(&app, "/mcp", &session, &request(1, "tools/call", json!({ "name": "report" }))).await.2;
+        let link = &call["result"]["content"][1];
+        let uri = format!("report://{}/summary", id);
+        assert_eq!(link, &json!({
+            "type": "resource_link",
+            "uri": uri,
+            "name": uri,
+            "title": "Summary",
+            "mimeType": "text/plain"
+        }));
+
+        // 同一会话的下一个请求读取链接指向的资源
+        let read = post(&app, "/mcp", &session, &request(2, "resources/read", json!({ "uri": link["uri"] }))).await.2;
+        assert_eq!(read["result"]["contents"][0]["text"], "3 rows");
+
+        // 会话结束后资源被删除
+        let end = Request::delete("/mcp").header("mcp-session-id", &id).body(Body::empty()).unwrap();
+        assert_eq!(send(&app, end).await.0, StatusCode::NO_CONTENT);
+        assert!(rustmcp.mcp_list_resources().is_empty());
+    }
+
+    #[tokio::test]
+    async fn background_work_sees_the_request_complete() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::builder().name("export").handler_with_context(|ctx, _| {
+            // 把上下文移入后台任务，响应发出两个节拍之后再通知
+            let ctx = ctx.clone();
+            tokio::spawn(async move {
+                let mut ticks = tokio::time::interval(Duration::from_millis(20));
+                while ctx.is_request_active() {
+                    ticks.tick().await;
+                }
+                ticks.tick().await;
+                ticks.tick().await;
+                ctx.notify("acme/export_ready", json!({ "file": "export.csv", "active": ctx.is_request_active() })).unwrap();
+            });
+            Ok(json!({ "started": true }))
+        }).build());
+
+        let mut client = WsClient::initialized(spawn_server(&rustmcp).await).await;
+        let response = client.call(2, "tools/call", json!({ "name": "export" })).await;
+        assert!(response["result"].is_object(), "{}", response);
+
+        // 同一会话在响应之后收到通知
+        let notification = client.recv().await;
+        assert_eq!(notification["method"], "acme/export_ready");
+        assert_eq!(notification["params"], json!({ "file": "export.csv", "active": false }));
+    }
+}
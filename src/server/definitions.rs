@@ -0,0 +1,228 @@
+//! 工具之间共享的JSON Schema定义
+//!
+//! 多个工具使用相同的参数结构（分页、日期范围等）时，可以用[`RustMCP::register_schema`](crate::RustMCP::register_schema)
+//! 注册一次，再在`inputSchema`或`outputSchema`中通过`{"$ref": "#/definitions/<name>"}`引用：
+//! - `tools/list`（以及OpenAPI文档和LLM导出）把引用到的定义打包进每个工具模式的`definitions`，
+//!   客户端看到的是自包含的模式；定义之间的引用也会一起打包；
+//! - 调用前的参数校验使用展开引用后的模式；
+//! - 注册定义或工具时，引用了不存在的定义直接返回错误（[`add_tool`](crate::RustMCP::add_tool)会panic），
+//!   工具模式自己的`definitions`中的定义不受影响。
+//!
+//! ```rust
+//! use rustmcp::{FunctionTool, RustMCP};
+//! use serde_json::json;
+//!
+//! let rustmcp = RustMCP::new();
+//! rustmcp.register_schema("PageParams", json!({
+//!     "type": "object",
+//!     "properties": { "page": { "type": "integer" }, "size": { "type": "integer" } }
+//! })).unwrap();
+//! rustmcp.add_tool(
+//!     FunctionTool::builder()
+//!         .name("list_users")
+//!         .input_schema(json!({ "type": "object", "properties": { "paging": { "$ref": "#/definitions/PageParams" } } }))
+//!         .handler(|args| Ok(json!(args)))
+//!         .build(),
+//! );
+//! ```
+
+use parking_lot::RwLock;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 共享定义的引用前缀
+const PREFIX: &str = "#/definitions/";
+
+/// 校验时最多展开的引用数，递归的定义超出后按任意值处理
+const MAX_EXPANSIONS: usize = 256;
+
+/// 注册的共享定义
+#[derive(Debug, Default)]
+pub(crate) struct SchemaRegistry {
+    schemas: RwLock<BTreeMap<String, Value>>,
+}
+
+impl SchemaRegistry {
+    /// 注册或替换定义，定义可以引用自己和已经注册的定义
+    pub(crate) fn register(&self, name: String, schema: Value) -> Result<(), String> {
+        if name.is_empty() || name.contains('/') {
+            return Err(format!("Invalid schema name '{}'", name));
+        }
+        let mut schemas = self.schemas.write();
+        let unknown = references(&schema)
+            .into_iter()
+            .find(|reference| *reference != name && !schemas.contains_key(reference) && !is_local(&schema, reference));
+        if let Some(reference) = unknown {
+            return Err(format!("Schema '{}' references unknown schema '{}{}'", name, PREFIX, reference));
+        }
+        schemas.insert(name, schema);
+        Ok(())
+    }
+
+    /// 检查模式中的引用都能找到，`owner`用于错误消息
+    pub(crate) fn check(&self, owner: &str, schema: &Value) -> Result<(), String> {
+        let schemas = self.schemas.read();
+        match references(schema)
+            .into_iter()
+            .find(|reference| !schemas.contains_key(reference) && !is_local(schema, reference))
+        {
+            Some(reference) => Err(format!("{} references unknown schema '{}{}'", owner, PREFIX, reference)),
+            None => Ok(()),
+        }
+    }
+
+    /// 把模式引用到的共享定义（包括定义之间的引用）放进模式的`definitions`，没有引用共享定义时返回`None`
+    pub(crate) fn bundle(&self, schema: &Value) -> Option<Value> {
+        let schemas = self.schemas.read();
+        let mut needed = BTreeSet::new();
+        let mut pending: Vec<String> = references(schema).into_iter().filter(|reference| !is_local(schema, reference)).collect();
+        while let Some(name) = pending.pop() {
+            if let Some(definition) = schemas.get(&name) {
+                if needed.insert(name) {
+                    pending.extend(references(definition));
+                }
+            }
+        }
+        if needed.is_empty() {
+            return None;
+        }
+        let mut bundled = schema.clone();
+        let definitions = bundled
+            .as_object_mut()?
+            .entry("definitions")
+            .or_insert_with(|| Value::Object(Default::default()));
+        if let Value::Object(definitions) = definitions {
+            for name in needed {
+                let definition = schemas[&name].clone();
+                definitions.entry(name).or_insert(definition);
+            }
+        }
+        Some(bundled)
+    }
+
+    /// 展开模式中的引用，用于参数校验；没有引用时返回`None`
+    pub(crate) fn resolve(&self, schema: &Value) -> Option<Value> {
+        if references(schema).is_empty() {
+            return None;
+        }
+        let schemas = self.schemas.read();
+        let local = schema.get("definitions");
+        let mut budget = MAX_EXPANSIONS;
+        // 顶层的`definitions`只用来查找，不需要展开
+        Some(match schema {
+            Value::Object(map) if !map.contains_key("$ref") => Value::Object(
+                map.iter()
+                    .filter(|(key, _)| key.as_str() != "definitions")
+                    .map(|(key, value)| (key.clone(), inline(value, local, &schemas, &mut budget)))
+                    .collect(),
+            ),
+            _ => inline(schema, local, &schemas, &mut budget),
+        })
+    }
+}
+
+/// 模式中所有`#/definitions/<name>`引用的名称
+fn references(schema: &Value) -> BTreeSet<String> {
+    fn collect(value: &Value, names: &mut BTreeSet<String>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(name) = map.get("$ref").and_then(Value::as_str).and_then(|r| r.strip_prefix(PREFIX)) {
+                    names.insert(name.to_string());
+                }
+                map.values().for_each(|value| collect(value, names));
+            }
+            Value::Array(items) => items.iter().for_each(|value| collect(value, names)),
+            _ => {}
+        }
+    }
+    let mut names = BTreeSet::new();
+    collect(schema, &mut names);
+    names
+}
+
+/// 模式自己的`definitions`中是否有这个定义
+fn is_local(schema: &Value, name: &str) -> bool {
+    schema.get("definitions").and_then(|definitions| definitions.get(name)).is_some()
+}
+
+/// 用定义替换引用，模式自己的定义优先于共享定义
+fn inline(value: &Value, local: Option<&Value>, schemas: &BTreeMap<String, Value>, budget: &mut usize) -> Value {
+    match value {
+        Value::Object(map) => {
+            if let Some(name) = map.get("$ref").and_then(Value::as_str).and_then(|r| r.strip_prefix(PREFIX)) {
+                let target = local.and_then(|local| local.get(name)).or_else(|| schemas.get(name));
+                return match target {
+                    Some(target) if *budget > 0 => {
+                        *budget -= 1;
+                        inline(target, local, schemas, budget)
+                    }
+                    _ => Value::Object(Default::default()),
+                };
+            }
+            Value::Object(map.iter().map(|(key, value)| (key.clone(), inline(value, local, schemas, budget))).collect())
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| inline(item, local, schemas, budget)).collect()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::{FunctionTool, RustMCP};
+
+    #[tokio::test]
+    async fn shared_definitions_are_bundled_and_validated() {
+        let rustmcp = RustMCP::new();
+        rustmcp.register_schema("PageParams", json!({
+            "type": "object",
+            "properties": { "page": { "type": "integer" }, "size": { "type": "integer" } },
+            "required": ["page"]
+        })).unwrap();
+
+        for name in ["list_users", "list_orders"] {
+            let tool = FunctionTool::builder()
+                .name(name)
+                .input_schema(json!({
+                    "type": "object",
+                    "properties": { "paging": { "$ref": "#/definitions/PageParams" } },
+                    "required": ["paging"]
+                }))
+                .validate_input(true)
+                .handler(|args| Ok(json!(args)))
+                .build();
+            rustmcp.add_tool(tool);
+        }
+
+        // 列表中的模式是自包含的
+        for tool in rustmcp.mcp_list_tools() {
+            let schema = tool.input_schema.unwrap();
+            assert_eq!(schema["properties"]["paging"]["$ref"], "#/definitions/PageParams");
+            assert_eq!(schema["definitions"]["PageParams"]["required"], json!(["page"]));
+        }
+
+        // 校验使用共享的定义
+        let args = |paging: Value| Some(json!({ "paging": paging }).as_object().unwrap().clone().into_iter().collect());
+        assert!(rustmcp.mcp_call_tool("list_users", args(json!({ "page": 2 }))).await.is_ok());
+        let error = rustmcp.mcp_call_tool("list_orders", args(json!({ "size": 10 }))).await.unwrap_err();
+        assert_eq!(error.to_jsonrpc_error().code, -32602);
+        let error = rustmcp.mcp_call_tool("list_orders", args(json!({ "page": "two" }))).await.unwrap_err();
+        assert!(error.to_string().contains("paging.page"));
+    }
+
+    #[test]
+    fn unknown_references_fail_at_registration() {
+        let rustmcp = RustMCP::new();
+        let broken = FunctionTool::builder()
+            .name("list_invoices")
+            .input_schema(json!({ "type": "object", "properties": { "range": { "$ref": "#/definitions/DateRange" } } }))
+            .handler(|args| Ok(json!(args)))
+            .build();
+        assert_eq!(
+            rustmcp.try_add_tool(broken).unwrap_err(),
+            "Tool 'list_invoices' inputSchema references unknown schema '#/definitions/DateRange'"
+        );
+        assert!(rustmcp.register_schema("Report", json!({ "$ref": "#/definitions/DateRange" })).is_err());
+    }
+}
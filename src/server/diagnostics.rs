@@ -0,0 +1,319 @@
+//! 注册项的启动校验
+//!
+//! 输入模式写错的工具、没有scheme的资源URI、模板里用不到的提示参数等问题，
+//! 只有客户端碰巧用到时才会暴露。[`RustMCP::validate`](crate::RustMCP::validate)在启动前检查
+//! 所有已注册的工具、资源和提示，返回带严重程度和出错项名称的问题列表。
+//! [`create_app`](crate::create_app)和[`serve_stdio`](crate::serve_stdio)启动时会记录这些问题，
+//! 设置了[`Settings::strict_validation`](crate::Settings::strict_validation)时遇到错误拒绝启动。
+//!
+//! ```rust
+//! use rustmcp::{FunctionResource, RustMCP, Severity};
+//!
+//! let rustmcp = RustMCP::new();
+//! rustmcp.add_resource(FunctionResource::from_function(
+//!     || Ok(serde_json::json!("hello")),
+//!     "greeting".to_string(),
+//!     None,
+//!     Some("A greeting".to_string()),
+//!     None,
+//!     None,
+//!     None,
+//!     None,
+//! ));
+//!
+//! let issues = rustmcp.validate();
+//! assert_eq!(issues.len(), 1);
+//! assert_eq!(issues[0].severity, Severity::Error);
+//! assert_eq!(issues[0].item, "greeting");
+//! ```
+
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::server::prompts::FunctionPrompt;
+use crate::server::resources::Resource;
+use crate::server::tools::FunctionTool;
+use crate::server::validation::{self, check_schema, validate_arguments};
+
+/// 问题的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// 可能是疏忽，不影响启动
+    Warning,
+    /// 注册项无法正常使用，严格校验时拒绝启动
+    Error,
+}
+
+/// 出问题的注册项类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ItemKind {
+    Tool,
+    Resource,
+    Prompt,
+    /// 服务器设置，例如出站HTTP客户端的配置
+    Settings,
+}
+
+impl std::fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ItemKind::Tool => write!(f, "tool"),
+            ItemKind::Resource => write!(f, "resource"),
+            ItemKind::Prompt => write!(f, "prompt"),
+            ItemKind::Settings => write!(f, "settings"),
+        }
+    }
+}
+
+/// 校验发现的问题
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    /// 严重程度
+    pub severity: Severity,
+    /// 注册项类型
+    pub kind: ItemKind,
+    /// 工具或提示的名称，资源的URI，或设置项的名称
+    pub item: String,
+    /// 问题描述
+    pub message: String,
+}
+
+impl ValidationIssue {
+    pub(crate) fn new(severity: Severity, kind: ItemKind, item: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            kind,
+            item: item.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} '{}': {}", self.kind, self.item, self.message)
+    }
+}
+
+/// 检查所有注册项，结果按类型和名称排序
+pub(crate) fn validate<'a>(
+    tools: impl Iterator<Item = &'a FunctionTool>,
+    resources: &[Resource],
+    prompts: impl Iterator<Item = &'a FunctionPrompt>,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    let mut tool_titles = HashMap::new();
+    for tool in tools {
+        check_tool(tool, &mut issues);
+        let title = tool.title.as_ref().or_else(|| tool.annotations.as_ref().and_then(|a| a.title.as_ref()));
+        if let Some(title) = title {
+            tool_titles.entry(title.clone()).or_insert_with(Vec::new).push(tool.name.clone());
+        }
+    }
+    duplicate_titles(ItemKind::Tool, tool_titles, &mut issues);
+
+    let mut resource_titles = HashMap::new();
+    for resource in resources {
+        check_resource(resource, &mut issues);
+        if let Some(title) = &resource.title {
+            // 同一URI的多个表示共用标题
+            let uris = resource_titles.entry(title.clone()).or_insert_with(Vec::new);
+            if !uris.contains(&resource.uri) {
+                uris.push(resource.uri.clone());
+            }
+        }
+    }
+    duplicate_titles(ItemKind::Resource, resource_titles, &mut issues);
+
+    let mut prompt_titles = HashMap::new();
+    for prompt in prompts {
+        check_prompt(prompt, &mut issues);
+        if let Some(title) = &prompt.title {
+            prompt_titles.entry(title.clone()).or_insert_with(Vec::new).push(prompt.name.clone());
+        }
+    }
+    duplicate_titles(ItemKind::Prompt, prompt_titles, &mut issues);
+
+    issues.sort_by(|a, b| (a.kind, &a.item, &a.message).cmp(&(b.kind, &b.item, &b.message)));
+    issues
+}
+
+/// 按当前的模式检查工具和提示的示例，结果按类型和名称排序
+///
+/// 只经过校验层，不调用工具或提示函数。工具示例的参数按输入模式校验（不论工具是否开启了输入校验），
+/// 预期结果按输出模式校验；提示示例只能使用声明过的参数。
+pub(crate) fn validate_examples<'a>(
+    tools: impl Iterator<Item = &'a FunctionTool>,
+    prompts: impl Iterator<Item = &'a FunctionPrompt>,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    for tool in tools {
+        let issue = |message: String| ValidationIssue::new(Severity::Error, ItemKind::Tool, &tool.name, message);
+        for example in &tool.examples {
+            let Value::Object(arguments) = &example.arguments else {
+                issues.push(issue(format!("example '{}': arguments must be an object", example.description)));
+                continue;
+            };
+            if let Some(schema) = &tool.input_schema {
+                let arguments: HashMap<String, Value> = arguments.clone().into_iter().collect();
+                if let Err(error) = validate_arguments(schema, Some(&arguments)) {
+                    issues.push(issue(format!("example '{}' does not match inputSchema: {}", example.description, error)));
+                }
+            }
+            if let (Some(schema), Some(output)) = (&tool.output_schema, &example.expected_output) {
+                if let Err(error) = validation::validate(schema, output, "") {
+                    issues.push(issue(format!("example '{}' does not match outputSchema: {}", example.description, error)));
+                }
+            }
+        }
+    }
+    for prompt in prompts {
+        for example in &prompt.examples {
+            let mut undeclared: Vec<&String> = example
+                .arguments
+                .keys()
+                .filter(|name| !prompt.arguments.as_ref().is_some_and(|arguments| arguments.contains_key(*name)))
+                .collect();
+            undeclared.sort();
+            for name in undeclared {
+                issues.push(ValidationIssue::new(
+                    Severity::Error,
+                    ItemKind::Prompt,
+                    &prompt.name,
+                    format!("example '{}' uses undeclared argument '{}'", example.description, name),
+                ));
+            }
+        }
+    }
+    issues.sort_by(|a, b| (a.kind, &a.item, &a.message).cmp(&(b.kind, &b.item, &b.message)));
+    issues
+}
+
+/// 检查单个工具、资源或提示，管理接口在运行时注册前使用
+#[cfg(feature = "admin")]
+pub(crate) fn check_item(item: Item<'_>) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    match item {
+        Item::Tool(tool) => check_tool(tool, &mut issues),
+        Item::Resource(resource) => check_resource(resource, &mut issues),
+        Item::Prompt(prompt) => check_prompt(prompt, &mut issues),
+    }
+    issues
+}
+
+/// 待检查的注册项
+#[cfg(feature = "admin")]
+pub(crate) enum Item<'a> {
+    Tool(&'a FunctionTool),
+    Resource(&'a Resource),
+    Prompt(&'a FunctionPrompt),
+}
+
+fn check_tool(tool: &FunctionTool, issues: &mut Vec<ValidationIssue>) {
+    let issue = |severity, message: String| ValidationIssue::new(severity, ItemKind::Tool, &tool.name, message);
+    if tool.description.trim().is_empty() {
+        issues.push(issue(Severity::Warning, "description is empty".to_string()));
+    }
+    if let Some(schema) = &tool.input_schema {
+        if let Err(message) = check_schema(schema, "inputSchema") {
+            issues.push(issue(Severity::Error, message));
+        } else if schema.get("type").is_some_and(|t| t != "object") {
+            issues.push(issue(Severity::Error, "inputSchema must have type 'object'".to_string()));
+        } else {
+            for name in undeclared_required(schema) {
+                issues.push(issue(
+                    Severity::Warning,
+                    format!("inputSchema requires '{}' but does not declare it in properties", name),
+                ));
+            }
+        }
+    }
+    if let Some(schema) = &tool.output_schema {
+        if let Err(message) = check_schema(schema, "outputSchema") {
+            issues.push(issue(Severity::Error, message));
+        }
+    }
+    if let Some(annotations) = &tool.annotations {
+        if annotations.read_only_hint == Some(true) && annotations.destructive_hint == Some(true) {
+            issues.push(issue(
+                Severity::Warning,
+                "annotations set both readOnlyHint and destructiveHint".to_string(),
+            ));
+        }
+    }
+}
+
+fn check_resource(resource: &Resource, issues: &mut Vec<ValidationIssue>) {
+    let issue = |severity, message: String| ValidationIssue::new(severity, ItemKind::Resource, &resource.uri, message);
+    if !has_scheme(&resource.uri) {
+        issues.push(issue(Severity::Error, "URI has no scheme (expected e.g. 'file://' or 'config://')".to_string()));
+    }
+    if resource.description.as_deref().is_none_or(|d| d.trim().is_empty()) {
+        issues.push(issue(Severity::Warning, "description is empty".to_string()));
+    }
+    if let Some(Err(message)) = resource.annotations.as_ref().map(|a| a.validate()) {
+        issues.push(issue(Severity::Error, format!("annotations.{}", message)));
+    }
+}
+
+fn check_prompt(prompt: &FunctionPrompt, issues: &mut Vec<ValidationIssue>) {
+    let issue = |severity, message: String| ValidationIssue::new(severity, ItemKind::Prompt, &prompt.name, message);
+    if prompt.description.trim().is_empty() {
+        issues.push(issue(Severity::Warning, "description is empty".to_string()));
+    }
+    if let (Some(template), Some(arguments)) = (&prompt.template, &prompt.arguments) {
+        for name in arguments.keys() {
+            if !template.contains(&format!("{{{}}}", name)) {
+                issues.push(issue(
+                    Severity::Warning,
+                    format!("argument '{}' is never used in the template", name),
+                ));
+            }
+        }
+    }
+}
+
+/// 同一类型中标题重复的项各记录一条警告
+fn duplicate_titles(kind: ItemKind, titles: HashMap<String, Vec<String>>, issues: &mut Vec<ValidationIssue>) {
+    for (title, mut items) in titles {
+        if items.len() < 2 {
+            continue;
+        }
+        items.sort();
+        for item in &items {
+            let others: Vec<&str> = items.iter().filter(|other| *other != item).map(|s| s.as_str()).collect();
+            issues.push(ValidationIssue::new(
+                Severity::Warning,
+                kind,
+                item,
+                format!("title '{}' is also used by {}", title, others.join(", ")),
+            ));
+        }
+    }
+}
+
+/// URI是否以`scheme:`开头
+fn has_scheme(uri: &str) -> bool {
+    match uri.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// `required`中没有在`properties`中声明的参数
+fn undeclared_required(schema: &Value) -> Vec<String> {
+    let Some(required) = schema.get("required").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    let properties = schema.get("properties").and_then(Value::as_object);
+    required
+        .iter()
+        .filter_map(Value::as_str)
+        .filter(|name| !properties.is_some_and(|p| p.contains_key(*name)))
+        .map(str::to_string)
+        .collect()
+}
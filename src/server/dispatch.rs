@@ -0,0 +1,1067 @@
+//! 共享的JSON-RPC方法分发
+//!
+//! HTTP和WebSocket传输层都通过这里处理MCP方法调用，保证两种传输的行为一致。
+
+use parking_lot::RwLock;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::protocol::{
+    Implementation, InitializeParams, InitializeResult, JsonRpcRequest, JsonRpcResponse, RequestId, ServerCapabilities,
+    PROTOCOL_VERSION, SUPPORTED_PROTOCOL_VERSIONS,
+};
+use crate::server::admission::Admitted;
+use crate::server::content::{Content, ResultFormat};
+use crate::server::error::McpError;
+use crate::server::features::{ProtocolVersion, VersionGate};
+use crate::server::generation;
+use crate::server::idempotency;
+use crate::server::locale::{self, LanguageTag};
+use crate::server::prompt_streaming;
+use crate::server::prompts::{PromptBody, PromptMessage};
+use crate::server::resources::ResourceBody;
+use crate::server::streaming;
+use crate::server::truncation::{Oversize, TruncationPolicy};
+use crate::server::watchdog;
+use crate::server::context::RequestGuard;
+use crate::server::{Context, RequestContext, RustMCP};
+use crate::settings::Settings;
+
+/// 构造指定协议版本的initialize结果，`_meta.readOnly`报告当前是否处于只读模式
+///
+/// 方法策略拒绝了某个领域的列表方法或使用方法时，不声明该领域的能力。
+/// 嵌入方通过[`RustMCP::with_experimental_capability`]添加的能力出现在`experimental`中。
+pub(crate) fn initialize_result(rustmcp: &RustMCP, protocol_version: &str) -> InitializeResult {
+    let settings = rustmcp.settings();
+    let allowed = |methods: [&str; 2]| methods.iter().all(|method| rustmcp.is_method_allowed(method));
+    let mut capabilities = ServerCapabilities::builder();
+    if allowed(["tools/list", "tools/call"]) {
+        capabilities = capabilities.tools(true);
+    }
+    if allowed(["resources/list", "resources/read"]) {
+        capabilities = capabilities.resources(true, true);
+    }
+    if allowed(["prompts/list", "prompts/get"]) {
+        capabilities = capabilities.prompts(true);
+    }
+    for (key, value) in rustmcp.experimental_capabilities.iter() {
+        capabilities = capabilities.experimental(key.clone(), value.clone());
+    }
+    let capabilities = capabilities.build();
+    let mut meta = serde_json::Map::new();
+    meta.insert("readOnly".to_string(), Value::Bool(rustmcp.is_read_only()));
+    InitializeResult {
+        protocol_version: protocol_version.to_string(),
+        capabilities,
+        server_info: Implementation {
+            name: settings.server_name.clone(),
+            title: None,
+            version: settings.server_version.clone(),
+        },
+        instructions: None,
+        meta: Some(meta),
+    }
+}
+
+/// 生成initialize结果时的注册表状态：工具、资源、提示、方法策略的修订号和只读模式
+pub(crate) type InitializeRevision = (u64, u64, u64, u64, bool);
+
+/// 按协商的协议版本缓存的initialize结果
+///
+/// 每份结果记录生成时的[`InitializeRevision`]，状态变化后下一次initialize重新生成。
+/// 生成期间注册表发生的变化会让记录的状态过期，不会留下陈旧的结果。
+#[derive(Debug, Default)]
+pub(crate) struct InitializeCache {
+    entries: RwLock<HashMap<&'static str, (InitializeRevision, Arc<InitializeResult>)>>,
+}
+
+impl InitializeCache {
+    /// 返回缓存的结果，状态不同或没有缓存时用`build`生成
+    pub(crate) fn get_or_build(
+        &self,
+        protocol_version: &'static str,
+        revision: InitializeRevision,
+        build: impl FnOnce() -> InitializeResult,
+    ) -> Arc<InitializeResult> {
+        if let Some((cached, result)) = self.entries.read().get(protocol_version) {
+            if *cached == revision {
+                return result.clone();
+            }
+        }
+        let result = Arc::new(build());
+        self.entries.write().insert(protocol_version, (revision, result.clone()));
+        result
+    }
+}
+
+/// 协商协议版本：客户端请求受支持的版本时使用该版本，否则使用默认版本并返回警告，
+/// 握手照常完成
+fn negotiate(params: &InitializeParams) -> (&'static str, Vec<String>) {
+    let Some(requested) = params.protocol_version.as_deref() else {
+        return (PROTOCOL_VERSION, Vec::new());
+    };
+    match SUPPORTED_PROTOCOL_VERSIONS.iter().find(|version| **version == requested) {
+        Some(version) => (version, Vec::new()),
+        None => (
+            PROTOCOL_VERSION,
+            vec![format!("requested {}, serving {}", requested, PROTOCOL_VERSION)],
+        ),
+    }
+}
+
+/// 分发JSON-RPC请求（有id的消息）并返回响应
+///
+/// `request_context`由传输层提供，会带入处理函数收到的[`Context`]，
+/// 其中的会话ID或远端IP也用于限流。
+pub(crate) async fn dispatch_request(
+    rustmcp: &RustMCP,
+    request: JsonRpcRequest,
+    request_context: Arc<RequestContext>,
+) -> JsonRpcResponse {
+    let tool = (request.method == "tools/call")
+        .then(|| request.params.as_ref().and_then(|p| p.get("name")).and_then(Value::as_str))
+        .flatten();
+    let mirrored = rustmcp.mirror.select(rustmcp, &request, tool, &request_context);
+    let response = if !rustmcp.captures.should_capture(&request.method, tool) {
+        canonical(rustmcp, dispatch(rustmcp, request, request_context).await)
+    } else {
+        let method = request.method.clone();
+        let tool = tool.map(str::to_string);
+        let params = rustmcp.captures.request_text(request.params.as_ref());
+        let session_id = request_context.session_id.clone();
+        let started = std::time::Instant::now();
+        let response = canonical(rustmcp, dispatch(rustmcp, request, request_context).await);
+        rustmcp.captures.record(
+            &method,
+            tool.as_deref(),
+            session_id.as_deref(),
+            params,
+            &response,
+            started.elapsed(),
+        );
+        response
+    };
+    if let Some(job) = mirrored {
+        job.submit(&response);
+    }
+    response
+}
+
+/// 开启规范化JSON时排列响应中对象的键
+fn canonical(rustmcp: &RustMCP, mut response: JsonRpcResponse) -> JsonRpcResponse {
+    if let Some(result) = &mut response.result {
+        rustmcp.canonicalize(result);
+    }
+    if let Some(data) = response.error.as_mut().and_then(|error| error.data.as_mut()) {
+        rustmcp.canonicalize(data);
+    }
+    response
+}
+
+async fn dispatch(
+    rustmcp: &RustMCP,
+    request: JsonRpcRequest,
+    request_context: Arc<RequestContext>,
+) -> JsonRpcResponse {
+    if let Some(connection) = &request_context.connection {
+        connection.record_request();
+    }
+    // 响应生成后把请求标记为已完成，见[`Context::is_request_active`]
+    let guard = RequestGuard::new();
+    // 计数持续到响应生成，超时后仍在运行的处理函数持有到结束；达到上限时在其他检查之前拒绝，尽量少花费
+    let admitted = match rustmcp.admit(&request.method, &request_context) {
+        Ok(admitted) => Arc::new(admitted),
+        Err(e) => return error(request.id, &e),
+    };
+    if let Err(e) = rustmcp.check_rate_limit(&request.method, &request_context) {
+        return error(request.id, &e);
+    }
+
+    if let Err(e) = check_params_limits(request.params.as_ref(), rustmcp.settings()) {
+        return error(request.id, &e);
+    }
+    if request.method != "initialize"
+        && request.method != "ping"
+        && !request_context.lifecycle.admit(request_context.effective_lifecycle_policy())
+    {
+        return error(request.id, &McpError::NotInitialized(request.method));
+    }
+    if !rustmcp.is_method_allowed(&request.method) {
+        return error(request.id, &McpError::MethodNotFound(request.method));
+    }
+    let strict = rustmcp.settings().strict_params;
+
+    match request.method.as_str() {
+        "initialize" => {
+            request_context.lifecycle.mark_negotiated();
+            let params = InitializeParams::from_params(request.params.as_ref());
+            let (protocol_version, mut warnings) = negotiate(&params);
+            let mut result = InitializeResult::clone(&rustmcp.initialize_result_for(protocol_version));
+            warnings.extend(rustmcp.hooks.run_initialize(&params));
+            for warning in &warnings {
+                log::warn!(
+                    "Initialize warning for session {}: {}",
+                    request_context.session_id.as_deref().unwrap_or("-"),
+                    warning
+                );
+            }
+            if !warnings.is_empty() {
+                result.meta.get_or_insert_with(Default::default).insert("warnings".to_string(), serde_json::json!(warnings));
+            }
+            if let Some(connection) = &request_context.connection {
+                // 记录客户端发送的原始内容，保留类型中没有的字段
+                let raw = |field: &str| request.params.as_ref().and_then(|params| params.get(field));
+                connection.record_initialize(Some(&result.protocol_version), raw("clientInfo"), raw("capabilities"));
+            }
+            success(request.id, serde_json::to_value(result).unwrap_or_default())
+        },
+        "tools/list" => {
+            let generation = rustmcp.registry_generation();
+            let Some(max_bytes) = rustmcp.settings().max_tools_list_bytes else {
+                return list_result(request, &request_context, generation, "tools", || {
+                    rustmcp.mcp_tool_listing().map(|tools| (Value::clone(&tools), None, None))
+                });
+            };
+            let cursor = match request.params.as_ref().and_then(Value::as_object) {
+                Some(params) => match optional_string_param(params, "cursor", strict) {
+                    Ok(cursor) => cursor.map(str::to_string),
+                    Err(e) => return error(request.id, &e),
+                },
+                None => None,
+            };
+            list_result(request, &request_context, generation, "tools", || {
+                let page = rustmcp.tool_manager.list_tools_within(cursor.as_deref(), max_bytes)?;
+                if !page.omitted.is_empty() {
+                    log::warn!(
+                        "tools/list exceeds {} bytes, omitted {} tool(s): {}",
+                        max_bytes,
+                        page.omitted.len(),
+                        page.omitted.join(", ")
+                    );
+                }
+                let truncated = (!page.omitted.is_empty()).then_some(page.omitted.len());
+                Ok((Value::Array(page.tools), page.next_cursor, truncated))
+            })
+        },
+        "resources/list" => {
+            let generation = rustmcp.registry_generation();
+            if rustmcp.resource_manager.has_lazy_loader() {
+                // 分页交给加载器，列表在生成结果之前取得
+                let cursor = match request.params.as_ref().and_then(Value::as_object) {
+                    Some(params) => match optional_string_param(params, "cursor", strict) {
+                        Ok(cursor) => cursor.map(str::to_string),
+                        Err(e) => return error(request.id, &e),
+                    },
+                    None => None,
+                };
+                let page = rustmcp.resource_manager.list_page(cursor.as_deref()).await;
+                return list_result(request, &request_context, generation, "resources", || {
+                    page.map(|(resources, next_cursor)| (resources, next_cursor, None))
+                });
+            }
+            list_result(request, &request_context, generation, "resources", || {
+                rustmcp.mcp_resource_listing().map(|resources| (Value::clone(&resources), None, None))
+            })
+        },
+        "resources/templates/list" => {
+            let mut templates = serde_json::to_value(rustmcp.resource_manager.list_resource_templates()).unwrap_or_default();
+            if let Some(gate) = version_gate(&request_context) {
+                gate.strip_items(&mut templates);
+            }
+            success(request.id, serde_json::json!({
+                "resourceTemplates": templates
+            }))
+        },
+        "prompts/list" => {
+            let generation = rustmcp.registry_generation();
+            let cursor = match request.params.as_ref().and_then(Value::as_object) {
+                Some(params) => match optional_string_param(params, "cursor", strict) {
+                    Ok(cursor) => cursor.map(str::to_string),
+                    Err(e) => return error(request.id, &e),
+                },
+                None => None,
+            };
+            let page_size = rustmcp.settings().prompt_page_size;
+            list_result(request, &request_context, generation, "prompts", || match (cursor, page_size) {
+                (None, None) => rustmcp.mcp_prompt_listing().map(|prompts| (Value::clone(&prompts), None, None)),
+                (cursor, page_size) => rustmcp
+                    .mcp_list_prompts_page(cursor.as_deref(), page_size.unwrap_or(usize::MAX))
+                    .map(|page| (Value::Array(page.prompts), page.next_cursor, None)),
+            })
+        },
+        "tools/call" => {
+            let mut params = match params_object(request.params) {
+                Ok(params) => params,
+                Err(e) => return error(request.id, &e),
+            };
+            // 导入的工具也可以用原始标识符调用
+            let name = match string_param(&params, "name", strict) {
+                Ok(name) => rustmcp.tool_manager.resolve_tool_name(name).into_owned(),
+                Err(e) => return error(request.id, &e),
+            };
+            // 幂等键的指纹在参数被取出之前计算
+            let idempotency = idempotency_key(&params, &request_context)
+                .map(|key| key.map(|key| (key, idempotency::fingerprint(&name, params.get("arguments")))));
+            let arguments_map = match arguments_map(&mut params, strict) {
+                Ok(arguments) => arguments,
+                Err(e) => return error(request.id, &e),
+            };
+            let (timeout_ms, warning) = match request_timeout(&params, rustmcp.settings()) {
+                Ok(timeout) => timeout,
+                Err(e) => return error(request.id, &e),
+            };
+            let ctx = with_timeout(handler_context(&params, &request_context, &guard), timeout_ms);
+
+            let call = async {
+                let work = {
+                    let (rustmcp, name, ctx) = (rustmcp.clone(), name.to_string(), ctx.clone());
+                    async move { rustmcp.mcp_call_tool_with_context(&name, arguments_map, &ctx).await }
+                };
+                let result = within_deadline("tools/call", timeout_ms, &ctx, &admitted, work).await;
+                tool_call_result(rustmcp, &name, result, &ctx)
+            };
+            let result = match idempotency {
+                Ok(Some((key, fingerprint))) => {
+                    let ttl = Duration::from_millis(rustmcp.settings().idempotency_ttl_ms);
+                    rustmcp.idempotency.run(key, fingerprint, ttl, call).await
+                }
+                Ok(None) => call.await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(result) => success(request.id, with_warning(result, warning)),
+                Err(e) => error(request.id, &e),
+            }
+        },
+        "resources/read" => {
+            let params = match params_object(request.params) {
+                Ok(params) => params,
+                Err(e) => return error(request.id, &e),
+            };
+            let uri = match string_param(&params, "uri", strict) {
+                Ok(uri) => uri,
+                Err(e) => return error(request.id, &e),
+            };
+            let mime_type = match optional_string_param(&params, "mimeType", strict) {
+                Ok(mime_type) => mime_type,
+                Err(e) => return error(request.id, &e),
+            };
+            let (timeout_ms, warning) = match request_timeout(&params, rustmcp.settings()) {
+                Ok(timeout) => timeout,
+                Err(e) => return error(request.id, &e),
+            };
+            let ctx = with_timeout(handler_context(&params, &request_context, &guard), timeout_ms);
+
+            let work = {
+                let (rustmcp, uri, mime_type, ctx) = (rustmcp.clone(), uri.to_string(), mime_type.map(str::to_string), ctx.clone());
+                let (request_id, request_context) = (request.id.clone(), request_context.clone());
+                async move {
+                    match rustmcp.open_resource(&uri, mime_type.as_deref(), &ctx).await? {
+                        (ResourceBody::Contents(contents), _) => Ok(serde_json::json!({
+                            "contents": [contents]
+                        })),
+                        (ResourceBody::Stream(stream), mime_type) => {
+                            let chunk_bytes = rustmcp.settings().resource_chunk_bytes;
+                            streaming::read_stream(&uri, &mime_type, stream, chunk_bytes, request_id.as_ref(), &request_context).await
+                        }
+                    }
+                }
+            };
+            match within_deadline("resources/read", timeout_ms, &ctx, &admitted, work).await {
+                Ok(result) => success(request.id, with_warning(with_result_meta(result, &ctx), warning)),
+                Err(e) => error(request.id, &e),
+            }
+        },
+        "prompts/get" => {
+            let mut params = match params_object(request.params) {
+                Ok(params) => params,
+                Err(e) => return error(request.id, &e),
+            };
+            let name = match string_param(&params, "name", strict) {
+                Ok(name) => name.to_string(),
+                Err(e) => return error(request.id, &e),
+            };
+            let arguments_map = match arguments_map(&mut params, strict) {
+                Ok(arguments) => arguments,
+                Err(e) => return error(request.id, &e),
+            };
+            let (timeout_ms, warning) = match request_timeout(&params, rustmcp.settings()) {
+                Ok(timeout) => timeout,
+                Err(e) => return error(request.id, &e),
+            };
+            let ctx = with_timeout(handler_context(&params, &request_context, &guard), timeout_ms);
+
+            // 不支持内容数组的会话收到合并后的单个文本块
+            let legacy = result_format(rustmcp, &ctx) == ResultFormat::Legacy;
+            let gate = version_gate(&request_context);
+            let render = move |message: PromptMessage| {
+                let message = if legacy { message.flattened() } else { message };
+                let mut message = serde_json::to_value(message).unwrap_or_default();
+                if let Some(gate) = gate {
+                    gate.strip_content(&mut message["content"]);
+                }
+                message
+            };
+            let streamed = prompt_streaming::requested(&params);
+
+            let work = {
+                let (rustmcp, ctx) = (rustmcp.clone(), ctx.clone());
+                let (request_id, request_context) = (request.id.clone(), request_context.clone());
+                async move {
+                    match rustmcp.open_prompt(&name, arguments_map, &ctx)? {
+                        PromptBody::Messages(messages) => Ok(serde_json::json!({
+                            "messages": messages.into_iter().map(render).collect::<Vec<_>>()
+                        })),
+                        PromptBody::Stream(stream) => {
+                            let request_id = request_id.as_ref();
+                            prompt_streaming::get_stream(&name, stream, render, streamed, request_id, &request_context, &ctx).await
+                        }
+                    }
+                }
+            };
+            match within_deadline("prompts/get", timeout_ms, &ctx, &admitted, work).await {
+                Ok(result) => success(request.id, with_warning(with_result_meta(result, &ctx), warning)),
+                Err(e) => error(request.id, &e),
+            }
+        },
+        method => {
+            let ctx = match &request.params {
+                Some(Value::Object(params)) => handler_context(params, &request_context, &guard),
+                _ => Context::new().with_request(request_context.clone()).with_request_guard(&guard),
+            };
+            let result = if let Some(handler) = rustmcp.method_handlers.get(method) {
+                handler(request.params, rustmcp.attach_state(&ctx)).await
+            } else if let Some(handler) = rustmcp.method_handlers.extension(method) {
+                handler(rustmcp.clone(), request.params, rustmcp.attach_state(&ctx)).await
+            } else {
+                Err(McpError::MethodNotFound(method.to_string()))
+            };
+            match result {
+                Ok(result) => success(request.id, result),
+                Err(e) => error(request.id, &e),
+            }
+        },
+    }
+}
+
+/// 将params解析为JSON对象
+fn params_object(params: Option<Value>) -> Result<serde_json::Map<String, Value>, McpError> {
+    match params {
+        Some(params) => serde_json::from_value::<serde_json::Map<String, Value>>(params)
+            .map_err(|_| McpError::invalid_params("Invalid params", "params", "object")),
+        None => Err(McpError::invalid_params("Missing params", "params", "object")),
+    }
+}
+
+/// 检查params的嵌套层数和大小
+fn check_params_limits(params: Option<&Value>, settings: &Settings) -> Result<(), McpError> {
+    let params = match params {
+        Some(params) => params,
+        None => return Ok(()),
+    };
+    if nesting_depth(params) > settings.max_params_depth {
+        return Err(McpError::invalid_params(
+            format!("Params are nested too deeply (limit {} levels)", settings.max_params_depth),
+            "params",
+            format!("at most {} levels of nesting", settings.max_params_depth),
+        ));
+    }
+    let mut counter = ByteCounter::default();
+    if serde_json::to_writer(&mut counter, params).is_ok() && counter.0 > settings.max_params_bytes {
+        return Err(McpError::invalid_params(
+            format!("Params are too large ({} bytes, limit {})", counter.0, settings.max_params_bytes),
+            "params",
+            format!("at most {} bytes", settings.max_params_bytes),
+        ));
+    }
+    Ok(())
+}
+
+/// 值的嵌套层数，标量为0，空对象或空数组为1
+fn nesting_depth(value: &Value) -> usize {
+    let mut max_depth = 0;
+    let mut stack = vec![(value, 1)];
+    while let Some((value, depth)) = stack.pop() {
+        match value {
+            Value::Object(map) => stack.extend(map.values().map(|child| (child, depth + 1))),
+            Value::Array(items) => stack.extend(items.iter().map(|child| (child, depth + 1))),
+            _ => continue,
+        }
+        max_depth = max_depth.max(depth);
+    }
+    max_depth
+}
+
+/// 只统计字节数的写入器
+#[derive(Default)]
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 读取字符串参数，没有传入时为空字符串
+///
+/// 严格模式下参数存在但不是字符串时返回错误，宽松模式下当作没有传入。
+fn string_param<'a>(params: &'a serde_json::Map<String, Value>, field: &str, strict: bool) -> Result<&'a str, McpError> {
+    match params.get(field) {
+        None => Ok(""),
+        Some(Value::String(value)) => Ok(value),
+        Some(value) if strict => Err(McpError::invalid_params(
+            format!("Invalid type for '{}': expected string, got {}", field, json_type(value)),
+            field,
+            "string",
+        )),
+        Some(_) => Ok(""),
+    }
+}
+
+/// 读取可选的字符串参数，`null`等同于没有传入，宽松模式下忽略类型错误的值
+fn optional_string_param<'a>(
+    params: &'a serde_json::Map<String, Value>,
+    field: &str,
+    strict: bool,
+) -> Result<Option<&'a str>, McpError> {
+    match params.get(field) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(value) if strict => Err(McpError::invalid_params(
+            format!("Invalid type for '{}': expected string, got {}", field, json_type(value)),
+            field,
+            "string",
+        )),
+        Some(_) => Ok(None),
+    }
+}
+
+/// 从params中取出arguments并转换为HashMap，参数值移动而不复制
+///
+/// `null`等同于没有传入。严格模式下arguments不是对象时返回错误，宽松模式下当作没有传入。
+fn arguments_map(params: &mut serde_json::Map<String, Value>, strict: bool) -> Result<Option<HashMap<String, Value>>, McpError> {
+    match params.remove("arguments") {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Object(args)) => Ok(Some(args.into_iter().collect())),
+        Some(value) if strict => Err(McpError::invalid_params(
+            format!("Invalid type for 'arguments': expected object, got {}", json_type(&value)),
+            "arguments",
+            "object",
+        )),
+        Some(_) => Ok(None),
+    }
+}
+
+/// JSON值的类型名称
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// 调用方的语言偏好：`params._meta.locale`优先，其次是`Accept-Language`请求头
+fn locale_preferences(params: Option<&Value>, request_context: &RequestContext) -> Vec<LanguageTag> {
+    locale::preferences(params.and_then(|params| params.get("_meta")), request_context.header("accept-language"))
+}
+
+/// 列表方法的结果，`_meta.registryGeneration`报告当前的注册表代数
+///
+/// `params._meta.ifGeneration`等于当前代数时返回不含列表的“未修改”结果，参见[`generation`]。
+/// 代数在生成列表之前读取，生成期间注册表发生的变化只会让客户端下一次多取一次列表。
+/// `listing`返回列表、可能的下一页游标和按字节预算省略的项数，游标放在结果的`nextCursor`中，
+/// 省略的项数放在`_meta.truncatedCount`中；列表序列化失败时返回
+/// `-32603`，参见[`serialization`](crate::server::serialization)。
+fn list_result(
+    request: JsonRpcRequest,
+    request_context: &RequestContext,
+    generation: u64,
+    key: &str,
+    listing: impl FnOnce() -> Result<(Value, Option<String>, Option<usize>), McpError>,
+) -> JsonRpcResponse {
+    match generation::if_generation(request.params.as_ref()) {
+        Err(e) => error(request.id, &e),
+        Ok(Some(known)) if known == generation => success(request.id, generation::not_modified_result(generation)),
+        Ok(_) => {
+            let (mut listing, next_cursor, truncated) = match listing() {
+                Ok(listing) => listing,
+                Err(e) => return error(request.id, &e),
+            };
+            locale::localize_listing(&mut listing, &locale_preferences(request.params.as_ref(), request_context));
+            if let Some(gate) = version_gate(request_context) {
+                gate.strip_items(&mut listing);
+            }
+            let mut result = serde_json::Map::new();
+            result.insert(key.to_string(), listing);
+            if let Some(next_cursor) = next_cursor {
+                result.insert("nextCursor".to_string(), Value::String(next_cursor));
+            }
+            let mut meta = serde_json::json!({ "registryGeneration": generation });
+            if let Some(truncated) = truncated {
+                meta["truncatedCount"] = truncated.into();
+            }
+            result.insert("_meta".to_string(), meta);
+            success(request.id, Value::Object(result))
+        }
+    }
+}
+
+/// 读取`_meta.idempotencyKey`，返回包含会话的存储键
+fn idempotency_key(params: &serde_json::Map<String, Value>, request_context: &RequestContext) -> Result<Option<String>, McpError> {
+    match params.get("_meta").and_then(|meta| meta.get("idempotencyKey")) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(key)) if !key.is_empty() => Ok(Some(format!("{}/{}", request_context.caller_key(), key))),
+        Some(_) => Err(McpError::invalid_params(
+            "'_meta.idempotencyKey' must be a non-empty string",
+            "_meta.idempotencyKey",
+            "string",
+        )),
+    }
+}
+
+/// 根据请求参数构造处理函数的上下文，携带请求信息和客户端提供的`_meta`
+fn handler_context(params: &serde_json::Map<String, Value>, request_context: &Arc<RequestContext>, guard: &RequestGuard) -> Context {
+    let ctx = Context::new().with_request(request_context.clone()).with_request_guard(guard);
+    match params.get("_meta") {
+        Some(meta) => ctx.with_request_meta(meta.clone()),
+        None => ctx,
+    }
+}
+
+/// 读取`_meta.timeoutMs`，返回请求的执行时限（毫秒）和可能的警告
+///
+/// 时限取客户端提示和[`Settings::request_timeout_ms`]中较短的一个。提示低于
+/// [`Settings::min_timeout_hint_ms`]时提高到下限，并返回一条放进结果`_meta.warnings`的警告。
+fn request_timeout(params: &serde_json::Map<String, Value>, settings: &Settings) -> Result<(Option<u64>, Option<String>), McpError> {
+    let hint = match params.get("_meta").and_then(|meta| meta.get("timeoutMs")) {
+        None | Some(Value::Null) => None,
+        Some(value) => match value.as_u64() {
+            Some(timeout_ms) if timeout_ms > 0 => Some(timeout_ms),
+            _ => return Err(McpError::invalid_params(
+                "'_meta.timeoutMs' must be a positive integer",
+                "_meta.timeoutMs",
+                "integer",
+            )),
+        },
+    };
+    let floor = settings.min_timeout_hint_ms;
+    let warning = hint
+        .filter(|&timeout_ms| timeout_ms < floor)
+        .map(|timeout_ms| format!("timeoutMs {} is below the minimum of {}ms; using {}ms", timeout_ms, floor, floor));
+    let timeout_ms = match (hint.map(|timeout_ms| timeout_ms.max(floor)), settings.request_timeout_ms) {
+        (Some(hint), Some(limit)) => Some(hint.min(limit)),
+        (hint, limit) => hint.or(limit),
+    };
+    Ok((timeout_ms, warning))
+}
+
+/// 按执行时限设置上下文的截止时间
+fn with_timeout(ctx: Context, timeout_ms: Option<u64>) -> Context {
+    match timeout_ms {
+        Some(timeout_ms) => ctx.with_deadline(Instant::now() + Duration::from_millis(timeout_ms)),
+        None => ctx,
+    }
+}
+
+/// 在上下文的截止时间之前完成`work`，超时后触发取消标记并返回[`McpError::Timeout`]
+///
+/// 工具、资源和提示的处理函数是同步的，有截止时间时放到阻塞线程池执行，超时后可以立即响应；
+/// 处理函数会继续运行到结束，可以检查[`Context::is_cancelled`]提前返回，在此之前仍然计入并发请求数
+/// （持有`admitted`）。没有截止时间时直接执行。
+async fn within_deadline<T, F>(
+    method: &str,
+    timeout_ms: Option<u64>,
+    ctx: &Context,
+    admitted: &Arc<Option<Admitted>>,
+    work: F,
+) -> Result<T, McpError>
+where
+    T: Send + 'static,
+    F: Future<Output = Result<T, McpError>> + Send + 'static,
+{
+    let (Some(timeout_ms), Some(deadline)) = (timeout_ms, ctx.deadline()) else {
+        return work.await;
+    };
+    let handle = tokio::runtime::Handle::current();
+    let admitted = admitted.clone();
+    let task = tokio::task::spawn_blocking(move || {
+        let _admitted = admitted;
+        watchdog::offloaded(|| handle.block_on(work))
+    });
+    match tokio::time::timeout_at(deadline.into(), task).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => Err(McpError::Internal(format!("'{}' failed: {}", method, e))),
+        Err(_) => {
+            ctx.cancellation_token().cancel();
+            Err(McpError::Timeout { method: method.to_string(), timeout_ms })
+        }
+    }
+}
+
+/// 把截止时间的警告追加到结果的`_meta.warnings`
+///
+/// 处理函数把`_meta`设置成了对象以外的值时丢弃警告并记录日志，与
+/// `Context::insert_result_meta`一样不覆盖处理函数设置的值。
+fn with_warning(mut result: Value, warning: Option<String>) -> Value {
+    let (Some(warning), Value::Object(map)) = (warning, &mut result) else {
+        return result;
+    };
+    match map.entry("_meta").or_insert_with(|| serde_json::json!({})) {
+        Value::Object(meta) => {
+            let warnings = meta.entry("warnings").or_insert_with(|| serde_json::json!([]));
+            if !warnings.is_array() {
+                *warnings = serde_json::json!([]);
+            }
+            if let Value::Array(warnings) = warnings {
+                warnings.push(Value::String(warning));
+            }
+        }
+        _ => log::warn!("Result metadata is not an object, dropping warning: {}", warning),
+    }
+    result
+}
+
+/// 把工具调用结果包装为MCP的`tools/call`结果，JSON-RPC和REST共用
+///
+/// - 工具直接返回内容块时原样使用，否则作为文本内容返回；
+/// - 工具声明了`outputSchema`时，返回值同时放在`structuredContent`中；
+/// - 会话使用[`ResultFormat::Legacy`]时，内容合并为一个文本块，不带`structuredContent`；
+/// - 会话协商的版本中还没有的字段和内容块按[`VersionGate`]去掉或换成文本占位；
+/// - 返回值超过大小上限时截断为一个文本块，参见[`truncation`](crate::server::truncation)；
+/// - 工具执行失败作为`isError: true`的结果返回，其余错误原样返回，由调用方转换为协议错误。
+pub(crate) fn tool_call_result(
+    rustmcp: &RustMCP,
+    name: &str,
+    result: Result<Value, McpError>,
+    ctx: &Context,
+) -> Result<Value, McpError> {
+    let mut envelope = match result {
+        Ok(result) => {
+            let tool = rustmcp.tool_manager.get_tool(name);
+            let has_output_schema = tool.as_ref().is_some_and(|tool| tool.output_schema.is_some());
+            let legacy = result_format(rustmcp, ctx) == ResultFormat::Legacy;
+            let limit = tool
+                .as_ref()
+                .and_then(|tool| tool.max_result_bytes())
+                .or(rustmcp.settings().max_result_bytes);
+            if let Some(oversize) = limit.and_then(|limit| Oversize::check(&result, limit)) {
+                if has_output_schema && !legacy && rustmcp.settings().truncation_policy == TruncationPolicy::Fail {
+                    return Ok(with_result_meta(serde_json::json!({
+                        "content": [Content::text(oversize.message(name))],
+                        "isError": true
+                    }), ctx));
+                }
+                return Ok(oversize.mark(with_result_meta(serde_json::json!({
+                    "content": [Content::text(oversize.text(&result))],
+                    "isError": false
+                }), ctx)));
+            }
+            let content = Content::from_tool_value(&result)
+                .unwrap_or_else(|| vec![Content::text(format!("{}", result))]);
+            let mut envelope = if legacy {
+                serde_json::json!({
+                    "content": [Content::flatten(&content)],
+                    "isError": false
+                })
+            } else {
+                serde_json::json!({
+                    "content": content,
+                    "isError": false
+                })
+            };
+            // 会话的版本中还没有structuredContent时由版本过滤去掉
+            if has_output_schema {
+                envelope["structuredContent"] = result;
+            }
+            envelope
+        },
+        Err(McpError::ToolExecution { message, .. }) => serde_json::json!({
+            "content": [Content::text(message)],
+            "isError": true
+        }),
+        Err(e) => return Err(e),
+    };
+    if let Some(gate) = result_gate(rustmcp, ctx) {
+        gate.strip_result(&mut envelope);
+    }
+    Ok(with_result_meta(envelope, ctx))
+}
+
+/// 工具结果使用的版本过滤：强制`Spec`格式时不过滤，强制`Legacy`时按最旧的版本，否则按会话协商的版本
+fn result_gate(rustmcp: &RustMCP, ctx: &Context) -> Option<VersionGate> {
+    match rustmcp.settings().result_format {
+        Some(ResultFormat::Spec) => None,
+        Some(ResultFormat::Legacy) => VersionGate::for_version(ProtocolVersion::OLDEST),
+        None => version_gate(ctx.request()),
+    }
+}
+
+/// 会话协商的协议版本需要的响应过滤，没有协商版本（没有会话或还没有`initialize`）时为`None`，参见[`VersionGate`]
+fn version_gate(request_context: &RequestContext) -> Option<VersionGate> {
+    let connection = request_context.connection.as_ref()?;
+    let version = ProtocolVersion::parse(&connection.protocol_version()?)?;
+    VersionGate::for_version(version)
+}
+
+/// 调用方会话使用的结果格式：服务器设置优先，否则按会话协商的协议版本选择
+fn result_format(rustmcp: &RustMCP, ctx: &Context) -> ResultFormat {
+    rustmcp.settings().result_format.unwrap_or_else(|| {
+        let protocol_version = ctx.request().connection.as_ref().and_then(|c| c.protocol_version());
+        ResultFormat::for_protocol_version(protocol_version.as_deref())
+    })
+}
+
+/// 将处理函数设置的`_meta`原样附加到结果上
+fn with_result_meta(mut result: Value, ctx: &Context) -> Value {
+    if let (Some(meta), Value::Object(map)) = (ctx.result_meta(), &mut result) {
+        map.insert("_meta".to_string(), meta);
+    }
+    result
+}
+
+fn success(id: Option<RequestId>, result: Value) -> JsonRpcResponse {
+    JsonRpcResponse::success(id.unwrap_or_default(), result)
+}
+
+fn error(id: Option<RequestId>, error: &McpError) -> JsonRpcResponse {
+    JsonRpcResponse::from_error(id.unwrap_or_default(), error.to_jsonrpc_error())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::server::test_support::{call, post};
+    use crate::server::{create_app, FunctionPrompt, FunctionResource, FunctionTool, PromptMessage, RustMCP};
+    use crate::settings::Settings;
+    use crate::ResultFormat;
+
+    use super::*;
+
+    /// 把请求的`_meta`原样返回，并设置一份嵌套的结果`_meta`
+    fn meta_app() -> axum::Router {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("echo_meta")
+                .handler_with_context(|ctx, _| {
+                    ctx.set_result_meta(json!({ "cost": 3, "trace": { "spans": [1, 2] } }));
+                    Ok(ctx.request_meta().cloned().unwrap_or_default())
+                })
+                .build(),
+        );
+        rustmcp.add_resource(FunctionResource::from_function_with_context(
+            |ctx| Ok(ctx.request_meta().cloned().unwrap_or_default()),
+            "memo://meta".to_string(),
+            None,
+            None,
+            Some("application/json".to_string()),
+            None,
+            None,
+            None,
+        ));
+        rustmcp.add_prompt(FunctionPrompt::from_function_with_context(
+            |ctx, _| Ok(vec![PromptMessage::user(ctx.request_meta().cloned().unwrap_or_default().to_string())]),
+            "meta".to_string(),
+            None,
+            None,
+            None,
+            None,
+        ));
+        create_app(rustmcp)
+    }
+
+    #[tokio::test]
+    async fn request_meta_reaches_tool_and_result_meta_round_trips() {
+        let app = meta_app();
+        let response = call(&app, "tools/call", json!({ "name": "echo_meta", "_meta": { "traceId": "abc" } })).await;
+        assert_eq!(response["result"]["content"][0]["text"], r#"{"traceId":"abc"}"#, "{}", response);
+        assert_eq!(response["result"]["_meta"], json!({ "cost": 3, "trace": { "spans": [1, 2] } }));
+    }
+
+    #[tokio::test]
+    async fn request_meta_reaches_resources_and_prompts() {
+        let app = meta_app();
+        let response = call(&app, "resources/read", json!({ "uri": "memo://meta", "_meta": { "traceId": "r" } })).await;
+        let text = response["result"]["contents"][0]["text"].as_str().unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(text).unwrap(), json!({ "traceId": "r" }));
+
+        let response = call(&app, "prompts/get", json!({ "name": "meta", "_meta": { "traceId": "p" } })).await;
+        assert_eq!(response["result"]["messages"][0]["content"]["text"], r#"{"traceId":"p"}"#, "{}", response);
+    }
+
+    /// 普通值、内容块、带输出模式和执行失败的工具
+    fn format_app(format: Option<ResultFormat>) -> axum::Router {
+        let settings = match format {
+            Some(format) => Settings::new().with_result_format(format),
+            None => Settings::new(),
+        };
+        let rustmcp = RustMCP::new().with_settings(settings);
+        rustmcp.add_tool(FunctionTool::simple("plain", "Plain value", |_| Ok(json!({ "answer": 42 }))));
+        rustmcp.add_tool(FunctionTool::simple("blocks", "Content blocks", |_| {
+            Ok(json!([{ "type": "text", "text": "first" }, { "type": "image", "data": "aGk=", "mimeType": "image/png" }]))
+        }));
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("structured")
+                .output_schema(json!({ "type": "object", "properties": { "sum": { "type": "number" } } }))
+                .handler(|_| Ok(json!({ "sum": 3 })))
+                .build(),
+        );
+        rustmcp.add_tool(FunctionTool::simple("broken", "Always fails", |_| Err("disk on fire".to_string())));
+        create_app(rustmcp)
+    }
+
+    #[tokio::test]
+    async fn rest_mcp_format_matches_jsonrpc_result() {
+        for format in [None, Some(ResultFormat::Spec), Some(ResultFormat::Legacy)] {
+            let app = format_app(format);
+            for name in ["plain", "blocks", "structured", "broken"] {
+                let expected = call(&app, "tools/call", json!({ "name": name, "arguments": {} })).await;
+                let (status, _, body) = post(&app, "/mcp/call-tool?format=mcp", &[], &json!({ "name": name, "arguments": {} })).await;
+                assert_eq!(status, 200, "{} {:?}", name, format);
+                assert_eq!(body, expected["result"], "{} {:?}", name, format);
+            }
+        }
+
+        // 规范格式下内容块和structuredContent都保留
+        let app = format_app(Some(ResultFormat::Spec));
+        let (_, _, body) = post(&app, "/mcp/call-tool?format=mcp", &[], &json!({ "name": "blocks" })).await;
+        assert_eq!(body["content"][1]["type"], "image");
+        let (_, _, body) = post(&app, "/mcp/call-tool?format=mcp", &[], &json!({ "name": "structured" })).await;
+        assert_eq!(body["structuredContent"], json!({ "sum": 3 }));
+        let (_, _, body) = post(&app, "/mcp/call-tool?format=mcp", &[], &json!({ "name": "broken" })).await;
+        assert_eq!(body, json!({ "content": [{ "type": "text", "text": "disk on fire" }], "isError": true }));
+    }
+
+    #[tokio::test]
+    async fn rest_default_format_is_the_raw_value() {
+        let app = format_app(None);
+        let (status, _, body) = post(&app, "/mcp/call-tool", &[], &json!({ "name": "plain" })).await;
+        assert_eq!((status.as_u16(), body), (200, json!({ "answer": 42 })));
+        let (status, _, body) = post(&app, "/mcp/call-tool?format=raw", &[], &json!({ "name": "broken" })).await;
+        assert_eq!(status, 500);
+        assert!(body.as_str().unwrap().contains("disk on fire"));
+
+        // mcp格式的协议错误带有与JSON-RPC相同的错误对象
+        let expected = call(&app, "tools/call", json!({ "name": "missing" })).await;
+        let (status, _, body) = post(&app, "/mcp/call-tool?format=mcp", &[], &json!({ "name": "missing" })).await;
+        assert_eq!(status, 404);
+        assert_eq!(body["error"], expected["error"]);
+
+        let (status, _, _) = post(&app, "/mcp/call-tool?format=xml", &[], &json!({ "name": "plain" })).await;
+        assert_eq!(status, 400);
+    }
+
+    /// 返回收到的参数
+    fn params_app(settings: Settings) -> (RustMCP, axum::Router) {
+        let rustmcp = RustMCP::new().with_settings(settings);
+        rustmcp.add_tool(FunctionTool::simple("echo", "Echoes", |args| Ok(json!(args))));
+        (rustmcp.clone(), create_app(rustmcp))
+    }
+
+    /// `depth`层嵌套的对象
+    fn nested(depth: usize) -> serde_json::Value {
+        (1..depth).fold(json!({}), |inner, _| json!({ "a": inner }))
+    }
+
+    #[tokio::test]
+    async fn strict_params_name_the_invalid_field() {
+        let (_, app) = params_app(Settings::new());
+        let cases = [
+            ("tools/call", json!({ "name": "echo", "arguments": [1, 2] }), "arguments", "object"),
+            ("tools/call", json!({ "name": 7 }), "name", "string"),
+            ("resources/read", json!({ "uri": { "path": "/" } }), "uri", "string"),
+            ("prompts/get", json!({ "name": "p", "arguments": "x=1" }), "arguments", "object"),
+        ];
+        for (method, params, param, expected) in cases {
+            let response = call(&app, method, params).await;
+            assert_eq!(response["error"]["code"], -32602, "{}", response);
+            assert_eq!(response["error"]["data"]["param"], param, "{}", response);
+            assert_eq!(response["error"]["data"]["expected"], expected, "{}", response);
+        }
+        // null表示没有参数
+        let response = call(&app, "tools/call", json!({ "name": "echo", "arguments": null })).await;
+        assert_eq!(response["result"]["content"][0]["text"], "null", "{}", response);
+    }
+
+    #[tokio::test]
+    async fn lenient_params_ignore_invalid_arguments() {
+        let (_, app) = params_app(Settings::new().with_strict_params(false));
+        let response = call(&app, "tools/call", json!({ "name": "echo", "arguments": [1, 2] })).await;
+        assert_eq!(response["result"]["content"][0]["text"], "null", "{}", response);
+    }
+
+    #[tokio::test]
+    async fn deeply_nested_and_oversized_params_are_rejected() {
+        let (rustmcp, app) = params_app(Settings::new());
+        // 200层的对象超出了HTTP请求体的JSON解析层数，直接构造请求交给分发器
+        let request = JsonRpcRequest::new(1, "tools/call", Some(json!({ "name": "echo", "arguments": { "deep": nested(200) } })));
+        let response = serde_json::to_value(dispatch_request(&rustmcp, request, Arc::default()).await).unwrap();
+        assert_eq!(response["error"]["code"], -32602, "{}", response);
+        assert_eq!(response["error"]["data"]["param"], "params");
+
+        let response = call(&app, "tools/call", json!({ "name": "echo", "arguments": { "deep": nested(40) } })).await;
+        assert_eq!(response["error"]["code"], -32602, "{}", response);
+        let response = call(&app, "tools/call", json!({ "name": "echo", "arguments": { "deep": nested(20) } })).await;
+        assert!(response["result"].is_object(), "{}", response);
+
+        let (_, app) = params_app(Settings::new().with_params_limits(32, 100));
+        let response = call(&app, "tools/call", json!({ "name": "echo", "arguments": { "text": "x".repeat(200) } })).await;
+        assert_eq!(response["error"]["code"], -32602, "{}", response);
+        assert!(response["error"]["message"].as_str().unwrap().contains("too large"));
+    }
+
+    #[test]
+    fn warnings_are_appended_to_result_meta() {
+        let warning = || Some("timeout raised".to_string());
+        assert_eq!(with_warning(json!({}), warning()), json!({ "_meta": { "warnings": ["timeout raised"] } }));
+        assert_eq!(
+            with_warning(json!({ "_meta": { "warnings": ["first"], "page": 2 } }), warning()),
+            json!({ "_meta": { "warnings": ["first", "timeout raised"], "page": 2 } })
+        );
+        assert_eq!(with_warning(json!({ "_meta": { "warnings": "x" } }), warning()), json!({ "_meta": { "warnings": ["timeout raised"] } }));
+        assert_eq!(with_warning(json!({}), None), json!({}));
+    }
+
+    #[test]
+    fn warning_is_dropped_when_result_meta_is_not_an_object() {
+        for meta in [json!("note"), json!(null), json!([1]), json!(42)] {
+            let result = json!({ "messages": [], "_meta": meta.clone() });
+            assert_eq!(with_warning(result.clone(), Some("timeout raised".to_string())), result);
+        }
+    }
+
+    #[tokio::test]
+    async fn non_object_result_meta_with_a_deadline_warning_is_returned() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_prompt(FunctionPrompt::from_function_with_context(
+            |ctx, _| {
+                ctx.set_result_meta(json!("annotated"));
+                Ok(vec![PromptMessage::user("hello")])
+            },
+            "annotated".to_string(),
+            None,
+            None,
+            None,
+            None,
+        ));
+        // 低于下限的时限产生警告
+        let params = json!({ "name": "annotated", "_meta": { "timeoutMs": 1 } });
+        let response = call(&create_app(rustmcp), "prompts/get", params).await;
+        assert_eq!(response["result"]["_meta"], "annotated");
+        assert_eq!(response["result"]["messages"].as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,441 @@
+//! MCP错误类型
+//!
+//! 管理器和分发器使用[`McpError`]描述失败原因，并由它生成带有结构化`data`的JSON-RPC错误。
+
+use serde_json::Value;
+
+use crate::protocol::{
+    JsonRpcError, INTERNAL_ERROR, INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND, RATE_LIMITED, READ_ONLY_MODE,
+    QUOTA_EXCEEDED, REQUEST_TIMEOUT, SERVER_ERROR, SERVER_NOT_INITIALIZED, SERVER_OVERLOADED, SERVER_SHUTTING_DOWN,
+    UNAUTHORIZED,
+};
+
+/// 计算建议时最多比较的候选名称数量，避免注册表很大时拖慢错误路径
+const MAX_SUGGESTION_CANDIDATES: usize = 1000;
+
+/// 参与比较的名称最大长度
+const MAX_SUGGESTION_NAME_LEN: usize = 128;
+
+/// 返回的建议数量上限
+const MAX_SUGGESTIONS: usize = 3;
+
+/// MCP错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum McpError {
+    /// 参数缺失或类型错误
+    InvalidParams {
+        message: String,
+        param: String,
+        expected: String,
+    },
+    /// 方法不存在
+    MethodNotFound(String),
+    /// 方法名为内置方法，不能注册自定义处理函数
+    ReservedMethod(String),
+    /// 工具不存在
+    ToolNotFound {
+        name: String,
+        suggestions: Vec<String>,
+    },
+    /// 工具已被禁用
+    ToolDisabled(String),
+    /// 工具没有绑定实现，参见[`ToolBinding`](crate::server::tools::ToolBinding)
+    ToolNotBound(String),
+    /// 资源不存在
+    ResourceNotFound {
+        uri: String,
+        suggestions: Vec<String>,
+    },
+    /// 提示不存在
+    PromptNotFound {
+        name: String,
+        suggestions: Vec<String>,
+    },
+    /// 资源已被停用
+    ResourceDisabled(String),
+    /// 提示已被停用
+    PromptDisabled(String),
+    /// 工具函数执行失败
+    ToolExecution {
+        name: String,
+        message: String,
+    },
+    /// 资源读取失败
+    ResourceRead {
+        uri: String,
+        message: String,
+    },
+    /// 提示生成失败
+    PromptGet {
+        name: String,
+        message: String,
+    },
+    /// 请求超过限流阈值
+    RateLimited {
+        retry_after_ms: u64,
+    },
+    /// 工具或资源超出用量配额，参见[`quota`](crate::server::quota)
+    QuotaExceeded {
+        kind: String,
+        name: String,
+        limit: u32,
+        window_ms: u64,
+        retry_after_ms: u64,
+        reset_at_ms: u64,
+    },
+    /// 请求超过截止时间
+    Timeout {
+        method: String,
+        timeout_ms: u64,
+    },
+    /// 会话还没有完成`initialize`
+    NotInitialized(String),
+    /// 服务器处于只读模式，工具可能修改状态
+    ReadOnly(String),
+    /// 调用方无权执行请求，通常由调用前钩子返回
+    Unauthorized(String),
+    /// 列表中的工具、资源或提示无法序列化，参见[`serialization`](crate::server::serialization)
+    Serialization {
+        kind: String,
+        name: String,
+        message: String,
+    },
+    /// 服务器正在关闭，参见[`shutdown`](crate::server::shutdown)
+    ShuttingDown {
+        reason: String,
+    },
+    /// 正在处理的请求数达到上限，参见[`admission`](crate::server::admission)
+    Overloaded {
+        scope: String,
+        limit: usize,
+        retry_after_ms: u64,
+    },
+    /// 内部错误
+    Internal(String),
+}
+
+impl McpError {
+    /// 创建参数错误
+    pub fn invalid_params(message: impl Into<String>, param: impl Into<String>, expected: impl Into<String>) -> Self {
+        McpError::InvalidParams {
+            message: message.into(),
+            param: param.into(),
+            expected: expected.into(),
+        }
+    }
+
+    /// JSON-RPC错误码
+    pub fn code(&self) -> i32 {
+        match self {
+            McpError::InvalidParams { .. } => INVALID_PARAMS,
+            McpError::MethodNotFound(_) => METHOD_NOT_FOUND,
+            McpError::ReservedMethod(_) => INVALID_REQUEST,
+            McpError::ToolNotFound { .. } => INVALID_PARAMS,
+            McpError::ToolDisabled(_) => INVALID_PARAMS,
+            McpError::ToolNotBound(_) => INVALID_PARAMS,
+            McpError::ResourceNotFound { .. } => SERVER_ERROR,
+            McpError::PromptNotFound { .. } => SERVER_ERROR,
+            McpError::ResourceDisabled(_) => SERVER_ERROR,
+            McpError::PromptDisabled(_) => SERVER_ERROR,
+            McpError::ToolExecution { .. } => SERVER_ERROR,
+            McpError::ResourceRead { .. } => SERVER_ERROR,
+            McpError::PromptGet { .. } => SERVER_ERROR,
+            McpError::RateLimited { .. } => RATE_LIMITED,
+            McpError::QuotaExceeded { .. } => QUOTA_EXCEEDED,
+            McpError::ShuttingDown { .. } => SERVER_SHUTTING_DOWN,
+            McpError::Overloaded { .. } => SERVER_OVERLOADED,
+            McpError::Timeout { .. } => REQUEST_TIMEOUT,
+            McpError::NotInitialized(_) => SERVER_NOT_INITIALIZED,
+            McpError::ReadOnly(_) => READ_ONLY_MODE,
+            McpError::Unauthorized(_) => UNAUTHORIZED,
+            McpError::Serialization { .. } => INTERNAL_ERROR,
+            McpError::Internal(_) => INTERNAL_ERROR,
+        }
+    }
+
+    /// 稳定的错误类型字符串，用于`data.kind`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            McpError::InvalidParams { .. } => "invalid_params",
+            McpError::MethodNotFound(_) => "method_not_found",
+            McpError::ReservedMethod(_) => "reserved_method",
+            McpError::ToolNotFound { .. } => "tool_not_found",
+            McpError::ToolDisabled(_) => "tool_disabled",
+            McpError::ToolNotBound(_) => "tool_not_bound",
+            McpError::ResourceNotFound { .. } => "resource_not_found",
+            McpError::PromptNotFound { .. } => "prompt_not_found",
+            McpError::ResourceDisabled(_) => "resource_disabled",
+            McpError::PromptDisabled(_) => "prompt_disabled",
+            McpError::ToolExecution { .. } => "tool_execution_failed",
+            McpError::ResourceRead { .. } => "resource_read_failed",
+            McpError::PromptGet { .. } => "prompt_get_failed",
+            McpError::RateLimited { .. } => "rate_limited",
+            McpError::QuotaExceeded { .. } => "quota_exceeded",
+            McpError::ShuttingDown { .. } => "shutting_down",
+            McpError::Overloaded { .. } => "overloaded",
+            McpError::Timeout { .. } => "timeout",
+            McpError::NotInitialized(_) => "not_initialized",
+            McpError::ReadOnly(_) => "read_only",
+            McpError::Unauthorized(_) => "unauthorized",
+            McpError::Serialization { .. } => "serialization_failed",
+            McpError::Internal(_) => "internal",
+        }
+    }
+
+    /// 错误的结构化数据
+    pub fn data(&self) -> Value {
+        let mut data = match self {
+            McpError::InvalidParams { param, expected, .. } => serde_json::json!({
+                "param": param,
+                "expected": expected
+            }),
+            McpError::MethodNotFound(method) | McpError::ReservedMethod(method) | McpError::NotInitialized(method) => serde_json::json!({
+                "method": method
+            }),
+            McpError::ToolNotFound { name, suggestions } | McpError::PromptNotFound { name, suggestions } => serde_json::json!({
+                "name": name,
+                "suggestions": suggestions
+            }),
+            McpError::ResourceNotFound { uri, suggestions } => serde_json::json!({
+                "uri": uri,
+                "suggestions": suggestions
+            }),
+            McpError::ToolDisabled(name) | McpError::PromptDisabled(name) | McpError::ReadOnly(name) => serde_json::json!({
+                "name": name
+            }),
+            McpError::ResourceDisabled(uri) => serde_json::json!({
+                "uri": uri
+            }),
+            McpError::ToolNotBound(name) => serde_json::json!({
+                "name": name,
+                "binding": "unbound"
+            }),
+            McpError::ToolExecution { name, .. } | McpError::PromptGet { name, .. } => serde_json::json!({
+                "name": name
+            }),
+            McpError::ResourceRead { uri, .. } => serde_json::json!({
+                "uri": uri
+            }),
+            McpError::RateLimited { retry_after_ms } => serde_json::json!({
+                "retryAfterMs": retry_after_ms
+            }),
+            McpError::QuotaExceeded { kind, name, limit, window_ms, retry_after_ms, reset_at_ms } => serde_json::json!({
+                "itemKind": kind,
+                "name": name,
+                "limit": limit,
+                "windowMs": window_ms,
+                "retryAfterMs": retry_after_ms,
+                "resetAtMs": reset_at_ms
+            }),
+            McpError::Timeout { method, timeout_ms } => serde_json::json!({
+                "method": method,
+                "timeoutMs": timeout_ms
+            }),
+            McpError::Serialization { kind, name, .. } => serde_json::json!({
+                "itemKind": kind,
+                "name": name
+            }),
+            McpError::ShuttingDown { reason } => serde_json::json!({
+                "reason": reason
+            }),
+            McpError::Overloaded { scope, limit, retry_after_ms } => serde_json::json!({
+                "scope": scope,
+                "limit": limit,
+                "retryAfterMs": retry_after_ms
+            }),
+            McpError::Unauthorized(_) | McpError::Internal(_) => serde_json::json!({}),
+        };
+        data["kind"] = Value::String(self.kind().to_string());
+        data
+    }
+
+    /// 转换为JSON-RPC错误
+    pub fn to_jsonrpc_error(&self) -> JsonRpcError {
+        JsonRpcError::new(self.code(), self.to_string()).with_data(self.data())
+    }
+}
+
+impl std::fmt::Display for McpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            McpError::InvalidParams { message, .. } => write!(f, "{}", message),
+            McpError::MethodNotFound(_) => write!(f, "Method not found"),
+            McpError::ReservedMethod(method) => write!(f, "Method '{}' is reserved by MCP and cannot be used", method),
+            McpError::ToolNotFound { name, .. } => write!(f, "Tool '{}' not found", name),
+            McpError::ToolDisabled(name) => write!(f, "Tool '{}' is disabled", name),
+            McpError::ToolNotBound(name) => write!(f, "Tool '{}' has no implementation bound", name),
+            McpError::ResourceNotFound { uri, .. } => write!(f, "Resource not found: {}", uri),
+            McpError::PromptNotFound { name, .. } => write!(f, "Prompt not found: {}", name),
+            McpError::ResourceDisabled(uri) => write!(f, "Resource '{}' is disabled", uri),
+            McpError::PromptDisabled(name) => write!(f, "Prompt '{}' is disabled", name),
+            McpError::ToolExecution { message, .. }
+            | McpError::ResourceRead { message, .. }
+            | McpError::PromptGet { message, .. } => write!(f, "{}", message),
+            McpError::RateLimited { .. } => write!(f, "Rate limited"),
+            McpError::QuotaExceeded { kind, name, limit, window_ms, .. } => {
+                write!(f, "Quota exceeded: {} '{}' allows {} calls per {}ms", kind, name, limit, window_ms)
+            }
+            McpError::Timeout { method, timeout_ms } => write!(f, "'{}' timed out after {}ms", method, timeout_ms),
+            McpError::NotInitialized(method) => {
+                write!(f, "Server not initialized: '{}' received before initialize", method)
+            }
+            McpError::ReadOnly(name) => {
+                write!(f, "Server is in read-only mode: tool '{}' may modify state", name)
+            }
+            McpError::Unauthorized(message) => write!(f, "Unauthorized: {}", message),
+            McpError::Serialization { kind, name, message } => {
+                write!(f, "Failed to serialize {} '{}': {}", kind, name, message)
+            }
+            McpError::ShuttingDown { reason } => write!(f, "Server shutting down: {}", reason),
+            McpError::Overloaded { scope, limit, .. } => {
+                write!(f, "Server overloaded: {} limit of {} in-flight requests reached", scope, limit)
+            }
+            McpError::Internal(message) => write!(f, "Internal error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for McpError {}
+
+impl From<McpError> for String {
+    fn from(e: McpError) -> Self {
+        e.to_string()
+    }
+}
+
+/// 在已注册名称中查找与请求名称相近的建议
+///
+/// 使用Levenshtein距离，最多比较[`MAX_SUGGESTION_CANDIDATES`]个候选，
+/// 返回距离不超过名称长度三分之一（至少为2）的名称，按距离排序。
+pub fn suggest<'a, I>(requested: &str, candidates: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    if requested.is_empty() || requested.chars().count() > MAX_SUGGESTION_NAME_LEN {
+        return Vec::new();
+    }
+    let requested_len = requested.chars().count();
+    let max_distance = (requested_len / 3).max(2);
+
+    let mut matches: Vec<(usize, &String)> = candidates
+        .into_iter()
+        .take(MAX_SUGGESTION_CANDIDATES)
+        .filter(|candidate| {
+            let len = candidate.chars().count();
+            len <= MAX_SUGGESTION_NAME_LEN && len.abs_diff(requested_len) <= max_distance
+        })
+        .filter_map(|candidate| {
+            let distance = levenshtein(requested, candidate);
+            (distance <= max_distance).then_some((distance, candidate))
+        })
+        .collect();
+    matches.sort();
+    matches.into_iter().take(MAX_SUGGESTIONS).map(|(_, name)| name.clone()).collect()
+}
+
+/// 计算两个字符串的Levenshtein编辑距离
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+    let mut current = vec![0; b_chars.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b_chars.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current[j + 1] = (previous[j + 1] + 1).min(current[j] + 1).min(previous[j] + cost);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b_chars.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use crate::server::test_support::call;
+    use crate::server::{create_app, FunctionPrompt, FunctionResource, FunctionTool, RustMCP};
+
+    fn app() -> axum::Router {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::simple("search_docs", "Searches", |_| Ok(json!([]))));
+        rustmcp.add_tool(FunctionTool::simple("fail", "Always fails", |_| Err("backend down".to_string())));
+        rustmcp.add_resource(FunctionResource::simple("memo://notes", || Ok(json!("notes"))));
+        rustmcp.add_resource(FunctionResource::simple("memo://broken", || Err("disk error".to_string())));
+        rustmcp.add_prompt(FunctionPrompt::simple("summarize", |_| Ok(Vec::new())));
+        rustmcp.add_prompt(FunctionPrompt::simple("broken", |_| Err("template error".to_string())));
+        create_app(rustmcp)
+    }
+
+    #[test]
+    fn every_error_carries_its_kind() {
+        let errors = [
+            McpError::MethodNotFound("x".to_string()),
+            McpError::ToolDisabled("t".to_string()),
+            McpError::RateLimited { retry_after_ms: 5 },
+            McpError::Unauthorized("no".to_string()),
+            McpError::Internal("oops".to_string()),
+        ];
+        for error in errors {
+            let jsonrpc = error.to_jsonrpc_error();
+            assert_eq!(jsonrpc.data.unwrap()["kind"], error.kind());
+        }
+    }
+
+    #[test]
+    fn suggestions_are_ranked_and_capped() {
+        let names: Vec<String> = ["search_docs", "search_doc", "search_logs", "search_dogs", "delete_all"].map(String::from).into();
+        // 距离相同时按名称排序
+        assert_eq!(suggest("search_docz", &names), ["search_doc", "search_docs", "search_dogs"]);
+        assert!(suggest("completely_different", &names).is_empty());
+        assert!(suggest("", &names).is_empty());
+        assert!(suggest(&"x".repeat(MAX_SUGGESTION_NAME_LEN + 1), &names).is_empty());
+    }
+
+    #[tokio::test]
+    async fn invalid_params_name_the_param_and_expected_type() {
+        let response = call(&app(), "tools/call", json!({ "name": 42 })).await;
+        assert_eq!(response["error"]["code"], -32602);
+        assert_eq!(response["error"]["data"], json!({ "param": "name", "expected": "string", "kind": "invalid_params" }));
+    }
+
+    #[tokio::test]
+    async fn typoed_tool_name_is_suggested() {
+        let response = call(&app(), "tools/call", json!({ "name": "serch_docs" })).await;
+        assert_eq!(
+            response["error"]["data"],
+            json!({ "name": "serch_docs", "suggestions": ["search_docs"], "kind": "tool_not_found" })
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_resources_and_prompts_carry_suggestions() {
+        let app = app();
+        let response = call(&app, "resources/read", json!({ "uri": "memo://note" })).await;
+        assert_eq!(
+            response["error"]["data"],
+            json!({ "uri": "memo://note", "suggestions": ["memo://notes"], "kind": "resource_not_found" })
+        );
+        let response = call(&app, "prompts/get", json!({ "name": "sumarize" })).await;
+        assert_eq!(
+            response["error"]["data"],
+            json!({ "name": "sumarize", "suggestions": ["summarize"], "kind": "prompt_not_found" })
+        );
+    }
+
+    #[tokio::test]
+    async fn execution_failures_carry_a_stable_kind() {
+        let app = app();
+        let response = call(&app, "resources/read", json!({ "uri": "memo://broken" })).await;
+        assert_eq!(response["error"]["data"], json!({ "uri": "memo://broken", "kind": "resource_read_failed" }));
+        let response = call(&app, "prompts/get", json!({ "name": "broken" })).await;
+        assert_eq!(response["error"]["data"], json!({ "name": "broken", "kind": "prompt_get_failed" }));
+        // 工具执行失败作为isError结果返回
+        let response = call(&app, "tools/call", json!({ "name": "fail" })).await;
+        assert_eq!(response["result"]["isError"], true);
+        let response = call(&app, "no/such/method", json!({})).await;
+        assert_eq!(response["error"]["code"], -32601);
+        assert_eq!(response["error"]["data"], json!({ "method": "no/such/method", "kind": "method_not_found" }));
+    }
+}
@@ -0,0 +1,150 @@
+//! 工具错误文本的整理
+//!
+//! 工具函数常把`format!("{:?}", err)`得到的整段错误（带完整URL和响应体的请求错误等）直接放进`Err(String)`。
+//! 工具执行失败时，错误文本在进入结果的内容块、调用后钩子和审计日志之前先经过整理：
+//! - 按[`Settings::with_error_scrubber`](crate::Settings::with_error_scrubber)添加的正则表达式把匹配的片段替换为`[REDACTED]`；
+//! - 去掉ASCII控制字符，保留换行和制表符；
+//! - 超过[`Settings::with_max_error_bytes`](crate::Settings::with_max_error_bytes)（默认4 KB，为0时不限制）时
+//!   在UTF-8字符边界处截断并加上`…`，结果的`_meta`中带有`errorTruncated: true`和`originalErrorSize`（原始字节数）。
+//!
+//! 先替换再截断，截断不会留下半个被替换的片段。无法编译的正则表达式在启动校验时报告为错误，
+//! 参见[`RustMCP::check_startup`](crate::RustMCP::check_startup)。
+//!
+//! ```rust
+//! use rustmcp::{RustMCP, Settings};
+//!
+//! // 隐去请求头中的令牌，错误文本最多1 KB
+//! let settings = Settings::new()
+//!     .with_max_error_bytes(1024)
+//!     .with_error_scrubber(r"(?i)authorization: bearer \S+");
+//! let rustmcp = RustMCP::new().with_settings(settings);
+//! assert!(rustmcp.check_startup().is_ok());
+//! ```
+
+use regex::Regex;
+
+/// 截断后追加的省略号
+const ELLIPSIS: &str = "…";
+
+/// 替换匹配片段使用的文本
+const REDACTED: &str = "[REDACTED]";
+
+/// 整理后的错误文本，截断时带有原始字节数
+pub(crate) struct Shaped {
+    pub(crate) text: String,
+    pub(crate) original_size: Option<usize>,
+}
+
+/// 按设置整理工具错误文本
+#[derive(Debug, Default)]
+pub(crate) struct ErrorShaper {
+    max_bytes: usize,
+    scrubbers: Vec<Regex>,
+    /// 无法编译的表达式
+    invalid: Vec<String>,
+}
+
+impl ErrorShaper {
+    /// `max_bytes`为0时不限制长度，无法编译的表达式被跳过并记录
+    pub(crate) fn new(max_bytes: usize, patterns: &[String]) -> Self {
+        let mut scrubbers = Vec::new();
+        let mut invalid = Vec::new();
+        for pattern in patterns {
+            match Regex::new(pattern) {
+                Ok(regex) => scrubbers.push(regex),
+                Err(e) => invalid.push(format!("invalid pattern '{}': {}", pattern, e)),
+            }
+        }
+        Self { max_bytes, scrubbers, invalid }
+    }
+
+    /// 无法编译的表达式
+    pub(crate) fn invalid(&self) -> &[String] {
+        &self.invalid
+    }
+
+    /// 替换匹配的片段、去掉控制字符并截断
+    pub(crate) fn shape(&self, message: String) -> Shaped {
+        let original_size = message.len();
+        let mut text = message;
+        for scrubber in &self.scrubbers {
+            if let std::borrow::Cow::Owned(scrubbed) = scrubber.replace_all(&text, REDACTED) {
+                text = scrubbed;
+            }
+        }
+        if text.chars().any(is_stripped) {
+            text.retain(|c| !is_stripped(c));
+        }
+        if self.max_bytes == 0 || text.len() <= self.max_bytes {
+            return Shaped { text, original_size: None };
+        }
+        let mut end = self.max_bytes;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str(ELLIPSIS);
+        Shaped { text, original_size: Some(original_size) }
+    }
+}
+
+/// 需要去掉的ASCII控制字符，换行和制表符保留
+fn is_stripped(c: char) -> bool {
+    c.is_ascii_control() && c != '\n' && c != '\t'
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::server::test_support::{request, stdio};
+    use crate::{FunctionTool, RustMCP, Settings};
+
+    async fn call(rustmcp: &RustMCP, name: &str) -> Value {
+        let mut output = stdio(rustmcp, &[request(0, "initialize", json!({})), request(1, "tools/call", json!({ "name": name }))]).await;
+        output.pop().unwrap()["result"].take()
+    }
+
+    #[tokio::test]
+    async fn tool_errors_are_scrubbed_cleaned_and_truncated() {
+        let settings = Settings::new()
+            .with_max_error_bytes(10)
+            .with_error_scrubber(r"(?i)authorization: bearer \S+");
+        let rustmcp = RustMCP::new().with_settings(settings);
+        let failing = |name: &'static str, message: &'static str| FunctionTool::simple(name, "", move |_| Err(message.to_string()));
+        rustmcp.add_tool(failing("short", "not found"));
+        // 每个汉字3个字节，第4个字会越过10字节的上限
+        rustmcp.add_tool(failing("wide", "错误错误错误"));
+        rustmcp.add_tool(failing("exact", "错误错x"));
+        rustmcp.add_tool(failing("leaky", "Authorization: Bearer s3cr3t"));
+        rustmcp.add_tool(failing("colored", "\u{1b}[1mbad\u{7}\tx\n"));
+
+        // 没有超过上限的错误不变
+        let result = call(&rustmcp, "short").await;
+        assert_eq!(result["content"][0]["text"], "not found");
+        assert_eq!(result["isError"], true);
+        assert!(result.get("_meta").is_none());
+
+        // 在字符边界处截断
+        let result = call(&rustmcp, "wide").await;
+        assert_eq!(result["content"][0]["text"], "错误错…");
+        assert_eq!(result["_meta"], json!({ "errorTruncated": true, "originalErrorSize": 18 }));
+        // 恰好10个字节时不截断
+        assert_eq!(call(&rustmcp, "exact").await["content"][0]["text"], "错误错x");
+
+        // 先替换再截断
+        let result = call(&rustmcp, "leaky").await;
+        assert_eq!(result["content"][0]["text"], "[REDACTED]");
+        assert!(result.get("_meta").is_none());
+
+        // 去掉控制字符，保留换行和制表符
+        assert_eq!(call(&rustmcp, "colored").await["content"][0]["text"], "[1mbad\tx\n");
+    }
+
+    #[test]
+    fn invalid_scrubbers_are_reported_at_startup() {
+        let broken = RustMCP::new().with_settings(Settings::new().with_error_scrubber("(unclosed"));
+        let errors = broken.check_startup().unwrap_err();
+        assert!(errors[0].to_string().contains("error_scrubbers"));
+    }
+}
@@ -0,0 +1,373 @@
+//! 协议版本和功能检测
+//!
+//! 工具可以按会话协商的协议版本和客户端声明的能力决定返回什么：
+//! - [`Context::protocol_version`](crate::Context::protocol_version)返回`initialize`协商的[`ProtocolVersion`]，
+//!   版本之间可以用`>=`比较；
+//! - [`Context::supports`](crate::Context::supports)判断当前会话是否支持某个[`Feature`]。内容类功能只看协议版本，
+//!   `Elicitation`和`Sampling`还要求客户端在`initialize`的`capabilities`中声明。
+//!
+//! 没有会话（无会话ID的HTTP请求、直接调用）或还没有完成`initialize`时按最旧的版本回答，
+//! 客户端能力视为空，即最保守的结果。
+//!
+//! 服务器构造响应时也按协商的版本过滤：[`VersionGate`]按[`Feature::min_version`]省略会话的版本中还没有的
+//! 字段，把还没有的内容块换成文本占位，旧版本的客户端不会收到不认识的结构。过滤作用于
+//! `tools/list`、`resources/list`、`resources/templates/list`、`prompts/list`、`tools/call`和`prompts/get`，
+//! 只针对协商了版本的会话；[`Settings::result_format`](crate::Settings::result_format)强制的格式对`tools/call`优先。
+//!
+//! ```rust
+//! use rustmcp::{Feature, FunctionTool, ProtocolVersion};
+//! use serde_json::json;
+//!
+//! let tool = FunctionTool::builder()
+//!     .name("clip")
+//!     .handler_with_context(|ctx, _| {
+//!         let format = if ctx.protocol_version() >= ProtocolVersion::V2025_06_18 { "new" } else { "old" };
+//!         Ok(json!({ "format": format, "elicitation": ctx.supports(Feature::Elicitation) }))
+//!     })
+//!     .build();
+//! ```
+
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+use crate::server::content::Content;
+
+/// 服务器支持的协议版本，按发布顺序排列，可以比较新旧
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtocolVersion {
+    /// `2024-11-05`
+    V2024_11_05,
+    /// `2025-06-18`
+    V2025_06_18,
+}
+
+impl ProtocolVersion {
+    /// 最旧的版本，没有协商时使用
+    pub const OLDEST: ProtocolVersion = ProtocolVersion::V2024_11_05;
+
+    /// 最新的版本
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V2025_06_18;
+
+    /// 解析版本字符串，不支持的版本返回`None`
+    pub fn parse(version: &str) -> Option<Self> {
+        match version {
+            "2024-11-05" => Some(ProtocolVersion::V2024_11_05),
+            "2025-06-18" => Some(ProtocolVersion::V2025_06_18),
+            _ => None,
+        }
+    }
+
+    /// 版本字符串
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProtocolVersion::V2024_11_05 => "2024-11-05",
+            ProtocolVersion::V2025_06_18 => "2025-06-18",
+        }
+    }
+}
+
+impl std::fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for ProtocolVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// 随协议版本或客户端能力变化的功能
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// 工具结果中的`structuredContent`
+    StructuredContent,
+    /// 向用户请求输入（`elicitation/create`），需要客户端声明`elicitation`能力
+    Elicitation,
+    /// 请求客户端调用模型（`sampling/createMessage`），需要客户端声明`sampling`能力
+    Sampling,
+    /// `resource_link`内容块
+    ResourceLinks,
+    /// `audio`内容块
+    AudioContent,
+    /// 工具、资源和提示的`title`字段
+    Titles,
+}
+
+impl Feature {
+    /// 所有功能
+    pub const ALL: [Feature; 6] = [
+        Feature::StructuredContent,
+        Feature::Elicitation,
+        Feature::Sampling,
+        Feature::ResourceLinks,
+        Feature::AudioContent,
+        Feature::Titles,
+    ];
+
+    /// 引入该功能的最早版本
+    pub fn min_version(&self) -> ProtocolVersion {
+        match self {
+            Feature::Sampling => ProtocolVersion::V2024_11_05,
+            Feature::StructuredContent
+            | Feature::Elicitation
+            | Feature::ResourceLinks
+            | Feature::AudioContent
+            | Feature::Titles => ProtocolVersion::V2025_06_18,
+        }
+    }
+
+    /// 客户端需要声明的能力名称
+    pub fn client_capability(&self) -> Option<&'static str> {
+        match self {
+            Feature::Elicitation => Some("elicitation"),
+            Feature::Sampling => Some("sampling"),
+            _ => None,
+        }
+    }
+
+    /// 在给定的协议版本和客户端能力下是否支持
+    pub fn is_supported(&self, version: ProtocolVersion, capabilities: Option<&Value>) -> bool {
+        if version < self.min_version() {
+            return false;
+        }
+        match self.client_capability() {
+            Some(name) => capabilities.and_then(|c| c.get(name)).is_some_and(|c| !c.is_null()),
+            None => true,
+        }
+    }
+}
+
+/// 响应中随功能引入的结构
+#[derive(Debug, Clone, Copy)]
+enum Gated {
+    /// 工具、资源、提示等列表项的字段
+    ItemField(&'static str),
+    /// `tools/call`结果的字段
+    ResultField(&'static str),
+    /// 内容块的`type`
+    ContentType(&'static str),
+}
+
+/// 功能与它在响应中引入的结构，需要的版本由[`Feature::min_version`]决定
+const GATED: [(Feature, Gated); 5] = [
+    (Feature::Titles, Gated::ItemField("title")),
+    (Feature::StructuredContent, Gated::ItemField("outputSchema")),
+    (Feature::StructuredContent, Gated::ResultField("structuredContent")),
+    (Feature::AudioContent, Gated::ContentType("audio")),
+    (Feature::ResourceLinks, Gated::ContentType("resource_link")),
+];
+
+/// 按协商的协议版本过滤响应
+///
+/// 列表项去掉版本中还没有的字段（例如`title`、`outputSchema`），`tools/call`结果去掉`structuredContent`，
+/// 内容块中的`audio`和`resource_link`换成与[`Content::flatten`]相同的文本占位。
+/// 服务器对协商了版本的会话自动应用；没有会话的请求收到完整的响应。
+///
+/// ```rust
+/// use rustmcp::{Feature, ProtocolVersion};
+/// use rustmcp::server::features::VersionGate;
+/// use serde_json::json;
+///
+/// // 最新版本不需要过滤
+/// assert!(VersionGate::for_version(ProtocolVersion::LATEST).is_none());
+/// let gate = VersionGate::for_version(ProtocolVersion::V2024_11_05).unwrap();
+/// assert!(!gate.allows(Feature::Titles));
+/// assert!(gate.allows(Feature::Sampling));
+///
+/// let mut tool = json!({ "name": "forecast", "title": "Forecast", "inputSchema": {}, "outputSchema": {} });
+/// gate.strip_item(&mut tool);
+/// assert_eq!(tool, json!({ "name": "forecast", "inputSchema": {} }));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionGate {
+    version: ProtocolVersion,
+}
+
+impl VersionGate {
+    /// 协商了`version`的会话使用的过滤，没有需要过滤的结构时返回`None`
+    pub fn for_version(version: ProtocolVersion) -> Option<Self> {
+        GATED
+            .iter()
+            .any(|(feature, _)| version < feature.min_version())
+            .then_some(Self { version })
+    }
+
+    /// 版本中是否已经有这个功能
+    pub fn allows(&self, feature: Feature) -> bool {
+        self.version >= feature.min_version()
+    }
+
+    /// 去掉列表项中版本还没有的字段
+    pub fn strip_item(&self, item: &mut Value) {
+        if let Value::Object(map) = item {
+            for field in self.omitted(|gated| match gated {
+                Gated::ItemField(field) => Some(field),
+                _ => None,
+            }) {
+                map.remove(field);
+            }
+        }
+    }
+
+    /// 对列表（数组）中的每一项调用[`VersionGate::strip_item`]
+    pub fn strip_items(&self, items: &mut Value) {
+        if let Value::Array(items) = items {
+            items.iter_mut().for_each(|item| self.strip_item(item));
+        }
+    }
+
+    /// 去掉`tools/call`结果中版本还没有的字段，并处理`content`中的内容块
+    pub fn strip_result(&self, result: &mut Value) {
+        if let Value::Object(map) = result {
+            for field in self.omitted(|gated| match gated {
+                Gated::ResultField(field) => Some(field),
+                _ => None,
+            }) {
+                map.remove(field);
+            }
+            if let Some(content) = map.get_mut("content") {
+                self.strip_content(content);
+            }
+        }
+    }
+
+    /// 把版本还没有的内容块换成文本占位，`content`可以是单个内容块或数组
+    pub fn strip_content(&self, content: &mut Value) {
+        match content {
+            Value::Array(blocks) => blocks.iter_mut().for_each(|block| self.strip_block(block)),
+            block => self.strip_block(block),
+        }
+    }
+
+    fn strip_block(&self, block: &mut Value) {
+        let Some(kind) = block.get("type").and_then(Value::as_str) else {
+            return;
+        };
+        let gated = self
+            .omitted(|gated| match gated {
+                Gated::ContentType(kind) => Some(kind),
+                _ => None,
+            })
+            .any(|omitted| omitted == kind);
+        if gated {
+            if let Ok(parsed) = serde_json::from_value::<Content>(block.clone()) {
+                *block = Content::flatten(&[parsed]).into();
+            }
+        }
+    }
+
+    /// 版本中还没有的某一类结构
+    fn omitted<'a>(&'a self, select: impl Fn(Gated) -> Option<&'static str> + 'a) -> impl Iterator<Item = &'static str> + 'a {
+        GATED
+            .iter()
+            .filter(|(feature, _)| !self.allows(*feature))
+            .filter_map(move |(_, gated)| select(*gated))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::server::test_support::{initialize, post, request};
+    use crate::{create_app, Content, FunctionResource, FunctionTool, RustMCP};
+
+    #[tokio::test]
+    async fn tools_see_the_negotiated_version_and_capabilities() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::builder().name("clip").handler_with_context(|ctx, _| {
+            let format = if ctx.protocol_version() >= ProtocolVersion::V2025_06_18 { "new" } else { "old" };
+            let features: Vec<String> = Feature::ALL
+                .iter()
+                .filter(|feature| ctx.supports(**feature))
+                .map(|feature| format!("{:?}", feature))
+                .collect();
+            let text = format!("{} {} [{}]", ctx.protocol_version(), format, features.join(","));
+            Ok(json!([Content::text(text)]))
+        }).build());
+        let app = create_app(rustmcp);
+
+        // 旧版本即使声明了elicitation也不支持
+        let old = initialize(&app, json!({ "protocolVersion": "2024-11-05", "capabilities": { "sampling": {}, "elicitation": {} } })).await;
+        let new = initialize(&app, json!({ "protocolVersion": "2025-06-18", "capabilities": { "elicitation": {} } })).await;
+        let call = request(2, "tools/call", json!({ "name": "clip" }));
+        let text = |response: Value| response["result"]["content"][0]["text"].as_str().unwrap().to_string();
+
+        let old = post(&app, "/mcp", &[("mcp-session-id", &old)], &call).await.2;
+        assert_eq!(text(old), "2024-11-05 old [Sampling]");
+        let new = post(&app, "/mcp", &[("mcp-session-id", &new)], &call).await.2;
+        assert_eq!(text(new), "2025-06-18 new [StructuredContent,Elicitation,ResourceLinks,AudioContent,Titles]");
+        // 没有会话时最保守
+        assert_eq!(text(post(&app, "/mcp", &[], &call).await.2), "2024-11-05 old []");
+    }
+
+    #[tokio::test]
+    async fn sessions_see_structures_from_their_version() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_resource(FunctionResource::simple("file:///notes.txt", || Ok(json!("notes"))).with_title("Notes"));
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("speak")
+                .title("Speak")
+                .output_schema(json!({ "type": "array" }))
+                .handler(|_| Ok(json!([Content::text("Hello"), { "type": "audio", "data": "UklGRg==", "mimeType": "audio/wav" }])))
+                .build(),
+        );
+        let app = create_app(rustmcp);
+        let old = initialize(&app, json!({ "protocolVersion": "2024-11-05" })).await;
+        let new = initialize(&app, json!({ "protocolVersion": "2025-06-18" })).await;
+        let result = |session: &str, method: &'static str, params: Value| {
+            let (app, session) = (app.clone(), session.to_string());
+            async move { post(&app, "/mcp", &[("mcp-session-id", &session)], &request(2, method, params)).await.2["result"].take() }
+        };
+
+        // 2025-06-18：完整的结构
+        assert_eq!(result(&new, "resources/list", json!({})).await["resources"][0]["title"], "Notes");
+        let tools = result(&new, "tools/list", json!({})).await;
+        assert_eq!(tools["tools"][0]["title"], "Speak");
+        assert!(tools["tools"][0].get("outputSchema").is_some());
+        let called = result(&new, "tools/call", json!({ "name": "speak" })).await;
+        assert_eq!(called["content"][1]["type"], "audio");
+        assert!(called.get("structuredContent").is_some());
+
+        // 2024-11-05：同一个注册表，去掉title、outputSchema和structuredContent，音频换成文本占位
+        assert!(result(&old, "resources/list", json!({})).await["resources"][0].get("title").is_none());
+        let tools = result(&old, "tools/list", json!({})).await;
+        assert!(tools["tools"][0].get("title").is_none());
+        assert!(tools["tools"][0].get("outputSchema").is_none());
+        let called = result(&old, "tools/call", json!({ "name": "speak" })).await;
+        assert_eq!(called["content"], json!([{ "type": "text", "text": "Hello\n[audio: audio/wav]" }]));
+        assert!(called.get("structuredContent").is_none());
+    }
+
+    #[test]
+    fn gate_replaces_unknown_content_and_drops_result_fields() {
+        let gate = VersionGate::for_version(ProtocolVersion::V2024_11_05).unwrap();
+        let mut result = json!({
+            "content": [
+                { "type": "text", "text": "Done" },
+                { "type": "audio", "data": "UklGRg==", "mimeType": "audio/wav" },
+                { "type": "resource_link", "uri": "file:///report.pdf", "name": "report" }
+            ],
+            "structuredContent": { "ok": true },
+            "isError": false
+        });
+        gate.strip_result(&mut result);
+        assert_eq!(result, json!({
+            "content": [
+                { "type": "text", "text": "Done" },
+                { "type": "text", "text": "[audio: audio/wav]" },
+                { "type": "text", "text": "[resource: file:///report.pdf]" }
+            ],
+            "isError": false
+        }));
+
+        // 单个内容块对象（提示消息）同样处理，无法解析的块原样保留
+        let mut content = json!({ "type": "audio", "data": "", "mimeType": "audio/mpeg" });
+        gate.strip_content(&mut content);
+        assert_eq!(content, json!({ "type": "text", "text": "[audio: audio/mpeg]" }));
+    }
+}
@@ -0,0 +1,182 @@
+//! 列表的注册表代数和条件请求
+//!
+//! 客户端频繁轮询工具、资源和提示列表，而注册表很少变化。[`RustMCP::registry_generation`]
+//! 返回注册表的代数，添加、删除、替换、启用或禁用工具、资源和提示后增大；列表的缓存按同样的
+//! 修订号失效，代数不变时列表的内容也不变。
+//!
+//! REST列表接口（`GET /mcp/tools`、`/mcp/resources`、`/mcp/prompts`）：
+//! - 响应带有由代数生成的弱`ETag`，以及`Vary: Accept-Language`；
+//! - `If-None-Match`包含当前的`ETag`时返回`304 Not Modified`，不含响应体；
+//! - `ETag`包含进程启动时生成的随机前缀，重启后旧的`ETag`不会误匹配；不同的分组和
+//!   `Accept-Language`得到不同的`ETag`。
+//!
+//! JSON-RPC列表方法（`tools/list`、`resources/list`、`prompts/list`）是本库的扩展：
+//! - 结果的`_meta.registryGeneration`报告生成列表时的代数；
+//! - 请求的`params._meta.ifGeneration`等于当前代数时，结果只有
+//!   `{"_meta": {"registryGeneration": n, "notModified": true}}`，不含列表；
+//! - `ifGeneration`不是非负整数时返回参数错误。不认识这个扩展的客户端不受影响。
+//!
+//! ```rust
+//! use rustmcp::{FunctionTool, RustMCP};
+//! use serde_json::json;
+//!
+//! let rustmcp = RustMCP::new();
+//! let before = rustmcp.registry_generation();
+//! rustmcp.add_tool(FunctionTool::simple("echo", "Echo the input", |args| Ok(json!(args))));
+//! assert!(rustmcp.registry_generation() > before);
+//! ```
+//!
+//! [`RustMCP::registry_generation`]: crate::RustMCP::registry_generation
+
+use axum::http::header::{ACCEPT_LANGUAGE, ETAG, IF_NONE_MATCH, VARY};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::OnceLock;
+
+use crate::server::error::McpError;
+
+/// 进程启动时生成的`ETag`前缀，重启后代数从头计数，旧的`ETag`不会误匹配
+fn epoch() -> &'static str {
+    static EPOCH: OnceLock<String> = OnceLock::new();
+    EPOCH.get_or_init(|| uuid::Uuid::new_v4().simple().to_string()[..8].to_string())
+}
+
+/// 由代数、分组和`Accept-Language`生成弱`ETag`
+///
+/// 使用弱`ETag`，压缩后的响应与原始响应的字节不同，但表示相同。
+pub(crate) fn etag(generation: u64, group: Option<&str>, headers: &HeaderMap) -> String {
+    let accept_language = headers.get(ACCEPT_LANGUAGE).and_then(|value| value.to_str().ok());
+    match (group, accept_language) {
+        (None, None) => format!("W/\"{}-{}\"", epoch(), generation),
+        variant => {
+            let mut hasher = DefaultHasher::new();
+            variant.hash(&mut hasher);
+            format!("W/\"{}-{}-{:x}\"", epoch(), generation, hasher.finish())
+        }
+    }
+}
+
+/// `If-None-Match`是否包含`etag`，按弱比较处理，`*`匹配任何`ETag`
+pub(crate) fn is_fresh(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = etag.trim_start_matches("W/");
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == opaque)
+}
+
+/// `304 Not Modified`响应
+pub(crate) fn not_modified(etag: &str) -> Response {
+    (StatusCode::NOT_MODIFIED, [(ETAG, etag), (VARY, "accept-language")]).into_response()
+}
+
+/// 带有`ETag`的列表响应
+pub(crate) fn tagged(etag: &str, body: String) -> Response {
+    ([(ETAG, etag), (VARY, "accept-language")], body).into_response()
+}
+
+/// 读取`params._meta.ifGeneration`
+pub(crate) fn if_generation(params: Option<&Value>) -> Result<Option<u64>, McpError> {
+    match params.and_then(|params| params.pointer("/_meta/ifGeneration")) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value.as_u64().map(Some).ok_or_else(|| {
+            McpError::invalid_params(
+                "'_meta.ifGeneration' must be a non-negative integer",
+                "_meta.ifGeneration",
+                "integer",
+            )
+        }),
+    }
+}
+
+/// 代数未变化时列表方法的结果，不含列表
+pub(crate) fn not_modified_result(generation: u64) -> Value {
+    serde_json::json!({
+        "_meta": {
+            "registryGeneration": generation,
+            "notModified": true
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+
+    use crate::server::test_support::{get, initialize, post, request};
+    use crate::{create_app, FunctionTool, RustMCP};
+
+    fn echo_server() -> RustMCP {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::simple("echo", "Echo the input", |args| Ok(json!(args))));
+        rustmcp
+    }
+
+    /// 状态码、ETag和响应体
+    async fn list(app: &axum::Router, path: &str, etag: Option<&str>) -> (StatusCode, String, Value) {
+        let headers: Vec<(&str, &str)> = etag.map(|etag| ("if-none-match", etag)).into_iter().collect();
+        let (status, headers, body) = get(app, path, &headers).await;
+        (status, headers["etag"].to_str().unwrap().to_string(), body)
+    }
+
+    #[tokio::test]
+    async fn rest_lists_answer_conditional_requests() {
+        let rustmcp = echo_server();
+        let app = create_app(rustmcp.clone());
+
+        // 第一次请求得到列表和ETag，带上ETag再请求时返回304，不含响应体
+        let (status, etag, body) = list(&app, "/mcp/tools", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(etag.starts_with("W/\""));
+        assert_eq!(body[0]["name"], "echo");
+        let (status, same, body) = list(&app, "/mcp/tools", Some(&etag)).await;
+        assert_eq!((status, same.as_str(), body), (StatusCode::NOT_MODIFIED, etag.as_str(), Value::Null));
+
+        // 注册表变化后旧的ETag失效
+        rustmcp.add_tool(FunctionTool::simple("reverse", "Reverse the input", |args| Ok(json!(args))));
+        let (status, fresh, body) = list(&app, "/mcp/tools", Some(&etag)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_ne!(fresh, etag);
+        assert_eq!(body.as_array().unwrap().len(), 2);
+
+        // 分组是不同的表示，ETag也不同
+        assert_ne!(list(&app, "/mcp/tools?group=none", None).await.1, fresh);
+    }
+
+    #[tokio::test]
+    async fn jsonrpc_lists_report_the_generation() {
+        let rustmcp = echo_server();
+        rustmcp.add_tool(FunctionTool::simple("reverse", "Reverse the input", |args| Ok(json!(args))));
+        let app = create_app(rustmcp.clone());
+        let session = initialize(&app, json!({})).await;
+        let rpc = |body: Value| {
+            let (app, session) = (app.clone(), session.clone());
+            async move { post(&app, "/mcp", &[("mcp-session-id", &session)], &body).await.2 }
+        };
+
+        // 结果报告代数，ifGeneration未变化时返回不含列表的结果
+        let listed = rpc(request(2, "tools/list", json!({}))).await;
+        let generation = listed["result"]["_meta"]["registryGeneration"].as_u64().unwrap();
+        assert_eq!(generation, rustmcp.registry_generation());
+        assert_eq!(listed["result"]["tools"].as_array().unwrap().len(), 2);
+
+        let conditional = request(3, "tools/list", json!({ "_meta": { "ifGeneration": generation } }));
+        let unchanged = rpc(conditional.clone()).await;
+        assert_eq!(unchanged["result"], json!({ "_meta": { "registryGeneration": generation, "notModified": true } }));
+
+        rustmcp.remove_tool("echo");
+        let changed = rpc(conditional).await;
+        assert_eq!(changed["result"]["tools"].as_array().unwrap().len(), 1);
+        assert!(changed["result"]["_meta"]["registryGeneration"].as_u64().unwrap() > generation);
+
+        let invalid = rpc(request(4, "prompts/list", json!({ "_meta": { "ifGeneration": "latest" } }))).await;
+        assert_eq!(invalid["error"]["code"], -32602);
+    }
+}
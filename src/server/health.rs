@@ -0,0 +1,289 @@
+//! 依赖健康检查
+//!
+//! 工具依赖的数据库、上游API等可以注册为健康检查：每个检查在后台按间隔运行，最近一次的结果
+//! 缓存起来，出现在`/health`的`dependencies`中；标记为`critical`的检查失败（或还没有结果）时，
+//! `/ready`返回`503`。
+//!
+//! 检查在有tokio运行时的地方注册时立即启动，否则在创建应用或第一次请求`/health`、`/ready`时启动。
+//! 调用[`RustMCP::stop_health_checks`](crate::RustMCP::stop_health_checks)或丢弃最后一个
+//! [`RustMCP`](crate::RustMCP)克隆时停止所有检查，停止后`/health`和`/ready`报告最近的结果。
+//!
+//! ```rust
+//! use rustmcp::{HealthCheck, RustMCP};
+//! use std::time::Duration;
+//!
+//! let rustmcp = RustMCP::new();
+//! rustmcp.add_health_check(
+//!     HealthCheck::new("database", Duration::from_secs(10), || async { Ok(()) }).critical(true),
+//! );
+//! ```
+
+use futures::future::BoxFuture;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::AbortHandle;
+
+/// 健康检查函数
+type CheckFn = Arc<dyn Fn() -> BoxFuture<'static, Result<(), String>> + Send + Sync>;
+
+/// 依赖健康检查
+#[derive(Clone)]
+pub struct HealthCheck {
+    name: String,
+    interval: Duration,
+    critical: bool,
+    check: CheckFn,
+}
+
+impl HealthCheck {
+    /// 创建健康检查，`check`每隔`interval`运行一次，超过`interval`没有完成按失败处理
+    pub fn new<F, Fut>(name: impl Into<String>, interval: Duration, check: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        Self {
+            name: name.into(),
+            interval,
+            critical: false,
+            check: Arc::new(move || Box::pin(check())),
+        }
+    }
+
+    /// 设置是否为关键依赖，关键依赖失败时`/ready`返回`503`
+    pub fn critical(mut self, critical: bool) -> Self {
+        self.critical = critical;
+        self
+    }
+}
+
+impl std::fmt::Debug for HealthCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HealthCheck")
+            .field("name", &self.name)
+            .field("interval", &self.interval)
+            .field("critical", &self.critical)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 依赖的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyState {
+    /// 还没有完成第一次检查
+    Pending,
+    /// 最近一次检查成功
+    Up,
+    /// 最近一次检查失败
+    Down,
+}
+
+/// 依赖最近一次检查的结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyStatus {
+    /// 状态
+    pub status: DependencyState,
+    /// 是否为关键依赖
+    pub critical: bool,
+    /// 最近一次失败的错误，检查成功后清空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// 最近一次成功的时间（Unix毫秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_success_ms: Option<u64>,
+    /// 最近一次检查的时间（Unix毫秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_checked_ms: Option<u64>,
+}
+
+/// 一个已注册的检查
+struct Registered {
+    check: HealthCheck,
+    status: Arc<Mutex<DependencyStatus>>,
+    task: Option<AbortHandle>,
+}
+
+/// 所有健康检查，克隆的[`RustMCP`](crate::RustMCP)共享同一份，最后一份丢弃时停止检查
+#[derive(Default)]
+pub(crate) struct HealthRegistry {
+    checks: Mutex<BTreeMap<String, Registered>>,
+    /// 停止后不再启动检查
+    stopped: AtomicBool,
+}
+
+impl HealthRegistry {
+    /// 注册检查，同名检查被替换；有tokio运行时时立即启动
+    pub fn add(&self, check: HealthCheck) {
+        let status = DependencyStatus {
+            status: DependencyState::Pending,
+            critical: check.critical,
+            last_error: None,
+            last_success_ms: None,
+            last_checked_ms: None,
+        };
+        let registered = Registered {
+            check,
+            status: Arc::new(Mutex::new(status)),
+            task: None,
+        };
+        let previous = self.checks.lock().insert(registered.check.name.clone(), registered);
+        if let Some(task) = previous.and_then(|previous| previous.task) {
+            task.abort();
+        }
+        self.start_pending();
+    }
+
+    /// 启动还没有运行的检查，没有tokio运行时或已经停止时什么也不做
+    pub fn start_pending(&self) {
+        if self.stopped.load(Ordering::Relaxed) {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        for registered in self.checks.lock().values_mut().filter(|registered| registered.task.is_none()) {
+            let task = runtime.spawn(run(registered.check.clone(), registered.status.clone()));
+            registered.task = Some(task.abort_handle());
+        }
+    }
+
+    /// 停止所有检查，缓存的结果保留，之后注册的检查也不会启动
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        for registered in self.checks.lock().values_mut() {
+            if let Some(task) = registered.task.take() {
+                task.abort();
+            }
+        }
+    }
+
+    /// 所有检查最近的结果
+    pub fn status(&self) -> BTreeMap<String, DependencyStatus> {
+        self.checks
+            .lock()
+            .iter()
+            .map(|(name, registered)| (name.clone(), registered.status.lock().clone()))
+            .collect()
+    }
+
+    /// 没有成功的关键检查
+    pub fn failing_critical(&self) -> Vec<String> {
+        self.status()
+            .into_iter()
+            .filter(|(_, status)| status.critical && status.status != DependencyState::Up)
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+impl Drop for HealthRegistry {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl std::fmt::Debug for HealthRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HealthRegistry")
+            .field("checks", &self.checks.lock().keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// 按间隔运行检查并记录结果
+async fn run(check: HealthCheck, status: Arc<Mutex<DependencyStatus>>) {
+    let mut ticker = tokio::time::interval(check.interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        let result = match tokio::time::timeout(check.interval, (check.check)()).await {
+            Ok(result) => result,
+            Err(_) => Err(format!("timed out after {:?}", check.interval)),
+        };
+        let now = now_ms();
+        let mut status = status.lock();
+        status.last_checked_ms = Some(now);
+        match result {
+            Ok(()) => {
+                if status.status == DependencyState::Down {
+                    log::info!("Dependency '{}' recovered", check.name);
+                }
+                status.status = DependencyState::Up;
+                status.last_error = None;
+                status.last_success_ms = Some(now);
+            }
+            Err(e) => {
+                if status.status != DependencyState::Down {
+                    log::warn!("Dependency '{}' is failing: {}", check.name, e);
+                }
+                status.status = DependencyState::Down;
+                status.last_error = Some(e);
+            }
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::server::test_support::get;
+    use crate::{create_app, HealthCheck, RustMCP};
+
+    async fn ready(app: &axum::Router) -> StatusCode {
+        get(app, "/ready", &[]).await.0
+    }
+
+    #[tokio::test]
+    async fn readiness_follows_critical_checks_until_stopped() {
+        let database_up = Arc::new(AtomicBool::new(false));
+        let rustmcp = RustMCP::new();
+        let up = database_up.clone();
+        rustmcp.add_health_check(
+            HealthCheck::new("database", Duration::from_millis(20), move || {
+                let up = up.load(Ordering::SeqCst);
+                async move { if up { Ok(()) } else { Err("connection refused".to_string()) } }
+            })
+            .critical(true),
+        );
+        // 非关键依赖失败不影响就绪状态
+        rustmcp.add_health_check(HealthCheck::new("cache", Duration::from_millis(20), || async {
+            Err("cache offline".to_string())
+        }));
+        let app = create_app(rustmcp.clone());
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(ready(&app).await, StatusCode::SERVICE_UNAVAILABLE);
+        let status = &rustmcp.dependency_status()["database"];
+        assert_eq!(status.last_error.as_deref(), Some("connection refused"));
+
+        database_up.store(true, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(ready(&app).await, StatusCode::OK);
+        assert!(rustmcp.dependency_status()["database"].last_success_ms.is_some());
+
+        database_up.store(false, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(ready(&app).await, StatusCode::SERVICE_UNAVAILABLE);
+
+        // 停止后不再检查，最近的结果保留
+        rustmcp.stop_health_checks();
+        database_up.store(true, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(ready(&app).await, StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
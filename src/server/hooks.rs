@@ -0,0 +1,273 @@
+//! 工具、资源和提示调用的钩子
+//!
+//! 钩子按注册顺序执行：
+//! - `before`钩子可以修改参数；任一钩子返回错误时调用被否决，后续钩子和处理函数都不再执行，
+//!   该错误直接作为调用结果返回。
+//! - `after`钩子只用于观察，会收到每次工具调用的最终结果（包括被否决的调用）和耗时。
+//! - `initialize`钩子返回的警告会附加到initialize结果的`_meta.warnings`，不影响握手。
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::protocol::InitializeParams;
+use crate::server::error::McpError;
+use crate::server::Context;
+
+/// 工具调用前钩子
+pub type BeforeToolCallHook = Arc<dyn Fn(&str, &mut HashMap<String, Value>, &Context) -> Result<(), McpError> + Send + Sync>;
+
+/// 工具调用后钩子
+pub type AfterToolCallHook = Arc<dyn Fn(&str, &Result<Value, McpError>, Duration) + Send + Sync>;
+
+/// 资源读取前钩子
+pub type BeforeResourceReadHook = Arc<dyn Fn(&str, &Context) -> Result<(), McpError> + Send + Sync>;
+
+/// 提示获取前钩子，签名与工具调用前钩子相同
+pub type BeforePromptGetHook = BeforeToolCallHook;
+
+/// initialize钩子，返回要附加给客户端的警告
+pub type InitializeHook = Arc<dyn Fn(&InitializeParams) -> Vec<String> + Send + Sync>;
+
+/// 钩子注册表
+#[derive(Clone, Default)]
+pub struct Hooks {
+    before_tool_call: Vec<BeforeToolCallHook>,
+    after_tool_call: Vec<AfterToolCallHook>,
+    before_resource_read: Vec<BeforeResourceReadHook>,
+    before_prompt_get: Vec<BeforePromptGetHook>,
+    initialize: Vec<InitializeHook>,
+}
+
+impl Hooks {
+    /// 创建空的钩子注册表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册工具调用前钩子
+    pub fn on_before_tool_call<F>(&mut self, hook: F)
+    where
+        F: Fn(&str, &mut HashMap<String, Value>, &Context) -> Result<(), McpError> + Send + Sync + 'static,
+    {
+        self.before_tool_call.push(Arc::new(hook));
+    }
+
+    /// 注册工具调用后钩子
+    pub fn on_after_tool_call<F>(&mut self, hook: F)
+    where
+        F: Fn(&str, &Result<Value, McpError>, Duration) + Send + Sync + 'static,
+    {
+        self.after_tool_call.push(Arc::new(hook));
+    }
+
+    /// 注册资源读取前钩子
+    pub fn on_before_resource_read<F>(&mut self, hook: F)
+    where
+        F: Fn(&str, &Context) -> Result<(), McpError> + Send + Sync + 'static,
+    {
+        self.before_resource_read.push(Arc::new(hook));
+    }
+
+    /// 注册提示获取前钩子
+    pub fn on_before_prompt_get<F>(&mut self, hook: F)
+    where
+        F: Fn(&str, &mut HashMap<String, Value>, &Context) -> Result<(), McpError> + Send + Sync + 'static,
+    {
+        self.before_prompt_get.push(Arc::new(hook));
+    }
+
+    /// 注册initialize钩子
+    pub fn on_initialize<F>(&mut self, hook: F)
+    where
+        F: Fn(&InitializeParams) -> Vec<String> + Send + Sync + 'static,
+    {
+        self.initialize.push(Arc::new(hook));
+    }
+
+    /// 执行工具调用前钩子
+    pub(crate) fn run_before_tool_call(
+        &self,
+        name: &str,
+        arguments: Option<HashMap<String, Value>>,
+        ctx: &Context,
+    ) -> Result<Option<HashMap<String, Value>>, McpError> {
+        if self.before_tool_call.is_empty() {
+            return Ok(arguments);
+        }
+        run_argument_hooks(&self.before_tool_call, name, arguments, ctx)
+    }
+
+    /// 执行工具调用后钩子
+    pub(crate) fn run_after_tool_call(&self, name: &str, result: &Result<Value, McpError>, elapsed: Duration) {
+        for hook in &self.after_tool_call {
+            hook(name, result, elapsed);
+        }
+    }
+
+    /// 执行资源读取前钩子
+    pub(crate) fn run_before_resource_read(&self, uri: &str, ctx: &Context) -> Result<(), McpError> {
+        for hook in &self.before_resource_read {
+            hook(uri, ctx)?;
+        }
+        Ok(())
+    }
+
+    /// 执行提示获取前钩子
+    pub(crate) fn run_before_prompt_get(
+        &self,
+        name: &str,
+        arguments: Option<HashMap<String, Value>>,
+        ctx: &Context,
+    ) -> Result<Option<HashMap<String, Value>>, McpError> {
+        if self.before_prompt_get.is_empty() {
+            return Ok(arguments);
+        }
+        run_argument_hooks(&self.before_prompt_get, name, arguments, ctx)
+    }
+
+    /// 执行initialize钩子，按注册顺序合并警告
+    pub(crate) fn run_initialize(&self, params: &InitializeParams) -> Vec<String> {
+        self.initialize.iter().flat_map(|hook| hook(params)).collect()
+    }
+}
+
+/// 依次执行可修改参数的钩子
+///
+/// 没有参数时钩子收到空表；如果钩子没有添加任何参数，仍然返回`None`。
+fn run_argument_hooks(
+    hooks: &[BeforeToolCallHook],
+    name: &str,
+    arguments: Option<HashMap<String, Value>>,
+    ctx: &Context,
+) -> Result<Option<HashMap<String, Value>>, McpError> {
+    let had_arguments = arguments.is_some();
+    let mut arguments = arguments.unwrap_or_default();
+    for hook in hooks {
+        hook(name, &mut arguments, ctx)?;
+    }
+    if had_arguments || !arguments.is_empty() {
+        Ok(Some(arguments))
+    } else {
+        Ok(None)
+    }
+}
+
+impl std::fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hooks")
+            .field("before_tool_call", &self.before_tool_call.len())
+            .field("after_tool_call", &self.after_tool_call.len())
+            .field("before_resource_read", &self.before_resource_read.len())
+            .field("before_prompt_get", &self.before_prompt_get.len())
+            .field("initialize", &self.initialize.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parking_lot::Mutex;
+    use serde_json::json;
+
+    use super::*;
+    use crate::server::test_support::call;
+    use crate::server::{create_app, FunctionPrompt, FunctionResource, FunctionTool, PromptMessage, RustMCP};
+
+    /// 返回收到的参数
+    fn echo_server() -> RustMCP {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::simple("echo", "Echoes arguments", |args| Ok(json!(args.unwrap_or_default()))));
+        rustmcp.add_resource(FunctionResource::simple("memo://note", || Ok(json!("note"))));
+        rustmcp.add_prompt(FunctionPrompt::simple("greet", |args| {
+            let name = args.and_then(|args| args.get("name").cloned()).unwrap_or_default();
+            Ok(vec![PromptMessage::user(format!("hello {}", name.as_str().unwrap_or("nobody")))])
+        }));
+        rustmcp
+    }
+
+    #[tokio::test]
+    async fn before_hooks_mutate_arguments_in_registration_order() {
+        let mut rustmcp = echo_server();
+        rustmcp.on_before_tool_call(|_, args, _| {
+            args.insert("step".to_string(), json!("first"));
+            Ok(())
+        });
+        rustmcp.on_before_tool_call(|_, args, _| {
+            assert_eq!(args["step"], "first");
+            args.insert("step".to_string(), json!("second"));
+            args.remove("secret");
+            Ok(())
+        });
+        let arguments = HashMap::from([("secret".to_string(), json!("x")), ("keep".to_string(), json!(1))]);
+        let result = rustmcp.mcp_call_tool("echo", Some(arguments)).await.unwrap();
+        assert_eq!(result, json!({ "step": "second", "keep": 1 }));
+
+        // 没有参数时钩子收到空表，添加的参数会传给工具
+        assert_eq!(rustmcp.mcp_call_tool("echo", None).await.unwrap(), json!({ "step": "second" }));
+
+        rustmcp.on_before_prompt_get(|_, args, _| {
+            args.entry("name".to_string()).or_insert(json!("guest"));
+            Ok(())
+        });
+        let messages = rustmcp.mcp_get_prompt("greet", None).unwrap();
+        assert!(serde_json::to_string(&messages[0]).unwrap().contains("hello guest"));
+    }
+
+    #[tokio::test]
+    async fn veto_short_circuits_later_hooks_and_the_handler() {
+        let mut rustmcp = RustMCP::new();
+        let called = Arc::new(Mutex::new(Vec::new()));
+        let log = called.clone();
+        rustmcp.add_tool(FunctionTool::simple("echo", "Echoes", move |_| {
+            log.lock().push("handler");
+            Ok(json!("ok"))
+        }));
+        let log = called.clone();
+        rustmcp.on_before_tool_call(move |_, _, _| {
+            log.lock().push("first");
+            Err(McpError::Unauthorized("denied".to_string()))
+        });
+        let log = called.clone();
+        rustmcp.on_before_tool_call(move |_, _, _| {
+            log.lock().push("second");
+            Ok(())
+        });
+        let observed = Arc::new(Mutex::new(Vec::new()));
+        for label in ["after-1", "after-2"] {
+            let observed = observed.clone();
+            rustmcp.on_after_tool_call(move |name, result, _| {
+                observed.lock().push((label, name.to_string(), result.as_ref().err().map(ToString::to_string)));
+            });
+        }
+
+        let error = rustmcp.mcp_call_tool("echo", None).await.unwrap_err();
+        assert!(matches!(error, McpError::Unauthorized(_)));
+        assert_eq!(*called.lock(), ["first"]);
+        // after钩子按注册顺序收到被否决的调用
+        let observed = observed.lock();
+        assert_eq!(observed.iter().map(|(label, _, _)| *label).collect::<Vec<_>>(), ["after-1", "after-2"]);
+        let denied = McpError::Unauthorized("denied".to_string()).to_string();
+        assert!(observed.iter().all(|(_, name, error)| name == "echo" && error.as_ref() == Some(&denied)));
+    }
+
+    #[tokio::test]
+    async fn resource_and_prompt_vetoes_are_returned_as_errors() {
+        let mut rustmcp = echo_server();
+        rustmcp.on_before_resource_read(|uri, _| match uri {
+            "memo://note" => Err(McpError::Unauthorized("no notes".to_string())),
+            _ => Ok(()),
+        });
+        rustmcp.on_before_prompt_get(|name, _, _| Err(McpError::invalid_params(format!("{} is disabled", name), "name", "enabled prompt")));
+        assert!(matches!(rustmcp.mcp_read_resource("memo://note"), Err(McpError::Unauthorized(_))));
+        assert!(matches!(rustmcp.mcp_get_prompt("greet", None), Err(McpError::InvalidParams { .. })));
+
+        // 通过HTTP调用时否决的错误同样返回给客户端
+        let app = create_app(rustmcp);
+        let response = call(&app, "resources/read", json!({ "uri": "memo://note" })).await;
+        assert!(response["error"]["message"].as_str().unwrap().contains("no notes"), "{}", response);
+        let response = call(&app, "prompts/get", json!({ "name": "greet" })).await;
+        assert_eq!(response["error"]["code"], -32602);
+    }
+}
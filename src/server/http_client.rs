@@ -0,0 +1,250 @@
+//! 出站HTTP客户端
+//!
+//! [转发工具](crate::server::proxy)、[URL资源](crate::FunctionResource::from_url)和
+//! [OpenAPI导入](crate::server::openapi)生成的工具共用一个带连接池的[`HttpClient`]，
+//! 连接在它们之间复用，代理和TLS设置也一致。客户端按[`Settings::http_client`](crate::Settings::http_client)
+//! （[`HttpClientConfig`]）创建，放在[共享状态](crate::server::state)中，处理函数可以通过`ctx.state::<HttpClient>()`取得。
+//!
+//! 配置有误（代理地址无法解析、根证书无法读取）时，[`RustMCP::check_startup`](crate::RustMCP::check_startup)
+//! 总是返回错误，服务器拒绝启动。发送请求需要`http-client`特性（`admin`和`openapi`特性包含它）。
+
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// 出站HTTP客户端的配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpClientConfig {
+    /// 建立连接的超时毫秒数，不设置时不限制
+    #[serde(default)]
+    pub connect_timeout_ms: Option<u64>,
+    /// 整个请求的超时毫秒数，不设置时不限制，各功能可以单独覆盖
+    #[serde(default)]
+    pub request_timeout_ms: Option<u64>,
+    /// 代理地址，例如`http://proxy.internal:3128`
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// 额外信任的根证书（PEM文件），与内置的根证书一起使用
+    #[serde(default)]
+    pub root_certificates: Vec<PathBuf>,
+    /// `User-Agent`请求头
+    #[serde(default)]
+    pub user_agent: Option<String>,
+}
+
+impl HttpClientConfig {
+    /// 创建默认配置：没有超时、不使用代理、只信任内置的根证书
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置建立连接的超时
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout_ms = Some(u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX));
+        self
+    }
+
+    /// 设置整个请求的超时
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout_ms = Some(u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX));
+        self
+    }
+
+    /// 设置代理地址
+    pub fn with_proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// 添加一个额外信任的根证书文件（PEM，可以包含多个证书）
+    pub fn with_root_certificate(mut self, path: impl Into<PathBuf>) -> Self {
+        self.root_certificates.push(path.into());
+        self
+    }
+
+    /// 设置`User-Agent`请求头
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+}
+
+/// 共用的出站HTTP客户端
+///
+/// 克隆共享同一个连接池。配置的请求超时作用于每个请求，[`ProxyTarget::with_timeout`](crate::ProxyTarget::with_timeout)
+/// 和[`OpenApiOptions::timeout`](crate::server::openapi::OpenApiOptions::timeout)可以为单个功能覆盖。
+///
+/// ```rust
+/// use rustmcp::{HttpClientConfig, RustMCP, Settings};
+/// use std::time::Duration;
+///
+/// let config = HttpClientConfig::new()
+///     .with_request_timeout(Duration::from_secs(10))
+///     .with_user_agent("inventory-bot/1.0");
+/// let rustmcp = RustMCP::new().with_settings(Settings::new().with_http_client(config));
+/// // 证书文件等配置错误在启动检查时报告
+/// rustmcp.check_startup().unwrap();
+/// ```
+#[cfg(feature = "http-client")]
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    agent: ureq::Agent,
+    request_timeout: Option<Duration>,
+}
+
+#[cfg(feature = "http-client")]
+impl HttpClient {
+    /// 按配置创建客户端，代理地址无法解析或根证书无法读取时返回错误
+    pub fn new(config: &HttpClientConfig) -> Result<Self, String> {
+        let mut builder = ureq::AgentBuilder::new();
+        if let Some(timeout_ms) = config.connect_timeout_ms {
+            builder = builder.timeout_connect(Duration::from_millis(timeout_ms));
+        }
+        if let Some(proxy) = &config.proxy {
+            // 代理地址可能带有密码，错误中不回显
+            let proxy = ureq::Proxy::new(proxy).map_err(|e| format!("Invalid HTTP proxy address: {}", e))?;
+            builder = builder.proxy(proxy);
+        }
+        if !config.root_certificates.is_empty() {
+            builder = builder.tls_config(std::sync::Arc::new(tls_config(&config.root_certificates)?));
+        }
+        if let Some(user_agent) = &config.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        Ok(Self {
+            agent: builder.build(),
+            request_timeout: config.request_timeout_ms.map(Duration::from_millis),
+        })
+    }
+
+    /// 底层的连接池
+    pub fn agent(&self) -> &ureq::Agent {
+        &self.agent
+    }
+
+    /// 配置的请求超时
+    pub fn request_timeout(&self) -> Option<Duration> {
+        self.request_timeout
+    }
+
+    /// 创建请求，`timeout`覆盖配置的请求超时
+    pub fn request(&self, method: &str, url: &str, timeout: Option<Duration>) -> ureq::Request {
+        let request = self.agent.request(method, url);
+        match timeout.or(self.request_timeout) {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        }
+    }
+
+    /// 上下文中没有客户端时（例如不经过`RustMCP`直接调用）使用的默认客户端
+    pub(crate) fn fallback() -> &'static HttpClient {
+        static FALLBACK: std::sync::OnceLock<HttpClient> = std::sync::OnceLock::new();
+        FALLBACK.get_or_init(HttpClient::default)
+    }
+}
+
+#[cfg(feature = "http-client")]
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self {
+            agent: ureq::Agent::new(),
+            request_timeout: None,
+        }
+    }
+}
+
+/// 内置根证书加上额外证书的TLS配置
+#[cfg(feature = "http-client")]
+fn tls_config(paths: &[PathBuf]) -> Result<rustls::ClientConfig, String> {
+    use rustls_pki_types::pem::PemObject;
+    use rustls_pki_types::CertificateDer;
+
+    let mut roots = rustls::RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    for path in paths {
+        let unreadable = |e: &dyn std::fmt::Display| format!("Cannot read root certificate {}: {}", path.display(), e);
+        let mut added = 0;
+        for certificate in CertificateDer::pem_file_iter(path).map_err(|e| unreadable(&e))? {
+            roots.add(certificate.map_err(|e| unreadable(&e))?).map_err(|e| unreadable(&e))?;
+            added += 1;
+        }
+        if added == 0 {
+            return Err(unreadable(&"no PEM certificates found"));
+        }
+    }
+    let provider = std::sync::Arc::new(rustls::crypto::ring::default_provider());
+    Ok(rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Cannot configure TLS: {}", e))?
+        .with_root_certificates(roots)
+        .with_no_client_auth())
+}
+
+#[cfg(all(test, feature = "http-client"))]
+mod tests {
+    use axum::{http::HeaderMap, routing::get, Json, Router};
+    use serde_json::{json, Value};
+    use std::time::{Duration, Instant};
+
+    use crate::{FunctionResource, FunctionTool, HttpClientConfig, ProxyTarget, ResourceContents, RustMCP, Settings};
+
+    async fn echo(headers: HeaderMap) -> Json<Value> {
+        Json(json!({ "agent": headers["user-agent"].to_str().unwrap() }))
+    }
+
+    /// 启动上游服务，返回基础URL
+    async fn upstream() -> String {
+        let upstream = Router::new().route("/echo", get(echo).post(echo)).route(
+            "/slow",
+            get(|| async {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                "late"
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, upstream).await });
+        base
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn proxies_and_url_resources_share_the_configured_client() {
+        let base = upstream().await;
+        let config = HttpClientConfig::new()
+            .with_request_timeout(Duration::from_millis(300))
+            .with_user_agent("inventory-bot/1.0");
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_http_client(config));
+        rustmcp.check_startup().unwrap();
+        let proxy = |path: &str| ProxyTarget::new(format!("{}{}", base, path));
+        let tool = |name: &str, target: ProxyTarget| {
+            FunctionTool::builder().name(name).description("Forwards to the upstream").remote(target).build()
+        };
+        rustmcp.add_tool(tool("lookup", proxy("/echo")));
+        rustmcp.add_tool(tool("report", proxy("/slow").with_method("GET").with_timeout(Duration::from_secs(5))));
+        rustmcp.add_resource(FunctionResource::from_url("inventory://echo", format!("{}/echo", base)));
+        rustmcp.add_resource(FunctionResource::from_url("inventory://slow", format!("{}/slow", base)));
+
+        // 转发工具和URL资源使用同一个客户端，带有同样的User-Agent
+        let from_tool = rustmcp.mcp_call_tool("lookup", None).await.unwrap();
+        let from_resource = match rustmcp.mcp_read_resource("inventory://echo").unwrap() {
+            ResourceContents::Text { text, .. } => serde_json::from_str::<Value>(&text).unwrap(),
+            other => panic!("expected text, got {:?}", other),
+        };
+        assert_eq!(from_tool["agent"], "inventory-bot/1.0");
+        assert_eq!(from_tool, from_resource);
+
+        // 配置的请求超时，工具可以单独放宽
+        let started = Instant::now();
+        assert!(rustmcp.mcp_read_resource("inventory://slow").is_err());
+        assert!(started.elapsed() < Duration::from_millis(900));
+        assert_eq!(rustmcp.mcp_call_tool("report", None).await.unwrap(), "late");
+    }
+
+    #[test]
+    fn configuration_errors_are_reported_at_startup() {
+        let config = HttpClientConfig::new().with_root_certificate("/nonexistent/ca.pem");
+        let broken = RustMCP::new().with_settings(Settings::new().with_http_client(config));
+        let errors = broken.check_startup().unwrap_err();
+        assert!(errors[0].message.contains("/nonexistent/ca.pem"), "{}", errors[0]);
+    }
+}
@@ -0,0 +1,320 @@
+//! `tools/call`的幂等键
+//!
+//! 连接在工具执行完、客户端读到响应之前断开时，客户端重试会让操作执行两次。客户端可以在
+//! `params._meta.idempotencyKey`中携带一个字符串键：
+//!
+//! - 第一次调用完成后，结果按（会话，键）保存[`Settings::idempotency_ttl_ms`](crate::Settings::idempotency_ttl_ms)毫秒；
+//! - 有效期内带相同键的请求直接返回保存的结果，不再调用工具；
+//! - 同时到达的重复请求等待第一次执行完成，然后返回它的结果；
+//! - 同一个键用于不同的工具或参数时返回`-32602`。
+//!
+//! 没有会话的请求按远端IP区分。只有工具实际执行了（包括`isError: true`的结果）才会保存结果，
+//! 参数错误、工具不存在、被钩子否决等错误不保存，重试时会重新处理。
+//! 幂等键只作用于JSON-RPC的`tools/call`，REST接口不支持。
+//!
+//! 结果默认保存在内存中（[`MemoryIdempotencyStore`]），多个实例共享结果时可以实现[`IdempotencyStore`]，
+//! 例如保存到Redis，通过[`RustMCP::with_idempotency_store`](crate::RustMCP::with_idempotency_store)设置。
+//! 等待同时到达的重复请求只在同一个进程内有效。
+//!
+//! ```json
+//! {"jsonrpc":"2.0","id":7,"method":"tools/call","params":{"name":"transfer","arguments":{"amount":5},"_meta":{"idempotencyKey":"2f6c1e"}}}
+//! ```
+//!
+//! ```rust
+//! use rustmcp::{MemoryIdempotencyStore, RustMCP, Settings};
+//! use std::time::Duration;
+//!
+//! let rustmcp = RustMCP::new()
+//!     .with_settings(Settings::new().with_idempotency_ttl(Duration::from_secs(600)))
+//!     .with_idempotency_store(MemoryIdempotencyStore::new());
+//! ```
+
+use futures::future::BoxFuture;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::server::error::McpError;
+
+/// 保存的调用结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredResult {
+    /// 工具名称和参数的摘要，用于发现同一个键被用于不同的调用
+    pub fingerprint: String,
+    /// `tools/call`的结果
+    pub result: Value,
+}
+
+/// 幂等结果的存储
+///
+/// 键已经包含会话，格式为`<会话>/<幂等键>`。过期的结果必须不再返回。
+pub trait IdempotencyStore: Send + Sync {
+    /// 读取没有过期的结果
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<StoredResult>>;
+    /// 保存结果，`ttl`之后过期
+    fn put<'a>(&'a self, key: &'a str, result: StoredResult, ttl: Duration) -> BoxFuture<'a, ()>;
+}
+
+/// 保存在内存中的幂等结果，保存时清理过期的结果
+#[derive(Debug, Default)]
+pub struct MemoryIdempotencyStore {
+    results: Mutex<HashMap<String, (StoredResult, Instant)>>,
+}
+
+impl MemoryIdempotencyStore {
+    /// 创建空的存储
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IdempotencyStore for MemoryIdempotencyStore {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<StoredResult>> {
+        let result = self
+            .results
+            .lock()
+            .get(key)
+            .filter(|(_, expires)| *expires > Instant::now())
+            .map(|(result, _)| result.clone());
+        Box::pin(async move { result })
+    }
+
+    fn put<'a>(&'a self, key: &'a str, result: StoredResult, ttl: Duration) -> BoxFuture<'a, ()> {
+        let now = Instant::now();
+        let mut results = self.results.lock();
+        results.retain(|_, (_, expires)| *expires > now);
+        results.insert(key.to_string(), (result, now + ttl));
+        Box::pin(async {})
+    }
+}
+
+/// 幂等存储和正在执行的键
+pub(crate) struct Idempotency {
+    store: Arc<dyn IdempotencyStore>,
+    in_flight: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl Idempotency {
+    pub fn new(store: Arc<dyn IdempotencyStore>) -> Self {
+        Self {
+            store,
+            in_flight: Mutex::default(),
+        }
+    }
+
+    /// 有保存的结果时直接返回，否则执行`call`并保存成功的结果；同一个键同时只执行一次
+    pub async fn run<F>(&self, key: String, fingerprint: String, ttl: Duration, call: F) -> Result<Value, McpError>
+    where
+        F: Future<Output = Result<Value, McpError>>,
+    {
+        let entry = InFlight {
+            in_flight: &self.in_flight,
+            lock: self.in_flight.lock().entry(key.clone()).or_default().clone(),
+            key: &key,
+        };
+        let _guard = entry.lock.lock().await;
+        match self.store.get(&key).await {
+            Some(stored) if stored.fingerprint == fingerprint => Ok(stored.result),
+            Some(_) => Err(McpError::invalid_params(
+                "Idempotency key was already used for a different call",
+                "_meta.idempotencyKey",
+                "a key not used for another tool or other arguments",
+            )),
+            None => {
+                let result = call.await;
+                if let Ok(value) = &result {
+                    let stored = StoredResult {
+                        fingerprint,
+                        result: value.clone(),
+                    };
+                    self.store.put(&key, stored, ttl).await;
+                }
+                result
+            }
+        }
+    }
+}
+
+/// 正在执行或等待的键
+///
+/// 离开作用域时（包括请求的future被丢弃）如果没有其他请求在等待，从表中移除锁。
+struct InFlight<'a> {
+    in_flight: &'a Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    lock: Arc<tokio::sync::Mutex<()>>,
+    key: &'a str,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = self.in_flight.lock();
+        // 表中的引用和这里的引用
+        let last = in_flight.get(self.key).is_some_and(|lock| Arc::ptr_eq(lock, &self.lock)) && Arc::strong_count(&self.lock) == 2;
+        if last {
+            in_flight.remove(self.key);
+        }
+    }
+}
+
+impl std::fmt::Debug for Idempotency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Idempotency")
+            .field("in_flight", &self.in_flight.lock().len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// 工具名称和参数的摘要
+pub(crate) fn fingerprint(name: &str, arguments: Option<&Value>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(name.as_bytes());
+    hasher.update([0]);
+    // 直接写入哈希，不生成参数的JSON文本；写入哈希不会失败
+    let _ = serde_json::to_writer(&mut hasher, arguments.unwrap_or(&Value::Null));
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::server::test_support::{post, request};
+    use crate::{create_app, FunctionTool, RustMCP, Settings};
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn idempotency() -> Idempotency {
+        Idempotency::new(Arc::new(MemoryIdempotencyStore::new()))
+    }
+
+    async fn count(executions: &AtomicUsize) -> Result<Value, McpError> {
+        Ok(json!(executions.fetch_add(1, Ordering::SeqCst) + 1))
+    }
+
+    #[tokio::test]
+    async fn stored_result_is_replayed_until_it_expires() {
+        let idempotency = idempotency();
+        let executions = AtomicUsize::new(0);
+        let ttl = Duration::from_millis(50);
+        let first = idempotency.run("s/key".to_string(), "call".to_string(), ttl, count(&executions)).await;
+        let replay = idempotency.run("s/key".to_string(), "call".to_string(), ttl, count(&executions)).await;
+        assert_eq!(first.unwrap(), json!(1));
+        assert_eq!(replay.unwrap(), json!(1));
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        let expired = idempotency.run("s/key".to_string(), "call".to_string(), ttl, count(&executions)).await;
+        assert_eq!(expired.unwrap(), json!(2));
+        assert_eq!(executions.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn errors_are_not_stored() {
+        let idempotency = idempotency();
+        let failed = idempotency
+            .run("s/key".to_string(), "call".to_string(), TTL, async { Err(McpError::Internal("unavailable".to_string())) })
+            .await;
+        assert!(failed.is_err());
+        let executions = AtomicUsize::new(0);
+        let retried = idempotency.run("s/key".to_string(), "call".to_string(), TTL, count(&executions)).await;
+        assert_eq!(retried.unwrap(), json!(1));
+    }
+
+    #[tokio::test]
+    async fn reused_key_for_another_call_is_rejected() {
+        let idempotency = idempotency();
+        let executions = AtomicUsize::new(0);
+        idempotency.run("s/key".to_string(), "call".to_string(), TTL, count(&executions)).await.unwrap();
+        let error = idempotency
+            .run("s/key".to_string(), "other".to_string(), TTL, count(&executions))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), -32602);
+        assert_eq!(executions.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn dropped_call_releases_the_key() {
+        let idempotency = idempotency();
+        let pending = idempotency.run("s/key".to_string(), "call".to_string(), TTL, futures::future::pending());
+        assert!(tokio::time::timeout(Duration::from_millis(20), pending).await.is_err());
+        assert!(idempotency.in_flight.lock().is_empty());
+
+        // 被丢弃的调用没有保存结果，也不会让后来的请求一直等待
+        let executions = AtomicUsize::new(0);
+        let retried = tokio::time::timeout(
+            Duration::from_secs(5),
+            idempotency.run("s/key".to_string(), "call".to_string(), TTL, count(&executions)),
+        );
+        assert_eq!(retried.await.unwrap().unwrap(), json!(1));
+        assert!(idempotency.in_flight.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn dropped_waiter_keeps_the_key_for_the_running_call() {
+        let idempotency = idempotency();
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+        let running = idempotency.run("s/key".to_string(), "call".to_string(), TTL, async move {
+            released.await.unwrap();
+            Ok(json!("done"))
+        });
+        let waiter = idempotency.run("s/key".to_string(), "call".to_string(), TTL, async { Ok(json!("duplicate")) });
+        let (running, ()) = tokio::join!(running, async {
+            // 等待者排队后被丢弃，正在执行的调用仍然持有锁
+            assert!(tokio::time::timeout(Duration::from_millis(20), waiter).await.is_err());
+            assert_eq!(idempotency.in_flight.lock().len(), 1);
+            release.send(()).unwrap();
+        });
+        assert_eq!(running.unwrap(), json!("done"));
+        assert!(idempotency.in_flight.lock().is_empty());
+    }
+
+    async fn transfer(app: &axum::Router, key: &str) -> Value {
+        let params = json!({ "name": "transfer", "arguments": { "amount": 5 }, "_meta": { "idempotencyKey": key } });
+        post(app, "/mcp", &[], &request(1, "tools/call", params)).await.2
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn duplicate_requests_over_http_execute_once() {
+        let executions = Arc::new(AtomicUsize::new(0));
+        let counter = executions.clone();
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_idempotency_ttl(Duration::from_millis(300)));
+        rustmcp.add_tool(FunctionTool::simple("transfer", "Moves money", move |_| {
+            let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            std::thread::sleep(Duration::from_millis(50));
+            Ok(json!(format!("transfer #{}", n)))
+        }));
+        let app = create_app(rustmcp);
+
+        // 重放相同的键只执行一次，返回相同的结果
+        let first = transfer(&app, "key-1").await;
+        assert!(first["result"]["content"][0]["text"].as_str().unwrap().contains("transfer #1"));
+        assert_eq!(transfer(&app, "key-1").await, first);
+        assert_eq!(executions.load(Ordering::SeqCst), 1);
+
+        // 同时到达的重复请求等待第一次执行
+        let (a, b) = tokio::join!(transfer(&app, "key-2"), transfer(&app, "key-2"));
+        assert_eq!(a, b);
+        assert_eq!(executions.load(Ordering::SeqCst), 2);
+
+        // 过期后重新执行
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert!(transfer(&app, "key-1").await["result"]["content"][0]["text"].as_str().unwrap().contains("transfer #3"));
+        assert_eq!(executions.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn malformed_key_is_rejected() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::simple("transfer", "Moves money", |_| Ok(json!("ok"))));
+        let params = json!({ "name": "transfer", "_meta": { "idempotencyKey": "" } });
+        let response = post(&create_app(rustmcp), "/mcp", &[], &request(1, "tools/call", params)).await.2;
+        assert_eq!(response["error"]["code"], -32602);
+    }
+}
@@ -0,0 +1,168 @@
+//! 启动信息和`GET /mcp/info`
+//!
+//! [`RustMCP::info`](crate::RustMCP::info)汇总实例的运行配置，格式与`GET /mcp/info`的响应相同：
+//! - `serverInfo`、`protocolVersions`和initialize结果中的`capabilities`；
+//! - `addresses`：[`run`](crate::run)绑定的地址，使用[`serve`](crate::serve)启动时为空；
+//! - `transports`、`routePrefix`（[`create_multi_app`](crate::create_multi_app)挂载的前缀，单实例为`/`）和`routes`；
+//! - `counts`：启用的工具、资源和提示的数量；
+//! - `features`：只读模式、限流、审计、管理接口等是否开启，以及当前的方法策略。
+//!
+//! 结果被缓存，注册表、方法策略、只读模式、地址或前缀变化后重新生成。[`run`](crate::run)启动时把它作为一行JSON
+//! 记录到`info`级别的日志。响应包含配置信息，设置[`Settings::protect_info`](crate::Settings::protect_info)后
+//! 要求`Authorization: Bearer <admin_token>`，没有设置`admin_token`时总是拒绝。
+//!
+//! ```rust
+//! use rustmcp::RustMCP;
+//!
+//! let info = RustMCP::new().info();
+//! assert_eq!(info["routePrefix"], "/");
+//! assert_eq!(info["counts"]["tools"], 0);
+//! ```
+
+use parking_lot::RwLock;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use crate::protocol::SUPPORTED_PROTOCOL_VERSIONS;
+use crate::server::dispatch::InitializeRevision;
+use crate::server::{RustMCP, TransportKind};
+
+/// `create_app`挂载的路由，相对于实例的前缀
+const ROUTES: &[&str] = &[
+    "/",
+    "/health",
+    "/ready",
+    "/mcp",
+    "/mcp/ws",
+    "/mcp/info",
+    "/mcp/tools",
+    "/mcp/tools/{name}/call",
+    "/mcp/call-tool",
+    "/mcp/resources",
+    "/mcp/resources/read",
+    "/mcp/prompts",
+    "/mcp/tags",
+    "/mcp/openapi.json",
+];
+
+/// 启动信息的缓存，以及只有启动时才知道的前缀和地址
+#[derive(Debug, Default)]
+pub(crate) struct InfoSnapshot {
+    prefix: RwLock<Option<String>>,
+    addresses: RwLock<Vec<SocketAddr>>,
+    cached: RwLock<Option<(InitializeRevision, Arc<Value>)>>,
+}
+
+impl InfoSnapshot {
+    /// 记录挂载的前缀
+    pub(crate) fn set_prefix(&self, prefix: &str) {
+        *self.prefix.write() = Some(prefix.to_string());
+        *self.cached.write() = None;
+    }
+
+    /// 记录绑定的地址
+    pub(crate) fn add_address(&self, address: SocketAddr) {
+        self.addresses.write().push(address);
+        *self.cached.write() = None;
+    }
+
+    /// 返回缓存的信息，状态不同或没有缓存时重新生成
+    pub(crate) fn get_or_build(&self, rustmcp: &RustMCP, revision: InitializeRevision) -> Arc<Value> {
+        if let Some((cached, info)) = &*self.cached.read() {
+            if *cached == revision {
+                return info.clone();
+            }
+        }
+        let info = Arc::new(self.build(rustmcp));
+        *self.cached.write() = Some((revision, info.clone()));
+        info
+    }
+
+    fn build(&self, rustmcp: &RustMCP) -> Value {
+        let settings = rustmcp.settings();
+        let initialize = rustmcp.mcp_initialize_result();
+        let addresses: Vec<String> = self.addresses.read().iter().map(SocketAddr::to_string).collect();
+        serde_json::json!({
+            "serverInfo": initialize.server_info,
+            "protocolVersions": SUPPORTED_PROTOCOL_VERSIONS,
+            "capabilities": initialize.capabilities,
+            "addresses": addresses,
+            "transports": [TransportKind::Http, TransportKind::Ws],
+            "routePrefix": self.prefix.read().as_deref().unwrap_or("/"),
+            "routes": ROUTES,
+            "counts": {
+                "tools": rustmcp.mcp_list_tools().len(),
+                "resources": rustmcp.mcp_list_resources().len(),
+                "prompts": rustmcp.mcp_list_prompts().len(),
+            },
+            "features": {
+                "readOnly": rustmcp.is_read_only(),
+                "rateLimit": settings.rate_limit.is_some(),
+                "loadShedding": settings.max_in_flight.is_some() || settings.max_in_flight_per_session.is_some(),
+                "audit": rustmcp.audit.is_some(),
+                "admin": cfg!(feature = "admin") && settings.admin_token.is_some(),
+                "canonicalJson": settings.canonical_json,
+                "strictParams": settings.strict_params,
+                "strictValidation": settings.strict_validation,
+                "strictTags": settings.strict_tags,
+                "validateExamples": settings.validate_examples,
+                "lifecyclePolicy": settings.lifecycle_policy,
+                "protectInfo": settings.protect_info,
+                "compression": settings.compression,
+                "methodPolicy": rustmcp.method_policy(),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::server::test_support::get;
+    use crate::{create_app, FunctionTool, RateLimit, RateLimitSettings, RustMCP, Settings};
+
+    #[tokio::test]
+    async fn info_reports_default_configuration() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::simple("echo", "Echoes its arguments", |args| Ok(json!(args))));
+        let (status, _, info) = get(&create_app(rustmcp), "/mcp/info", &[]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(info["protocolVersions"], json!(["2025-06-18", "2024-11-05"]));
+        assert_eq!(info["transports"], json!(["http", "ws"]));
+        assert_eq!(info["routePrefix"], "/");
+        assert_eq!(info["counts"], json!({ "tools": 1, "resources": 0, "prompts": 0 }));
+        assert_eq!(info["features"]["readOnly"], false);
+        assert_eq!(info["features"]["rateLimit"], false);
+        assert_eq!(info["features"]["protectInfo"], false);
+    }
+
+    #[tokio::test]
+    async fn protected_info_requires_the_admin_token() {
+        let settings = Settings::new()
+            .with_read_only(true)
+            .with_rate_limit(RateLimitSettings::new(RateLimit::new(10.0, 10)))
+            .with_admin_token("s3cret")
+            .with_protect_info(true);
+        let app = create_app(RustMCP::new().with_settings(settings));
+        assert_eq!(get(&app, "/mcp/info", &[]).await.0, StatusCode::UNAUTHORIZED);
+        assert_eq!(get(&app, "/mcp/info", &[("authorization", "Bearer wrong")]).await.0, StatusCode::UNAUTHORIZED);
+        let (status, _, info) = get(&app, "/mcp/info", &[("authorization", "Bearer s3cret")]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(info["features"]["readOnly"], true);
+        assert_eq!(info["features"]["rateLimit"], true);
+        assert_eq!(info["features"]["protectInfo"], true);
+        assert_eq!(info["features"]["admin"], cfg!(feature = "admin"));
+    }
+
+    #[test]
+    fn info_is_rebuilt_when_the_registry_changes() {
+        let rustmcp = RustMCP::new();
+        let before = rustmcp.info();
+        assert!(std::sync::Arc::ptr_eq(&before, &rustmcp.info()));
+        rustmcp.add_tool(FunctionTool::simple("echo", "Echoes its arguments", |args| Ok(json!(args))));
+        assert_eq!(rustmcp.info()["counts"]["tools"], 1);
+    }
+}
@@ -0,0 +1,260 @@
+//! 注册表自省工具
+//!
+//! 有些客户端只能通过工具与服务器交互，看不到`tools/list`的完整结果。
+//! [`RustMCP::enable_introspection_tools`](crate::RustMCP::enable_introspection_tools)注册三个内置工具：
+//! - [`list_registered_tools`](LIST_REGISTERED_TOOLS)：按名称分页列出工具的名称、描述和标签，
+//!   参数`cursor`为上一页结果中的`nextCursor`，`limit`为每页的工具数（默认50）；
+//! - [`describe_tool`](DESCRIBE_TOOL)：返回一个工具在`tools/list`中的完整定义，包括输入输出模式；
+//! - [`search_tools`](SEARCH_TOOLS)：在名称和描述中按子串查找（不区分大小写），或按标签筛选。
+//!
+//! 这些工具直接读取工具管理器，运行时添加、删除、启用或禁用的工具立即反映在结果中；
+//! 禁用的工具和这三个工具本身不出现在结果中。它们和其他工具一样经过`tools/call`，
+//! 方法策略、调用前钩子、限流和审计照常生效；方法策略拒绝`tools/list`时，它们也拒绝列出工具。
+//! 三个工具都标注为只读，只读模式下仍然可用。
+//!
+//! ```rust
+//! use rustmcp::RustMCP;
+//!
+//! let mut rustmcp = RustMCP::new();
+//! rustmcp.enable_introspection_tools();
+//! assert!(rustmcp.mcp_list_tools().iter().any(|tool| tool.name == "search_tools"));
+//! ```
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::server::error::{suggest, McpError};
+use crate::server::policy::MethodPolicyState;
+use crate::server::schema::ToolArgs;
+use crate::server::tools::{FunctionTool, ToolAnnotations, ToolManager};
+
+/// 分页列出工具的工具名称
+pub const LIST_REGISTERED_TOOLS: &str = "list_registered_tools";
+
+/// 返回一个工具完整定义的工具名称
+pub const DESCRIBE_TOOL: &str = "describe_tool";
+
+/// 查找工具的工具名称
+pub const SEARCH_TOOLS: &str = "search_tools";
+
+/// `list_registered_tools`每页默认的工具数
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// 自省工具只读取当前的工具表
+#[derive(Clone)]
+struct Registry {
+    tools: ToolManager,
+    policy: Arc<MethodPolicyState>,
+}
+
+impl Registry {
+    /// 按名称排序的工具定义，不含自省工具；方法策略拒绝`tools/list`时返回错误
+    fn listed(&self) -> Result<Vec<Value>, String> {
+        if !self.policy.permits("tools/list") {
+            return Err("Listing tools is not allowed by the method policy".to_string());
+        }
+        let listing = self.tools.tool_listing().map_err(|e| e.to_string())?;
+        Ok(listing
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|tool| !is_introspection_tool(tool["name"].as_str().unwrap_or_default()))
+            .cloned()
+            .collect())
+    }
+}
+
+/// 是否为自省工具
+fn is_introspection_tool(name: &str) -> bool {
+    matches!(name, LIST_REGISTERED_TOOLS | DESCRIBE_TOOL | SEARCH_TOOLS)
+}
+
+/// 工具的名称、描述和标签
+fn summary(tool: &Value) -> Value {
+    json!({
+        "name": tool["name"],
+        "description": tool["description"],
+        "tags": tool.get("tags").cloned().unwrap_or_else(|| json!([])),
+    })
+}
+
+/// 读取可选的字符串参数
+fn string_arg<'a>(args: &'a Option<HashMap<String, Value>>, name: &str) -> Option<&'a str> {
+    args.as_ref().and_then(|args| args.get(name)).and_then(Value::as_str)
+}
+
+/// 创建三个自省工具
+pub(crate) fn tools(manager: ToolManager, policy: Arc<MethodPolicyState>) -> Vec<FunctionTool> {
+    let registry = Registry { tools: manager, policy };
+    let read_only = ToolAnnotations {
+        read_only_hint: Some(true),
+        ..ToolAnnotations::default()
+    };
+
+    let list = {
+        let registry = registry.clone();
+        FunctionTool::builder()
+            .name(LIST_REGISTERED_TOOLS)
+            .description("Lists the registered tools with their names, descriptions and tags, sorted by name")
+            .args(
+                ToolArgs::new()
+                    .string("cursor", "The nextCursor from the previous page", false)
+                    .integer("limit", "Maximum number of tools to return (default 50)", false),
+            )
+            .validate_input(true)
+            .annotations(read_only.clone())
+            .handler(move |args| {
+                let tools = registry.listed()?;
+                let limit = args
+                    .as_ref()
+                    .and_then(|args| args.get("limit"))
+                    .and_then(Value::as_u64)
+                    .map_or(DEFAULT_PAGE_SIZE, |limit| usize::try_from(limit).unwrap_or(usize::MAX))
+                    .max(1);
+                let start = match string_arg(&args, "cursor") {
+                    Some(cursor) => tools.partition_point(|tool| tool["name"].as_str().unwrap_or_default() <= cursor),
+                    None => 0,
+                };
+                let end = start.saturating_add(limit).min(tools.len());
+                let mut result = json!({ "tools": tools[start..end].iter().map(summary).collect::<Vec<_>>() });
+                if end < tools.len() {
+                    result["nextCursor"] = tools[end - 1]["name"].clone();
+                }
+                Ok(result)
+            })
+            .build()
+    };
+
+    let describe = {
+        let registry = registry.clone();
+        FunctionTool::builder()
+            .name(DESCRIBE_TOOL)
+            .description("Returns the full definition of one tool, including its input and output schemas")
+            .args(ToolArgs::new().string("name", "The tool name", true))
+            .validate_input(true)
+            .annotations(read_only.clone())
+            .handler(move |args| {
+                let name = string_arg(&args, "name").unwrap_or_default();
+                let tools = registry.listed()?;
+                match tools.iter().find(|tool| tool["name"] == name) {
+                    Some(tool) => Ok(tool.clone()),
+                    None => {
+                        let names: Vec<String> = tools.iter().filter_map(|tool| tool["name"].as_str().map(str::to_string)).collect();
+                        let error = McpError::ToolNotFound {
+                            name: name.to_string(),
+                            suggestions: suggest(name, &names),
+                        };
+                        Err(error.to_string())
+                    }
+                }
+            })
+            .build()
+    };
+
+    let search = FunctionTool::builder()
+        .name(SEARCH_TOOLS)
+        .description("Finds tools whose name or description contains the query (case-insensitive), or that carry the tag")
+        .args(
+            ToolArgs::new()
+                .string("query", "Text to look for in tool names and descriptions", false)
+                .string("tag", "Only return tools with this tag", false),
+        )
+        .validate_input(true)
+        .annotations(read_only)
+        .handler(move |args| {
+            let query = string_arg(&args, "query").map(str::to_lowercase);
+            let tag = string_arg(&args, "tag");
+            if query.is_none() && tag.is_none() {
+                return Err("Provide 'query', 'tag' or both".to_string());
+            }
+            let tools = registry.listed()?;
+            let matches: Vec<Value> = tools
+                .iter()
+                .filter(|tool| {
+                    query.as_ref().is_none_or(|query| {
+                        ["name", "description"]
+                            .iter()
+                            .any(|field| tool[*field].as_str().is_some_and(|text| text.to_lowercase().contains(query.as_str())))
+                    })
+                })
+                .filter(|tool| {
+                    tag.is_none_or(|tag| tool["tags"].as_array().is_some_and(|tags| tags.iter().any(|t| t == tag)))
+                })
+                .map(summary)
+                .collect();
+            Ok(json!({ "tools": matches }))
+        })
+        .build();
+
+    vec![list, describe, search]
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::server::test_support::{request, stdio};
+    use crate::{FunctionTool, McpError, MethodPolicy, RustMCP, ToolArgs};
+
+    async fn call(rustmcp: &RustMCP, name: &str, arguments: Value) -> Value {
+        let responses = stdio(rustmcp, &[
+            request(1, "initialize", json!({})),
+            request(2, "tools/call", json!({ "name": name, "arguments": arguments })),
+        ])
+        .await;
+        responses[1].clone()
+    }
+
+    fn text(result: &Value) -> Value {
+        serde_json::from_str(result["result"]["content"][0]["text"].as_str().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn introspection_tools_see_the_live_registry() {
+        let mut rustmcp = RustMCP::new();
+        rustmcp.on_before_tool_call(|name, arguments, _ctx| match (name, arguments.get("name")) {
+            ("describe_tool", Some(target)) if target == "rotate_keys" => Err(McpError::Unauthorized("not allowed".to_string())),
+            _ => Ok(()),
+        });
+        rustmcp.enable_introspection_tools();
+
+        // 启用之后添加的工具也能查到
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("send_email")
+                .description("Sends an email to a recipient")
+                .tags(vec!["mail".to_string()])
+                .args(ToolArgs::new().string("to", "Recipient address", true))
+                .handler(|_| Ok(json!("sent")))
+                .build(),
+        );
+        rustmcp.add_tool(FunctionTool::simple("rotate_keys", "Rotates the signing keys", |_| Ok(json!("rotated"))));
+        let send_email = json!({ "name": "send_email", "description": "Sends an email to a recipient", "tags": ["mail"] });
+
+        let found = text(&call(&rustmcp, "search_tools", json!({ "query": "EMAIL" })).await);
+        assert_eq!(found, json!({ "tools": [send_email] }));
+        let found = text(&call(&rustmcp, "search_tools", json!({ "tag": "mail" })).await);
+        assert_eq!(found["tools"][0]["name"], "send_email");
+
+        // 结果中没有自省工具本身
+        let listed = text(&call(&rustmcp, "list_registered_tools", json!({ "limit": 1 })).await);
+        assert_eq!((listed["tools"][0]["name"].as_str(), listed["nextCursor"].as_str()), (Some("rotate_keys"), Some("rotate_keys")));
+        let listed = text(&call(&rustmcp, "list_registered_tools", json!({ "cursor": "rotate_keys" })).await);
+        assert_eq!(listed, json!({ "tools": [send_email] }));
+
+        let described = text(&call(&rustmcp, "describe_tool", json!({ "name": "send_email" })).await);
+        assert_eq!(described["inputSchema"]["required"], json!(["to"]));
+        assert_eq!(described["tags"], json!(["mail"]));
+        let missing = call(&rustmcp, "describe_tool", json!({ "name": "list_registered_tools" })).await;
+        assert_eq!(missing["result"]["isError"], true);
+
+        // 调用前钩子和方法策略同样生效
+        let denied = call(&rustmcp, "describe_tool", json!({ "name": "rotate_keys" })).await;
+        assert_eq!(denied["error"]["code"], -32010);
+        rustmcp.set_method_policy(Some(MethodPolicy::deny(["tools/list"])));
+        let denied = call(&rustmcp, "search_tools", json!({ "query": "email" })).await;
+        assert_eq!(denied["result"]["isError"], true);
+    }
+}
@@ -0,0 +1,166 @@
+//! 会话生命周期
+//!
+//! MCP要求客户端先完成`initialize`，再发送其他请求。[`LifecyclePolicy`]决定在这之前到达的
+//! 请求如何处理：严格模式返回`-32002`，宽松模式允许请求并为会话合成默认的初始化状态。
+//! `Auto`（默认）在有会话的传输（WebSocket、标准输入输出、带`Mcp-Session-Id`的HTTP）上严格，
+//! 在没有会话的HTTP POST上宽松，方便用curl直接调试。
+//!
+//! 处理函数可以通过[`Context::lifecycle_policy`](crate::Context::lifecycle_policy)和
+//! [`Context::is_negotiated`](crate::Context::is_negotiated)判断当前会话是否完成了协商。
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+use crate::server::context::TransportKind;
+
+/// 初始化之前到达的请求的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LifecyclePolicy {
+    /// 拒绝，返回`-32002`
+    Strict,
+    /// 允许，并为会话合成默认的初始化状态
+    Lenient,
+    /// 有会话的传输上严格，没有会话的HTTP请求上宽松
+    #[default]
+    Auto,
+}
+
+impl LifecyclePolicy {
+    /// 按传输解析`Auto`，返回`Strict`或`Lenient`
+    pub fn resolve(self, transport: TransportKind, has_session: bool) -> Self {
+        match self {
+            LifecyclePolicy::Auto => match transport {
+                TransportKind::Http if !has_session => LifecyclePolicy::Lenient,
+                TransportKind::Direct => LifecyclePolicy::Lenient,
+                _ => LifecyclePolicy::Strict,
+            },
+            policy => policy,
+        }
+    }
+}
+
+/// 会话的初始化状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LifecycleState {
+    /// 还没有收到`initialize`
+    #[default]
+    Uninitialized,
+    /// 客户端完成了`initialize`
+    Negotiated,
+    /// 宽松策略下没有经过`initialize`，使用默认状态
+    Synthesized,
+}
+
+/// 会话初始化状态的共享句柄，同一会话的所有请求共享同一个状态
+#[derive(Debug, Clone, Default)]
+pub struct Lifecycle {
+    state: Arc<Mutex<LifecycleState>>,
+}
+
+impl Lifecycle {
+    /// 创建未初始化的状态
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 当前状态
+    pub fn state(&self) -> LifecycleState {
+        *self.state.lock().unwrap()
+    }
+
+    /// 记录客户端完成了`initialize`
+    pub(crate) fn mark_negotiated(&self) {
+        *self.state.lock().unwrap() = LifecycleState::Negotiated;
+    }
+
+    /// 按策略检查请求是否可以在当前状态下处理，宽松策略下会合成默认状态
+    pub(crate) fn admit(&self, policy: LifecyclePolicy) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match (*state, policy) {
+            (LifecycleState::Uninitialized, LifecyclePolicy::Strict) => false,
+            (LifecycleState::Uninitialized, _) => {
+                *state = LifecycleState::Synthesized;
+                true
+            }
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::server::test_support::{call, spawn_server, WsClient};
+    use crate::server::{create_app, FunctionTool, RustMCP};
+    use crate::settings::Settings;
+
+    /// 报告工具看到的策略和初始化状态
+    fn server(policy: LifecyclePolicy) -> RustMCP {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_lifecycle_policy(policy));
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("state")
+                .handler_with_context(|ctx, _| Ok(Value::String(format!("{:?} {:?}", ctx.lifecycle_policy(), ctx.lifecycle_state()))))
+                .build(),
+        );
+        rustmcp
+    }
+
+    /// 调用`state`工具，成功时返回工具看到的状态，失败时返回错误码
+    fn outcome(response: &Value) -> Result<String, i64> {
+        match response["error"]["code"].as_i64() {
+            Some(code) => Err(code),
+            None => Ok(serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap()),
+        }
+    }
+
+    #[test]
+    fn auto_resolves_by_transport() {
+        let resolve = |transport, has_session| LifecyclePolicy::Auto.resolve(transport, has_session);
+        assert_eq!(resolve(TransportKind::Http, false), LifecyclePolicy::Lenient);
+        assert_eq!(resolve(TransportKind::Http, true), LifecyclePolicy::Strict);
+        assert_eq!(resolve(TransportKind::Ws, false), LifecyclePolicy::Strict);
+        assert_eq!(resolve(TransportKind::Stdio, false), LifecyclePolicy::Strict);
+        assert_eq!(resolve(TransportKind::Direct, false), LifecyclePolicy::Lenient);
+        assert_eq!(LifecyclePolicy::Strict.resolve(TransportKind::Http, false), LifecyclePolicy::Strict);
+        assert_eq!(LifecyclePolicy::Lenient.resolve(TransportKind::Ws, true), LifecyclePolicy::Lenient);
+    }
+
+    #[tokio::test]
+    async fn policies_over_http_without_a_session() {
+        let cases = [
+            (LifecyclePolicy::Strict, Err(-32002)),
+            (LifecyclePolicy::Lenient, Ok("Lenient Synthesized")),
+            (LifecyclePolicy::Auto, Ok("Lenient Synthesized")),
+        ];
+        for (policy, expected) in cases {
+            let app = create_app(server(policy));
+            let response = call(&app, "tools/call", json!({ "name": "state" })).await;
+            assert_eq!(outcome(&response), expected.map(str::to_string), "{:?}", policy);
+        }
+    }
+
+    #[tokio::test]
+    async fn policies_over_websocket() {
+        let cases = [
+            (LifecyclePolicy::Strict, Err(-32002)),
+            (LifecyclePolicy::Lenient, Ok("Lenient Synthesized")),
+            (LifecyclePolicy::Auto, Err(-32002)),
+        ];
+        for (policy, expected) in cases {
+            let addr = spawn_server(&server(policy)).await;
+            let mut client = WsClient::connect(addr).await;
+            let response = client.call(1, "tools/call", json!({ "name": "state" })).await;
+            assert_eq!(outcome(&response), expected.map(str::to_string), "{:?}", policy);
+
+            // initialize之后所有策略都允许请求，会话标记为已协商
+            let mut client = WsClient::initialized(addr).await;
+            let response = client.call(1, "tools/call", json!({ "name": "state" })).await;
+            let resolved = policy.resolve(TransportKind::Ws, false);
+            assert_eq!(outcome(&response), Ok(format!("{:?} Negotiated", resolved)), "{:?}", policy);
+        }
+    }
+}
@@ -0,0 +1,323 @@
+//! 导出为LLM函数调用API使用的工具定义
+//!
+//! 同一组工具除了提供给MCP客户端，也可以直接交给支持函数调用的LLM API：
+//! - [`FunctionTool::to_openai_function`]：OpenAI的`{"type":"function","function":{"name","description","parameters"}}`；
+//! - [`FunctionTool::to_anthropic_tool`]：Anthropic的`{"name","description","input_schema"}`；
+//! - [`ToolManager::export_openai_functions`]和[`ToolManager::export_anthropic_tools`]导出所有启用的工具，按名称排序。
+//!
+//! 两家API都要求名称匹配`^[a-zA-Z0-9_-]{1,64}$`。合法的名称保持不变；其他名称把不允许的字符替换为`_`，
+//! 截断后加上原名称SHA-256的前8位十六进制，保证映射是确定的且不同的名称不会冲突。
+//! 模型调用改写后的名称时，用[`ToolManager::resolve_llm_name`]找回原来的工具名称。
+//! 描述截断到[`MAX_DESCRIPTION_CHARS`]个字符，没有输入模式的工具导出为没有属性的对象模式。
+//!
+//! ```rust
+//! use rustmcp::server::ToolManager;
+//! use rustmcp::FunctionTool;
+//! use serde_json::json;
+//!
+//! let manager = ToolManager::new();
+//! manager.add_tool(FunctionTool::simple("weather.lookup", "Looks up the weather", |_| Ok(json!("sunny"))));
+//! let functions = manager.export_openai_functions();
+//! let name = functions[0]["function"]["name"].as_str().unwrap();
+//! assert_eq!(manager.resolve_llm_name(name).as_deref(), Some("weather.lookup"));
+//! ```
+//!
+//! ## 执行模型的函数调用
+//!
+//! 模型返回函数调用（导出的名称和JSON参数字符串）后，[`ToolManager::dispatch_llm_call`]把它交给注册的工具：
+//! 按导出名称找回工具，用[`parse_llm_arguments`]解析参数，按输入模式宽松转换参数类型（不受
+//! [`ToolManager::lenient_coercion`]设置影响），校验后调用工具。导出名称不存在时返回带有相近导出名称建议的
+//! [`McpError::ToolNotFound`]。[`RustMCP::dispatch_llm_call`](crate::RustMCP::dispatch_llm_call)还会执行钩子、
+//! 只读检查和审计。[`to_llm_result_string`]把结果转换为回传给模型的文本。
+//!
+//! ```rust
+//! use rustmcp::server::llm::to_llm_result_string;
+//! use rustmcp::server::ToolManager;
+//! use rustmcp::FunctionTool;
+//! use serde_json::json;
+//!
+//! let manager = ToolManager::new();
+//! manager.add_tool(FunctionTool::simple("echo", "Echoes its arguments", |args| Ok(json!(args))));
+//! // 模型返回的函数名和参数字符串
+//! let result = manager.dispatch_llm_call("echo", r#"{"text": "hi"}"#);
+//! assert_eq!(to_llm_result_string(&result, 100), r#"{"text":"hi"}"#);
+//! ```
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::server::error::{suggest, McpError};
+use crate::server::tools::{FunctionTool, ToolManager};
+use crate::server::validation::{coerce_arguments, type_name};
+use crate::server::Context;
+
+/// 函数名称的最大长度，OpenAI和Anthropic相同
+pub const MAX_NAME_LEN: usize = 64;
+
+/// 导出时描述的最大字符数
+pub const MAX_DESCRIPTION_CHARS: usize = 1024;
+
+/// 改写名称时附加的哈希的十六进制位数
+const HASH_SUFFIX_LEN: usize = 8;
+
+/// 工具在LLM函数调用API中使用的名称
+///
+/// 已经匹配`^[a-zA-Z0-9_-]{1,64}$`的名称原样返回，其他名称替换不允许的字符、截断，
+/// 并附加原名称的哈希。
+pub fn llm_name(name: &str) -> String {
+    if !name.is_empty() && name.len() <= MAX_NAME_LEN && name.chars().all(allowed) {
+        return name.to_string();
+    }
+    let base: String = name
+        .chars()
+        .map(|c| if allowed(c) { c } else { '_' })
+        .take(MAX_NAME_LEN - HASH_SUFFIX_LEN - 1)
+        .collect();
+    let hash = Sha256::digest(name.as_bytes());
+    let hex: String = hash.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("{}_{}", base, &hex[..HASH_SUFFIX_LEN])
+}
+
+/// 名称中允许的字符
+fn allowed(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// 截断到指定字符数
+fn truncate(text: &str, max_chars: usize) -> String {
+    text.chars().take(max_chars).collect()
+}
+
+/// 解析模型给出的参数字符串
+///
+/// 模型生成的参数不总是规范的JSON对象：空字符串和`null`按没有参数处理，Markdown代码块的围栏被去掉，
+/// 被再次编码成JSON字符串的对象会被解开。其他不是对象的值返回参数错误。
+pub fn parse_llm_arguments(arguments_json: &str) -> Result<Option<HashMap<String, Value>>, McpError> {
+    let text = strip_code_fence(arguments_json.trim());
+    if text.is_empty() {
+        return Ok(None);
+    }
+    let mut value: Value = serde_json::from_str(text).map_err(|e| {
+        McpError::invalid_params(format!("Invalid arguments JSON: {}", e), "arguments", "object")
+    })?;
+    if let Value::String(inner) = &value {
+        if let Ok(decoded @ Value::Object(_)) = serde_json::from_str(inner) {
+            value = decoded;
+        }
+    }
+    match value {
+        Value::Null => Ok(None),
+        Value::Object(arguments) => Ok(Some(arguments.into_iter().collect())),
+        other => Err(McpError::invalid_params(
+            format!("Invalid type for 'arguments': expected object, got {}", type_name(&other)),
+            "arguments",
+            "object",
+        )),
+    }
+}
+
+/// 去掉包住整个文本的Markdown代码块围栏和语言标记
+fn strip_code_fence(text: &str) -> &str {
+    let Some(rest) = text.strip_prefix("```") else {
+        return text;
+    };
+    let rest = rest.strip_suffix("```").unwrap_or(rest);
+    match rest.split_once('\n') {
+        Some((_language, body)) => body.trim(),
+        None => rest.trim(),
+    }
+}
+
+/// 把工具调用结果转换为回传给模型的文本，最多`max_chars`个字符
+///
+/// 字符串结果原样使用，其他值序列化为紧凑的JSON，错误转换为`Error: `加错误消息。
+/// 超出长度时截断，最后一个字符替换为`…`。
+pub fn to_llm_result_string(result: &Result<Value, McpError>, max_chars: usize) -> String {
+    let text = match result {
+        Ok(Value::String(text)) => text.clone(),
+        Ok(value) => value.to_string(),
+        Err(e) => format!("Error: {}", e),
+    };
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    let mut truncated = truncate(&text, max_chars.saturating_sub(1));
+    truncated.push('…');
+    truncated
+}
+
+impl FunctionTool {
+    /// 输入模式，没有时为没有属性的对象模式
+    fn parameters(&self) -> Value {
+        self.input_schema
+            .clone()
+            .unwrap_or_else(|| serde_json::json!({ "type": "object", "properties": {} }))
+    }
+
+    /// OpenAI函数调用使用的工具定义
+    pub fn to_openai_function(&self) -> Value {
+        serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": llm_name(&self.name),
+                "description": truncate(&self.description, MAX_DESCRIPTION_CHARS),
+                "parameters": self.parameters()
+            }
+        })
+    }
+
+    /// Anthropic tool use接口使用的工具定义
+    pub fn to_anthropic_tool(&self) -> Value {
+        serde_json::json!({
+            "name": llm_name(&self.name),
+            "description": truncate(&self.description, MAX_DESCRIPTION_CHARS),
+            "input_schema": self.parameters()
+        })
+    }
+}
+
+impl ToolManager {
+    /// 所有启用的工具的OpenAI函数定义，按工具名称排序
+    pub fn export_openai_functions(&self) -> Vec<Value> {
+        self.list_function_tools().iter().map(FunctionTool::to_openai_function).collect()
+    }
+
+    /// 所有启用的工具的Anthropic工具定义，按工具名称排序
+    pub fn export_anthropic_tools(&self) -> Vec<Value> {
+        self.list_function_tools().iter().map(FunctionTool::to_anthropic_tool).collect()
+    }
+
+    /// 导出名称到工具名称的映射，包括名称没有改写的工具
+    pub fn llm_names(&self) -> HashMap<String, String> {
+        self.list_tools()
+            .into_iter()
+            .map(|tool| (llm_name(&tool.name), tool.name))
+            .collect()
+    }
+
+    /// 按导出名称查找启用的工具的名称
+    pub fn resolve_llm_name(&self, name: &str) -> Option<String> {
+        self.list_tools().into_iter().map(|tool| tool.name).find(|tool| llm_name(tool) == name)
+    }
+
+    /// 执行模型发起的函数调用，参见[模块文档](self)
+    pub fn dispatch_llm_call(&self, name: &str, arguments_json: &str) -> Result<Value, McpError> {
+        let (name, arguments) = self.prepare_llm_call(name, arguments_json)?;
+        self.call_tool(&name, arguments, &Context::new())
+    }
+
+    /// 找回工具名称，解析并宽松转换参数
+    pub(crate) fn prepare_llm_call(
+        &self,
+        name: &str,
+        arguments_json: &str,
+    ) -> Result<(String, Option<HashMap<String, Value>>), McpError> {
+        let tools = self.list_tools();
+        let Some(tool) = tools.iter().find(|tool| llm_name(&tool.name) == name) else {
+            let names: Vec<String> = tools.iter().map(|tool| llm_name(&tool.name)).collect();
+            return Err(McpError::ToolNotFound {
+                name: name.to_string(),
+                suggestions: suggest(name, &names),
+            });
+        };
+        let mut arguments = parse_llm_arguments(arguments_json)?;
+        if let (Some(schema), Some(arguments)) = (&tool.input_schema, arguments.as_mut()) {
+            coerce_arguments(schema, arguments);
+        }
+        Ok((tool.name.clone(), arguments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::ToolArgs;
+
+    #[test]
+    fn tools_are_exported_with_valid_names() {
+        let manager = ToolManager::new();
+        manager.add_tool(
+            FunctionTool::builder()
+                .name("weather.lookup")
+                .description("Looks up the current weather for a city")
+                .args(ToolArgs::new().string("city", "City name", true))
+                .handler(|_| Ok(json!("sunny")))
+                .build(),
+        );
+        manager.add_tool(FunctionTool::simple("echo", "Echoes its arguments", |args| Ok(json!(args))));
+
+        let parameters = json!({
+            "type": "object",
+            "properties": { "city": { "type": "string", "description": "City name" } },
+            "required": ["city"]
+        });
+        assert_eq!(manager.export_openai_functions(), vec![
+            json!({
+                "type": "function",
+                "function": {
+                    "name": "echo",
+                    "description": "Echoes its arguments",
+                    "parameters": { "type": "object", "properties": {} }
+                }
+            }),
+            json!({
+                "type": "function",
+                "function": {
+                    "name": "weather_lookup_c5e04a1f",
+                    "description": "Looks up the current weather for a city",
+                    "parameters": parameters
+                }
+            }),
+        ]);
+        assert_eq!(manager.export_anthropic_tools()[1], json!({
+            "name": "weather_lookup_c5e04a1f",
+            "description": "Looks up the current weather for a city",
+            "input_schema": parameters
+        }));
+
+        // 模型调用改写后的名称时找回原来的工具
+        assert_eq!(manager.resolve_llm_name("weather_lookup_c5e04a1f").as_deref(), Some("weather.lookup"));
+        assert_eq!(manager.resolve_llm_name("echo").as_deref(), Some("echo"));
+        assert_eq!(manager.resolve_llm_name("weather.lookup"), None);
+
+        // 描述按字符截断
+        let long = FunctionTool::simple("long", "x".repeat(2000), |_| Ok(json!(null)));
+        assert_eq!(long.to_openai_function()["function"]["description"].as_str().unwrap().len(), 1024);
+    }
+
+    #[test]
+    fn model_calls_are_dispatched_to_tools() {
+        let manager = ToolManager::new();
+        manager.add_tool(
+            FunctionTool::builder()
+                .name("weather.forecast")
+                .description("Forecasts the weather")
+                .args(ToolArgs::new().string("city", "City name", true).integer("days", "Number of days", true))
+                .handler(|args| {
+                    let args = args.unwrap();
+                    Ok(json!({ "city": args["city"], "days": args["days"], "summary": "sunny" }))
+                })
+                .build(),
+        );
+
+        // 导出给模型，模型按导出的名称发起调用，参数是不太规范的JSON字符串
+        let exported = manager.export_openai_functions();
+        let name = exported[0]["function"]["name"].as_str().unwrap();
+        let arguments = "```json\n{\"city\": \"Oslo\", \"days\": \"3\"}\n```";
+        let result = manager.dispatch_llm_call(name, arguments);
+        assert_eq!(result, Ok(json!({ "city": "Oslo", "days": 3, "summary": "sunny" })));
+        assert_eq!(to_llm_result_string(&result, 100), r#"{"city":"Oslo","days":3,"summary":"sunny"}"#);
+        assert_eq!(to_llm_result_string(&result, 10), "{\"city\":\"…");
+
+        // 未知名称返回建议，参数错误照常返回
+        match manager.dispatch_llm_call(&name[..name.len() - 1], "{}") {
+            Err(McpError::ToolNotFound { suggestions, .. }) => assert_eq!(suggestions, [name]),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        let missing = manager.dispatch_llm_call(name, "");
+        assert_eq!(to_llm_result_string(&missing, 100), "Error: Missing required argument 'city'");
+    }
+}
@@ -0,0 +1,171 @@
+//! 按需加载的资源
+//!
+//! 资源目录在数据库里、条目很多时，启动时全部注册会拖慢启动，而大部分资源从来不会被读取。
+//! [`RustMCP::set_lazy_resource_loader`](crate::RustMCP::set_lazy_resource_loader)
+//! （或[`ResourceManager::set_lazy_loader`](crate::ResourceManager::set_lazy_loader)）安装一个[`ResourceLoader`]：
+//! - `resources/read`找不到按URI注册的资源、也没有前缀匹配的[解析器](crate::server::resolvers)时，
+//!   询问加载器；加载的资源缓存在管理器中，之后的读取不再询问加载器，
+//!   [`RustMCP::remove_resource`](crate::RustMCP::remove_resource)清除缓存；
+//! - 加载器返回`None`时返回资源不存在，返回错误时返回带有URI的资源读取错误；
+//! - `resources/list`的分页交给加载器，`cursor`原样传给[`ResourceLoader::list_page`]，返回的游标作为`nextCursor`；
+//!   按URI注册的资源列在第一页的最前面。
+//!
+//! 同步的[`RustMCP::mcp_read_resource`](crate::RustMCP::mcp_read_resource)不询问加载器，只能读取已经加载的资源。
+//!
+//! ```rust
+//! use futures::future::BoxFuture;
+//! use rustmcp::{FunctionResource, ResourceLoader, ResourcePage, RustMCP};
+//! use serde_json::json;
+//!
+//! struct Catalog;
+//!
+//! impl ResourceLoader for Catalog {
+//!     fn load<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Result<Option<FunctionResource>, String>> {
+//!         // 实际的实现在这里查询数据库
+//!         let found = uri.starts_with("catalog://item/");
+//!         Box::pin(async move { Ok(found.then(|| FunctionResource::simple(uri, || Ok(json!("item"))))) })
+//!     }
+//!
+//!     fn list_page<'a>(&'a self, _cursor: Option<&'a str>) -> BoxFuture<'a, Result<ResourcePage, String>> {
+//!         Box::pin(async { Ok((Vec::new(), None)) })
+//!     }
+//! }
+//!
+//! let rustmcp = RustMCP::new();
+//! rustmcp.set_lazy_resource_loader(Catalog);
+//! ```
+
+use futures::future::BoxFuture;
+use std::sync::Arc;
+
+use crate::server::resources::{FunctionResource, Resource};
+
+/// 一页资源和下一页的游标，没有下一页时游标为`None`
+pub type ResourcePage = (Vec<Resource>, Option<String>);
+
+/// 按需加载资源的目录
+///
+/// 方法返回装箱的future，实现时通常写成`Box::pin(async move { ... })`。
+pub trait ResourceLoader: Send + Sync {
+    /// 加载URI对应的资源，目录中没有时返回`None`
+    fn load<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Result<Option<FunctionResource>, String>>;
+
+    /// 列出一页资源，`cursor`为上一页返回的游标，第一页为`None`；返回这一页的资源和下一页的游标
+    fn list_page<'a>(&'a self, cursor: Option<&'a str>) -> BoxFuture<'a, Result<ResourcePage, String>>;
+}
+
+/// 安装的加载器
+#[derive(Clone)]
+pub(crate) struct Loader(pub(crate) Arc<dyn ResourceLoader>);
+
+impl std::fmt::Debug for Loader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Loader").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::BoxFuture;
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::server::test_support::{request, stdio};
+    use crate::RustMCP;
+
+    /// 内存中的目录，记录加载次数
+    #[derive(Default)]
+    struct Catalog {
+        loads: Arc<AtomicUsize>,
+    }
+
+    const ITEMS: u32 = 5;
+
+    impl ResourceLoader for Catalog {
+        fn load<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Result<Option<FunctionResource>, String>> {
+            Box::pin(async move {
+                self.loads.fetch_add(1, Ordering::SeqCst);
+                if uri == "catalog://broken" {
+                    return Err("database unavailable".to_string());
+                }
+                let Some(id) = uri.strip_prefix("catalog://item/").and_then(|id| id.parse::<u32>().ok()).filter(|id| *id < ITEMS) else {
+                    return Ok(None);
+                };
+                Ok(Some(FunctionResource::simple(uri, move || Ok(json!(format!("item {}", id))))))
+            })
+        }
+
+        fn list_page<'a>(&'a self, cursor: Option<&'a str>) -> BoxFuture<'a, Result<ResourcePage, String>> {
+            Box::pin(async move {
+                let start: u32 = cursor.map_or(Ok(0), str::parse).map_err(|_| "invalid cursor".to_string())?;
+                let end = (start + 2).min(ITEMS);
+                let page = (start..end)
+                    .map(|id| FunctionResource::simple(format!("catalog://item/{}", id), || Ok(json!(null))).to_resource())
+                    .collect();
+                Ok((page, (end < ITEMS).then(|| end.to_string())))
+            })
+        }
+    }
+
+    /// 在新的stdio会话中完成initialize后发送一个请求，返回响应
+    async fn send(rustmcp: &RustMCP, method: &str, params: Value) -> Value {
+        let mut output = stdio(rustmcp, &[request(1, "initialize", json!({})), request(2, method, params)]).await;
+        output.pop().unwrap()
+    }
+
+    fn server() -> (RustMCP, Arc<AtomicUsize>) {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_resource(FunctionResource::simple("static://readme", || Ok(json!("read me"))));
+        let catalog = Catalog::default();
+        let loads = catalog.loads.clone();
+        rustmcp.set_lazy_resource_loader(catalog);
+        (rustmcp, loads)
+    }
+
+    #[tokio::test]
+    async fn resources_are_loaded_once_and_cached() {
+        let (rustmcp, loads) = server();
+        let read = |uri: &str| json!({ "uri": uri });
+
+        // 第一次读取时加载，之后使用缓存
+        let first = send(&rustmcp, "resources/read", read("catalog://item/1")).await;
+        assert_eq!(first["result"]["contents"][0]["text"], "item 1");
+        let second = send(&rustmcp, "resources/read", read("catalog://item/1")).await;
+        assert_eq!(second["result"], first["result"]);
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        assert!(rustmcp.mcp_read_resource("catalog://item/1").is_ok());
+        assert!(rustmcp.mcp_read_resource("catalog://item/2").is_err());
+
+        // 注册的资源不询问加载器
+        send(&rustmcp, "resources/read", read("static://readme")).await;
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+        let missing = send(&rustmcp, "resources/read", read("catalog://item/9")).await;
+        assert_eq!(missing["error"]["data"]["kind"], "resource_not_found");
+        let broken = send(&rustmcp, "resources/read", read("catalog://broken")).await;
+        assert_eq!(broken["error"]["data"], json!({ "kind": "resource_read_failed", "uri": "catalog://broken" }));
+        assert_eq!(broken["error"]["message"], "database unavailable");
+    }
+
+    #[tokio::test]
+    async fn listing_is_paged_by_the_loader() {
+        let (rustmcp, _) = server();
+        let mut uris = Vec::new();
+        let mut params = json!({});
+        loop {
+            let page = send(&rustmcp, "resources/list", params).await["result"].take();
+            uris.push(page["resources"].as_array().unwrap().iter().map(|r| r["uri"].as_str().unwrap().to_string()).collect::<Vec<_>>());
+            match page["nextCursor"].as_str() {
+                Some(cursor) => params = json!({ "cursor": cursor }),
+                None => break,
+            }
+        }
+        assert_eq!(uris, [
+            vec!["static://readme", "catalog://item/0", "catalog://item/1"],
+            vec!["catalog://item/2", "catalog://item/3"],
+            vec!["catalog://item/4"],
+        ]);
+    }
+}
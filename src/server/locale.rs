@@ -0,0 +1,283 @@
+//! 工具、资源和提示的本地化标题与描述
+//!
+//! 注册时用`title_localized`/`description_localized`为每种语言提供文本（工具在构建器上设置，
+//! 资源和提示使用`with_title_localized`/`with_description_localized`）。列表方法按调用方的语言偏好选择文本：
+//! - JSON-RPC请求的`params._meta.locale`，格式与`Accept-Language`相同，例如`"fr-CA"`或`"fr-CA, en;q=0.5"`；
+//! - 没有`_meta.locale`时使用HTTP请求头`Accept-Language`，REST接口`/mcp/tools`等同样适用。
+//!
+//! 对每个偏好依次查找完全匹配的语言，再逐级去掉子标签（`zh-Hant-TW` → `zh-Hant` → `zh`）。
+//! 都找不到时保留默认文本。所有本地化文本同时放在每一项的`_meta.localizations`中，
+//! 格式为`{"fr": {"title": ..., "description": ...}}`，需要自行选择语言的客户端可以直接使用。
+//!
+//! ```rust
+//! use rustmcp::FunctionTool;
+//!
+//! let tool = FunctionTool::builder()
+//!     .name("search")
+//!     .description("Searches the catalog")
+//!     .title_localized("fr", "Recherche")
+//!     .description_localized("fr", "Recherche dans le catalogue")
+//!     .handler(|_| Ok(serde_json::json!([])))
+//!     .build();
+//! ```
+
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// 语言标签（BCP 47），比较时不区分大小写
+#[derive(Debug, Clone, Eq)]
+pub struct LanguageTag(String);
+
+impl LanguageTag {
+    /// 创建语言标签
+    pub fn new(tag: impl Into<String>) -> Self {
+        Self(tag.into().trim().to_string())
+    }
+
+    /// 标签文本
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// 去掉最后一个子标签后的标签（`fr-CA` → `fr`），没有更短的形式时为`None`
+    ///
+    /// 去掉后以单字符子标签结尾时（例如`x`），一并去掉。
+    pub fn parent(&self) -> Option<LanguageTag> {
+        let mut tag = &self.0[..self.0.rfind('-')?];
+        if let Some(index) = tag.rfind('-') {
+            if tag.len() - index == 2 {
+                tag = &tag[..index];
+            }
+        }
+        Some(LanguageTag(tag.to_string()))
+    }
+}
+
+impl PartialEq for LanguageTag {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(&other.0)
+    }
+}
+
+impl Hash for LanguageTag {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for byte in self.0.bytes() {
+            state.write_u8(byte.to_ascii_lowercase());
+        }
+    }
+}
+
+impl From<&str> for LanguageTag {
+    fn from(tag: &str) -> Self {
+        Self::new(tag)
+    }
+}
+
+impl From<String> for LanguageTag {
+    fn from(tag: String) -> Self {
+        Self::new(tag)
+    }
+}
+
+impl std::fmt::Display for LanguageTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for LanguageTag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+/// 按语言提供的标题和描述
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Localizations {
+    /// 本地化的标题
+    pub titles: HashMap<LanguageTag, String>,
+    /// 本地化的描述
+    pub descriptions: HashMap<LanguageTag, String>,
+}
+
+impl Localizations {
+    /// 是否没有任何本地化文本
+    pub fn is_empty(&self) -> bool {
+        self.titles.is_empty() && self.descriptions.is_empty()
+    }
+
+    /// 按偏好选择标题，没有匹配的语言时为`None`
+    pub fn title(&self, preferences: &[LanguageTag]) -> Option<&str> {
+        lookup(&self.titles, preferences)
+    }
+
+    /// 按偏好选择描述，没有匹配的语言时为`None`
+    pub fn description(&self, preferences: &[LanguageTag]) -> Option<&str> {
+        lookup(&self.descriptions, preferences)
+    }
+
+    /// `_meta.localizations`的内容，按语言排列；没有本地化文本时为`None`
+    pub(crate) fn to_meta(&self) -> Option<Value> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut languages: BTreeMap<&str, Map<String, Value>> = BTreeMap::new();
+        for (field, texts) in [("title", &self.titles), ("description", &self.descriptions)] {
+            for (tag, text) in texts {
+                languages.entry(tag.as_str()).or_default().insert(field.to_string(), Value::String(text.clone()));
+            }
+        }
+        Some(serde_json::json!(languages))
+    }
+
+    /// 把`localizations`合并到`_meta`中
+    pub(crate) fn merge_into_meta(&self, meta: Option<Value>) -> Option<Value> {
+        let Some(localizations) = self.to_meta() else {
+            return meta;
+        };
+        let mut meta = match meta {
+            Some(Value::Object(map)) => map,
+            _ => Map::new(),
+        };
+        meta.insert("localizations".to_string(), localizations);
+        Some(Value::Object(meta))
+    }
+}
+
+/// 解析`Accept-Language`格式的语言偏好，按权重从高到低排列，去掉`*`和权重为0的语言
+pub fn parse_accept_language(value: &str) -> Vec<LanguageTag> {
+    let mut weighted: Vec<(LanguageTag, f32)> = value
+        .split(',')
+        .filter_map(|part| {
+            let mut pieces = part.split(';');
+            let tag = pieces.next()?.trim();
+            let weight = pieces
+                .filter_map(|piece| piece.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (!tag.is_empty() && tag != "*" && weight > 0.0).then(|| (LanguageTag::new(tag), weight))
+        })
+        .collect();
+    // 稳定排序，权重相同时保持原来的顺序
+    weighted.sort_by(|a, b| b.1.total_cmp(&a.1));
+    weighted.into_iter().map(|(tag, _)| tag).collect()
+}
+
+/// 调用方的语言偏好：请求元数据中的`locale`优先，其次是`Accept-Language`请求头
+pub(crate) fn preferences(request_meta: Option<&Value>, accept_language: Option<&str>) -> Vec<LanguageTag> {
+    request_meta
+        .and_then(|meta| meta.get("locale"))
+        .and_then(Value::as_str)
+        .or(accept_language)
+        .map(parse_accept_language)
+        .unwrap_or_default()
+}
+
+/// 依次按每个偏好查找，先完全匹配，再逐级去掉子标签
+fn lookup<'a>(texts: &'a HashMap<LanguageTag, String>, preferences: &[LanguageTag]) -> Option<&'a str> {
+    preferences.iter().find_map(|preferred| {
+        let mut candidate = Some(preferred.clone());
+        while let Some(tag) = candidate {
+            if let Some(text) = texts.get(&tag) {
+                return Some(text.as_str());
+            }
+            candidate = tag.parent();
+        }
+        None
+    })
+}
+
+/// 按语言偏好替换列表中每一项的`title`和`description`，使用`_meta.localizations`中的文本
+pub(crate) fn localize_listing(listing: &mut Value, preferences: &[LanguageTag]) {
+    if preferences.is_empty() {
+        return;
+    }
+    let Some(items) = listing.as_array_mut() else {
+        return;
+    };
+    for item in items {
+        let Some(localizations) = item.pointer("/_meta/localizations").and_then(Value::as_object) else {
+            continue;
+        };
+        let mut chosen = Vec::new();
+        for field in ["title", "description"] {
+            let texts: HashMap<LanguageTag, String> = localizations
+                .iter()
+                .filter_map(|(tag, texts)| Some((LanguageTag::new(tag.as_str()), texts.get(field)?.as_str()?.to_string())))
+                .collect();
+            if let Some(text) = lookup(&texts, preferences) {
+                chosen.push((field, text.to_string()));
+            }
+        }
+        for (field, text) in chosen {
+            item[field] = Value::String(text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+
+    use crate::server::test_support::{get, request, stdio};
+    use crate::{create_app, FunctionPrompt, FunctionTool, RustMCP};
+
+    fn catalog() -> RustMCP {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("search")
+                .description("Searches the catalog")
+                .description_localized("fr", "Recherche dans le catalogue")
+                .description_localized("de-DE", "Durchsucht den Katalog")
+                .title_localized("fr", "Recherche")
+                .handler(|_| Ok(json!([])))
+                .build(),
+        );
+        rustmcp.add_prompt(FunctionPrompt::simple("plain", |_| Ok(vec![])).with_title("Plain"));
+        rustmcp
+    }
+
+    #[tokio::test]
+    async fn lists_follow_the_requested_locale() {
+        let list = |id: u64, locale: &str| request(id, "tools/list", json!({ "_meta": { "locale": locale } }));
+        let responses = stdio(&catalog(), &[
+            request(1, "initialize", json!({ "protocolVersion": "2025-06-18" })),
+            list(2, "de-DE"),
+            list(3, "fr-CA"),
+            list(4, "ja, en;q=0.5"),
+            request(5, "tools/list", json!({})),
+            request(6, "prompts/list", json!({ "_meta": { "locale": "fr" } })),
+        ])
+        .await;
+        let tool = |id: u64| responses.iter().find(|r| r["id"] == id).unwrap()["result"]["tools"][0].clone();
+
+        // 完全匹配
+        assert_eq!(tool(2)["description"], "Durchsucht den Katalog");
+        assert!(tool(2).get("title").is_none());
+        // fr-CA退回fr
+        assert_eq!(tool(3)["description"], "Recherche dans le catalogue");
+        assert_eq!(tool(3)["title"], "Recherche");
+        // 没有匹配的语言或没有偏好时使用默认文本
+        assert_eq!(tool(4)["description"], "Searches the catalog");
+        assert_eq!(tool(5)["description"], "Searches the catalog");
+        assert_eq!(tool(5)["_meta"]["localizations"]["fr"]["title"], "Recherche");
+        assert_eq!(tool(5)["_meta"]["localizations"]["de-DE"]["description"], "Durchsucht den Katalog");
+
+        // 没有本地化文本的提示保持不变
+        let prompt: &Value = &responses[5]["result"]["prompts"][0];
+        assert_eq!(prompt["title"], "Plain");
+        assert!(prompt.get("_meta").is_none());
+    }
+
+    #[tokio::test]
+    async fn rest_listing_uses_accept_language() {
+        let app = create_app(catalog());
+        let (status, _, tools) = get(&app, "/mcp/tools", &[("accept-language", "fr-CA, en;q=0.5")]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(tools[0]["description"], "Recherche dans le catalogue");
+    }
+}
@@ -0,0 +1,129 @@
+//! 声明式工具清单
+//!
+//! 清单目录热加载（`watch`特性）和管理接口（`admin`特性）共用的工具描述：名称、描述、
+//! 输入模式，以及一个后端——执行命令的模板或转发请求的HTTP地址。
+//!
+//! ```json
+//! {
+//!   "name": "greet",
+//!   "description": "Says hello",
+//!   "schema": {
+//!     "type": "object",
+//!     "properties": { "who": { "type": "string" } },
+//!     "required": ["who"]
+//!   },
+//!   "command": ["echo", "hello {who}"]
+//! }
+//! ```
+//!
+//! 命令不经过shell，模板的每个元素单独替换`{参数名}`后作为一个参数传给程序。
+//! `proxy`后端创建[远程绑定](crate::server::tools::ToolBinding::Remote)的工具，把工具参数作为JSON请求体发送到目标地址，
+//! 响应体为JSON时原样返回，否则作为字符串返回：
+//!
+//! ```json
+//! {
+//!   "name": "lookup",
+//!   "description": "Looks up a user",
+//!   "proxy": { "url": "http://127.0.0.1:9000/lookup", "headers": { "x-api-key": "${env:LOOKUP_API_KEY}" } }
+//! }
+//! ```
+//!
+//! 命令模板的元素、`proxy.url`和请求头的值可以引用密钥（`${env:NAME}`或`${file:/path}`），
+//! 见[`secrets`](crate::server::secrets)。环境变量在创建工具时读取（转发目标在每次调用时重新读取），缺失时注册失败；
+//! 文件在每次调用时读取。密钥的值不会被`{参数名}`替换，工具返回的错误中的密钥值会被脱敏。
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::process::Command;
+
+use crate::server::command::interpolate;
+use crate::server::proxy::ProxyTarget;
+use crate::server::secrets::{self, SecretTemplate};
+use crate::server::tools::FunctionTool;
+
+/// 工具清单
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ToolManifest {
+    /// 工具名称
+    pub name: String,
+    /// 工具标题
+    #[serde(default)]
+    pub title: Option<String>,
+    /// 工具描述
+    #[serde(default)]
+    pub description: String,
+    /// 输入模式，设置后调用前会校验参数
+    #[serde(default)]
+    pub schema: Option<Value>,
+    /// 命令模板，第一个元素为程序名
+    #[serde(default)]
+    pub command: Option<Vec<String>>,
+    /// 转发调用的HTTP目标
+    #[serde(default)]
+    pub proxy: Option<ProxyTarget>,
+}
+
+impl ToolManifest {
+    /// 检查清单并创建工具，清单必须恰好声明一个后端
+    pub fn into_tool(self) -> Result<FunctionTool, String> {
+        if self.name.is_empty() {
+            return Err("name must not be empty".to_string());
+        }
+        let mut builder = FunctionTool::builder().name(self.name).description(self.description);
+        builder = match (self.command, self.proxy) {
+            (Some(command), None) => {
+                if command.is_empty() {
+                    return Err("command must not be empty".to_string());
+                }
+                let handler = command_handler(command)?;
+                builder.handler(move |args: Option<HashMap<String, Value>>| {
+                    handler(args.unwrap_or_default()).map_err(|e| secrets::redact(&e))
+                })
+            }
+            (None, Some(proxy)) => {
+                proxy.check()?;
+                builder.remote(proxy)
+            }
+            (Some(_), Some(_)) => return Err("only one of 'command' and 'proxy' may be set".to_string()),
+            (None, None) => return Err("one of 'command' or 'proxy' is required".to_string()),
+        };
+        if let Some(title) = self.title {
+            builder = builder.title(title);
+        }
+        if let Some(schema) = self.schema {
+            builder = builder.input_schema(schema).validate_input(true);
+        }
+        Ok(builder.build())
+    }
+}
+
+/// 清单后端的调用函数
+type Handler = Box<dyn Fn(HashMap<String, Value>) -> Result<Value, String> + Send + Sync>;
+
+/// 执行命令的后端
+fn command_handler(command: Vec<String>) -> Result<Handler, String> {
+    let command = parse_templates(&command)?;
+    Ok(Box::new(move |args| {
+        let argv = command
+            .iter()
+            .map(|part| part.render(|text| interpolate(text, &args)))
+            .collect::<Result<Vec<String>, _>>()?;
+        let output = Command::new(&argv[0])
+            .args(&argv[1..])
+            .output()
+            .map_err(|e| format!("Failed to run '{}': {}", argv[0], e))?;
+        if output.status.success() {
+            Ok(Value::String(String::from_utf8_lossy(&output.stdout).into_owned()))
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(format!("'{}' exited with {}: {}", argv[0], output.status, stderr.trim()))
+        }
+    }))
+}
+
+/// 解析每个元素中的密钥引用
+fn parse_templates(parts: &[String]) -> Result<Vec<SecretTemplate>, String> {
+    parts.iter().map(|part| SecretTemplate::parse(part).map_err(String::from)).collect()
+}
@@ -0,0 +1,165 @@
+//! 自定义JSON-RPC方法
+//!
+//! 通过[`RustMCP::add_method_handler`](crate::RustMCP::add_method_handler)注册不属于MCP规范的方法
+//! （例如`x-myco/reindex`）。分发器在内置方法之后、返回`-32601`之前查找这些方法，
+//! 因此它们在所有传输上的行为都相同。
+//!
+//! 注册表中还预置了随crate提供的扩展方法（[`EXTENSION_METHODS`]），例如批量调用工具的
+//! [`x-rustmcp/tools/call_batch`](crate::server::batch)和取回未送达响应的
+//! [`x-rustmcp/session/pending`](crate::server::pending)。它们的名称同样不能被自定义方法覆盖。
+
+use futures::future::BoxFuture;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::server::{batch, pending};
+use crate::server::error::McpError;
+use crate::server::{Context, RustMCP};
+
+/// 内置方法名称，不能被自定义方法覆盖
+pub const BUILTIN_METHODS: &[&str] = &[
+    "initialize",
+    "notifications/initialized",
+    "tools/list",
+    "tools/call",
+    "resources/list",
+    "resources/read",
+    "resources/templates/list",
+    "prompts/list",
+    "prompts/get",
+];
+
+/// 随crate提供的扩展方法名称，不能被自定义方法覆盖
+pub const EXTENSION_METHODS: &[&str] = &[batch::METHOD, pending::METHOD];
+
+/// MCP规范定义的其他方法名称，服务器没有实现，但也不能用作自定义通知
+const SPEC_METHODS: &[&str] = &[
+    "ping",
+    "completion/complete",
+    "logging/setLevel",
+    "resources/subscribe",
+    "resources/unsubscribe",
+    "sampling/createMessage",
+    "roots/list",
+    "elicitation/create",
+];
+
+/// 自定义方法处理函数
+pub type MethodHandler = Arc<dyn Fn(Option<Value>, Context) -> BoxFuture<'static, Result<Value, McpError>> + Send + Sync>;
+
+/// 扩展方法处理函数，额外收到处理请求的服务器
+pub(crate) type ExtensionHandler =
+    Arc<dyn Fn(RustMCP, Option<Value>, Context) -> BoxFuture<'static, Result<Value, McpError>> + Send + Sync>;
+
+/// 判断方法名是否为内置方法
+pub fn is_builtin_method(method: &str) -> bool {
+    BUILTIN_METHODS.contains(&method)
+}
+
+/// 判断方法名是否被MCP规范保留（包括内置方法）
+pub fn is_spec_reserved(method: &str) -> bool {
+    is_builtin_method(method) || SPEC_METHODS.contains(&method)
+}
+
+/// 自定义方法注册表
+#[derive(Clone)]
+pub struct MethodRegistry {
+    handlers: HashMap<String, MethodHandler>,
+    extensions: HashMap<&'static str, ExtensionHandler>,
+}
+
+impl Default for MethodRegistry {
+    fn default() -> Self {
+        let mut extensions: HashMap<&'static str, ExtensionHandler> = HashMap::new();
+        extensions.insert(batch::METHOD, Arc::new(|rustmcp, params, ctx| Box::pin(batch::call_batch(rustmcp, params, ctx))));
+        extensions.insert(pending::METHOD, Arc::new(|rustmcp, params, ctx| Box::pin(pending::take_pending(rustmcp, params, ctx))));
+        Self {
+            handlers: HashMap::new(),
+            extensions,
+        }
+    }
+}
+
+impl MethodRegistry {
+    /// 创建只包含随crate提供的扩展方法的注册表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册方法处理函数，内置方法名和扩展方法名返回错误
+    pub fn insert(&mut self, method: &str, handler: MethodHandler) -> Result<(), McpError> {
+        if is_builtin_method(method) || EXTENSION_METHODS.contains(&method) {
+            return Err(McpError::ReservedMethod(method.to_string()));
+        }
+        self.handlers.insert(method.to_string(), handler);
+        Ok(())
+    }
+
+    /// 查找方法处理函数
+    pub fn get(&self, method: &str) -> Option<&MethodHandler> {
+        self.handlers.get(method)
+    }
+
+    /// 查找随crate提供的扩展方法
+    pub(crate) fn extension(&self, method: &str) -> Option<&ExtensionHandler> {
+        self.extensions.get(method)
+    }
+}
+
+impl std::fmt::Debug for MethodRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.handlers.keys().map(String::as_str).chain(self.extensions.keys().copied())).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::server::test_support::{call, request, stdio};
+    use crate::server::{create_app, RustMCP};
+
+    fn reindex_server() -> RustMCP {
+        let mut rustmcp = RustMCP::new();
+        rustmcp
+            .add_method_handler("x-myco/reindex", |params, ctx| async move {
+                let shards = params.as_ref().and_then(|params| params["shards"].as_u64()).unwrap_or(1);
+                Ok(json!({ "reindexed": shards, "transport": format!("{:?}", ctx.request().transport) }))
+            })
+            .unwrap();
+        rustmcp
+    }
+
+    #[tokio::test]
+    async fn custom_methods_work_over_http_and_stdio() {
+        let rustmcp = reindex_server();
+        let response = call(&create_app(rustmcp.clone()), "x-myco/reindex", json!({ "shards": 3 })).await;
+        assert_eq!(response["result"], json!({ "reindexed": 3, "transport": "Http" }));
+
+        let lines = stdio(
+            &rustmcp,
+            &[
+                request(1, "initialize", json!({})),
+                request(2, "x-myco/reindex", json!({ "shards": 5 })),
+                request(3, "x-myco/unknown", json!({})),
+            ],
+        )
+        .await;
+        let response = |id: u64| lines.iter().find(|line| line["id"] == id).unwrap();
+        assert_eq!(response(2)["result"], json!({ "reindexed": 5, "transport": "Stdio" }));
+        assert_eq!(response(3)["error"]["code"], -32601);
+    }
+
+    #[test]
+    fn builtin_methods_cannot_be_overridden() {
+        let mut rustmcp = RustMCP::new();
+        for method in ["tools/call", "initialize", "prompts/get"] {
+            let result = rustmcp.add_method_handler(method, |_, _| async { Ok(json!(null)) });
+            assert!(matches!(result, Err(McpError::ReservedMethod(ref name)) if name == method), "{}", method);
+        }
+        assert!(BUILTIN_METHODS.iter().all(|method| is_builtin_method(method)));
+        assert!(!is_builtin_method("x-myco/reindex"));
+    }
+}
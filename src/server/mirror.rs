@@ -0,0 +1,488 @@
+//! 把只读请求镜像到影子实例
+//!
+//! 上线新的工具实现之前，可以把生产流量的一部分复制给影子实例（另一个[`RustMCP`]或远程的`POST /mcp`地址），
+//! 比较两边的响应，客户端只会收到主实例的响应：
+//! - 默认只镜像列表方法（`tools/list`、`resources/list`、`resources/templates/list`、`prompts/list`），
+//!   以及注解中`readOnlyHint`为`true`的工具的`tools/call`；可能修改状态或没有注解的工具不会被镜像，
+//!   除非用[`MirrorConfig::tool`]显式加入；
+//! - 按采样率每N个符合条件的请求镜像1个，影子请求在后台执行，不等待结果；同时执行的影子请求达到
+//!   [`MirrorConfig::max_in_flight`]时新的请求直接跳过，计入`dropped`；
+//! - 比较前去掉`id`和所有`_meta`，并把UUID和时间戳替换为占位符；不一致时记录一条[`Divergence`]，
+//!   列出两边不同的JSON路径，保存在有界的环形缓冲区中，通过[`RustMCP::divergences`](crate::RustMCP::divergences)
+//!   或管理接口的`GET /mirror/divergences`读取。
+//!
+//! 影子实例使用主会话协商的协议版本，两边的响应按同样的版本裁剪。记录中的参数和响应与
+//! [采集](crate::server::capture)一样脱敏。远程地址需要`http-client`特性。
+//!
+//! ```rust
+//! use rustmcp::{MirrorConfig, RustMCP};
+//!
+//! let primary = RustMCP::new();
+//! // 影子实例注册新的工具实现
+//! let shadow = RustMCP::new();
+//! // 每10个符合条件的请求镜像1个，另外镜像没有只读注解的`search`工具
+//! primary.enable_mirror(MirrorConfig::instance(shadow).sample_rate(10).tool("search"));
+//! ```
+
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
+
+use crate::protocol::{JsonRpcRequest, JsonRpcResponse};
+use crate::server::capture::Redacted;
+use crate::server::connections::Connection;
+use crate::server::lifecycle::{Lifecycle, LifecyclePolicy};
+use crate::server::session::SessionStore;
+use crate::server::{dispatch, secrets, testing, RequestContext, RustMCP, TransportKind};
+
+/// 默认镜像的列表方法
+pub const DEFAULT_MIRRORED_METHODS: &[&str] = &["tools/list", "resources/list", "resources/templates/list", "prompts/list"];
+
+/// 影子实例
+#[derive(Debug, Clone)]
+pub enum MirrorTarget {
+    /// 同一进程中的另一个实例
+    Instance(Arc<RustMCP>),
+    /// 远程服务器的`POST /mcp`地址
+    Url(String),
+}
+
+/// 镜像设置
+#[derive(Debug, Clone)]
+pub struct MirrorConfig {
+    /// 接收镜像请求的影子实例
+    pub target: MirrorTarget,
+    /// 镜像的方法，`tools/call`由工具的注解和[`tools`](Self::tools)决定，不在这里列出
+    pub methods: Vec<String>,
+    /// 无论注解如何都镜像的工具
+    pub tools: Vec<String>,
+    /// 是否镜像注解中`readOnlyHint`为`true`的工具
+    pub read_only_tools: bool,
+    /// 采样率：每N个符合条件的请求镜像1个
+    pub sample_rate: u64,
+    /// 同时执行的影子请求上限
+    pub max_in_flight: usize,
+    /// 环形缓冲区保存的不一致记录条数
+    pub capacity: usize,
+    /// 影子请求的时限
+    pub timeout: Duration,
+}
+
+impl MirrorConfig {
+    /// 镜像到同一进程中的另一个实例
+    pub fn instance(shadow: RustMCP) -> Self {
+        Self::new(MirrorTarget::Instance(Arc::new(shadow)))
+    }
+
+    /// 镜像到远程服务器的`POST /mcp`地址
+    pub fn url(url: impl Into<String>) -> Self {
+        Self::new(MirrorTarget::Url(url.into()))
+    }
+
+    fn new(target: MirrorTarget) -> Self {
+        Self {
+            target,
+            methods: DEFAULT_MIRRORED_METHODS.iter().map(|method| method.to_string()).collect(),
+            tools: Vec::new(),
+            read_only_tools: true,
+            sample_rate: 1,
+            max_in_flight: 4,
+            capacity: 100,
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// 替换镜像的方法
+    pub fn methods<I, S>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.methods = methods.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 镜像调用指定工具的请求，即使它可能修改状态
+    pub fn tool(mut self, name: impl Into<String>) -> Self {
+        self.tools.push(name.into());
+        self
+    }
+
+    /// 设置是否镜像只读工具
+    pub fn read_only_tools(mut self, enabled: bool) -> Self {
+        self.read_only_tools = enabled;
+        self
+    }
+
+    /// 每`rate`个符合条件的请求镜像1个，0按1处理
+    pub fn sample_rate(mut self, rate: u64) -> Self {
+        self.sample_rate = rate;
+        self
+    }
+
+    /// 设置同时执行的影子请求上限，0按1处理
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight;
+        self
+    }
+
+    /// 设置缓冲区保存的不一致记录条数
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// 设置影子请求的时限
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 请求是否符合镜像条件
+    fn eligible(&self, rustmcp: &RustMCP, method: &str, tool: Option<&str>) -> bool {
+        if method != "tools/call" {
+            return self.methods.iter().any(|m| m == method);
+        }
+        let Some(name) = tool else { return false };
+        self.tools.iter().any(|t| t == name)
+            || (self.read_only_tools
+                && rustmcp
+                    .tool_manager
+                    .get_tool(name)
+                    .and_then(|tool| tool.annotations)
+                    .is_some_and(|annotations| annotations.read_only_hint == Some(true)))
+    }
+}
+
+/// 主实例和影子实例的一次不一致
+#[derive(Debug, Clone, Serialize)]
+pub struct Divergence {
+    /// 记录时间（Unix毫秒）
+    pub timestamp_ms: u64,
+    /// JSON-RPC方法
+    pub method: String,
+    /// `tools/call`调用的工具
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// 脱敏后的请求参数
+    pub params: Value,
+    /// 两边不同的JSON路径，形如`$.result.content[0].text: "10" != "12"`
+    pub differences: Vec<String>,
+    /// 规范化、脱敏后的主实例响应
+    pub primary: Value,
+    /// 规范化、脱敏后的影子实例响应
+    pub shadow: Value,
+}
+
+/// 镜像计数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MirrorStats {
+    /// 发给影子实例的请求
+    pub mirrored: u64,
+    /// 响应一致的请求
+    pub matched: u64,
+    /// 响应不一致的请求
+    pub diverged: u64,
+    /// 影子请求达到上限而跳过的请求
+    pub dropped: u64,
+    /// 影子请求失败或超时的请求
+    pub failed: u64,
+}
+
+/// 开启时的设置和并发许可
+#[derive(Debug)]
+struct Active {
+    config: MirrorConfig,
+    permits: Arc<Semaphore>,
+}
+
+/// 镜像开关、计数和不一致记录，克隆的[`RustMCP`]共享同一份
+#[derive(Debug, Default)]
+pub(crate) struct Mirror {
+    active: RwLock<Option<Arc<Active>>>,
+    /// 符合条件的请求计数，用于采样
+    seen: AtomicU64,
+    mirrored: AtomicU64,
+    matched: AtomicU64,
+    diverged: AtomicU64,
+    dropped: AtomicU64,
+    failed: AtomicU64,
+    divergences: Mutex<VecDeque<Divergence>>,
+}
+
+impl Mirror {
+    /// 开启镜像并替换设置，清空计数和之前的记录
+    pub(crate) fn enable(&self, config: MirrorConfig) {
+        let permits = Arc::new(Semaphore::new(config.max_in_flight.max(1)));
+        for counter in [&self.seen, &self.mirrored, &self.matched, &self.diverged, &self.dropped, &self.failed] {
+            counter.store(0, Ordering::Relaxed);
+        }
+        self.divergences.lock().clear();
+        *self.active.write() = Some(Arc::new(Active { config, permits }));
+    }
+
+    /// 关闭镜像，正在执行的影子请求仍会完成，已有的记录保留
+    pub(crate) fn disable(&self) {
+        *self.active.write() = None;
+    }
+
+    pub(crate) fn stats(&self) -> MirrorStats {
+        MirrorStats {
+            mirrored: self.mirrored.load(Ordering::Relaxed),
+            matched: self.matched.load(Ordering::Relaxed),
+            diverged: self.diverged.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 按时间顺序返回不一致记录
+    pub(crate) fn divergences(&self) -> Vec<Divergence> {
+        self.divergences.lock().iter().cloned().collect()
+    }
+
+    /// 清空不一致记录
+    pub(crate) fn clear(&self) {
+        self.divergences.lock().clear();
+    }
+
+    /// 请求需要镜像时复制一份，符合条件的请求计入采样；应在处理请求之前调用
+    pub(crate) fn select(
+        self: &Arc<Self>,
+        rustmcp: &RustMCP,
+        request: &JsonRpcRequest,
+        tool: Option<&str>,
+        request_context: &RequestContext,
+    ) -> Option<MirrorJob> {
+        let active = self.active.read().clone()?;
+        if request.is_notification() || !active.config.eligible(rustmcp, &request.method, tool) {
+            return None;
+        }
+        if !self.seen.fetch_add(1, Ordering::Relaxed).is_multiple_of(active.config.sample_rate.max(1)) {
+            return None;
+        }
+        Some(MirrorJob {
+            mirror: self.clone(),
+            active,
+            request: request.clone(),
+            tool: tool.map(str::to_string),
+            primary_connection: request_context.connection.clone(),
+            #[cfg(feature = "http-client")]
+            http_client: rustmcp.http_client(),
+        })
+    }
+
+    fn record(&self, job: &MirrorJob, primary: Value, shadow: Value) {
+        let (primary, shadow) = (normalize(primary), normalize(shadow));
+        let mut differences = Vec::new();
+        testing::diff("$", &primary, &shadow, &mut differences);
+        if differences.is_empty() {
+            self.matched.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        self.diverged.fetch_add(1, Ordering::Relaxed);
+        log::debug!("Shadow response for {} differs at {} path(s)", job.request.method, differences.len());
+        let params = job.request.params.as_ref().map_or(Redacted::Value(&Value::Null), Redacted::Value);
+        let divergence = Divergence {
+            timestamp_ms: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0),
+            method: job.request.method.clone(),
+            tool: job.tool.clone(),
+            params: redacted(&params),
+            differences: differences.iter().map(|difference| secrets::redact(difference)).collect(),
+            primary: redacted(&primary),
+            shadow: redacted(&shadow),
+        };
+        let mut divergences = self.divergences.lock();
+        while divergences.len() >= job.active.config.capacity.max(1) {
+            divergences.pop_front();
+        }
+        divergences.push_back(divergence);
+    }
+}
+
+/// 一个等待发给影子实例的请求
+pub(crate) struct MirrorJob {
+    mirror: Arc<Mirror>,
+    active: Arc<Active>,
+    request: JsonRpcRequest,
+    tool: Option<String>,
+    /// 主会话的连接信息，影子请求使用同样的协商结果
+    primary_connection: Option<Arc<Connection>>,
+    #[cfg(feature = "http-client")]
+    http_client: Arc<crate::server::http_client::HttpClient>,
+}
+
+impl MirrorJob {
+    /// 在后台把请求发给影子实例并比较响应，影子请求达到上限时跳过
+    pub(crate) fn submit(self, primary: &JsonRpcResponse) {
+        let Ok(permit) = self.active.permits.clone().try_acquire_owned() else {
+            self.mirror.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        };
+        self.mirror.mirrored.fetch_add(1, Ordering::Relaxed);
+        let primary = serde_json::to_value(primary).unwrap_or_default();
+        tokio::spawn(async move {
+            let _permit = permit;
+            let shadow = match tokio::time::timeout(self.active.config.timeout, self.send()).await {
+                Ok(Ok(shadow)) => shadow,
+                Ok(Err(e)) => {
+                    self.mirror.failed.fetch_add(1, Ordering::Relaxed);
+                    log::warn!("Mirrored {} request failed: {}", self.request.method, e);
+                    return;
+                }
+                Err(_) => {
+                    self.mirror.failed.fetch_add(1, Ordering::Relaxed);
+                    log::warn!("Mirrored {} request timed out", self.request.method);
+                    return;
+                }
+            };
+            self.mirror.record(&self, primary, shadow);
+        });
+    }
+
+    async fn send(&self) -> Result<Value, String> {
+        match &self.active.config.target {
+            MirrorTarget::Instance(shadow) => {
+                let response = dispatch::dispatch_request(shadow, self.request.clone(), self.shadow_context(shadow)).await;
+                serde_json::to_value(response).map_err(|e| e.to_string())
+            }
+            MirrorTarget::Url(url) => self.post(url).await,
+        }
+    }
+
+    /// 影子实例的请求上下文：已初始化的临时会话，协商结果与主会话相同
+    fn shadow_context(&self, shadow: &RustMCP) -> Arc<RequestContext> {
+        let connection = Connection::new("mirror", TransportKind::Direct, None);
+        if let Some(primary) = &self.primary_connection {
+            let (client_info, capabilities) = (primary.client_info(), primary.client_capabilities());
+            connection.record_initialize(primary.protocol_version().as_deref(), client_info.as_ref(), capabilities.as_ref());
+        }
+        let lifecycle = Lifecycle::new();
+        lifecycle.mark_negotiated();
+        Arc::new(RequestContext {
+            session: Some(SessionStore::ephemeral(shadow.settings.session_limits())),
+            lifecycle,
+            lifecycle_policy: LifecyclePolicy::Lenient,
+            connection: Some(connection),
+            ..RequestContext::new(TransportKind::Direct)
+        })
+    }
+
+    #[cfg(feature = "http-client")]
+    async fn post(&self, url: &str) -> Result<Value, String> {
+        let (client, url, request) = (self.http_client.clone(), url.to_string(), self.request.clone());
+        tokio::task::spawn_blocking(move || {
+            let body = serde_json::to_value(request).map_err(|e| e.to_string())?;
+            let response = client
+                .request("POST", &url, None)
+                .send_json(body)
+                .map_err(|e| format!("Request to {} failed: {}", url, e))?;
+            response.into_json::<Value>().map_err(|e| format!("Invalid response from {}: {}", url, e))
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    #[cfg(not(feature = "http-client"))]
+    async fn post(&self, _url: &str) -> Result<Value, String> {
+        Err("Mirroring to a URL requires the 'http-client' feature".to_string())
+    }
+}
+
+/// 去掉`id`、`jsonrpc`和所有`_meta`，再替换每次运行都会变化的值
+fn normalize(mut response: Value) -> Value {
+    if let Value::Object(map) = &mut response {
+        map.remove("id");
+        map.remove("jsonrpc");
+    }
+    testing::normalize(strip_meta(response))
+}
+
+fn strip_meta(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(key, _)| key != "_meta")
+                .map(|(key, value)| (key, strip_meta(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(strip_meta).collect()),
+        other => other,
+    }
+}
+
+/// 按采集的规则脱敏：名称像敏感信息的字段和读取过的密钥的值
+fn redacted(value: &impl Serialize) -> Value {
+    let text = secrets::redact(&serde_json::to_string(value).unwrap_or_default());
+    serde_json::from_str(&text).unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::server::test_support::{request, stdio};
+    use crate::{FunctionTool, MirrorConfig, RustMCP, ToolAnnotations};
+
+    fn server(price: i64, deletes: Arc<AtomicUsize>) -> RustMCP {
+        let rustmcp = RustMCP::new();
+        let read_only = ToolAnnotations { read_only_hint: Some(true), ..Default::default() };
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("price")
+                .annotations(read_only)
+                .handler(move |_| Ok(json!(price)))
+                .build(),
+        );
+        rustmcp.add_tool(FunctionTool::simple("delete", "Deletes an item", move |_| {
+            deletes.fetch_add(1, Ordering::SeqCst);
+            Ok(json!("deleted"))
+        }));
+        rustmcp
+    }
+
+    #[tokio::test]
+    async fn read_only_requests_are_mirrored_and_compared() {
+        let (primary_deletes, shadow_deletes) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let primary = server(10, primary_deletes.clone());
+        // 影子实例中的新实现返回了不同的价格
+        let shadow = server(12, shadow_deletes.clone());
+        primary.enable_mirror(MirrorConfig::instance(shadow));
+
+        let responses = stdio(&primary, &[
+            request(0, "initialize", json!({})),
+            request(1, "tools/list", json!({})),
+            request(2, "tools/call", json!({ "name": "price" })),
+            request(3, "tools/call", json!({ "name": "delete" })),
+        ])
+        .await;
+
+        // 客户端只看到主实例的结果
+        assert_eq!(responses[2]["result"]["content"][0]["text"], "10");
+
+        // 影子请求在后台执行
+        while primary.mirror_stats().matched + primary.mirror_stats().diverged < 2 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let stats = primary.mirror_stats();
+        assert_eq!((stats.mirrored, stats.matched, stats.diverged), (2, 1, 1));
+
+        let divergences = primary.divergences();
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].method, "tools/call");
+        assert_eq!(divergences[0].tool.as_deref(), Some("price"));
+        assert_eq!(divergences[0].differences, vec![r#"$.result.content[0].text: "10" != "12""#]);
+
+        // 可能修改状态的工具不会被镜像
+        assert_eq!(primary_deletes.load(Ordering::SeqCst), 1);
+        assert_eq!(shadow_deletes.load(Ordering::SeqCst), 0);
+    }
+}
@@ -10,37 +10,25 @@
 //! ## 使用示例
 //!
 //! ```rust
-//! use rustmcp::{RustMCP, FunctionTool, create_app};
+//! use rustmcp::{RustMCP, FunctionTool, ToolArgs, create_app};
 //!
 //! // 创建RustMCP实例
-//! let mut rustmcp = RustMCP::new();
+//! let rustmcp = RustMCP::new();
 //!
 //! // 添加工具
-//! let echo_tool = FunctionTool::from_function(
-//!     |_args: Option<std::collections::HashMap<String, serde_json::Value>>| -> Result<serde_json::Value, String> {
-//!         let message = _args
+//! let echo_tool = FunctionTool::builder()
+//!     .name("echo")
+//!     .description("Echoes back the provided message")
+//!     .args(ToolArgs::new().string("message", "The message to echo", true))
+//!     .handler(|args| {
+//!         let message = args
 //!             .as_ref()
 //!             .and_then(|m| m.get("message"))
 //!             .and_then(|v| v.as_str())
 //!             .unwrap_or("Hello, World!");
 //!         Ok(serde_json::Value::String(message.to_string()))
-//!     },
-//!     Some("echo".to_string()),
-//!     Some("Echoes back the provided message".to_string()),
-//!     Some(vec!["utility".to_string()]),
-//!     Some(serde_json::json!({
-//!         "type": "object",
-//!         "properties": {
-//!             "message": {
-//!                 "type": "string",
-//!                 "description": "The message to echo"
-//!             }
-//!         },
-//!         "required": ["message"]
-//!     })),
-//!     None,
-//!     None,
-//! );
+//!     })
+//!     .build();
 //! rustmcp.add_tool(echo_tool);
 //!
 //! // 创建Axum应用
@@ -53,36 +41,196 @@
 //! - [resources](resources/index.html): 资源管理实现
 //! - [prompts](prompts/index.html): 提示管理实现
 //! - [ws](ws/index.html): WebSocket支持实现
+//! - [content](content/index.html): 内容块定义
+//! - [error](error/index.html): MCP错误类型
+//! - [hooks](hooks/index.html): 调用钩子
+//! - [context](context/index.html): 请求上下文
+//! - [state](state/index.html): 共享的应用状态
+//! - [methods](methods/index.html): 自定义JSON-RPC方法
+//! - [notifications](notifications/index.html): 服务器发起的通知
+//! - [schema](schema/index.html): 工具参数声明
+//! - [validation](validation/index.html): 参数校验
+//! - [stdio](stdio/index.html): 标准输入输出传输
+//! - [capture](capture/index.html): 按需的请求/响应内容采集
+//! - [trace](trace/index.html): 协议跟踪
+//! - [health](health/index.html): 依赖健康检查
+//! - [audit](audit/index.html): 工具调用审计日志
+//! - [idempotency](idempotency/index.html): `tools/call`的幂等键
+//! - [secrets](secrets/index.html): 声明式工具中的密钥引用
+//! - [connections](connections/index.html): 连接和会话查询
+//! - [streaming](streaming/index.html): 大资源的分块读取
+//! - [prompt_streaming](prompt_streaming/index.html): 大提示的流式生成
+//! - [llm](llm/index.html): 导出为LLM函数调用API的工具定义
+//! - [command](command/index.html): 执行外部程序的工具
+//! - [info](info/index.html): 启动信息和`/mcp/info`
+//! - [policy](policy/index.html): JSON-RPC方法的允许/拒绝列表
+//! - [testing](testing/index.html): 传输一致性测试
+//! - [compression](compression/index.html): HTTP响应压缩
+//! - [generation](generation/index.html): 列表的注册表代数、ETag和条件请求
+//! - [serialization](serialization/index.html): 列表的逐项序列化
+//! - [quota](quota/index.html): 工具和资源的用量配额
+//! - [shutdown](shutdown/index.html): 优雅关闭和关闭通知
+//! - [admission](admission/index.html): 并发请求数统计和过载保护
+//! - [introspection](introspection/index.html): 查询注册表的内置工具
+//! - [pending](pending/index.html): WebSocket断开后保留未送达的响应
+//! - [tags](tags/index.html): 标签目录和严格标签
+//! - [http_client](http_client/index.html): 共用的出站HTTP客户端
+//! - [loader](loader/index.html): 按需加载的资源
+//! - [watchdog](watchdog/index.html): 阻塞工具的看门狗
+//! - [error_shaping](error_shaping/index.html): 工具错误文本的整理
+//! - [body](body/index.html): JSON-RPC请求体的提取
+//! - [tool_hooks](tool_hooks/index.html): 工具的启动和关闭钩子
+//! - [mirror](mirror/index.html): 把只读请求镜像到影子实例
+//! - [openapi](openapi/index.html): OpenAPI导入（需要`openapi`特性）
+//! - [wasm](wasm/index.html): WASM沙箱工具（需要`wasm`特性）
+//! - [admin](admin/index.html): 运行时管理接口（需要`admin`特性）
 
 pub mod tools;
 pub mod resources;
 pub mod prompts;
 pub mod ws;
+pub mod content;
+pub mod error;
+pub mod hooks;
+pub mod context;
+pub mod state;
+pub mod methods;
+pub mod notifications;
+pub mod schema;
+pub mod validation;
+pub mod stdio;
+pub mod session;
+pub mod retry;
+pub mod lifecycle;
+pub mod diagnostics;
+pub mod capture;
+pub mod mirror;
+pub mod trace;
+pub mod health;
+pub mod audit;
+pub mod idempotency;
+pub mod secrets;
+pub mod connections;
+pub mod streaming;
+pub mod prompt_streaming;
+pub mod llm;
+pub mod command;
+pub mod command_policy;
+pub mod info;
+pub mod policy;
+pub mod batch;
+pub mod definitions;
+pub mod locale;
+pub mod resolvers;
+pub mod sse;
+pub mod features;
+pub mod proxy;
+pub mod truncation;
+pub mod compression;
+pub mod generation;
+pub mod serialization;
+pub mod quota;
+pub mod shutdown;
+pub mod admission;
+pub mod introspection;
+pub mod pending;
+pub mod tags;
+pub mod http_client;
+pub mod loader;
+pub mod watchdog;
+pub mod error_shaping;
+pub mod body;
+pub mod tool_hooks;
+pub mod probe;
+pub mod testing;
+#[cfg(feature = "openapi")]
+pub mod openapi;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "admin")]
+pub mod admin;
+#[cfg(any(feature = "watch", feature = "admin"))]
+mod manifest;
+mod dispatch;
+mod ws_deflate;
+mod api_doc;
+mod rate_limit;
+#[cfg(test)]
+mod test_support;
 
 use axum::{
-    extract::{State},
-    response::{IntoResponse, Json},
+    extract::{ConnectInfo, Extension, Path, Query, State},
+    response::{sse::{KeepAlive, Sse}, IntoResponse, Json},
     http::StatusCode,
     http::HeaderMap,
     body::Bytes,
     routing::{get, post},
     Router,
 };
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use futures::StreamExt;
+use base64::Engine;
 
-// 重新导出主要类型
-pub use tools::{ToolManager, FunctionTool, DuplicateBehavior as ToolDuplicateBehavior};
-pub use resources::{ResourceManager, Resource, FunctionResource, DuplicateBehavior as ResourceDuplicateBehavior};
-pub use prompts::{PromptManager, Prompt, FunctionPrompt, PromptMessage, DuplicateBehavior as PromptDuplicateBehavior};
+use crate::protocol::{InitializeParams, InitializeResult, JsonRpcRequest, JsonRpcResponse, RequestId, INVALID_REQUEST};
+use rate_limit::RateLimiter;
+use hooks::Hooks;
+use methods::{MethodHandler, MethodRegistry};
+use notifications::{Notifier, Outbox, Push};
+use session::{HttpSession, SessionRegistry};
+use connections::{ConnectionGuard, ConnectionRegistry};
+use capture::CaptureBuffer;
+use mirror::Mirror;
+use health::HealthRegistry;
+use audit::AuditLog;
+use idempotency::Idempotency;
+use crate::settings::Settings;
 
-/// RustMCP上下文
-#[derive(Debug, Clone)]
-pub struct Context {
-    // 可以添加上下文相关字段
-}
+// 重新导出主要类型
+pub use tools::{ToolManager, FunctionTool, FunctionToolBuilder, Tool, ToolBinding, ToolExample, ToolGroup, ToolPage, DuplicateBehavior as ToolDuplicateBehavior};
+pub use proxy::ProxyTarget;
+pub use truncation::TruncationPolicy;
+pub use serialization::SerializationPolicy;
+pub use quota::{Quota, QuotaStatus};
+pub use resources::{ResourceManager, Resource, ResourceAnnotations, Role, FunctionResource, ResourceContents, ResourceStream, ResourceStreamFunction, DuplicateBehavior as ResourceDuplicateBehavior};
+use resources::{ResourceBody, ResourceRegistrar};
+use body::JsonRpcBody;
+pub use resolvers::{ResourceResolver, ResourceTemplate};
+pub use loader::{ResourceLoader, ResourcePage};
+pub use features::{Feature, ProtocolVersion, VersionGate};
+pub use prompts::{PromptManager, Prompt, PromptArgument, PromptBuilder, PromptContent, PromptExample, PromptPage, FunctionPrompt, PromptMessage, PromptStream, PromptStreamFunction, TurnPolicy, DuplicateBehavior as PromptDuplicateBehavior};
+use prompts::PromptBody;
+pub use content::{Content, ContentError};
+pub use schema::{ToolArgs, ArgKind, ArgSpec};
+pub use error::McpError;
+pub use context::{Context, RequestContext, TransportKind, Identity, CancellationToken};
+pub use state::StateMap;
+pub use notifications::{Delivery, SessionChannel};
+pub use session::{SessionError, SessionEviction, SessionLimits, SessionMetrics, SessionStore};
+pub use retry::{Backoff, RetryPolicy};
+pub use lifecycle::{Lifecycle, LifecyclePolicy, LifecycleState};
+pub use diagnostics::{ItemKind, Severity, ValidationIssue};
+pub use capture::{Capture, CaptureConfig};
+pub use mirror::{Divergence, MirrorConfig, MirrorStats, MirrorTarget};
+pub use trace::TraceConfig;
+pub use health::{DependencyState, DependencyStatus, HealthCheck};
+pub use tool_hooks::{HookState, ToolHookStatus, ToolStartupError};
+pub use audit::{AuditEntry, AuditOutcome, AuditSink, JsonlAuditSink, MemoryAuditSink};
+pub use idempotency::{IdempotencyStore, MemoryIdempotencyStore, StoredResult};
+pub use secrets::{SecretError, SecretTemplate};
+pub use connections::SessionInfo;
+pub use tags::TagInfo;
+pub use admission::LoadMetrics;
+pub use policy::MethodPolicy;
+pub use command::CommandTool;
+pub use command_policy::{ParsedCommand, PolicyError, SafeCommandPolicy};
+pub use locale::{LanguageTag, Localizations};
+pub use stdio::serve_stdio;
 
 /// RustMCP核心类
 #[derive(Debug, Clone)]
@@ -90,6 +238,40 @@ pub struct RustMCP {
     tool_manager: ToolManager,
     resource_manager: ResourceManager,
     prompt_manager: PromptManager,
+    settings: Settings,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    hooks: Hooks,
+    method_handlers: MethodRegistry,
+    notifier: Notifier,
+    state: Arc<StateMap>,
+    sessions: Arc<SessionRegistry>,
+    connections: Arc<ConnectionRegistry>,
+    read_only: Arc<AtomicBool>,
+    captures: Arc<CaptureBuffer>,
+    /// 请求镜像，参见[`mirror`]
+    mirror: Arc<Mirror>,
+    tracer: Arc<trace::Tracer>,
+    /// 阻塞工具的看门狗，未开启时为`None`
+    watchdog: Option<Arc<watchdog::Watchdog>>,
+    /// 整理工具错误文本
+    error_shaper: Arc<error_shaping::ErrorShaper>,
+    dropped_notifications: Arc<AtomicU64>,
+    health: Arc<HealthRegistry>,
+    audit: Option<Arc<AuditLog>>,
+    idempotency: Arc<Idempotency>,
+    initialize_cache: Arc<dispatch::InitializeCache>,
+    info: Arc<info::InfoSnapshot>,
+    method_policy: Arc<policy::MethodPolicyState>,
+    shutdown: Arc<shutdown::Shutdown>,
+    /// 工具启动和关闭钩子的执行记录
+    tool_hooks: Arc<tool_hooks::HookRegistry>,
+    admission: Arc<admission::Admission>,
+    pending: Arc<pending::PendingResults>,
+    tags: Arc<tags::TagCatalog>,
+    /// 出站HTTP客户端配置错误，启动时报告
+    http_client_error: Option<String>,
+    /// 在`initialize`结果的`capabilities.experimental`中声明的能力
+    experimental_capabilities: Arc<serde_json::Map<String, Value>>,
 }
 
 impl RustMCP {
@@ -99,6 +281,34 @@ impl RustMCP {
             tool_manager: ToolManager::new(),
             resource_manager: ResourceManager::new(),
             prompt_manager: PromptManager::new(),
+            settings: Settings::new(),
+            rate_limiter: None,
+            hooks: Hooks::new(),
+            method_handlers: MethodRegistry::new(),
+            notifier: Notifier::new(),
+            state: initial_state(),
+            sessions: Arc::new(SessionRegistry::default()),
+            connections: Arc::default(),
+            read_only: Arc::new(AtomicBool::new(false)),
+            captures: Arc::default(),
+            mirror: Arc::default(),
+            tracer: Arc::default(),
+            watchdog: None,
+            error_shaper: Arc::new(error_shaping::ErrorShaper::new(Settings::new().max_error_bytes, &[])),
+            dropped_notifications: Arc::default(),
+            health: Arc::default(),
+            audit: None,
+            idempotency: Arc::new(Idempotency::new(Arc::new(MemoryIdempotencyStore::new()))),
+            initialize_cache: Arc::default(),
+            info: Arc::default(),
+            method_policy: Arc::default(),
+            shutdown: Arc::default(),
+            tool_hooks: Arc::default(),
+            admission: Arc::default(),
+            pending: Arc::default(),
+            tags: Arc::default(),
+            http_client_error: None,
+            experimental_capabilities: Arc::default(),
         }
     }
     
@@ -112,29 +322,1093 @@ impl RustMCP {
             tool_manager: ToolManager::with_behavior(tool_behavior),
             resource_manager: ResourceManager::with_behavior(resource_behavior),
             prompt_manager: PromptManager::with_behavior(prompt_behavior),
+            settings: Settings::new(),
+            rate_limiter: None,
+            hooks: Hooks::new(),
+            method_handlers: MethodRegistry::new(),
+            notifier: Notifier::new(),
+            state: initial_state(),
+            sessions: Arc::new(SessionRegistry::default()),
+            connections: Arc::default(),
+            read_only: Arc::new(AtomicBool::new(false)),
+            captures: Arc::default(),
+            mirror: Arc::default(),
+            tracer: Arc::default(),
+            watchdog: None,
+            error_shaper: Arc::new(error_shaping::ErrorShaper::new(Settings::new().max_error_bytes, &[])),
+            dropped_notifications: Arc::default(),
+            health: Arc::default(),
+            audit: None,
+            idempotency: Arc::new(Idempotency::new(Arc::new(MemoryIdempotencyStore::new()))),
+            initialize_cache: Arc::default(),
+            info: Arc::default(),
+            method_policy: Arc::default(),
+            shutdown: Arc::default(),
+            tool_hooks: Arc::default(),
+            admission: Arc::default(),
+            pending: Arc::default(),
+            tags: Arc::default(),
+            http_client_error: None,
+            experimental_capabilities: Arc::default(),
+        }
+    }
+    
+    /// 使用指定设置
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.rate_limiter = settings.rate_limit.clone().map(|limits| Arc::new(RateLimiter::new(limits)));
+        self.read_only.store(settings.read_only, Ordering::Relaxed);
+        self.method_policy.set(settings.method_policy.clone());
+        self.resource_manager.set_pretty_json(settings.resource_json_pretty);
+        self.resource_manager.set_probe_policy(settings.resource_probe_timeout(), settings.resource_disable_after);
+        self.tool_manager.set_serialization_policy(settings.serialization_policy);
+        self.watchdog = settings
+            .blocking_watchdog()
+            .map(|threshold| Arc::new(watchdog::Watchdog::new(threshold, settings.blocking_escalation)));
+        self.error_shaper = Arc::new(error_shaping::ErrorShaper::new(settings.max_error_bytes, &settings.error_scrubbers));
+        self.resource_manager.set_serialization_policy(settings.serialization_policy);
+        self.prompt_manager.set_serialization_policy(settings.serialization_policy);
+        self.prompt_manager.set_turn_policy(settings.prompt_turn_policy);
+        #[cfg(feature = "http-client")]
+        match http_client::HttpClient::new(&settings.http_client) {
+            Ok(client) => {
+                self.insert_state(client);
+                self.http_client_error = None;
+            }
+            Err(e) => self.http_client_error = Some(e),
+        }
+        // 服务器信息可能变化，不再与克隆前的实例共享initialize结果
+        self.initialize_cache = Arc::default();
+        self.info = Arc::default();
+        self.settings = settings;
+        self
+    }
+    
+    /// 在`initialize`结果的`capabilities.experimental`中声明一项扩展能力，同名的能力被替换
+    ///
+    /// ```rust
+    /// use axum::body::Body;
+    /// use axum::http::Request;
+    /// use rustmcp::{create_app, RustMCP};
+    /// use serde_json::{json, Value};
+    /// use tower::Service;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let rustmcp = RustMCP::new().with_experimental_capability("acme/batching", json!({ "maxCalls": 16 }));
+    /// let initialize = json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} });
+    /// let request = Request::post("/mcp")
+    ///     .header("content-type", "application/json")
+    ///     .body(Body::from(initialize.to_string()))
+    ///     .unwrap();
+    /// let response = create_app(rustmcp.clone()).call(request).await.unwrap();
+    /// let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    /// let capabilities = serde_json::from_slice::<Value>(&bytes).unwrap()["result"]["capabilities"].take();
+    /// assert_eq!(capabilities["experimental"], json!({ "acme/batching": { "maxCalls": 16 } }));
+    /// assert_eq!(capabilities["tools"], json!({ "listChanged": true }));
+    /// assert_eq!(capabilities, serde_json::to_value(&rustmcp.mcp_initialize_result().capabilities).unwrap());
+    /// # }
+    /// ```
+    pub fn with_experimental_capability(mut self, key: impl Into<String>, value: Value) -> Self {
+        Arc::make_mut(&mut self.experimental_capabilities).insert(key.into(), value);
+        // 不再与克隆前的实例共享initialize结果
+        self.initialize_cache = Arc::default();
+        self.info = Arc::default();
+        self
+    }
+    
+    /// 设置是否在校验前把字符串参数宽松转换为输入模式声明的类型（默认关闭）
+    ///
+    /// 参见[`ToolManager::lenient_coercion`]。
+    pub fn with_lenient_coercion(mut self, enabled: bool) -> Self {
+        self.tool_manager.set_lenient_coercion(enabled);
+        self
+    }
+    
+    /// 获取当前设置
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+    
+    /// 切换只读模式
+    ///
+    /// 只读模式下，调用可能修改状态的工具（参见[`FunctionTool::may_mutate`]）返回
+    /// [`McpError::ReadOnly`]，不会执行工具函数；只读工具、资源和提示不受影响。
+    /// 克隆得到的实例共享同一个开关，可以在服务器运行时切换。
+    ///
+    /// ```rust
+    /// use rustmcp::{FunctionTool, McpError, RustMCP, ToolAnnotations};
+    ///
+    /// # futures::executor::block_on(async {
+    /// let rustmcp = RustMCP::new();
+    /// rustmcp.add_tool(FunctionTool::builder()
+    ///     .name("drop_table")
+    ///     .description("Drops a table")
+    ///     .annotations(ToolAnnotations { destructive_hint: Some(true), ..Default::default() })
+    ///     .handler(|_| Ok(serde_json::json!("dropped")))
+    ///     .build());
+    ///
+    /// rustmcp.set_read_only(true);
+    /// let blocked = rustmcp.mcp_call_tool("drop_table", None).await;
+    /// assert_eq!(blocked, Err(McpError::ReadOnly("drop_table".to_string())));
+    ///
+    /// rustmcp.set_read_only(false);
+    /// assert!(rustmcp.mcp_call_tool("drop_table", None).await.is_ok());
+    /// # });
+    /// ```
+    pub fn set_read_only(&self, read_only: bool) {
+        if self.read_only.swap(read_only, Ordering::Relaxed) != read_only {
+            log::info!("Read-only mode {}", if read_only { "enabled" } else { "disabled" });
+        }
+    }
+    
+    /// 是否处于只读模式
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+    
+    /// 更换JSON-RPC方法的允许/拒绝列表，`None`允许所有方法，参见[`policy`]
+    ///
+    /// 对之后到达的请求生效，下一次`initialize`按新的策略声明能力。
+    pub fn set_method_policy(&self, policy: Option<MethodPolicy>) {
+        log::info!("Method policy set to {:?}", policy);
+        self.method_policy.set(policy);
+    }
+    
+    /// 当前的方法策略
+    pub fn method_policy(&self) -> Option<MethodPolicy> {
+        self.method_policy.get()
+    }
+    
+    /// 当前的方法策略是否允许调用该方法
+    pub fn is_method_allowed(&self, method: &str) -> bool {
+        self.method_policy.permits(method)
+    }
+    
+    /// 因连接读得太慢而被丢弃的通知总数，参见[`notifications`]
+    pub fn dropped_notifications(&self) -> u64 {
+        self.dropped_notifications.load(Ordering::Relaxed)
+    }
+    
+    /// 当前的会话，按连接时间排序，参见[`connections`]
+    pub fn sessions(&self) -> Vec<SessionInfo> {
+        let mut sessions = self.connections.list();
+        sessions.extend(self.sessions.list());
+        sessions.sort_by(|a, b| a.connected_at_ms.cmp(&b.connected_at_ms).then_with(|| a.session_id.cmp(&b.session_id)));
+        sessions
+    }
+    
+    /// 强制结束会话，会话存在时返回`true`
+    ///
+    /// WebSocket连接收到关闭帧后断开，标准输入输出停止服务，HTTP会话与`DELETE /mcp`相同。
+    pub fn disconnect_session(&self, session_id: &str) -> bool {
+        self.connections.disconnect(session_id) || self.sessions.remove(session_id)
+    }
+    
+    /// 优雅关闭：通知每个会话，拒绝新的工具调用，等待正在执行的调用完成后关闭连接，参见[`shutdown`]
+    ///
+    /// 正在执行的调用都在`grace`内完成时返回`true`。
+    pub async fn shutdown(&self, reason: &str, grace: std::time::Duration) -> bool {
+        log::info!("Shutting down ({}), waiting up to {}ms for in-flight tool calls", reason, grace.as_millis());
+        self.shutdown.begin(reason);
+        self.notifier.notify(
+            crate::protocol::SHUTDOWN_NOTIFICATION,
+            Some(serde_json::json!({
+                "reason": reason,
+                "graceMs": u64::try_from(grace.as_millis()).unwrap_or(u64::MAX)
+            })),
+        );
+        let drained = self.shutdown.drained(grace).await;
+        if !drained {
+            log::warn!("Grace period of {}ms expired with tool calls still running", grace.as_millis());
+        }
+        self.tool_hooks.stop(self.tool_manager.list_tool_hooks(), self.settings.tool_shutdown_timeout()).await;
+        self.connections.go_away();
+        self.sessions.remove_all();
+        drained
+    }
+    
+    /// 是否已经开始关闭，参见[`RustMCP::shutdown`]
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_draining()
+    }
+    
+    /// 正在处理的请求数、拒绝的请求数以及是否处于降级状态，参见[`admission`]
+    pub fn load_metrics(&self) -> LoadMetrics {
+        self.admission.metrics(self.settings.admission_limits())
+    }
+    
+    /// 登记一个JSON-RPC请求，豁免的方法返回`None`，达到并发上限时返回错误
+    pub(crate) fn admit(&self, method: &str, request_context: &RequestContext) -> Result<Option<admission::Admitted>, McpError> {
+        if self.settings.load_shedding_exempt.iter().any(|exempt| exempt == method) {
+            return Ok(None);
+        }
+        self.admission
+            .admit(&request_context.caller_key(), self.settings.admission_limits())
+            .map(Some)
+    }
+    
+    /// 按调用方和方法限流，超限时返回带有建议等待时间的错误
+    pub(crate) fn check_rate_limit(&self, method: &str, request_context: &RequestContext) -> Result<(), McpError> {
+        let Some(limiter) = &self.rate_limiter else {
+            return Ok(());
+        };
+        limiter.check(&request_context.caller_key(), method).map_err(|retry_after| McpError::RateLimited {
+            retry_after_ms: u64::try_from(retry_after.as_millis()).unwrap_or(u64::MAX).max(1),
+        })
+    }
+    
+    /// HTTP会话的数量以及创建、清理、淘汰和重置的次数，参见[`session`]
+    pub fn session_metrics(&self) -> SessionMetrics {
+        self.sessions.metrics()
+    }
+    
+    /// 立即丢弃超过空闲超时的HTTP会话，返回丢弃的数量；后台任务也会定期执行
+    pub fn reap_idle_sessions(&self) -> usize {
+        self.sessions.reap()
+    }
+    
+    /// 登记WebSocket或标准输入输出连接，返回的句柄释放时移除
+    pub(crate) fn register_connection(&self, session_id: &str, transport: TransportKind, outbox: Arc<Outbox>) -> ConnectionGuard {
+        self.connections.register(session_id, transport, outbox)
+    }
+    
+    /// 会话保留的未送达响应数，参见[`pending`]
+    pub fn pending_results(&self, session_id: &str) -> usize {
+        self.settings.pending_limits().map_or(0, |limits| self.pending.count(session_id, limits.ttl))
+    }
+    
+    /// 是否保留连接断开后未送达的响应
+    pub(crate) fn parks_responses(&self) -> bool {
+        self.settings.pending_results_ttl_ms.is_some()
+    }
+    
+    /// 处理连接断开后未送达的响应：同一会话已经重新连接时交给新连接，否则按设置保留
+    pub(crate) fn park_response(&self, request_context: &RequestContext, id: RequestId, text: String) {
+        let (Some(limits), Some(session_id)) = (self.settings.pending_limits(), request_context.session_id.as_deref()) else {
+            return;
+        };
+        if self.settings.pending_redelivery {
+            if let Some(outbox) = self.connections.other_outbox(session_id, request_context.connection.as_ref()) {
+                if !matches!(outbox.push(text.clone(), false), Push::Closed) {
+                    return;
+                }
+            }
+        }
+        self.pending.park(session_id, id, text, limits);
+    }
+    
+    /// 取出会话保留的响应
+    pub(crate) fn take_pending(&self, session_id: &str) -> Vec<(RequestId, String)> {
+        self.settings.pending_limits().map(|limits| self.pending.take(session_id, limits.ttl)).unwrap_or_default()
+    }
+    
+    /// 为新连接创建发送队列，写入的消息记入[跟踪](trace)
+    pub(crate) fn outbox(&self, transport: TransportKind, session_id: &str) -> Arc<Outbox> {
+        let trace = self.tracer.tap(transport, Some(session_id));
+        Outbox::new(self.settings.outbound_queue_depth, self.dropped_notifications.clone(), trace)
+    }
+    
+    /// 开启规范化JSON时把值中所有对象的键按字典序排列，参见[`Settings::with_canonical_json`]
+    pub(crate) fn canonicalize(&self, value: &mut Value) {
+        if self.settings.canonical_json {
+            value.sort_all_objects();
+        }
+    }
+    
+    /// 只读模式下拒绝可能修改状态的工具
+    fn check_read_only(&self, name: &str) -> Result<(), McpError> {
+        if self.is_read_only() && self.tool_manager.may_mutate(name, !self.settings.read_only_allow_unannotated) {
+            return Err(McpError::ReadOnly(name.to_string()));
+        }
+        Ok(())
+    }
+    
+    /// 设置工具调用的审计接收端，替换之前的接收端，参见[`audit`]
+    ///
+    /// 队列长度取自当前设置的[`Settings::audit_queue_depth`]。
+    pub fn with_audit_sink(mut self, sink: impl AuditSink + 'static) -> Self {
+        self.audit = Some(Arc::new(AuditLog::new(Arc::new(sink), self.settings.audit_queue_depth)));
+        self
+    }
+    
+    /// 设置保存幂等结果的存储，替换默认的内存存储，参见[`idempotency`]
+    pub fn with_idempotency_store(mut self, store: impl IdempotencyStore + 'static) -> Self {
+        self.idempotency = Arc::new(Idempotency::new(Arc::new(store)));
+        self
+    }
+    
+    /// 等待已经产生的审计记录都交给接收端；服务器关闭前调用
+    pub async fn flush_audit(&self) {
+        if let Some(audit) = &self.audit {
+            audit.flush().await;
         }
     }
     
+    /// 因审计队列满而丢弃的记录数
+    pub fn dropped_audit_entries(&self) -> u64 {
+        self.audit.as_ref().map_or(0, |audit| audit.dropped())
+    }
+    
+    /// 注册依赖健康检查，同名检查被替换，参见[`health`]
+    pub fn add_health_check(&self, check: HealthCheck) {
+        self.health.add(check);
+    }
+    
+    /// 停止所有健康检查，最近的结果保留；服务器关闭前调用
+    pub fn stop_health_checks(&self) {
+        self.health.stop();
+    }
+    
+    /// 各依赖最近一次检查的结果
+    pub fn dependency_status(&self) -> BTreeMap<String, DependencyStatus> {
+        self.health.status()
+    }
+    
+    /// 所有关键依赖最近一次检查是否都成功，并且工具的启动钩子都已成功执行
+    pub fn is_ready(&self) -> bool {
+        self.health.failing_critical().is_empty() && self.tool_hooks_ready()
+    }
+    
+    /// 按工具名称顺序执行还没有成功的启动钩子，某个钩子失败时停止并返回错误，参见[`tool_hooks`]
+    ///
+    /// [`run`]在开始监听之前调用；自己组装应用时在`serve`之前调用。
+    pub async fn initialize_tools(&self) -> Result<(), ToolStartupError> {
+        self.tool_hooks.start(self.tool_manager.list_tool_hooks()).await
+    }
+    
+    /// 声明了钩子的工具的钩子状态
+    pub fn tool_hook_status(&self) -> BTreeMap<String, ToolHookStatus> {
+        self.tool_hooks.status(self.tool_manager.list_tool_hooks())
+    }
+    
+    /// 工具的启动钩子是否都已成功执行
+    fn tool_hooks_ready(&self) -> bool {
+        self.tool_hook_status()
+            .values()
+            .all(|status| status.startup.is_none_or(|state| state == HookState::Done))
+    }
+    
+    /// 开启请求/响应内容采集，替换之前的设置并清空已有的采集
+    ///
+    /// 克隆得到的实例共享同一个缓冲区，可以在服务器运行时开启。参见[`capture`]。
+    pub fn enable_capture(&self, config: CaptureConfig) {
+        log::info!("Body capture enabled (method: {:?}, tool: {:?}, 1 in {})", config.method, config.tool, config.sample_rate.max(1));
+        self.captures.enable(config);
+    }
+    
+    /// 关闭内容采集，已有的采集保留到下次开启
+    pub fn disable_capture(&self) {
+        self.captures.disable();
+        log::info!("Body capture disabled");
+    }
+    
+    /// 当前的采集设置，关闭时为`None`
+    pub fn capture_config(&self) -> Option<CaptureConfig> {
+        self.captures.config()
+    }
+    
+    /// 按时间顺序返回最近的采集
+    pub fn captures(&self) -> Vec<Capture> {
+        self.captures.captures()
+    }
+    
+    /// 开启请求镜像，替换之前的设置并清空计数和已有的不一致记录
+    ///
+    /// 克隆得到的实例共享同一份记录，可以在服务器运行时开启。参见[`mirror`]。
+    pub fn enable_mirror(&self, config: MirrorConfig) {
+        let target = match &config.target {
+            MirrorTarget::Instance(_) => "instance".to_string(),
+            MirrorTarget::Url(url) => url.clone(),
+        };
+        log::info!("Request mirroring enabled (target: {}, 1 in {})", target, config.sample_rate.max(1));
+        self.mirror.enable(config);
+    }
+    
+    /// 关闭请求镜像，正在执行的影子请求仍会完成，已有的记录保留到下次开启
+    pub fn disable_mirror(&self) {
+        self.mirror.disable();
+        log::info!("Request mirroring disabled");
+    }
+    
+    /// 镜像计数
+    pub fn mirror_stats(&self) -> MirrorStats {
+        self.mirror.stats()
+    }
+    
+    /// 按时间顺序返回最近的不一致记录
+    pub fn divergences(&self) -> Vec<Divergence> {
+        self.mirror.divergences()
+    }
+    
+    /// 清空不一致记录，计数不变
+    pub fn clear_divergences(&self) {
+        self.mirror.clear();
+    }
+    
+    /// 开启协议跟踪，替换之前的设置，跟踪文件无法打开时返回错误
+    ///
+    /// 克隆得到的实例共享同一个跟踪，可以在服务器运行时开启。参见[`trace`]。
+    pub fn enable_trace(&self, config: TraceConfig) -> std::io::Result<()> {
+        self.tracer.enable(config.clone())?;
+        log::info!("Protocol trace enabled, writing to {} (per session: {})", config.path.display(), config.per_session);
+        Ok(())
+    }
+    
+    /// 关闭协议跟踪，已经记录的条目仍会写完
+    pub fn disable_trace(&self) {
+        self.tracer.disable();
+        log::info!("Protocol trace disabled");
+    }
+    
+    /// 当前的跟踪设置，关闭时为`None`
+    pub fn trace_config(&self) -> Option<TraceConfig> {
+        self.tracer.config()
+    }
+    
+    /// 最近写出的`lines`条跟踪记录，按时间顺序
+    pub fn trace_tail(&self, lines: usize) -> Vec<Value> {
+        self.tracer.tail(lines)
+    }
+    
+    /// 等待已经记录的跟踪条目都写到文件
+    pub async fn flush_trace(&self) {
+        self.tracer.flush().await;
+    }
+    
+    /// 因跟踪队列满而丢弃的条目数
+    pub fn dropped_trace_entries(&self) -> u64 {
+        self.tracer.dropped()
+    }
+    
+    /// 注册共享的应用状态，返回之前同类型的值
+    ///
+    /// 工具、资源和提示可以通过[`Context::state`]读取。每种类型只保存一个值；
+    /// 需要修改的状态应自行使用`Mutex`、原子类型等内部可变性。
+    pub fn insert_state<T: Send + Sync + 'static>(&mut self, value: T) -> Option<Arc<T>> {
+        Arc::make_mut(&mut self.state).insert(value)
+    }
+    
+    /// 转发工具、URL资源和OpenAPI导入共用的出站HTTP客户端，参见[`http_client`]
+    #[cfg(feature = "http-client")]
+    pub fn http_client(&self) -> Arc<http_client::HttpClient> {
+        self.state.get::<http_client::HttpClient>().unwrap_or_default()
+    }
+    
+    /// 获取共享的应用状态
+    pub fn state<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.state.get::<T>()
+    }
+    
+    /// 为上下文附加服务器的状态表和注册资源的入口
+    fn attach_state(&self, ctx: &Context) -> Context {
+        ctx.clone()
+            .with_state(self.state.clone())
+            .with_resources(ResourceRegistrar::new(self.resource_manager.clone(), self.notifier.clone()))
+    }
+    
+    /// 获取通知广播器
+    pub fn notifier(&self) -> &Notifier {
+        &self.notifier
+    }
+    
+    /// 注册工具调用前钩子
+    ///
+    /// 钩子可以修改参数，返回错误时否决本次调用。多个钩子按注册顺序执行，
+    /// 第一个返回错误的钩子会跳过其余钩子和工具函数。
+    pub fn on_before_tool_call<F>(&mut self, hook: F)
+    where
+        F: Fn(&str, &mut HashMap<String, Value>, &Context) -> Result<(), McpError> + Send + Sync + 'static,
+    {
+        self.hooks.on_before_tool_call(hook);
+    }
+    
+    /// 注册工具调用后钩子
+    ///
+    /// 钩子按注册顺序收到每次调用的最终结果（包括被否决的调用）和耗时。
+    pub fn on_after_tool_call<F>(&mut self, hook: F)
+    where
+        F: Fn(&str, &Result<Value, McpError>, std::time::Duration) + Send + Sync + 'static,
+    {
+        self.hooks.on_after_tool_call(hook);
+    }
+    
+    /// 注册资源读取前钩子，返回错误时否决本次读取
+    pub fn on_before_resource_read<F>(&mut self, hook: F)
+    where
+        F: Fn(&str, &Context) -> Result<(), McpError> + Send + Sync + 'static,
+    {
+        self.hooks.on_before_resource_read(hook);
+    }
+    
+    /// 注册提示获取前钩子，可以修改参数，返回错误时否决本次获取
+    pub fn on_before_prompt_get<F>(&mut self, hook: F)
+    where
+        F: Fn(&str, &mut HashMap<String, Value>, &Context) -> Result<(), McpError> + Send + Sync + 'static,
+    {
+        self.hooks.on_before_prompt_get(hook);
+    }
+
+    /// 注册initialize钩子，返回的警告附加到initialize结果的`_meta.warnings`
+    ///
+    /// 警告不会让握手失败。服务器自己的协商警告（例如客户端请求了不同的协议版本）排在钩子的警告之前，
+    /// 每条警告都会带着会话ID记录到`warn`级别的日志。
+    ///
+    /// ```rust
+    /// use rustmcp::protocol::InitializeParams;
+    /// use rustmcp::RustMCP;
+    ///
+    /// let mut rustmcp = RustMCP::new();
+    /// rustmcp.on_initialize(|params: &InitializeParams| match params.client_info.version.starts_with("0.") {
+    ///     true => vec![format!("client version {} is deprecated", params.client_info.version)],
+    ///     false => Vec::new(),
+    /// });
+    /// ```
+    pub fn on_initialize<F>(&mut self, hook: F)
+    where
+        F: Fn(&InitializeParams) -> Vec<String> + Send + Sync + 'static,
+    {
+        self.hooks.on_initialize(hook);
+    }
+
+    /// 注册自定义JSON-RPC方法
+    ///
+    /// 处理函数收到请求的`params`和上下文。内置方法名不能被覆盖，尝试注册时返回
+    /// [`McpError::ReservedMethod`]；重复注册同一个自定义方法会替换之前的处理函数。
+    pub fn add_method_handler<F, Fut>(&mut self, method: &str, handler: F) -> Result<(), McpError>
+    where
+        F: Fn(Option<Value>, Context) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<Value, McpError>> + Send + 'static,
+    {
+        let handler: MethodHandler = Arc::new(move |params, ctx| Box::pin(handler(params, ctx)));
+        self.method_handlers.insert(method, handler)
+    }
+    
     /// 添加工具
-    pub fn add_tool(&mut self, tool: FunctionTool) {
+    ///
+    /// 注册表在内部加锁，服务器运行时也可以通过共享的`RustMCP`添加，并发添加同名工具时按重复行为处理。
+    pub fn add_tool(&self, tool: FunctionTool) {
+        if let Err(message) = self.check_tags("Tool", &tool.name, tool.tags.as_deref().unwrap_or_default()) {
+            panic!("{}", message);
+        }
         self.tool_manager.add_tool(tool);
+        self.notifier.tools_list_changed();
+    }
+    
+    /// 注册查询工具注册表的内置工具`list_registered_tools`、`describe_tool`和`search_tools`，参见[`introspection`]
+    ///
+    /// 工具直接读取当前的工具表，之后添加或删除的工具立即可见。已经注册的同名工具按重复行为处理。
+    pub fn enable_introspection_tools(&self) {
+        for tool in introspection::tools(self.tool_manager.clone(), self.method_policy.clone()) {
+            self.tool_manager.add_tool(tool);
+        }
+        self.notifier.tools_list_changed();
+    }
+    
+    /// 注册工具模式可以通过`{"$ref": "#/definitions/<name>"}`引用的共享定义，参见[`definitions`]
+    ///
+    /// 定义引用了未注册的定义时返回错误。注册后发送工具列表变更通知。
+    pub fn register_schema(&self, name: impl Into<String>, schema: Value) -> Result<(), String> {
+        self.tool_manager.register_schema(name, schema)?;
+        self.notifier.tools_list_changed();
+        Ok(())
+    }
+    
+    /// 添加工具，重复行为为`Error`且工具已存在时返回错误而不是panic
+    ///
+    /// 返回工具是否被添加，添加后发送列表变更通知。开启了输入校验的工具在注册时编译输入模式，
+    /// 模式结构不正确时返回错误；替换共享定义后所有工具重新编译。
+    ///
+    /// ```rust
+    /// use rustmcp::{FunctionTool, RustMCP};
+    /// use serde_json::json;
+    ///
+    /// let rustmcp = RustMCP::new();
+    /// let broken = FunctionTool::builder()
+    ///     .name("search")
+    ///     .input_schema(json!({ "type": "object", "properties": { "limit": { "type": "int" } } }))
+    ///     .validate_input(true)
+    ///     .handler(|_| Ok(json!("found")))
+    ///     .build();
+    /// assert!(rustmcp.try_add_tool(broken).is_err());
+    /// ```
+    pub fn try_add_tool(&self, tool: FunctionTool) -> Result<bool, String> {
+        self.check_tags("Tool", &tool.name, tool.tags.as_deref().unwrap_or_default())?;
+        let added = self.tool_manager.try_add_tool(tool)?;
+        if added {
+            self.notifier.tools_list_changed();
+        }
+        Ok(added)
+    }
+    
+    /// 添加从外部标识符导入的工具，返回注册使用的名称，有工具被添加时发送列表变更通知
+    ///
+    /// 名称按[`sanitize_tool_name`](tools::sanitize_tool_name)规范化，与来自其他标识符的工具冲突时附加哈希，
+    /// 之后用原始标识符或注册的名称都可以调用工具，参见[`ToolManager::add_imported_tool`]。
+    ///
+    /// ```rust
+    /// use rustmcp::{FunctionTool, RustMCP};
+    /// use serde_json::json;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let rustmcp = RustMCP::new();
+    /// let imported = |raw: &str, value: i64| FunctionTool::builder().name(raw).handler(move |_| Ok(json!(value))).build();
+    ///
+    /// let first = rustmcp.add_imported_tool(imported("List Orders", 1)).unwrap();
+    /// assert_eq!(first, "list_orders");
+    /// // 规范化后冲突的标识符附加哈希，不会替换已有的工具
+    /// let second = rustmcp.add_imported_tool(imported("list/orders", 2)).unwrap();
+    /// assert!(second.starts_with("list_orders_"));
+    /// let third = rustmcp.add_imported_tool(imported("Bestellübersicht", 3)).unwrap();
+    /// assert_eq!(third, "bestellubersicht");
+    ///
+    /// // 原始标识符和注册的名称都能调用
+    /// assert_eq!(rustmcp.mcp_call_tool("List Orders", None).await.unwrap(), json!(1));
+    /// assert_eq!(rustmcp.mcp_call_tool("list_orders", None).await.unwrap(), json!(1));
+    /// assert_eq!(rustmcp.mcp_call_tool("list/orders", None).await.unwrap(), json!(2));
+    /// assert_eq!(rustmcp.mcp_call_tool(&second, None).await.unwrap(), json!(2));
+    /// assert_eq!(rustmcp.mcp_call_tool("Bestellübersicht", None).await.unwrap(), json!(3));
+    ///
+    /// // 再次导入同一个标识符时替换原来的工具
+    /// assert_eq!(rustmcp.add_imported_tool(imported("list/orders", 4)).unwrap(), second);
+    /// assert_eq!(rustmcp.mcp_call_tool("list/orders", None).await.unwrap(), json!(4));
+    /// assert_eq!(rustmcp.mcp_list_tools().len(), 3);
+    /// # }
+    /// ```
+    pub fn add_imported_tool(&self, tool: FunctionTool) -> Result<String, String> {
+        self.check_tags("Tool", &tool.name, tool.tags.as_deref().unwrap_or_default())?;
+        let name = self.tool_manager.add_imported_tool(tool)?;
+        self.notifier.tools_list_changed();
+        Ok(name)
+    }
+    
+    /// 启用单个工具，返回工具是否存在，状态变化时发送列表变更通知
+    pub fn enable_tool(&self, name: &str) -> bool {
+        self.set_tool_enabled(name, true)
+    }
+    
+    /// 禁用单个工具，返回工具是否存在，状态变化时发送列表变更通知
+    pub fn disable_tool(&self, name: &str) -> bool {
+        self.set_tool_enabled(name, false)
+    }
+    
+    fn set_tool_enabled(&self, name: &str, enabled: bool) -> bool {
+        match self.tool_manager.set_tool_enabled(name, enabled) {
+            Some(changed) => {
+                if changed {
+                    self.notifier.tools_list_changed();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 为已注册的工具设置工具函数，返回工具是否存在，设置后发送列表变更通知
+    ///
+    /// 反序列化得到的工具没有实现（[`ToolBinding::Unbound`]），可以先注册再绑定。没有实现的工具仍然出现在列表中，
+    /// 带有`_meta.bound: false`，调用时返回[`McpError::ToolNotBound`]。
+    ///
+    /// ```rust
+    /// use rustmcp::{FunctionTool, RustMCP};
+    /// use serde_json::json;
+    ///
+    /// let rustmcp = RustMCP::new();
+    /// let tool: FunctionTool = serde_json::from_value(json!({ "name": "echo", "description": "Echo the input", "inputSchema": { "type": "object" } })).unwrap();
+    /// rustmcp.add_tool(tool);
+    /// assert!(rustmcp.bind_tool_function("echo", |_ctx, args| Ok(json!(args))));
+    /// ```
+    pub fn bind_tool_function<F>(&self, name: &str, function: F) -> bool
+    where
+        F: Fn(&Context, Option<HashMap<String, Value>>) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        let bound = self.tool_manager.bind_function(name, function);
+        if bound {
+            self.notifier.tools_list_changed();
+        }
+        bound
+    }
+
+    /// 删除工具，工具存在时发送列表变更通知
+    pub fn remove_tool(&self, name: &str) -> bool {
+        let removed = self.tool_manager.remove_tool(name);
+        if removed {
+            self.notifier.tools_list_changed();
+        }
+        removed
+    }
+    
+    /// 添加工具分组
+    pub fn add_tool_group(&self, name: &str, description: &str) {
+        self.tool_manager.add_group(name, description);
+    }
+    
+    /// 禁用分组中的所有工具，有工具状态变化时发送一次列表变更通知
+    pub fn disable_tool_group(&self, name: &str) -> usize {
+        let changed = self.tool_manager.disable_group(name);
+        if changed > 0 {
+            self.notifier.tools_list_changed();
+        }
+        changed
+    }
+    
+    /// 启用分组中的所有工具，有工具状态变化时发送一次列表变更通知
+    pub fn enable_tool_group(&self, name: &str) -> usize {
+        let changed = self.tool_manager.enable_group(name);
+        if changed > 0 {
+            self.notifier.tools_list_changed();
+        }
+        changed
+    }
+    
+    /// 删除分组及其中的所有工具，有工具被删除时发送一次列表变更通知
+    pub fn remove_tool_group(&self, name: &str) -> usize {
+        let removed = self.tool_manager.remove_group(name);
+        if removed > 0 {
+            self.notifier.tools_list_changed();
+        }
+        removed
+    }
+    
+    /// 从OpenAPI 3.x文档导入工具，返回导入的工具名称
+    ///
+    /// 每个操作生成一个工具，调用时向`base_url`发起对应的HTTP请求。工具名称按
+    /// [`add_imported_tool`](Self::add_imported_tool)的规则规范化，与已有的工具冲突时附加哈希。
+    #[cfg(feature = "openapi")]
+    pub fn import_openapi(
+        &self,
+        spec: &str,
+        base_url: &str,
+        options: openapi::OpenApiOptions,
+    ) -> Result<Vec<String>, openapi::OpenApiError> {
+        let options = match options.has_client() {
+            true => options,
+            false => options.http_client(http_client::HttpClient::clone(&self.http_client())),
+        };
+        let tools = openapi::tools_from_spec(spec, base_url, &options)?;
+        tools
+            .into_iter()
+            .map(|tool| self.tool_manager.add_imported_tool(tool))
+            .collect::<Result<_, _>>()
+            .map_err(openapi::OpenApiError::InvalidSpec)
+    }
+    
+    /// 加载目录中的`*.json`工具清单，并在后台监视目录变化
+    ///
+    /// 新增或修改的清单会重新注册工具，删除清单会移除对应的工具，工具列表变化时发送列表变更通知。
+    /// 无法解析的清单记录日志后跳过。返回的监视器被丢弃时停止监视。参见[`watch`]。
+    #[cfg(feature = "watch")]
+    pub fn watch_manifest_dir(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<watch::ManifestWatcher> {
+        watch::watch(path.as_ref(), self.tool_manager.clone(), self.notifier.clone())
     }
     
     /// 添加资源
-    pub fn add_resource(&mut self, resource: FunctionResource) {
+    pub fn add_resource(&self, resource: FunctionResource) {
+        if let Err(message) = self.check_tags("Resource", &resource.uri, &resource.tags) {
+            panic!("{}", message);
+        }
         self.resource_manager.add_resource(resource);
+        self.notifier.resources_list_changed();
+    }
+    
+    /// 添加资源，重复行为为`Error`且资源已存在时返回错误而不是panic
+    pub fn try_add_resource(&self, resource: FunctionResource) -> Result<bool, String> {
+        self.check_tags("Resource", &resource.uri, &resource.tags)?;
+        let added = self.resource_manager.try_add_resource(resource)?;
+        if added {
+            self.notifier.resources_list_changed();
+        }
+        Ok(added)
+    }
+    
+    /// 删除资源，资源存在时发送列表变更通知
+    pub fn remove_resource(&self, uri: &str) -> bool {
+        let removed = self.resource_manager.remove_resource(uri);
+        if removed {
+            self.notifier.resources_list_changed();
+        }
+        removed
+    }
+    
+    /// 启用手动停用的资源，返回资源是否存在，状态变化时发送列表变更通知
+    pub fn enable_resource(&self, uri: &str) -> bool {
+        self.set_resource_enabled(uri, true)
+    }
+    
+    /// 停用资源，返回资源是否存在，状态变化时发送列表变更通知
+    ///
+    /// 停用的资源不出现在列表中，读取时返回[`McpError::ResourceDisabled`]；探测成功不会恢复手动停用的资源。
+    pub fn disable_resource(&self, uri: &str) -> bool {
+        self.set_resource_enabled(uri, false)
+    }
+    
+    fn set_resource_enabled(&self, uri: &str, enabled: bool) -> bool {
+        match self.resource_manager.set_resource_enabled(uri, enabled) {
+            Some(changed) => {
+                if changed {
+                    self.notifier.resources_list_changed();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+    
+    /// 为URI前缀注册资源解析器并发送列表变更通知，参见[`resolvers`]
+    pub fn add_resource_resolver(&self, template: impl Into<ResourceTemplate>, resolver: impl ResourceResolver + 'static) {
+        self.resource_manager.add_resolver(template, resolver);
+        self.notifier.resources_list_changed();
+    }
+    
+    /// 删除前缀的资源解析器，解析器存在时发送列表变更通知
+    pub fn remove_resource_resolver(&self, prefix: &str) -> bool {
+        let removed = self.resource_manager.remove_resolver(prefix);
+        if removed {
+            self.notifier.resources_list_changed();
+        }
+        removed
+    }
+    
+    /// 安装按需加载资源的加载器并发送列表变更通知，参见[`loader`]
+    pub fn set_lazy_resource_loader(&self, loader: impl ResourceLoader + 'static) {
+        self.resource_manager.set_lazy_loader(loader);
+        self.notifier.resources_list_changed();
     }
     
     /// 添加提示
-    pub fn add_prompt(&mut self, prompt: FunctionPrompt) {
+    pub fn add_prompt(&self, prompt: FunctionPrompt) {
+        if let Err(message) = self.check_tags("Prompt", &prompt.name, &prompt.tags) {
+            panic!("{}", message);
+        }
         self.prompt_manager.add_prompt(prompt);
+        self.notifier.prompts_list_changed();
+    }
+    
+    /// 添加提示，重复行为为`Error`且提示已存在时返回错误而不是panic
+    pub fn try_add_prompt(&self, prompt: FunctionPrompt) -> Result<bool, String> {
+        self.check_tags("Prompt", &prompt.name, &prompt.tags)?;
+        let added = self.prompt_manager.try_add_prompt(prompt)?;
+        if added {
+            self.notifier.prompts_list_changed();
+        }
+        Ok(added)
+    }
+    
+    /// 删除提示，提示存在时发送列表变更通知
+    pub fn remove_prompt(&self, name: &str) -> bool {
+        let removed = self.prompt_manager.remove_prompt(name);
+        if removed {
+            self.notifier.prompts_list_changed();
+        }
+        removed
+    }
+    
+    /// 启用停用的提示，返回提示是否存在，状态变化时发送列表变更通知
+    pub fn enable_prompt(&self, name: &str) -> bool {
+        self.set_prompt_enabled(name, true)
+    }
+    
+    /// 停用提示，返回提示是否存在，状态变化时发送列表变更通知
+    ///
+    /// 停用的提示不出现在列表中，获取时返回[`McpError::PromptDisabled`]。
+    pub fn disable_prompt(&self, name: &str) -> bool {
+        self.set_prompt_enabled(name, false)
+    }
+    
+    fn set_prompt_enabled(&self, name: &str, enabled: bool) -> bool {
+        match self.prompt_manager.set_prompt_enabled(name, enabled) {
+            Some(changed) => {
+                if changed {
+                    self.notifier.prompts_list_changed();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+    
+    /// 登记标签及其说明，已经登记的标签替换说明，参见[`tags`]
+    pub fn define_tag(&self, name: impl Into<String>, description: impl Into<String>) {
+        self.tags.define(name.into(), description.into());
+    }
+    
+    /// 登记的和正在使用的标签及其使用次数，按名称排序，与`GET /mcp/tags`的响应相同
+    pub fn list_tags(&self) -> Vec<TagInfo> {
+        let tools = self.tool_manager.all_tools();
+        let resources = self.resource_manager.list_resources();
+        let prompts = self.prompt_manager.function_prompts();
+        self.tags.list(
+            tools.iter().map(|tool| tool.tags.as_deref().unwrap_or_default()),
+            resources.iter().map(|resource| resource.tags.as_deref().unwrap_or_default()),
+            prompts.iter().map(|prompt| prompt.tags.as_slice()),
+        )
+    }
+    
+    /// 以`prefix`开头的标签，使用次数多的在前，用作标签筛选参数的补全值
+    pub fn complete_tag(&self, prefix: &str) -> Vec<String> {
+        let mut tags: Vec<TagInfo> = self.list_tags().into_iter().filter(|tag| tag.name.starts_with(prefix)).collect();
+        tags.sort_by_key(|tag| std::cmp::Reverse(tag.tools + tag.resources + tag.prompts));
+        tags.into_iter().map(|tag| tag.name).collect()
+    }
+    
+    /// 开启了严格标签时检查标签都已登记
+    fn check_tags(&self, kind: &str, name: &str, tags: &[String]) -> Result<(), String> {
+        if self.settings.strict_tags {
+            self.tags.check(kind, name, tags)
+        } else {
+            Ok(())
+        }
+    }
+    
+    /// 检查所有已注册的工具、资源和提示，返回发现的问题
+    ///
+    /// 检查输入输出模式的结构、资源URI的scheme、重复的标题、空描述、互相矛盾的工具注解，
+    /// 以及声明了模板的提示中没有被使用的参数。参见[`diagnostics`]。
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        diagnostics::validate(
+            self.tool_manager.all_tools().iter(),
+            &self.resource_manager.list_resources(),
+            self.prompt_manager.function_prompts().iter(),
+        )
+    }
+    
+    /// 按当前的模式检查工具和提示的示例，返回发现的问题
+    ///
+    /// 示例只经过校验层，不会执行工具或提示函数：工具示例的参数按输入模式校验，预期结果按输出模式校验，
+    /// 提示示例只能使用声明过的参数。模式改了而示例没有跟着改时，这里会报告错误。
+    /// 设置了[`Settings::validate_examples`](crate::Settings::validate_examples)时启动校验也会运行这项检查。
+    ///
+    /// ```rust
+    /// use rustmcp::{FunctionTool, RustMCP, ToolArgs, ToolExample};
+    /// use serde_json::json;
+    ///
+    /// let rustmcp = RustMCP::new();
+    /// rustmcp.add_tool(
+    ///     FunctionTool::builder()
+    ///         .name("resize")
+    ///         .args(ToolArgs::new().integer("width", "Width in pixels", true))
+    ///         .example(ToolExample::new("Banner", json!({ "size": 1200 })))
+    ///         .handler(|_| Ok(json!("ok")))
+    ///         .build(),
+    /// );
+    /// assert_eq!(rustmcp.validate_examples().len(), 1);
+    /// ```
+    pub fn validate_examples(&self) -> Vec<ValidationIssue> {
+        diagnostics::validate_examples(self.tool_manager.all_tools().iter(), self.prompt_manager.function_prompts().iter())
+    }
+    
+    /// 先运行[`RustMCP::validate`]，再探测每个资源能否读取，读取失败的资源作为错误加入结果，参见[`probe`]
+    pub async fn validate_with_probes(&self, concurrency: usize) -> Vec<ValidationIssue> {
+        let mut issues = self.validate();
+        for (uri, result) in self.probe_resources(concurrency).await {
+            if let Err(message) = result {
+                let message = format!("read failed: {}", message);
+                issues.push(ValidationIssue::new(Severity::Error, ItemKind::Resource, &uri, message));
+            }
+        }
+        issues.sort_by(|a, b| (a.kind, &a.item, &a.message).cmp(&(b.kind, &b.item, &b.message)));
+        issues
+    }
+    
+    /// 并发探测每个资源能否读取，返回按URI排序的结果，参见[`probe`]
+    ///
+    /// 资源因连续失败被停用或探测成功后恢复时发送资源列表变更通知。
+    pub async fn probe_resources(&self, concurrency: usize) -> Vec<(String, Result<(), String>)> {
+        let (results, changed) = self.resource_manager.probe(concurrency).await;
+        if changed {
+            self.notifier.resources_list_changed();
+        }
+        results
+    }
+    
+    /// 连续探测失败而被停用的资源URI
+    pub fn disabled_resources(&self) -> Vec<String> {
+        self.resource_manager.disabled_resources()
+    }
+    
+    /// 设置了配额的工具和资源的当前用量，工具在前，参见[`quota`]
+    pub fn quota_status(&self) -> Vec<QuotaStatus> {
+        let mut status = self.tool_manager.quota_status();
+        status.extend(self.resource_manager.quota_status());
+        status
+    }
+    
+    /// 启动前记录校验发现的问题；设置了`strict_validation`且有错误时返回这些错误
+    ///
+    /// 设置了`validate_examples`时一并检查示例，参见[`RustMCP::validate_examples`]。
+    /// 设置有误（例如[出站HTTP客户端](http_client)的代理地址无法解析）时总是返回错误。
+    pub fn check_startup(&self) -> Result<(), Vec<ValidationIssue>> {
+        let settings_issues: Vec<ValidationIssue> = self
+            .http_client_error
+            .iter()
+            .map(|e| ValidationIssue::new(Severity::Error, ItemKind::Settings, "http_client", e.clone()))
+            .chain(
+                self.error_shaper
+                    .invalid()
+                    .iter()
+                    .map(|e| ValidationIssue::new(Severity::Error, ItemKind::Settings, "error_scrubbers", e.clone())),
+            )
+            .collect();
+        let fatal = !settings_issues.is_empty();
+        let mut issues = settings_issues;
+        issues.extend(self.validate());
+        if self.settings.validate_examples {
+            issues.extend(self.validate_examples());
+        }
+        for issue in &issues {
+            match issue.severity {
+                Severity::Warning => log::warn!("{}", issue),
+                Severity::Error => log::error!("{}", issue),
+            }
+        }
+        let errors: Vec<ValidationIssue> = issues.into_iter().filter(|i| i.severity == Severity::Error).collect();
+        if (self.settings.strict_validation || fatal) && !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(())
     }
     
     /// 列出所有工具
-    pub fn mcp_list_tools(&self) -> Vec<&tools::FunctionTool> {
+    pub fn mcp_list_tools(&self) -> Vec<Tool> {
         self.tool_manager.list_tools()
     }
     
+    /// 所有启用的工具的OpenAI函数定义，参见[`llm`]
+    pub fn export_openai_functions(&self) -> Vec<Value> {
+        self.tool_manager.export_openai_functions()
+    }
+    
+    /// 所有启用的工具的Anthropic工具定义，参见[`llm`]
+    pub fn export_anthropic_tools(&self) -> Vec<Value> {
+        self.tool_manager.export_anthropic_tools()
+    }
+    
+    /// 按导出给LLM API的名称查找工具名称，参见[`llm`]
+    pub fn resolve_llm_name(&self, name: &str) -> Option<String> {
+        self.tool_manager.resolve_llm_name(name)
+    }
+    
+    /// 执行模型发起的函数调用，与[`RustMCP::mcp_call_tool`]一样执行钩子、只读检查和审计，参见[`llm`]
+    pub async fn dispatch_llm_call(&self, name: &str, arguments_json: &str) -> Result<Value, McpError> {
+        let (name, arguments) = self.tool_manager.prepare_llm_call(name, arguments_json)?;
+        self.mcp_call_tool(&name, arguments).await
+    }
+    
+    /// 列出指定分组中的工具
+    pub fn mcp_list_tools_in_group(&self, group: &str) -> Vec<Tool> {
+        self.tool_manager.list_tools_in_group(group)
+    }
+    
+    /// 列出所有工具分组
+    pub fn mcp_list_tool_groups(&self) -> Vec<ToolGroup> {
+        self.tool_manager.list_groups()
+    }
+    
+    /// 生成描述REST接口和已注册工具的OpenAPI 3.1文档
+    pub fn openapi_spec(&self) -> Value {
+        api_doc::build(self)
+    }
+    
     /// 列出所有资源
     pub fn mcp_list_resources(&self) -> Vec<Resource> {
         self.resource_manager.list_resources()
@@ -145,19 +1419,231 @@ impl RustMCP {
         self.prompt_manager.list_prompts()
     }
     
+    /// 按名称分页列出提示，参见[`PromptManager::list_prompts_page`]
+    ///
+    /// 设置[`Settings::with_prompt_page_size`]后，`prompts/list`同样分页：结果带有下一页的`nextCursor`，
+    /// 客户端在`params.cursor`中传回游标。没有设置时一次返回所有提示，传入游标时返回游标之后的所有提示。
+    ///
+    /// ```rust
+    /// use rustmcp::{FunctionPrompt, RustMCP};
+    ///
+    /// let rustmcp = RustMCP::new();
+    /// for name in ["a", "b", "c"] {
+    ///     rustmcp.add_prompt(FunctionPrompt::simple(name, |_| Ok(Vec::new())));
+    /// }
+    /// let first = rustmcp.mcp_list_prompts_page(None, 2).unwrap();
+    /// let second = rustmcp.mcp_list_prompts_page(first.next_cursor.as_deref(), 2).unwrap();
+    /// assert_eq!(second.prompts[0]["name"], "c");
+    /// ```
+    pub fn mcp_list_prompts_page(&self, cursor: Option<&str>, limit: usize) -> Result<PromptPage, McpError> {
+        self.prompt_manager.list_prompts_page(cursor, limit)
+    }
+    
+    /// 序列化后的资源列表，在资源变化之前的调用之间共享，序列化失败时参见[`serialization`]
+    pub fn mcp_resource_listing(&self) -> Result<Arc<Value>, McpError> {
+        self.resource_manager.resource_listing()
+    }
+    
+    /// 序列化后的提示列表，在提示变化之前的调用之间共享，序列化失败时参见[`serialization`]
+    pub fn mcp_prompt_listing(&self) -> Result<Arc<Value>, McpError> {
+        self.prompt_manager.prompt_listing()
+    }
+    
+    /// 序列化后的工具列表，在工具变化之前的调用之间共享，序列化失败时参见[`serialization`]
+    pub fn mcp_tool_listing(&self) -> Result<Arc<Value>, McpError> {
+        self.tool_manager.tool_listing()
+    }
+    
+    /// 注册表的代数，添加、删除、替换、启用或禁用工具、资源和提示后增大，参见[`generation`]
+    ///
+    /// 代数是三个注册表修订号之和，列表的缓存按同样的修订号失效，代数不变时列表的内容也不变。
+    pub fn registry_generation(&self) -> u64 {
+        self.tool_manager.revision() + self.resource_manager.revision() + self.prompt_manager.revision()
+    }
+    
+    /// 默认协议版本的initialize结果（不含每个会话的`_meta.warnings`）
+    ///
+    /// 结果被缓存，注册表和只读模式不变时多次调用共享同一份；添加、删除、启用或禁用工具，
+    /// 添加或删除资源和提示，切换只读模式，更换方法策略，或者通过[`RustMCP::with_settings`]更换设置后重新生成。
+    ///
+    /// ```rust
+    /// use rustmcp::RustMCP;
+    /// use std::sync::Arc;
+    ///
+    /// let rustmcp = RustMCP::new();
+    /// assert!(Arc::ptr_eq(&rustmcp.mcp_initialize_result(), &rustmcp.mcp_initialize_result()));
+    /// ```
+    pub fn mcp_initialize_result(&self) -> Arc<InitializeResult> {
+        self.initialize_result_for(crate::protocol::PROTOCOL_VERSION)
+    }
+    
+    /// 指定协议版本的initialize结果，与[`RustMCP::mcp_initialize_result`]共用缓存
+    pub(crate) fn initialize_result_for(&self, protocol_version: &'static str) -> Arc<InitializeResult> {
+        self.initialize_cache.get_or_build(protocol_version, self.registry_revision(), || {
+            dispatch::initialize_result(self, protocol_version)
+        })
+    }
+    
+    /// 实例的启动信息，与`GET /mcp/info`的响应相同，参见[`info`]模块
+    pub fn info(&self) -> Arc<Value> {
+        self.info.get_or_build(self, self.registry_revision())
+    }
+    
+    /// 注册表、方法策略和只读模式的版本，用于判断缓存的结果是否过期
+    pub(crate) fn registry_revision(&self) -> dispatch::InitializeRevision {
+        (
+            self.tool_manager.revision(),
+            self.resource_manager.revision(),
+            self.prompt_manager.revision(),
+            self.method_policy.revision(),
+            self.is_read_only(),
+        )
+    }
+    
     /// 调用工具
-    pub async fn mcp_call_tool(&self, name: &str, arguments: Option<HashMap<String, Value>>) -> Result<Value, String> {
-        self.tool_manager.call_tool(name, arguments)
+    pub async fn mcp_call_tool(&self, name: &str, arguments: Option<HashMap<String, Value>>) -> Result<Value, McpError> {
+        self.mcp_call_tool_with_context(name, arguments, &Context::new()).await
+    }
+    
+    /// 使用指定上下文调用工具
+    ///
+    /// 工具设置了[`max_concurrency`](FunctionTool::with_max_concurrency)时，先等待空闲的许可。
+    ///
+    /// 参数在调用路径上移动而不复制：`tools/call`的`arguments`从请求中取出后依次交给钩子、
+    /// 输入校验和工具函数，工具函数拿到的是唯一的一份，返回后随之释放。输入校验直接在参数表上进行，
+    /// 审计摘要和幂等键指纹把参数直接写入哈希，都不生成副本。仍会复制参数的只有：
+    /// 设置了[重试](FunctionToolBuilder::retries)时除最后一次以外的每次尝试，以及开启了
+    /// [采集](crate::server::capture)时在处理前生成的脱敏、截断后的请求文本。
+    ///
+    /// ```rust
+    /// use rustmcp::{Context, FunctionTool, RustMCP};
+    /// use serde_json::json;
+    /// use std::collections::HashMap;
+    ///
+    /// # futures::executor::block_on(async {
+    /// let rustmcp = RustMCP::new();
+    /// rustmcp.add_tool(FunctionTool::simple("echo", "Echoes the arguments", |args| Ok(json!(args))));
+    /// let arguments = HashMap::from([("text".to_string(), json!("hi"))]);
+    /// let result = rustmcp.mcp_call_tool_with_context("echo", Some(arguments), &Context::new()).await.unwrap();
+    /// assert_eq!(result["text"], "hi");
+    /// # });
+    /// ```
+    ///
+    /// 参数不被复制由`tests/argument_copies.rs`统计堆内存验证。
+    pub async fn mcp_call_tool_with_context(&self, name: &str, arguments: Option<HashMap<String, Value>>, ctx: &Context) -> Result<Value, McpError> {
+        let _in_flight = self.shutdown.enter()?;
+        let name = &*self.tool_manager.resolve_tool_name(name);
+        let ctx = &self.attach_state(ctx);
+        let _permit = match self.tool_manager.concurrency_permits(name) {
+            Some(permits) => permits.acquire_owned().await.ok(),
+            None => None,
+        };
+        let started = std::time::Instant::now();
+        let audit = self.audit.as_ref().map(|audit| (audit, audit::digest(arguments.as_ref()), std::time::SystemTime::now()));
+        let result = match self.check_read_only(name).and_then(|()| self.hooks.run_before_tool_call(name, arguments, ctx)) {
+            Ok(arguments) => self.invoke_tool(name, arguments, ctx).await,
+            Err(e) => Err(e),
+        }
+        .map_err(|e| self.shape_error(e, ctx));
+        let elapsed = started.elapsed();
+        self.hooks.run_after_tool_call(name, &result, elapsed);
+        if let Some((audit, digest, timestamp)) = audit {
+            audit.record(AuditEntry::new(name, ctx, digest, timestamp, elapsed, &result));
+        }
+        result
+    }
+    
+    /// 按[设置](error_shaping)整理工具执行失败的错误文本，截断时在结果元数据中记录原始字节数
+    fn shape_error(&self, error: McpError, ctx: &Context) -> McpError {
+        let McpError::ToolExecution { name, message } = error else {
+            return error;
+        };
+        let shaped = self.error_shaper.shape(message);
+        if let Some(original_size) = shaped.original_size {
+            ctx.insert_result_meta("errorTruncated", Value::Bool(true));
+            ctx.insert_result_meta("originalErrorSize", Value::from(original_size));
+        }
+        McpError::ToolExecution { name, message: shaped.text }
+    }
+    
+    /// 执行工具函数，开启了[看门狗](watchdog)时登记调用，已经升级的工具放到阻塞线程池执行
+    async fn invoke_tool(&self, name: &str, arguments: Option<HashMap<String, Value>>, ctx: &Context) -> Result<Value, McpError> {
+        let Some(watchdog) = &self.watchdog else {
+            return self.tool_manager.call_tool(name, arguments, ctx);
+        };
+        if watchdog.is_escalated(name) && !watchdog::is_offloaded() {
+            let (tools, tool, ctx) = (self.tool_manager.clone(), name.to_string(), ctx.clone());
+            return tokio::task::spawn_blocking(move || tools.call_tool(&tool, arguments, &ctx))
+                .await
+                .unwrap_or_else(|e| Err(McpError::Internal(format!("Tool '{}' failed: {}", name, e))));
+        }
+        let _watch = watchdog.watch(name);
+        self.tool_manager.call_tool(name, arguments, ctx)
     }
     
     /// 读取资源
-    pub fn mcp_read_resource(&self, uri: &str) -> Result<Value, String> {
-        self.resource_manager.read_resource(uri)
+    ///
+    /// 不询问[加载器](loader)，只能读取已经加载的资源。
+    pub fn mcp_read_resource(&self, uri: &str) -> Result<ResourceContents, McpError> {
+        self.mcp_read_resource_with_context(uri, &Context::new())
+    }
+    
+    /// 使用指定上下文读取资源
+    pub fn mcp_read_resource_with_context(&self, uri: &str, ctx: &Context) -> Result<ResourceContents, McpError> {
+        self.mcp_read_representation(uri, None, ctx)
+    }
+    
+    /// 读取资源指定MIME类型的表示
+    ///
+    /// 同一URI可以注册多个MIME类型不同的资源，`resources/list`分别列出。`mime_type`为`None`时
+    /// 读取最先注册的表示；指定的表示不存在时返回参数错误，错误消息中列出可用的MIME类型。
+    /// `resources/read`通过扩展参数`mimeType`选择表示，HTTP的`GET /mcp/resources/read`使用同名查询参数。
+    ///
+    /// ```rust
+    /// use rustmcp::{Context, FunctionResource, RustMCP};
+    /// use serde_json::json;
+    ///
+    /// let rustmcp = RustMCP::new();
+    /// rustmcp.add_resource(FunctionResource::from_function(
+    ///     || Ok(json!("city,temp\nOslo,4\n")), "data://weather".into(), None, None, Some("text/csv".into()), None, None, None,
+    /// ));
+    /// rustmcp.add_resource(FunctionResource::from_function(
+    ///     || Ok(json!([{"city": "Oslo", "temp": 4}])), "data://weather".into(), None, None, Some("application/json".into()), None, None, None,
+    /// ));
+    /// let json = rustmcp.mcp_read_representation("data://weather", Some("application/json"), &Context::new()).unwrap();
+    /// assert_eq!(json.text(), Some(r#"[{"city":"Oslo","temp":4}]"#));
+    /// ```
+    pub fn mcp_read_representation(&self, uri: &str, mime_type: Option<&str>, ctx: &Context) -> Result<ResourceContents, McpError> {
+        let ctx = &self.attach_state(ctx);
+        self.hooks.run_before_resource_read(uri, ctx)?;
+        self.resource_manager.read_representation(uri, mime_type, ctx)
+    }
+    
+    /// 打开资源，流式资源返回字节流，同时返回MIME类型
+    pub(crate) async fn open_resource(&self, uri: &str, mime_type: Option<&str>, ctx: &Context) -> Result<(ResourceBody, String), McpError> {
+        let ctx = &self.attach_state(ctx);
+        self.hooks.run_before_resource_read(uri, ctx)?;
+        self.resource_manager.load_missing(uri).await?;
+        self.resource_manager.open_resource(uri, mime_type, ctx)
     }
     
     /// 获取提示
-    pub fn mcp_get_prompt(&self, name: &str, arguments: Option<HashMap<String, Value>>) -> Result<Vec<PromptMessage>, String> {
-        self.prompt_manager.get_prompt(name, arguments)
+    pub fn mcp_get_prompt(&self, name: &str, arguments: Option<HashMap<String, Value>>) -> Result<Vec<PromptMessage>, McpError> {
+        self.mcp_get_prompt_with_context(name, arguments, &Context::new())
+    }
+    
+    /// 使用指定上下文获取提示
+    pub fn mcp_get_prompt_with_context(&self, name: &str, arguments: Option<HashMap<String, Value>>, ctx: &Context) -> Result<Vec<PromptMessage>, McpError> {
+        let ctx = &self.attach_state(ctx);
+        let arguments = self.hooks.run_before_prompt_get(name, arguments, ctx)?;
+        self.prompt_manager.get_prompt(name, arguments, ctx)
+    }
+    
+    /// 执行提示，流式提示返回消息流
+    pub(crate) fn open_prompt(&self, name: &str, arguments: Option<HashMap<String, Value>>, ctx: &Context) -> Result<PromptBody, McpError> {
+        let ctx = &self.attach_state(ctx);
+        let arguments = self.hooks.run_before_prompt_get(name, arguments, ctx)?;
+        self.prompt_manager.open_prompt(name, arguments, ctx)
     }
 }
 
@@ -167,20 +1653,235 @@ impl Default for RustMCP {
     }
 }
 
+/// 新实例的共享状态，包含默认配置的出站HTTP客户端
+fn initial_state() -> Arc<StateMap> {
+    #[cfg_attr(not(feature = "http-client"), allow(unused_mut))]
+    let mut state = StateMap::new();
+    #[cfg(feature = "http-client")]
+    state.insert(http_client::HttpClient::default());
+    Arc::new(state)
+}
+
 /// 创建Axum应用
+///
+/// 要让请求上下文中包含远端地址，需要使用[`serve`]启动服务，
+/// 或者自行调用`into_make_service_with_connect_info::<SocketAddr>()`。
+/// 创建时会记录[`RustMCP::validate`]发现的问题。
+///
+/// # Panics
+///
+/// 设置了`strict_validation`且校验有错误时panic，需要处理错误时使用[`try_create_app`]。
 pub fn create_app(rustmcp: RustMCP) -> Router {
+    match try_create_app(rustmcp) {
+        Ok(app) => app,
+        Err(errors) => {
+            let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            panic!("Refusing to start with invalid registrations: {}", errors.join("; "))
+        }
+    }
+}
+
+/// 创建Axum应用，设置了`strict_validation`且校验有错误时返回这些错误
+pub fn try_create_app(rustmcp: RustMCP) -> Result<Router, Vec<ValidationIssue>> {
+    rustmcp.check_startup()?;
+    rustmcp.health.start_pending();
+    rustmcp.sessions.start_reaper(std::time::Duration::from_millis(rustmcp.settings.session_reap_interval_ms));
+    rustmcp.sessions.start_forwarder(&rustmcp.notifier);
+    #[cfg(feature = "admin")]
+    let admin_router = rustmcp
+        .settings
+        .admin_token
+        .clone()
+        .map(|token| admin::create_admin_router(rustmcp.clone(), admin::AdminAuth::bearer_token(token)));
+    #[cfg(not(feature = "admin"))]
+    if rustmcp.settings.admin_token.is_some() {
+        log::warn!("admin_token is set but the admin feature is disabled; the admin API is not mounted");
+    }
+    let compress_above = rustmcp.settings.compression.then_some(rustmcp.settings.compression_min_bytes);
     let shared_state = Arc::new(rustmcp);
+    // REST接口按对应的JSON-RPC方法受方法策略限制
+    let policy = |method: &'static str| {
+        axum::middleware::from_fn_with_state((shared_state.clone(), method), method_policy_guard)
+    };
     
-    Router::new()
+    let app = Router::new()
         .route("/", get(root))
         .route("/health", get(health_check))
-        .route("/mcp/tools", get(mcp_list_tools_handler))
-        .route("/mcp/resources", get(mcp_list_resources_handler))
-        .route("/mcp/prompts", get(mcp_list_prompts_handler))
-        .route("/mcp/call-tool", post(mcp_call_tool_handler))
-        .route("/mcp", post(mcp_jsonrpc_handler))
+        .route("/ready", get(readiness_check))
+        .route("/mcp/tools", get(mcp_list_tools_handler).route_layer(policy("tools/list")))
+        .route("/mcp/resources", get(mcp_list_resources_handler).route_layer(policy("resources/list")))
+        .route("/mcp/prompts", get(mcp_list_prompts_handler).route_layer(policy("prompts/list")))
+        .route("/mcp/resources/read", get(mcp_read_resource_handler).route_layer(policy("resources/read")))
+        .route("/mcp/call-tool", post(mcp_call_tool_handler).route_layer(policy("tools/call")))
+        .route("/mcp/tools/:name/call", post(mcp_call_named_tool_handler).route_layer(policy("tools/call")))
+        .route("/mcp/tags", get(mcp_tags_handler))
+        .route("/mcp/openapi.json", get(mcp_openapi_handler))
+        .route("/mcp/info", get(mcp_info_handler))
+        .route("/mcp", post(mcp_jsonrpc_handler).get(mcp_event_stream_handler).delete(mcp_delete_session_handler))
         .route("/mcp/ws", get(ws::ws_handler))
-        .with_state(shared_state)
+        .with_state(shared_state);
+    #[cfg(feature = "admin")]
+    let app = match admin_router {
+        Some(admin_router) => app.nest("/admin", admin_router),
+        None => app,
+    };
+    let app = match compress_above {
+        Some(min_bytes) => app.layer(compression::layer(min_bytes)),
+        None => app,
+    };
+    Ok(app)
+}
+
+/// 创建按路径前缀挂载多个实例的Axum应用
+///
+/// 每个实例的全部路由（`/mcp`、`/mcp/ws`、REST接口和管理接口）挂载在各自的前缀下，
+/// 例如`/teams/alpha/mcp`。工具、会话、通知和限流都属于各自的实例，互不影响。
+/// 根路径的`/health`汇总各实例的注册项和会话数量，各实例自己的`/health`仍然可用。
+///
+/// ```rust
+/// use rustmcp::{create_multi_app, RustMCP};
+///
+/// let app = create_multi_app(vec![("/teams/alpha", RustMCP::new()), ("/teams/beta", RustMCP::new())]);
+/// // POST /teams/alpha/mcp、GET /teams/beta/tools、GET /health……
+/// ```
+///
+/// # Panics
+///
+/// 前缀为空、为`/`或重复时panic；设置了`strict_validation`的实例校验有错误时panic，
+/// 需要处理校验错误时使用[`try_create_multi_app`]。
+pub fn create_multi_app(servers: Vec<(&str, RustMCP)>) -> Router {
+    match try_create_multi_app(servers) {
+        Ok(app) => app,
+        Err(errors) => {
+            let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            panic!("Refusing to start with invalid registrations: {}", errors.join("; "))
+        }
+    }
+}
+
+/// 创建按路径前缀挂载多个实例的Axum应用，实例校验有错误时返回所有实例的错误
+///
+/// # Panics
+///
+/// 前缀为空、为`/`或重复时panic。
+pub fn try_create_multi_app(servers: Vec<(&str, RustMCP)>) -> Result<Router, Vec<ValidationIssue>> {
+    let mut app = Router::new();
+    let mut instances: Vec<(String, RustMCP)> = Vec::new();
+    let mut errors = Vec::new();
+    for (prefix, rustmcp) in servers {
+        let prefix = format!("/{}", prefix.trim_matches('/'));
+        assert!(prefix != "/", "Server prefix must not be empty or '/'");
+        assert!(
+            !instances.iter().any(|(existing, _)| *existing == prefix),
+            "Duplicate server prefix '{}'",
+            prefix
+        );
+        rustmcp.info.set_prefix(&prefix);
+        match try_create_app(rustmcp.clone()) {
+            Ok(router) => app = app.nest(&prefix, router),
+            Err(issues) => errors.extend(issues),
+        }
+        instances.push((prefix, rustmcp));
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let instances = Arc::new(instances);
+    Ok(app.route("/health", get(move || multi_health_check(instances.clone()))))
+}
+
+/// 启动服务，并为每个连接提供远端地址信息
+pub async fn serve(listener: tokio::net::TcpListener, app: Router) -> std::io::Result<()> {
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await
+}
+
+/// 创建应用并在`listener`上启动服务
+///
+/// 与`serve(listener, create_app(rustmcp))`相同，另外记录绑定的地址，并在`info`级别记录一行
+/// `RustMCP started: {...}`，内容为[`RustMCP::info`]。开始监听之前执行工具的启动钩子
+/// （[`RustMCP::initialize_tools`]），某个钩子失败时返回错误，不开始监听。
+///
+/// # Panics
+///
+/// 与[`create_app`]相同，设置了`strict_validation`且校验有错误时panic。
+pub async fn run(rustmcp: RustMCP, listener: tokio::net::TcpListener) -> std::io::Result<()> {
+    rustmcp.initialize_tools().await.map_err(std::io::Error::other)?;
+    rustmcp.info.add_address(listener.local_addr()?);
+    let app = create_app(rustmcp.clone());
+    log::info!("RustMCP started: {}", rustmcp.info());
+    serve(listener, app).await
+}
+
+/// 比较令牌，耗时与不匹配的位置无关
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 根据HTTP请求构造请求上下文
+///
+/// 带会话的请求使用该会话的存储，否则使用临时存储；会话由[`http_session`]或`initialize`取得。
+fn http_request_context(
+    rustmcp: &RustMCP,
+    connect_info: Option<ConnectInfo<std::net::SocketAddr>>,
+    identity: Option<Extension<Identity>>,
+    headers: &HeaderMap,
+    session: Option<(String, HttpSession)>,
+) -> Arc<RequestContext> {
+    let limits = rustmcp.settings.session_limits();
+    let (session_id, session, lifecycle, connection) = match session {
+        Some((id, (session, lifecycle, connection))) => (Some(id), session, lifecycle, Some(connection)),
+        None => (None, SessionStore::ephemeral(limits), Lifecycle::new(), None),
+    };
+    Arc::new(RequestContext {
+        transport: TransportKind::Http,
+        remote_addr: connect_info.map(|ConnectInfo(addr)| addr),
+        headers: RequestContext::collect_headers(headers),
+        session_id,
+        identity: identity.map(|Extension(identity)| identity),
+        back_channel: None,
+        session: Some(session),
+        lifecycle,
+        lifecycle_policy: rustmcp.settings.lifecycle_policy,
+        connection,
+    })
+}
+
+/// 请求头`Mcp-Session-Id`指定的会话不存在：从未创建、已过期或已终止，响应为404
+struct UnknownSession;
+
+impl UnknownSession {
+    const MESSAGE: &'static str = "Unknown or expired session; send initialize without Mcp-Session-Id to start a new one";
+}
+
+impl IntoResponse for UnknownSession {
+    fn into_response(self) -> axum::response::Response {
+        (StatusCode::NOT_FOUND, Self::MESSAGE).into_response()
+    }
+}
+
+impl From<UnknownSession> for (StatusCode, String) {
+    fn from(_: UnknownSession) -> Self {
+        (StatusCode::NOT_FOUND, UnknownSession::MESSAGE.to_string())
+    }
+}
+
+/// 查找`Mcp-Session-Id`请求头指定的会话，没有请求头时为`None`
+fn http_session(rustmcp: &RustMCP, headers: &HeaderMap) -> Result<Option<(String, HttpSession)>, UnknownSession> {
+    let Some(id) = session_id_header(headers) else {
+        return Ok(None);
+    };
+    match rustmcp.sessions.get(&id) {
+        Some(session) => Ok(Some((id, session))),
+        None => Err(UnknownSession),
+    }
+}
+
+/// 读取`Mcp-Session-Id`请求头
+fn session_id_header(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("mcp-session-id")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
 }
 
 // HTTP处理函数
@@ -188,91 +1889,435 @@ async fn root() -> &'static str {
     "Welcome to RustMCP-rs server!"
 }
 
-async fn health_check() -> &'static str {
-    "OK"
+async fn health_check(State(rustmcp): State<Arc<RustMCP>>) -> Json<Value> {
+    rustmcp.health.start_pending();
+    let load = rustmcp.load_metrics();
+    Json(serde_json::json!({
+        "status": "ok",
+        "degraded": load.degraded,
+        "load": load,
+        "readOnly": rustmcp.is_read_only(),
+        "droppedNotifications": rustmcp.dropped_notifications(),
+        "droppedAuditEntries": rustmcp.dropped_audit_entries(),
+        "sessions": rustmcp.session_metrics(),
+        "dependencies": rustmcp.dependency_status(),
+    }))
+}
+
+/// 关键依赖都正常时返回200，否则返回503和失败的依赖
+async fn readiness_check(State(rustmcp): State<Arc<RustMCP>>) -> (StatusCode, Json<Value>) {
+    rustmcp.health.start_pending();
+    let failing = rustmcp.health.failing_critical();
+    let ready = failing.is_empty() && rustmcp.tool_hooks_ready();
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let mut body = serde_json::json!({
+        "ready": ready,
+        "failing": failing,
+    });
+    let hooks = rustmcp.tool_hook_status();
+    if !hooks.is_empty() {
+        body["toolHooks"] = serde_json::to_value(hooks).unwrap_or_default();
+    }
+    (status, Json(body))
+}
+
+/// 汇总多个实例的健康状态
+async fn multi_health_check(instances: Arc<Vec<(String, RustMCP)>>) -> Json<Value> {
+    let servers: serde_json::Map<String, Value> = instances
+        .iter()
+        .map(|(prefix, rustmcp)| {
+            let status = serde_json::json!({
+                "tools": rustmcp.mcp_list_tools().len(),
+                "resources": rustmcp.mcp_list_resources().len(),
+                "prompts": rustmcp.mcp_list_prompts().len(),
+                "sessions": rustmcp.sessions.count(),
+                "readOnly": rustmcp.is_read_only(),
+                "droppedNotifications": rustmcp.dropped_notifications(),
+                "ready": rustmcp.is_ready(),
+                "degraded": rustmcp.load_metrics().degraded,
+            });
+            (prefix.clone(), status)
+        })
+        .collect();
+    Json(serde_json::json!({
+        "status": "ok",
+        "servers": servers,
+    }))
+}
+
+/// 方法策略拒绝REST接口对应的JSON-RPC方法时返回404，与不存在的接口相同
+async fn method_policy_guard(
+    State((rustmcp, method)): State<(Arc<RustMCP>, &'static str)>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    if rustmcp.is_method_allowed(method) {
+        next.run(request).await
+    } else {
+        StatusCode::NOT_FOUND.into_response()
+    }
+}
+
+/// 工具列表查询参数
+#[derive(Deserialize)]
+struct ListToolsQuery {
+    group: Option<String>,
+}
+
+async fn mcp_list_tools_handler(
+    State(rustmcp): State<Arc<RustMCP>>,
+    Query(query): Query<ListToolsQuery>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    let generation = rustmcp.registry_generation();
+    let etag = generation::etag(generation, query.group.as_deref(), &headers);
+    if generation::is_fresh(&headers, &etag) {
+        return generation::not_modified(&etag);
+    }
+    let tools = match &query.group {
+        Some(group) => serialization::serialize_items(
+            &rustmcp.mcp_list_tools_in_group(group),
+            "tool",
+            |tool| &tool.name,
+            rustmcp.settings().serialization_policy,
+        )
+        .map(Value::Array),
+        None => rustmcp.mcp_tool_listing().map(|tools| Value::clone(&tools)),
+    };
+    listing_response(&rustmcp, &headers, &etag, tools)
+}
+
+async fn mcp_list_resources_handler(State(rustmcp): State<Arc<RustMCP>>, headers: HeaderMap) -> axum::response::Response {
+    let generation = rustmcp.registry_generation();
+    let etag = generation::etag(generation, None, &headers);
+    if generation::is_fresh(&headers, &etag) {
+        return generation::not_modified(&etag);
+    }
+    let resources = rustmcp.mcp_resource_listing().map(|resources| Value::clone(&resources));
+    listing_response(&rustmcp, &headers, &etag, resources)
+}
+
+async fn mcp_list_prompts_handler(State(rustmcp): State<Arc<RustMCP>>, headers: HeaderMap) -> axum::response::Response {
+    let generation = rustmcp.registry_generation();
+    let etag = generation::etag(generation, None, &headers);
+    if generation::is_fresh(&headers, &etag) {
+        return generation::not_modified(&etag);
+    }
+    let prompts = rustmcp.mcp_prompt_listing().map(|prompts| Value::clone(&prompts));
+    listing_response(&rustmcp, &headers, &etag, prompts)
 }
 
-async fn mcp_list_tools_handler(State(rustmcp): State<Arc<RustMCP>>) -> String {
-    let tools = rustmcp.mcp_list_tools();
-    serde_json::to_string(&tools).unwrap_or_else(|_| "[]".to_string())
+/// REST列表接口的响应，列表序列化失败时返回`500`和带有失败项名称的错误，参见[`serialization`]
+fn listing_response(rustmcp: &RustMCP, headers: &HeaderMap, etag: &str, listing: Result<Value, McpError>) -> axum::response::Response {
+    match listing {
+        Ok(listing) => generation::tagged(etag, json_text(rustmcp, &localized(headers, listing), "[]")),
+        Err(e) => (status_for_error(&e), Json(serde_json::json!({ "error": e.to_jsonrpc_error() }))).into_response(),
+    }
+}
+
+/// 按`Accept-Language`请求头选择列表中的标题和描述，参见[`locale`]
+fn localized(headers: &HeaderMap, mut listing: Value) -> Value {
+    if let Some(accept_language) = headers.get(axum::http::header::ACCEPT_LANGUAGE).and_then(|value| value.to_str().ok()) {
+        locale::localize_listing(&mut listing, &locale::parse_accept_language(accept_language));
+    }
+    listing
 }
 
-async fn mcp_list_resources_handler(State(rustmcp): State<Arc<RustMCP>>) -> String {
-    let resources = rustmcp.mcp_list_resources();
-    serde_json::to_string(&resources).unwrap_or_else(|_| "[]".to_string())
+/// 序列化REST接口的响应体，开启规范化JSON时排列对象的键，序列化失败时返回`fallback`
+fn json_text(rustmcp: &RustMCP, value: &impl Serialize, fallback: &str) -> String {
+    match serde_json::to_value(value) {
+        Ok(mut value) => {
+            rustmcp.canonicalize(&mut value);
+            value.to_string()
+        }
+        Err(_) => fallback.to_string(),
+    }
 }
 
-async fn mcp_list_prompts_handler(State(rustmcp): State<Arc<RustMCP>>) -> String {
-    let prompts = rustmcp.mcp_list_prompts();
-    serde_json::to_string(&prompts).unwrap_or_else(|_| "[]".to_string())
+/// `/mcp/call-tool`的查询参数
+#[derive(Deserialize)]
+struct CallToolQuery {
+    /// 返回格式：`raw`（默认）返回工具的原始返回值；
+    /// `mcp`返回与JSON-RPC `tools/call`相同的`content`/`isError`结果
+    format: Option<String>,
 }
 
 async fn mcp_call_tool_handler(
     State(rustmcp): State<Arc<RustMCP>>,
-    body: String,
-) -> Result<String, (StatusCode, String)> {
+    Query(query): Query<CallToolQuery>,
+    connect_info: Option<ConnectInfo<std::net::SocketAddr>>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+    JsonRpcBody(body): JsonRpcBody,
+) -> axum::response::Response {
     #[derive(Deserialize)]
     struct CallToolRequest {
         name: String,
         arguments: Option<std::collections::HashMap<String, Value>>,
     }
 
-    let request: CallToolRequest = serde_json::from_str(&body)
-        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid JSON: {}", e)))?;
+    let mcp_format = match query.format.as_deref() {
+        None | Some("raw") => false,
+        Some("mcp") => true,
+        Some(other) => {
+            return (StatusCode::BAD_REQUEST, format!("Unknown format '{}', expected 'raw' or 'mcp'", other)).into_response();
+        }
+    };
+
+    let request: CallToolRequest = match serde_json::from_str(&body) {
+        Ok(request) => request,
+        Err(e) => return (StatusCode::BAD_REQUEST, format!("Invalid JSON: {}", e)).into_response(),
+    };
 
-    match rustmcp.mcp_call_tool(&request.name, request.arguments).await {
-        Ok(result) => Ok(serde_json::to_string(&result)
-            .unwrap_or_else(|_| r#"{"error": "Failed to serialize result"}"#.to_string())),
-        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e)),
+    let session = match http_session(&rustmcp, &headers) {
+        Ok(session) => session,
+        Err(unknown) => return unknown.into_response(),
+    };
+    let request_context = http_request_context(&rustmcp, connect_info, identity, &headers, session);
+    // 与JSON-RPC的`tools/call`一样计入并发请求数并限流，计数持续到响应生成
+    let _admitted = match admit_rest_call(&rustmcp, &request_context) {
+        Ok(admitted) => admitted,
+        Err(e) => {
+            return match mcp_format {
+                true => (status_for_error(&e), Json(serde_json::json!({ "error": e.to_jsonrpc_error() }))).into_response(),
+                false => (status_for_error(&e), e.to_string()).into_response(),
+            };
+        }
+    };
+    let ctx = Context::new().with_request(request_context);
+    let result = rustmcp.mcp_call_tool_with_context(&request.name, request.arguments, &ctx).await;
+    if mcp_format {
+        return match dispatch::tool_call_result(&rustmcp, &request.name, result, &ctx) {
+            Ok(mut envelope) => {
+                rustmcp.canonicalize(&mut envelope);
+                Json(envelope).into_response()
+            }
+            Err(e) => (status_for_error(&e), Json(serde_json::json!({ "error": e.to_jsonrpc_error() }))).into_response(),
+        };
+    }
+    match result {
+        Ok(result) => json_text(&rustmcp, &result, r#"{"error": "Failed to serialize result"}"#).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// REST接口调用工具前的并发请求数和限流检查，与JSON-RPC的`tools/call`共用计数
+fn admit_rest_call(rustmcp: &RustMCP, request_context: &RequestContext) -> Result<Option<admission::Admitted>, McpError> {
+    let admitted = rustmcp.admit("tools/call", request_context)?;
+    rustmcp.check_rate_limit("tools/call", request_context)?;
+    Ok(admitted)
+}
+
+/// MCP错误对应的HTTP状态码
+fn status_for_error(error: &McpError) -> StatusCode {
+    match error {
+        McpError::ToolNotFound { .. }
+        | McpError::ToolDisabled(_)
+        | McpError::ResourceNotFound { .. }
+        | McpError::PromptNotFound { .. }
+        | McpError::ResourceDisabled(_)
+        | McpError::PromptDisabled(_)
+        | McpError::MethodNotFound(_) => StatusCode::NOT_FOUND,
+        McpError::InvalidParams { .. } | McpError::ReservedMethod(_) => StatusCode::BAD_REQUEST,
+        McpError::RateLimited { .. } | McpError::QuotaExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+        McpError::ReadOnly(_) | McpError::Unauthorized(_) => StatusCode::FORBIDDEN,
+        McpError::ToolNotBound(_) => StatusCode::NOT_IMPLEMENTED,
+        McpError::ShuttingDown { .. } | McpError::Overloaded { .. } => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// 按路径中的工具名称调用工具，请求体即为工具参数
+async fn mcp_call_named_tool_handler(
+    State(rustmcp): State<Arc<RustMCP>>,
+    Path(name): Path<String>,
+    connect_info: Option<ConnectInfo<std::net::SocketAddr>>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    let arguments: Option<HashMap<String, Value>> = if body.is_empty() {
+        None
+    } else {
+        Some(serde_json::from_slice(&body).map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid JSON: {}", e)))?)
+    };
+
+    let session = http_session(&rustmcp, &headers)?;
+    let request_context = http_request_context(&rustmcp, connect_info, identity, &headers, session);
+    let _admitted = admit_rest_call(&rustmcp, &request_context).map_err(|e| (status_for_error(&e), e.to_string()))?;
+    let ctx = Context::new().with_request(request_context);
+    match rustmcp.mcp_call_tool_with_context(&name, arguments, &ctx).await {
+        Ok(mut result) => {
+            rustmcp.canonicalize(&mut result);
+            Ok(Json(result))
+        }
+        Err(e) => Err((status_for_error(&e), e.to_string())),
+    }
+}
+
+/// `/mcp/resources/read`的查询参数
+#[derive(Deserialize)]
+struct ReadResourceQuery {
+    uri: String,
+    /// 选择资源的表示，参见[`RustMCP::mcp_read_representation`]
+    #[serde(rename = "mimeType")]
+    mime_type: Option<String>,
+}
+
+/// 读取资源，流式资源作为分块的响应体返回，参见[`streaming`]
+async fn mcp_read_resource_handler(
+    State(rustmcp): State<Arc<RustMCP>>,
+    Query(query): Query<ReadResourceQuery>,
+    connect_info: Option<ConnectInfo<std::net::SocketAddr>>,
+    identity: Option<Extension<Identity>>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    let session = match http_session(&rustmcp, &headers) {
+        Ok(session) => session,
+        Err(unknown) => return unknown.into_response(),
+    };
+    let ctx = Context::new().with_request(http_request_context(&rustmcp, connect_info, identity, &headers, session));
+    match rustmcp.open_resource(&query.uri, query.mime_type.as_deref(), &ctx).await {
+        Ok((ResourceBody::Stream(stream), mime_type)) => {
+            let stream = stream.map(|chunk| chunk.map_err(std::io::Error::other));
+            ([(axum::http::header::CONTENT_TYPE, mime_type)], axum::body::Body::from_stream(stream)).into_response()
+        }
+        Ok((ResourceBody::Contents(ResourceContents::Text { text, mime_type, .. }), _)) => {
+            ([(axum::http::header::CONTENT_TYPE, mime_type)], text).into_response()
+        }
+        Ok((ResourceBody::Contents(ResourceContents::Blob { blob, mime_type, .. }), _)) => {
+            match base64::engine::general_purpose::STANDARD.decode(blob) {
+                Ok(bytes) => ([(axum::http::header::CONTENT_TYPE, mime_type)], bytes).into_response(),
+                Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+            }
+        }
+        Err(e) => (status_for_error(&e), e.to_string()).into_response(),
+    }
+}
+
+/// 打开`Mcp-Session-Id`指定的会话的通知流，会话不存在时返回404，参见[`sse`]
+async fn mcp_event_stream_handler(State(rustmcp): State<Arc<RustMCP>>, headers: HeaderMap) -> axum::response::Response {
+    let Some(id) = session_id_header(&headers) else {
+        return (StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header").into_response();
+    };
+    let Some((_, _, connection)) = rustmcp.sessions.get(&id) else {
+        return UnknownSession.into_response();
+    };
+    let Some(events) = connection.events() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let last_event_id = headers.get("last-event-id").and_then(|v| v.to_str().ok());
+    let trace = rustmcp.tracer.tap(TransportKind::Http, Some(&id));
+    let stream = Sse::new(sse::stream(events, last_event_id, rustmcp.settings.sse_retry(), trace));
+    match rustmcp.settings.sse_keep_alive() {
+        Some(interval) => stream.keep_alive(KeepAlive::new().interval(interval).text("keepalive")).into_response(),
+        None => stream.into_response(),
+    }
+}
+
+/// 终止`Mcp-Session-Id`指定的会话，丢弃其会话存储
+async fn mcp_delete_session_handler(State(rustmcp): State<Arc<RustMCP>>, headers: HeaderMap) -> StatusCode {
+    match session_id_header(&headers) {
+        Some(id) if rustmcp.sessions.remove(&id) => StatusCode::NO_CONTENT,
+        Some(_) => StatusCode::NOT_FOUND,
+        None => StatusCode::BAD_REQUEST,
+    }
+}
+
+async fn mcp_tags_handler(State(rustmcp): State<Arc<RustMCP>>) -> Json<Value> {
+    Json(serde_json::to_value(rustmcp.list_tags()).unwrap_or_default())
+}
+
+async fn mcp_openapi_handler(State(rustmcp): State<Arc<RustMCP>>) -> Json<Value> {
+    let mut spec = rustmcp.openapi_spec();
+    rustmcp.canonicalize(&mut spec);
+    Json(spec)
+}
+
+// 启动信息，设置了protect_info时要求管理令牌
+async fn mcp_info_handler(State(rustmcp): State<Arc<RustMCP>>, headers: HeaderMap) -> impl IntoResponse {
+    if rustmcp.settings.protect_info {
+        let provided = headers
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        let authorized = match (provided, &rustmcp.settings.admin_token) {
+            (Some(provided), Some(token)) => constant_time_eq(provided.as_bytes(), token.as_bytes()),
+            _ => false,
+        };
+        if !authorized {
+            return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({ "error": "Unauthorized" }))).into_response();
+        }
     }
+    let mut info = (*rustmcp.info()).clone();
+    rustmcp.canonicalize(&mut info);
+    Json(info).into_response()
 }
 
 // JSON-RPC处理函数
 async fn mcp_jsonrpc_handler(
     State(rustmcp): State<Arc<RustMCP>>,
+    connect_info: Option<ConnectInfo<std::net::SocketAddr>>,
+    identity: Option<Extension<Identity>>,
     headers: HeaderMap,
-    request: Bytes,
+    JsonRpcBody(body): JsonRpcBody,
 ) -> impl IntoResponse {
-    // 记录请求头和内容
-    println!("Received request headers: {:?}", headers);
-    println!("Received request body: {}", String::from_utf8_lossy(&request));
-    
     // 解析JSON-RPC请求
-    let request: JsonRpcRequest = match serde_json::from_slice(&request) {
+    let request = JsonRpcRequest::parse(&body);
+    let initialize = matches!(&request, Ok(request) if request.method == "initialize" && request.id.is_some());
+
+    // 不带会话ID的initialize创建会话；已经初始化的会话重新initialize时重置会话，
+    // 客户端没有收到上一次响应时可以安全重试
+    let (session, created) = match session_id_header(&headers) {
+        Some(id) => {
+            if initialize {
+                rustmcp.sessions.reset(&id, &rustmcp.settings);
+            }
+            (rustmcp.sessions.get(&id).map(|session| (id, session)), false)
+        }
+        None if initialize => (Some(rustmcp.sessions.create(&rustmcp.settings)), true),
+        None => (None, false),
+    };
+    let session_id = session.as_ref().map(|(id, _)| id.clone());
+
+    let trace = rustmcp.tracer.tap(TransportKind::Http, session_id.as_deref());
+    trace.inbound(&body);
+    let reply = |status: StatusCode, response: JsonRpcResponse| {
+        if let Ok(text) = serde_json::to_string(&response) {
+            trace.outbound(&text);
+        }
+        (status, [("content-type", "application/json")], Json(response)).into_response()
+    };
+
+    let request = match request {
         Ok(req) => req,
-        Err(e) => {
-            eprintln!("Failed to parse JSON-RPC request: {}", e);
-            return (StatusCode::UNPROCESSABLE_ENTITY, format!("Failed to parse JSON: {}", e)).into_response();
+        Err(response) => {
+            log::debug!("Failed to parse JSON-RPC request: {:?}", response.error);
+            return reply(StatusCode::BAD_REQUEST, *response);
         }
     };
     
     // 记录请求日志
-    println!("Received JSON-RPC request: method={}, id={:?}", request.method, request.id);
+    log::debug!("Received JSON-RPC request: method={}, id={:?}", request.method, request.id);
+
+    // 请求头中的会话ID不存在时不创建会话，客户端需要重新initialize
+    if session.is_none() && session_id_header(&headers).is_some() {
+        let id = request.id.clone().unwrap_or(RequestId::Null);
+        return reply(StatusCode::NOT_FOUND, JsonRpcResponse::error(id, INVALID_REQUEST, UnknownSession::MESSAGE));
+    }
     
     // 处理通知消息（没有id的消息）
     if request.id.is_none() {
         match request.method.as_str() {
             "notifications/initialized" => {
                 // initialized通知不需要响应
-                println!("Received initialized notification, sending success response");
+                log::debug!("Received initialized notification");
                 // 对于通知消息，发送一个特殊的成功响应
-                let response = JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: Some(serde_json::Value::Number(serde_json::Number::from(0))),
-                    result: Some(serde_json::json!({})),
-                    error: None,
-                };
-                return (StatusCode::OK, [("content-type", "application/json")], Json(response)).into_response();
+                return reply(StatusCode::OK, JsonRpcResponse::success(0, serde_json::json!({})));
             }
             _ => {
-                println!("Unknown notification: {}, sending success response", request.method);
-                let response = JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: Some(serde_json::Value::Number(serde_json::Number::from(0))),
-                    result: Some(serde_json::json!({})),
-                    error: None,
-                };
-                return (StatusCode::OK, [("content-type", "application/json")], Json(response)).into_response();
+                log::debug!("Unknown notification: {}", request.method);
+                return reply(StatusCode::OK, JsonRpcResponse::success(0, serde_json::json!({})));
             }
         }
     }
@@ -280,295 +2325,296 @@ async fn mcp_jsonrpc_handler(
     // 为日志输出创建id的克隆
     let request_id_for_log = request.id.clone();
     
+    let request_context = http_request_context(&rustmcp, connect_info, identity, &headers, session);
+    
+    // 要求逐条发送消息的流式提示使用SSE响应
+    if prompt_streaming::wants_sse(&request, &headers) {
+        return prompt_streaming::sse_response(rustmcp, request, request_context, trace);
+    }
+    
     // 处理请求消息（有id的消息）
-    let response = match request.method.as_str() {
-        "initialize" => {
-            // 构造响应
-            let result = serde_json::json!({
-                "protocolVersion": "2024-11-05",
-                "capabilities": {
-                    "tools": {
-                        "listChanged": true
-                    },
-                    "resources": {
-                        "subscribe": true,
-                        "listChanged": true
-                    },
-                    "prompts": {
-                        "listChanged": true
-                    }
-                },
-                "serverInfo": {
-                    "name": "RustMCP-rs",
-                    "version": "0.1.0"
-                }
-            });
+    let response = dispatch::dispatch_request(&rustmcp, request, request_context).await;
+    
+    // 记录响应日志
+    log::debug!("Sending JSON-RPC response: id={:?}", request_id_for_log);
+    
+    // initialize的响应头中返回会话ID；初始化失败时丢弃刚创建的会话
+    let Some(session_id) = session_id.filter(|_| initialize) else {
+        return reply(StatusCode::OK, response);
+    };
+    if response.error.is_some() {
+        if created {
+            rustmcp.sessions.remove(&session_id);
+        }
+        return reply(StatusCode::OK, response);
+    }
+    let mut reply = reply(StatusCode::OK, response);
+    if let Ok(value) = axum::http::HeaderValue::from_str(&session_id) {
+        reply.headers_mut().insert("mcp-session-id", value);
+    }
+    reply
+}
 
-            JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id: request.id, // 保持原始ID
-                result: Some(result),
-                error: None,
-            }
-        },
-        "tools/list" => {
-            let tools = rustmcp.mcp_list_tools();
-            JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id: request.id,
-                result: Some(serde_json::json!({
-                    "tools": tools
-                })),
-                error: None,
-            }
-        },
-        "resources/list" => {
-            let resources = rustmcp.mcp_list_resources();
-            JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id: request.id,
-                result: Some(serde_json::json!({
-                    "resources": resources
-                })),
-                error: None,
-            }
-        },
-        "prompts/list" => {
-            let prompts = rustmcp.mcp_list_prompts();
-            JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id: request.id,
-                result: Some(serde_json::json!({
-                    "prompts": prompts
-                })),
-                error: None,
-            }
-        },
-        "tools/call" => {
-            if let Some(params) = request.params {
-                if let Ok(call_params) = serde_json::from_value::<serde_json::Map<String, Value>>(params) {
-                    let name = call_params.get("name").and_then(|v| v.as_str()).unwrap_or("");
-                    let arguments = call_params.get("arguments").cloned();
-                    
-                    // 转换arguments为HashMap
-                    let arguments_map = if let Some(args) = arguments {
-                        serde_json::from_value::<std::collections::HashMap<String, Value>>(args).ok()
-                    } else {
-                        None
-                    };
-                    
-                    match rustmcp.mcp_call_tool(name, arguments_map).await {
-                        Ok(result) => JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            id: request.id,
-                            result: Some(serde_json::json!({
-                                "content": [{
-                                    "type": "text",
-                                    "text": format!("{}", result)
-                                }],
-                                "isError": false
-                            })),
-                            error: None,
-                        },
-                        Err(e) => JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            id: request.id,
-                            result: Some(serde_json::json!({
-                                "content": [{
-                                    "type": "text",
-                                    "text": e
-                                }],
-                                "isError": true
-                            })),
-                            error: None,
-                        },
-                    }
-                } else {
-                    JsonRpcResponse {
-                        jsonrpc: "2.0".to_string(),
-                        id: request.id,
-                        result: None,
-                        error: Some(JsonRpcError {
-                            code: -32602,
-                            message: "Invalid params".to_string(),
-                            data: None,
-                        }),
-                    }
-                }
-            } else {
-                JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: request.id,
-                    result: None,
-                    error: Some(JsonRpcError {
-                        code: -32602,
-                        message: "Missing params".to_string(),
-                        data: None,
-                    }),
-                }
-            }
-        },
-        "resources/read" => {
-            if let Some(params) = request.params {
-                if let Ok(read_params) = serde_json::from_value::<serde_json::Map<String, Value>>(params) {
-                    let uri = read_params.get("uri").and_then(|v| v.as_str()).unwrap_or("");
-                    
-                    match rustmcp.mcp_read_resource(uri) {
-                        Ok(result) => JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            id: request.id,
-                            result: Some(serde_json::json!({
-                                "contents": [{
-                                    "uri": uri,
-                                    "text": result
-                                }]
-                            })),
-                            error: None,
-                        },
-                        Err(e) => JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            id: request.id,
-                            result: None,
-                            error: Some(JsonRpcError {
-                                code: -32000,
-                                message: e,
-                                data: None,
-                            }),
-                        },
-                    }
-                } else {
-                    JsonRpcResponse {
-                        jsonrpc: "2.0".to_string(),
-                        id: request.id,
-                        result: None,
-                        error: Some(JsonRpcError {
-                            code: -32602,
-                            message: "Invalid params".to_string(),
-                            data: None,
-                        }),
-                    }
-                }
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use crate::protocol::InitializeParams;
+    use crate::server::test_support::{capture_warnings, get, initialize, post, request, stdio, warnings};
+    use crate::{create_app, create_multi_app, FunctionPrompt, FunctionResource, FunctionTool, PromptMessage, RustMCP, Settings};
+    use crate::{PromptExample, Severity, ToolArgs, ToolExample};
+
+    #[tokio::test]
+    async fn prefixed_instances_are_isolated() {
+        let alpha = RustMCP::new();
+        alpha.add_tool(FunctionTool::simple("deploy", "Deploys alpha", |_| Ok(json!("alpha deployed"))));
+        let beta = RustMCP::new();
+        beta.add_tool(FunctionTool::simple("rollback", "Rolls back beta", |_| Ok(json!("beta rolled back"))));
+        let app = create_multi_app(vec![("/teams/alpha", alpha), ("/teams/beta", beta)]);
+
+        let list = request(1, "tools/list", json!({}));
+        let tools = post(&app, "/teams/alpha/mcp", &[], &list).await.2;
+        assert_eq!(tools["result"]["tools"].as_array().unwrap().len(), 1);
+        assert_eq!(tools["result"]["tools"][0]["name"], "deploy");
+        let tools = post(&app, "/teams/beta/mcp", &[], &list).await.2;
+        assert_eq!(tools["result"]["tools"][0]["name"], "rollback");
+
+        let call = |name: &str| request(2, "tools/call", json!({ "name": name }));
+        let result = post(&app, "/teams/beta/mcp", &[], &call("rollback")).await.2;
+        assert!(result["result"]["content"][0]["text"].as_str().unwrap().contains("beta rolled back"));
+        let result = post(&app, "/teams/beta/mcp", &[], &call("deploy")).await.2;
+        assert!(result["error"].is_object());
+
+        let health = get(&app, "/health", &[]).await.2;
+        assert_eq!(health["servers"]["/teams/alpha"]["tools"], 1);
+        assert_eq!(health["servers"]["/teams/beta"]["tools"], 1);
+    }
+
+    #[tokio::test]
+    async fn initialize_warnings_are_returned_and_logged() {
+        capture_warnings();
+        let mut rustmcp = RustMCP::new();
+        rustmcp.on_initialize(|params: &InitializeParams| {
+            let version = &params.client_info.version;
+            if version.starts_with("0.") {
+                vec![format!("client version {} is deprecated", version)]
             } else {
-                JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: request.id,
-                    result: None,
-                    error: Some(JsonRpcError {
-                        code: -32602,
-                        message: "Missing params".to_string(),
-                        data: None,
-                    }),
-                }
+                Vec::new()
             }
-        },
-        "prompts/get" => {
-            if let Some(params) = request.params {
-                if let Ok(get_params) = serde_json::from_value::<serde_json::Map<String, Value>>(params) {
-                    let name = get_params.get("name").and_then(|v| v.as_str()).unwrap_or("");
-                    let arguments = get_params.get("arguments").cloned();
-                    
-                    // 转换arguments为HashMap
-                    let arguments_map = if let Some(args) = arguments {
-                        serde_json::from_value::<std::collections::HashMap<String, Value>>(args).ok()
-                    } else {
-                        None
-                    };
-                    
-                    match rustmcp.mcp_get_prompt(name, arguments_map) {
-                        Ok(messages) => JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            id: request.id,
-                            result: Some(serde_json::json!({
-                                "messages": messages
-                            })),
-                            error: None,
-                        },
-                        Err(e) => JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            id: request.id,
-                            result: None,
-                            error: Some(JsonRpcError {
-                                code: -32000,
-                                message: e,
-                                data: None,
-                            }),
-                        },
-                    }
-                } else {
-                    JsonRpcResponse {
-                        jsonrpc: "2.0".to_string(),
-                        id: request.id,
-                        result: None,
-                        error: Some(JsonRpcError {
-                            code: -32602,
-                            message: "Invalid params".to_string(),
-                            data: None,
-                        }),
-                    }
-                }
-            } else {
-                JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: request.id,
-                    result: None,
-                    error: Some(JsonRpcError {
-                        code: -32602,
-                        message: "Missing params".to_string(),
-                        data: None,
-                    }),
-                }
+        });
+
+        let params = json!({
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": { "name": "legacy", "version": "0.9" }
+        });
+        let response = &stdio(&rustmcp, &[request(1, "initialize", params)]).await[0];
+
+        // 握手照常完成，服务器按自己的版本提供服务
+        assert_eq!(response["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(
+            response["result"]["_meta"]["warnings"],
+            json!(["requested 2025-03-26, serving 2024-11-05", "client version 0.9 is deprecated"])
+        );
+
+        // 两条警告都带着同一个会话ID记录
+        let deprecated = warnings(": client version 0.9 is deprecated");
+        assert_eq!(deprecated.len(), 1);
+        let session = deprecated[0].strip_prefix("Initialize warning for session ").unwrap();
+        let session = session.strip_suffix(": client version 0.9 is deprecated").unwrap();
+        let negotiated = format!("Initialize warning for session {}: requested 2025-03-26, serving 2024-11-05", session);
+        assert_eq!(warnings(&negotiated), vec![negotiated]);
+    }
+
+    #[tokio::test]
+    async fn initialize_result_is_cached_until_state_changes() {
+        let rustmcp = RustMCP::new();
+        let first = rustmcp.mcp_initialize_result();
+        assert!(Arc::ptr_eq(&first, &rustmcp.mcp_initialize_result()));
+
+        // 注册表变化后重新生成
+        rustmcp.add_prompt(FunctionPrompt::simple("greet", |_| Ok(vec![])));
+        let second = rustmcp.mcp_initialize_result();
+        assert!(!Arc::ptr_eq(&first, &second));
+        assert!(Arc::ptr_eq(&second, &rustmcp.mcp_initialize_result()));
+
+        // 每次initialize都反映当前状态
+        async fn initialize(rustmcp: &RustMCP) -> Value {
+            stdio(rustmcp, &[request(1, "initialize", json!({}))]).await[0]["result"].clone()
+        }
+        assert_eq!(initialize(&rustmcp).await["_meta"]["readOnly"], false);
+        rustmcp.set_read_only(true);
+        assert_eq!(initialize(&rustmcp).await["_meta"]["readOnly"], true);
+        assert_eq!(initialize(&rustmcp).await, serde_json::to_value(&*rustmcp.mcp_initialize_result()).unwrap());
+    }
+
+    #[tokio::test]
+    async fn representations_are_listed_and_selected_by_mime_type() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_resource(FunctionResource::from_function(
+            || Ok(json!("city,temp\nOslo,4\n")), "data://weather".into(), None, None, Some("text/csv".into()), None, None, None,
+        ));
+        rustmcp.add_resource(FunctionResource::from_function(
+            || Ok(json!([{"city": "Oslo", "temp": 4}])), "data://weather".into(), None, None, Some("application/json".into()), None, None, None,
+        ));
+
+        let read = |id: u64, mime_type: Option<&str>| {
+            let mut params = json!({ "uri": "data://weather" });
+            if let Some(mime_type) = mime_type {
+                params["mimeType"] = json!(mime_type);
             }
-        },
-        _ => {
-            JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id: request.id,
-                result: None,
-                error: Some(JsonRpcError {
-                    code: -32601,
-                    message: "Method not found".to_string(),
-                    data: None,
-                }),
+            request(id, "resources/read", params)
+        };
+        let responses = stdio(&rustmcp, &[
+            request(1, "initialize", json!({})),
+            request(2, "resources/list", json!({})),
+            read(3, Some("application/json")),
+            read(4, Some("text/csv")),
+            read(5, None),
+            read(6, Some("text/html")),
+        ])
+        .await;
+        let response = |id: i64| responses.iter().find(|r| r["id"] == id).unwrap().clone();
+
+        // 两个表示分别列出
+        let listed = response(2)["result"]["resources"].clone();
+        assert_eq!(listed[0]["mimeType"], "text/csv");
+        assert_eq!(listed[1]["mimeType"], "application/json");
+        assert!(listed.as_array().unwrap().iter().all(|r| r["uri"] == "data://weather"));
+
+        assert_eq!(response(3)["result"]["contents"][0]["text"], r#"[{"city":"Oslo","temp":4}]"#);
+        assert_eq!(response(4)["result"]["contents"][0]["text"], "city,temp\nOslo,4\n");
+        // 没有指定时使用最先注册的表示
+        assert_eq!(response(5)["result"]["contents"][0]["mimeType"], "text/csv");
+        assert_eq!(response(6)["error"]["code"], -32602);
+        assert_eq!(
+            response(6)["error"]["message"],
+            "Resource 'data://weather' has no 'text/html' representation (available: text/csv, application/json)"
+        );
+    }
+
+    #[tokio::test]
+    async fn unbound_tools_are_listed_and_fail_until_bound() {
+        let rustmcp = RustMCP::new();
+        let tool: FunctionTool = serde_json::from_value(json!({
+            "name": "echo",
+            "description": "Echo the input",
+            "inputSchema": { "type": "object", "properties": { "text": { "type": "string" } } }
+        }))
+        .unwrap();
+        assert!(!tool.is_bound());
+        rustmcp.add_tool(tool);
+        let initialize = request(0, "initialize", json!({}));
+        let call = request(2, "tools/call", json!({ "name": "echo", "arguments": { "text": "hi" } }));
+
+        let responses = stdio(&rustmcp, &[initialize.clone(), request(1, "tools/list", json!({})), call.clone()]).await;
+        assert_eq!(responses[1]["result"]["tools"][0]["_meta"]["bound"], false);
+        let error = &responses[2]["error"];
+        assert_eq!(error["code"], -32602);
+        assert_eq!(error["message"], "Tool 'echo' has no implementation bound");
+        assert_eq!(error["data"], json!({ "name": "echo", "binding": "unbound", "kind": "tool_not_bound" }));
+
+        assert!(rustmcp.bind_tool_function("echo", |_ctx, args| Ok(args.unwrap_or_default()["text"].clone())));
+        assert!(!rustmcp.bind_tool_function("missing", |_ctx, _args| Ok(Value::Null)));
+
+        let responses = stdio(&rustmcp, &[initialize, request(3, "tools/list", json!({})), call]).await;
+        assert!(responses[1]["result"]["tools"][0].get("_meta").is_none());
+        assert_eq!(responses[2]["result"]["content"][0]["text"], "\"hi\"");
+    }
+
+    #[tokio::test]
+    async fn tool_schemas_are_compiled_on_registration() {
+        let rustmcp = RustMCP::new();
+        let search = |schema| {
+            FunctionTool::builder()
+                .name("search")
+                .input_schema(schema)
+                .validate_input(true)
+                .handler(|_| Ok(json!("found")))
+                .build()
+        };
+
+        // 模式在注册时失败，而不是调用时
+        let broken = search(json!({ "type": "object", "properties": { "limit": { "type": "int" } } }));
+        assert_eq!(
+            rustmcp.try_add_tool(broken).unwrap_err(),
+            "Tool 'search' inputSchema.properties.limit.type has an unknown type \"int\""
+        );
+        assert!(rustmcp.mcp_list_tools().is_empty());
+
+        // 共享定义变化后按新的定义校验
+        rustmcp.register_schema("Limit", json!({ "type": "integer" })).unwrap();
+        let tool = search(json!({ "type": "object", "properties": { "limit": { "$ref": "#/definitions/Limit" } } }));
+        assert!(rustmcp.try_add_tool(tool).unwrap());
+        let args: HashMap<_, _> = [("limit".to_string(), json!("ten"))].into();
+        assert!(rustmcp.mcp_call_tool("search", Some(args.clone())).await.is_err());
+        rustmcp.register_schema("Limit", json!({ "type": "string" })).unwrap();
+        assert!(rustmcp.mcp_call_tool("search", Some(args)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn prompts_list_is_paged_over_http() {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_prompt_page_size(400));
+        for i in 0..2000 {
+            rustmcp.add_prompt(FunctionPrompt::from_function(|_| Ok(vec![PromptMessage::user("Hi")]), format!("prompt_{:04}", i), None, None, None, None));
+        }
+        let first = rustmcp.mcp_list_prompts_page(None, 3).unwrap();
+        assert_eq!(first.prompts[2]["name"], "prompt_0002");
+        let second = rustmcp.mcp_list_prompts_page(first.next_cursor.as_deref(), 3).unwrap();
+        assert_eq!(second.prompts[0]["name"], "prompt_0003");
+
+        let app = create_app(rustmcp);
+        let id = initialize(&app, json!({})).await;
+        let session = [("mcp-session-id", id.as_str())];
+        let mut pages = 0;
+        let mut seen = 0;
+        let mut params = json!({});
+        loop {
+            let result = post(&app, "/mcp", &session, &request(2 + pages, "prompts/list", params)).await.2["result"].take();
+            seen += result["prompts"].as_array().unwrap().len();
+            pages += 1;
+            match result["nextCursor"].as_str() {
+                Some(cursor) => params = json!({ "cursor": cursor }),
+                None => break,
             }
         }
-    };
-    
-    // 记录响应日志
-    println!("Sending JSON-RPC response: id={:?}", request_id_for_log);
-    if let Some(ref result) = response.result {
-        println!("Response body: {}", serde_json::to_string(result).unwrap_or_else(|_| "无法序列化响应".to_string()));
-    }
-    
-    // 返回响应
-    (StatusCode::OK, [("content-type", "application/json")], Json(response)).into_response()
-}
-
-// JSON-RPC数据结构定义
-#[derive(Serialize, Deserialize, Debug)]
-struct JsonRpcRequest {
-    jsonrpc: String,
-    method: String,
-    params: Option<Value>,
-    id: Option<Value>,
-}
-
-// JSON-RPC响应结构
-#[derive(Serialize, Deserialize, Debug)]
-struct JsonRpcResponse {
-    jsonrpc: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    id: Option<Value>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    result: Option<Value>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    error: Option<JsonRpcError>,
-}
-
-// JSON-RPC错误结构
-#[derive(Serialize, Deserialize, Debug)]
-struct JsonRpcError {
-    code: i32,
-    message: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    data: Option<Value>,
-}
\ No newline at end of file
+        assert_eq!((pages, seen), (5, 2000));
+    }
+
+    #[test]
+    fn stale_examples_are_reported_and_listed() {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_strict_validation(true).with_validate_examples(true));
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("resize")
+                .description("Resizes an image")
+                .args(ToolArgs::new().string("path", "Image path", true).integer("width", "Width in pixels", true))
+                .example(ToolExample::new("Thumbnail", json!({ "path": "cat.png", "width": 128 })))
+                // 参数从`size`改名为`width`后没有更新的示例
+                .example(ToolExample::new("Banner", json!({ "path": "banner.png", "size": 1200 })))
+                .handler(|_| Ok(json!("ok")))
+                .build(),
+        );
+        rustmcp.add_prompt(
+            FunctionPrompt::from_function(|_| Ok(vec![]), "review".to_string(), None, None, Some([("language".to_string(), "Source language".to_string())].into()), None)
+                .with_example(PromptExample::new("Rust review", [("language", "rust"), ("style", "strict")])),
+        );
+
+        let issues = rustmcp.validate_examples();
+        assert_eq!(issues.len(), 2);
+        assert!(issues.iter().all(|issue| issue.severity == Severity::Error));
+        assert_eq!(issues[0].item, "resize");
+        assert!(issues[0].message.starts_with("example 'Banner' does not match inputSchema"));
+        assert_eq!(issues[1].message, "example 'Rust review' uses undeclared argument 'style'");
+        // 严格校验时拒绝启动
+        assert_eq!(rustmcp.check_startup().unwrap_err(), issues);
+
+        // 示例出现在列表的_meta.examples中
+        let tools = serde_json::to_value(rustmcp.mcp_list_tools()).unwrap();
+        assert_eq!(tools[0]["_meta"]["examples"][0], json!({ "description": "Thumbnail", "arguments": { "path": "cat.png", "width": 128 } }));
+        let prompts = serde_json::to_value(rustmcp.mcp_list_prompts()).unwrap();
+        assert_eq!(prompts[0]["_meta"]["examples"][0]["arguments"]["language"], "rust");
+    }
+}
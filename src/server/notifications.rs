@@ -0,0 +1,417 @@
+//! 服务器发起的通知
+//!
+//! [`Notifier`]把通知广播给所有已连接的会话（目前为WebSocket连接）；
+//! [`SessionChannel`]是单个会话的回传通道，处理函数通过[`Context::notify`](crate::Context::notify)
+//! 只向发起请求的会话发送通知。
+//!
+//! 每个连接的响应和通知先写入有界的发送队列（[`Settings::outbound_queue_depth`](crate::Settings::outbound_queue_depth)），
+//! 再由连接自己的写出任务发送，广播和处理函数都不会等待读得慢的客户端：
+//!
+//! - 队列满时丢弃最早的进度和日志通知（`notifications/progress`、`notifications/message`），
+//!   客户端追上（队列清空）后收到一条`notifications/message`警告，说明丢弃了多少条；
+//! - 响应和其他通知不会被丢弃，队列中没有可丢弃的消息时断开连接；
+//! - 丢弃的总数可以通过[`RustMCP::dropped_notifications`](crate::RustMCP::dropped_notifications)和`/health`查看。
+//!
+//! ```rust
+//! use rustmcp::{RustMCP, Settings};
+//!
+//! let rustmcp = RustMCP::new().with_settings(Settings::new().with_outbound_queue_depth(64));
+//! println!("dropped: {}", rustmcp.dropped_notifications());
+//! ```
+
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{broadcast, Notify};
+
+use crate::protocol::JsonRpcNotification;
+use crate::server::trace::TraceTap;
+
+/// 广播通道容量
+const CHANNEL_CAPACITY: usize = 256;
+
+/// 通知广播器
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    sender: broadcast::Sender<JsonRpcNotification>,
+}
+
+impl Notifier {
+    /// 创建新的通知广播器
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    /// 订阅通知
+    pub fn subscribe(&self) -> broadcast::Receiver<JsonRpcNotification> {
+        self.sender.subscribe()
+    }
+
+    /// 广播通知，没有订阅者时忽略
+    pub fn notify(&self, method: &str, params: Option<Value>) {
+        let _ = self.sender.send(JsonRpcNotification::new(method, params));
+    }
+
+    /// 广播工具列表变更通知
+    pub fn tools_list_changed(&self) {
+        self.notify("notifications/tools/list_changed", None);
+    }
+
+    /// 广播资源列表变更通知
+    pub fn resources_list_changed(&self) {
+        self.notify("notifications/resources/list_changed", None);
+    }
+
+    /// 广播提示列表变更通知
+    pub fn prompts_list_changed(&self) {
+        self.notify("notifications/prompts/list_changed", None);
+    }
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 会话通知的投递结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// 已写入会话的发送队列
+    Delivered,
+    /// 传输没有回传通道（例如普通HTTP请求或直接调用），或会话已关闭，通知被丢弃
+    NotDelivered,
+}
+
+/// 会话的回传通道
+///
+/// 由传输层创建并放入[`RequestContext`](crate::RequestContext)。通知和响应写入同一个发送队列，
+/// 因此同一次调用中发出的通知按发送顺序到达，并且都在该调用的响应之前。
+#[derive(Clone)]
+pub struct SessionChannel {
+    send: SendFn,
+    /// 连接的发送队列，用于等待队列空出位置
+    outbox: Option<Arc<Outbox>>,
+}
+
+/// 回传通道的写入函数，收到通知和序列化后的文本
+type SendFn = Arc<dyn Fn(&JsonRpcNotification, String) -> Delivery + Send + Sync>;
+
+impl SessionChannel {
+    /// 创建回传通道，`send`收到序列化后的消息，写入失败（会话已关闭）时返回`false`
+    pub fn new<F>(send: F) -> Self
+    where
+        F: Fn(String) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            send: Arc::new(move |_, text| match send(text) {
+                true => Delivery::Delivered,
+                false => Delivery::NotDelivered,
+            }),
+            outbox: None,
+        }
+    }
+
+    /// 写入连接发送队列的回传通道，被丢弃的进度和日志通知返回[`Delivery::NotDelivered`]
+    pub(crate) fn outbox(outbox: Arc<Outbox>) -> Self {
+        let queue = outbox.clone();
+        Self {
+            send: Arc::new(move |notification, text| match queue.push(text, droppable(&notification.method)) {
+                Push::Queued => Delivery::Delivered,
+                Push::Dropped | Push::Closed => Delivery::NotDelivered,
+            }),
+            outbox: Some(outbox),
+        }
+    }
+
+    /// 等待发送队列空出一半，用于大量发送时的背压；不是写入发送队列的回传通道立即返回
+    pub(crate) async fn writable(&self) {
+        if let Some(outbox) = &self.outbox {
+            outbox.writable().await;
+        }
+    }
+
+    /// 向会话发送通知
+    pub fn send(&self, notification: &JsonRpcNotification) -> Delivery {
+        match serde_json::to_string(notification) {
+            Ok(text) => (self.send)(notification, text),
+            Err(_) => Delivery::NotDelivered,
+        }
+    }
+}
+
+impl std::fmt::Debug for SessionChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionChannel").finish_non_exhaustive()
+    }
+}
+
+/// 队列满时可以丢弃的通知：进度和日志
+pub(crate) fn droppable(method: &str) -> bool {
+    matches!(method, "notifications/progress" | "notifications/message")
+}
+
+/// 消息写入发送队列的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Push {
+    /// 已放入队列
+    Queued,
+    /// 队列已满，按策略丢弃
+    Dropped,
+    /// 连接已关闭或因队列溢出断开
+    Closed,
+}
+
+/// 单个连接的有界发送队列
+///
+/// 写入从不等待；连接的写出任务通过[`Outbox::next`]依次取出消息发送。
+#[derive(Debug)]
+pub(crate) struct Outbox {
+    queue: Mutex<VecDeque<Outbound>>,
+    depth: usize,
+    /// 队列中有新消息，或者连接结束
+    ready: Notify,
+    /// 连接因溢出断开
+    closing: Notify,
+    /// 写出任务取走了消息，或者连接断开
+    space: Notify,
+    closed: AtomicBool,
+    finished: AtomicBool,
+    /// 上次报告之后丢弃的消息数
+    dropped: AtomicU64,
+    /// 服务器范围的丢弃总数
+    total_dropped: Arc<AtomicU64>,
+    /// 放入队列的消息记入协议跟踪
+    trace: TraceTap,
+}
+
+#[derive(Debug)]
+struct Outbound {
+    text: String,
+    droppable: bool,
+}
+
+impl Outbox {
+    /// 创建容量为`depth`的队列，丢弃的消息同时计入`total_dropped`
+    pub fn new(depth: usize, total_dropped: Arc<AtomicU64>, trace: TraceTap) -> Arc<Self> {
+        Arc::new(Self {
+            queue: Mutex::new(VecDeque::new()),
+            depth: depth.max(1),
+            ready: Notify::new(),
+            closing: Notify::new(),
+            space: Notify::new(),
+            closed: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+            dropped: AtomicU64::new(0),
+            total_dropped,
+            trace,
+        })
+    }
+
+    /// 写入消息
+    ///
+    /// 队列满时先丢弃最早的可丢弃消息；没有可丢弃的消息时，新消息可丢弃则丢弃新消息，
+    /// 否则断开连接，不让响应丢失。
+    pub fn push(&self, text: String, droppable: bool) -> Push {
+        if self.closed.load(Ordering::Acquire) {
+            return Push::Closed;
+        }
+        let mut queue = self.queue.lock();
+        if queue.len() >= self.depth {
+            if let Some(index) = queue.iter().position(|message| message.droppable) {
+                queue.remove(index);
+                self.record_drops(1);
+            } else if droppable {
+                self.record_drops(1);
+                return Push::Dropped;
+            } else {
+                drop(queue);
+                log::warn!("Closing connection: outbound queue is full ({} messages) and the client is not reading", self.depth);
+                self.close();
+                return Push::Closed;
+            }
+        }
+        self.trace.outbound(&text);
+        queue.push_back(Outbound { text, droppable });
+        drop(queue);
+        self.ready.notify_one();
+        Push::Queued
+    }
+
+    /// 写入服务器广播的通知
+    pub fn push_notification(&self, notification: &JsonRpcNotification) -> Push {
+        match serde_json::to_string(notification) {
+            Ok(text) => self.push(text, droppable(&notification.method)),
+            Err(_) => Push::Dropped,
+        }
+    }
+
+    /// 记录在队列之外丢失的消息，例如广播接收端落后时跳过的通知
+    pub fn record_drops(&self, count: u64) {
+        self.dropped.fetch_add(count, Ordering::Relaxed);
+        self.total_dropped.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// 取出下一条要发送的消息
+    ///
+    /// 丢弃过消息且队列已清空时，先返回一条说明丢弃数量的警告。连接断开时返回`None`；
+    /// 调用[`Outbox::finish`]后发完剩余消息再返回`None`。
+    pub async fn next(&self) -> Option<String> {
+        loop {
+            if self.closed.load(Ordering::Acquire) {
+                return None;
+            }
+            let message = self.queue.lock().pop_front();
+            if let Some(message) = message {
+                self.space.notify_waiters();
+                return Some(message.text);
+            }
+            let dropped = self.dropped.swap(0, Ordering::Relaxed);
+            if dropped > 0 {
+                return serde_json::to_string(&dropped_warning(dropped)).ok();
+            }
+            if self.finished.load(Ordering::Acquire) {
+                return None;
+            }
+            self.ready.notified().await;
+        }
+    }
+
+    /// 不再写入新消息，写出任务发完队列中的消息后结束
+    pub fn finish(&self) {
+        self.finished.store(true, Ordering::Release);
+        self.ready.notify_one();
+    }
+
+    /// 断开连接，丢弃队列中的消息
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.ready.notify_one();
+        self.closing.notify_waiters();
+        self.space.notify_waiters();
+    }
+
+    /// 是否已断开
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// 等待队列中的消息少于容量的一半，连接断开时立即返回
+    pub async fn writable(&self) {
+        loop {
+            let notified = self.space.notified();
+            if self.is_closed() || self.queue.lock().len() < self.depth.div_ceil(2) {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// 等待连接因溢出断开，写出任务可能正阻塞在读得慢的客户端上，传输层据此放弃连接
+    pub async fn closed(&self) {
+        loop {
+            let notified = self.closing.notified();
+            if self.is_closed() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// 客户端追上后发送的丢弃警告
+fn dropped_warning(dropped: u64) -> JsonRpcNotification {
+    JsonRpcNotification::new(
+        "notifications/message",
+        Some(serde_json::json!({
+            "level": "warning",
+            "logger": "rustmcp",
+            "data": {
+                "message": format!("{} notifications were dropped because the connection fell behind", dropped),
+                "dropped": dropped,
+            },
+        })),
+    )
+}
+
+/// 把广播的通知转发到连接的发送队列，接收端落后时跳过的通知计为丢弃
+pub(crate) async fn forward(mut notifications: broadcast::Receiver<JsonRpcNotification>, outbox: Arc<Outbox>) {
+    loop {
+        match notifications.recv().await {
+            Ok(notification) => {
+                if outbox.push_notification(&notification) == Push::Closed {
+                    break;
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(skipped)) => outbox.record_drops(skipped),
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FunctionTool, RustMCP, Settings};
+    use crate::server::stdio::serve_lines;
+    use std::sync::Arc;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn slow_client_drops_progress_without_blocking_others() {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_outbound_queue_depth(4));
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("work")
+                .description("Reports progress while working")
+                .handler_with_context(|ctx, _| {
+                    for step in 0..20 {
+                        ctx.notify("notifications/progress", serde_json::json!({ "progress": step })).unwrap();
+                    }
+                    Ok(serde_json::json!("done"))
+                })
+                .build(),
+        );
+        let rustmcp = Arc::new(rustmcp);
+
+        // 读得很慢的客户端：输出缓冲区只有16字节，暂时不读
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"work"}}"#,
+        ]
+        .join("\n");
+        let (slow_writer, mut slow_reader) = tokio::io::duplex(16);
+        let slow = tokio::spawn(serve_lines(rustmcp.clone(), std::io::Cursor::new(input.into_bytes()), slow_writer));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(rustmcp.dropped_notifications() > 0);
+
+        // 其他客户端不受影响
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+        ]
+        .join("\n");
+        let (writer, mut reader) = tokio::io::duplex(64 * 1024);
+        serve_lines(rustmcp.clone(), input.as_bytes(), writer).await.unwrap();
+        let mut output = String::new();
+        reader.read_to_string(&mut output).await.unwrap();
+        assert!(output.contains(r#""name":"work""#));
+
+        // 慢客户端恢复读取后收到响应和丢弃警告
+        let mut output = String::new();
+        slow_reader.read_to_string(&mut output).await.unwrap();
+        slow.await.unwrap().unwrap();
+        assert!(output.contains(r#""id":2"#));
+        assert!(output.contains("notifications were dropped"));
+
+        // 响应不会被丢弃：队列被响应占满时断开连接
+        let pings: String = (0..10)
+            .map(|id| format!("{{\"jsonrpc\":\"2.0\",\"id\":{},\"method\":\"ping\"}}\n", id))
+            .collect();
+        let (stalled_writer, _stalled_reader) = tokio::io::duplex(16);
+        let error = serve_lines(rustmcp.clone(), pings.as_bytes(), stalled_writer).await.unwrap_err();
+        assert!(error.to_string().contains("overflowed"));
+    }
+}
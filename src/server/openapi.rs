@@ -0,0 +1,622 @@
+//! OpenAPI导入
+//!
+//! 把OpenAPI 3.x文档（JSON或YAML）中的每个操作转换为一个工具：
+//! - 工具名称取`operationId`，没有时由方法和路径生成，再按[`sanitize_tool_name`]规范化；
+//!   规范化后与文档中其他操作冲突时附加原始标识符的哈希（参见[`unique_tool_name`]），原始标识符记录在
+//!   [`FunctionTool::source_name`]中，调用时也可以使用；
+//! - 描述取`summary`和`description`；
+//! - 路径、查询和请求头参数成为同名参数，JSON请求体成为`body`参数。路径参数按路径段编码，
+//!   空值和只由`.`组成的值（`.`、`..`）会被拒绝。
+//!
+//! 生成的工具函数向`base_url`发起HTTP请求，2xx时返回响应体（能解析为JSON时返回JSON），
+//! 否则返回包含状态码和响应体的错误。请求通过[出站HTTP客户端](crate::server::http_client)发送，
+//! [`RustMCP::import_openapi`](crate::RustMCP::import_openapi)导入的工具使用实例共用的客户端。
+
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::server::api_doc::encode_path_segment;
+use crate::server::http_client::HttpClient;
+use crate::server::tools::{unique_tool_name, FunctionTool};
+
+/// 支持的HTTP方法
+const METHODS: &[&str] = &["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// 解析`$ref`的最大深度，避免循环引用
+const MAX_REF_DEPTH: usize = 16;
+
+/// 选项和客户端都没有设置超时时的请求超时
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 认证请求头生成函数，每次请求时调用，返回请求头名称和值
+pub type AuthHeaderInjector = Arc<dyn Fn() -> Option<(String, String)> + Send + Sync>;
+
+/// OpenAPI导入选项
+#[derive(Clone)]
+pub struct OpenApiOptions {
+    allowed_operations: Option<HashSet<String>>,
+    auth_header: Option<AuthHeaderInjector>,
+    timeout: Option<Duration>,
+    client: Option<HttpClient>,
+}
+
+impl OpenApiOptions {
+    /// 创建默认选项：导入所有操作，不添加认证请求头
+    pub fn new() -> Self {
+        Self {
+            allowed_operations: None,
+            auth_header: None,
+            timeout: None,
+            client: None,
+        }
+    }
+
+    /// 只导入列出的操作（按`operationId`或规范化后的工具名称匹配）
+    pub fn allow_operations<I, S>(mut self, operations: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_operations = Some(operations.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// 设置认证请求头生成函数
+    pub fn auth_header<F>(mut self, injector: F) -> Self
+    where
+        F: Fn() -> Option<(String, String)> + Send + Sync + 'static,
+    {
+        self.auth_header = Some(Arc::new(injector));
+        self
+    }
+
+    /// 使用固定的Bearer令牌
+    pub fn bearer_token(self, token: impl Into<String>) -> Self {
+        let value = format!("Bearer {}", token.into());
+        self.auth_header(move || Some(("Authorization".to_string(), value.clone())))
+    }
+
+    /// 设置请求超时，覆盖客户端配置的请求超时；都没有设置时为30秒
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// 使用指定的出站HTTP客户端，不设置时`RustMCP::import_openapi`使用实例共用的客户端
+    pub fn http_client(mut self, client: HttpClient) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// 是否指定了客户端
+    pub(crate) fn has_client(&self) -> bool {
+        self.client.is_some()
+    }
+
+    fn is_allowed(&self, raw: &str, name: &str) -> bool {
+        self.allowed_operations.as_ref().is_none_or(|allowed| allowed.contains(raw) || allowed.contains(name))
+    }
+}
+
+impl Default for OpenApiOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for OpenApiOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenApiOptions")
+            .field("allowed_operations", &self.allowed_operations)
+            .field("auth_header", &self.auth_header.is_some())
+            .field("timeout", &self.timeout)
+            .field("client", &self.client.is_some())
+            .finish()
+    }
+}
+
+/// OpenAPI导入错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpenApiError {
+    /// 文档既不是合法的JSON也不是合法的YAML
+    Parse(String),
+    /// 文档不是OpenAPI 3.x
+    UnsupportedVersion(String),
+    /// 文档结构不正确
+    InvalidSpec(String),
+}
+
+impl std::fmt::Display for OpenApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OpenApiError::Parse(message) => write!(f, "Failed to parse OpenAPI document: {}", message),
+            OpenApiError::UnsupportedVersion(version) => write!(f, "Unsupported OpenAPI version: {}", version),
+            OpenApiError::InvalidSpec(message) => write!(f, "Invalid OpenAPI document: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for OpenApiError {}
+
+impl From<OpenApiError> for String {
+    fn from(e: OpenApiError) -> Self {
+        e.to_string()
+    }
+}
+
+/// 参数位置
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ParamLocation {
+    Path,
+    Query,
+    Header,
+}
+
+/// 一个操作的请求描述
+#[derive(Debug, Clone)]
+struct Operation {
+    method: String,
+    path: String,
+    params: Vec<(String, ParamLocation)>,
+    has_body: bool,
+}
+
+/// 解析OpenAPI文档并生成工具
+///
+/// ```rust
+/// use rustmcp::server::openapi::{tools_from_spec, OpenApiOptions};
+/// use serde_json::json;
+///
+/// let spec = json!({
+///     "openapi": "3.0.0",
+///     "paths": { "/pets": { "get": { "operationId": "listPets" } } }
+/// })
+/// .to_string();
+/// let tools = tools_from_spec(&spec, "http://localhost", &OpenApiOptions::new()).unwrap();
+/// assert_eq!(tools[0].name, "list_pets");
+/// assert_eq!(tools[0].source_name(), Some("listPets"));
+/// ```
+pub fn tools_from_spec(spec: &str, base_url: &str, options: &OpenApiOptions) -> Result<Vec<FunctionTool>, OpenApiError> {
+    let document: Value = match serde_json::from_str(spec) {
+        Ok(document) => document,
+        Err(_) => serde_yaml::from_str(spec).map_err(|e| OpenApiError::Parse(e.to_string()))?,
+    };
+
+    let version = document.get("openapi").and_then(|v| v.as_str()).unwrap_or_default();
+    if !version.starts_with("3.") {
+        return Err(OpenApiError::UnsupportedVersion(version.to_string()));
+    }
+    let paths = document
+        .get("paths")
+        .and_then(|p| p.as_object())
+        .ok_or_else(|| OpenApiError::InvalidSpec("missing 'paths' object".to_string()))?;
+
+    let client = options.client.clone().unwrap_or_default();
+    let timeout = options.timeout.or(client.request_timeout()).unwrap_or(DEFAULT_TIMEOUT);
+    let base_url = base_url.trim_end_matches('/').to_string();
+    let mut tools = Vec::new();
+    let mut raw_names = HashSet::new();
+    let mut names = HashSet::new();
+
+    for (path, item) in paths {
+        let item = resolve(&document, item);
+        let shared_params = item.get("parameters").and_then(|p| p.as_array()).cloned().unwrap_or_default();
+        for method in METHODS {
+            let operation = match item.get(*method) {
+                Some(operation) => resolve(&document, operation),
+                None => continue,
+            };
+            let raw = operation
+                .get("operationId")
+                .and_then(|id| id.as_str())
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| default_operation_name(method, path));
+            if !raw_names.insert(raw.clone()) {
+                return Err(OpenApiError::InvalidSpec(format!("duplicate operation '{}'", raw)));
+            }
+            let name = unique_tool_name(&raw, |name| names.contains(name));
+            if !options.is_allowed(&raw, &name) {
+                continue;
+            }
+            names.insert(name.clone());
+
+            let (input_schema, request) = build_operation(&document, method, path, &shared_params, &operation)?;
+            let description = [operation.get("summary"), operation.get("description")]
+                .iter()
+                .filter_map(|v| v.and_then(|v| v.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n\n");
+
+            let client = client.clone();
+            let base_url = base_url.clone();
+            let auth_header = options.auth_header.clone();
+            let tool = FunctionTool::builder()
+                .name(name)
+                .source_name(raw)
+                .description(description)
+                .input_schema(input_schema)
+                .validate_input(true)
+                .handler(move |args: Option<HashMap<String, Value>>| {
+                    execute(&client, timeout, &base_url, &request, auth_header.as_ref(), args.unwrap_or_default())
+                })
+                .build();
+            tools.push(tool);
+        }
+    }
+
+    Ok(tools)
+}
+
+/// 生成操作的输入模式和请求描述
+fn build_operation(
+    document: &Value,
+    method: &str,
+    path: &str,
+    shared_params: &[Value],
+    operation: &Value,
+) -> Result<(Value, Operation), OpenApiError> {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    let mut params: Vec<(String, ParamLocation)> = Vec::new();
+
+    // 操作级参数覆盖路径级的同名参数
+    let operation_params = operation.get("parameters").and_then(|p| p.as_array()).cloned().unwrap_or_default();
+    for param in shared_params.iter().chain(operation_params.iter()) {
+        let param = resolve(document, param);
+        let name = param
+            .get("name")
+            .and_then(|n| n.as_str())
+            .ok_or_else(|| OpenApiError::InvalidSpec(format!("parameter without name in {} {}", method, path)))?;
+        let location = match param.get("in").and_then(|l| l.as_str()) {
+            Some("path") => ParamLocation::Path,
+            Some("query") => ParamLocation::Query,
+            Some("header") => ParamLocation::Header,
+            _ => continue,
+        };
+        let mut schema = param.get("schema").map(|s| resolve_schema(document, s, 0)).unwrap_or_else(|| serde_json::json!({}));
+        if let (Some(description), Some(object)) = (param.get("description"), schema.as_object_mut()) {
+            object.insert("description".to_string(), description.clone());
+        }
+        properties.insert(name.to_string(), schema);
+        params.retain(|(existing, _)| existing != name);
+        params.push((name.to_string(), location));
+        required.retain(|r: &String| r != name);
+        if location == ParamLocation::Path || param.get("required").and_then(|r| r.as_bool()).unwrap_or(false) {
+            required.push(name.to_string());
+        }
+    }
+
+    let body = operation.get("requestBody").map(|b| resolve(document, b));
+    let body_schema = body
+        .as_ref()
+        .and_then(|b| b.get("content"))
+        .and_then(|c| c.get("application/json"))
+        .map(|media| media.get("schema").map(|s| resolve_schema(document, s, 0)).unwrap_or_else(|| serde_json::json!({})));
+    if let Some(mut schema) = body_schema.clone() {
+        if let (Some(description), Some(object)) = (body.as_ref().and_then(|b| b.get("description")), schema.as_object_mut()) {
+            object.insert("description".to_string(), description.clone());
+        }
+        properties.insert("body".to_string(), schema);
+        if body.as_ref().and_then(|b| b.get("required")).and_then(|r| r.as_bool()).unwrap_or(false) {
+            required.push("body".to_string());
+        }
+    }
+
+    let mut input_schema = serde_json::json!({
+        "type": "object",
+        "properties": properties,
+    });
+    if !required.is_empty() {
+        input_schema["required"] = serde_json::json!(required);
+    }
+
+    Ok((
+        input_schema,
+        Operation {
+            method: method.to_uppercase(),
+            path: path.to_string(),
+            params,
+            has_body: body_schema.is_some(),
+        },
+    ))
+}
+
+/// 执行HTTP请求
+fn execute(
+    client: &HttpClient,
+    timeout: Duration,
+    base_url: &str,
+    operation: &Operation,
+    auth_header: Option<&AuthHeaderInjector>,
+    args: HashMap<String, Value>,
+) -> Result<Value, String> {
+    let mut path = operation.path.clone();
+    for (name, location) in &operation.params {
+        if *location == ParamLocation::Path {
+            let value = args.get(name).ok_or_else(|| format!("Missing path parameter '{}'", name))?;
+            let segment = parameter_text(value);
+            // `.`和`..`即使编码为`%2E`也会被URL解析按相对路径处理，直接拒绝
+            if segment.is_empty() || segment.bytes().all(|b| b == b'.') {
+                return Err(format!("Invalid path parameter '{}': {:?} is not a valid path segment", name, segment));
+            }
+            path = path.replace(&format!("{{{}}}", name), &encode_path_segment(&segment));
+        }
+    }
+
+    let mut request = client.request(&operation.method, &format!("{}{}", base_url, path), Some(timeout));
+    for (name, location) in &operation.params {
+        let value = match args.get(name) {
+            Some(Value::Null) | None => continue,
+            Some(value) => value,
+        };
+        match location {
+            ParamLocation::Query => match value {
+                Value::Array(items) => {
+                    for item in items {
+                        request = request.query(name, &parameter_text(item));
+                    }
+                }
+                value => request = request.query(name, &parameter_text(value)),
+            },
+            ParamLocation::Header => request = request.set(name, &parameter_text(value)),
+            ParamLocation::Path => {}
+        }
+    }
+    if let Some((name, value)) = auth_header.and_then(|injector| injector()) {
+        request = request.set(&name, &value);
+    }
+
+    let result = match args.get("body").filter(|_| operation.has_body) {
+        Some(body) => request.send_json(body.clone()),
+        None => request.call(),
+    };
+    match result {
+        Ok(response) => {
+            let text = response.into_string().map_err(|e| format!("Failed to read response: {}", e))?;
+            Ok(serde_json::from_str(&text).unwrap_or(Value::String(text)))
+        }
+        Err(ureq::Error::Status(status, response)) => {
+            let body = response.into_string().unwrap_or_default();
+            Err(format!("HTTP {} {} failed with status {}: {}", operation.method, path, status, body))
+        }
+        Err(e) => Err(format!("HTTP {} {} failed: {}", operation.method, path, e)),
+    }
+}
+
+/// 解析本地`$ref`引用（`#/components/...`）
+fn resolve(document: &Value, value: &Value) -> Value {
+    let mut current = value.clone();
+    for _ in 0..MAX_REF_DEPTH {
+        let reference = match current.get("$ref").and_then(|r| r.as_str()) {
+            Some(reference) => reference.to_string(),
+            None => break,
+        };
+        match reference.strip_prefix('#').and_then(|pointer| document.pointer(pointer)) {
+            Some(target) => current = target.clone(),
+            None => break,
+        }
+    }
+    current
+}
+
+/// 递归解析模式中的所有本地引用，超过最大深度的引用保持原样
+fn resolve_schema(document: &Value, schema: &Value, depth: usize) -> Value {
+    if depth >= MAX_REF_DEPTH {
+        return schema.clone();
+    }
+    match resolve(document, schema) {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, resolve_schema(document, &value, depth + 1)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(|item| resolve_schema(document, item, depth + 1)).collect()),
+        other => other,
+    }
+}
+
+/// 没有`operationId`时由方法和路径生成工具名称
+fn default_operation_name(method: &str, path: &str) -> String {
+    let mut name = method.to_string();
+    for part in path.split('/').filter(|p| !p.is_empty()) {
+        name.push('_');
+        name.extend(part.chars().filter(|c| c.is_alphanumeric() || *c == '_'));
+    }
+    name
+}
+
+/// 参数值转换为字符串
+fn parameter_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parking_lot::Mutex;
+    use serde_json::json;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    use super::*;
+    use crate::server::error::McpError;
+    use crate::server::RustMCP;
+
+    const SPEC: &str = r#"
+openapi: 3.0.0
+info: { title: Pets, version: "1" }
+paths:
+  /pets/{petId}:
+    get:
+      operationId: getPet
+      summary: Get a pet
+      parameters:
+        - { name: petId, in: path, required: true, schema: { type: string } }
+        - { name: fields, in: query, schema: { type: array, items: { type: string } } }
+    delete:
+      operationId: deletePet
+      parameters:
+        - { name: petId, in: path, required: true, schema: { type: string } }
+  /pets:
+    post:
+      operationId: createPet
+      description: Create a pet
+      requestBody:
+        content:
+          application/json:
+            schema: { type: object, properties: { name: { type: string } } }
+  /fail:
+    get:
+      operationId: fail
+"#;
+
+    /// 收到的请求：方法、路径（含查询）、Authorization请求头和请求体
+    type Seen = Arc<Mutex<Vec<(String, String, Option<String>, String)>>>;
+
+    /// 在后台线程运行的模拟服务：`/fail`返回500，其他路径返回请求的方法和路径
+    fn mock_server() -> (String, Seen) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let seen = Seen::default();
+        let log = seen.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut parts = line.split_whitespace();
+                let (method, target) = (parts.next().unwrap().to_string(), parts.next().unwrap().to_string());
+                let (mut length, mut authorization) = (0, None);
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    let header = header.trim_end();
+                    if header.is_empty() {
+                        break;
+                    }
+                    let (name, value) = header.split_once(':').unwrap();
+                    match name.to_ascii_lowercase().as_str() {
+                        "content-length" => length = value.trim().parse().unwrap(),
+                        "authorization" => authorization = Some(value.trim().to_string()),
+                        _ => {}
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                log.lock().push((method.clone(), target.clone(), authorization, String::from_utf8(body).unwrap()));
+
+                let (status, body) = match target.as_str() {
+                    "/fail" => ("500 Internal Server Error", "boom".to_string()),
+                    _ => ("200 OK", json!({ "method": method, "path": target }).to_string()),
+                };
+                let response = format!("HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}", status, body.len(), body);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (base_url, seen)
+    }
+
+    fn arguments(value: Value) -> Option<HashMap<String, Value>> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn imported(base_url: &str) -> RustMCP {
+        let rustmcp = RustMCP::new();
+        let options = OpenApiOptions::new().allow_operations(["getPet", "createPet", "fail"]).bearer_token("secret");
+        let mut names = rustmcp.import_openapi(SPEC, base_url, options).unwrap();
+        names.sort();
+        assert_eq!(names, ["create_pet", "fail", "get_pet"]);
+        rustmcp
+    }
+
+    #[test]
+    fn tools_are_built_from_operations() {
+        let tools = tools_from_spec(SPEC, "http://localhost", &OpenApiOptions::new()).unwrap();
+        let tool = |name: &str| tools.iter().find(|tool| tool.name == name).unwrap();
+        assert_eq!(tools.len(), 4);
+        assert_eq!(tool("get_pet").description, "Get a pet");
+        let schema = tool("get_pet").input_schema.clone().unwrap();
+        assert_eq!(schema["required"], json!(["petId"]));
+        assert_eq!(schema["properties"]["fields"]["type"], "array");
+        assert!(tool("create_pet").input_schema.as_ref().unwrap()["properties"]["body"].is_object());
+    }
+
+    #[tokio::test]
+    async fn generated_tools_call_the_mock_server() {
+        let (base_url, seen) = mock_server();
+        let rustmcp = imported(&base_url);
+
+        let result = rustmcp
+            .mcp_call_tool("get_pet", arguments(json!({ "petId": "a b/c", "fields": ["name", "age"] })))
+            .await
+            .unwrap();
+        assert_eq!(result, json!({ "method": "GET", "path": "/pets/a%20b%2Fc?fields=name&fields=age" }));
+
+        // 原始的operationId也可以调用
+        let result = rustmcp.mcp_call_tool("createPet", arguments(json!({ "body": { "name": "Rex" } }))).await.unwrap();
+        assert_eq!(result["method"], "POST");
+
+        let seen = seen.lock();
+        assert!(seen.iter().all(|(_, _, authorization, _)| authorization.as_deref() == Some("Bearer secret")));
+        assert_eq!(serde_json::from_str::<Value>(&seen[1].3).unwrap(), json!({ "name": "Rex" }));
+    }
+
+    #[tokio::test]
+    async fn non_success_status_is_an_error_with_status_and_body() {
+        let (base_url, _) = mock_server();
+        let rustmcp = imported(&base_url);
+        let error = rustmcp.mcp_call_tool("fail", None).await.unwrap_err().to_string();
+        assert!(error.contains("500") && error.contains("boom"), "{}", error);
+        // 不在允许列表中的操作没有导入
+        assert!(matches!(rustmcp.mcp_call_tool("deletePet", None).await, Err(McpError::ToolNotFound { .. })));
+    }
+
+    #[tokio::test]
+    async fn dot_segments_are_rejected_before_sending() {
+        let (base_url, seen) = mock_server();
+        let rustmcp = imported(&base_url);
+        for pet_id in ["..", ".", "", "..."] {
+            let error = rustmcp.mcp_call_tool("getPet", arguments(json!({ "petId": pet_id }))).await.unwrap_err();
+            assert!(error.to_string().contains("Invalid path parameter 'petId'"), "{}", error);
+        }
+        // 含有其他字符的值正常编码
+        let result = rustmcp.mcp_call_tool("getPet", arguments(json!({ "petId": "../admin" }))).await.unwrap();
+        assert_eq!(result["path"], "/pets/..%2Fadmin");
+        assert_eq!(seen.lock().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn operation_ids_are_normalized_and_stay_callable() {
+        let spec = json!({
+            "openapi": "3.0.0",
+            "paths": {
+                "/pets": { "get": { "operationId": "listPets" } },
+                "/pets/{id}": { "get": { "operationId": "list pets" }, "delete": {} },
+                "/orders": { "get": { "operationId": "Bestellübersicht" } }
+            }
+        })
+        .to_string();
+        let tools = tools_from_spec(&spec, "http://localhost", &OpenApiOptions::new()).unwrap();
+        let mut names: Vec<&str> = tools.iter().map(|tool| tool.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names[..3], ["bestellubersicht", "delete_pets_id", "list_pets"]);
+        // 规范化后冲突的操作附加哈希
+        assert!(names[3].starts_with("list_pets_"));
+        assert!(tools.iter().any(|tool| tool.source_name() == Some("list pets") && tool.name == names[3]));
+
+        // 导入后也可以用operationId调用（这里没有服务在监听，工具本身执行失败）
+        let rustmcp = RustMCP::new();
+        let options = OpenApiOptions::new().allow_operations(["listPets"]);
+        let imported = rustmcp.import_openapi(&spec, "http://127.0.0.1:9", options).unwrap();
+        assert_eq!(imported, ["list_pets"]);
+        match rustmcp.mcp_call_tool("listPets", None).await {
+            Err(McpError::ToolExecution { name, .. }) => assert_eq!(name, "list_pets"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! 连接断开后保留未送达的响应
+//!
+//! WebSocket在一个耗时的工具调用执行期间断开时，结果原本会被丢弃，客户端只能重新执行
+//! （可能有副作用的）工具。设置了[`Settings::with_pending_results`](crate::Settings::with_pending_results)后：
+//! - WebSocket在处理请求期间继续读取，客户端断开时，请求完成后的响应按会话保留`ttl`；
+//! - 客户端在升级请求中带上`Mcp-Session-Id`请求头（或`sessionId`查询参数）以同一会话重新连接，
+//!   服务器在升级响应的`Mcp-Session-Id`中返回会话ID，第一次连接时由服务器生成；
+//! - 重新连接后自动重发保留的响应，它们是原样的JSON-RPC响应，带有原来的请求`id`；
+//!   请求完成时客户端已经重新连接的，响应直接发给新的连接；
+//! - 关闭了自动重发（[`Settings::with_pending_redelivery`](crate::Settings::with_pending_redelivery)）时，
+//!   客户端调用[`x-rustmcp/session/pending`](METHOD)取回，结果为`{"results": {"<请求id>": <响应>}}`，
+//!   取回后不再保留；
+//! - 每个会话最多保留[`pending_results_max`](crate::Settings::pending_results_max)个响应、
+//!   [`pending_results_max_bytes`](crate::Settings::pending_results_max_bytes)字节，超出时丢弃最早的响应，
+//!   单个超过字节上限的响应不保留；过期的响应在下一次保留或取回时清除。
+//!
+//! 会话ID是取回结果的唯一凭据，应像HTTP会话ID一样保密。
+//!
+//! ```rust
+//! use rustmcp::{RustMCP, Settings};
+//! use std::time::Duration;
+//!
+//! // 保留5分钟，由客户端调用x-rustmcp/session/pending取回
+//! let settings = Settings::new().with_pending_results(Duration::from_secs(300)).with_pending_redelivery(false);
+//! let rustmcp = RustMCP::new().with_settings(settings);
+//! assert_eq!(rustmcp.pending_results("session"), 0);
+//! ```
+
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::protocol::RequestId;
+use crate::server::error::McpError;
+use crate::server::{Context, RustMCP};
+
+/// 取回保留的响应的方法名
+pub const METHOD: &str = "x-rustmcp/session/pending";
+
+/// 保留的数量和时间限制
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PendingLimits {
+    pub(crate) ttl: Duration,
+    pub(crate) max_results: usize,
+    pub(crate) max_bytes: usize,
+}
+
+/// 一个保留的响应
+#[derive(Debug)]
+struct Parked {
+    id: RequestId,
+    /// 序列化后的JSON-RPC响应
+    text: String,
+    parked_at: Instant,
+}
+
+/// 按会话保留的响应
+#[derive(Debug, Default)]
+pub(crate) struct PendingResults {
+    sessions: Mutex<HashMap<String, VecDeque<Parked>>>,
+}
+
+impl PendingResults {
+    /// 保留一个响应，超出数量或字节上限时丢弃该会话最早的响应
+    pub(crate) fn park(&self, session_id: &str, id: RequestId, text: String, limits: PendingLimits) {
+        if text.len() > limits.max_bytes || limits.max_results == 0 {
+            log::warn!("Dropping undelivered response {} for session {}: exceeds the pending result limits", id, session_id);
+            return;
+        }
+        let mut sessions = self.sessions.lock();
+        expire(&mut sessions, limits.ttl);
+        let parked = sessions.entry(session_id.to_string()).or_default();
+        parked.push_back(Parked {
+            id,
+            text,
+            parked_at: Instant::now(),
+        });
+        let mut bytes: usize = parked.iter().map(|p| p.text.len()).sum();
+        while parked.len() > limits.max_results || bytes > limits.max_bytes {
+            let Some(oldest) = parked.pop_front() else { break };
+            bytes -= oldest.text.len();
+            log::warn!("Dropping undelivered response {} for session {}: too many pending results", oldest.id, session_id);
+        }
+    }
+
+    /// 取出会话保留的所有响应，按保留的顺序排列
+    pub(crate) fn take(&self, session_id: &str, ttl: Duration) -> Vec<(RequestId, String)> {
+        let mut sessions = self.sessions.lock();
+        expire(&mut sessions, ttl);
+        sessions
+            .remove(session_id)
+            .map(|parked| parked.into_iter().map(|p| (p.id, p.text)).collect())
+            .unwrap_or_default()
+    }
+
+    /// 会话保留的响应数
+    pub(crate) fn count(&self, session_id: &str, ttl: Duration) -> usize {
+        self.sessions
+            .lock()
+            .get(session_id)
+            .map_or(0, |parked| parked.iter().filter(|p| p.parked_at.elapsed() < ttl).count())
+    }
+}
+
+/// 清除所有会话中过期的响应
+fn expire(sessions: &mut HashMap<String, VecDeque<Parked>>, ttl: Duration) {
+    sessions.retain(|_, parked| {
+        parked.retain(|p| p.parked_at.elapsed() < ttl);
+        !parked.is_empty()
+    });
+}
+
+/// 执行`x-rustmcp/session/pending`，没有会话或没有保留的响应时返回空表
+pub(crate) async fn take_pending(rustmcp: RustMCP, _params: Option<Value>, ctx: Context) -> Result<Value, McpError> {
+    let results: serde_json::Map<String, Value> = match &ctx.request().session_id {
+        Some(session_id) => rustmcp
+            .take_pending(session_id)
+            .into_iter()
+            .map(|(id, text)| {
+                let key = match id {
+                    RequestId::String(id) => id,
+                    id => id.to_string(),
+                };
+                (key, serde_json::from_str(&text).unwrap_or(Value::Null))
+            })
+            .collect(),
+        None => serde_json::Map::new(),
+    };
+    Ok(json!({ "results": results }))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{SinkExt, StreamExt};
+    use serde_json::{json, Value};
+    use std::time::Duration;
+    use tokio::net::TcpStream;
+    use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+    use crate::server::test_support::{request, spawn_server};
+    use crate::{FunctionTool, RustMCP, Settings};
+
+    type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+    async fn start(settings: Settings) -> (RustMCP, String) {
+        let rustmcp = RustMCP::new().with_settings(settings);
+        rustmcp.add_tool(FunctionTool::simple("migrate", "Runs a slow migration", |_| {
+            std::thread::sleep(Duration::from_millis(300));
+            Ok(json!("migrated"))
+        }));
+        let url = format!("ws://{}/mcp/ws", spawn_server(&rustmcp).await);
+        (rustmcp, url)
+    }
+
+    /// 连接，返回客户端和升级响应中的会话ID
+    async fn connect(url: &str, session: Option<&str>) -> (Client, String) {
+        let mut upgrade = url.into_client_request().unwrap();
+        if let Some(session) = session {
+            upgrade.headers_mut().insert("mcp-session-id", session.parse().unwrap());
+        }
+        let (client, response) = tokio_tungstenite::connect_async(upgrade).await.unwrap();
+        (client, response.headers()["mcp-session-id"].to_str().unwrap().to_string())
+    }
+
+    async fn send(client: &mut Client, message: Value) {
+        client.send(Message::text(message.to_string())).await.unwrap();
+    }
+
+    async fn receive(client: &mut Client) -> Value {
+        serde_json::from_str(client.next().await.unwrap().unwrap().to_text().unwrap()).unwrap()
+    }
+
+    /// 开始一个调用，然后不发关闭帧直接断开
+    async fn call_and_drop(url: &str) -> String {
+        let (mut client, session) = connect(url, None).await;
+        send(&mut client, request(1, "initialize", json!({}))).await;
+        receive(&mut client).await;
+        send(&mut client, request(7, "tools/call", json!({ "name": "migrate" }))).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        drop(client);
+        session
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn kept_responses_are_resent_on_reconnect() {
+        let (rustmcp, url) = start(Settings::new().with_pending_results(Duration::from_secs(30))).await;
+        let session = call_and_drop(&url).await;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(rustmcp.pending_results(&session), 1);
+
+        let (mut client, resumed) = connect(&url, Some(&session)).await;
+        assert_eq!(resumed, session);
+        let response = receive(&mut client).await;
+        assert_eq!(response["id"], 7);
+        assert!(response["result"]["content"][0]["text"].as_str().unwrap().contains("migrated"));
+        assert_eq!(rustmcp.pending_results(&session), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn kept_responses_can_be_fetched_explicitly() {
+        let settings = Settings::new().with_pending_results(Duration::from_secs(30)).with_pending_redelivery(false);
+        let (_rustmcp, url) = start(settings).await;
+        let session = call_and_drop(&url).await;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        let (mut client, _) = connect(&url, Some(&session)).await;
+        send(&mut client, request(1, "initialize", json!({}))).await;
+        assert_eq!(receive(&mut client).await["id"], 1);
+        send(&mut client, request(2, "x-rustmcp/session/pending", json!({}))).await;
+        let results = receive(&mut client).await["result"]["results"].take();
+        assert_eq!(results["7"]["id"], 7);
+        assert_eq!(results["7"]["result"]["isError"], false);
+        // 取回后不再保留
+        send(&mut client, request(3, "x-rustmcp/session/pending", json!({}))).await;
+        assert_eq!(receive(&mut client).await["result"]["results"], json!({}));
+    }
+}
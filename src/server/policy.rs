@@ -0,0 +1,162 @@
+//! JSON-RPC方法的允许/拒绝列表
+//!
+//! [`MethodPolicy`]可以关闭整个协议领域，例如在面向公网的实例上禁止`resources/read`而保留工具。
+//! 被拒绝的方法在所有传输上都按不存在处理：JSON-RPC返回`-32601`，对应的REST接口返回404。
+//! `initialize`的`capabilities`中不再声明列表方法或使用方法被拒绝的领域
+//! （`tools`需要`tools/list`和`tools/call`，`resources`需要`resources/list`和`resources/read`，
+//! `prompts`需要`prompts/list`和`prompts/get`），客户端不会去尝试。
+//!
+//! 模式可以是完整的方法名，`resources/*`这样的前缀通配符，或匹配所有方法的`*`。
+//! `initialize`、`notifications/initialized`和`ping`总是允许。
+//!
+//! 策略通过[`Settings::with_method_policy`](crate::Settings::with_method_policy)设置，运行时可以用
+//! [`RustMCP::set_method_policy`](crate::RustMCP::set_method_policy)或管理接口的`PUT /method-policy`更换。
+//!
+//! ```rust
+//! use rustmcp::{MethodPolicy, RustMCP, Settings};
+//!
+//! let rustmcp = RustMCP::new().with_settings(Settings::new().with_method_policy(MethodPolicy::deny(["resources/*"])));
+//! assert!(rustmcp.mcp_initialize_result().capabilities.resources.is_none());
+//! ```
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 不受策略限制的方法，否则客户端无法完成握手
+const ALWAYS_ALLOWED: &[&str] = &["initialize", "notifications/initialized", "ping"];
+
+/// JSON-RPC方法的允许或拒绝列表
+///
+/// 序列化为`{"allow": [...]}`或`{"deny": [...]}`。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MethodPolicy {
+    /// 只允许匹配的方法
+    Allow(Vec<String>),
+    /// 拒绝匹配的方法
+    Deny(Vec<String>),
+}
+
+impl MethodPolicy {
+    /// 只允许匹配这些模式的方法
+    pub fn allow<I, S>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        MethodPolicy::Allow(patterns.into_iter().map(Into::into).collect())
+    }
+
+    /// 拒绝匹配这些模式的方法
+    pub fn deny<I, S>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        MethodPolicy::Deny(patterns.into_iter().map(Into::into).collect())
+    }
+
+    /// 策略是否允许调用该方法
+    pub fn permits(&self, method: &str) -> bool {
+        if ALWAYS_ALLOWED.contains(&method) {
+            return true;
+        }
+        match self {
+            MethodPolicy::Allow(patterns) => patterns.iter().any(|pattern| matches(pattern, method)),
+            MethodPolicy::Deny(patterns) => !patterns.iter().any(|pattern| matches(pattern, method)),
+        }
+    }
+}
+
+/// 判断方法名是否匹配模式：`*`匹配所有方法，`prefix/*`匹配`prefix/`开头的方法
+fn matches(pattern: &str, method: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) if prefix.is_empty() || prefix.ends_with('/') => method.starts_with(prefix),
+        _ => pattern == method,
+    }
+}
+
+/// 当前生效的策略，更换时增加修订号，让缓存的initialize结果过期
+#[derive(Debug, Default)]
+pub(crate) struct MethodPolicyState {
+    policy: RwLock<Option<MethodPolicy>>,
+    revision: AtomicU64,
+}
+
+impl MethodPolicyState {
+    /// 当前的策略
+    pub(crate) fn get(&self) -> Option<MethodPolicy> {
+        self.policy.read().clone()
+    }
+
+    /// 更换策略
+    pub(crate) fn set(&self, policy: Option<MethodPolicy>) {
+        *self.policy.write() = policy;
+        self.revision.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 当前策略是否允许调用该方法，没有策略时允许所有方法
+    pub(crate) fn permits(&self, method: &str) -> bool {
+        self.policy.read().as_ref().is_none_or(|policy| policy.permits(method))
+    }
+
+    pub(crate) fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::server::test_support::{get, request, stdio};
+    use crate::{create_app, FunctionResource, FunctionTool, MethodPolicy, RustMCP, Settings};
+
+    #[test]
+    fn patterns_match_exact_names_prefixes_and_everything() {
+        let policy = MethodPolicy::allow(["tools/*", "prompts/get"]);
+        assert!(policy.permits("tools/call"));
+        assert!(policy.permits("prompts/get"));
+        assert!(!policy.permits("prompts/list"));
+        // 通配符只作用于完整的路径段
+        assert!(!MethodPolicy::allow(["tools*"]).permits("tools/call"));
+        assert!(!MethodPolicy::deny(["*"]).permits("resources/read"));
+        // 握手和ping总是允许
+        for method in ["initialize", "notifications/initialized", "ping"] {
+            assert!(MethodPolicy::deny(["*"]).permits(method));
+        }
+    }
+
+    #[tokio::test]
+    async fn denied_methods_are_hidden_and_rejected() {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_method_policy(MethodPolicy::deny(["resources/*"])));
+        rustmcp.add_tool(FunctionTool::simple("echo", "Echoes its arguments", |args| Ok(json!(args))));
+        rustmcp.add_resource(FunctionResource::simple("config://app", || Ok(json!("secret"))));
+
+        let responses = stdio(&rustmcp, &[
+            request(1, "initialize", json!({})),
+            request(2, "resources/read", json!({ "uri": "config://app" })),
+            request(3, "resources/list", json!({})),
+            request(4, "tools/call", json!({ "name": "echo", "arguments": { "text": "hi" } })),
+        ])
+        .await;
+        let capabilities = &responses[0]["result"]["capabilities"];
+        assert!(capabilities.get("resources").is_none());
+        assert!(capabilities.get("tools").is_some());
+        assert_eq!(responses[1]["error"]["code"], -32601);
+        assert_eq!(responses[2]["error"]["code"], -32601);
+        assert_eq!(responses[3]["result"]["isError"], false);
+
+        // REST接口按不存在处理
+        let app = create_app(rustmcp.clone());
+        assert_eq!(get(&app, "/mcp/resources/read?uri=config%3A%2F%2Fapp", &[]).await.0, StatusCode::NOT_FOUND);
+        assert_eq!(get(&app, "/mcp/tools", &[]).await.0, StatusCode::OK);
+
+        // 运行时取消策略
+        rustmcp.set_method_policy(None);
+        assert!(rustmcp.mcp_initialize_result().capabilities.resources.is_some());
+        assert_eq!(get(&app, "/mcp/resources/read?uri=config%3A%2F%2Fapp", &[]).await.0, StatusCode::OK);
+    }
+}
@@ -0,0 +1,133 @@
+//! 资源的可读性探测
+//!
+//! 资源列表只说明注册了哪些资源，指向不存在的文件或已下线的服务的资源要等客户端读取时才会失败。
+//! [`RustMCP::probe_resources`](crate::RustMCP::probe_resources)并发读取每个按URI注册的资源，返回每个URI的结果：
+//! - 同一URI的所有表示都要读取并按MIME类型渲染成功，流式资源只读取第一块；
+//! - 每次探测最多运行[`Settings::with_resource_probes`](crate::Settings::with_resource_probes)设置的时间（默认5秒），
+//!   超时按失败处理；
+//! - 失败记录为警告日志。[`RustMCP::validate_with_probes`](crate::RustMCP::validate_with_probes)把失败作为
+//!   [`ValidationIssue`](crate::ValidationIssue)与静态校验的问题一起返回，管理接口的
+//!   `POST /diagnostics/probe-resources`也会运行探测。
+//!
+//! 设置了停用阈值时，连续失败达到阈值的资源从`resources/list`中移除并发送列表变更通知，
+//! 之后某次探测成功时恢复。停用的资源仍然可以按URI读取。解析器提供的资源不探测。
+//!
+//! ```rust
+//! use rustmcp::{RustMCP, Settings};
+//! use std::time::Duration;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! // 每次探测最多2秒，连续失败3次后停用
+//! let rustmcp = RustMCP::new().with_settings(Settings::new().with_resource_probes(Duration::from_secs(2), Some(3)));
+//! for (uri, result) in rustmcp.probe_resources(8).await {
+//!     if let Err(e) = result {
+//!         eprintln!("{}: {}", uri, e);
+//!     }
+//! }
+//! # }
+//! ```
+
+use futures::StreamExt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::server::resources::{FunctionResource, ResourceBody};
+use crate::server::Context;
+
+/// 默认的探测时限
+pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 读取同一URI的所有表示，超过`timeout`时触发取消标记并返回错误
+///
+/// 资源函数是同步的，在阻塞线程池中执行；超时后函数会继续运行到结束。
+pub(crate) async fn probe(representations: Vec<Arc<FunctionResource>>, timeout: Duration, pretty_json: bool) -> Result<(), String> {
+    let ctx = Context::new().with_deadline(Instant::now() + timeout);
+    let token = ctx.cancellation_token().clone();
+    let handle = tokio::runtime::Handle::current();
+    let task = tokio::task::spawn_blocking(move || {
+        let several = representations.len() > 1;
+        for resource in &representations {
+            let result = match resource.open(&ctx, pretty_json) {
+                Ok(ResourceBody::Stream(mut stream)) => match handle.block_on(stream.next()) {
+                    Some(Err(message)) => Err(message),
+                    _ => Ok(()),
+                },
+                Ok(ResourceBody::Contents(_)) => Ok(()),
+                Err(message) => Err(message),
+            };
+            // 有多个表示时说明是哪一个失败
+            result.map_err(|message| if several { format!("{}: {}", resource.mime_type, message) } else { message })?;
+        }
+        Ok(())
+    });
+    match tokio::time::timeout(timeout, task).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => Err(format!("probe failed: {}", e)),
+        Err(_) => {
+            token.cancel();
+            Err(format!("probe timed out after {}ms", timeout.as_millis()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::{FunctionResource, ItemKind, RustMCP, Settings, Severity};
+
+    fn listed(rustmcp: &RustMCP) -> Vec<String> {
+        rustmcp.mcp_list_resources().into_iter().map(|r| r.uri).collect()
+    }
+
+    #[tokio::test]
+    async fn failing_resources_are_disabled_until_a_probe_succeeds() {
+        let rustmcp = RustMCP::new().with_settings(
+            Settings::new().with_resource_probes(Duration::from_millis(50), Some(2)),
+        );
+        rustmcp.add_resource(FunctionResource::simple("memo://ok", || Ok(json!("fine"))));
+        rustmcp.add_resource(FunctionResource::simple("memo://missing", || Err("No such file".to_string())));
+        // 前两次读取失败
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        rustmcp.add_resource(FunctionResource::simple("memo://flaky", move || {
+            if counter.fetch_add(1, Ordering::SeqCst) < 2 { Err("busy".to_string()) } else { Ok(json!("back")) }
+        }));
+        rustmcp.add_resource(FunctionResource::simple("memo://slow", || {
+            std::thread::sleep(Duration::from_millis(200));
+            Ok(json!("late"))
+        }));
+
+        let results = rustmcp.probe_resources(4).await;
+        assert_eq!(results, vec![
+            ("memo://flaky".to_string(), Err("busy".to_string())),
+            ("memo://missing".to_string(), Err("No such file".to_string())),
+            ("memo://ok".to_string(), Ok(())),
+            ("memo://slow".to_string(), Err("probe timed out after 50ms".to_string())),
+        ]);
+        // 一次失败还不到阈值
+        assert_eq!(listed(&rustmcp).len(), 4);
+
+        // 第二次连续失败后停用
+        rustmcp.probe_resources(4).await;
+        assert_eq!(listed(&rustmcp), ["memo://ok"]);
+        assert_eq!(rustmcp.disabled_resources(), ["memo://flaky", "memo://missing", "memo://slow"]);
+
+        // 探测成功后恢复，校验报告仍然失败的资源
+        let issues = rustmcp.validate_with_probes(4).await;
+        assert_eq!(listed(&rustmcp), ["memo://flaky", "memo://ok"]);
+        let failing: Vec<(&str, &str)> = issues
+            .iter()
+            .filter(|i| i.kind == ItemKind::Resource && i.severity == Severity::Error)
+            .map(|i| (i.item.as_str(), i.message.as_str()))
+            .collect();
+        assert_eq!(failing, [
+            ("memo://missing", "read failed: No such file"),
+            ("memo://slow", "read failed: probe timed out after 50ms"),
+        ]);
+    }
+}
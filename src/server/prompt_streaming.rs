@@ -0,0 +1,281 @@
+//! 大提示的流式生成
+//!
+//! [`FunctionPrompt::from_stream_fn`](crate::FunctionPrompt::from_stream_fn)创建的提示逐条生成消息。
+//! 客户端在`prompts/get`的参数中设置`_meta.streamMessages`为`true`时，每条消息生成后立即发送，
+//! 内存占用不超过最大的一条消息：
+//!
+//! - 请求头`Accept`包含`text/event-stream`的`POST /mcp`：响应为SSE流，每条消息是一个
+//!   `notifications/prompts/message`事件，最后一个事件是请求的响应；客户端断开后停止生成。
+//!   与[通知流](crate::server::sse)一样先发送`retry:`字段，生成较慢时按保活间隔发送`: keepalive`注释；
+//! - WebSocket和stdio：每条消息作为一条`notifications/prompts/message`通知发送，之后返回响应。
+//!   发送前等待连接的发送队列空出位置；
+//! - 其他情况（普通HTTP的JSON响应，或者没有设置`_meta.streamMessages`）：收集全部消息后一起返回，
+//!   结果与普通提示相同。
+//!
+//! 通知的`requestId`为对应请求的id，`index`为从0开始的序号，`messages`中是一条消息，格式与
+//! `prompts/get`结果中的消息相同。逐条发送时响应中的`messages`为空，`_meta.streamedMessages`为发送的消息数：
+//!
+//! ```json
+//! {"jsonrpc":"2.0","method":"notifications/prompts/message","params":{"requestId":2,"index":0,"messages":[{"role":"user","content":[{"type":"text","text":"..."}]}]}}
+//! {"jsonrpc":"2.0","id":2,"result":{"messages":[],"_meta":{"streamedMessages":1}}}
+//! ```
+//!
+//! 生成过程中出错时响应为错误，客户端应丢弃已经收到的消息。
+//! 轮次检查（[`TurnPolicy`](crate::TurnPolicy)）在每条消息产生时进行。
+//!
+//! ```rust
+//! use rustmcp::{FunctionPrompt, PromptMessage, RustMCP};
+//!
+//! let rustmcp = RustMCP::new();
+//! rustmcp.add_prompt(FunctionPrompt::from_stream_fn("transcript", |_ctx, _args| {
+//!     // 实际的实现在这里逐条读取对话记录
+//!     futures::stream::iter((0..1000).map(|i| Ok(PromptMessage::user(format!("turn {}", i)))))
+//! }));
+//! ```
+
+use axum::http::{header, HeaderMap};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use futures::StreamExt;
+use serde_json::{Map, Value};
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use crate::protocol::{JsonRpcNotification, JsonRpcRequest, RequestId};
+use crate::server::dispatch;
+use crate::server::error::McpError;
+use crate::server::notifications::{Delivery, Outbox, SessionChannel};
+use crate::server::prompts::{PromptMessage, PromptStream};
+use crate::server::trace::TraceTap;
+use crate::server::{Context, RequestContext, RustMCP};
+
+/// 逐条发送消息的通知方法名
+pub const MESSAGE_NOTIFICATION: &str = "notifications/prompts/message";
+
+/// SSE响应的发送队列长度，发送下一条消息前等待队列清空
+const SSE_QUEUE_DEPTH: usize = 2;
+
+/// 请求是否要求逐条发送消息：`params._meta.streamMessages`为`true`
+pub(crate) fn requested(params: &Map<String, Value>) -> bool {
+    params
+        .get("_meta")
+        .and_then(|meta| meta.get("streamMessages"))
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// HTTP请求是否应该用SSE响应：要求逐条发送消息的`prompts/get`，并且`Accept`包含`text/event-stream`
+pub(crate) fn wants_sse(request: &JsonRpcRequest, headers: &HeaderMap) -> bool {
+    let accepts_sse = headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media_type| {
+            let media_type = media_type.split(';').next().unwrap_or_default().trim();
+            media_type.eq_ignore_ascii_case("text/event-stream")
+        });
+    let streamed = match &request.params {
+        Some(Value::Object(params)) => requested(params),
+        _ => false,
+    };
+    accepts_sse && streamed && request.method == "prompts/get"
+}
+
+/// 读取流式提示，返回`prompts/get`的结果
+///
+/// 要求逐条发送并且有回传通道时，每条消息作为通知发送，结果中的`messages`为空；
+/// 否则收集全部消息。`render`把消息转换为会话使用的格式。
+pub(crate) async fn get_stream(
+    name: &str,
+    mut stream: PromptStream,
+    render: impl Fn(PromptMessage) -> Value,
+    streamed: bool,
+    request_id: Option<&RequestId>,
+    request_context: &RequestContext,
+    ctx: &Context,
+) -> Result<Value, McpError> {
+    let get_error = |message: String| McpError::PromptGet {
+        name: name.to_string(),
+        message,
+    };
+    let Some(channel) = request_context.back_channel.as_ref().filter(|_| streamed) else {
+        let mut messages = Vec::new();
+        while let Some(message) = stream.next().await {
+            messages.push(render(message.map_err(get_error)?));
+        }
+        return Ok(serde_json::json!({ "messages": messages }));
+    };
+
+    let mut sent = 0u64;
+    while let Some(message) = stream.next().await {
+        let message = render(message.map_err(get_error)?);
+        let notification = JsonRpcNotification::new(
+            MESSAGE_NOTIFICATION,
+            Some(serde_json::json!({ "requestId": request_id, "index": sent, "messages": [message] })),
+        );
+        channel.writable().await;
+        match channel.send(&notification) {
+            Delivery::Delivered => sent += 1,
+            Delivery::NotDelivered => return Err(get_error("Connection closed while streaming the prompt".to_string())),
+        }
+    }
+    ctx.insert_result_meta("streamedMessages", Value::from(sent));
+    Ok(serde_json::json!({ "messages": [] }))
+}
+
+/// 客户端断开SSE响应时关闭发送队列，正在生成的提示在下一次发送时停止
+struct CloseOnDrop(Arc<Outbox>);
+
+impl Drop for CloseOnDrop {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+/// 在后台处理请求，通知和最后的响应作为SSE事件返回
+pub(crate) fn sse_response(
+    rustmcp: Arc<RustMCP>,
+    request: JsonRpcRequest,
+    request_context: Arc<RequestContext>,
+    trace: TraceTap,
+) -> Response {
+    let outbox = Outbox::new(SSE_QUEUE_DEPTH, rustmcp.dropped_notifications.clone(), trace);
+    let request_context = Arc::new(RequestContext {
+        back_channel: Some(SessionChannel::outbox(outbox.clone())),
+        ..(*request_context).clone()
+    });
+    let (keep_alive, retry) = (rustmcp.settings.sse_keep_alive(), rustmcp.settings.sse_retry());
+    let queue = outbox.clone();
+    tokio::spawn(async move {
+        let response = dispatch::dispatch_request(&rustmcp, request, request_context).await;
+        if let Ok(text) = serde_json::to_string(&response) {
+            queue.push(text, false);
+        }
+        queue.finish();
+    });
+    let retry = futures::stream::iter(retry.map(|retry| Ok(Event::default().retry(retry))));
+    let events = futures::stream::unfold(CloseOnDrop(outbox), |guard| async move {
+        let text = guard.0.next().await?;
+        Some((Ok::<_, Infallible>(Event::default().data(text)), guard))
+    });
+    let stream = Sse::new(retry.chain(events));
+    match keep_alive {
+        Some(interval) => stream.keep_alive(KeepAlive::new().interval(interval).text("keepalive")).into_response(),
+        None => stream.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use std::time::Duration;
+
+    use crate::server::test_support::{post, request, stdio};
+    use crate::{create_app, FunctionPrompt, PromptMessage, RustMCP, Settings};
+
+    const TURNS: usize = 6;
+
+    fn text(message: &Value) -> &str {
+        message["content"]["text"].as_str().or(message["content"][0]["text"].as_str()).unwrap()
+    }
+
+    fn server() -> RustMCP {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_prompt(FunctionPrompt::from_stream_fn("transcript", |_ctx, _args| {
+            futures::stream::iter((0..TURNS).map(|i| match i % 2 {
+                0 => Ok(PromptMessage::user(format!("question {}", i))),
+                _ => Ok(PromptMessage::assistant(format!("answer {}", i))),
+            }))
+        }));
+        rustmcp
+    }
+
+    fn streamed_get(id: u64) -> Value {
+        request(id, "prompts/get", json!({ "name": "transcript", "_meta": { "streamMessages": true } }))
+    }
+
+    #[tokio::test]
+    async fn stdio_sends_each_message_before_the_response() {
+        let lines = stdio(&server(), &[
+            request(1, "initialize", json!({})),
+            streamed_get(2),
+            request(3, "prompts/get", json!({ "name": "transcript" })),
+        ])
+        .await;
+
+        let streamed: Vec<&Value> = lines.iter().filter(|line| line["method"] == "notifications/prompts/message").collect();
+        assert_eq!(streamed.len(), TURNS);
+        for (i, notification) in streamed.iter().enumerate() {
+            assert_eq!(notification["params"]["requestId"], 2);
+            assert_eq!(notification["params"]["index"], i);
+            assert!(text(&notification["params"]["messages"][0]).ends_with(&i.to_string()));
+        }
+        let response = lines.iter().find(|line| line["id"] == 2).unwrap();
+        assert_eq!(response["result"]["messages"], json!([]));
+        assert_eq!(response["result"]["_meta"]["streamedMessages"], TURNS);
+        // 响应在所有通知之后
+        let position = |line: &Value| lines.iter().position(|other| other == line).unwrap();
+        assert!(streamed.iter().all(|notification| position(notification) < position(response)));
+
+        // 没有要求逐条发送的请求一起返回
+        let buffered = lines.iter().find(|line| line["id"] == 3).unwrap();
+        assert_eq!(buffered["result"]["messages"].as_array().unwrap().len(), TURNS);
+        assert_eq!(text(&buffered["result"]["messages"][5]), "answer 5");
+    }
+
+    #[tokio::test]
+    async fn http_streams_events_only_when_accepted() {
+        let app = create_app(server());
+
+        // 接受SSE的请求按顺序收到事件，最后一个事件是响应
+        let (_, headers, events) = post(&app, "/mcp", &[("accept", "application/json, text/event-stream")], &streamed_get(4)).await;
+        assert_eq!(headers["content-type"], "text/event-stream");
+        let events: Vec<Value> = events
+            .as_str()
+            .unwrap()
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(|data| serde_json::from_str(data.trim()).unwrap())
+            .collect();
+        assert_eq!(events.len(), TURNS + 1);
+        for (i, event) in events[..TURNS].iter().enumerate() {
+            assert_eq!(event["params"]["index"], i);
+        }
+        assert_eq!(events[TURNS]["id"], 4);
+        assert_eq!(events[TURNS]["result"]["_meta"]["streamedMessages"], TURNS);
+
+        // 只接受JSON的请求收到收集后的全部消息
+        let (_, headers, response) = post(&app, "/mcp", &[], &streamed_get(4)).await;
+        assert_eq!(headers["content-type"], "application/json");
+        let messages = response["result"]["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), TURNS);
+        assert_eq!(text(&messages[0]), "question 0");
+    }
+
+    #[tokio::test]
+    async fn slow_prompts_keep_the_sse_response_alive() {
+        let settings = Settings::new()
+            .with_sse_keep_alive(Duration::from_millis(50))
+            .with_sse_retry(Duration::from_millis(1500));
+        let rustmcp = RustMCP::new().with_settings(settings);
+        rustmcp.add_prompt(FunctionPrompt::from_stream_fn("slow", |_ctx, _args| {
+            futures::stream::once(async {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                Ok(PromptMessage::user("finally"))
+            })
+        }));
+        let app = create_app(rustmcp);
+
+        let get = request(5, "prompts/get", json!({ "name": "slow", "_meta": { "streamMessages": true } }));
+        let (_, _, body) = post(&app, "/mcp", &[("accept", "text/event-stream")], &get).await;
+        let frames: Vec<&str> = body.as_str().unwrap().split("\n\n").map(str::trim).filter(|frame| !frame.is_empty()).collect();
+        assert_eq!(frames[0], "retry:1500");
+        // 第一条消息生成之前已经过了多个保活间隔
+        let first_data = frames.iter().position(|frame| frame.starts_with("data:")).unwrap();
+        assert!(frames[1..first_data].len() >= 2, "{:?}", frames);
+        assert!(frames[1..first_data].iter().all(|frame| *frame == ": keepalive"), "{:?}", frames);
+        assert!(frames[first_data].contains("finally"));
+        assert!(frames.last().unwrap().contains(r#""id":5"#));
+    }
+}
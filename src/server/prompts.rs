@@ -1,29 +1,296 @@
+use futures::stream::{BoxStream, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use parking_lot::RwLock;
+use std::sync::{Arc, OnceLock};
 use serde_json::Value;
 use log::warn;
 
+use crate::server::Context;
+use crate::server::content::Content;
+use crate::server::error::{suggest, McpError};
+use crate::server::locale::{LanguageTag, Localizations};
+use crate::server::serialization::{serialize_items, SerializationPolicy};
+
+/// 提示消息中的内容块，与工具结果使用同一组类型
+pub type PromptContent = Content;
+
 /// 提示消息
-#[derive(Serialize, Deserialize, Debug, Clone)]
+///
+/// 一条消息可以有多个内容块（例如一段说明加一张图片）。只有一个内容块时`content`序列化为单个对象，
+/// 与MCP规范一致；有多个时序列化为数组，这是规范之外的扩展，只发给协商了2025-06-18及以后版本的会话，
+/// 更早的会话收到合并后的单个文本块（与工具结果的[格式选择](crate::ResultFormat)相同）。
+/// 反序列化时`content`可以是对象、数组或字符串（旧格式，视为一个文本块）。
+///
+/// ```rust
+/// use rustmcp::{Content, PromptMessage};
+/// use serde_json::json;
+///
+/// let single = PromptMessage::user("Describe this chart");
+/// assert_eq!(
+///     serde_json::to_value(&single).unwrap(),
+///     json!({ "role": "user", "content": { "type": "text", "text": "Describe this chart" } })
+/// );
+///
+/// let mixed = PromptMessage::new("user", vec![Content::text("Describe this chart"), Content::text("Keep it short")]);
+/// let value = serde_json::to_value(&mixed).unwrap();
+/// assert_eq!(value["content"].as_array().unwrap().len(), 2);
+/// let parsed: PromptMessage = serde_json::from_value(value).unwrap();
+/// assert_eq!(parsed.content, mixed.content);
+///
+/// // 旧格式的字符串内容
+/// let legacy: PromptMessage = serde_json::from_value(json!({ "role": "assistant", "content": "Sure." })).unwrap();
+/// assert_eq!(legacy.content, [Content::text("Sure.")]);
+/// assert_eq!(legacy.text(), "Sure.");
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "RawMessage")]
 pub struct PromptMessage {
-    /// 角色
+    /// 角色，MCP规范中为`user`或`assistant`
     pub role: String,
     
-    /// 内容
-    pub content: String,
+    /// 内容块
+    pub content: Vec<PromptContent>,
     
     /// 名称
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
 }
 
+impl PromptMessage {
+    /// 创建指定角色和内容块的消息
+    pub fn new(role: impl Into<String>, content: Vec<PromptContent>) -> Self {
+        Self {
+            role: role.into(),
+            content,
+            name: None,
+        }
+    }
+    
+    /// 只有一段文本的用户消息
+    pub fn user(text: impl Into<String>) -> Self {
+        Self::new(USER, vec![Content::text(text)])
+    }
+    
+    /// 只有一段文本的助手消息
+    pub fn assistant(text: impl Into<String>) -> Self {
+        Self::new(ASSISTANT, vec![Content::text(text)])
+    }
+    
+    /// 消息的文本，多个内容块以换行连接，媒体内容用占位文本代替
+    pub fn text(&self) -> String {
+        match self.content.as_slice() {
+            [Content::Text { text }] => text.clone(),
+            content => match Content::flatten(content) {
+                Content::Text { text } => text,
+                _ => String::new(),
+            },
+        }
+    }
+    
+    /// 把多个内容块合并为一个文本块，用于不支持内容数组的会话
+    pub(crate) fn flattened(mut self) -> Self {
+        if self.content.len() > 1 {
+            self.content = vec![Content::flatten(&self.content)];
+        }
+        self
+    }
+}
+
+/// 序列化用的消息视图
+#[derive(Serialize)]
+struct SerializedMessage<'a> {
+    role: &'a str,
+    content: SerializedContent<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: &'a Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum SerializedContent<'a> {
+    One(&'a PromptContent),
+    Many(&'a [PromptContent]),
+}
+
+impl Serialize for PromptMessage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let content = match self.content.as_slice() {
+            [one] => SerializedContent::One(one),
+            many => SerializedContent::Many(many),
+        };
+        SerializedMessage { role: &self.role, content, name: &self.name }.serialize(serializer)
+    }
+}
+
+/// 反序列化用的消息，`content`可以是对象、数组或字符串
+#[derive(Deserialize)]
+struct RawMessage {
+    role: String,
+    content: RawContent,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawContent {
+    Text(String),
+    One(PromptContent),
+    Many(Vec<PromptContent>),
+}
+
+impl From<RawMessage> for PromptMessage {
+    fn from(raw: RawMessage) -> Self {
+        let content = match raw.content {
+            RawContent::Text(text) => vec![Content::text(text)],
+            RawContent::One(content) => vec![content],
+            RawContent::Many(content) => content,
+        };
+        Self { role: raw.role, content, name: raw.name }
+    }
+}
+
+/// 用户角色
+const USER: &str = "user";
+
+/// 助手角色
+const ASSISTANT: &str = "assistant";
+
+/// 按轮次构造提示消息
+///
+/// 同一角色连续添加的内容合并到同一条消息中，角色变化时开始新的一条，得到的消息总是交替出现。
+///
+/// ```rust
+/// use rustmcp::{FunctionPrompt, PromptBuilder, RustMCP};
+///
+/// // 少样本提示：先说明任务，再给两组问答示例，最后是真正的问题和一张图片
+/// let prompt = FunctionPrompt::simple("classify_ticket", |args| {
+///     let ticket = args.unwrap_or_default().get("ticket").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+///     Ok(PromptBuilder::new()
+///         .user("Classify each support ticket as billing, bug or other. Reply with one word.")
+///         .user("Ticket: I was charged twice this month.")
+///         .assistant("billing")
+///         .user("Ticket: The export button does nothing.")
+///         .assistant("bug")
+///         .user(format!("Ticket: {}", ticket))
+///         .user_image("iVBORw0KGgo=", "image/png")
+///         .build())
+/// });
+/// let rustmcp = RustMCP::new();
+/// rustmcp.add_prompt(prompt);
+///
+/// let args = [("ticket".to_string(), "Screenshot attached".into())].into_iter().collect();
+/// let messages = rustmcp.mcp_get_prompt("classify_ticket", Some(args)).unwrap();
+/// let roles: Vec<&str> = messages.iter().map(|message| message.role.as_str()).collect();
+/// assert_eq!(roles, ["user", "assistant", "user", "assistant", "user"]);
+/// assert_eq!(messages[0].content.len(), 2);
+/// assert_eq!(messages[4].text(), "Ticket: Screenshot attached\n[image: image/png]");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PromptBuilder {
+    messages: Vec<PromptMessage>,
+}
+
+impl PromptBuilder {
+    /// 创建空的构造器
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// 添加用户文本
+    pub fn user(self, text: impl Into<String>) -> Self {
+        self.push(USER, Content::text(text))
+    }
+    
+    /// 添加助手文本
+    pub fn assistant(self, text: impl Into<String>) -> Self {
+        self.push(ASSISTANT, Content::text(text))
+    }
+    
+    /// 添加用户图片，`data`为base64编码的图片数据
+    pub fn user_image(self, data: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        self.push(USER, Content::Image { data: data.into(), mime_type: mime_type.into() })
+    }
+    
+    /// 添加用户的任意内容块
+    pub fn user_content(self, content: PromptContent) -> Self {
+        self.push(USER, content)
+    }
+    
+    /// 添加助手的任意内容块
+    pub fn assistant_content(self, content: PromptContent) -> Self {
+        self.push(ASSISTANT, content)
+    }
+    
+    /// 构造的消息
+    pub fn build(self) -> Vec<PromptMessage> {
+        self.messages
+    }
+    
+    fn push(mut self, role: &str, content: PromptContent) -> Self {
+        match self.messages.last_mut() {
+            Some(last) if last.role == role => last.content.push(content),
+            _ => self.messages.push(PromptMessage::new(role, vec![content])),
+        }
+        self
+    }
+}
+
+/// 提示消息的轮次检查
+///
+/// 提示函数返回的消息应当只使用`user`和`assistant`角色，并且两种角色交替出现。
+/// 使用[`Settings::with_prompt_turn_policy`](crate::Settings::with_prompt_turn_policy)设置，默认记录警告。
+///
+/// ```rust
+/// use rustmcp::{RustMCP, Settings, TurnPolicy};
+///
+/// // 角色不交替的提示在`prompts/get`时返回错误
+/// let rustmcp = RustMCP::new().with_settings(Settings::new().with_prompt_turn_policy(TurnPolicy::Strict));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnPolicy {
+    /// 不检查
+    Off,
+    /// 记录警告，照常返回消息
+    #[default]
+    Warn,
+    /// `prompts/get`返回提示生成错误
+    Strict,
+}
+
+/// 检查消息的角色和轮次，返回第一个问题
+fn check_turns(messages: &[PromptMessage]) -> Option<String> {
+    messages
+        .iter()
+        .enumerate()
+        .find_map(|(index, message)| check_turn(index, index.checked_sub(1).map(|i| messages[i].role.as_str()), message))
+}
+
+/// 检查第`index`条消息的角色，以及它与上一条消息是否是同一方的轮次
+fn check_turn(index: usize, previous: Option<&str>, message: &PromptMessage) -> Option<String> {
+    if message.role != USER && message.role != ASSISTANT {
+        return Some(format!("message {} has role '{}', expected 'user' or 'assistant'", index, message.role));
+    }
+    if previous == Some(message.role.as_str()) {
+        return Some(format!("messages {} and {} are both '{}' turns", index - 1, index, message.role));
+    }
+    None
+}
+
 /// 提示定义
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Prompt {
     /// 提示名称
     pub name: String,
     
+    /// 提示的人类可读标题
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    
     /// 提示描述
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
@@ -36,12 +303,73 @@ pub struct Prompt {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub annotations: Option<HashMap<String, Value>>,
     
-    /// 元数据
+    /// 参数，按名称排序
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<Vec<PromptArgument>>,
+    
+    /// 元数据
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
     pub meta: Option<HashMap<String, Value>>,
 }
 
-type PromptFunction = Arc<dyn Fn(Option<HashMap<String, Value>>) -> Result<Vec<PromptMessage>, String> + Send + Sync>;
+/// 提示参数的声明
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PromptArgument {
+    /// 参数名称
+    pub name: String,
+    
+    /// 参数描述
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// 提示的参数示例，序列化在提示列表的`_meta.examples`中
+///
+/// 开启[`Settings::validate_examples`](crate::Settings::validate_examples)时，启动校验检查示例只使用了声明过的参数，
+/// 参见[`RustMCP::validate_examples`](crate::RustMCP::validate_examples)。
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PromptExample {
+    /// 示例说明
+    pub description: String,
+    /// 参数值
+    pub arguments: HashMap<String, String>,
+}
+
+impl PromptExample {
+    /// 使用说明和参数值创建示例
+    pub fn new<K: Into<String>, V: Into<String>>(description: impl Into<String>, arguments: impl IntoIterator<Item = (K, V)>) -> Self {
+        Self {
+            description: description.into(),
+            arguments: arguments.into_iter().map(|(name, value)| (name.into(), value.into())).collect(),
+        }
+    }
+}
+
+/// 一页提示列表，参见[`PromptManager::list_prompts_page`]
+#[derive(Debug, Clone)]
+pub struct PromptPage {
+    /// 本页的提示（序列化后的[`Prompt`]），按名称排序
+    pub prompts: Vec<Value>,
+    
+    /// 下一页的游标，最后一页为`None`
+    pub next_cursor: Option<String>,
+}
+
+type PromptFunction = Arc<dyn Fn(&Context, Option<HashMap<String, Value>>) -> Result<Vec<PromptMessage>, String> + Send + Sync>;
+
+/// 提示消息流
+pub type PromptStream = BoxStream<'static, Result<PromptMessage, String>>;
+
+/// 流式提示函数类型定义
+pub type PromptStreamFunction = Arc<dyn Fn(&Context, Option<HashMap<String, Value>>) -> PromptStream + Send + Sync>;
+
+/// 执行提示得到的消息
+pub(crate) enum PromptBody {
+    /// 提示函数一次返回的消息
+    Messages(Vec<PromptMessage>),
+    /// 流式提示的消息流
+    Stream(PromptStream),
+}
 
 /// 函数式提示
 #[derive(Clone)]
@@ -52,6 +380,9 @@ pub struct FunctionPrompt {
     /// 提示名称
     pub name: String,
     
+    /// 提示的人类可读标题
+    pub title: Option<String>,
+    
     /// 提示描述
     pub description: String,
     
@@ -61,8 +392,20 @@ pub struct FunctionPrompt {
     /// 参数
     pub arguments: Option<HashMap<String, String>>,
     
+    /// 提示使用的模板文本（可选），启动校验用它检查没有被使用的参数
+    pub template: Option<String>,
+    
     /// 元数据
     pub meta: Option<Value>,
+    
+    /// 参数示例，序列化在`_meta.examples`中
+    pub examples: Vec<PromptExample>,
+    
+    /// 本地化的标题和描述，序列化在`_meta.localizations`中
+    pub localizations: Localizations,
+    
+    /// 流式提示函数，设置后逐条生成消息，参见[`prompt_streaming`](crate::server::prompt_streaming)
+    pub stream: Option<PromptStreamFunction>,
 }
 
 impl FunctionPrompt {
@@ -86,25 +429,174 @@ impl FunctionPrompt {
     ) -> Self
     where
         F: Fn(Option<HashMap<String, Value>>) -> Result<Vec<PromptMessage>, String> + Send + Sync + 'static,
+    {
+        Self::from_function_with_context(
+            move |_ctx: &Context, args: Option<HashMap<String, Value>>| function(args),
+            name,
+            description,
+            tags,
+            arguments,
+            meta,
+        )
+    }
+    
+    /// 从接收上下文的函数创建提示
+    ///
+    /// 参数与 [`FunctionPrompt::from_function`] 相同，区别在于函数的第一个参数为当前请求的 [`Context`]。
+    /// 提示可以按[`Context::client_info`]、[`Context::locale`]、[`Context::request_meta`]和
+    /// [`Context::protocol_version`]调整消息；直接调用[`PromptManager::get_prompt`]时传入的上下文没有会话，
+    /// 这些方法返回默认值。
+    ///
+    /// ```rust
+    /// use rustmcp::{FunctionPrompt, PromptMessage};
+    ///
+    /// let prompt = FunctionPrompt::from_function_with_context(
+    ///     |ctx, _args| {
+    ///         let client = ctx.client_info().map(|info| info.name).unwrap_or_else(|| "unknown".to_string());
+    ///         Ok(vec![PromptMessage::user(format!("Hello {}", client))])
+    ///     },
+    ///     "greeting".to_string(),
+    ///     None,
+    ///     None,
+    ///     None,
+    ///     None,
+    /// );
+    /// ```
+    pub fn from_function_with_context<F>(
+        function: F,
+        name: String,
+        description: Option<String>,
+        tags: Option<Vec<String>>,
+        arguments: Option<HashMap<String, String>>,
+        meta: Option<Value>,
+    ) -> Self
+    where
+        F: Fn(&Context, Option<HashMap<String, Value>>) -> Result<Vec<PromptMessage>, String> + Send + Sync + 'static,
     {
         Self {
             function: Some(Arc::new(function)),
             name,
+            title: None,
             description: description.unwrap_or_default(),
             tags: tags.unwrap_or_default(),
             arguments,
+            template: None,
             meta,
+            examples: Vec::new(),
+            localizations: Localizations::default(),
+            stream: None,
         }
     }
     
+    /// 从逐条生成消息的函数创建流式提示
+    ///
+    /// 客户端请求时每条消息生成后立即发送，内存占用不超过最大的一条消息，
+    /// 参见[`prompt_streaming`](crate::server::prompt_streaming)。
+    /// 同步的[`FunctionPrompt::get`]不能读取流式提示，会返回错误。
+    pub fn from_stream_fn<F, S>(name: impl Into<String>, function: F) -> Self
+    where
+        F: Fn(&Context, Option<HashMap<String, Value>>) -> S + Send + Sync + 'static,
+        S: Stream<Item = Result<PromptMessage, String>> + Send + 'static,
+    {
+        let name = name.into();
+        let message = format!("Prompt '{}' is streamed and cannot be read synchronously", name);
+        let mut prompt = Self::from_function(move |_| Err(message.clone()), name, None, None, None, None);
+        prompt.stream = Some(Arc::new(move |ctx: &Context, arguments| function(ctx, arguments).boxed()));
+        prompt
+    }
+    
+    /// 使用名称和函数创建提示
+    pub fn simple<F>(name: impl Into<String>, function: F) -> Self
+    where
+        F: Fn(Option<HashMap<String, Value>>) -> Result<Vec<PromptMessage>, String> + Send + Sync + 'static,
+    {
+        Self::from_function(function, name.into(), None, None, None, None)
+    }
+    
+    /// 设置提示标题
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+    
+    /// 设置某种语言的标题，参见[`locale`](crate::server::locale)
+    pub fn with_title_localized(mut self, lang: impl Into<LanguageTag>, title: impl Into<String>) -> Self {
+        self.localizations.titles.insert(lang.into(), title.into());
+        self
+    }
+    
+    /// 设置某种语言的描述，参见[`locale`](crate::server::locale)
+    pub fn with_description_localized(mut self, lang: impl Into<LanguageTag>, description: impl Into<String>) -> Self {
+        self.localizations.descriptions.insert(lang.into(), description.into());
+        self
+    }
+    
+    /// 设置提示使用的模板文本
+    pub fn with_template(mut self, template: impl Into<String>) -> Self {
+        self.template = Some(template.into());
+        self
+    }
+    
+    /// 设置提示元数据
+    pub fn with_meta(mut self, meta: Value) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+    
+    /// 添加一个参数示例
+    pub fn with_example(mut self, example: PromptExample) -> Self {
+        self.examples.push(example);
+        self
+    }
+    
     /// 获取提示
-    pub fn get(&self, arguments: Option<HashMap<String, Value>>) -> Result<Vec<PromptMessage>, String> {
+    pub fn get(&self, ctx: &Context, arguments: Option<HashMap<String, Value>>) -> Result<Vec<PromptMessage>, String> {
         if let Some(func) = &self.function {
-            func(arguments)
+            func(ctx, arguments)
         } else {
             Err("Prompt function not available".to_string())
         }
     }
+    
+    /// 执行提示，流式提示返回消息流
+    pub(crate) fn open(&self, ctx: &Context, arguments: Option<HashMap<String, Value>>) -> Result<PromptBody, String> {
+        match &self.stream {
+            Some(stream) => Ok(PromptBody::Stream(stream(ctx, arguments))),
+            None => self.get(ctx, arguments).map(PromptBody::Messages),
+        }
+    }
+    
+    /// 转换为不含函数的提示定义
+    pub fn to_prompt(&self) -> Prompt {
+        Prompt {
+            name: self.name.clone(),
+            title: self.title.clone(),
+            description: if self.description.is_empty() { None } else { Some(self.description.clone()) },
+            tags: if self.tags.is_empty() { None } else { Some(self.tags.clone()) },
+            annotations: None, // 注解字段已移除
+            arguments: self.arguments.as_ref().filter(|arguments| !arguments.is_empty()).map(|arguments| {
+                let mut arguments: Vec<PromptArgument> = arguments
+                    .iter()
+                    .map(|(name, description)| PromptArgument {
+                        name: name.clone(),
+                        description: if description.is_empty() { None } else { Some(description.clone()) },
+                    })
+                    .collect();
+                arguments.sort_by(|a, b| a.name.cmp(&b.name));
+                arguments
+            }),
+            meta: {
+                let mut meta: HashMap<String, Value> = match self.localizations.merge_into_meta(self.meta.clone()) {
+                    Some(Value::Object(obj)) => obj.into_iter().collect(),
+                    _ => HashMap::new(),
+                };
+                if !self.examples.is_empty() {
+                    meta.insert("examples".to_string(), serde_json::json!(self.examples));
+                }
+                if meta.is_empty() { None } else { Some(meta) }
+            },
+        }
+    }
 }
 
 impl Serialize for FunctionPrompt {
@@ -113,20 +605,7 @@ impl Serialize for FunctionPrompt {
         S: serde::Serializer,
     {
         // 只序列化Prompt部分，不包括函数
-        let prompt = Prompt {
-            name: self.name.clone(),
-            description: if self.description.is_empty() { None } else { Some(self.description.clone()) },
-            tags: if self.tags.is_empty() { None } else { Some(self.tags.clone()) },
-            annotations: None, // 注解字段已移除
-            meta: match &self.meta {
-                Some(Value::Object(obj)) if !obj.is_empty() => {
-                    let map: HashMap<String, Value> = obj.clone().into_iter().collect();
-                    Some(map)
-                },
-                _ => None,
-            },
-        };
-        prompt.serialize(serializer)
+        self.to_prompt().serialize(serializer)
     }
 }
 
@@ -134,10 +613,15 @@ impl std::fmt::Debug for FunctionPrompt {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("FunctionPrompt")
             .field("name", &self.name)
+            .field("title", &self.title)
             .field("description", &self.description)
             .field("tags", &self.tags)
             .field("arguments", &self.arguments)
+            .field("template", &self.template)
             .field("meta", &self.meta)
+            .field("examples", &self.examples)
+            .field("localizations", &self.localizations)
+            .field("stream", &self.stream.is_some())
             .finish()
     }
 }
@@ -151,30 +635,87 @@ pub enum DuplicateBehavior {
     Ignore,
 }
 
+/// 提示表和列表结果的缓存
+#[derive(Debug, Default)]
+struct PromptTable {
+    prompts: HashMap<String, Arc<FunctionPrompt>>,
+    /// 序列化后的提示列表（或序列化错误），第一次列出时生成，提示表变化时清空
+    listing: OnceLock<Result<Arc<Value>, McpError>>,
+    /// 修订号，提示表每次变化时加一
+    revision: u64,
+    /// 停用的提示，不出现在列表中
+    disabled: HashSet<String>,
+}
+
+impl PromptTable {
+    fn insert(&mut self, key: String, value: FunctionPrompt) {
+        self.disabled.remove(&key);
+        self.prompts.insert(key, Arc::new(value));
+        self.listing = OnceLock::new();
+        self.revision += 1;
+    }
+
+    fn remove(&mut self, key: &str) -> bool {
+        let removed = self.prompts.remove(key).is_some();
+        if removed {
+            self.disabled.remove(key);
+            self.listing = OnceLock::new();
+            self.revision += 1;
+        }
+        removed
+    }
+
+    /// 按名称排序的提示列表，保证每次列出的顺序一致，不包括停用的提示
+    fn sorted(&self) -> Vec<Prompt> {
+        let mut prompts: Vec<Prompt> = self
+            .prompts
+            .iter()
+            .filter(|(name, _)| !self.disabled.contains(*name))
+            .map(|(_, p)| p.to_prompt())
+            .collect();
+        prompts.sort_by(|a, b| a.name.cmp(&b.name));
+        prompts
+    }
+}
+
 /// 提示管理器
+///
+/// 提示表在内部加锁，增删查都只需要`&self`。克隆得到的管理器共享同一份表。
 #[derive(Debug, Clone)]
 pub struct PromptManager {
     /// 提示集合
-    prompts: HashMap<String, FunctionPrompt>,
+    table: Arc<RwLock<PromptTable>>,
     duplicate_behavior: DuplicateBehavior,
+    serialization_policy: SerializationPolicy,
+    turn_policy: TurnPolicy,
 }
 
 impl PromptManager {
     /// 创建新的提示管理器
     pub fn new() -> Self {
-        Self {
-            prompts: HashMap::new(),
-            duplicate_behavior: DuplicateBehavior::Warn,
-        }
+        Self::with_behavior(DuplicateBehavior::Warn)
     }
     
     /// 创建具有指定重复行为的新提示管理器
     pub fn with_behavior(duplicate_behavior: DuplicateBehavior) -> Self {
         Self {
-            prompts: HashMap::new(),
+            table: Arc::default(),
             duplicate_behavior,
+            serialization_policy: SerializationPolicy::default(),
+            turn_policy: TurnPolicy::default(),
         }
     }
+    
+    /// 设置提示列表中某个提示序列化失败时的处理方式，参见[`serialization`](crate::server::serialization)
+    pub fn set_serialization_policy(&mut self, policy: SerializationPolicy) {
+        self.serialization_policy = policy;
+        self.table.write().listing = OnceLock::new();
+    }
+    
+    /// 设置提示消息的轮次检查（默认记录警告）
+    pub fn set_turn_policy(&mut self, policy: TurnPolicy) {
+        self.turn_policy = policy;
+    }
 }
 
 impl Default for PromptManager {
@@ -185,60 +726,347 @@ impl Default for PromptManager {
 
 impl PromptManager {
     /// 添加提示
-    pub fn add_prompt(&mut self, prompt: FunctionPrompt) {
-        if self.prompts.contains_key(&prompt.name) {
+    ///
+    /// # Panics
+    ///
+    /// 重复行为为`Error`且提示已存在时panic，需要处理错误时使用[`PromptManager::try_add_prompt`]。
+    pub fn add_prompt(&self, prompt: FunctionPrompt) {
+        if let Err(message) = self.try_add_prompt(prompt) {
+            panic!("{}", message);
+        }
+    }
+    
+    /// 添加提示，重复行为为`Error`且提示已存在时返回错误
+    ///
+    /// 返回提示是否被添加，重复行为为`Ignore`且提示已存在时返回`Ok(false)`。
+    pub fn try_add_prompt(&self, prompt: FunctionPrompt) -> Result<bool, String> {
+        let mut table = self.table.write();
+        if table.prompts.contains_key(&prompt.name) {
             match self.duplicate_behavior {
                 DuplicateBehavior::Warn => {
                     warn!("Prompt '{}' already exists, replacing", prompt.name);
-                    self.prompts.insert(prompt.name.clone(), prompt);
                 }
                 DuplicateBehavior::Error => {
-                    panic!("Prompt '{}' already exists", prompt.name);
-                }
-                DuplicateBehavior::Replace => {
-                    self.prompts.insert(prompt.name.clone(), prompt);
+                    return Err(format!("Prompt '{}' already exists", prompt.name));
                 }
+                DuplicateBehavior::Replace => {}
                 DuplicateBehavior::Ignore => {
                     // 不添加新提示
+                    return Ok(false);
                 }
             }
-        } else {
-            self.prompts.insert(prompt.name.clone(), prompt);
         }
+        table.insert(prompt.name.clone(), prompt);
+        Ok(true)
+    }
+    
+    /// 删除提示，返回提示是否存在
+    pub fn remove_prompt(&self, name: &str) -> bool {
+        self.table.write().remove(name)
+    }
+    
+    /// 启用或停用提示，提示不存在时返回`None`，否则返回状态是否发生变化
+    pub fn set_prompt_enabled(&self, name: &str, enabled: bool) -> Option<bool> {
+        let mut table = self.table.write();
+        if !table.prompts.contains_key(name) {
+            return None;
+        }
+        let changed = match enabled {
+            true => table.disabled.remove(name),
+            false => table.disabled.insert(name.to_string()),
+        };
+        if changed {
+            table.listing = OnceLock::new();
+            table.revision += 1;
+        }
+        Some(changed)
     }
     
     /// 列出所有提示
     pub fn list_prompts(&self) -> Vec<Prompt> {
-        self.prompts.values().map(|p| {
-            Prompt {
-                name: p.name.clone(),
-                description: if p.description.is_empty() { None } else { Some(p.description.clone()) },
-                tags: if p.tags.is_empty() { None } else { Some(p.tags.clone()) },
-                annotations: None, // 注解字段已移除
-                meta: match &p.meta {
-                    Some(Value::Object(obj)) if !obj.is_empty() => {
-                        let map: HashMap<String, Value> = obj.clone().into_iter().collect();
-                        Some(map)
-                    },
-                    _ => None,
-                },
-
-            }
-        }).collect()
+        self.table.read().sorted()
+    }
+    
+    /// 序列化后的提示列表（JSON数组）
+    ///
+    /// 结果被缓存，提示表不变时多次调用和并发请求共享同一份，添加或删除提示后重新生成。
+    /// 某个提示序列化失败时按[`SerializationPolicy`]返回错误或跳过该提示。
+    pub fn prompt_listing(&self) -> Result<Arc<Value>, McpError> {
+        let table = self.table.read();
+        table
+            .listing
+            .get_or_init(|| {
+                serialize_items(&table.sorted(), "prompt", |prompt| &prompt.name, self.serialization_policy)
+                    .map(|prompts| Arc::new(Value::Array(prompts)))
+            })
+            .clone()
+    }
+    
+    /// 按名称分页列出提示，每页最多`limit`个
+    ///
+    /// 页面从[`PromptManager::prompt_listing`]缓存的列表中切出，不重新生成提示定义。
+    /// `cursor`为上一页的[`PromptPage::next_cursor`]，为`None`时从第一页开始。游标记录上一页
+    /// 最后一个提示的名称，翻页期间添加或删除提示时，没有变化的提示不会重复或遗漏。
+    /// 客户端应当把游标当作不透明的字符串。
+    ///
+    /// ```rust
+    /// use rustmcp::server::{FunctionPrompt, PromptManager};
+    ///
+    /// let manager = PromptManager::new();
+    /// manager.add_prompt(FunctionPrompt::simple("summarize", |_| Ok(Vec::new())));
+    /// let page = manager.list_prompts_page(None, 50).unwrap();
+    /// assert_eq!(page.prompts.len(), 1);
+    /// assert!(page.next_cursor.is_none());
+    /// ```
+    pub fn list_prompts_page(&self, cursor: Option<&str>, limit: usize) -> Result<PromptPage, McpError> {
+        let listing = self.prompt_listing()?;
+        let prompts = listing.as_array().map(Vec::as_slice).unwrap_or_default();
+        let name = |prompt: &Value| prompt["name"].as_str().unwrap_or_default().to_string();
+        let start = match cursor {
+            Some(cursor) => prompts.partition_point(|prompt| prompt["name"].as_str().unwrap_or_default() <= cursor),
+            None => 0,
+        };
+        let end = start.saturating_add(limit.max(1)).min(prompts.len());
+        Ok(PromptPage {
+            prompts: prompts[start..end].to_vec(),
+            next_cursor: (end < prompts.len()).then(|| name(&prompts[end - 1])),
+        })
+    }
+    
+    /// 提示表的修订号，添加或删除提示后增大
+    pub(crate) fn revision(&self) -> u64 {
+        self.table.read().revision
+    }
+    
+    /// 列出所有提示的完整定义
+    pub(crate) fn function_prompts(&self) -> Vec<FunctionPrompt> {
+        self.table.read().prompts.values().map(|p| FunctionPrompt::clone(p)).collect()
     }
     
     /// 获取提示函数
     #[allow(clippy::type_complexity)]
     pub fn get_prompt_function(&self, name: &str) -> Option<PromptFunction> {
-        self.prompts.get(name).and_then(|prompt| prompt.function.clone())
+        self.table.read().prompts.get(name).and_then(|prompt| prompt.function.clone())
     }
     
-    /// 获取提示
-    pub fn get_prompt(&self, name: &str, arguments: Option<HashMap<String, Value>>) -> Result<Vec<PromptMessage>, String> {
-        if let Some(prompt) = self.prompts.get(name) {
-            prompt.get(arguments)
-        } else {
-            Err(format!("Prompt not found: {}", name))
+    /// 执行提示，流式提示的消息流在产生消息时逐条检查轮次
+    pub(crate) fn open_prompt(&self, name: &str, arguments: Option<HashMap<String, Value>>, ctx: &Context) -> Result<PromptBody, McpError> {
+        let prompt = self.lookup(name)?;
+        match prompt.open(ctx, arguments).map_err(|message| McpError::PromptGet { name: name.to_string(), message })? {
+            PromptBody::Messages(messages) => {
+                self.apply_turn_policy(name, check_turns(&messages))?;
+                Ok(PromptBody::Messages(messages))
+            }
+            PromptBody::Stream(stream) if self.turn_policy == TurnPolicy::Off => Ok(PromptBody::Stream(stream)),
+            PromptBody::Stream(stream) => {
+                let (policy, name) = (self.turn_policy, name.to_string());
+                let mut previous: Option<String> = None;
+                let mut index = 0;
+                let checked = stream.map(move |message| {
+                    let message = message?;
+                    if let Some(problem) = check_turn(index, previous.as_deref(), &message) {
+                        if policy == TurnPolicy::Strict {
+                            return Err(problem);
+                        }
+                        warn!("Prompt '{}': {}", name, problem);
+                    }
+                    previous = Some(message.role.clone());
+                    index += 1;
+                    Ok(message)
+                });
+                Ok(PromptBody::Stream(checked.boxed()))
+            }
         }
     }
-}
\ No newline at end of file
+    
+    /// 获取提示，提示函数执行期间不持有锁
+    pub fn get_prompt(&self, name: &str, arguments: Option<HashMap<String, Value>>, ctx: &Context) -> Result<Vec<PromptMessage>, McpError> {
+        let prompt = self.lookup(name)?;
+        let messages = prompt.get(ctx, arguments).map_err(|message| McpError::PromptGet {
+            name: name.to_string(),
+            message,
+        })?;
+        self.apply_turn_policy(name, check_turns(&messages))?;
+        Ok(messages)
+    }
+    
+    /// 按轮次策略处理检查出的问题
+    fn apply_turn_policy(&self, name: &str, problem: Option<String>) -> Result<(), McpError> {
+        if self.turn_policy == TurnPolicy::Off {
+            return Ok(());
+        }
+        if let Some(problem) = problem {
+            if self.turn_policy == TurnPolicy::Strict {
+                return Err(McpError::PromptGet { name: name.to_string(), message: problem });
+            }
+            warn!("Prompt '{}': {}", name, problem);
+        }
+        Ok(())
+    }
+    
+    /// 查找提示，返回克隆的定义
+    fn lookup(&self, name: &str) -> Result<FunctionPrompt, McpError> {
+        let table = self.table.read();
+        match table.prompts.get(name) {
+            Some(_) if table.disabled.contains(name) => Err(McpError::PromptDisabled(name.to_string())),
+            Some(prompt) => Ok(FunctionPrompt::clone(prompt)),
+            None => Err(McpError::PromptNotFound {
+                name: name.to_string(),
+                suggestions: suggest(name, table.prompts.keys()),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::test_support::{request, spawn_server, stdio, WsClient};
+    use crate::{RustMCP, Settings};
+    use serde_json::json;
+
+    #[test]
+    fn prompt_serializes_title_and_meta() {
+        let prompt = FunctionPrompt::simple("greeting", |_| Ok(vec![PromptMessage::user("Hi")]))
+            .with_title("Greeting")
+            .with_meta(json!({ "category": "onboarding" }));
+        assert_eq!(
+            serde_json::to_value(prompt.to_prompt()).unwrap(),
+            json!({ "name": "greeting", "title": "Greeting", "_meta": { "category": "onboarding" } })
+        );
+    }
+
+    #[test]
+    fn prompt_without_title_or_meta_omits_them() {
+        let prompt = FunctionPrompt::simple("plain", |_| Ok(Vec::new()));
+        assert_eq!(serde_json::to_value(prompt.to_prompt()).unwrap(), json!({ "name": "plain" }));
+    }
+
+    fn names(listing: &Value) -> Vec<&str> {
+        listing.as_array().unwrap().iter().map(|prompt| prompt["name"].as_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn listing_snapshot_is_shared_until_the_table_changes() {
+        let prompt = |name: &str| FunctionPrompt::simple(name, |_| Ok(vec![PromptMessage::user("Hi")]));
+        let manager = PromptManager::new();
+        manager.add_prompt(prompt("review"));
+        let first = manager.prompt_listing().unwrap();
+        assert!(Arc::ptr_eq(&first, &manager.prompt_listing().unwrap()));
+
+        manager.add_prompt(prompt("greet"));
+        let added = manager.prompt_listing().unwrap();
+        assert!(!Arc::ptr_eq(&first, &added));
+        assert_eq!(names(&added), ["greet", "review"]);
+        assert_eq!(names(&first), ["review"]);
+
+        assert!(!manager.remove_prompt("missing"));
+        assert!(Arc::ptr_eq(&added, &manager.prompt_listing().unwrap()));
+        assert!(manager.remove_prompt("review"));
+        assert_eq!(names(&manager.prompt_listing().unwrap()), ["greet"]);
+
+        manager.add_prompt(prompt("greet").with_title("Greeting"));
+        assert_eq!(manager.prompt_listing().unwrap()[0]["title"], "Greeting");
+    }
+
+    #[test]
+    fn pages_cover_every_prompt_once_and_share_the_listing() {
+        let manager = PromptManager::new();
+        for i in 0..3000 {
+            let arguments = HashMap::from([("topic".to_string(), "What to write about".to_string())]);
+            manager.add_prompt(FunctionPrompt::from_function(
+                |_| Ok(Vec::new()),
+                format!("template_{:04}", i),
+                Some("Generated from the template library".to_string()),
+                None,
+                Some(arguments),
+                None,
+            ));
+        }
+
+        // 逐页列出，所有提示恰好出现一次，顺序与名称顺序一致
+        let mut names = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = manager.list_prompts_page(cursor.as_deref(), 250).unwrap();
+            assert!(page.prompts.len() <= 250);
+            for prompt in &page.prompts {
+                assert_eq!(prompt["arguments"][0]["name"], "topic");
+                names.push(prompt["name"].as_str().unwrap().to_string());
+            }
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        let expected: Vec<String> = (0..3000).map(|i| format!("template_{:04}", i)).collect();
+        assert_eq!(names, expected);
+
+        // 提示表不变时，翻页和重复列出共享同一份缓存的列表
+        let listing = manager.prompt_listing().unwrap();
+        manager.list_prompts_page(None, 100).unwrap();
+        assert!(Arc::ptr_eq(&listing, &manager.prompt_listing().unwrap()));
+        manager.remove_prompt("template_0000");
+        assert!(!Arc::ptr_eq(&listing, &manager.prompt_listing().unwrap()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn context_prompts_see_the_client_locale_and_version() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_prompt(FunctionPrompt::from_function_with_context(
+            |ctx, _args| {
+                let client = ctx.client_info().map(|info| info.name).unwrap_or_else(|| "unknown".to_string());
+                let locale = ctx.locale().first().map(|tag| tag.as_str().to_string()).unwrap_or_default();
+                let content = format!("Hello {} ({}, {})", client, locale, ctx.protocol_version());
+                Ok(vec![PromptMessage::user(content)])
+            },
+            "greeting".to_string(),
+            None,
+            None,
+            None,
+            None,
+        ));
+        let addr = spawn_server(&rustmcp).await;
+        let mut client = WsClient::connect(addr).await;
+        let initialize = json!({ "protocolVersion": "2025-06-18", "clientInfo": { "name": "inspector", "version": "0.9" } });
+        client.call(1, "initialize", initialize).await;
+        let response = client.call(2, "prompts/get", json!({ "name": "greeting", "_meta": { "locale": "fr-CA" } })).await;
+        assert_eq!(response["result"]["messages"][0]["content"]["text"], "Hello inspector (fr-CA, 2025-06-18)");
+
+        // 直接调用时没有会话
+        let messages = rustmcp.mcp_get_prompt("greeting", None).unwrap();
+        assert_eq!(messages[0].text(), "Hello unknown (, 2024-11-05)");
+    }
+
+    /// 按协议版本初始化stdio会话后获取提示，返回响应
+    async fn get_with_version(rustmcp: &RustMCP, version: &str, name: &str) -> Value {
+        let initialize = request(0, "initialize", json!({ "protocolVersion": version }));
+        let mut output = stdio(rustmcp, &[initialize, request(1, "prompts/get", json!({ "name": name }))]).await;
+        output.pop().unwrap()
+    }
+
+    #[tokio::test]
+    async fn turns_are_merged_for_old_versions_and_checked_in_strict_mode() {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_prompt_turn_policy(TurnPolicy::Strict));
+        rustmcp.add_prompt(FunctionPrompt::simple("review", |_| {
+            Ok(PromptBuilder::new().user("Review this diff.").user("Focus on error handling.").build())
+        }));
+        rustmcp.add_prompt(FunctionPrompt::simple("echo", |_| {
+            Ok(vec![PromptMessage::user("Hello"), PromptMessage::user("again")])
+        }));
+
+        // 2025-06-18的会话收到内容数组
+        let response = get_with_version(&rustmcp, "2025-06-18", "review").await;
+        assert_eq!(response["result"]["messages"][0]["content"].as_array().unwrap().len(), 2);
+        // 更早的会话收到合并后的单个文本块
+        let response = get_with_version(&rustmcp, "2024-11-05", "review").await;
+        assert_eq!(
+            response["result"]["messages"][0]["content"],
+            json!({ "type": "text", "text": "Review this diff.\nFocus on error handling." })
+        );
+
+        // 连续两条用户消息在严格模式下被拒绝
+        let response = get_with_version(&rustmcp, "2025-06-18", "echo").await;
+        assert!(response["error"]["message"].as_str().unwrap().contains("both 'user' turns"));
+    }
+}
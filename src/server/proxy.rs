@@ -0,0 +1,147 @@
+//! 把工具调用转发到HTTP地址
+//!
+//! 绑定为[`ToolBinding::Remote`](crate::server::tools::ToolBinding::Remote)的工具把参数作为JSON请求体
+//! 发送到[`ProxyTarget`]，响应体为JSON时原样返回，否则作为字符串返回。地址和请求头的值可以引用密钥
+//! （`${env:NAME}`或`${file:/path}`），见[`secrets`](crate::server::secrets)，返回的错误中的密钥值会被脱敏。
+//! 请求通过共用的[出站HTTP客户端](crate::server::http_client)发送，需要`http-client`特性，否则调用返回错误。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::server::context::Context;
+use crate::server::secrets::SecretTemplate;
+
+/// 支持的请求方法
+const METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+/// 没有HTTP客户端时的错误
+const UNSUPPORTED: &str = "proxy backends require the 'http-client' feature";
+
+/// 解析过密钥引用的转发目标
+#[cfg_attr(not(feature = "http-client"), allow(dead_code))]
+struct Prepared {
+    method: String,
+    url: SecretTemplate,
+    headers: Vec<(String, SecretTemplate)>,
+}
+
+/// HTTP转发目标
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProxyTarget {
+    /// 目标地址
+    pub url: String,
+    /// 请求方法，默认为`POST`
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// 附加的请求头
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// 请求超时毫秒数，覆盖出站HTTP客户端配置的请求超时
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+impl ProxyTarget {
+    /// 创建以`POST`转发到`url`的目标
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            method: default_method(),
+            headers: HashMap::new(),
+            timeout_ms: None,
+        }
+    }
+
+    /// 设置请求方法
+    pub fn with_method(mut self, method: impl Into<String>) -> Self {
+        self.method = method.into();
+        self
+    }
+
+    /// 添加请求头
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// 设置这个目标的请求超时，覆盖出站HTTP客户端配置的请求超时
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout_ms = Some(u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX));
+        self
+    }
+
+    /// 检查地址、请求方法和密钥引用，引用的环境变量缺失或没有启用HTTP客户端时返回错误
+    pub fn check(&self) -> Result<(), String> {
+        self.prepare()?;
+        if !cfg!(feature = "http-client") {
+            return Err(UNSUPPORTED.to_string());
+        }
+        Ok(())
+    }
+
+    /// 解析地址和请求头中的密钥引用
+    fn prepare(&self) -> Result<Prepared, String> {
+        if self.url.is_empty() {
+            return Err("proxy.url must not be empty".to_string());
+        }
+        let method = self.method.to_uppercase();
+        if !METHODS.contains(&method.as_str()) {
+            return Err(format!("proxy.method '{}' is not supported", self.method));
+        }
+        let url = SecretTemplate::parse(&self.url)?;
+        let headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), SecretTemplate::parse(value)?)))
+            .collect::<Result<Vec<(String, SecretTemplate)>, String>>()?;
+        Ok(Prepared { method, url, headers })
+    }
+
+    /// 发送请求，`GET`以外的方法把参数作为JSON请求体，响应体为JSON时解析
+    pub(crate) fn call(&self, ctx: &Context, args: HashMap<String, Value>) -> Result<Value, String> {
+        let body = self.send(ctx, Value::Object(args.into_iter().collect()))?;
+        Ok(serde_json::from_str(&body).unwrap_or(Value::String(body)))
+    }
+
+    /// 发送请求，返回响应体
+    #[cfg(feature = "http-client")]
+    pub(crate) fn send(&self, ctx: &Context, body: Value) -> Result<String, String> {
+        use crate::server::http_client::HttpClient;
+
+        let Prepared { method, url, headers } = self.prepare()?;
+        let url = url.resolve()?;
+        let shared = ctx.state::<HttpClient>();
+        let client = shared.as_deref().unwrap_or_else(|| HttpClient::fallback());
+        let mut request = client.request(&method, &url, self.timeout_ms.map(Duration::from_millis));
+        for (name, value) in &headers {
+            request = request.set(name, &value.resolve()?);
+        }
+        let result = if method == "GET" {
+            request.call()
+        } else {
+            request.send_json(body)
+        };
+        let response = match result {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, response)) => {
+                let body = response.into_string().unwrap_or_default();
+                return Err(format!("{} returned {}: {}", url, status, body.trim()));
+            }
+            Err(e) => return Err(format!("Request to {} failed: {}", url, e)),
+        };
+        response.into_string().map_err(|e| format!("Failed to read response: {}", e))
+    }
+
+    /// 没有HTTP客户端时无法转发
+    #[cfg(not(feature = "http-client"))]
+    pub(crate) fn send(&self, _ctx: &Context, _body: Value) -> Result<String, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+}
@@ -0,0 +1,188 @@
+//! 工具和资源的用量配额
+//!
+//! 限流按调用方限制请求速率，配额限制一个工具或资源在所有会话中的总用量，例如
+//! "`send_email`每小时最多执行100次"。用[`FunctionToolBuilder::quota`](crate::FunctionToolBuilder::quota)、
+//! [`FunctionTool::with_quota`](crate::FunctionTool::with_quota)或
+//! [`FunctionResource::with_quota`](crate::FunctionResource::with_quota)设置：
+//! - 按滑动窗口计数：过去`window`内已放行的调用达到`max_calls`时拒绝新的调用；
+//! - 工具在参数校验通过后、执行前计数，参数错误的调用不占用配额，重试算作一次调用；
+//! - 资源在每次`resources/read`时计数，流式资源的一次读取只算一次，[探测](crate::server::probe)不计数；
+//! - 超出配额时返回`-32007`错误，`data`中有`limit`、`windowMs`、`retryAfterMs`和`resetAtMs`
+//!   （窗口中最早的一次调用过期、重新可以调用的Unix毫秒时间）；HTTP接口返回`429`；
+//! - 检查和记录在同一把锁中完成，并发调用不会超额放行。
+//!
+//! 配额的状态保存在工具或资源中，重新注册同名的工具或资源后从零开始计数。
+//! [`RustMCP::quota_status`](crate::RustMCP::quota_status)和管理接口的`GET /quotas`列出当前的用量。
+//!
+//! ```rust
+//! use rustmcp::{FunctionTool, RustMCP};
+//! use serde_json::json;
+//! use std::time::Duration;
+//!
+//! let rustmcp = RustMCP::new();
+//! rustmcp.add_tool(
+//!     FunctionTool::builder()
+//!         .name("send_email")
+//!         .handler(|_| Ok(json!("sent")))
+//!         .quota(100, Duration::from_secs(3600))
+//!         .build(),
+//! );
+//! ```
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::server::error::McpError;
+
+/// 一个工具或资源的用量配额，克隆的工具或资源共享同一份计数
+#[derive(Debug)]
+pub struct Quota {
+    max_calls: u32,
+    window: Duration,
+    /// 窗口内放行的调用时间，从旧到新排列
+    admitted: Mutex<VecDeque<Instant>>,
+}
+
+/// 配额的当前用量，参见[`RustMCP::quota_status`](crate::RustMCP::quota_status)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaStatus {
+    /// `tool`或`resource`
+    pub kind: &'static str,
+    /// 工具名称或资源URI
+    pub name: String,
+    /// 窗口内最多放行的调用数
+    pub limit: u32,
+    /// 窗口长度（毫秒）
+    pub window_ms: u64,
+    /// 窗口内已放行的调用数
+    pub used: u32,
+    /// 还可以放行的调用数
+    pub remaining: u32,
+    /// 窗口中最早的一次调用过期的Unix毫秒时间，窗口内没有调用时为`None`
+    pub reset_at_ms: Option<u64>,
+}
+
+impl Quota {
+    /// 每`window`最多放行`max_calls`次调用，`max_calls`为0时拒绝所有调用
+    pub fn new(max_calls: u32, window: Duration) -> Self {
+        Self {
+            max_calls,
+            window,
+            admitted: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// 窗口内最多放行的调用数
+    pub fn max_calls(&self) -> u32 {
+        self.max_calls
+    }
+
+    /// 窗口长度
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// 放行一次调用，超出配额时返回[`McpError::QuotaExceeded`]
+    pub(crate) fn admit(&self, kind: &str, name: &str) -> Result<(), McpError> {
+        let now = Instant::now();
+        let mut admitted = self.admitted.lock();
+        self.expire(&mut admitted, now);
+        if admitted.len() < self.max_calls as usize {
+            admitted.push_back(now);
+            return Ok(());
+        }
+        // 配额为0时没有可以过期的调用，按一个完整的窗口计算
+        let reset_after = admitted.front().map_or(self.window, |oldest| (*oldest + self.window).saturating_duration_since(now));
+        let retry_after_ms = u64::try_from(reset_after.as_millis()).unwrap_or(u64::MAX).max(1);
+        Err(McpError::QuotaExceeded {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            limit: self.max_calls,
+            window_ms: u64::try_from(self.window.as_millis()).unwrap_or(u64::MAX),
+            retry_after_ms,
+            reset_at_ms: unix_ms().saturating_add(retry_after_ms),
+        })
+    }
+
+    /// 当前用量
+    pub(crate) fn status(&self, kind: &'static str, name: &str) -> QuotaStatus {
+        let now = Instant::now();
+        let mut admitted = self.admitted.lock();
+        self.expire(&mut admitted, now);
+        let used = admitted.len() as u32;
+        QuotaStatus {
+            kind,
+            name: name.to_string(),
+            limit: self.max_calls,
+            window_ms: u64::try_from(self.window.as_millis()).unwrap_or(u64::MAX),
+            used,
+            remaining: self.max_calls.saturating_sub(used),
+            reset_at_ms: admitted.front().map(|oldest| {
+                let reset_after = (*oldest + self.window).saturating_duration_since(now);
+                unix_ms().saturating_add(u64::try_from(reset_after.as_millis()).unwrap_or(u64::MAX))
+            }),
+        }
+    }
+
+    /// 移除窗口之外的调用
+    fn expire(&self, admitted: &mut VecDeque<Instant>, now: Instant) {
+        while admitted.front().is_some_and(|oldest| now.saturating_duration_since(*oldest) >= self.window) {
+            admitted.pop_front();
+        }
+    }
+}
+
+/// 当前的Unix毫秒时间
+fn unix_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{FunctionResource, FunctionTool, RustMCP};
+
+    #[tokio::test]
+    async fn tools_and_resources_are_limited_per_window() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("send_email")
+                .handler(|_| Ok(json!("sent")))
+                .quota(3, Duration::from_millis(300))
+                .build(),
+        );
+        rustmcp.add_resource(FunctionResource::simple("data://report", || Ok(json!("ok"))).with_quota(1, Duration::from_secs(60)));
+
+        for _ in 0..3 {
+            rustmcp.mcp_call_tool("send_email", None).await.unwrap();
+        }
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+        let error = rustmcp.mcp_call_tool("send_email", None).await.unwrap_err();
+        assert_eq!(error.code(), -32007);
+        let data = error.data();
+        assert_eq!((data["kind"].as_str(), data["name"].as_str(), data["limit"].as_u64()), (Some("quota_exceeded"), Some("send_email"), Some(3)));
+        let retry_after_ms = data["retryAfterMs"].as_u64().unwrap();
+        assert!(retry_after_ms > 0 && retry_after_ms <= 300);
+        assert!(data["resetAtMs"].as_u64().unwrap() >= now_ms);
+
+        let status = rustmcp.quota_status();
+        let email = status.iter().find(|quota| quota.name == "send_email").unwrap();
+        assert_eq!((email.used, email.remaining), (3, 0));
+        assert!(email.reset_at_ms.is_some());
+
+        // 窗口过去后重新放行
+        tokio::time::sleep(Duration::from_millis(retry_after_ms + 20)).await;
+        assert_eq!(rustmcp.mcp_call_tool("send_email", None).await.unwrap(), json!("sent"));
+
+        // 资源同样计数
+        rustmcp.mcp_read_resource("data://report").unwrap();
+        let error = rustmcp.mcp_read_resource("data://report").unwrap_err();
+        assert_eq!((error.code(), error.data()["itemKind"].as_str()), (-32007, Some("resource")));
+    }
+}
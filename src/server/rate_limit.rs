@@ -0,0 +1,180 @@
+//! 请求限流
+//!
+//! 使用令牌桶算法，按调用方（会话ID或远端IP）和方法分别计数。
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::settings::{RateLimit, RateLimitSettings};
+
+/// 超过该数量的令牌桶时清理已经补满的桶
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// 令牌桶
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: &RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: limit.burst as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.requests_per_second).min(limit.burst as f64);
+        self.last_refill = now;
+    }
+
+    /// 尝试取出一个令牌，失败时返回需要等待的时间
+    fn try_take(&mut self, limit: &RateLimit, now: Instant) -> Result<(), Duration> {
+        self.refill(limit, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            // 速率极小（或为0）时等待时间超出Duration的范围，取最大值
+            let wait = (1.0 - self.tokens) / limit.requests_per_second;
+            Err(Duration::try_from_secs_f64(wait).unwrap_or(Duration::MAX))
+        }
+    }
+}
+
+/// 请求限流器
+#[derive(Debug)]
+pub struct RateLimiter {
+    settings: RateLimitSettings,
+    buckets: Mutex<HashMap<(String, String), TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// 根据设置创建限流器
+    pub fn new(settings: RateLimitSettings) -> Self {
+        Self {
+            settings,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 检查调用方对指定方法的请求是否允许，超限时返回建议的重试等待时间
+    pub fn check(&self, key: &str, method: &str) -> Result<(), Duration> {
+        // 单独配置的方法使用独立的桶，其余方法共享默认桶
+        let (bucket_name, limit) = match self.settings.methods.get(method) {
+            Some(limit) => (method, limit),
+            None => ("", &self.settings.default),
+        };
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > PRUNE_THRESHOLD {
+            self.prune(&mut buckets, now);
+        }
+        buckets
+            .entry((key.to_string(), bucket_name.to_string()))
+            .or_insert_with(|| TokenBucket::new(limit, now))
+            .try_take(limit, now)
+    }
+
+    /// 清理已经补满的桶，这些桶与新建的桶没有区别
+    fn prune(&self, buckets: &mut HashMap<(String, String), TokenBucket>, now: Instant) {
+        buckets.retain(|(_, bucket_name), bucket| {
+            let limit = self.settings.methods.get(bucket_name).unwrap_or(&self.settings.default);
+            bucket.refill(limit, now);
+            bucket.tokens < limit.burst as f64
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use crate::server::test_support::{call, post};
+    use crate::server::{create_app, FunctionTool, RustMCP};
+    use crate::settings::Settings;
+
+    /// 每20ms补充一个令牌，容量为2
+    fn limits() -> RateLimitSettings {
+        RateLimitSettings::new(RateLimit::new(1000.0, 100)).with_method_limit("tools/call", RateLimit::new(50.0, 2))
+    }
+
+    fn app() -> axum::Router {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_rate_limit(limits()));
+        rustmcp.add_tool(FunctionTool::simple("echo", "Echoes", |_| Ok(json!("ok"))));
+        create_app(rustmcp)
+    }
+
+    #[test]
+    fn bucket_allows_burst_then_refills() {
+        let limiter = RateLimiter::new(limits());
+        assert!(limiter.check("a", "tools/call").is_ok());
+        assert!(limiter.check("a", "tools/call").is_ok());
+        let wait = limiter.check("a", "tools/call").unwrap_err();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_millis(20), "{:?}", wait);
+        // 其他调用方和默认桶不受影响
+        assert!(limiter.check("b", "tools/call").is_ok());
+        assert!(limiter.check("a", "tools/list").is_ok());
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(limiter.check("a", "tools/call").is_ok());
+    }
+
+    #[test]
+    fn tiny_or_zero_rates_do_not_overflow() {
+        let limiter = RateLimiter::new(
+            RateLimitSettings::new(RateLimit::new(1e-300, 1)).with_method_limit("tools/call", RateLimit::new(0.0, 1)),
+        );
+        for method in ["tools/list", "tools/call"] {
+            assert!(limiter.check("a", method).is_ok());
+            assert_eq!(limiter.check("a", method), Err(Duration::MAX));
+        }
+    }
+
+    #[tokio::test]
+    async fn jsonrpc_calls_past_the_limit_are_rejected_then_recover() {
+        let app = app();
+        let params = json!({ "name": "echo" });
+        for _ in 0..2 {
+            assert!(call(&app, "tools/call", params.clone()).await["result"].is_object());
+        }
+        let response = call(&app, "tools/call", params.clone()).await;
+        assert_eq!(response["error"]["code"], -32005);
+        assert_eq!(response["error"]["data"]["kind"], "rate_limited");
+        assert!(response["error"]["data"]["retryAfterMs"].as_u64().unwrap() >= 1);
+        // 列表方法使用单独的桶
+        assert!(call(&app, "tools/list", json!({})).await["result"].is_object());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(call(&app, "tools/call", params).await["result"].is_object());
+    }
+
+    #[tokio::test]
+    async fn rest_tool_calls_share_the_tools_call_bucket() {
+        let app = app();
+        let (status, _, _) = post(&app, "/mcp/call-tool", &[], &json!({ "name": "echo" })).await;
+        assert_eq!(status, 200);
+        let (status, _, _) = post(&app, "/mcp/tools/echo/call", &[], &json!({})).await;
+        assert_eq!(status, 200);
+        let (status, _, body) = post(&app, "/mcp/call-tool", &[], &json!({ "name": "echo" })).await;
+        assert_eq!(status, 429);
+        assert_eq!(body, "Rate limited");
+        let (status, _, body) = post(&app, "/mcp/call-tool?format=mcp", &[], &json!({ "name": "echo" })).await;
+        assert_eq!(status, 429);
+        assert_eq!(body["error"]["code"], -32005);
+        let (status, _, _) = post(&app, "/mcp/tools/echo/call", &[], &json!({})).await;
+        assert_eq!(status, 429);
+        // JSON-RPC与REST共用同一个桶
+        assert_eq!(call(&app, "tools/call", json!({ "name": "echo" })).await["error"]["code"], -32005);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let (status, _, _) = post(&app, "/mcp/tools/echo/call", &[], &json!({})).await;
+        assert_eq!(status, 200);
+    }
+}
@@ -0,0 +1,187 @@
+//! 按URI前缀动态解析的资源
+//!
+//! 后端的资源太多、无法逐个注册时（例如`db://table/{rowid}`有上百万行），可以用
+//! [`RustMCP::add_resource_resolver`](crate::RustMCP::add_resource_resolver)为一个URI前缀注册解析器：
+//! - `resources/read`先查找按URI注册的资源，找不到时依次询问前缀匹配的解析器，最长的前缀优先；
+//! - 解析器返回`None`时继续询问前缀更短的解析器，都没有结果时返回资源不存在；
+//! - `resources/list`不列出解析出的资源，每个解析器在`resources/templates/list`中显示为一个模板
+//!   `<prefix>{+path}`，客户端据此知道这一组URI存在。
+//!
+//! ```rust
+//! use rustmcp::{FunctionResource, RustMCP};
+//! use serde_json::json;
+//!
+//! let rustmcp = RustMCP::new();
+//! rustmcp.add_resource_resolver("db://", |uri: &str| {
+//!     let table = uri.strip_prefix("db://")?.split('/').next()?.to_string();
+//!     Some(FunctionResource::simple(uri, move || Ok(json!(format!("table {}", table)))))
+//! });
+//! ```
+
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::server::resources::FunctionResource;
+
+/// 按URI解析资源
+///
+/// 对`Fn(&str) -> Option<FunctionResource>`自动实现。解析器在读取资源时同步调用，
+/// 不能解析的URI返回`None`。
+pub trait ResourceResolver: Send + Sync {
+    /// 解析URI对应的资源
+    fn resolve(&self, uri: &str) -> Option<FunctionResource>;
+}
+
+impl<F> ResourceResolver for F
+where
+    F: Fn(&str) -> Option<FunctionResource> + Send + Sync,
+{
+    fn resolve(&self, uri: &str) -> Option<FunctionResource> {
+        self(uri)
+    }
+}
+
+/// `resources/templates/list`中的资源模板
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceTemplate {
+    /// URI模板（RFC 6570）
+    pub uri_template: String,
+    /// 模板名称
+    pub name: String,
+    /// 人类可读的标题
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// 描述
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 解析出的资源的MIME类型
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// 注册解析器的URI前缀，不序列化
+    #[serde(skip)]
+    prefix: String,
+}
+
+impl ResourceTemplate {
+    /// 为URI前缀创建模板，URI模板为`<prefix>{+path}`
+    pub fn new(prefix: impl Into<String>, name: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        Self {
+            uri_template: format!("{}{{+path}}", prefix),
+            name: name.into(),
+            title: None,
+            description: None,
+            mime_type: None,
+            prefix,
+        }
+    }
+
+    /// 设置标题
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// 设置描述
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// 设置MIME类型
+    pub fn with_mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.mime_type = Some(mime_type.into());
+        self
+    }
+
+    /// 注册解析器的URI前缀
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+}
+
+impl From<&str> for ResourceTemplate {
+    fn from(prefix: &str) -> Self {
+        Self::new(prefix, prefix)
+    }
+}
+
+impl From<String> for ResourceTemplate {
+    fn from(prefix: String) -> Self {
+        Self::new(prefix.clone(), prefix)
+    }
+}
+
+/// 按前缀注册的解析器
+#[derive(Clone)]
+pub(crate) struct Resolver {
+    pub(crate) template: ResourceTemplate,
+    pub(crate) resolver: Arc<dyn ResourceResolver>,
+}
+
+impl std::fmt::Debug for Resolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Resolver").field("prefix", &self.template.prefix).finish_non_exhaustive()
+    }
+}
+
+/// 前缀匹配`uri`的解析器，最长的前缀在前
+pub(crate) fn matching(resolvers: &[Resolver], uri: &str) -> Vec<Resolver> {
+    let mut matching: Vec<Resolver> = resolvers.iter().filter(|r| uri.starts_with(&r.template.prefix)).cloned().collect();
+    matching.sort_by_key(|r| std::cmp::Reverse(r.template.prefix.len()));
+    matching
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::server::test_support::{request, stdio};
+    use crate::{FunctionResource, ResourceTemplate, RustMCP};
+
+    #[tokio::test]
+    async fn longest_prefix_resolves_first_and_falls_back() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_resource_resolver("db://", |uri: &str| {
+            let table = uri.strip_prefix("db://")?.split('/').next()?.to_string();
+            Some(FunctionResource::simple(uri, move || Ok(json!(format!("table {}", table)))))
+        });
+        // 更长的前缀优先；只认识数字行号，其他URI交给更短的前缀
+        rustmcp.add_resource_resolver(
+            ResourceTemplate::new("db://users/", "users").with_description("One user per row"),
+            |uri: &str| {
+                let id: u64 = uri.strip_prefix("db://users/")?.parse().ok()?;
+                Some(FunctionResource::simple(uri, move || Ok(json!(format!("user {}", id)))))
+            },
+        );
+
+        let read = |id: u64, uri: &str| request(id, "resources/read", json!({ "uri": uri }));
+        let responses = stdio(&rustmcp, &[
+            request(1, "initialize", json!({})),
+            read(2, "db://users/42"),
+            read(3, "db://users/admin"),
+            read(4, "db://orders/7"),
+            read(5, "file://missing"),
+            request(6, "resources/templates/list", json!({})),
+            request(7, "resources/list", json!({})),
+        ])
+        .await;
+        let response = |id: u64| responses.iter().find(|r| r["id"] == id).unwrap().clone();
+        let text = |id: u64| response(id)["result"]["contents"][0]["text"].as_str().unwrap().to_string();
+
+        assert_eq!(text(2), "user 42");
+        assert_eq!(response(2)["result"]["contents"][0]["uri"], "db://users/42");
+        // users解析器返回None，交给db://
+        assert_eq!(text(3), "table users");
+        assert_eq!(text(4), "table orders");
+        // 没有匹配的前缀
+        assert_eq!(response(5)["error"]["data"]["kind"], "resource_not_found");
+
+        let templates = response(6)["result"]["resourceTemplates"].clone();
+        assert_eq!(templates[0]["uriTemplate"], "db://{+path}");
+        assert_eq!(templates[1]["uriTemplate"], "db://users/{+path}");
+        assert_eq!(templates[1]["description"], "One user per row");
+        assert_eq!(response(7)["result"]["resources"], json!([]));
+    }
+}
@@ -1,9 +1,230 @@
+use axum::body::Bytes;
+use base64::Engine;
+use futures::stream::{BoxStream, Stream, StreamExt};
 use serde::{Deserialize, Serialize, Serializer};
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use parking_lot::RwLock;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 use serde_json::Value;
 use log::warn;
 
+use crate::server::Context;
+use crate::server::error::{suggest, McpError};
+use crate::server::locale::{LanguageTag, Localizations};
+use crate::server::notifications::Notifier;
+use crate::server::probe;
+use crate::server::proxy::ProxyTarget;
+use crate::server::quota::{Quota, QuotaStatus};
+use crate::server::loader::{Loader, ResourceLoader};
+use crate::server::resolvers::{self, ResourceResolver, ResourceTemplate, Resolver};
+use crate::server::secrets;
+use crate::server::serialization::{serialize_items, SerializationPolicy};
+use crate::server::validation::type_name;
+
+/// 资源函数类型定义
+pub type ResourceFunction = Arc<dyn Fn(&Context) -> Result<Value, String> + Send + Sync>;
+
+/// 资源内容的字节流
+pub type ResourceStream = BoxStream<'static, Result<Bytes, String>>;
+
+/// 流式资源函数类型定义
+pub type ResourceStreamFunction = Arc<dyn Fn(&Context) -> ResourceStream + Send + Sync>;
+
+/// 打开资源得到的内容
+pub(crate) enum ResourceBody {
+    /// 按MIME类型渲染后的内容
+    Contents(ResourceContents),
+    /// 流式资源的字节流
+    Stream(ResourceStream),
+}
+
+/// `resources/read`返回的资源内容
+///
+/// 资源函数返回的值按资源的MIME类型渲染：
+/// - `application/json`（以及`+json`后缀）：字符串视为已经序列化的JSON原样返回，其他值序列化为JSON文本，
+///   是否缩进由[`Settings::resource_json_pretty`](crate::Settings::resource_json_pretty)决定；
+/// - `text/*`：必须返回字符串；
+/// - 其他类型：必须返回base64编码的字符串，作为`blob`返回。
+///
+/// 返回值与MIME类型不符时读取失败，错误中包含资源URI。
+///
+/// ```rust
+/// use rustmcp::{FunctionResource, RustMCP};
+/// use serde_json::json;
+///
+/// let rustmcp = RustMCP::new();
+/// rustmcp.add_resource(FunctionResource::from_function(
+///     || Ok(json!({"b": 1, "a": [true]})), "data://config".into(), None, None, Some("application/json".into()), None, None, None,
+/// ));
+/// assert_eq!(rustmcp.mcp_read_resource("data://config").unwrap().text(), Some(r#"{"a":[true],"b":1}"#));
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ResourceContents {
+    /// 文本内容
+    Text {
+        uri: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+        text: String,
+    },
+    /// 二进制内容（base64编码）
+    Blob {
+        uri: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+        blob: String,
+    },
+}
+
+impl ResourceContents {
+    /// 资源URI
+    pub fn uri(&self) -> &str {
+        match self {
+            ResourceContents::Text { uri, .. } | ResourceContents::Blob { uri, .. } => uri,
+        }
+    }
+
+    /// MIME类型
+    pub fn mime_type(&self) -> &str {
+        match self {
+            ResourceContents::Text { mime_type, .. } | ResourceContents::Blob { mime_type, .. } => mime_type,
+        }
+    }
+
+    /// 文本内容，二进制内容返回`None`
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            ResourceContents::Text { text, .. } => Some(text),
+            ResourceContents::Blob { .. } => None,
+        }
+    }
+}
+
+/// MIME类型对应的内容格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MimeKind {
+    Json,
+    Text,
+    Binary,
+}
+
+impl MimeKind {
+    /// 按MIME类型（忽略参数）判断，没有MIME类型时按文本处理
+    fn of(mime_type: &str) -> Self {
+        let essence = mime_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+        if essence == "application/json" || essence.ends_with("+json") {
+            MimeKind::Json
+        } else if essence.is_empty() || essence.starts_with("text/") {
+            MimeKind::Text
+        } else {
+            MimeKind::Binary
+        }
+    }
+}
+
+/// 内容面向的角色
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+/// 资源注解
+///
+/// `audience`、`priority`和`lastModified`是MCP规范定义、客户端会解读的字段；
+/// 其他自定义字段放在`extra`中，序列化时合并到同一个JSON对象。
+///
+/// ```rust
+/// use rustmcp::{ResourceAnnotations, Role};
+///
+/// let annotations = ResourceAnnotations::new()
+///     .audience(vec![Role::User, Role::Assistant])
+///     .priority(0.8)
+///     .last_modified("2025-01-12T15:00:58Z");
+/// assert_eq!(
+///     serde_json::to_value(&annotations).unwrap(),
+///     serde_json::json!({
+///         "audience": ["user", "assistant"],
+///         "priority": 0.8,
+///         "lastModified": "2025-01-12T15:00:58Z"
+///     })
+/// );
+///
+/// let annotations = ResourceAnnotations::new()
+///     .audience(vec![Role::User])
+///     .extra("source", serde_json::json!("wiki"));
+/// assert_eq!(
+///     serde_json::to_value(&annotations).unwrap(),
+///     serde_json::json!({ "audience": ["user"], "source": "wiki" })
+/// );
+/// assert_eq!(ResourceAnnotations::new().priority(1.5).validate().unwrap_err(), "priority must be between 0 and 1, got 1.5");
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceAnnotations {
+    /// 内容面向的角色
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audience: Option<Vec<Role>>,
+    /// 重要程度，0表示可有可无，1表示必不可少
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<f64>,
+    /// 最后修改时间（ISO 8601）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// 规范之外的自定义字段
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl ResourceAnnotations {
+    /// 创建空注解
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置面向的角色
+    pub fn audience(mut self, audience: Vec<Role>) -> Self {
+        self.audience = Some(audience);
+        self
+    }
+
+    /// 设置重要程度，应在0到1之间
+    pub fn priority(mut self, priority: f64) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// 设置最后修改时间
+    pub fn last_modified(mut self, last_modified: impl Into<String>) -> Self {
+        self.last_modified = Some(last_modified.into());
+        self
+    }
+
+    /// 添加自定义字段
+    pub fn extra(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.extra.insert(key.into(), value);
+        self
+    }
+
+    /// 是否没有任何字段
+    pub fn is_empty(&self) -> bool {
+        self.audience.is_none() && self.priority.is_none() && self.last_modified.is_none() && self.extra.is_empty()
+    }
+
+    /// 检查`priority`是否在0到1之间
+    pub fn validate(&self) -> Result<(), String> {
+        match self.priority {
+            Some(priority) if !(0.0..=1.0).contains(&priority) => {
+                Err(format!("priority must be between 0 and 1, got {}", priority))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
 /// 资源定义
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Resource {
@@ -13,6 +234,10 @@ pub struct Resource {
     /// 资源名称
     pub name: String,
     
+    /// 资源的人类可读标题
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    
     /// 资源描述
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
@@ -27,10 +252,10 @@ pub struct Resource {
     
     /// 注解
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub annotations: Option<HashMap<String, Value>>,
+    pub annotations: Option<ResourceAnnotations>,
     
     /// 元数据
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
     pub meta: Option<HashMap<String, Value>>,
 }
 
@@ -38,7 +263,7 @@ pub struct Resource {
 #[derive(Clone)]
 pub struct FunctionResource {
     /// 资源函数
-    pub function: Arc<dyn Fn() -> Result<Value, String> + Send + Sync>,
+    pub function: ResourceFunction,
     
     /// 资源URI
     pub uri: String,
@@ -46,6 +271,9 @@ pub struct FunctionResource {
     /// 资源名称
     pub name: String,
     
+    /// 资源的人类可读标题
+    pub title: Option<String>,
+    
     /// 资源描述
     pub description: String,
     
@@ -56,10 +284,19 @@ pub struct FunctionResource {
     pub tags: Vec<String>,
     
     /// 注解
-    pub annotations: HashMap<String, Value>,
+    pub annotations: ResourceAnnotations,
     
     /// 元数据
     pub meta: Option<HashMap<String, Value>>,
+    
+    /// 流式资源函数，设置后按块读取内容，参见[`streaming`](crate::server::streaming)
+    pub stream: Option<ResourceStreamFunction>,
+    
+    /// 本地化的标题和描述，序列化在`_meta.localizations`中
+    pub localizations: Localizations,
+    
+    /// 用量配额，参见[`quota`](crate::server::quota)
+    pub quota: Option<Arc<Quota>>,
 }
 
 impl FunctionResource {
@@ -82,27 +319,225 @@ impl FunctionResource {
         description: Option<String>,
         mime_type: Option<String>,
         tags: Option<Vec<String>>,
-        annotations: Option<HashMap<String, Value>>,
+        annotations: Option<ResourceAnnotations>,
         meta: Option<HashMap<String, Value>>,
     ) -> Self
     where
         F: Fn() -> Result<Value, String> + Send + Sync + 'static,
+    {
+        Self::from_function_with_context(
+            move |_ctx: &Context| function(),
+            uri,
+            name,
+            description,
+            mime_type,
+            tags,
+            annotations,
+            meta,
+        )
+    }
+
+    /// 从接收上下文的函数创建资源
+    ///
+    /// 参数与 [`FunctionResource::from_function`] 相同，区别在于函数接收当前请求的 [`Context`]。
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_function_with_context<F>(
+        function: F,
+        uri: String,
+        name: Option<String>,
+        description: Option<String>,
+        mime_type: Option<String>,
+        tags: Option<Vec<String>>,
+        annotations: Option<ResourceAnnotations>,
+        meta: Option<HashMap<String, Value>>,
+    ) -> Self
+    where
+        F: Fn(&Context) -> Result<Value, String> + Send + Sync + 'static,
     {
         Self {
             function: Arc::new(function),
-            uri: uri.clone(),
+            uri,
             name: name.unwrap_or_else(|| "unnamed_resource".to_string()),
+            title: None,
             description: description.unwrap_or_default(),
             mime_type: mime_type.unwrap_or_else(|| "text/plain".to_string()),
             tags: tags.unwrap_or_default(),
             annotations: annotations.unwrap_or_default(),
             meta,
+            stream: None,
+            localizations: Localizations::default(),
+            quota: None,
         }
     }
     
+    /// 从返回字节流的函数创建流式资源，名称取URI
+    ///
+    /// 内容较大时按块发送，内存占用与资源大小无关，参见[`streaming`](crate::server::streaming)。
+    /// 同步的[`FunctionResource::read`]不能读取流式资源，会返回错误。
+    pub fn from_stream_fn<F, S>(uri: impl Into<String>, mime_type: impl Into<String>, function: F) -> Self
+    where
+        F: Fn(&Context) -> S + Send + Sync + 'static,
+        S: Stream<Item = Result<Bytes, String>> + Send + 'static,
+    {
+        let uri = uri.into();
+        let message = format!("Resource '{}' is streamed and cannot be read synchronously", uri);
+        let mut resource = Self::from_function(
+            move || Err(message.clone()),
+            uri.clone(),
+            Some(uri),
+            None,
+            Some(mime_type.into()),
+            None,
+            None,
+            None,
+        );
+        resource.stream = Some(Arc::new(move |ctx: &Context| function(ctx).boxed()));
+        resource
+    }
+    
+    /// 创建读取时向`url`发起`GET`请求的资源，内容为响应体，名称取URI，MIME类型为`text/plain`
+    ///
+    /// 请求通过共用的[出站HTTP客户端](crate::server::http_client)发送，需要`http-client`特性。
+    /// 地址可以引用密钥（`${env:NAME}`），错误中的密钥值会被脱敏，参见[`secrets`](crate::server::secrets)。
+    pub fn from_url(uri: impl Into<String>, url: impl Into<String>) -> Self {
+        let uri = uri.into();
+        let target = ProxyTarget::new(url).with_method("GET");
+        Self::from_function_with_context(
+            move |ctx: &Context| target.send(ctx, Value::Null).map(Value::String).map_err(|e| secrets::redact(&e)),
+            uri.clone(),
+            Some(uri),
+            None,
+            Some("text/plain".to_string()),
+            None,
+            None,
+            None,
+        )
+    }
+    
+    /// 使用URI和函数创建资源，名称取URI，MIME类型为`text/plain`
+    pub fn simple<F>(uri: impl Into<String>, function: F) -> Self
+    where
+        F: Fn() -> Result<Value, String> + Send + Sync + 'static,
+    {
+        let uri = uri.into();
+        Self::from_function(function, uri.clone(), Some(uri), None, Some("text/plain".to_string()), None, None, None)
+    }
+    
+    /// 设置资源标题
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+    
+    /// 设置某种语言的标题，参见[`locale`](crate::server::locale)
+    pub fn with_title_localized(mut self, lang: impl Into<LanguageTag>, title: impl Into<String>) -> Self {
+        self.localizations.titles.insert(lang.into(), title.into());
+        self
+    }
+    
+    /// 设置某种语言的描述，参见[`locale`](crate::server::locale)
+    pub fn with_description_localized(mut self, lang: impl Into<LanguageTag>, description: impl Into<String>) -> Self {
+        self.localizations.descriptions.insert(lang.into(), description.into());
+        self
+    }
+    
+    /// 设置资源注解
+    pub fn with_annotations(mut self, annotations: ResourceAnnotations) -> Self {
+        self.annotations = annotations;
+        self
+    }
+    
+    /// 设置资源面向的角色
+    pub fn with_audience(mut self, audience: Vec<Role>) -> Self {
+        self.annotations.audience = Some(audience);
+        self
+    }
+    
+    /// 设置资源的重要程度，应在0到1之间，超出范围时启动校验报告错误
+    pub fn with_priority(mut self, priority: f64) -> Self {
+        self.annotations.priority = Some(priority);
+        self
+    }
+    
+    /// 设置资源的最后修改时间（ISO 8601）
+    pub fn with_last_modified(mut self, last_modified: impl Into<String>) -> Self {
+        self.annotations.last_modified = Some(last_modified.into());
+        self
+    }
+    
+    /// 设置资源元数据
+    pub fn with_meta(mut self, meta: HashMap<String, Value>) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+    
+    /// 每`window`最多读取`max_calls`次，参见[`quota`](crate::server::quota)
+    pub fn with_quota(mut self, max_calls: u32, window: Duration) -> Self {
+        self.quota = Some(Arc::new(Quota::new(max_calls, window)));
+        self
+    }
+    
     /// 读取资源
-    pub fn read(&self) -> Result<Value, String> {
-        (self.function)()
+    pub fn read(&self, ctx: &Context) -> Result<Value, String> {
+        (self.function)(ctx)
+    }
+    
+    /// 读取资源并按MIME类型渲染，参见[`ResourceContents`]
+    pub fn read_contents(&self, ctx: &Context, pretty_json: bool) -> Result<ResourceContents, String> {
+        let value = self.read(ctx)?;
+        let mismatch = |expected: &str| {
+            format!(
+                "Resource '{}' has MIME type '{}' and must return {}, but returned {}",
+                self.uri,
+                self.mime_type,
+                expected,
+                article(type_name(&value))
+            )
+        };
+        let uri = self.uri.clone();
+        let mime_type = self.mime_type.clone();
+        match (MimeKind::of(&self.mime_type), &value) {
+            (MimeKind::Json, Value::String(text)) => Ok(ResourceContents::Text { uri, mime_type, text: text.clone() }),
+            (MimeKind::Json, value) => {
+                let text = if pretty_json { serde_json::to_string_pretty(value) } else { serde_json::to_string(value) };
+                Ok(ResourceContents::Text { uri, mime_type, text: text.map_err(|e| e.to_string())? })
+            }
+            (MimeKind::Text, Value::String(text)) => Ok(ResourceContents::Text { uri, mime_type, text: text.clone() }),
+            (MimeKind::Text, _) => Err(mismatch("a string")),
+            (MimeKind::Binary, Value::String(blob)) if base64::engine::general_purpose::STANDARD.decode(blob).is_ok() => {
+                Ok(ResourceContents::Blob { uri, mime_type, blob: blob.clone() })
+            }
+            (MimeKind::Binary, _) => Err(mismatch("a base64-encoded string")),
+        }
+    }
+    
+    /// 打开资源，流式资源返回字节流，其他资源读取并渲染内容
+    pub(crate) fn open(&self, ctx: &Context, pretty_json: bool) -> Result<ResourceBody, String> {
+        match &self.stream {
+            Some(stream) => Ok(ResourceBody::Stream(stream(ctx))),
+            None => self.read_contents(ctx, pretty_json).map(ResourceBody::Contents),
+        }
+    }
+    
+    /// 转换为不含函数的资源定义
+    pub fn to_resource(&self) -> Resource {
+        Resource {
+            uri: self.uri.clone(),
+            name: self.name.clone(),
+            title: self.title.clone(),
+            description: if self.description.is_empty() { None } else { Some(self.description.clone()) },
+            mime_type: if self.mime_type.is_empty() { None } else { Some(self.mime_type.clone()) },
+            tags: if self.tags.is_empty() { None } else { Some(self.tags.clone()) },
+            annotations: if self.annotations.is_empty() { None } else { Some(self.annotations.clone()) },
+            meta: match self.localizations.to_meta() {
+                Some(localizations) => {
+                    let mut meta = self.meta.clone().unwrap_or_default();
+                    meta.insert("localizations".to_string(), localizations);
+                    Some(meta)
+                }
+                None => self.meta.clone(),
+            },
+        }
     }
 }
 
@@ -112,16 +547,7 @@ impl Serialize for FunctionResource {
         S: Serializer,
     {
         // 只序列化Resource部分，不包括函数
-        let resource = Resource {
-            uri: self.uri.clone(),
-            name: self.name.clone(),
-            description: if self.description.is_empty() { None } else { Some(self.description.clone()) },
-            mime_type: if self.mime_type.is_empty() { None } else { Some(self.mime_type.clone()) },
-            tags: if self.tags.is_empty() { None } else { Some(self.tags.clone()) },
-            annotations: if self.annotations.is_empty() { None } else { Some(self.annotations.clone()) },
-            meta: self.meta.clone(),
-        };
-        resource.serialize(serializer)
+        self.to_resource().serialize(serializer)
     }
 }
 
@@ -130,15 +556,48 @@ impl std::fmt::Debug for FunctionResource {
         f.debug_struct("FunctionResource")
             .field("uri", &self.uri)
             .field("name", &self.name)
+            .field("title", &self.title)
             .field("description", &self.description)
             .field("mime_type", &self.mime_type)
             .field("tags", &self.tags)
             .field("annotations", &self.annotations)
             .field("meta", &self.meta)
+            .field("stream", &self.stream.is_some())
+            .field("localizations", &self.localizations)
+            .field("quota", &self.quota)
             .finish()
     }
 }
 
+/// 处理函数在调用期间注册资源的入口，参见[`Context::register_resource`]
+#[derive(Debug, Clone)]
+pub(crate) struct ResourceRegistrar {
+    manager: ResourceManager,
+    notifier: Notifier,
+}
+
+impl ResourceRegistrar {
+    pub fn new(manager: ResourceManager, notifier: Notifier) -> Self {
+        Self { manager, notifier }
+    }
+
+    /// 注册资源并发送列表变更通知，返回资源URI
+    pub fn add(&self, resource: FunctionResource) -> Result<String, String> {
+        let uri = resource.uri.clone();
+        if self.manager.try_add_resource(resource)? {
+            self.notifier.resources_list_changed();
+        }
+        Ok(uri)
+    }
+
+    /// 删除资源，资源存在时发送列表变更通知
+    pub fn remove(&self, uri: &str) {
+        if self.manager.remove_resource(uri) {
+            self.notifier.resources_list_changed();
+        }
+    }
+}
+
 /// 重复资源处理行为
 #[derive(Debug, Clone)]
 pub enum DuplicateBehavior {
@@ -148,28 +607,111 @@ pub enum DuplicateBehavior {
     Ignore,
 }
 
+/// 资源表和列表结果的缓存
+#[derive(Debug, Default)]
+struct ResourceTable {
+    /// 按URI分组的资源，同一URI的不同表示（MIME类型）按注册顺序排列
+    resources: HashMap<String, Vec<Arc<FunctionResource>>>,
+    /// 序列化后的资源列表（或序列化错误），第一次列出时生成，资源表变化时清空
+    listing: OnceLock<Result<Arc<Value>, McpError>>,
+    /// 修订号，资源表每次变化时加一
+    revision: u64,
+    /// 按URI前缀注册的解析器
+    resolvers: Vec<Resolver>,
+    /// 每个URI连续探测失败的次数
+    probe_failures: HashMap<String, u32>,
+    /// 连续探测失败而被停用的URI，不出现在列表中
+    disabled: HashSet<String>,
+    /// 手动停用的URI，不出现在列表中，也不能读取，探测成功时不恢复
+    switched_off: HashSet<String>,
+    /// 按需加载资源的加载器，参见[`loader`](crate::server::loader)
+    loader: Option<Loader>,
+    /// 加载器加载过的资源，不出现在按URI注册的列表中
+    loaded: HashMap<String, Arc<FunctionResource>>,
+}
+
+impl ResourceTable {
+    /// 查找URI和MIME类型都相同的表示
+    fn contains(&self, uri: &str, mime_type: &str) -> bool {
+        self.resources
+            .get(uri)
+            .is_some_and(|representations| representations.iter().any(|r| r.mime_type == mime_type))
+    }
+
+    /// 添加资源，替换URI和MIME类型都相同的表示时保留它原来的位置
+    fn insert(&mut self, value: FunctionResource) {
+        let value = Arc::new(value);
+        self.probe_failures.remove(&value.uri);
+        self.disabled.remove(&value.uri);
+        self.switched_off.remove(&value.uri);
+        let representations = self.resources.entry(value.uri.clone()).or_default();
+        match representations.iter_mut().find(|r| r.mime_type == value.mime_type) {
+            Some(existing) => *existing = value,
+            None => representations.push(value),
+        }
+        self.listing = OnceLock::new();
+        self.revision += 1;
+    }
+
+    /// 删除URI的所有表示，以及加载器加载过的资源
+    fn remove(&mut self, key: &str) -> bool {
+        let removed = self.resources.remove(key).is_some();
+        if removed {
+            self.probe_failures.remove(key);
+            self.disabled.remove(key);
+            self.switched_off.remove(key);
+            self.listing = OnceLock::new();
+            self.revision += 1;
+        }
+        self.loaded.remove(key).is_some() || removed
+    }
+
+    /// 按URI排序的资源列表，保证每次列出的顺序一致，同一URI的表示按注册顺序排列，不包括停用的资源
+    fn sorted(&self) -> Vec<Resource> {
+        let mut resources: Vec<Resource> = self
+            .resources
+            .iter()
+            .filter(|(uri, _)| !self.disabled.contains(*uri) && !self.switched_off.contains(*uri))
+            .flat_map(|(_, representations)| representations.iter().map(|r| r.to_resource()))
+            .collect();
+        resources.sort_by(|a, b| a.uri.cmp(&b.uri));
+        resources
+    }
+}
+
 /// 资源管理器
+///
+/// 资源表在内部加锁，增删查都只需要`&self`。克隆得到的管理器共享同一份表。
 #[derive(Debug, Clone)]
 pub struct ResourceManager {
     /// 资源集合
-    resources: HashMap<String, FunctionResource>,
+    table: Arc<RwLock<ResourceTable>>,
     duplicate_behavior: DuplicateBehavior,
+    /// JSON资源是否缩进
+    pretty_json: bool,
+    /// 每次探测的最长时间
+    probe_timeout: Duration,
+    /// 连续探测失败多少次后停用资源，为`None`时不停用
+    disable_after: Option<u32>,
+    /// 资源列表中某个资源序列化失败时的处理方式
+    serialization_policy: SerializationPolicy,
 }
 
 impl ResourceManager {
     /// 创建新的资源管理器
     pub fn new() -> Self {
-        Self {
-            resources: HashMap::new(),
-            duplicate_behavior: DuplicateBehavior::Warn,
-        }
+        Self::with_behavior(DuplicateBehavior::Warn)
     }
     
     /// 创建具有指定重复行为的新资源管理器
     pub fn with_behavior(duplicate_behavior: DuplicateBehavior) -> Self {
         Self {
-            resources: HashMap::new(),
+            table: Arc::default(),
             duplicate_behavior,
+            pretty_json: false,
+            probe_timeout: probe::DEFAULT_TIMEOUT,
+            disable_after: None,
+            serialization_policy: SerializationPolicy::default(),
         }
     }
 }
@@ -182,49 +724,484 @@ impl Default for ResourceManager {
 
 impl ResourceManager {
     /// 添加资源
-    pub fn add_resource(&mut self, resource: FunctionResource) {
-        if self.resources.contains_key(&resource.uri) {
+    ///
+    /// # Panics
+    ///
+    /// 重复行为为`Error`且资源已存在时panic，需要处理错误时使用[`ResourceManager::try_add_resource`]。
+    pub fn add_resource(&self, resource: FunctionResource) {
+        if let Err(message) = self.try_add_resource(resource) {
+            panic!("{}", message);
+        }
+    }
+    
+    /// 添加资源，重复行为为`Error`且资源已存在时返回错误
+    ///
+    /// 返回资源是否被添加，重复行为为`Ignore`且资源已存在时返回`Ok(false)`。
+    pub fn try_add_resource(&self, resource: FunctionResource) -> Result<bool, String> {
+        let mut table = self.table.write();
+        if table.contains(&resource.uri, &resource.mime_type) {
             match self.duplicate_behavior {
                 DuplicateBehavior::Warn => {
-                    warn!("Resource '{}' already exists, replacing", resource.uri);
-                    self.resources.insert(resource.uri.clone(), resource);
+                    warn!("Resource '{}' ({}) already exists, replacing", resource.uri, resource.mime_type);
                 }
                 DuplicateBehavior::Error => {
-                    panic!("Resource '{}' already exists", resource.uri);
-                }
-                DuplicateBehavior::Replace => {
-                    self.resources.insert(resource.uri.clone(), resource);
+                    return Err(format!("Resource '{}' ({}) already exists", resource.uri, resource.mime_type));
                 }
+                DuplicateBehavior::Replace => {}
                 DuplicateBehavior::Ignore => {
                     // 不添加新资源
+                    return Ok(false);
                 }
             }
-        } else {
-            self.resources.insert(resource.uri.clone(), resource);
         }
+        table.insert(resource);
+        Ok(true)
+    }
+    
+    /// 删除资源的所有表示，返回资源是否存在
+    ///
+    /// 同时清除[加载器](ResourceManager::set_lazy_loader)加载过的资源，下次读取时重新加载。
+    pub fn remove_resource(&self, uri: &str) -> bool {
+        self.table.write().remove(uri)
+    }
+    
+    /// 启用或停用按URI注册的资源，资源不存在时返回`None`，否则返回状态是否发生变化
+    pub fn set_resource_enabled(&self, uri: &str, enabled: bool) -> Option<bool> {
+        let mut table = self.table.write();
+        if !table.resources.contains_key(uri) {
+            return None;
+        }
+        let changed = match enabled {
+            true => table.switched_off.remove(uri),
+            false => table.switched_off.insert(uri.to_string()),
+        };
+        if changed {
+            table.listing = OnceLock::new();
+            table.revision += 1;
+        }
+        Some(changed)
     }
     
     /// 列出所有资源
     pub fn list_resources(&self) -> Vec<Resource> {
-        self.resources.values().map(|r| {
-            Resource {
-                uri: r.uri.clone(),
-                name: r.name.clone(),
-                description: if r.description.is_empty() { None } else { Some(r.description.clone()) },
-                mime_type: if r.mime_type.is_empty() { None } else { Some(r.mime_type.clone()) },
-                tags: if r.tags.is_empty() { None } else { Some(r.tags.clone()) },
-                annotations: if r.annotations.is_empty() { None } else { Some(r.annotations.clone()) },
-                meta: r.meta.clone(),
+        self.table.read().sorted()
+    }
+    
+    /// 序列化后的资源列表（JSON数组）
+    ///
+    /// 结果被缓存，资源表不变时多次调用和并发请求共享同一份，添加或删除资源后重新生成。
+    /// 某个资源序列化失败时按[`SerializationPolicy`]返回错误或跳过该资源。
+    pub fn resource_listing(&self) -> Result<Arc<Value>, McpError> {
+        let table = self.table.read();
+        table
+            .listing
+            .get_or_init(|| {
+                serialize_items(&table.sorted(), "resource", |resource| &resource.uri, self.serialization_policy)
+                    .map(|resources| Arc::new(Value::Array(resources)))
+            })
+            .clone()
+    }
+    
+    /// 资源表的修订号，添加或删除资源后增大
+    pub(crate) fn revision(&self) -> u64 {
+        self.table.read().revision
+    }
+    
+    /// 设置JSON资源是否缩进
+    pub fn set_pretty_json(&mut self, enabled: bool) {
+        self.pretty_json = enabled;
+    }
+    
+    /// 设置资源列表中某个资源序列化失败时的处理方式，参见[`serialization`](crate::server::serialization)
+    pub fn set_serialization_policy(&mut self, policy: SerializationPolicy) {
+        self.serialization_policy = policy;
+        self.table.write().listing = OnceLock::new();
+    }
+    
+    /// 设置每次探测的最长时间，以及连续失败多少次后停用资源，参见[`probe`](crate::server::probe)
+    pub fn set_probe_policy(&mut self, timeout: Duration, disable_after: Option<u32>) {
+        self.probe_timeout = timeout;
+        self.disable_after = disable_after;
+    }
+    
+    /// 并发读取每个按URI注册的资源，检查读取是否成功，结果按URI排序
+    ///
+    /// 最多同时探测`concurrency`个资源，每次探测受[`ResourceManager::set_probe_policy`]的时限约束。
+    /// 同一URI的所有表示都要读取成功；流式资源只读取第一块。解析器提供的资源不探测。
+    /// 设置了停用阈值时，连续失败达到阈值的资源从列表中移除，之后探测成功时恢复。
+    pub async fn probe_all(&self, concurrency: usize) -> Vec<(String, Result<(), String>)> {
+        self.probe(concurrency).await.0
+    }
+    
+    /// 探测所有资源，同时返回列表是否因停用或恢复而变化
+    pub(crate) async fn probe(&self, concurrency: usize) -> (Vec<(String, Result<(), String>)>, bool) {
+        let mut resources: Vec<(String, Vec<Arc<FunctionResource>>)> = self
+            .table
+            .read()
+            .resources
+            .iter()
+            .map(|(uri, representations)| (uri.clone(), representations.clone()))
+            .collect();
+        resources.sort_by(|a, b| a.0.cmp(&b.0));
+        let (timeout, pretty_json) = (self.probe_timeout, self.pretty_json);
+        let results: Vec<(String, Result<(), String>)> = futures::stream::iter(resources)
+            .map(|(uri, representations)| async move {
+                let result = probe::probe(representations, timeout, pretty_json).await;
+                (uri, result)
+            })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await;
+        let changed = self.record_probes(&results);
+        (results, changed)
+    }
+    
+    /// 记录探测结果，更新连续失败次数和停用状态，返回列表是否变化
+    fn record_probes(&self, results: &[(String, Result<(), String>)]) -> bool {
+        let mut table = self.table.write();
+        let mut changed = false;
+        for (uri, result) in results {
+            // 探测期间被删除的资源
+            if !table.resources.contains_key(uri) {
+                continue;
             }
-        }).collect()
+            match result {
+                Ok(()) => {
+                    table.probe_failures.remove(uri);
+                    if table.disabled.remove(uri) {
+                        log::info!("Resource '{}' is readable again and listed", uri);
+                        changed = true;
+                    }
+                }
+                Err(message) => {
+                    let failures = table.probe_failures.entry(uri.clone()).or_insert(0);
+                    *failures += 1;
+                    let failures = *failures;
+                    warn!("Resource '{}' failed its probe ({} in a row): {}", uri, failures, message);
+                    if self.disable_after.is_some_and(|limit| failures >= limit) && table.disabled.insert(uri.clone()) {
+                        warn!("Resource '{}' disabled after {} failed probes", uri, failures);
+                        changed = true;
+                    }
+                }
+            }
+        }
+        if changed {
+            table.listing = OnceLock::new();
+            table.revision += 1;
+        }
+        changed
     }
     
-    /// 读取资源
-    pub fn read_resource(&self, uri: &str) -> Result<Value, String> {
-        if let Some(resource) = self.resources.get(uri) {
-            resource.read()
-        } else {
-            Err(format!("Resource not found: {}", uri))
+    /// 设置了配额的资源的当前用量，按URI排序
+    ///
+    /// 同一URI的多个表示各自计数。
+    pub fn quota_status(&self) -> Vec<QuotaStatus> {
+        let mut status: Vec<QuotaStatus> = self
+            .table
+            .read()
+            .resources
+            .values()
+            .flatten()
+            .filter_map(|resource| resource.quota.as_ref().map(|quota| quota.status("resource", &resource.uri)))
+            .collect();
+        status.sort_by(|a, b| a.name.cmp(&b.name));
+        status
+    }
+    
+    /// 被停用的资源URI（连续探测失败或手动停用），按URI排序
+    pub fn disabled_resources(&self) -> Vec<String> {
+        let table = self.table.read();
+        let mut uris: Vec<String> = table.disabled.union(&table.switched_off).cloned().collect();
+        uris.sort();
+        uris
+    }
+    
+    /// 读取资源并按MIME类型渲染，资源函数执行期间不持有锁
+    ///
+    /// 同一URI有多个表示时读取最先注册的表示。
+    pub fn read_resource(&self, uri: &str, ctx: &Context) -> Result<ResourceContents, McpError> {
+        self.read_representation(uri, None, ctx)
+    }
+    
+    /// 读取资源指定MIME类型的表示，`mime_type`为`None`时读取最先注册的表示
+    pub fn read_representation(&self, uri: &str, mime_type: Option<&str>, ctx: &Context) -> Result<ResourceContents, McpError> {
+        let resource = self.get(uri, mime_type)?;
+        if let Some(quota) = &resource.quota {
+            quota.admit("resource", uri)?;
+        }
+        resource.read_contents(ctx, self.pretty_json).map_err(|message| McpError::ResourceRead {
+            uri: uri.to_string(),
+            message,
+        })
+    }
+    
+    /// 打开资源，同时返回MIME类型
+    pub(crate) fn open_resource(&self, uri: &str, mime_type: Option<&str>, ctx: &Context) -> Result<(ResourceBody, String), McpError> {
+        let resource = self.get(uri, mime_type)?;
+        if let Some(quota) = &resource.quota {
+            quota.admit("resource", uri)?;
+        }
+        let body = resource.open(ctx, self.pretty_json).map_err(|message| McpError::ResourceRead {
+            uri: uri.to_string(),
+            message,
+        })?;
+        Ok((body, resource.mime_type.clone()))
+    }
+    
+    /// 查找资源的表示，没有指定MIME类型时返回最先注册的表示
+    ///
+    /// 没有按URI注册的资源时依次查找加载器加载过的资源和前缀匹配的解析器，解析器执行期间不持有锁。
+    fn get(&self, uri: &str, mime_type: Option<&str>) -> Result<Arc<FunctionResource>, McpError> {
+        let resolvers = {
+            let table = self.table.read();
+            if let Some(representations) = table.resources.get(uri) {
+                if table.switched_off.contains(uri) {
+                    return Err(McpError::ResourceDisabled(uri.to_string()));
+                }
+                return select(uri, representations, mime_type);
+            }
+            if let Some(resource) = table.loaded.get(uri) {
+                return select(uri, std::slice::from_ref(resource), mime_type);
+            }
+            resolvers::matching(&table.resolvers, uri)
+        };
+        match resolvers.iter().find_map(|r| r.resolver.resolve(uri)) {
+            Some(resource) => select(uri, &[Arc::new(resource)], mime_type),
+            None => Err(McpError::ResourceNotFound {
+                uri: uri.to_string(),
+                suggestions: suggest(uri, self.table.read().resources.keys()),
+            }),
+        }
+    }
+    
+    /// 为URI前缀注册解析器，替换同一前缀已有的解析器，参见[`resolvers`](crate::server::resolvers)
+    ///
+    /// `template`可以直接传入前缀，此时模板名称也是前缀。
+    pub fn add_resolver(&self, template: impl Into<ResourceTemplate>, resolver: impl ResourceResolver + 'static) {
+        let template = template.into();
+        let mut table = self.table.write();
+        table.resolvers.retain(|r| r.template.prefix() != template.prefix());
+        table.resolvers.push(Resolver {
+            template,
+            resolver: Arc::new(resolver),
+        });
+        table.revision += 1;
+    }
+    
+    /// 删除前缀的解析器，返回解析器是否存在
+    pub fn remove_resolver(&self, prefix: &str) -> bool {
+        let mut table = self.table.write();
+        let before = table.resolvers.len();
+        table.resolvers.retain(|r| r.template.prefix() != prefix);
+        let removed = table.resolvers.len() != before;
+        if removed {
+            table.revision += 1;
+        }
+        removed
+    }
+    
+    /// 安装按需加载资源的加载器，替换已有的加载器并清除它加载过的资源，参见[`loader`](crate::server::loader)
+    pub fn set_lazy_loader(&self, loader: impl ResourceLoader + 'static) {
+        let mut table = self.table.write();
+        table.loader = Some(Loader(Arc::new(loader)));
+        table.loaded.clear();
+        table.listing = OnceLock::new();
+        table.revision += 1;
+    }
+    
+    /// 是否安装了加载器
+    pub(crate) fn has_lazy_loader(&self) -> bool {
+        self.table.read().loader.is_some()
+    }
+    
+    /// 没有按URI注册、加载过或前缀匹配的资源时询问加载器，加载到的资源缓存起来
+    ///
+    /// 加载器返回`None`时不做任何事，之后的读取返回资源不存在；加载器出错时返回带有URI的读取错误。
+    pub(crate) async fn load_missing(&self, uri: &str) -> Result<(), McpError> {
+        let loader = {
+            let table = self.table.read();
+            let known = table.resources.contains_key(uri)
+                || table.loaded.contains_key(uri)
+                || table.resolvers.iter().any(|r| uri.starts_with(r.template.prefix()));
+            match &table.loader {
+                Some(loader) if !known => loader.clone(),
+                _ => return Ok(()),
+            }
+        };
+        match loader.0.load(uri).await {
+            Ok(Some(resource)) => {
+                self.table.write().loaded.entry(uri.to_string()).or_insert_with(|| Arc::new(resource));
+                Ok(())
+            }
+            Ok(None) => Ok(()),
+            Err(message) => Err(McpError::ResourceRead {
+                uri: uri.to_string(),
+                message,
+            }),
+        }
+    }
+    
+    /// 加载器列出的一页资源（JSON数组）和下一页的游标，按URI注册的资源列在第一页的最前面
+    pub(crate) async fn list_page(&self, cursor: Option<&str>) -> Result<(Value, Option<String>), McpError> {
+        let Some(loader) = self.table.read().loader.clone() else {
+            return Ok((Value::clone(&*self.resource_listing()?), None));
+        };
+        let (page, next_cursor) = loader.0.list_page(cursor).await.map_err(McpError::Internal)?;
+        let page = serialize_items(&page, "resource", |resource| &resource.uri, self.serialization_policy)?;
+        let resources = match cursor {
+            None => match Value::clone(&*self.resource_listing()?) {
+                Value::Array(mut registered) => {
+                    registered.extend(page);
+                    registered
+                }
+                _ => page,
+            },
+            Some(_) => page,
+        };
+        Ok((Value::Array(resources), next_cursor))
+    }
+    
+    /// 列出解析器的资源模板，按前缀排序
+    pub fn list_resource_templates(&self) -> Vec<ResourceTemplate> {
+        let mut templates: Vec<ResourceTemplate> = self.table.read().resolvers.iter().map(|r| r.template.clone()).collect();
+        templates.sort_by(|a, b| a.prefix().cmp(b.prefix()));
+        templates
+    }
+}
+
+/// 按MIME类型选择资源的表示，没有指定MIME类型时返回第一个
+fn select(uri: &str, representations: &[Arc<FunctionResource>], mime_type: Option<&str>) -> Result<Arc<FunctionResource>, McpError> {
+    let found = match mime_type {
+        Some(mime_type) => representations.iter().find(|r| r.mime_type == mime_type),
+        None => representations.first(),
+    };
+    match found {
+        Some(resource) => Ok(resource.clone()),
+        None => {
+            let available: Vec<&str> = representations.iter().map(|r| r.mime_type.as_str()).collect();
+            Err(McpError::invalid_params(
+                format!(
+                    "Resource '{}' has no '{}' representation (available: {})",
+                    uri,
+                    mime_type.unwrap_or_default(),
+                    available.join(", ")
+                ),
+                "mimeType",
+                available.join(" | "),
+            ))
         }
     }
-}
\ No newline at end of file
+}
+
+/// 加上不定冠词的类型名称，用于错误消息
+fn article(name: &str) -> String {
+    match name.chars().next() {
+        Some('a' | 'e' | 'i' | 'o' | 'u') => format!("an {}", name),
+        _ => format!("a {}", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use crate::{RustMCP, Settings};
+
+    #[test]
+    fn resource_serializes_title_and_meta() {
+        let resource = FunctionResource::simple("memo://notes", || Ok(json!("notes")))
+            .with_title("Team notes")
+            .with_meta(HashMap::from([("owner".to_string(), json!({ "team": "ops" }))]));
+        assert_eq!(
+            serde_json::to_value(resource.to_resource()).unwrap(),
+            json!({
+                "uri": "memo://notes",
+                "name": "memo://notes",
+                "title": "Team notes",
+                "mimeType": "text/plain",
+                "_meta": { "owner": { "team": "ops" } }
+            })
+        );
+    }
+
+    #[test]
+    fn resource_without_title_or_meta_omits_them() {
+        let resource = FunctionResource::simple("memo://plain", || Ok(json!("plain")));
+        let value = serde_json::to_value(resource.to_resource()).unwrap();
+        assert_eq!(value, json!({ "uri": "memo://plain", "name": "memo://plain", "mimeType": "text/plain" }));
+    }
+
+    fn uris(listing: &Value) -> Vec<&str> {
+        listing.as_array().unwrap().iter().map(|resource| resource["uri"].as_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn listing_snapshot_is_shared_until_the_table_changes() {
+        let manager = ResourceManager::new();
+        manager.add_resource(FunctionResource::simple("memo://b", || Ok(json!("b"))));
+        let first = manager.resource_listing().unwrap();
+        let again = manager.resource_listing().unwrap();
+        assert!(Arc::ptr_eq(&first, &again));
+        assert_eq!(uris(&first), ["memo://b"]);
+
+        manager.add_resource(FunctionResource::simple("memo://a", || Ok(json!("a"))));
+        let added = manager.resource_listing().unwrap();
+        assert!(!Arc::ptr_eq(&first, &added));
+        // 快照按URI排序
+        assert_eq!(uris(&added), ["memo://a", "memo://b"]);
+        assert_eq!(uris(&first), ["memo://b"]);
+
+        // 删除不存在的资源不会使快照失效
+        assert!(!manager.remove_resource("memo://missing"));
+        assert!(Arc::ptr_eq(&added, &manager.resource_listing().unwrap()));
+        assert!(manager.remove_resource("memo://b"));
+        assert_eq!(uris(&manager.resource_listing().unwrap()), ["memo://a"]);
+
+        // 替换同一URI的资源后快照反映新的元数据
+        manager.add_resource(FunctionResource::simple("memo://a", || Ok(json!("a"))).with_title("Renamed"));
+        assert_eq!(manager.resource_listing().unwrap()[0]["title"], "Renamed");
+        assert_eq!(manager.list_resources().len(), 1);
+    }
+
+    #[test]
+    fn contents_are_rendered_by_mime_type() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_resource(FunctionResource::from_function(
+            || Ok(json!({"b": 1, "a": [true]})), "data://config".into(), None, None, Some("application/json".into()), None, None, None,
+        ));
+        rustmcp.add_resource(FunctionResource::simple("data://motd", || Ok(json!("hello"))));
+        rustmcp.add_resource(FunctionResource::simple("data://broken", || Ok(json!({"not": "text"}))));
+        rustmcp.add_resource(FunctionResource::from_function(
+            || Ok(json!("iVBORw0KGgo=")), "data://logo".into(), None, None, Some("image/png".into()), None, None, None,
+        ));
+        rustmcp.add_resource(FunctionResource::from_function(
+            || Ok(json!(42)), "data://raw".into(), None, None, Some("application/octet-stream".into()), None, None, None,
+        ));
+
+        // JSON序列化后作为文本返回，默认不缩进
+        assert_eq!(rustmcp.mcp_read_resource("data://config").unwrap(), ResourceContents::Text {
+            uri: "data://config".into(),
+            mime_type: "application/json".into(),
+            text: r#"{"a":[true],"b":1}"#.into(),
+        });
+        let pretty = rustmcp.clone().with_settings(Settings::new().with_resource_json_pretty(true));
+        assert_eq!(pretty.mcp_read_resource("data://config").unwrap().text(), Some("{\n  \"a\": [\n    true\n  ],\n  \"b\": 1\n}"));
+
+        // 文本类型必须返回字符串
+        assert_eq!(rustmcp.mcp_read_resource("data://motd").unwrap().text(), Some("hello"));
+        let error = rustmcp.mcp_read_resource("data://broken").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Resource 'data://broken' has MIME type 'text/plain' and must return a string, but returned an object"
+        );
+
+        // 其他类型作为blob返回
+        assert_eq!(rustmcp.mcp_read_resource("data://logo").unwrap(), ResourceContents::Blob {
+            uri: "data://logo".into(),
+            mime_type: "image/png".into(),
+            blob: "iVBORw0KGgo=".into(),
+        });
+        assert!(rustmcp.mcp_read_resource("data://raw").unwrap_err().to_string().contains("must return a base64-encoded string"));
+    }
+}
@@ -0,0 +1,205 @@
+//! 工具重试策略
+//!
+//! 通过[`FunctionToolBuilder::retries`](crate::FunctionToolBuilder::retries)为调用外部服务的工具
+//! 配置重试。工具函数返回错误时，[`ToolManager::call_tool`](crate::server::ToolManager::call_tool)
+//! 按退避间隔重新调用，直到成功或达到最大尝试次数；参数校验失败等调用前的错误不会重试。
+//! 配置了重试策略的工具会在结果的`_meta.attempts`中报告实际尝试次数。
+//!
+//! 工具函数是同步执行的，退避等待也在调用线程上进行。请求被取消，或者下一次等待会超过请求的截止时间时
+//! 停止重试，返回最后一次的错误。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 判断错误是否可以重试的函数
+pub type RetryPredicate = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// 重试之间的退避方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backoff {
+    /// 固定间隔
+    Fixed(Duration),
+    /// 指数退避：第n次重试前等待`base * 2^(n-1)`，不超过`max`
+    Exponential {
+        /// 第一次重试前的等待时间
+        base: Duration,
+        /// 最长等待时间
+        max: Duration,
+    },
+}
+
+impl Backoff {
+    /// 第`retry`次重试（从1开始）前的等待时间
+    pub fn delay(&self, retry: u32) -> Duration {
+        match *self {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential { base, max } => {
+                let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+                base.checked_mul(factor).unwrap_or(max).min(max)
+            }
+        }
+    }
+}
+
+/// 工具的重试策略
+#[derive(Clone)]
+pub struct RetryPolicy {
+    /// 最大尝试次数（包括第一次调用）
+    pub max_attempts: u32,
+    /// 退避方式
+    pub backoff: Backoff,
+    /// 判断错误是否可以重试，为`None`时所有工具错误都会重试
+    pub retry_if: Option<RetryPredicate>,
+}
+
+impl RetryPolicy {
+    /// 创建重试策略，`max_attempts`至少为1
+    pub fn new(max_attempts: u32, backoff: Backoff) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            backoff,
+            retry_if: None,
+        }
+    }
+
+    /// 错误是否可以重试
+    pub fn is_retryable(&self, message: &str) -> bool {
+        self.retry_if.as_ref().is_none_or(|predicate| predicate(message))
+    }
+}
+
+impl std::fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("backoff", &self.backoff)
+            .field("retry_if", &self.retry_if.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parking_lot::Mutex;
+    use serde_json::{json, Value};
+    use std::time::Instant;
+
+    use super::*;
+    use crate::server::test_support::call;
+    use crate::server::{create_app, Context, FunctionTool, RustMCP};
+
+    /// 前`failures`次调用失败的工具，返回调用次数的计数器
+    fn flaky(failures: u32, max_attempts: u32, backoff: Backoff) -> (FunctionTool, Arc<Mutex<u32>>) {
+        let calls = Arc::new(Mutex::new(0));
+        let counter = calls.clone();
+        let tool = FunctionTool::builder()
+            .name("flaky")
+            .retries(max_attempts, backoff)
+            .handler(move |_| {
+                let mut calls = counter.lock();
+                *calls += 1;
+                match *calls <= failures {
+                    true => Err(format!("transient failure {}", calls)),
+                    false => Ok(json!("done")),
+                }
+            })
+            .build();
+        (tool, calls)
+    }
+
+    fn attempts(ctx: &Context) -> Value {
+        ctx.result_meta().unwrap_or_default()["attempts"].clone()
+    }
+
+    #[test]
+    fn exponential_backoff_doubles_up_to_the_maximum() {
+        let backoff = Backoff::Exponential { base: Duration::from_millis(10), max: Duration::from_millis(50) };
+        let delays: Vec<u128> = (1..=5).map(|retry| backoff.delay(retry).as_millis()).collect();
+        assert_eq!(delays, [10, 20, 40, 50, 50]);
+        assert_eq!(backoff.delay(u32::MAX), Duration::from_millis(50));
+        assert_eq!(Backoff::Fixed(Duration::from_millis(7)).delay(3), Duration::from_millis(7));
+        assert_eq!(RetryPolicy::new(0, Backoff::Fixed(Duration::ZERO)).max_attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn fails_twice_then_succeeds() {
+        let rustmcp = RustMCP::new();
+        let (tool, calls) = flaky(2, 5, Backoff::Exponential { base: Duration::from_millis(5), max: Duration::from_millis(20) });
+        rustmcp.add_tool(tool);
+        let ctx = Context::new();
+        let started = Instant::now();
+        assert_eq!(rustmcp.mcp_call_tool_with_context("flaky", None, &ctx).await.unwrap(), json!("done"));
+        assert_eq!(*calls.lock(), 3);
+        assert_eq!(attempts(&ctx), 3);
+        // 两次退避：5ms和10ms
+        assert!(started.elapsed() >= Duration::from_millis(15));
+    }
+
+    #[tokio::test]
+    async fn always_failing_tool_stops_at_max_attempts() {
+        let rustmcp = RustMCP::new();
+        let (tool, calls) = flaky(u32::MAX, 3, Backoff::Fixed(Duration::from_millis(1)));
+        rustmcp.add_tool(tool);
+        let app = create_app(rustmcp);
+        let response = call(&app, "tools/call", json!({ "name": "flaky" })).await;
+        assert_eq!(response["result"]["isError"], true, "{}", response);
+        assert_eq!(response["result"]["content"][0]["text"], "transient failure 3");
+        assert_eq!(response["result"]["_meta"]["attempts"], 3);
+        assert_eq!(*calls.lock(), 3);
+    }
+
+    #[tokio::test]
+    async fn predicate_limits_which_errors_are_retried() {
+        let calls = Arc::new(Mutex::new(0));
+        let counter = calls.clone();
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("strict")
+                .retries(5, Backoff::Fixed(Duration::ZERO))
+                .retry_if(|message| message.starts_with("503"))
+                .handler(move |_| {
+                    *counter.lock() += 1;
+                    Err("400 bad request".to_string())
+                })
+                .build(),
+        );
+        let ctx = Context::new();
+        assert!(rustmcp.mcp_call_tool_with_context("strict", None, &ctx).await.is_err());
+        assert_eq!(*calls.lock(), 1);
+        assert_eq!(attempts(&ctx), 1);
+    }
+
+    #[tokio::test]
+    async fn retries_stop_when_cancelled() {
+        let rustmcp = RustMCP::new();
+        let (tool, calls) = flaky(u32::MAX, 100, Backoff::Fixed(Duration::from_millis(50)));
+        rustmcp.add_tool(tool);
+        let ctx = Context::new();
+        let token = ctx.cancellation_token().clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(75));
+            token.cancel();
+        });
+        let started = Instant::now();
+        assert!(rustmcp.mcp_call_tool_with_context("flaky", None, &ctx).await.is_err());
+        assert!(started.elapsed() < Duration::from_millis(500), "{:?}", started.elapsed());
+        assert_eq!(*calls.lock(), 2);
+        assert_eq!(attempts(&ctx), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_stop_before_the_deadline() {
+        let rustmcp = RustMCP::new();
+        let (tool, calls) = flaky(u32::MAX, 100, Backoff::Fixed(Duration::from_millis(40)));
+        rustmcp.add_tool(tool);
+        let started = Instant::now();
+        let ctx = Context::new().with_deadline(started + Duration::from_millis(100));
+        assert!(rustmcp.mcp_call_tool_with_context("flaky", None, &ctx).await.is_err());
+        // 大约在0ms、40ms和80ms各调用一次，不会等待到截止时间之后
+        assert!(started.elapsed() < Duration::from_millis(100), "{:?}", started.elapsed());
+        let calls = *calls.lock();
+        assert!((2..=3).contains(&calls), "{}", calls);
+        assert_eq!(attempts(&ctx), calls);
+    }
+}
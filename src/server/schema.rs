@@ -0,0 +1,236 @@
+//! 工具参数声明
+//!
+//! [`ToolArgs`]用链式调用声明参数，生成工具的`inputSchema`：
+//!
+//! ```rust
+//! use rustmcp::ToolArgs;
+//!
+//! let schema = ToolArgs::new()
+//!     .string("message", "The message to echo", true)
+//!     .integer("count", "Repeat count", false)
+//!     .build_schema();
+//! assert_eq!(schema["required"], serde_json::json!(["message"]));
+//! ```
+
+use serde_json::{Map, Value};
+
+/// 参数类型
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgKind {
+    /// 字符串
+    String,
+    /// 整数
+    Integer,
+    /// 数字
+    Number,
+    /// 布尔值
+    Boolean,
+    /// 取值限定在给定列表中的字符串
+    Enum(Vec<String>),
+    /// 字符串数组
+    StringArray,
+    /// 嵌套对象
+    Object(ToolArgs),
+}
+
+/// 单个参数声明
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgSpec {
+    /// 参数名称
+    pub name: String,
+    /// 参数描述
+    pub description: String,
+    /// 参数类型
+    pub kind: ArgKind,
+    /// 是否必填
+    pub required: bool,
+}
+
+impl ArgSpec {
+    /// 生成该参数的JSON Schema
+    fn schema(&self) -> Value {
+        let mut schema = match &self.kind {
+            ArgKind::String => serde_json::json!({ "type": "string" }),
+            ArgKind::Integer => serde_json::json!({ "type": "integer" }),
+            ArgKind::Number => serde_json::json!({ "type": "number" }),
+            ArgKind::Boolean => serde_json::json!({ "type": "boolean" }),
+            ArgKind::Enum(values) => serde_json::json!({ "type": "string", "enum": values }),
+            ArgKind::StringArray => serde_json::json!({ "type": "array", "items": { "type": "string" } }),
+            ArgKind::Object(args) => args.build_schema(),
+        };
+        schema["description"] = Value::String(self.description.clone());
+        schema
+    }
+}
+
+/// 工具参数声明构建器
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolArgs {
+    args: Vec<ArgSpec>,
+}
+
+impl ToolArgs {
+    /// 创建空的参数声明
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加参数声明，同名参数会替换之前的声明
+    pub fn arg(mut self, name: &str, description: &str, kind: ArgKind, required: bool) -> Self {
+        self.args.retain(|arg| arg.name != name);
+        self.args.push(ArgSpec {
+            name: name.to_string(),
+            description: description.to_string(),
+            kind,
+            required,
+        });
+        self
+    }
+
+    /// 添加字符串参数
+    pub fn string(self, name: &str, description: &str, required: bool) -> Self {
+        self.arg(name, description, ArgKind::String, required)
+    }
+
+    /// 添加整数参数
+    pub fn integer(self, name: &str, description: &str, required: bool) -> Self {
+        self.arg(name, description, ArgKind::Integer, required)
+    }
+
+    /// 添加数字参数
+    pub fn number(self, name: &str, description: &str, required: bool) -> Self {
+        self.arg(name, description, ArgKind::Number, required)
+    }
+
+    /// 添加布尔参数
+    pub fn boolean(self, name: &str, description: &str, required: bool) -> Self {
+        self.arg(name, description, ArgKind::Boolean, required)
+    }
+
+    /// 添加取值限定在`values`中的字符串参数
+    pub fn enumeration(self, name: &str, description: &str, values: &[&str], required: bool) -> Self {
+        let values = values.iter().map(|v| v.to_string()).collect();
+        self.arg(name, description, ArgKind::Enum(values), required)
+    }
+
+    /// 添加字符串数组参数
+    pub fn string_array(self, name: &str, description: &str, required: bool) -> Self {
+        self.arg(name, description, ArgKind::StringArray, required)
+    }
+
+    /// 添加嵌套对象参数
+    pub fn object(self, name: &str, description: &str, fields: ToolArgs, required: bool) -> Self {
+        self.arg(name, description, ArgKind::Object(fields), required)
+    }
+
+    /// 已声明的参数
+    pub fn args(&self) -> &[ArgSpec] {
+        &self.args
+    }
+
+    /// 生成JSON Schema对象，没有必填参数时省略`required`
+    pub fn build_schema(&self) -> Value {
+        let properties: Map<String, Value> = self.args.iter().map(|arg| (arg.name.clone(), arg.schema())).collect();
+        let required: Vec<&str> = self.args.iter().filter(|arg| arg.required).map(|arg| arg.name.as_str()).collect();
+
+        let mut schema = serde_json::json!({
+            "type": "object",
+            "properties": properties,
+        });
+        if !required.is_empty() {
+            schema["required"] = serde_json::json!(required);
+        }
+        schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::server::error::McpError;
+    use crate::server::{FunctionTool, RustMCP};
+
+    fn all_kinds() -> ToolArgs {
+        ToolArgs::new()
+            .string("message", "Message", true)
+            .integer("count", "Count", false)
+            .number("ratio", "Ratio", false)
+            .boolean("loud", "Loud", false)
+            .enumeration("level", "Level", &["low", "high"], false)
+            .string_array("tags", "Tags", false)
+            .object("target", "Target", ToolArgs::new().string("host", "Host", true).integer("port", "Port", false), false)
+    }
+
+    #[test]
+    fn schema_matches_golden_json() {
+        assert_eq!(
+            all_kinds().build_schema(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "message": { "type": "string", "description": "Message" },
+                    "count": { "type": "integer", "description": "Count" },
+                    "ratio": { "type": "number", "description": "Ratio" },
+                    "loud": { "type": "boolean", "description": "Loud" },
+                    "level": { "type": "string", "enum": ["low", "high"], "description": "Level" },
+                    "tags": { "type": "array", "items": { "type": "string" }, "description": "Tags" },
+                    "target": {
+                        "type": "object",
+                        "properties": {
+                            "host": { "type": "string", "description": "Host" },
+                            "port": { "type": "integer", "description": "Port" }
+                        },
+                        "required": ["host"],
+                        "description": "Target"
+                    }
+                },
+                "required": ["message"]
+            })
+        );
+        assert_eq!(ToolArgs::new().build_schema(), json!({ "type": "object", "properties": {} }));
+        // 同名参数替换之前的声明
+        let args = ToolArgs::new().string("a", "first", true).integer("a", "second", false);
+        assert_eq!(args.args().len(), 1);
+        assert_eq!(args.build_schema(), json!({ "type": "object", "properties": { "a": { "type": "integer", "description": "second" } } }));
+    }
+
+    #[tokio::test]
+    async fn declared_args_are_validated_per_kind() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::builder().name("all").args(all_kinds()).handler(|_| Ok(json!("ok"))).build());
+        let tool = rustmcp.tool_manager.get_tool("all").unwrap();
+        assert_eq!(tool.input_schema, Some(all_kinds().build_schema()));
+
+        let call = |arguments: serde_json::Value| {
+            let rustmcp = rustmcp.clone();
+            async move {
+                let arguments: HashMap<String, serde_json::Value> = serde_json::from_value(arguments).unwrap();
+                rustmcp.mcp_call_tool("all", Some(arguments)).await
+            }
+        };
+        let valid = json!({
+            "message": "hi", "count": 2, "ratio": 0.5, "loud": true, "level": "high",
+            "tags": ["a", "b"], "target": { "host": "example.com", "port": 80 }
+        });
+        assert_eq!(call(valid).await.unwrap(), json!("ok"));
+
+        let invalid = [
+            json!({}),
+            json!({ "message": 1 }),
+            json!({ "message": "hi", "count": 1.5 }),
+            json!({ "message": "hi", "ratio": "half" }),
+            json!({ "message": "hi", "loud": "yes" }),
+            json!({ "message": "hi", "level": "medium" }),
+            json!({ "message": "hi", "tags": ["a", 1] }),
+            json!({ "message": "hi", "target": { "port": 80 } }),
+            json!({ "message": "hi", "target": { "host": "h", "port": "80" } }),
+        ];
+        for arguments in invalid {
+            let result = call(arguments.clone()).await;
+            assert!(matches!(result, Err(McpError::InvalidParams { .. })), "{} -> {:?}", arguments, result);
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! 声明式工具中的密钥引用
+//!
+//! 清单和配置文件中的命令模板、转发地址和请求头可以引用密钥，而不是直接写入明文：
+//!
+//! - `${env:NAME}`：环境变量，创建工具时读取，变量不存在时注册失败；
+//! - `${file:/run/secrets/token}`：文件内容，每次调用时读取（去掉结尾的换行），文件轮换后立即生效。
+//!
+//! 错误消息只包含引用本身，不包含值。[`SecretTemplate`]的`Debug`和`Display`输出原始模板，
+//! 读取过的值会在内容采集和清单工具返回的错误中替换为`[REDACTED]`（少于4字节的值除外）。
+//! 文件轮换后旧值仍然脱敏，每个引用保留最近读到的8个不同的值。
+//! 其他`${...}`原样保留。
+//!
+//! ```rust
+//! use rustmcp::SecretTemplate;
+//!
+//! # std::env::set_var("API_TOKEN", "tok-123456");
+//! let header = SecretTemplate::parse("Bearer ${env:API_TOKEN}").unwrap();
+//! // Display和Debug输出引用，不是值
+//! assert_eq!(header.to_string(), "Bearer ${env:API_TOKEN}");
+//! ```
+
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+
+/// 脱敏后的占位值
+const REDACTED: &str = "[REDACTED]";
+
+/// 短于这个长度的值不做脱敏，否则会破坏普通文本
+const MIN_REDACTED_LEN: usize = 4;
+
+/// 每个引用保留的值的个数，超过时丢弃最早读到的值
+const MAX_VALUES_PER_REFERENCE: usize = 8;
+
+/// 读取过的密钥值，按引用保存最近读到的不同的值，先读到的在前
+static KNOWN_SECRETS: Mutex<BTreeMap<String, VecDeque<String>>> = parking_lot::const_mutex(BTreeMap::new());
+
+/// 密钥解析错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretError {
+    /// 出错的引用，例如`${env:API_TOKEN}`
+    pub reference: String,
+    /// 错误描述
+    pub message: String,
+}
+
+impl std::fmt::Display for SecretError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cannot resolve secret '{}': {}", self.reference, self.message)
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+impl From<SecretError> for String {
+    fn from(error: SecretError) -> Self {
+        error.to_string()
+    }
+}
+
+/// 模板的组成部分
+#[derive(Clone)]
+enum Part {
+    /// 普通文本
+    Text(String),
+    /// 创建时读取的环境变量
+    Env(String),
+    /// 每次解析时读取的文件
+    File { reference: String, path: PathBuf },
+}
+
+/// 可能包含密钥引用的模板
+#[derive(Clone)]
+pub struct SecretTemplate {
+    source: String,
+    parts: Vec<Part>,
+}
+
+impl SecretTemplate {
+    /// 解析模板并读取其中的环境变量
+    pub fn parse(template: &str) -> Result<Self, SecretError> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = find_reference(rest) {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            let after = &rest[start..];
+            let Some(end) = after.find('}') else {
+                return Err(SecretError {
+                    reference: after.to_string(),
+                    message: "missing closing '}'".to_string(),
+                });
+            };
+            let reference = &after[..=end];
+            parts.push(parse_reference(reference)?);
+            rest = &after[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+        Ok(Self {
+            source: template.to_string(),
+            parts,
+        })
+    }
+
+    /// 模板是否引用了密钥
+    pub fn has_secrets(&self) -> bool {
+        self.parts.iter().any(|part| !matches!(part, Part::Text(_)))
+    }
+
+    /// 解析出完整的值，文件引用在这里读取
+    pub fn resolve(&self) -> Result<String, SecretError> {
+        self.render(str::to_string)
+    }
+
+    /// 解析出完整的值，普通文本先经过`text`处理；密钥的值原样插入，不会被`text`处理
+    pub fn render(&self, mut text: impl FnMut(&str) -> String) -> Result<String, SecretError> {
+        let mut output = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(s) => output.push_str(&text(s)),
+                Part::Env(value) => output.push_str(value),
+                Part::File { reference, path } => output.push_str(&read_file(reference, path)?),
+            }
+        }
+        Ok(output)
+    }
+}
+
+impl std::fmt::Display for SecretTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl std::fmt::Debug for SecretTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SecretTemplate").field(&self.source).finish()
+    }
+}
+
+/// 把读取过的密钥值替换为`[REDACTED]`
+pub fn redact(text: &str) -> String {
+    let known = KNOWN_SECRETS.lock();
+    let mut values: Vec<&String> = known.values().flatten().filter(|value| value.len() >= MIN_REDACTED_LEN).collect();
+    // 先替换较长的值，避免一个值是另一个值的一部分时残留
+    values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+    let mut output = text.to_string();
+    for value in values {
+        if output.contains(value.as_str()) {
+            output = output.replace(value.as_str(), REDACTED);
+        }
+    }
+    output
+}
+
+/// 查找下一个`${env:`或`${file:`引用的位置
+fn find_reference(text: &str) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find("${") {
+        let position = offset + start;
+        let after = &text[position + 2..];
+        if after.starts_with("env:") || after.starts_with("file:") {
+            return Some(position);
+        }
+        offset = position + 2;
+    }
+    None
+}
+
+/// 解析单个引用，环境变量立即读取
+fn parse_reference(reference: &str) -> Result<Part, SecretError> {
+    let error = |message: &str| SecretError {
+        reference: reference.to_string(),
+        message: message.to_string(),
+    };
+    let inner = &reference[2..reference.len() - 1];
+    if let Some(name) = inner.strip_prefix("env:") {
+        if name.is_empty() {
+            return Err(error("environment variable name is empty"));
+        }
+        let value = std::env::var(name).map_err(|e| match e {
+            std::env::VarError::NotPresent => error("environment variable is not set"),
+            std::env::VarError::NotUnicode(_) => error("environment variable is not valid UTF-8"),
+        })?;
+        remember(reference, &value);
+        Ok(Part::Env(value))
+    } else {
+        let path = inner.strip_prefix("file:").unwrap_or_default();
+        if path.is_empty() {
+            return Err(error("file path is empty"));
+        }
+        Ok(Part::File {
+            reference: reference.to_string(),
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+/// 读取文件引用，去掉结尾的换行
+fn read_file(reference: &str, path: &PathBuf) -> Result<String, SecretError> {
+    let mut value = std::fs::read_to_string(path).map_err(|e| SecretError {
+        reference: reference.to_string(),
+        message: e.to_string(),
+    })?;
+    let trimmed = value.trim_end_matches(['\r', '\n']).len();
+    value.truncate(trimmed);
+    remember(reference, &value);
+    Ok(value)
+}
+
+/// 记录读取到的值，用于脱敏，轮换前的值继续保留
+fn remember(reference: &str, value: &str) {
+    let mut known = KNOWN_SECRETS.lock();
+    let values = known.entry(reference.to_string()).or_default();
+    if values.iter().any(|known| known == value) {
+        return;
+    }
+    values.push_back(value.to_string());
+    if values.len() > MAX_VALUES_PER_REFERENCE {
+        values.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SecretTemplate;
+
+    #[test]
+    fn references_resolve_rotate_and_redact() {
+        std::env::set_var("DEMO_API_TOKEN", "tok-123456");
+        let dir = std::env::temp_dir().join(format!("rustmcp-secrets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("password");
+        std::fs::write(&file, "first-password\n").unwrap();
+
+        let header = SecretTemplate::parse("Bearer ${env:DEMO_API_TOKEN}").unwrap();
+        assert_eq!(header.resolve().unwrap(), "Bearer tok-123456");
+        // 输出的是引用，不是值
+        assert_eq!(header.to_string(), "Bearer ${env:DEMO_API_TOKEN}");
+        assert!(!format!("{:?}", header).contains("tok-123456"));
+
+        // 文件在每次解析时读取，轮换后返回新值
+        let password = SecretTemplate::parse(&format!("--password=${{file:{}}}", file.display())).unwrap();
+        assert_eq!(password.resolve().unwrap(), "--password=first-password");
+        std::fs::write(&file, "rotated-password\n").unwrap();
+        assert_eq!(password.resolve().unwrap(), "--password=rotated-password");
+
+        // 读取过的值会被脱敏
+        assert_eq!(
+            crate::server::secrets::redact("login failed for rotated-password with tok-123456"),
+            "login failed for [REDACTED] with [REDACTED]"
+        );
+
+        // 错误只包含引用
+        let error = SecretTemplate::parse("${env:DEMO_MISSING_TOKEN}").unwrap_err();
+        assert_eq!(error.to_string(), "cannot resolve secret '${env:DEMO_MISSING_TOKEN}': environment variable is not set");
+        std::fs::remove_file(&file).unwrap();
+        assert!(password.resolve().unwrap_err().to_string().starts_with("cannot resolve secret '${file:"));
+    }
+
+    #[test]
+    fn rotated_file_secrets_keep_every_recent_value_redacted() {
+        let dir = std::env::temp_dir().join(format!("rustmcp-rotation-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("api-key");
+        let key = SecretTemplate::parse(&format!("${{file:{}}}", file.display())).unwrap();
+        std::fs::write(&file, "old-api-key\n").unwrap();
+        assert_eq!(key.resolve().unwrap(), "old-api-key");
+        std::fs::write(&file, "new-api-key\n").unwrap();
+        assert_eq!(key.resolve().unwrap(), "new-api-key");
+
+        // 轮换前读到的值仍然脱敏
+        assert_eq!(redact("old-api-key then new-api-key"), "[REDACTED] then [REDACTED]");
+
+        // 每个引用只保留最近的几个值
+        for i in 0..MAX_VALUES_PER_REFERENCE - 1 {
+            std::fs::write(&file, format!("api-key-{}\n", i)).unwrap();
+            key.resolve().unwrap();
+        }
+        assert_eq!(redact("old-api-key"), "old-api-key");
+        assert_eq!(redact("new-api-key"), "[REDACTED]");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
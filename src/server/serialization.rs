@@ -0,0 +1,87 @@
+//! 列表的逐项序列化
+//!
+//! 工具、资源和提示列表逐项序列化，某一项失败时按[`SerializationPolicy`]处理，不会让整个列表
+//! 悄悄变成空数组：
+//! - [`SerializationPolicy::FailClosed`]（默认）：列表请求失败，REST接口返回`500`，
+//!   JSON-RPC列表方法返回`-32603`，错误的`data`中带有失败项的类型（`itemKind`）和名称；
+//! - [`SerializationPolicy::SkipBad`]：跳过失败的项并记录警告，其余项照常返回。
+//!
+//! 两种策略都记录serde的原始错误。列表结果按注册表的修订号缓存，失败的结果同样被缓存，
+//! 注册表变化后重新序列化。
+//!
+//! ```rust
+//! use rustmcp::server::serialization::{serialize_items, SerializationPolicy};
+//! use serde::Serialize;
+//! use std::collections::HashMap;
+//!
+//! // 键不是字符串的映射无法序列化为JSON
+//! #[derive(Serialize)]
+//! struct Entry {
+//!     name: String,
+//!     grid: HashMap<(u32, u32), String>,
+//! }
+//!
+//! let entries = vec![
+//!     Entry { name: "plain".to_string(), grid: HashMap::new() },
+//!     Entry { name: "broken".to_string(), grid: HashMap::from([((0, 0), "origin".to_string())]) },
+//!     Entry { name: "also_plain".to_string(), grid: HashMap::new() },
+//! ];
+//!
+//! let error = serialize_items(&entries, "tool", |entry| &entry.name, SerializationPolicy::FailClosed).unwrap_err();
+//! assert_eq!(error.code(), -32603);
+//! assert_eq!(error.data()["name"], "broken");
+//! assert_eq!(error.data()["itemKind"], "tool");
+//! assert!(error.to_string().contains("key must be a string"));
+//!
+//! let items = serialize_items(&entries, "tool", |entry| &entry.name, SerializationPolicy::SkipBad).unwrap();
+//! let names: Vec<&str> = items.iter().map(|item| item["name"].as_str().unwrap()).collect();
+//! assert_eq!(names, ["plain", "also_plain"]);
+//! ```
+
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::server::error::McpError;
+
+/// 列表中的某一项序列化失败时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SerializationPolicy {
+    /// 整个列表请求失败，返回带有失败项名称的内部错误
+    #[default]
+    FailClosed,
+    /// 跳过失败的项并记录警告
+    SkipBad,
+}
+
+/// 逐项序列化列表，`kind`为项的类型（`tool`、`resource`或`prompt`），`name`取出项的名称
+///
+/// 失败时记录serde的原始错误，按`policy`返回[`McpError::Serialization`]或跳过该项。
+pub fn serialize_items<T: Serialize>(
+    items: &[T],
+    kind: &str,
+    name: impl Fn(&T) -> &str,
+    policy: SerializationPolicy,
+) -> Result<Vec<Value>, McpError> {
+    let mut values = Vec::with_capacity(items.len());
+    for item in items {
+        match serde_json::to_value(item) {
+            Ok(value) => values.push(value),
+            Err(e) => match policy {
+                SerializationPolicy::FailClosed => {
+                    error!("Failed to serialize {} '{}' for listing: {}", kind, name(item), e);
+                    return Err(McpError::Serialization {
+                        kind: kind.to_string(),
+                        name: name(item).to_string(),
+                        message: e.to_string(),
+                    });
+                }
+                SerializationPolicy::SkipBad => {
+                    warn!("Skipping {} '{}' in listing, failed to serialize: {}", kind, name(item), e);
+                }
+            },
+        }
+    }
+    Ok(values)
+}
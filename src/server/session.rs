@@ -0,0 +1,637 @@
+//! 会话存储
+//!
+//! 每个客户端会话一个键值存储，用于在同一会话的多次工具调用之间保存数据（例如先登录再操作、
+//! 多步向导）。处理函数通过[`Context::session`](crate::Context::session)访问：
+//! - WebSocket和标准输入输出：每个连接一个存储，连接关闭时丢弃；
+//! - HTTP：`initialize`时创建，之后按`Mcp-Session-Id`请求头查找，由服务器保存，超时或`DELETE /mcp`终止会话时丢弃；
+//! - 没有会话的HTTP请求和直接调用：只在本次调用内有效的临时存储，使用时会记录警告。
+//!
+//! 每个存储有大小上限，超过[`SessionLimits::ttl`]没有访问时数据被清空。
+//!
+//! HTTP会话由服务器管理：
+//! - 不带`Mcp-Session-Id`的`initialize`创建会话，服务器生成的会话ID在响应头`Mcp-Session-Id`中返回；
+//!   请求头中的会话ID不存在（从未创建、已过期或已终止）时返回404，客户端应重新`initialize`；
+//! - 超过空闲超时没有请求的会话被丢弃，`create_app`启动的后台任务按
+//!   [`Settings::session_reap_interval_ms`](crate::Settings::session_reap_interval_ms)定期清理；
+//! - 会话数达到[`Settings::max_sessions`](crate::Settings::max_sessions)（默认10000）后，
+//!   新会话按[`SessionEviction`]淘汰一个旧会话；
+//! - 已经初始化的会话再次收到`initialize`（例如客户端没有收到响应而重试）时重置该会话，
+//!   丢弃存储的数据、协商结果和连接信息，不返回错误；
+//! - [`RustMCP::session_metrics`](crate::RustMCP::session_metrics)和`/health`报告创建、清理、淘汰和重置的会话数。
+//!
+//! ```rust
+//! use rustmcp::{RustMCP, SessionEviction, Settings};
+//! use std::time::Duration;
+//!
+//! let settings = Settings::new()
+//!     .with_session_limits(64 * 1024, Some(Duration::from_secs(15 * 60)))
+//!     .with_max_sessions(1000, SessionEviction::LeastRecentlyUsed);
+//! let rustmcp = RustMCP::new().with_settings(settings);
+//! assert_eq!(rustmcp.session_metrics().active, 0);
+//! ```
+
+use log::{debug, info, warn};
+use parking_lot::{Mutex, MutexGuard};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+use crate::server::connections::{Connection, SessionInfo};
+use crate::server::lifecycle::{Lifecycle, LifecycleState};
+use crate::server::notifications::Notifier;
+use crate::settings::Settings;
+
+/// 默认大小上限（1MB）
+pub(crate) const DEFAULT_MAX_BYTES: usize = 1024 * 1024;
+
+/// 默认空闲超时（1小时）
+pub(crate) const DEFAULT_TTL: Duration = Duration::from_secs(3600);
+
+/// 会话存储的限制
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SessionLimits {
+    /// 所有键和值序列化后的总字节数上限
+    pub max_bytes: usize,
+    /// 空闲超时，为`None`时不过期
+    pub ttl: Option<Duration>,
+}
+
+impl SessionLimits {
+    /// 创建会话限制
+    pub fn new(max_bytes: usize, ttl: Option<Duration>) -> Self {
+        Self { max_bytes, ttl }
+    }
+}
+
+impl Default for SessionLimits {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_BYTES, Some(DEFAULT_TTL))
+    }
+}
+
+/// 会话存储错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionError {
+    /// 写入后会超过大小上限
+    CapacityExceeded {
+        /// 写入后的总字节数
+        size: usize,
+        /// 上限
+        limit: usize,
+    },
+}
+
+impl std::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionError::CapacityExceeded { size, limit } => {
+                write!(f, "Session storage limit exceeded ({} bytes, limit {})", size, limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<SessionError> for String {
+    fn from(e: SessionError) -> Self {
+        e.to_string()
+    }
+}
+
+#[derive(Debug)]
+struct SessionData {
+    values: HashMap<String, (Value, usize)>,
+    size: usize,
+    last_used: Instant,
+}
+
+/// 会话的键值存储
+///
+/// 克隆得到的是同一个存储的句柄。
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+    data: Arc<Mutex<SessionData>>,
+    limits: SessionLimits,
+    ephemeral: bool,
+}
+
+impl SessionStore {
+    /// 创建空的会话存储
+    pub fn new(limits: SessionLimits) -> Self {
+        Self {
+            data: Arc::new(Mutex::new(SessionData {
+                values: HashMap::new(),
+                size: 0,
+                last_used: Instant::now(),
+            })),
+            limits,
+            ephemeral: false,
+        }
+    }
+
+    /// 创建临时存储，用于没有会话的请求
+    pub(crate) fn ephemeral(limits: SessionLimits) -> Self {
+        Self {
+            ephemeral: true,
+            ..Self::new(limits)
+        }
+    }
+
+    /// 是否为临时存储（数据不会保留到下一次调用）
+    pub fn is_ephemeral(&self) -> bool {
+        self.ephemeral
+    }
+
+    /// 读取值
+    pub fn get(&self, key: &str) -> Option<Value> {
+        self.access("get").values.get(key).map(|(value, _)| value.clone())
+    }
+
+    /// 写入值，返回之前的值；写入后超过大小上限时不修改存储并返回错误
+    pub fn set(&self, key: impl Into<String>, value: Value) -> Result<Option<Value>, SessionError> {
+        let key = key.into();
+        let entry_size = key.len() + serde_json::to_vec(&value).map(|bytes| bytes.len()).unwrap_or(0);
+        let mut data = self.access("set");
+        let previous_size = data.values.get(&key).map(|(_, size)| *size).unwrap_or(0);
+        let size = data.size - previous_size + entry_size;
+        if size > self.limits.max_bytes {
+            return Err(SessionError::CapacityExceeded {
+                size,
+                limit: self.limits.max_bytes,
+            });
+        }
+        data.size = size;
+        Ok(data.values.insert(key, (value, entry_size)).map(|(previous, _)| previous))
+    }
+
+    /// 删除值，返回删除的值
+    pub fn remove(&self, key: &str) -> Option<Value> {
+        let mut data = self.access("remove");
+        let (value, size) = data.values.remove(key)?;
+        data.size -= size;
+        Some(value)
+    }
+
+    /// 清空存储
+    pub fn clear(&self) {
+        let mut data = self.access("clear");
+        data.values.clear();
+        data.size = 0;
+    }
+
+    /// 当前所有键和值的总字节数
+    pub fn size_bytes(&self) -> usize {
+        self.access("size_bytes").size
+    }
+
+    /// 清空数据，不刷新访问时间
+    fn clear_data(&self) {
+        let mut data = self.data.lock();
+        data.values.clear();
+        data.size = 0;
+    }
+
+    /// 加锁并刷新访问时间，已过期的数据先清空
+    fn access(&self, operation: &str) -> MutexGuard<'_, SessionData> {
+        if self.ephemeral {
+            warn!("Session storage '{}' used without a session; data will not outlive this call", operation);
+        }
+        let mut data = self.data.lock();
+        if self.limits.ttl.is_some_and(|ttl| data.last_used.elapsed() > ttl) {
+            data.values.clear();
+            data.size = 0;
+        }
+        data.last_used = Instant::now();
+        data
+    }
+}
+
+/// HTTP会话的存储、初始化状态和连接信息
+pub(crate) type HttpSession = (SessionStore, Lifecycle, Arc<Connection>);
+
+/// 默认同时保留的HTTP会话数上限
+pub(crate) const DEFAULT_MAX_SESSIONS: usize = 10_000;
+
+/// 会话数量达到上限时选择丢弃哪个会话
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionEviction {
+    /// 最久没有请求的会话
+    #[default]
+    LeastRecentlyUsed,
+    /// 最早创建的会话
+    Oldest,
+}
+
+/// HTTP会话的计数，参见[`RustMCP::session_metrics`](crate::RustMCP::session_metrics)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionMetrics {
+    /// 当前的会话数
+    pub active: usize,
+    /// 创建过的会话数
+    pub created: u64,
+    /// 因空闲超时丢弃的会话数
+    pub reaped: u64,
+    /// 因会话数达到上限丢弃的会话数
+    pub evicted: u64,
+    /// 重新`initialize`而重置的会话数
+    pub reset: u64,
+}
+
+struct HttpSessionEntry {
+    session: HttpSession,
+    created: Instant,
+    last_seen: Instant,
+}
+
+impl HttpSessionEntry {
+    fn new(session_id: &str, settings: &Settings) -> Self {
+        let now = Instant::now();
+        Self {
+            session: (
+                SessionStore::new(settings.session_limits()),
+                Lifecycle::new(),
+                Connection::http(session_id, settings.replay_limits()),
+            ),
+            created: now,
+            last_seen: now,
+        }
+    }
+
+    /// 是否超过空闲超时没有请求
+    fn is_expired(&self) -> bool {
+        self.session.0.limits.ttl.is_some_and(|ttl| self.last_seen.elapsed() > ttl)
+    }
+}
+
+/// 按会话ID保存的HTTP会话
+///
+/// 过期的会话在访问注册表时和后台清理任务中丢弃，参见[`SessionRegistry::start_reaper`]。
+#[derive(Default)]
+pub(crate) struct SessionRegistry {
+    stores: Mutex<HashMap<String, HttpSessionEntry>>,
+    created: AtomicU64,
+    reaped: AtomicU64,
+    evicted: AtomicU64,
+    reset: AtomicU64,
+    reaper_started: AtomicBool,
+    forwarder_started: AtomicBool,
+}
+
+impl std::fmt::Debug for SessionRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionRegistry").field("metrics", &self.metrics()).finish()
+    }
+}
+
+impl SessionRegistry {
+    /// 创建会话，返回服务器生成的会话ID和会话；同时丢弃已过期的会话
+    ///
+    /// 会话数已达到[`Settings::max_sessions`]时，按淘汰策略丢弃一个会话后再创建。
+    pub(crate) fn create(&self, settings: &Settings) -> (String, HttpSession) {
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let mut stores = self.stores.lock();
+        self.remove_expired(&mut stores);
+        if let Some(max_sessions) = settings.max_sessions {
+            while stores.len() >= max_sessions.max(1) {
+                let victim = match settings.session_eviction {
+                    SessionEviction::LeastRecentlyUsed => stores.iter().min_by_key(|(_, entry)| entry.last_seen),
+                    SessionEviction::Oldest => stores.iter().min_by_key(|(_, entry)| entry.created),
+                };
+                let Some(victim) = victim.map(|(id, _)| id.clone()) else {
+                    break;
+                };
+                info!("Evicting session '{}': session limit of {} reached", victim, max_sessions);
+                if let Some(entry) = stores.remove(&victim) {
+                    entry.session.0.clear_data();
+                }
+                self.evicted.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.created.fetch_add(1, Ordering::Relaxed);
+        let entry = HttpSessionEntry::new(&session_id, settings);
+        let session = entry.session.clone();
+        stores.insert(session_id.clone(), entry);
+        (session_id, session)
+    }
+
+    /// 获取会话存储、初始化状态和连接信息，会话不存在或已过期时返回`None`
+    pub(crate) fn get(&self, session_id: &str) -> Option<HttpSession> {
+        let mut stores = self.stores.lock();
+        self.remove_expired(&mut stores);
+        let entry = stores.get_mut(session_id)?;
+        entry.last_seen = Instant::now();
+        Some(entry.session.clone())
+    }
+
+    /// 重置已经初始化过的会话：丢弃数据、初始化状态和连接信息，会话ID保持不变；会话被重置时返回`true`
+    pub(crate) fn reset(&self, session_id: &str, settings: &Settings) -> bool {
+        let mut stores = self.stores.lock();
+        self.remove_expired(&mut stores);
+        let Some(entry) = stores.get_mut(session_id) else {
+            return false;
+        };
+        if entry.session.1.state() == LifecycleState::Uninitialized {
+            return false;
+        }
+        entry.session.0.clear_data();
+        *entry = HttpSessionEntry::new(session_id, settings);
+        self.reset.fetch_add(1, Ordering::Relaxed);
+        info!("Session '{}' re-initialized; previous state discarded", session_id);
+        true
+    }
+
+    /// 丢弃所有过期的会话，返回丢弃的数量
+    pub(crate) fn reap(&self) -> usize {
+        self.remove_expired(&mut self.stores.lock())
+    }
+
+    fn remove_expired(&self, stores: &mut HashMap<String, HttpSessionEntry>) -> usize {
+        let before = stores.len();
+        stores.retain(|session_id, entry| {
+            if !entry.is_expired() {
+                return true;
+            }
+            debug!("Reaping idle session '{}'", session_id);
+            entry.session.0.clear_data();
+            false
+        });
+        let reaped = before - stores.len();
+        self.reaped.fetch_add(reaped as u64, Ordering::Relaxed);
+        reaped
+    }
+
+    /// 启动后台清理任务，每隔`interval`丢弃过期的会话
+    ///
+    /// 每个注册表只启动一次；没有tokio运行时时什么也不做。任务只持有注册表的弱引用，
+    /// 注册表被丢弃后任务结束。
+    pub(crate) fn start_reaper(self: &Arc<Self>, interval: Duration) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        if self.reaper_started.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let registry = Arc::downgrade(self);
+        let interval = interval.max(Duration::from_millis(1));
+        runtime.spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(registry) = registry.upgrade() else {
+                    break;
+                };
+                registry.reap();
+            }
+        });
+    }
+
+    /// 启动后台任务，把广播的通知写入每个HTTP会话的通知记录
+    ///
+    /// 与[`SessionRegistry::start_reaper`]相同，每个注册表只启动一次，没有tokio运行时时什么也不做。
+    pub(crate) fn start_forwarder(self: &Arc<Self>, notifier: &Notifier) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        if self.forwarder_started.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let registry = Arc::downgrade(self);
+        let mut notifications = notifier.subscribe();
+        runtime.spawn(async move {
+            loop {
+                let notification = match notifications.recv().await {
+                    Ok(notification) => notification,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("{} notifications were not recorded for HTTP sessions", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let Some(registry) = registry.upgrade() else {
+                    break;
+                };
+                if let Ok(text) = serde_json::to_string(&notification) {
+                    registry.publish(&text);
+                }
+            }
+        });
+    }
+
+    /// 把通知写入每个HTTP会话的通知记录
+    fn publish(&self, text: &str) {
+        for entry in self.stores.lock().values() {
+            if let Some(events) = entry.session.2.events() {
+                events.push(text.to_string());
+            }
+        }
+    }
+
+    /// 会话计数
+    pub(crate) fn metrics(&self) -> SessionMetrics {
+        SessionMetrics {
+            active: self.count(),
+            created: self.created.load(Ordering::Relaxed),
+            reaped: self.reaped.load(Ordering::Relaxed),
+            evicted: self.evicted.load(Ordering::Relaxed),
+            reset: self.reset.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 未过期的会话数量
+    pub(crate) fn count(&self) -> usize {
+        self.stores.lock().values().filter(|entry| !entry.is_expired()).count()
+    }
+
+    /// 未过期的会话信息
+    pub(crate) fn list(&self) -> Vec<SessionInfo> {
+        self.stores
+            .lock()
+            .values()
+            .filter(|entry| !entry.is_expired())
+            .map(|entry| entry.session.2.info())
+            .collect()
+    }
+
+    /// 终止所有会话并丢弃其数据，返回终止的数量
+    pub(crate) fn remove_all(&self) -> usize {
+        let entries: Vec<_> = self.stores.lock().drain().map(|(_, entry)| entry).collect();
+        for entry in &entries {
+            entry.session.0.clear_data();
+        }
+        entries.len()
+    }
+
+    /// 终止会话并丢弃其数据，会话存在时返回`true`
+    pub(crate) fn remove(&self, session_id: &str) -> bool {
+        match self.stores.lock().remove(session_id) {
+            Some(entry) => {
+                entry.session.0.clear_data();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use serde_json::json;
+
+    use super::*;
+    use crate::server::test_support::{call, get, initialize, post, request, send, spawn_server, WsClient};
+    use crate::server::{create_app, FunctionTool, RustMCP};
+
+    /// `remember`把`value`写入会话，`recall`读出
+    fn memory_server() -> RustMCP {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("remember")
+                .handler_with_context(|ctx, args| {
+                    let value = args.and_then(|args| args.get("value").cloned()).unwrap_or_default();
+                    ctx.session().set("value", value).map_err(|e| e.to_string())?;
+                    Ok(json!(ctx.session().is_ephemeral()))
+                })
+                .build(),
+        );
+        rustmcp.add_tool(FunctionTool::builder().name("recall").handler_with_context(|ctx, _| Ok(ctx.session().get("value").unwrap_or_default())).build());
+        rustmcp
+    }
+
+    fn text(response: &Value) -> &str {
+        response["result"]["content"][0]["text"].as_str().unwrap_or_else(|| panic!("{}", response))
+    }
+
+    #[test]
+    fn store_tracks_size_and_enforces_the_cap() {
+        let store = SessionStore::new(SessionLimits::new(20, None));
+        assert_eq!(store.set("a", json!("12345")), Ok(None));
+        assert_eq!(store.size_bytes(), 8);
+        assert_eq!(store.set("a", json!(1)), Ok(Some(json!("12345"))));
+        assert_eq!(store.size_bytes(), 2);
+        assert_eq!(store.set("b", json!("x".repeat(30))), Err(SessionError::CapacityExceeded { size: 35, limit: 20 }));
+        // 超过上限的写入不修改存储
+        assert_eq!(store.get("b"), None);
+        assert_eq!(store.size_bytes(), 2);
+        assert_eq!(store.remove("a"), Some(json!(1)));
+        assert_eq!(store.size_bytes(), 0);
+    }
+
+    #[test]
+    fn idle_data_expires_after_the_ttl() {
+        let store = SessionStore::new(SessionLimits::new(1024, Some(Duration::from_millis(20))));
+        store.set("k", json!(true)).unwrap();
+        assert_eq!(store.get("k"), Some(json!(true)));
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(store.get("k"), None);
+        assert_eq!(store.size_bytes(), 0);
+    }
+
+    #[tokio::test]
+    async fn ws_connections_keep_values_between_calls_and_are_isolated() {
+        let addr = spawn_server(&memory_server()).await;
+        let mut alice = WsClient::initialized(addr).await;
+        let mut bob = WsClient::initialized(addr).await;
+
+        assert_eq!(text(&alice.call(1, "tools/call", json!({ "name": "remember", "arguments": { "value": "alice" } })).await), "false");
+        assert_eq!(text(&bob.call(1, "tools/call", json!({ "name": "remember", "arguments": { "value": "bob" } })).await), "false");
+        assert_eq!(text(&alice.call(2, "tools/call", json!({ "name": "recall" })).await), "\"alice\"");
+        assert_eq!(text(&bob.call(2, "tools/call", json!({ "name": "recall" })).await), "\"bob\"");
+
+        // 新的连接从空的存储开始
+        let mut carol = WsClient::initialized(addr).await;
+        assert_eq!(text(&carol.call(1, "tools/call", json!({ "name": "recall" })).await), "null");
+    }
+
+    #[tokio::test]
+    async fn sessionless_http_calls_get_an_ephemeral_store() {
+        let app = create_app(memory_server());
+        let response = call(&app, "tools/call", json!({ "name": "remember", "arguments": { "value": 1 } })).await;
+        assert_eq!(text(&response), "true");
+        assert_eq!(text(&call(&app, "tools/call", json!({ "name": "recall" })).await), "null");
+    }
+
+    #[tokio::test]
+    async fn http_sessions_are_reset_evicted_and_reaped() {
+        let settings = Settings::new()
+            .with_session_limits(1024, Some(Duration::from_secs(1)))
+            .with_max_sessions(2, SessionEviction::LeastRecentlyUsed)
+            .with_session_reap_interval(Duration::from_millis(100));
+        let rustmcp = RustMCP::new().with_settings(settings);
+        let app = create_app(rustmcp.clone());
+        let params = |name: &str| json!({ "clientInfo": { "name": name } });
+        let send = |session: &str, body: Value| {
+            let (app, session) = (app.clone(), session.to_string());
+            async move { post(&app, "/mcp", &[("mcp-session-id", &session)], &body).await.2 }
+        };
+
+        // 重试initialize重置会话，而不是报错
+        let a = initialize(&app, params("first")).await;
+        let retried = send(&a, request(1, "initialize", params("retry"))).await;
+        assert!(retried["result"]["protocolVersion"].is_string());
+        assert_eq!(rustmcp.sessions()[0].client_info.as_ref().unwrap()["name"], "retry");
+        assert_eq!(rustmcp.sessions()[0].requests, 1);
+
+        // 第三个会话淘汰最久没有请求的会话
+        let b = initialize(&app, params("b")).await;
+        send(&a, request(2, "ping", json!({}))).await;
+        let c = initialize(&app, params("c")).await;
+        let mut ids: Vec<String> = rustmcp.sessions().into_iter().map(|s| s.session_id).collect();
+        ids.sort();
+        let mut expected = vec![a, c];
+        expected.sort();
+        assert_eq!(ids, expected);
+        assert_eq!(send(&b, request(3, "ping", json!({}))).await["error"]["code"], -32600);
+
+        // 空闲超时后由后台任务清理
+        tokio::time::sleep(Duration::from_millis(1300)).await;
+        let metrics = rustmcp.session_metrics();
+        assert_eq!(metrics.active, 0);
+        assert_eq!((metrics.created, metrics.reset, metrics.evicted, metrics.reaped), (3, 1, 1, 2));
+    }
+
+    #[tokio::test]
+    async fn initialize_creates_sessions_and_unknown_ids_are_rejected() {
+        let rustmcp = memory_server();
+        let app = create_app(rustmcp.clone());
+        assert_eq!(rustmcp.settings().max_sessions, Some(DEFAULT_MAX_SESSIONS));
+
+        // 会话ID由服务器生成，在initialize的响应头中返回
+        let (status, headers, _) = post(&app, "/mcp", &[], &request(0, "initialize", json!({}))).await;
+        assert_eq!(status, StatusCode::OK);
+        let id = headers["mcp-session-id"].to_str().unwrap().to_string();
+        assert!(uuid::Uuid::parse_str(&id).is_ok(), "{}", id);
+        let session = [("mcp-session-id", id.as_str())];
+        post(&app, "/mcp", &session, &request(1, "tools/call", json!({ "name": "remember", "arguments": { "value": 7 } }))).await;
+        let recalled = post(&app, "/mcp", &session, &request(2, "tools/call", json!({ "name": "recall" }))).await.2;
+        assert_eq!(text(&recalled), "7");
+        // 之后的响应不再带会话ID
+        assert!(post(&app, "/mcp", &session, &request(3, "ping", json!({}))).await.1.get("mcp-session-id").is_none());
+
+        // 客户端给出的ID不创建会话
+        let unknown = [("mcp-session-id", "made-up")];
+        let (status, _, response) = post(&app, "/mcp", &unknown, &request(4, "ping", json!({}))).await;
+        assert_eq!((status, response["id"].clone(), response["error"]["code"].clone()), (StatusCode::NOT_FOUND, json!(4), json!(-32600)));
+        let (status, headers, _) = post(&app, "/mcp", &unknown, &request(5, "initialize", json!({}))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(headers.get("mcp-session-id").is_none());
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert_eq!(post(&app, "/mcp", &unknown, &notification).await.0, StatusCode::NOT_FOUND);
+        assert_eq!(get(&app, "/mcp", &unknown).await.0, StatusCode::NOT_FOUND);
+        assert_eq!(post(&app, "/mcp/call-tool", &unknown, &json!({ "name": "recall" })).await.0, StatusCode::NOT_FOUND);
+        assert_eq!(rustmcp.session_metrics().active, 1);
+
+        // 终止后的会话同样被拒绝
+        let end = Request::delete("/mcp").header("mcp-session-id", &id).body(Body::empty()).unwrap();
+        assert_eq!(send(&app, end).await.0, StatusCode::NO_CONTENT);
+        assert_eq!(post(&app, "/mcp", &session, &request(6, "ping", json!({}))).await.0, StatusCode::NOT_FOUND);
+    }
+}
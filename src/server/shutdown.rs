@@ -0,0 +1,175 @@
+//! 优雅关闭
+//!
+//! 部署时服务器要下线，客户端应该尽快知道并连接其他实例，而不是在工具调用中途超时。
+//! [`RustMCP::shutdown`](crate::RustMCP::shutdown)按顺序：
+//! 1. 向每个会话（WebSocket、HTTP的SSE通知流、标准输入输出）广播
+//!    [`notifications/x-rustmcp/shutdown`](crate::protocol::SHUTDOWN_NOTIFICATION)，
+//!    参数为`{"reason": ..., "graceMs": ...}`；
+//! 2. 不再接受新的工具调用，返回[`-32008`](crate::protocol::SERVER_SHUTTING_DOWN)错误
+//!    （HTTP接口返回`503`），`data.reason`为关闭原因；其他方法照常处理；
+//! 3. 等待正在执行的工具调用完成，最多等待`grace`；
+//! 4. 以`1001`（Going Away）关闭WebSocket连接，结束HTTP会话，SSE通知流随之结束。
+//!
+//! 返回值表示正在执行的调用是否都在宽限期内完成。之后服务器对象不再接受工具调用，
+//! 通常与`axum::serve(...).with_graceful_shutdown(...)`配合，在停止监听前调用。
+//!
+//! ```rust,no_run
+//! use rustmcp::{create_app, RustMCP};
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let rustmcp = RustMCP::new();
+//! let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await.unwrap();
+//! let server = rustmcp.clone();
+//! axum::serve(listener, create_app(rustmcp.clone()))
+//!     .with_graceful_shutdown(async move {
+//!         tokio::signal::ctrl_c().await.ok();
+//!         server.shutdown("deploy", Duration::from_secs(30)).await;
+//!     })
+//!     .await
+//!     .unwrap();
+//! # }
+//! ```
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+
+use crate::server::error::McpError;
+
+/// 关闭状态和正在执行的工具调用数
+#[derive(Debug, Default)]
+pub(crate) struct Shutdown {
+    draining: AtomicBool,
+    reason: Mutex<String>,
+    in_flight: AtomicUsize,
+    /// 正在执行的调用数降到0时唤醒
+    idle: Notify,
+}
+
+/// 正在执行的工具调用，释放时计数减一
+#[derive(Debug)]
+pub(crate) struct InFlight(Arc<Shutdown>);
+
+impl Shutdown {
+    /// 开始关闭，之后的[`Shutdown::enter`]返回错误
+    pub(crate) fn begin(&self, reason: &str) {
+        *self.reason.lock() = reason.to_string();
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    /// 是否已经开始关闭
+    pub(crate) fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// 登记一个工具调用；已经开始关闭时返回[`McpError::ShuttingDown`]
+    ///
+    /// 先计数再检查状态，与[`Shutdown::drained`]的检查顺序相反，两者不会互相错过。
+    pub(crate) fn enter(self: &Arc<Self>) -> Result<InFlight, McpError> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlight(self.clone());
+        if self.is_draining() {
+            return Err(McpError::ShuttingDown {
+                reason: self.reason.lock().clone(),
+            });
+        }
+        Ok(guard)
+    }
+
+    /// 等待正在执行的调用完成，超过`grace`时返回`false`
+    pub(crate) async fn drained(&self, grace: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + grace;
+        loop {
+            let notified = self.idle.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.in_flight.load(Ordering::SeqCst) == 0 {
+                return true;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return self.in_flight.load(Ordering::SeqCst) == 0;
+            }
+        }
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{SinkExt, StreamExt};
+    use serde_json::{json, Value};
+    use std::time::Duration;
+    use tokio_tungstenite::tungstenite::Message;
+
+    use crate::server::test_support::{request, spawn_server};
+    use crate::{FunctionTool, RustMCP};
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn shutdown_notifies_rejects_new_calls_and_waits_for_running_ones() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::simple("slow", "Takes a while", |_| {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(json!("done"))
+        }));
+        rustmcp.add_tool(FunctionTool::simple("fast", "Returns at once", |_| Ok(json!("ok"))));
+        let url = format!("ws://{}/mcp/ws", spawn_server(&rustmcp).await);
+
+        let mut clients = Vec::new();
+        for _ in 0..2 {
+            let (mut client, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+            client.send(Message::text(request(1, "initialize", json!({})).to_string())).await.unwrap();
+            client.next().await.unwrap().unwrap();
+            clients.push(client);
+        }
+        let (mut busy, mut idle) = (clients.remove(0), clients.remove(0));
+        let call = |name: &str| Message::text(request(2, "tools/call", json!({ "name": name })).to_string());
+
+        // 一个调用正在执行时开始关闭
+        busy.send(call("slow")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let shutdown = tokio::spawn({
+            let rustmcp = rustmcp.clone();
+            async move { rustmcp.shutdown("deploy", Duration::from_secs(5)).await }
+        });
+
+        // 每个会话都收到通知，新的调用被拒绝
+        let notification: Value = serde_json::from_str(idle.next().await.unwrap().unwrap().to_text().unwrap()).unwrap();
+        assert_eq!(notification["method"], "notifications/x-rustmcp/shutdown");
+        assert_eq!(notification["params"], json!({ "reason": "deploy", "graceMs": 5000 }));
+        idle.send(call("fast")).await.unwrap();
+        let rejected: Value = serde_json::from_str(idle.next().await.unwrap().unwrap().to_text().unwrap()).unwrap();
+        assert_eq!(rejected["error"]["code"], -32008);
+        assert_eq!(rejected["error"]["data"]["reason"], "deploy");
+
+        // 正在执行的调用照常完成，之后连接以1001关闭
+        let mut methods = Vec::new();
+        let code = loop {
+            match busy.next().await {
+                Some(Ok(Message::Text(text))) => {
+                    let message: Value = serde_json::from_str(&text).unwrap();
+                    match message["method"].as_str() {
+                        Some(method) => methods.push(method.to_string()),
+                        None => assert!(message["result"]["content"][0]["text"].as_str().unwrap().contains("done")),
+                    }
+                }
+                Some(Ok(Message::Close(Some(frame)))) => break u16::from(frame.code),
+                other => panic!("unexpected message {:?}", other),
+            }
+        };
+        assert_eq!(methods, ["notifications/x-rustmcp/shutdown"]);
+        assert_eq!(code, 1001);
+        assert!(shutdown.await.unwrap());
+        assert_eq!(rustmcp.mcp_call_tool("fast", None).await.unwrap_err().code(), -32008);
+    }
+}
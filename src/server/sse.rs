@@ -0,0 +1,373 @@
+//! HTTP会话的通知流：`GET /mcp`
+//!
+//! 带`Mcp-Session-Id`请求头的`GET /mcp`打开该会话的SSE（`text/event-stream`）通知流，
+//! 服务器广播的通知（例如`notifications/tools/list_changed`）通过它发给HTTP客户端。
+//! 会话由`initialize`创建，会话ID不存在时返回404。
+//!
+//! 每个会话的每条通知有单调递增的事件ID，最近的通知保存在有界的重放缓冲区中
+//! （[`Settings::with_sse_replay`](crate::Settings::with_sse_replay)，默认256条、1 MiB），
+//! 客户端没有连接时也会缓冲。断线后带`Last-Event-ID`请求头重新连接时：
+//! - 先按顺序重放该ID之后缓冲的通知，再继续实时发送，不会遗漏或重复；
+//! - 该ID已经不在缓冲区中（通知太多被淘汰、会话被重置或ID无法识别）时，先发送一个
+//!   `resync`事件，数据为`{"lastEventId": ...}`，事件ID为最新的ID。客户端需要重新获取
+//!   工具、资源和提示列表等完整状态，之后从这个ID继续。
+//!
+//! 不带`Last-Event-ID`的连接先收到一个只有事件ID、没有数据的事件，客户端断线后用它作为`Last-Event-ID`。
+//! 同一会话同时只有一个通知流，新的连接会结束之前的连接；会话终止、过期或被淘汰时通知流结束。
+//!
+//! 通知流一开始发送`retry:`字段（[`Settings::with_sse_retry`](crate::Settings::with_sse_retry)，默认3秒），
+//! 支持的客户端断线后按它等待再重新连接。代理常会静默断开长时间空闲的连接，所以没有通知时每隔
+//! [`Settings::with_sse_keep_alive`](crate::Settings::with_sse_keep_alive)（默认25秒）发送一个`: keepalive`注释，
+//! 客户端应忽略注释行。
+//!
+//! ```rust
+//! use rustmcp::{RustMCP, Settings};
+//! use std::time::Duration;
+//!
+//! // 每个会话保留最近64条、最多256 KiB的通知供断线重连时重放，空闲10秒发送保活注释
+//! let settings = Settings::new()
+//!     .with_sse_replay(64, 256 * 1024)
+//!     .with_sse_keep_alive(Duration::from_secs(10));
+//! let rustmcp = RustMCP::new().with_settings(settings);
+//! ```
+
+use axum::response::sse::Event;
+use futures::Stream;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::server::trace::TraceTap;
+
+/// 默认重放缓冲区的通知数
+pub(crate) const DEFAULT_REPLAY_EVENTS: usize = 256;
+
+/// 默认重放缓冲区的字节数（1 MiB）
+pub(crate) const DEFAULT_REPLAY_BYTES: usize = 1024 * 1024;
+
+/// 通知流的重放缓冲区限制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ReplayLimits {
+    pub events: usize,
+    pub bytes: usize,
+}
+
+#[derive(Debug, Default)]
+struct Buffer {
+    events: VecDeque<(u64, String)>,
+    bytes: usize,
+}
+
+/// 单个HTTP会话的通知记录
+///
+/// 序号从1开始，事件ID为`<epoch>-<序号>`；`epoch`在每个记录创建时随机生成，
+/// 会话被重置后旧的事件ID不会被误认为新记录中的位置。
+#[derive(Debug)]
+pub(crate) struct EventLog {
+    epoch: String,
+    limits: ReplayLimits,
+    buffer: Mutex<Buffer>,
+    /// 最新的序号，通知流通过它等待新的通知
+    latest: watch::Sender<u64>,
+    /// 最近打开的通知流的编号，更早的通知流据此结束
+    streams: AtomicU64,
+}
+
+/// 通知流从哪里开始
+enum Start {
+    /// 没有`Last-Event-ID`，从最新的通知之后开始
+    Fresh,
+    /// 从该序号之后开始
+    After(u64),
+    /// `Last-Event-ID`无法识别
+    Unknown(String),
+}
+
+impl EventLog {
+    pub(crate) fn new(limits: ReplayLimits) -> Arc<Self> {
+        let epoch = uuid::Uuid::new_v4().simple().to_string();
+        Arc::new(Self {
+            epoch: epoch[..8].to_string(),
+            limits,
+            buffer: Mutex::default(),
+            latest: watch::channel(0).0,
+            streams: AtomicU64::new(0),
+        })
+    }
+
+    /// 记录一条通知，超出限制时淘汰最早的通知，最新的一条总是保留
+    pub(crate) fn push(&self, text: String) {
+        let mut buffer = self.buffer.lock();
+        let seq = *self.latest.borrow() + 1;
+        buffer.bytes += text.len();
+        buffer.events.push_back((seq, text));
+        while buffer.events.len() > 1
+            && (buffer.events.len() > self.limits.events.max(1) || buffer.bytes > self.limits.bytes)
+        {
+            if let Some((_, text)) = buffer.events.pop_front() {
+                buffer.bytes -= text.len();
+            }
+        }
+        self.latest.send_replace(seq);
+    }
+
+    fn event_id(&self, seq: u64) -> String {
+        format!("{}-{}", self.epoch, seq)
+    }
+
+    /// 解析`Last-Event-ID`
+    fn start(&self, last_event_id: Option<&str>) -> Start {
+        let Some(last_event_id) = last_event_id else {
+            return Start::Fresh;
+        };
+        match last_event_id.trim().split_once('-') {
+            Some((epoch, seq)) if epoch == self.epoch => match seq.parse::<u64>() {
+                Ok(seq) if seq <= *self.latest.borrow() => Start::After(seq),
+                _ => Start::Unknown(last_event_id.to_string()),
+            },
+            _ => Start::Unknown(last_event_id.to_string()),
+        }
+    }
+
+    /// 序号`cursor`之后的通知；其中有通知已被淘汰时返回`None`
+    fn after(&self, cursor: u64) -> Option<Vec<(u64, String)>> {
+        let buffer = self.buffer.lock();
+        let oldest = buffer.events.front().map_or(*self.latest.borrow() + 1, |(seq, _)| *seq);
+        if cursor + 1 < oldest {
+            return None;
+        }
+        Some(buffer.events.iter().filter(|(seq, _)| *seq > cursor).cloned().collect())
+    }
+}
+
+/// 通知流的状态
+struct StreamState {
+    log: Weak<EventLog>,
+    latest: watch::Receiver<u64>,
+    generation: u64,
+    cursor: u64,
+    pending: VecDeque<Event>,
+    trace: TraceTap,
+}
+
+/// 打开会话的通知流，参见模块文档
+///
+/// 设置了`retry`时先发送只有`retry:`字段的事件；发出的通知记入`trace`。
+pub(crate) fn stream(
+    log: &Arc<EventLog>,
+    last_event_id: Option<&str>,
+    retry: Option<Duration>,
+    trace: TraceTap,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let generation = log.streams.fetch_add(1, Ordering::AcqRel) + 1;
+    // 唤醒之前的通知流，让它们结束
+    log.latest.send_modify(|_| {});
+    let mut latest = log.latest.subscribe();
+    latest.borrow_and_update();
+    let mut pending = VecDeque::new();
+    if let Some(retry) = retry {
+        pending.push_back(Event::default().retry(retry));
+    }
+    let cursor = match log.start(last_event_id) {
+        Start::Fresh => {
+            let cursor = *latest.borrow();
+            pending.push_back(Event::default().id(log.event_id(cursor)));
+            cursor
+        }
+        Start::After(seq) => seq,
+        Start::Unknown(last_event_id) => {
+            let cursor = *latest.borrow();
+            pending.push_back(resync(log, cursor, &last_event_id));
+            cursor
+        }
+    };
+    let state = StreamState {
+        log: Arc::downgrade(log),
+        latest,
+        generation,
+        cursor,
+        pending,
+        trace,
+    };
+    futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(event) = state.pending.pop_front() {
+                return Some((Ok(event), state));
+            }
+            {
+                let log = state.log.upgrade()?;
+                if log.streams.load(Ordering::Acquire) != state.generation {
+                    return None;
+                }
+                state.latest.borrow_and_update();
+                match log.after(state.cursor) {
+                    Some(events) if !events.is_empty() => {
+                        for (seq, text) in events {
+                            state.trace.outbound(&text);
+                            state.pending.push_back(Event::default().id(log.event_id(seq)).data(text));
+                            state.cursor = seq;
+                        }
+                        continue;
+                    }
+                    Some(_) => {}
+                    // 读得太慢，未发送的通知已被淘汰
+                    None => {
+                        let cursor = *state.latest.borrow();
+                        state.pending.push_back(resync(&log, cursor, &log.event_id(state.cursor)));
+                        state.cursor = cursor;
+                        continue;
+                    }
+                }
+            }
+            // 会话被丢弃时发送端随之释放，等待返回错误
+            state.latest.changed().await.ok()?;
+        }
+    })
+}
+
+/// 告诉客户端需要重新获取完整状态的事件
+fn resync(log: &EventLog, cursor: u64, last_event_id: &str) -> Event {
+    log::debug!("Event '{}' is no longer buffered; asking the client to resync", last_event_id);
+    Event::default()
+        .event("resync")
+        .id(log.event_id(cursor))
+        .data(serde_json::json!({ "lastEventId": last_event_id }).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::Request;
+    use futures::StreamExt;
+    use serde_json::{json, Value};
+    use std::time::Duration;
+    use tower::Service;
+
+    use crate::server::test_support::initialize;
+    use crate::{create_app, RustMCP, Settings};
+
+    /// 会话的通知流
+    struct Events {
+        body: axum::body::BodyDataStream,
+        buffer: String,
+    }
+
+    impl Events {
+        async fn open(app: &axum::Router, session: &str, last_event_id: Option<&str>) -> Events {
+            let mut request = Request::get("/mcp").header("mcp-session-id", session);
+            if let Some(id) = last_event_id {
+                request = request.header("last-event-id", id);
+            }
+            let response = app.clone().call(request.body(Body::empty()).unwrap()).await.unwrap();
+            assert_eq!(response.headers()["content-type"], "text/event-stream");
+            Events { body: response.into_body().into_data_stream(), buffer: String::new() }
+        }
+
+        /// 读取到下一个空行为止的一帧
+        async fn frame(&mut self) -> String {
+            loop {
+                if let Some(end) = self.buffer.find("\n\n") {
+                    return self.buffer.drain(..end + 2).collect::<String>().trim_end().to_string();
+                }
+                let chunk = self.body.next().await.unwrap().unwrap();
+                self.buffer.push_str(&String::from_utf8_lossy(&chunk));
+            }
+        }
+
+        /// 下一个事件的(事件类型, ID, 数据)，跳过没有ID的帧
+        async fn next(&mut self) -> (Option<String>, String, String) {
+            loop {
+                let event = self.frame().await;
+                let field = |name: &str| event.lines().find_map(|line| line.strip_prefix(name)).map(|value| value.trim_start().to_string());
+                if let Some(id) = field("id:") {
+                    return (field("event:"), id, field("data:").unwrap_or_default());
+                }
+            }
+        }
+
+        /// 下一条通知的序号和事件ID
+        async fn next_message(&mut self) -> (u64, String) {
+            let (event, id, data) = self.next().await;
+            assert_eq!(event, None);
+            let message: Value = serde_json::from_str(&data).unwrap();
+            (message["params"]["n"].as_u64().unwrap(), id)
+        }
+    }
+
+    #[tokio::test]
+    async fn reconnecting_replays_missed_notifications_or_resyncs() {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_sse_replay(4, 64 * 1024));
+        let app = create_app(rustmcp.clone());
+        let session = initialize(&app, json!({})).await;
+        let notify = |n: u64| rustmcp.notifier().notify("notifications/message", Some(json!({ "n": n })));
+
+        // 新的连接先收到只有事件ID的事件
+        let mut events = Events::open(&app, &session, None).await;
+        let (_, mut last_id, data) = events.next().await;
+        assert!(data.is_empty());
+        let mut seen = Vec::new();
+        for n in 0..3 {
+            notify(n);
+            let (n, id) = events.next_message().await;
+            seen.push(n);
+            last_id = id;
+        }
+
+        // 断线期间的通知在重新连接后按顺序重放，然后继续实时发送
+        drop(events);
+        notify(3);
+        notify(4);
+        let mut events = Events::open(&app, &session, Some(&last_id)).await;
+        for _ in 0..2 {
+            seen.push(events.next_message().await.0);
+        }
+        notify(5);
+        let (n, id) = events.next_message().await;
+        seen.push(n);
+        assert_eq!(seen, [0, 1, 2, 3, 4, 5]);
+
+        // 缓冲区只保留4条，错过更多通知的客户端收到resync事件
+        drop(events);
+        for n in 6..12 {
+            notify(n);
+        }
+        let mut events = Events::open(&app, &session, Some(&id)).await;
+        let (event, resync_id, data) = events.next().await;
+        assert_eq!(event.as_deref(), Some("resync"));
+        assert_eq!(serde_json::from_str::<Value>(&data).unwrap()["lastEventId"], id.as_str());
+        notify(12);
+        assert_eq!(events.next_message().await.0, 12);
+
+        // 从resync事件的ID继续不会收到重复的通知
+        drop(events);
+        let mut events = Events::open(&app, &session, Some(&resync_id)).await;
+        assert_eq!(events.next_message().await.0, 12);
+    }
+
+    #[tokio::test]
+    async fn idle_streams_send_keepalive_comments() {
+        let settings = Settings::new()
+            .with_sse_keep_alive(Duration::from_millis(100))
+            .with_sse_retry(Duration::from_millis(1500));
+        let rustmcp = RustMCP::new().with_settings(settings);
+        let app = create_app(rustmcp.clone());
+        let session = initialize(&app, json!({})).await;
+        let mut events = Events::open(&app, &session, None).await;
+
+        assert_eq!(events.frame().await, "retry:1500");
+        assert!(events.frame().await.starts_with("id:"));
+        // 空闲超过两个间隔，收到两个保活注释
+        assert_eq!(events.frame().await, ": keepalive");
+        assert_eq!(events.frame().await, ": keepalive");
+
+        // 通知照常送达，之后继续保活
+        rustmcp.notifier().notify("notifications/message", Some(json!({ "n": 1 })));
+        let event = events.frame().await;
+        assert!(event.contains(r#"data: {"jsonrpc":"2.0","method":"notifications/message","params":{"n":1}}"#), "{}", event);
+        assert_eq!(events.frame().await, ": keepalive");
+    }
+}
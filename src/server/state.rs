@@ -0,0 +1,63 @@
+//! 共享的应用状态
+//!
+//! 按类型存放的状态表，通过[`RustMCP::insert_state`](crate::RustMCP::insert_state)注册，
+//! 在工具、资源和提示中通过[`Context::state`](crate::Context::state)读取。
+//! 每种类型只保存一个值，值以`Arc`共享，克隆状态表只复制指针。
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 按类型存放的状态表
+#[derive(Clone, Default)]
+pub struct StateMap {
+    values: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl StateMap {
+    /// 创建空的状态表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 插入状态，返回之前同类型的值
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) -> Option<Arc<T>> {
+        self.insert_arc(Arc::new(value))
+    }
+
+    /// 插入已共享的状态，返回之前同类型的值
+    pub fn insert_arc<T: Send + Sync + 'static>(&mut self, value: Arc<T>) -> Option<Arc<T>> {
+        self.values
+            .insert(TypeId::of::<T>(), value)
+            .and_then(|previous| previous.downcast::<T>().ok())
+    }
+
+    /// 获取状态，没有该类型的值时返回`None`
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.values
+            .get(&TypeId::of::<T>())
+            .cloned()
+            .and_then(|value| value.downcast::<T>().ok())
+    }
+
+    /// 是否包含该类型的状态
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.values.contains_key(&TypeId::of::<T>())
+    }
+
+    /// 状态数量
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl std::fmt::Debug for StateMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StateMap").field("len", &self.values.len()).finish()
+    }
+}
@@ -0,0 +1,108 @@
+//! 标准输入输出传输
+//!
+//! 每行一个JSON-RPC消息：从标准输入读取请求，把响应和服务器通知写到标准输出。
+//! 日志不能写到标准输出，否则会破坏协议流。
+
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::protocol::JsonRpcRequest;
+use crate::server::notifications::{self, Push};
+use crate::server::{dispatch, RequestContext, RustMCP, SessionChannel, SessionStore, TransportKind};
+
+/// 在标准输入输出上运行MCP服务器，直到标准输入关闭
+pub async fn serve_stdio(rustmcp: Arc<RustMCP>) -> std::io::Result<()> {
+    serve_lines(rustmcp, tokio::io::stdin(), tokio::io::stdout()).await
+}
+
+/// 在任意按行读写的流上运行MCP服务器
+///
+/// 对方停止读取、发送队列被响应占满时返回错误。
+pub async fn serve_lines<R, W>(rustmcp: Arc<RustMCP>, reader: R, mut writer: W) -> std::io::Result<()>
+where
+    R: tokio::io::AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    if let Err(errors) = rustmcp.check_startup() {
+        let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid registrations: {}", errors.join("; ")),
+        ));
+    }
+
+    // 响应和通知都通过同一个有界队列写出，保证每行完整
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let outbox = rustmcp.outbox(TransportKind::Stdio, &session_id);
+    let trace = rustmcp.tracer.tap(TransportKind::Stdio, Some(&session_id));
+    let registration = rustmcp.register_connection(&session_id, TransportKind::Stdio, outbox.clone());
+    let connection = registration.connection();
+    let request_context = Arc::new(RequestContext {
+        session_id: Some(session_id),
+        back_channel: Some(SessionChannel::outbox(outbox.clone())),
+        session: Some(SessionStore::new(rustmcp.settings().session_limits())),
+        lifecycle_policy: rustmcp.settings().lifecycle_policy,
+        connection: Some(connection.clone()),
+        ..RequestContext::new(TransportKind::Stdio)
+    });
+    let writer_outbox = outbox.clone();
+    let writer_handle = tokio::spawn(async move {
+        while let Some(line) = writer_outbox.next().await {
+            let written = async {
+                writer.write_all(line.as_bytes()).await?;
+                writer.write_all(b"\n").await?;
+                writer.flush().await
+            };
+            if let Err(e) = written.await {
+                // 输出已关闭，停止读取请求
+                writer_outbox.close();
+                return Err(e);
+            }
+        }
+        Ok::<(), std::io::Error>(())
+    });
+
+    let notification_handle = tokio::spawn(notifications::forward(rustmcp.notifier().subscribe(), outbox.clone()));
+
+    let mut lines = BufReader::new(reader).lines();
+    loop {
+        // 服务器强制断开时不再等待输入
+        let line = tokio::select! {
+            line = lines.next_line() => line?,
+            _ = outbox.closed() => break,
+        };
+        let Some(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+        trace.inbound(&line);
+        let response = match JsonRpcRequest::parse(&line) {
+            // 没有id的消息是通知，不需要响应
+            Ok(request) if request.is_notification() => continue,
+            Ok(request) => dispatch::dispatch_request(&rustmcp, request, request_context.clone()).await,
+            Err(response) => *response,
+        };
+        if let Ok(text) = serde_json::to_string(&response) {
+            if outbox.push(text, false) == Push::Closed {
+                break;
+            }
+        }
+    }
+
+    notification_handle.abort();
+    drop(registration);
+    if connection.is_disconnected() {
+        writer_handle.abort();
+        return Ok(());
+    }
+    if outbox.is_closed() {
+        // 写出失败时返回写出错误；队列溢出时写出任务可能阻塞在不再读取的输出上，直接放弃
+        writer_handle.abort();
+        return match writer_handle.await {
+            Ok(Err(e)) => Err(e),
+            _ => Err(std::io::Error::other("peer stopped reading; outbound queue overflowed")),
+        };
+    }
+    outbox.finish();
+    writer_handle.await.map_err(std::io::Error::other)?
+}
@@ -0,0 +1,222 @@
+//! 大资源的分块读取
+//!
+//! [`FunctionResource::from_stream_fn`](crate::FunctionResource::from_stream_fn)创建的资源按字节流读取，
+//! 内容不会一次性放在内存中：
+//!
+//! - `GET /mcp/resources/read?uri=...`：内容作为分块的HTTP响应体返回，按客户端读取的速度生成；
+//! - WebSocket和stdio上的`resources/read`：内容按[`Settings::resource_chunk_bytes`](crate::Settings::resource_chunk_bytes)
+//!   切成块，除最后一块外每块作为一条`notifications/resources/chunk`通知发送，最后一块在响应中返回。
+//!   发送前等待连接的发送队列空出位置，内存占用不超过发送队列长度乘以块大小；
+//! - 普通HTTP上的`resources/read`没有回传通道，内容不超过一块时正常返回，否则返回错误，
+//!   提示改用上面两种方式。
+//!
+//! 内容不超过一块时，响应与普通资源相同。分块时每块是一个`contents`条目，`_meta.chunk`为从0开始的序号，
+//! 响应中的最后一块还带有`_meta.totalChunks`；通知的`requestId`为对应请求的id：
+//!
+//! ```json
+//! {"jsonrpc":"2.0","method":"notifications/resources/chunk","params":{"requestId":2,"contents":[{"uri":"data://big","mimeType":"text/plain","text":"...","_meta":{"chunk":0}}]}}
+//! {"jsonrpc":"2.0","id":2,"result":{"contents":[{"uri":"data://big","mimeType":"text/plain","text":"...","_meta":{"chunk":1,"totalChunks":2}}]}}
+//! ```
+//!
+//! 文本类型（`text/*`以及JSON、XML、YAML、JavaScript）的块放在`text`中，在UTF-8字符边界切分；
+//! 其他类型放在`blob`中，使用base64编码。
+//!
+//! ```rust
+//! use axum::body::Bytes;
+//! use rustmcp::FunctionResource;
+//!
+//! let resource = FunctionResource::from_stream_fn("data://big", "text/plain", |_ctx| {
+//!     futures::stream::iter((0..80).map(|_| Ok(Bytes::from(vec![b'a'; 64 * 1024]))))
+//! });
+//! ```
+
+use base64::Engine;
+use futures::StreamExt;
+use serde_json::Value;
+
+use crate::protocol::{JsonRpcNotification, RequestId};
+use crate::server::error::McpError;
+use crate::server::notifications::Delivery;
+use crate::server::resources::ResourceStream;
+use crate::server::RequestContext;
+
+/// 分块通知的方法名
+pub const CHUNK_NOTIFICATION: &str = "notifications/resources/chunk";
+
+/// 是否按文本发送
+fn is_text(mime_type: &str) -> bool {
+    let mime_type = mime_type.to_ascii_lowercase();
+    mime_type.starts_with("text/") || ["json", "xml", "yaml", "javascript"].iter().any(|kind| mime_type.contains(kind))
+}
+
+/// 把字节流切成固定大小的块，文本在UTF-8字符边界切分
+struct Chunker {
+    buffer: Vec<u8>,
+    chunk_bytes: usize,
+    text: bool,
+}
+
+impl Chunker {
+    fn new(chunk_bytes: usize, text: bool) -> Self {
+        Self {
+            buffer: Vec::new(),
+            chunk_bytes: chunk_bytes.max(1),
+            text,
+        }
+    }
+
+    /// 放入字节，返回已经凑满的块
+    fn push(&mut self, bytes: &[u8]) -> Vec<Vec<u8>> {
+        self.buffer.extend_from_slice(bytes);
+        let mut chunks = Vec::new();
+        while self.buffer.len() >= self.chunk_bytes {
+            let mut end = self.chunk_bytes;
+            if self.text {
+                // 块末尾是不完整的字符时留到下一块
+                if let Err(e) = std::str::from_utf8(&self.buffer[..end]) {
+                    if e.error_len().is_none() && e.valid_up_to() > 0 {
+                        end = e.valid_up_to();
+                    }
+                }
+            }
+            let rest = self.buffer.split_off(end);
+            chunks.push(std::mem::replace(&mut self.buffer, rest));
+        }
+        chunks
+    }
+
+    /// 剩下的字节，没有时为`None`
+    fn finish(self) -> Option<Vec<u8>> {
+        (!self.buffer.is_empty()).then_some(self.buffer)
+    }
+}
+
+/// 读取流式资源，返回`resources/read`的结果
+///
+/// 内容超过一块时，除最后一块外的块通过回传通道发送；没有回传通道时返回错误。
+pub(crate) async fn read_stream(
+    uri: &str,
+    mime_type: &str,
+    mut stream: ResourceStream,
+    chunk_bytes: usize,
+    request_id: Option<&RequestId>,
+    request_context: &RequestContext,
+) -> Result<Value, McpError> {
+    let text = is_text(mime_type);
+    let entry = |bytes: Vec<u8>| {
+        let mut entry = serde_json::json!({ "uri": uri, "mimeType": mime_type });
+        if text {
+            entry["text"] = Value::String(String::from_utf8_lossy(&bytes).into_owned());
+        } else {
+            entry["blob"] = Value::String(base64::engine::general_purpose::STANDARD.encode(bytes));
+        }
+        entry
+    };
+    let read_error = |message: String| McpError::ResourceRead {
+        uri: uri.to_string(),
+        message,
+    };
+
+    let mut chunker = Chunker::new(chunk_bytes, text);
+    // 最近一块先留着，确定还有后续内容时才作为通知发出
+    let mut pending: Option<Vec<u8>> = None;
+    let mut sent = 0u64;
+    let send = |bytes: Vec<u8>, sent: u64| {
+        let channel = request_context.back_channel.clone().ok_or_else(|| {
+            read_error(format!(
+                "Resource is larger than {} bytes; read it from GET /mcp/resources/read, or over WebSocket or stdio",
+                chunk_bytes
+            ))
+        });
+        let mut contents = entry(bytes);
+        contents["_meta"] = serde_json::json!({ "chunk": sent });
+        let notification = JsonRpcNotification::new(
+            CHUNK_NOTIFICATION,
+            Some(serde_json::json!({ "requestId": request_id, "contents": [contents] })),
+        );
+        async move {
+            let channel = channel?;
+            channel.writable().await;
+            match channel.send(&notification) {
+                Delivery::Delivered => Ok(()),
+                Delivery::NotDelivered => Err(McpError::ResourceRead {
+                    uri: uri.to_string(),
+                    message: "Connection closed while streaming the resource".to_string(),
+                }),
+            }
+        }
+    };
+
+    while let Some(bytes) = stream.next().await {
+        let bytes = bytes.map_err(read_error)?;
+        for chunk in chunker.push(&bytes) {
+            if let Some(previous) = pending.replace(chunk) {
+                send(previous, sent).await?;
+                sent += 1;
+            }
+        }
+    }
+    if let Some(rest) = chunker.finish() {
+        if let Some(previous) = pending.replace(rest) {
+            send(previous, sent).await?;
+            sent += 1;
+        }
+    }
+
+    let mut last = entry(pending.unwrap_or_default());
+    if sent > 0 {
+        last["_meta"] = serde_json::json!({ "chunk": sent, "totalChunks": sent + 1 });
+    }
+    Ok(serde_json::json!({ "contents": [last] }))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Bytes;
+    use serde_json::{json, Value};
+
+    use crate::server::test_support::{call, get, request, stdio};
+    use crate::{create_app, FunctionResource, RustMCP, Settings};
+
+    const BLOCK: usize = 64 * 1024;
+    const BLOCKS: usize = 80; // 5 MB
+
+    #[tokio::test]
+    async fn large_resource_is_chunked_per_transport() {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_resource_chunk_bytes(1024 * 1024));
+        rustmcp.add_resource(FunctionResource::from_stream_fn("data://big", "text/plain", |_ctx| {
+            futures::stream::iter((0..BLOCKS).map(|i| Ok(Bytes::from(vec![b'a' + (i % 26) as u8; BLOCK]))))
+        }));
+        let expected: String = (0..BLOCKS).map(|i| ((b'a' + (i % 26) as u8) as char).to_string().repeat(BLOCK)).collect();
+
+        // stdio：4条分块通知和最后一块的响应
+        let lines = stdio(&rustmcp, &[request(1, "initialize", json!({})), request(2, "resources/read", json!({ "uri": "data://big" }))]).await;
+        let mut chunks = Vec::new();
+        let mut total = None;
+        for line in &lines {
+            let contents = match (line["method"].as_str(), line["id"].as_i64()) {
+                (Some("notifications/resources/chunk"), _) => &line["params"]["contents"][0],
+                (None, Some(2)) => {
+                    total = line["result"]["contents"][0]["_meta"]["totalChunks"].as_u64();
+                    &line["result"]["contents"][0]
+                }
+                _ => continue,
+            };
+            assert_eq!(contents["_meta"]["chunk"].as_u64(), Some(chunks.len() as u64));
+            chunks.push(contents["text"].as_str().unwrap().to_string());
+        }
+        assert_eq!(chunks.len(), 5);
+        assert_eq!(total, Some(5));
+        assert!(chunks.concat() == expected);
+
+        // REST：分块的HTTP响应体
+        let app = create_app(rustmcp);
+        let (_, headers, body) = get(&app, "/mcp/resources/read?uri=data%3A%2F%2Fbig", &[]).await;
+        assert_eq!(headers["content-type"], "text/plain");
+        assert!(body == Value::String(expected));
+
+        // 普通HTTP上的JSON-RPC没有回传通道，超过一块时返回错误
+        let response = call(&app, "resources/read", json!({ "uri": "data://big" })).await;
+        assert!(response["error"]["message"].as_str().unwrap().contains("/mcp/resources/read"));
+    }
+}
@@ -0,0 +1,171 @@
+//! 标签目录
+//!
+//! 工具、资源和提示的标签是自由的字符串，`util`、`utils`和`utility`可能同时存在，按标签筛选并不可靠。
+//! [`RustMCP::define_tag`](crate::RustMCP::define_tag)登记标签和说明。开启
+//! [`Settings::with_strict_tags`](crate::Settings::with_strict_tags)后，通过`RustMCP`的`try_add_*`方法
+//! 或管理接口注册带有未登记标签的项会失败，错误中给出相近的标签和所有已登记的标签；`add_*`方法遇到同样的错误时panic。
+//! 不开启时行为不变。
+//!
+//! [`RustMCP::list_tags`](crate::RustMCP::list_tags)返回登记的和正在使用的标签，以及每个标签被多少工具、资源和提示使用，
+//! 与`GET /mcp/tags`的响应相同。使用次数在列出时统计，随添加和删除变化。
+//! [`RustMCP::complete_tag`](crate::RustMCP::complete_tag)按前缀补全标签，可以作为标签筛选参数
+//! （例如[`search_tools`](crate::server::introspection)的`tag`）的补全值。
+//!
+//! ```rust
+//! use rustmcp::{FunctionTool, RustMCP, Settings};
+//! use serde_json::json;
+//!
+//! let rustmcp = RustMCP::new().with_settings(Settings::new().with_strict_tags(true));
+//! rustmcp.define_tag("util", "General helpers");
+//! let tool = FunctionTool::builder()
+//!     .name("trim")
+//!     .description("Trims text")
+//!     .tags(vec!["utils".to_string()])
+//!     .handler(|_| Ok(json!("ok")))
+//!     .build();
+//! assert!(rustmcp.try_add_tool(tool).unwrap_err().contains("did you mean 'util'?"));
+//! ```
+
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::server::error::suggest;
+
+/// 标签及其使用次数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagInfo {
+    /// 标签名称
+    pub name: String,
+    /// 登记的说明
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 是否通过[`RustMCP::define_tag`](crate::RustMCP::define_tag)登记，没有登记的标签只是被使用
+    pub defined: bool,
+    /// 使用该标签的工具数
+    pub tools: usize,
+    /// 使用该标签的资源数
+    pub resources: usize,
+    /// 使用该标签的提示数
+    pub prompts: usize,
+}
+
+/// 登记的标签和说明
+#[derive(Debug, Default)]
+pub(crate) struct TagCatalog {
+    tags: RwLock<BTreeMap<String, String>>,
+}
+
+impl TagCatalog {
+    /// 登记标签，已经登记的标签替换说明
+    pub(crate) fn define(&self, name: String, description: String) {
+        self.tags.write().insert(name, description);
+    }
+
+    /// 检查`kind` `name`的标签都已登记，否则返回列出相近和已知标签的错误
+    pub(crate) fn check(&self, kind: &str, name: &str, tags: &[String]) -> Result<(), String> {
+        let defined = self.tags.read();
+        let Some(tag) = tags.iter().find(|tag| !defined.contains_key(*tag)) else {
+            return Ok(());
+        };
+        let known: Vec<String> = defined.keys().cloned().collect();
+        let mut message = format!("{} '{}' uses undefined tag '{}'", kind, name, tag);
+        let suggestions = suggest(tag, &known);
+        if !suggestions.is_empty() {
+            let quoted: Vec<String> = suggestions.iter().map(|s| format!("'{}'", s)).collect();
+            message.push_str(&format!(" (did you mean {}?)", quoted.join(" or ")));
+        }
+        if known.is_empty() {
+            message.push_str("; no tags are defined");
+        } else {
+            message.push_str(&format!("; known tags: {}", known.join(", ")));
+        }
+        Err(message)
+    }
+
+    /// 登记的和使用中的标签，按名称排序
+    pub(crate) fn list<'a>(
+        &self,
+        tools: impl IntoIterator<Item = &'a [String]>,
+        resources: impl IntoIterator<Item = &'a [String]>,
+        prompts: impl IntoIterator<Item = &'a [String]>,
+    ) -> Vec<TagInfo> {
+        let mut tags: BTreeMap<String, TagInfo> = self
+            .tags
+            .read()
+            .iter()
+            .map(|(name, description)| (name.clone(), info(name, Some(description.clone()))))
+            .collect();
+        let mut count = |used: &'a [String], field: fn(&mut TagInfo) -> &mut usize| {
+            for tag in used {
+                *field(tags.entry(tag.clone()).or_insert_with(|| info(tag, None))) += 1;
+            }
+        };
+        tools.into_iter().for_each(|used| count(used, |tag| &mut tag.tools));
+        resources.into_iter().for_each(|used| count(used, |tag| &mut tag.resources));
+        prompts.into_iter().for_each(|used| count(used, |tag| &mut tag.prompts));
+        tags.into_values().collect()
+    }
+}
+
+/// 还没有使用次数的标签
+fn info(name: &str, description: Option<String>) -> TagInfo {
+    TagInfo {
+        name: name.to_string(),
+        defined: description.is_some(),
+        description,
+        tools: 0,
+        resources: 0,
+        prompts: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::server::test_support::get;
+    use crate::{create_app, FunctionPrompt, FunctionTool, RustMCP, Settings};
+
+    fn tool(name: &str, tag: &str) -> FunctionTool {
+        FunctionTool::builder()
+            .name(name)
+            .description("Does a thing")
+            .tags(vec![tag.to_string()])
+            .handler(|_| Ok(json!("ok")))
+            .build()
+    }
+
+    fn counts(rustmcp: &RustMCP) -> Vec<(String, usize, usize)> {
+        rustmcp.list_tags().iter().map(|tag| (tag.name.clone(), tag.tools, tag.prompts)).collect()
+    }
+
+    #[tokio::test]
+    async fn strict_tags_reject_undefined_tags_and_count_usage() {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_strict_tags(true));
+        rustmcp.define_tag("util", "General helpers");
+        rustmcp.define_tag("network", "Tools that reach other hosts");
+
+        // 未登记的标签被拒绝
+        assert_eq!(
+            rustmcp.try_add_tool(tool("trim", "utils")).unwrap_err(),
+            "Tool 'trim' uses undefined tag 'utils' (did you mean 'util'?); known tags: network, util"
+        );
+        assert!(rustmcp.mcp_list_tools().is_empty());
+
+        rustmcp.try_add_tool(tool("trim", "util")).unwrap();
+        rustmcp.try_add_tool(tool("fetch", "network")).unwrap();
+        let mut prompt = FunctionPrompt::simple("summarize", |_| Ok(vec![]));
+        prompt.tags = vec!["util".to_string()];
+        rustmcp.try_add_prompt(prompt).unwrap();
+
+        assert_eq!(counts(&rustmcp), [("network".to_string(), 1, 0), ("util".to_string(), 1, 1)]);
+        rustmcp.remove_tool("fetch");
+        assert_eq!(counts(&rustmcp), [("network".to_string(), 0, 0), ("util".to_string(), 1, 1)]);
+        assert_eq!(rustmcp.complete_tag("u"), ["util"]);
+
+        let (_, _, tags) = get(&create_app(rustmcp), "/mcp/tags", &[]).await;
+        assert_eq!(tags[1], json!({ "name": "util", "description": "General helpers", "defined": true, "tools": 1, "resources": 0, "prompts": 1 }));
+    }
+}
@@ -0,0 +1,184 @@
+//! crate内部测试共用的请求辅助函数
+
+use axum::body::Body;
+use axum::http::{HeaderMap, Request, StatusCode};
+use axum::Router;
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tower::Service;
+
+use crate::server::{create_app, serve, RustMCP};
+
+/// 等待响应的最长时间
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 收集`warn`及以上级别日志的记录器
+struct Capture(Mutex<Vec<String>>);
+
+impl log::Log for Capture {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Warn
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGS: Capture = Capture(Mutex::new(Vec::new()));
+
+/// 开始收集警告日志，可以多次调用
+pub(crate) fn capture_warnings() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        log::set_logger(&LOGS).unwrap();
+        log::set_max_level(log::LevelFilter::Warn);
+    });
+}
+
+/// 已收集的包含`needle`的警告
+///
+/// 测试并行运行，共用一个记录器，只能按内容筛选属于自己的日志。
+pub(crate) fn warnings(needle: &str) -> Vec<String> {
+    LOGS.0.lock().unwrap().iter().filter(|line| line.contains(needle)).cloned().collect()
+}
+
+/// 构造JSON-RPC请求
+pub(crate) fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+/// 发送请求到应用，返回状态、响应头和响应体，响应体不是JSON时为字符串，空响应体为`null`
+pub(crate) async fn send(app: &Router, request: Request<Body>) -> (StatusCode, HeaderMap, Value) {
+    let response = app.clone().call(request).await.unwrap();
+    let (status, headers) = (response.status(), response.headers().clone());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = match body.is_empty() {
+        true => Value::Null,
+        false => serde_json::from_slice(&body).unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).into_owned())),
+    };
+    (status, headers, body)
+}
+
+/// `GET path`
+pub(crate) async fn get(app: &Router, path: &str, headers: &[(&str, &str)]) -> (StatusCode, HeaderMap, Value) {
+    let mut builder = Request::get(path);
+    for (name, value) in headers {
+        builder = builder.header(*name, *value);
+    }
+    send(app, builder.body(Body::empty()).unwrap()).await
+}
+
+/// `POST path`，请求体为JSON
+pub(crate) async fn post(app: &Router, path: &str, headers: &[(&str, &str)], body: &Value) -> (StatusCode, HeaderMap, Value) {
+    let mut builder = Request::post(path).header("content-type", "application/json");
+    for (name, value) in headers {
+        builder = builder.header(*name, *value);
+    }
+    send(app, builder.body(Body::from(body.to_string())).unwrap()).await
+}
+
+/// 不带会话用`POST /mcp`调用一个方法，返回JSON-RPC响应
+pub(crate) async fn call(app: &Router, method: &str, params: Value) -> Value {
+    post(app, "/mcp", &[], &request(1, method, params)).await.2
+}
+
+/// 不带会话用`POST /mcp`发送`initialize`，返回服务器生成的会话ID
+pub(crate) async fn initialize(app: &Router, params: Value) -> String {
+    let (status, headers, response) = post(app, "/mcp", &[], &request(0, "initialize", params)).await;
+    assert_eq!(status, StatusCode::OK, "{}", response);
+    headers["mcp-session-id"].to_str().unwrap().to_string()
+}
+
+/// 在stdio传输上依次发送消息，输入结束后返回所有输出行
+pub(crate) async fn stdio(rustmcp: &RustMCP, messages: &[Value]) -> Vec<Value> {
+    let input: String = messages.iter().map(|message| format!("{}\n", message)).collect();
+    let (writer, mut reader) = tokio::io::duplex(1024 * 1024);
+    let server = tokio::spawn(crate::server::stdio::serve_lines(Arc::new(rustmcp.clone()), std::io::Cursor::new(input.into_bytes()), writer));
+    let mut output = String::new();
+    reader.read_to_string(&mut output).await.unwrap();
+    server.await.unwrap().unwrap();
+    output.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+}
+
+/// 在本机临时端口上启动服务
+pub(crate) async fn spawn_server(rustmcp: &RustMCP) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(serve(listener, create_app(rustmcp.clone())));
+    addr
+}
+
+/// 通过真实的TCP连接发送`POST path`，返回状态码和JSON响应体
+pub(crate) async fn post_tcp(addr: SocketAddr, path: &str, headers: &[(&str, &str)], body: &Value) -> (u16, Value) {
+    let body = body.to_string();
+    let mut head = format!("POST {} HTTP/1.1\r\nhost: {}\r\nconnection: close\r\ncontent-type: application/json\r\ncontent-length: {}\r\n", path, addr, body.len());
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(format!("{}\r\n{}", head, body).as_bytes()).await.unwrap();
+    let mut response = String::new();
+    tokio::time::timeout(RESPONSE_TIMEOUT, stream.read_to_string(&mut response)).await.unwrap().unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    (status, serde_json::from_str(body).unwrap_or(Value::Null))
+}
+
+/// 测试用的WebSocket客户端
+pub(crate) struct WsClient {
+    stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl WsClient {
+    /// 连接`/mcp/ws`
+    pub(crate) async fn connect(addr: SocketAddr) -> Self {
+        let (stream, _) = tokio_tungstenite::connect_async(format!("ws://{}/mcp/ws", addr)).await.unwrap();
+        Self { stream }
+    }
+
+    /// 连接并完成initialize
+    pub(crate) async fn initialized(addr: SocketAddr) -> Self {
+        let mut client = Self::connect(addr).await;
+        let response = client.call(0, "initialize", json!({ "clientInfo": { "name": "test", "version": "1" } })).await;
+        assert!(response["result"].is_object(), "{}", response);
+        client
+    }
+
+    /// 发送一条消息
+    pub(crate) async fn send(&mut self, message: &Value) {
+        self.stream.send(Message::text(message.to_string())).await.unwrap();
+    }
+
+    /// 接收下一条文本消息
+    pub(crate) async fn recv(&mut self) -> Value {
+        loop {
+            let message = tokio::time::timeout(RESPONSE_TIMEOUT, self.stream.next()).await.unwrap().unwrap().unwrap();
+            if let Message::Text(text) = message {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    /// 发送请求，跳过通知，返回对应的响应
+    pub(crate) async fn call(&mut self, id: u64, method: &str, params: Value) -> Value {
+        self.send(&request(id, method, params)).await;
+        loop {
+            let message = self.recv().await;
+            if message["id"] == id {
+                return message;
+            }
+        }
+    }
+}
@@ -0,0 +1,317 @@
+//! 传输一致性测试
+//!
+//! 所有传输共享同一个分发器，但会话处理、通知投递等传输层代码仍然各自实现，可能逐渐出现差异。
+//! [`assert_transport_parity`]把同一组请求依次发送到三个地方，比较得到的响应：
+//! - 内存中的分发器，不经过任何传输；
+//! - 进程内的HTTP应用（`POST /mcp`，`initialize`之后的请求使用服务器返回的`Mcp-Session-Id`）；
+//! - 连接到本机临时端口的WebSocket（`/mcp/ws`）。
+//!
+//! HTTP上`initialize`之前的请求没有会话，默认的[`LifecyclePolicy::Auto`](crate::LifecyclePolicy::Auto)
+//! 对它们宽松处理；请求组中有`initialize`之前的请求时，服务器应使用`LifecyclePolicy::Strict`。
+//!
+//! 比较前规范化每次运行都会变化的字段：UUID形式的字符串（会话ID等）、RFC 3339时间戳和`retryAfterMs`。
+//! 有差异时panic，消息中列出出错的请求和两边不同的JSON路径。没有`id`的通知只发送，不比较。
+//!
+//! 三次运行使用同一个服务器实例：有副作用的工具（计数器、运行时注册的资源）在每次运行中看到的状态不同，
+//! 一致性测试中的处理函数应当是确定的。下游的自定义方法也可以用它检查。
+//!
+//! crate自己的测试用一组请求覆盖所有内置方法和扩展方法。下游的用法：
+//!
+//! ```rust
+//! use rustmcp::{FunctionTool, RustMCP};
+//! use serde_json::json;
+//!
+//! # #[tokio::main(flavor = "multi_thread", worker_threads = 2)]
+//! # async fn main() {
+//! let rustmcp = RustMCP::new();
+//! rustmcp.add_tool(FunctionTool::simple("echo", "Echoes the arguments", |args| Ok(json!(args))));
+//! let corpus = [
+//!     json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": { "protocolVersion": "2025-06-18" } }),
+//!     json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": { "name": "echo", "arguments": { "a": 1 } } }),
+//! ];
+//! let responses = rustmcp::testing::assert_transport_parity(&rustmcp, &corpus).await;
+//! assert_eq!(responses.len(), 2);
+//! # }
+//! ```
+
+use axum::body::Body;
+use axum::http::{HeaderValue, Method, Request};
+use futures::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+use tower::Service;
+
+use crate::protocol::JsonRpcRequest;
+use crate::server::{create_app, dispatch, serve, RequestContext, RustMCP, SessionStore, TransportKind};
+
+/// 等待一个WebSocket响应的时间
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 规范化时替换为占位符的键
+const VOLATILE_KEYS: &[&str] = &["retryAfterMs"];
+
+/// 通过内存中的分发器、HTTP和WebSocket发送同一组请求，响应不一致时panic
+///
+/// 返回规范化后的响应，每个带`id`的请求一个，顺序与请求相同。
+///
+/// # Panics
+///
+/// 任意两个传输的响应不同、WebSocket在10秒内没有响应，或者服务器无法启动时panic。
+pub async fn assert_transport_parity(rustmcp: &RustMCP, requests: &[Value]) -> Vec<Value> {
+    let memory = run_memory(rustmcp, requests).await;
+    let app = create_app(rustmcp.clone());
+    let http = run_http(app.clone(), requests).await;
+    let ws = run_ws(app, requests).await;
+
+    let mut report = Vec::new();
+    let expected: Vec<&Value> = requests.iter().filter(|request| request.get("id").is_some()).collect();
+    for (index, request) in expected.iter().enumerate() {
+        let mut lines = Vec::new();
+        let reference = memory.get(index).unwrap_or(&Value::Null);
+        for (name, responses) in [("http", &http), ("ws", &ws)] {
+            let mut paths = Vec::new();
+            diff("$", reference, responses.get(index).unwrap_or(&Value::Null), &mut paths);
+            if !paths.is_empty() {
+                lines.push(format!("  memory vs {}:", name));
+                lines.extend(paths.into_iter().map(|path| format!("    {}", path)));
+            }
+        }
+        if !lines.is_empty() {
+            report.push(format!("request {}:\n{}", request, lines.join("\n")));
+        }
+    }
+    if !report.is_empty() {
+        panic!("Transports disagree on {} request(s):\n{}", report.len(), report.join("\n"));
+    }
+    memory
+}
+
+/// 直接调用分发器，会话上下文与连接型传输相同
+async fn run_memory(rustmcp: &RustMCP, requests: &[Value]) -> Vec<Value> {
+    let settings = rustmcp.settings();
+    let request_context = Arc::new(RequestContext {
+        session_id: Some(uuid::Uuid::new_v4().to_string()),
+        session: Some(SessionStore::new(settings.session_limits())),
+        // 与带会话的HTTP和WebSocket使用同样的生命周期策略
+        lifecycle_policy: settings.lifecycle_policy.resolve(TransportKind::Ws, true),
+        ..RequestContext::new(TransportKind::Direct)
+    });
+    let mut responses = Vec::new();
+    for request in requests {
+        let response = match JsonRpcRequest::parse(&request.to_string()) {
+            Ok(request) if request.is_notification() => continue,
+            Ok(request) => dispatch::dispatch_request(rustmcp, request, request_context.clone()).await,
+            Err(response) => *response,
+        };
+        responses.push(normalize(serde_json::to_value(response).unwrap_or_default()));
+    }
+    responses
+}
+
+/// 通过进程内的HTTP应用发送请求
+async fn run_http(mut app: axum::Router, requests: &[Value]) -> Vec<Value> {
+    let mut session_id: Option<HeaderValue> = None;
+    let mut responses = Vec::new();
+    for request in requests {
+        let mut http_request = Request::builder()
+            .method(Method::POST)
+            .uri("/mcp")
+            .header("content-type", "application/json");
+        if let Some(id) = &session_id {
+            http_request = http_request.header("mcp-session-id", id);
+        }
+        let http_request = http_request.body(Body::from(request.to_string())).expect("valid HTTP request");
+        let response = app.call(http_request).await.expect("router is infallible");
+        if let Some(id) = response.headers().get("mcp-session-id") {
+            session_id = Some(id.clone());
+        }
+        if request.get("id").is_none() {
+            continue;
+        }
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap_or_default();
+        responses.push(normalize(serde_json::from_slice(&body).unwrap_or(Value::Null)));
+    }
+    if let Some(id) = session_id {
+        let delete = Request::builder()
+            .method(Method::DELETE)
+            .uri("/mcp")
+            .header("mcp-session-id", id)
+            .body(Body::empty())
+            .expect("valid HTTP request");
+        let _ = app.call(delete).await;
+    }
+    responses
+}
+
+/// 在本机临时端口上启动服务器，通过WebSocket发送请求
+async fn run_ws(app: axum::Router, requests: &[Value]) -> Vec<Value> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind a local port");
+    let url = format!("ws://{}/mcp/ws", listener.local_addr().expect("local address"));
+    let server = tokio::spawn(serve(listener, app));
+    let (mut client, _) = tokio_tungstenite::connect_async(url.as_str()).await.expect("connect over WebSocket");
+
+    let mut responses = Vec::new();
+    for request in requests {
+        client.send(Message::text(request.to_string())).await.expect("send over WebSocket");
+        if request.get("id").is_none() {
+            continue;
+        }
+        // 跳过服务器发出的通知，下一条响应就是这个请求的响应
+        let response = loop {
+            let message = tokio::time::timeout(RESPONSE_TIMEOUT, client.next())
+                .await
+                .unwrap_or_else(|_| panic!("No WebSocket response to {} within {:?}", request, RESPONSE_TIMEOUT));
+            let Some(Ok(Message::Text(text))) = message else {
+                panic!("WebSocket closed before responding to {}", request);
+            };
+            let value: Value = serde_json::from_str(&text).unwrap_or(Value::Null);
+            if value.get("method").is_none() {
+                break value;
+            }
+        };
+        responses.push(normalize(response));
+    }
+    let _ = client.close(None).await;
+    server.abort();
+    responses
+}
+
+/// 把每次运行都会变化的值替换为占位符
+pub(crate) fn normalize(value: Value) -> Value {
+    match value {
+        Value::String(text) if uuid::Uuid::try_parse(&text).is_ok() && text.len() == 36 => Value::String("<uuid>".to_string()),
+        Value::String(text) if is_timestamp(&text) => Value::String("<timestamp>".to_string()),
+        Value::Array(items) => Value::Array(items.into_iter().map(normalize).collect()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let value = if VOLATILE_KEYS.contains(&key.as_str()) { Value::String("<volatile>".to_string()) } else { normalize(value) };
+                    (key, value)
+                })
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// 是否以RFC 3339的`YYYY-MM-DDTHH:MM:SS`开头
+fn is_timestamp(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() >= 19
+        && bytes[..19].iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            10 => *b == b'T',
+            13 | 16 => *b == b':',
+            _ => b.is_ascii_digit(),
+        })
+}
+
+/// 列出两个值不同的JSON路径
+pub(crate) fn diff(path: &str, expected: &Value, actual: &Value, out: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(left), Value::Object(right)) => {
+            let keys: BTreeSet<&String> = left.keys().chain(right.keys()).collect();
+            for key in keys {
+                let path = format!("{}.{}", path, key);
+                match (left.get(key), right.get(key)) {
+                    (Some(left), Some(right)) => diff(&path, left, right, out),
+                    (Some(left), None) => out.push(format!("{}: {} != (missing)", path, left)),
+                    (None, Some(right)) => out.push(format!("{}: (missing) != {}", path, right)),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(left), Value::Array(right)) if left.len() == right.len() => {
+            for (index, (left, right)) in left.iter().zip(right).enumerate() {
+                diff(&format!("{}[{}]", path, index), left, right, out);
+            }
+        }
+        _ if expected != actual => out.push(format!("{}: {} != {}", path, expected, actual)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::server::methods::{BUILTIN_METHODS, EXTENSION_METHODS};
+    use crate::{FunctionPrompt, FunctionResource, FunctionTool, LifecyclePolicy, PromptMessage, Settings};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn all_methods_behave_the_same_on_every_transport() {
+        let mut rustmcp = RustMCP::new().with_settings(Settings::new().with_lifecycle_policy(LifecyclePolicy::Strict));
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("add")
+                .description("Adds two integers")
+                .input_schema(json!({
+                    "type": "object",
+                    "properties": { "a": { "type": "integer" }, "b": { "type": "integer" } },
+                    "required": ["a", "b"]
+                }))
+                .validate_input(true)
+                .handler(|args| {
+                    let args = args.unwrap_or_default();
+                    Ok(json!(args["a"].as_i64().unwrap() + args["b"].as_i64().unwrap()))
+                })
+                .build(),
+        );
+        rustmcp.add_tool(FunctionTool::simple("fail", "Always fails", |_| Err("boom".to_string())));
+        rustmcp.add_resource(FunctionResource::simple("memo://greeting", || Ok(json!("hello"))));
+        rustmcp.add_resource_resolver("db://", |uri: &str| {
+            let uri = uri.to_string();
+            Some(FunctionResource::simple(uri.clone(), move || Ok(json!(uri.clone()))))
+        });
+        rustmcp.add_prompt(FunctionPrompt::simple("welcome", |_| {
+            Ok(vec![PromptMessage::user("Hi")])
+        }));
+        rustmcp
+            .add_method_handler("x-myco/echo", |params, _ctx| async move { Ok(json!({ "echo": params })) })
+            .unwrap();
+
+        let request = |id: u64, method: &str, params: Value| json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let corpus = vec![
+            // 初始化之前的请求被拒绝
+            request(1, "tools/list", json!({})),
+            request(2, "initialize", json!({ "protocolVersion": "2025-06-18", "clientInfo": { "name": "parity", "version": "1" } })),
+            json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+            request(3, "tools/list", json!({})),
+            request(4, "tools/call", json!({ "name": "add", "arguments": { "a": 2, "b": 3 } })),
+            request(5, "tools/call", json!({ "name": "add", "arguments": { "a": "two" } })),
+            request(6, "tools/call", json!({ "name": "fail" })),
+            request(7, "tools/call", json!({ "name": "ad" })),
+            request(8, "resources/list", json!({})),
+            request(9, "resources/read", json!({ "uri": "memo://greeting" })),
+            request(10, "resources/read", json!({ "uri": "db://users/7" })),
+            request(11, "resources/read", json!({ "uri": "file://missing" })),
+            request(12, "resources/templates/list", json!({})),
+            request(13, "prompts/list", json!({})),
+            request(14, "prompts/get", json!({ "name": "welcome" })),
+            request(15, "prompts/get", json!({ "name": "farewell" })),
+            request(16, "x-rustmcp/tools/call_batch", json!({
+                "name": "add",
+                "calls": [{ "id": "a", "arguments": { "a": 1, "b": 1 } }, { "id": "b", "arguments": {} }]
+            })),
+            request(17, "x-myco/echo", json!({ "value": [1, 2, 3] })),
+            request(18, "ping", json!({})),
+            request(19, "no/such/method", json!({})),
+            request(20, "tools/call", json!([1, 2])),
+            request(21, "x-rustmcp/session/pending", json!({})),
+        ];
+        for method in BUILTIN_METHODS.iter().chain(EXTENSION_METHODS) {
+            assert!(corpus.iter().any(|r| r["method"] == *method), "corpus does not cover {}", method);
+        }
+
+        let responses = assert_transport_parity(&rustmcp, &corpus).await;
+        assert_eq!(responses.len(), 21);
+        assert_eq!(responses[0]["error"]["data"]["kind"], "not_initialized");
+        assert_eq!(responses[3]["result"]["content"][0]["text"], "5");
+        assert_eq!(responses[16]["result"], json!({ "echo": { "value": [1, 2, 3] } }));
+        assert_eq!(responses[20]["result"], json!({ "results": {} }));
+    }
+}
@@ -0,0 +1,289 @@
+//! 工具的启动和关闭钩子
+//!
+//! 依赖连接池等外部状态的工具可以在构建时声明钩子，而不是在工具函数里用`OnceCell`懒加载
+//! （懒加载把初始化失败推迟到第一次调用）：
+//! - [`FunctionToolBuilder::on_startup`](crate::FunctionToolBuilder::on_startup)：
+//!   [`run`](crate::run)在开始监听之前调用[`RustMCP::initialize_tools`](crate::RustMCP::initialize_tools)，
+//!   按工具名称顺序依次执行启动钩子，某个钩子失败时停止启动，错误中带有工具名称；
+//!   自己组装应用时在`serve`之前调用`initialize_tools`；
+//! - [`FunctionToolBuilder::on_shutdown`](crate::FunctionToolBuilder::on_shutdown)：
+//!   [`RustMCP::shutdown`](crate::RustMCP::shutdown)等待正在执行的调用完成后并发执行所有关闭钩子，
+//!   每个钩子最多等待[`Settings::with_tool_shutdown_timeout`](crate::Settings::with_tool_shutdown_timeout)（默认5秒）。
+//!
+//! 钩子的状态出现在`/ready`的`toolHooks`中；有启动钩子还没有成功执行时`/ready`返回`503`。
+//! 禁用的工具同样执行钩子，每个钩子最多成功执行一次。
+//!
+//! ```rust
+//! use rustmcp::FunctionTool;
+//! use serde_json::json;
+//!
+//! let tool = FunctionTool::builder()
+//!     .name("query")
+//!     .handler(|_| Ok(json!([])))
+//!     // 实际的实现在这里建立和关闭连接池
+//!     .on_startup(|_ctx| async { Ok(()) })
+//!     .on_shutdown(|| async {})
+//!     .build();
+//! ```
+
+use futures::future::BoxFuture;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::server::Context;
+
+/// 启动钩子
+pub(crate) type StartupHook = Arc<dyn Fn(Context) -> BoxFuture<'static, Result<(), String>> + Send + Sync>;
+
+/// 关闭钩子
+pub(crate) type ShutdownHook = Arc<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// 工具声明的钩子，克隆的工具共享同一组钩子
+#[derive(Clone, Default)]
+pub(crate) struct ToolHooks {
+    pub(crate) startup: Option<StartupHook>,
+    pub(crate) shutdown: Option<ShutdownHook>,
+}
+
+impl ToolHooks {
+    pub(crate) fn set_startup<F, Fut>(&mut self, hook: F)
+    where
+        F: Fn(Context) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.startup = Some(Arc::new(move |ctx| Box::pin(hook(ctx))));
+    }
+
+    pub(crate) fn set_shutdown<F, Fut>(&mut self, hook: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.shutdown = Some(Arc::new(move || Box::pin(hook())));
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.startup.is_none() && self.shutdown.is_none()
+    }
+}
+
+impl std::fmt::Debug for ToolHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolHooks")
+            .field("startup", &self.startup.is_some())
+            .field("shutdown", &self.shutdown.is_some())
+            .finish()
+    }
+}
+
+/// 钩子的执行状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookState {
+    /// 还没有执行
+    Pending,
+    /// 正在执行
+    Running,
+    /// 执行成功
+    Done,
+    /// 返回了错误
+    Failed,
+    /// 超过时限没有完成（只用于关闭钩子）
+    TimedOut,
+}
+
+/// 一个工具的钩子状态，没有声明的钩子为`None`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolHookStatus {
+    /// 启动钩子
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub startup: Option<HookState>,
+    /// 关闭钩子
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shutdown: Option<HookState>,
+    /// 启动钩子返回的错误
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 启动钩子失败，[`RustMCP::initialize_tools`](crate::RustMCP::initialize_tools)返回
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolStartupError {
+    /// 工具名称
+    pub tool: String,
+    /// 钩子返回的错误
+    pub message: String,
+}
+
+impl std::fmt::Display for ToolStartupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Startup hook of tool '{}' failed: {}", self.tool, self.message)
+    }
+}
+
+impl std::error::Error for ToolStartupError {}
+
+/// 各工具钩子的执行记录
+#[derive(Debug, Default)]
+pub(crate) struct HookRegistry {
+    statuses: Mutex<BTreeMap<String, ToolHookStatus>>,
+}
+
+impl HookRegistry {
+    /// 依次执行还没有成功的启动钩子，遇到失败时停止
+    pub(crate) async fn start(&self, tools: Vec<(String, ToolHooks)>) -> Result<(), ToolStartupError> {
+        for (name, hooks) in tools {
+            let Some(hook) = hooks.startup else { continue };
+            if self.statuses.lock().get(&name).and_then(|status| status.startup) == Some(HookState::Done) {
+                continue;
+            }
+            self.update(&name, |status| status.startup = Some(HookState::Running));
+            match hook(Context::new()).await {
+                Ok(()) => self.update(&name, |status| {
+                    status.startup = Some(HookState::Done);
+                    status.error = None;
+                }),
+                Err(message) => {
+                    self.update(&name, |status| {
+                        status.startup = Some(HookState::Failed);
+                        status.error = Some(message.clone());
+                    });
+                    return Err(ToolStartupError { tool: name, message });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 并发执行还没有执行过的关闭钩子，每个最多等待`timeout`
+    pub(crate) async fn stop(&self, tools: Vec<(String, ToolHooks)>, timeout: Duration) {
+        let pending = tools.into_iter().filter_map(|(name, hooks)| {
+            let hook = hooks.shutdown?;
+            let state = self.statuses.lock().get(&name).and_then(|status| status.shutdown);
+            if state.is_some_and(|state| state != HookState::Pending) {
+                return None;
+            }
+            self.update(&name, |status| status.shutdown = Some(HookState::Running));
+            Some(async move {
+                let state = match tokio::time::timeout(timeout, hook()).await {
+                    Ok(()) => HookState::Done,
+                    Err(_) => {
+                        log::warn!("Shutdown hook of tool '{}' did not finish within {}ms", name, timeout.as_millis());
+                        HookState::TimedOut
+                    }
+                };
+                self.update(&name, |status| status.shutdown = Some(state));
+            })
+        });
+        futures::future::join_all(pending.collect::<Vec<_>>()).await;
+    }
+
+    /// 当前工具的钩子状态，没有执行过的钩子为[`HookState::Pending`]
+    pub(crate) fn status(&self, tools: Vec<(String, ToolHooks)>) -> BTreeMap<String, ToolHookStatus> {
+        let statuses = self.statuses.lock();
+        tools
+            .into_iter()
+            .map(|(name, hooks)| {
+                let recorded = statuses.get(&name).cloned().unwrap_or_default();
+                let status = ToolHookStatus {
+                    startup: hooks.startup.map(|_| recorded.startup.unwrap_or(HookState::Pending)),
+                    shutdown: hooks.shutdown.map(|_| recorded.shutdown.unwrap_or(HookState::Pending)),
+                    error: recorded.error,
+                };
+                (name, status)
+            })
+            .collect()
+    }
+
+    fn update(&self, name: &str, change: impl FnOnce(&mut ToolHookStatus)) {
+        change(self.statuses.lock().entry(name.to_string()).or_default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::server::test_support::get;
+    use crate::{create_app, FunctionTool, RustMCP, Settings};
+
+    #[tokio::test]
+    async fn failing_startup_hooks_stop_run() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("query")
+                .handler(|_| Ok(json!([])))
+                .on_startup(|_ctx| async { Err("connection refused".to_string()) })
+                .build(),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let error = crate::run(rustmcp, listener).await.unwrap_err();
+        assert!(error.to_string().contains("'query'"));
+        assert!(error.to_string().contains("connection refused"));
+    }
+
+    #[tokio::test]
+    async fn hooks_gate_readiness_and_run_on_shutdown() {
+        let (connected, closed) = (Arc::new(AtomicBool::new(false)), Arc::new(AtomicBool::new(false)));
+        let settings = Settings::new().with_tool_shutdown_timeout(Duration::from_millis(200));
+        let rustmcp = RustMCP::new().with_settings(settings);
+        let (on_connect, on_close) = (connected.clone(), closed.clone());
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("query")
+                .handler(|_| Ok(json!([])))
+                .on_startup(move |_ctx| {
+                    let connected = on_connect.clone();
+                    async move {
+                        connected.store(true, Ordering::SeqCst);
+                        Ok(())
+                    }
+                })
+                .on_shutdown(move || {
+                    let closed = on_close.clone();
+                    async move {
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        closed.store(true, Ordering::SeqCst);
+                    }
+                })
+                .build(),
+        );
+        // 超过时限的关闭钩子不会拖住关闭
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("stuck")
+                .handler(|_| Ok(json!(null)))
+                .on_shutdown(std::future::pending)
+                .build(),
+        );
+        let app = create_app(rustmcp.clone());
+
+        // 启动钩子执行之前还没有就绪
+        let (status, _, body) = get(&app, "/ready", &[]).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["toolHooks"]["query"]["startup"], "pending");
+
+        rustmcp.initialize_tools().await.unwrap();
+        assert!(connected.load(Ordering::SeqCst));
+        let (status, _, body) = get(&app, "/ready", &[]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["toolHooks"]["query"], json!({ "startup": "done", "shutdown": "pending" }));
+
+        assert!(rustmcp.shutdown("deploy", Duration::from_secs(1)).await);
+        assert!(closed.load(Ordering::SeqCst));
+        let status = rustmcp.tool_hook_status();
+        assert_eq!(status["query"].shutdown, Some(HookState::Done));
+        assert_eq!(status["stuck"].shutdown, Some(HookState::TimedOut));
+    }
+}
@@ -1,11 +1,73 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use serde_json::Value;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::future::Future;
 use std::sync::Arc;
-use log::warn;
+use std::time::Duration;
+use log::{debug, warn};
+use sha2::{Digest, Sha256};
+use tokio::sync::Semaphore;
+
+use crate::server::Context;
+use crate::server::definitions::SchemaRegistry;
+use crate::server::error::{suggest, McpError};
+use crate::server::locale::{LanguageTag, Localizations};
+use crate::server::proxy::ProxyTarget;
+use crate::server::quota::{Quota, QuotaStatus};
+use crate::server::retry::{Backoff, RetryPolicy};
+use crate::server::schema::ToolArgs;
+use crate::server::secrets;
+use crate::server::serialization::{serialize_items, SerializationPolicy};
+use crate::server::tool_hooks::ToolHooks;
+use crate::server::validation::{coerce_arguments, validate_arguments, CompiledSchema};
 
 /// 工具函数类型定义
-pub type ToolFunction = Box<dyn Fn(Option<HashMap<String, Value>>) -> Result<Value, String> + Send + Sync>;
+pub type ToolFunction = Box<dyn Fn(&Context, Option<HashMap<String, Value>>) -> Result<Value, String> + Send + Sync>;
+
+/// 工具的实现
+///
+/// 反序列化得到的工具（例如导入的工具定义）没有实现，为`Unbound`；可以先注册，
+/// 再用[`ToolManager::bind_function`]或[`RustMCP::bind_tool_function`](crate::RustMCP::bind_tool_function)补上。
+/// 调用没有实现的工具返回[`McpError::ToolNotBound`]，列表中这类工具带有`_meta.bound: false`。
+#[derive(Clone, Default)]
+pub enum ToolBinding {
+    /// 本地的工具函数
+    Bound(Arc<ToolFunction>),
+    /// 还没有实现
+    #[default]
+    Unbound,
+    /// 转发到HTTP地址，参见[`proxy`](crate::server::proxy)
+    Remote(ProxyTarget),
+}
+
+impl ToolBinding {
+    /// 是否有可以调用的实现
+    pub fn is_bound(&self) -> bool {
+        !matches!(self, ToolBinding::Unbound)
+    }
+
+    /// 状态名称：`bound`、`unbound`或`remote`
+    pub fn state(&self) -> &'static str {
+        match self {
+            ToolBinding::Bound(_) => "bound",
+            ToolBinding::Unbound => "unbound",
+            ToolBinding::Remote(_) => "remote",
+        }
+    }
+}
+
+impl std::fmt::Debug for ToolBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToolBinding::Bound(_) => f.write_str("Bound"),
+            ToolBinding::Unbound => f.write_str("Unbound"),
+            ToolBinding::Remote(target) => f.debug_tuple("Remote").field(target).finish(),
+        }
+    }
+}
 
 /// 重复工具处理行为
 #[derive(Debug, Clone)]
@@ -17,7 +79,7 @@ pub enum DuplicateBehavior {
 }
 
 /// 工具注解结构体
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ToolAnnotations {
     /// 工具的人类可读标题
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -36,8 +98,62 @@ pub struct ToolAnnotations {
     pub open_world_hint: Option<bool>,
 }
 
+/// 工具的调用示例，序列化在工具列表的`_meta.examples`中
+///
+/// 开启[`Settings::validate_examples`](crate::Settings::validate_examples)时，启动校验按输入模式检查
+/// `arguments`、按输出模式检查`expected_output`，参见[`RustMCP::validate_examples`](crate::RustMCP::validate_examples)。
+///
+/// ```rust
+/// use rustmcp::{FunctionTool, RustMCP, Tool, ToolExample};
+/// use serde_json::json;
+///
+/// let tool = FunctionTool::builder()
+///     .name("add")
+///     .output_schema(json!({ "type": "object", "properties": { "sum": { "type": "integer" } } }))
+///     .example(ToolExample::new("Small numbers", json!({ "a": 1, "b": 2 })).with_expected_output(json!({ "sum": 3 })))
+///     .example(ToolExample::new("Stale output", json!({ "a": 1, "b": 2 })).with_expected_output(json!({ "sum": "3" })))
+///     .handler(|_| Ok(json!({ "sum": 3 })))
+///     .build();
+/// let listed = serde_json::to_value(Tool::from(&tool)).unwrap();
+/// assert_eq!(listed["_meta"]["examples"][0]["expectedOutput"], json!({ "sum": 3 }));
+///
+/// let rustmcp = RustMCP::new();
+/// rustmcp.add_tool(tool);
+/// let issues = rustmcp.validate_examples();
+/// assert_eq!(issues.len(), 1);
+/// assert!(issues[0].message.starts_with("example 'Stale output' does not match outputSchema"));
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolExample {
+    /// 示例说明
+    pub description: String,
+    /// 调用参数，应为对象
+    pub arguments: Value,
+    /// 预期的结果（可选）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_output: Option<Value>,
+}
+
+impl ToolExample {
+    /// 使用说明和参数创建示例
+    pub fn new(description: impl Into<String>, arguments: Value) -> Self {
+        Self {
+            description: description.into(),
+            arguments,
+            expected_output: None,
+        }
+    }
+
+    /// 设置预期的结果
+    pub fn with_expected_output(mut self, output: Value) -> Self {
+        self.expected_output = Some(output);
+        self
+    }
+}
+
 /// 函数式工具结构体
-#[derive(Serialize, Deserialize)]
+#[derive(Deserialize)]
 pub struct FunctionTool {
     /// 工具名称
     pub name: String,
@@ -61,10 +177,54 @@ pub struct FunctionTool {
     /// 工具元数据
     #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
     pub meta: Option<Value>,
+    /// 工具所属分组，序列化在`_meta.group`中
+    #[serde(skip)]
+    pub group: Option<String>,
+    /// 调用示例，序列化在`_meta.examples`中
+    #[serde(skip)]
+    pub examples: Vec<ToolExample>,
+    /// 工具是否启用，禁用的工具不会出现在列表中，也不能被调用
+    #[serde(skip, default = "default_enabled")]
+    enabled: bool,
+    /// 调用前是否按输入模式校验参数
+    #[serde(skip)]
+    validate_input: bool,
+    /// 注册时编译的输入模式，克隆的工具共享同一份
+    #[serde(skip)]
+    validator: Option<Arc<CompiledSchema>>,
+    /// 是否在校验前宽松转换参数，为`None`时使用工具管理器的设置
+    #[serde(skip)]
+    lenient_coercion: Option<bool>,
+    /// 工具函数返回错误时的重试策略
+    #[serde(skip)]
+    retry: Option<RetryPolicy>,
+    /// 同时执行的调用数上限，克隆的工具共享同一组许可
+    #[serde(skip)]
+    max_concurrency: Option<usize>,
+    #[serde(skip)]
+    permits: Option<Arc<Semaphore>>,
+    /// 结果序列化后的最大字节数，为`None`时使用服务器的设置
+    #[serde(skip)]
+    max_result_bytes: Option<usize>,
+    /// 用量配额，克隆的工具共享同一份计数
+    #[serde(skip)]
+    quota: Option<Arc<Quota>>,
+    /// 工具列表超出字节预算时的优先级，越大越先列出
+    #[serde(skip)]
+    priority: u8,
+    /// 本地化的标题和描述，序列化在`_meta.localizations`中
+    #[serde(skip)]
+    localizations: Localizations,
     
-    /// 工具函数（不参与序列化）
+    /// 启动和关闭钩子，参见[`tool_hooks`](crate::server::tool_hooks)
+    #[serde(skip)]
+    tool_hooks: ToolHooks,
+    /// 导入时的原始标识符，与名称不同时调用方也可以用它调用工具
     #[serde(skip)]
-    function: Option<Arc<ToolFunction>>,
+    source_name: Option<String>,
+    /// 工具的实现（不参与序列化）
+    #[serde(skip)]
+    binding: ToolBinding,
 }
 
 // 手动实现Clone trait
@@ -79,7 +239,22 @@ impl Clone for FunctionTool {
             annotations: self.annotations.clone(),
             tags: self.tags.clone(),
             meta: self.meta.clone(),
-            function: None, // 函数对象不参与克隆
+            group: self.group.clone(),
+            examples: self.examples.clone(),
+            enabled: self.enabled,
+            validate_input: self.validate_input,
+            validator: self.validator.clone(),
+            lenient_coercion: self.lenient_coercion,
+            retry: self.retry.clone(),
+            max_concurrency: self.max_concurrency,
+            permits: self.permits.clone(),
+            max_result_bytes: self.max_result_bytes,
+            quota: self.quota.clone(),
+            priority: self.priority,
+            localizations: self.localizations.clone(),
+            tool_hooks: self.tool_hooks.clone(),
+            source_name: self.source_name.clone(),
+            binding: self.binding.clone(),
         }
     }
 }
@@ -96,10 +271,194 @@ impl std::fmt::Debug for FunctionTool {
             .field("annotations", &self.annotations)
             .field("tags", &self.tags)
             .field("meta", &self.meta)
+            .field("group", &self.group)
+            .field("examples", &self.examples)
+            .field("enabled", &self.enabled)
+            .field("validate_input", &self.validate_input)
+            .field("compiled", &self.validator.is_some())
+            .field("lenient_coercion", &self.lenient_coercion)
+            .field("retry", &self.retry)
+            .field("max_concurrency", &self.max_concurrency)
+            .field("max_result_bytes", &self.max_result_bytes)
+            .field("quota", &self.quota)
+            .field("priority", &self.priority)
+            .field("localizations", &self.localizations)
+            .field("tool_hooks", &self.tool_hooks)
+            .field("binding", &self.binding)
             .finish()
     }
 }
 
+fn default_enabled() -> bool {
+    true
+}
+
+/// 工具定义
+///
+/// [`FunctionTool`]的元数据部分，不带工具函数，`tools/list`列出的就是它。`_meta`中合并了分组信息、
+/// 本地化文本、示例和未绑定标记。[`FunctionTool`]按这个结构序列化；反过来转换得到的是
+/// [未绑定](ToolBinding::Unbound)的工具，`_meta.group`和`_meta.examples`恢复为分组和示例。
+///
+/// ```rust
+/// use rustmcp::{FunctionTool, Tool, ToolBinding, ToolExample, ToolManager};
+/// use serde_json::json;
+///
+/// let manager = ToolManager::new();
+/// manager.add_tool(
+///     FunctionTool::builder()
+///         .name("add")
+///         .description("Adds two numbers")
+///         .input_schema(json!({ "type": "object" }))
+///         .group("math")
+///         .example(ToolExample::new("Small numbers", json!({ "a": 1, "b": 2 })))
+///         .handler(|_| Ok(json!(3)))
+///         .build(),
+/// );
+/// let tools: Vec<Tool> = manager.list_tools();
+/// assert_eq!(tools[0].name, "add");
+/// assert_eq!(tools[0].meta.as_ref().unwrap()["group"], "math");
+/// assert_eq!(serde_json::to_value(&tools[0]).unwrap(), serde_json::to_value(manager.get_tool("add").unwrap()).unwrap());
+///
+/// // 转换回来的工具没有实现，分组和示例保留
+/// let tool = FunctionTool::try_from(tools[0].clone()).unwrap();
+/// assert!(matches!(tool.binding(), ToolBinding::Unbound));
+/// assert_eq!((tool.group.as_deref(), tool.examples.len()), (Some("math"), 1));
+/// assert_eq!(serde_json::to_value(Tool::from(&tool)).unwrap()["_meta"]["bound"], false);
+///
+/// let nameless = Tool { name: String::new(), ..tools[0].clone() };
+/// assert_eq!(FunctionTool::try_from(nameless).unwrap_err(), "tool name must not be empty");
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tool {
+    /// 工具名称
+    pub name: String,
+    
+    /// 工具的人类可读标题
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    
+    /// 工具描述
+    #[serde(default)]
+    pub description: String,
+    
+    /// 工具参数的JSON Schema
+    #[serde(rename = "inputSchema", default, skip_serializing_if = "Option::is_none")]
+    pub input_schema: Option<Value>,
+    
+    /// 工具输出的JSON Schema
+    #[serde(rename = "outputSchema", default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Value>,
+    
+    /// 工具注解
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<ToolAnnotations>,
+    
+    /// 工具标签
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    
+    /// 元数据
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
+}
+
+impl From<&FunctionTool> for Tool {
+    fn from(tool: &FunctionTool) -> Self {
+        // 分组信息、本地化文本、示例和未绑定标记合并到_meta中，其余元数据保持不变
+        let meta = match (&tool.meta, &tool.group) {
+            (meta, None) => meta.clone(),
+            (Some(Value::Object(map)), Some(group)) => {
+                let mut map = map.clone();
+                map.insert("group".to_string(), Value::String(group.clone()));
+                Some(Value::Object(map))
+            }
+            (_, Some(group)) => Some(serde_json::json!({ "group": group })),
+        };
+        let mut meta = tool.localizations.merge_into_meta(meta);
+        if !tool.examples.is_empty() {
+            let examples = tool.examples.iter().filter_map(|example| serde_json::to_value(example).ok()).collect();
+            insert_meta(&mut meta, "examples", Value::Array(examples));
+        }
+        if !tool.binding.is_bound() {
+            insert_meta(&mut meta, "bound", Value::Bool(false));
+        }
+        Tool {
+            name: tool.name.clone(),
+            title: tool.title.clone(),
+            description: tool.description.clone(),
+            input_schema: tool.input_schema.clone(),
+            output_schema: tool.output_schema.clone(),
+            annotations: tool.annotations.clone(),
+            tags: tool.tags.clone(),
+            meta,
+        }
+    }
+}
+
+impl TryFrom<Tool> for FunctionTool {
+    type Error = String;
+
+    /// 创建未绑定的工具，名称为空、输入模式不是对象或`_meta.examples`格式不对时失败
+    fn try_from(tool: Tool) -> Result<Self, Self::Error> {
+        if tool.name.is_empty() {
+            return Err("tool name must not be empty".to_string());
+        }
+        if tool.input_schema.as_ref().is_some_and(|schema| !schema.is_object()) {
+            return Err(format!("inputSchema of tool '{}' must be an object", tool.name));
+        }
+        let mut meta = tool.meta;
+        let mut group = None;
+        let mut examples = Vec::new();
+        if let Some(Value::Object(map)) = &mut meta {
+            map.remove("bound");
+            group = match map.remove("group") {
+                Some(Value::String(group)) => Some(group),
+                Some(other) => {
+                    map.insert("group".to_string(), other);
+                    None
+                }
+                None => None,
+            };
+            if let Some(value) = map.remove("examples") {
+                examples = serde_json::from_value(value)
+                    .map_err(|e| format!("_meta.examples of tool '{}' is invalid: {}", tool.name, e))?;
+            }
+            if map.is_empty() {
+                meta = None;
+            }
+        }
+        let mut function_tool = FunctionTool::builder().name(tool.name).description(tool.description).build();
+        function_tool.title = tool.title;
+        function_tool.input_schema = tool.input_schema;
+        function_tool.output_schema = tool.output_schema;
+        function_tool.annotations = tool.annotations;
+        function_tool.tags = tool.tags;
+        function_tool.meta = meta;
+        function_tool.group = group;
+        function_tool.examples = examples;
+        Ok(function_tool)
+    }
+}
+
+impl Serialize for FunctionTool {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Tool::from(self).serialize(serializer)
+    }
+}
+
+/// 在`_meta`中设置一个键，`_meta`不是对象时替换它
+fn insert_meta(meta: &mut Option<Value>, key: &str, value: Value) {
+    match meta {
+        Some(Value::Object(map)) => {
+            map.insert(key.to_string(), value);
+        }
+        _ => *meta = Some(Value::Object(serde_json::Map::from_iter([(key.to_string(), value)]))),
+    }
+}
+
 impl FunctionTool {
     /// 从函数创建工具
     /// 
@@ -129,53 +488,755 @@ impl FunctionTool {
     where
         F: Fn(Option<HashMap<String, Value>>) -> Result<Value, String> + Send + Sync + 'static,
     {
-        Self {
-            function: Some(Arc::new(Box::new(function))),
-            name: name.unwrap_or_else(|| "unnamed_tool".to_string()),
+        Self::from_function_with_context(
+            move |_ctx: &Context, args: Option<HashMap<String, Value>>| function(args),
+            name,
+            title,
+            description,
+            input_schema,
+            output_schema,
+            annotations,
+            tags,
+            meta,
+        )
+    }
+
+    /// 从接收上下文的函数创建工具
+    ///
+    /// 参数与 [`FunctionTool::from_function`] 相同，区别在于函数的第一个参数为当前请求的 [`Context`]，
+    /// 可以通过它读取客户端在请求 `params._meta` 中携带的元数据。
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_function_with_context<F>(
+        function: F,
+        name: Option<String>,
+        title: Option<String>,
+        description: Option<String>,
+        input_schema: Option<Value>,
+        output_schema: Option<Value>,
+        annotations: Option<ToolAnnotations>,
+        tags: Option<Vec<String>>,
+        meta: Option<Value>,
+    ) -> Self
+    where
+        F: Fn(&Context, Option<HashMap<String, Value>>) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        FunctionToolBuilder {
+            name,
             title,
-            description: description.unwrap_or_default(),
+            description,
             input_schema,
             output_schema,
             annotations,
             tags,
             meta,
+            ..FunctionToolBuilder::default()
+        }
+        .handler_with_context(function)
+        .build()
+    }
+
+    /// 创建工具构建器
+    pub fn builder() -> FunctionToolBuilder {
+        FunctionToolBuilder::default()
+    }
+
+    /// 使用名称、描述和函数创建工具
+    ///
+    /// ```rust
+    /// use rustmcp::FunctionTool;
+    ///
+    /// let tool = FunctionTool::simple("ping", "Replies with pong", |_args| Ok(serde_json::json!("pong")));
+    /// assert_eq!(tool.name, "ping");
+    /// ```
+    pub fn simple<F>(name: impl Into<String>, description: impl Into<String>, function: F) -> Self
+    where
+        F: Fn(Option<HashMap<String, Value>>) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        Self::builder().name(name).description(description).handler(function).build()
+    }
+
+    /// 使用名称、描述、输入模式和函数创建工具
+    pub fn with_schema<F>(name: impl Into<String>, description: impl Into<String>, input_schema: Value, function: F) -> Self
+    where
+        F: Fn(Option<HashMap<String, Value>>) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        Self::builder()
+            .name(name)
+            .description(description)
+            .input_schema(input_schema)
+            .handler(function)
+            .build()
+    }
+
+    /// 设置工具所属分组
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.group = Some(group.into());
+        self
+    }
+
+    /// 添加一个调用示例
+    pub fn with_example(mut self, example: ToolExample) -> Self {
+        self.examples.push(example);
+        self
+    }
+
+    /// 限制同时执行的调用数，超出的调用等待之前的调用完成
+    ///
+    /// 对所有传输和[`x-rustmcp/tools/call_batch`](crate::server::batch)都生效；为0时按1处理。
+    pub fn with_max_concurrency(mut self, limit: usize) -> Self {
+        let limit = limit.max(1);
+        self.max_concurrency = Some(limit);
+        self.permits = Some(Arc::new(Semaphore::new(limit)));
+        self
+    }
+
+    /// 同时执行的调用数上限，没有限制时为`None`
+    pub fn max_concurrency(&self) -> Option<usize> {
+        self.max_concurrency
+    }
+
+    /// 结果序列化后的最大字节数，为`None`时使用服务器的设置
+    pub fn max_result_bytes(&self) -> Option<usize> {
+        self.max_result_bytes
+    }
+
+    /// 设置结果序列化后的最大字节数，超过时截断，参见[`truncation`](crate::server::truncation)
+    pub fn with_max_result_bytes(mut self, bytes: usize) -> Self {
+        self.max_result_bytes = Some(bytes);
+        self
+    }
+
+    /// 用量配额，没有配额时为`None`
+    pub fn quota(&self) -> Option<&Quota> {
+        self.quota.as_deref()
+    }
+
+    /// 每`window`最多执行`max_calls`次，参见[`quota`](crate::server::quota)
+    pub fn with_quota(mut self, max_calls: u32, window: Duration) -> Self {
+        self.quota = Some(Arc::new(Quota::new(max_calls, window)));
+        self
+    }
+
+    /// 工具列表超出字节预算时的优先级，参见[`ToolManager::list_tools_within`]
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    /// 设置工具列表超出字节预算时的优先级，越大越先列出，默认为0
+    ///
+    /// 设置了[`Settings::max_tools_list_bytes`](crate::Settings::max_tools_list_bytes)且工具列表放不下时，
+    /// `tools/list`先列出优先级高的工具，结果的`_meta.truncatedCount`为省略的工具数，`nextCursor`指向它们，
+    /// 服务器记录一条列出被省略工具名称的警告。
+    ///
+    /// ```rust
+    /// use rustmcp::{FunctionTool, RustMCP, Settings};
+    /// use serde_json::json;
+    ///
+    /// let rustmcp = RustMCP::new().with_settings(Settings::new().with_max_tools_list_bytes(32 * 1024));
+    /// rustmcp.add_tool(FunctionTool::simple("search", "Searches the catalog", |_| Ok(json!([]))).with_priority(9));
+    /// ```
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// 本地化的标题和描述，参见[`locale`](crate::server::locale)
+    pub fn localizations(&self) -> &Localizations {
+        &self.localizations
+    }
+
+    /// 导入时的原始标识符
+    pub fn source_name(&self) -> Option<&str> {
+        self.source_name.as_deref()
+    }
+
+    /// 工具是否启用
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// 工具是否可能修改状态：注解中`readOnlyHint`为`false`或`destructiveHint`为`true`；
+    /// 没有注解时返回`unannotated`
+    pub fn may_mutate(&self, unannotated: bool) -> bool {
+        match &self.annotations {
+            Some(annotations) => annotations.read_only_hint == Some(false) || annotations.destructive_hint == Some(true),
+            None => unannotated,
+        }
+    }
+
+    /// 设置调用前是否按输入模式校验参数
+    pub fn with_input_validation(mut self, validate: bool) -> Self {
+        self.validate_input = validate;
+        self
+    }
+
+    /// 设置是否在校验前宽松转换参数，覆盖工具管理器的设置
+    pub fn with_lenient_coercion(mut self, enabled: bool) -> Self {
+        self.lenient_coercion = Some(enabled);
+        self
+    }
+
+    /// 按输入模式校验参数，未开启校验或没有输入模式时直接通过
+    ///
+    /// 已注册的工具使用注册时编译的模式，未注册的工具逐次解释模式。
+    pub fn validate_arguments(&self, args: Option<&HashMap<String, Value>>) -> Result<(), McpError> {
+        match (&self.validator, &self.input_schema) {
+            _ if !self.validate_input => Ok(()),
+            (Some(validator), _) => validator.validate_arguments(args),
+            (None, Some(schema)) => validate_arguments(schema, args),
+            (None, None) => Ok(()),
         }
     }
 
     /// 调用工具函数
-    #[allow(dead_code)]
-    pub fn call(&self, args: Option<HashMap<String, Value>>) -> Result<Value, String> {
-        if let Some(ref function) = self.function {
-            function(args)
-        } else {
-            Err("Tool function not available".to_string())
+    ///
+    /// `args`区分“没有提供参数”和“明确提供了空参数”：`tools/call`请求省略`arguments`或传入`null`时为`None`，
+    /// 传入`{}`时为`Some`空表。开启输入校验时三种情况一样处理，都按空对象检查`required`。
+    ///
+    /// ```rust
+    /// use rustmcp::{Context, FunctionTool};
+    ///
+    /// let tool = FunctionTool::simple("inspect", "Reports how arguments arrived", |args| {
+    ///     Ok(serde_json::json!(args.map(|args| args.len())))
+    /// });
+    /// assert_eq!(tool.call(&Context::new(), None).unwrap(), serde_json::json!(null));
+    /// assert_eq!(tool.call(&Context::new(), Some(Default::default())).unwrap(), serde_json::json!(0));
+    /// ```
+    ///
+    /// 没有实现的工具返回错误；经过[`ToolManager::call_tool`]调用时返回[`McpError::ToolNotBound`]。
+    pub fn call(&self, ctx: &Context, args: Option<HashMap<String, Value>>) -> Result<Value, String> {
+        match &self.binding {
+            ToolBinding::Bound(function) => function(ctx, args),
+            ToolBinding::Remote(target) => target.call(ctx, args.unwrap_or_default()).map_err(|e| secrets::redact(&e)),
+            ToolBinding::Unbound => Err(McpError::ToolNotBound(self.name.clone()).to_string()),
         }
     }
+
+    /// 工具的实现
+    pub fn binding(&self) -> &ToolBinding {
+        &self.binding
+    }
+
+    /// 是否有可以调用的实现
+    pub fn is_bound(&self) -> bool {
+        self.binding.is_bound()
+    }
+
+    /// 替换工具的实现
+    pub fn with_binding(mut self, binding: ToolBinding) -> Self {
+        self.binding = binding;
+        self
+    }
 }
 
+/// 工具构建器
+///
+/// ```rust
+/// use rustmcp::{FunctionTool, ToolArgs};
+///
+/// let tool = FunctionTool::builder()
+///     .name("echo")
+///     .description("Echoes back the provided message")
+///     .args(ToolArgs::new().string("message", "The message to echo", true))
+///     .handler(|args| Ok(args.and_then(|a| a.get("message").cloned()).unwrap_or_default()))
+///     .build();
+/// assert!(tool.input_schema.is_some());
+/// ```
+#[derive(Default)]
+pub struct FunctionToolBuilder {
+    name: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+    input_schema: Option<Value>,
+    output_schema: Option<Value>,
+    annotations: Option<ToolAnnotations>,
+    tags: Option<Vec<String>>,
+    meta: Option<Value>,
+    group: Option<String>,
+    examples: Vec<ToolExample>,
+    validate_input: bool,
+    lenient_coercion: Option<bool>,
+    retry: Option<RetryPolicy>,
+    max_concurrency: Option<usize>,
+    max_result_bytes: Option<usize>,
+    quota: Option<(u32, Duration)>,
+    priority: u8,
+    localizations: Localizations,
+    tool_hooks: ToolHooks,
+    source_name: Option<String>,
+    binding: ToolBinding,
+}
+
+impl FunctionToolBuilder {
+    /// 设置工具名称
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// 设置工具标题
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// 设置工具描述
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// 设置某种语言的标题，参见[`locale`](crate::server::locale)
+    pub fn title_localized(mut self, lang: impl Into<LanguageTag>, title: impl Into<String>) -> Self {
+        self.localizations.titles.insert(lang.into(), title.into());
+        self
+    }
+
+    /// 设置某种语言的描述，参见[`locale`](crate::server::locale)
+    pub fn description_localized(mut self, lang: impl Into<LanguageTag>, description: impl Into<String>) -> Self {
+        self.localizations.descriptions.insert(lang.into(), description.into());
+        self
+    }
+
+    /// 使用参数声明生成输入模式，并开启调用前的参数校验
+    pub fn args(mut self, args: ToolArgs) -> Self {
+        self.input_schema = Some(args.build_schema());
+        self.validate_input = true;
+        self
+    }
+
+    /// 直接设置输入模式（不开启参数校验）
+    pub fn input_schema(mut self, schema: Value) -> Self {
+        self.input_schema = Some(schema);
+        self
+    }
+
+    /// 设置输出模式
+    pub fn output_schema(mut self, schema: Value) -> Self {
+        self.output_schema = Some(schema);
+        self
+    }
+
+    /// 设置注解
+    pub fn annotations(mut self, annotations: ToolAnnotations) -> Self {
+        self.annotations = Some(annotations);
+        self
+    }
+
+    /// 设置标签
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = Some(tags);
+        self
+    }
+
+    /// 设置元数据
+    pub fn meta(mut self, meta: Value) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+
+    /// 设置所属分组
+    pub fn group(mut self, group: impl Into<String>) -> Self {
+        self.group = Some(group.into());
+        self
+    }
+
+    /// 设置是否在调用前校验参数
+    pub fn validate_input(mut self, validate: bool) -> Self {
+        self.validate_input = validate;
+        self
+    }
+
+    /// 设置是否在校验前宽松转换参数，覆盖工具管理器的设置
+    pub fn lenient_coercion(mut self, enabled: bool) -> Self {
+        self.lenient_coercion = Some(enabled);
+        self
+    }
+
+    /// 工具函数返回错误时最多尝试`max_attempts`次（包括第一次调用），重试之间按`backoff`等待
+    pub fn retries(mut self, max_attempts: u32, backoff: Backoff) -> Self {
+        let retry_if = self.retry.take().and_then(|policy| policy.retry_if);
+        self.retry = Some(RetryPolicy {
+            retry_if,
+            ..RetryPolicy::new(max_attempts, backoff)
+        });
+        self
+    }
+
+    /// 只重试`predicate`返回`true`的错误，需要和[`retries`](Self::retries)一起使用
+    pub fn retry_if<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        let policy = self.retry.get_or_insert_with(|| RetryPolicy::new(1, Backoff::Fixed(Duration::ZERO)));
+        policy.retry_if = Some(Arc::new(predicate));
+        self
+    }
+
+    /// 限制同时执行的调用数，参见[`FunctionTool::with_max_concurrency`]
+    pub fn max_concurrency(mut self, limit: usize) -> Self {
+        self.max_concurrency = Some(limit);
+        self
+    }
+
+    /// 设置结果序列化后的最大字节数，覆盖服务器的设置，参见[`truncation`](crate::server::truncation)
+    pub fn max_result_bytes(mut self, bytes: usize) -> Self {
+        self.max_result_bytes = Some(bytes);
+        self
+    }
+
+    /// 每`window`最多执行`max_calls`次，参见[`FunctionTool::with_quota`]
+    pub fn quota(mut self, max_calls: u32, window: Duration) -> Self {
+        self.quota = Some((max_calls, window));
+        self
+    }
+
+    /// 设置工具列表超出字节预算时的优先级，参见[`FunctionTool::with_priority`]
+    pub fn priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// 设置导入时的原始标识符，名称应为它[规范化](sanitize_tool_name)的结果，参见[`ToolManager::add_imported_tool`]
+    pub fn source_name(mut self, source_name: impl Into<String>) -> Self {
+        self.source_name = Some(source_name.into());
+        self
+    }
+
+    /// 设置启动钩子，在服务器开始接受请求之前执行，返回错误时停止启动，参见[`tool_hooks`](crate::server::tool_hooks)
+    pub fn on_startup<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn(Context) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.tool_hooks.set_startup(hook);
+        self
+    }
+
+    /// 设置关闭钩子，在优雅关闭时执行，参见[`tool_hooks`](crate::server::tool_hooks)
+    pub fn on_shutdown<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.tool_hooks.set_shutdown(hook);
+        self
+    }
+
+    /// 设置调用示例，替换之前添加的示例
+    pub fn examples(mut self, examples: Vec<ToolExample>) -> Self {
+        self.examples = examples;
+        self
+    }
+
+    /// 添加一个调用示例
+    pub fn example(mut self, example: ToolExample) -> Self {
+        self.examples.push(example);
+        self
+    }
+
+    /// 设置工具函数
+    pub fn handler<F>(self, function: F) -> Self
+    where
+        F: Fn(Option<HashMap<String, Value>>) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        self.handler_with_context(move |_ctx: &Context, args: Option<HashMap<String, Value>>| function(args))
+    }
+
+    /// 设置接收上下文的工具函数
+    pub fn handler_with_context<F>(mut self, function: F) -> Self
+    where
+        F: Fn(&Context, Option<HashMap<String, Value>>) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        self.binding = ToolBinding::Bound(Arc::new(Box::new(function)));
+        self
+    }
+
+    /// 把调用转发到HTTP地址，参见[`proxy`](crate::server::proxy)
+    pub fn remote(mut self, target: ProxyTarget) -> Self {
+        self.binding = ToolBinding::Remote(target);
+        self
+    }
+
+    /// 构建工具，未设置名称时使用`unnamed_tool`
+    pub fn build(self) -> FunctionTool {
+        let tool = FunctionTool {
+            binding: self.binding,
+            name: self.name.unwrap_or_else(|| "unnamed_tool".to_string()),
+            title: self.title,
+            description: self.description.unwrap_or_default(),
+            input_schema: self.input_schema,
+            output_schema: self.output_schema,
+            annotations: self.annotations,
+            tags: self.tags,
+            meta: self.meta,
+            group: self.group,
+            examples: self.examples,
+            enabled: true,
+            validate_input: self.validate_input,
+            validator: None,
+            lenient_coercion: self.lenient_coercion,
+            retry: self.retry,
+            max_concurrency: None,
+            permits: None,
+            max_result_bytes: self.max_result_bytes,
+            quota: self.quota.map(|(max_calls, window)| Arc::new(Quota::new(max_calls, window))),
+            priority: self.priority,
+            localizations: self.localizations,
+            tool_hooks: self.tool_hooks,
+            source_name: self.source_name,
+        };
+        match self.max_concurrency {
+            Some(limit) => tool.with_max_concurrency(limit),
+            None => tool,
+        }
+    }
+}
+
+/// 规范化后的工具名称的最大长度
+pub const MAX_TOOL_NAME_LEN: usize = 64;
+
+/// 消歧义时附加的哈希的十六进制位数
+const NAME_HASH_LEN: usize = 8;
+
+/// 把外部标识符（OpenAPI的`operationId`等）转换为客户端都能接受的工具名称
+///
+/// 规则如下，结果只由`raw`决定：
+/// - 小写字母前后的大写字母处断开（`getPetById`变为`get_pet_by_id`），然后全部转为小写；
+/// - 常见的带变音符号的拉丁字母转写为ASCII（`é`变为`e`、`ß`变为`ss`、`æ`变为`ae`），组合用的变音符号去掉；
+/// - `[a-z0-9_-]`以外的字符（空格、斜杠、点、无法转写的字符）替换为`_`，连续的`_`合并为一个，首尾的`_`和`-`去掉；
+/// - 结果为空时为`tool`，超过[`MAX_TOOL_NAME_LEN`]时截断；这两种情况都会附加原始标识符的8位哈希，
+///   避免不同的标识符得到同一个名称。
+///
+/// 规范化后的名称再规范化不会变化。处理名称冲突见[`unique_tool_name`]。
+///
+/// ```rust
+/// use rustmcp::server::tools::{sanitize_tool_name, MAX_TOOL_NAME_LEN};
+///
+/// assert_eq!(sanitize_tool_name("getPetById"), "get_pet_by_id");
+/// assert_eq!(sanitize_tool_name("GET /pets/{petId}"), "get_pets_pet_id");
+/// assert_eq!(sanitize_tool_name("Café Crème-Brûlée"), "cafe_creme-brulee");
+/// assert_eq!(sanitize_tool_name("Straße"), "strasse");
+/// assert_eq!(sanitize_tool_name("already_clean-name"), "already_clean-name");
+///
+/// // 无法转写的名称和过长的名称带有原始标识符的哈希
+/// let (orders, users) = (sanitize_tool_name("查询订单"), sanitize_tool_name("查询用户"));
+/// assert!(orders.starts_with("tool_") && users.starts_with("tool_"));
+/// assert_ne!(orders, users);
+/// let long = sanitize_tool_name(&"segment/".repeat(20));
+/// assert_eq!(long.len(), MAX_TOOL_NAME_LEN);
+/// assert_eq!(sanitize_tool_name(&long), long);
+/// ```
+pub fn sanitize_tool_name(raw: &str) -> String {
+    let mut name = String::with_capacity(raw.len());
+    let mut after_lower = false;
+    for c in raw.chars() {
+        if c.is_uppercase() && after_lower {
+            push_separator(&mut name);
+        }
+        after_lower = c.is_lowercase() || c.is_ascii_digit();
+        for c in c.to_lowercase() {
+            match c {
+                'a'..='z' | '0'..='9' | '-' => name.push(c),
+                // 组合用的变音符号
+                '\u{300}'..='\u{36f}' => {}
+                _ => match transliterate(c) {
+                    Some(ascii) => name.push_str(ascii),
+                    None => push_separator(&mut name),
+                },
+            }
+        }
+    }
+    let name = name.trim_matches(['_', '-']);
+    if name.is_empty() {
+        with_hash_suffix("tool", &name_hash(raw)[..NAME_HASH_LEN])
+    } else if name.len() > MAX_TOOL_NAME_LEN {
+        with_hash_suffix(name, &name_hash(raw)[..NAME_HASH_LEN])
+    } else {
+        name.to_string()
+    }
+}
+
+/// 规范化`raw`，名称已被占用（`taken`返回`true`）时附加原始标识符的哈希
+///
+/// 同一组标识符按同样的顺序导入时得到同样的名称。哈希后缀仍然冲突时依次使用更长的哈希，最后附加序号。
+///
+/// ```rust
+/// use rustmcp::server::tools::unique_tool_name;
+/// use std::collections::HashSet;
+///
+/// let mut names = HashSet::new();
+/// for raw in ["get user", "get_user", "Get-User", "getUser"] {
+///     let name = unique_tool_name(raw, |name| names.contains(name));
+///     names.insert(name);
+/// }
+/// // 第一个标识符得到规范化的名称，后面冲突的附加各自的哈希
+/// assert!(names.contains("get_user"));
+/// assert!(names.contains("get-user"));
+/// assert_eq!(names.iter().filter(|name| name.starts_with("get_user_")).count(), 2);
+/// assert_eq!(unique_tool_name("get_user", |name| names.contains(name)), unique_tool_name("get_user", |name| names.contains(name)));
+/// ```
+pub fn unique_tool_name(raw: &str, taken: impl Fn(&str) -> bool) -> String {
+    disambiguate(sanitize_tool_name(raw), raw, taken)
+}
+
+/// `name`被占用时附加`raw`的哈希
+fn disambiguate(name: String, raw: &str, taken: impl Fn(&str) -> bool) -> String {
+    if !taken(&name) {
+        return name;
+    }
+    let hash = name_hash(raw);
+    for len in [NAME_HASH_LEN, 12, 16] {
+        let candidate = with_hash_suffix(&name, &hash[..len]);
+        if !taken(&candidate) {
+            return candidate;
+        }
+    }
+    (2..)
+        .map(|n| with_hash_suffix(&name, &format!("{}{}", &hash[..NAME_HASH_LEN], n)))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or(name)
+}
+
+/// 截断`name`并附加`_{suffix}`，结果不超过[`MAX_TOOL_NAME_LEN`]；`name`只含ASCII字符
+fn with_hash_suffix(name: &str, suffix: &str) -> String {
+    let keep = MAX_TOOL_NAME_LEN - suffix.len() - 1;
+    let name = name[..name.len().min(keep)].trim_end_matches(['_', '-']);
+    format!("{}_{}", name, suffix)
+}
+
+/// 原始标识符的SHA-256（十六进制）
+fn name_hash(raw: &str) -> String {
+    Sha256::digest(raw.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// 追加一个`_`，已经以`_`结尾时不追加
+fn push_separator(name: &mut String) {
+    if !name.is_empty() && !name.ends_with('_') {
+        name.push('_');
+    }
+}
+
+/// 常见的带变音符号的拉丁字母（小写）的ASCII转写
+fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'ĥ' | 'ħ' => "h",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'ĵ' => "j",
+        'ķ' => "k",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'œ' => "oe",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'ß' => "ss",
+        'ś' | 'ŝ' | 'ş' | 'š' => "s",
+        'ţ' | 'ť' | 'ŧ' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'ŵ' => "w",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    })
+}
+
+/// 按字节预算截断的工具列表，参见[`ToolManager::list_tools_within`]
+#[derive(Debug, Clone)]
+pub struct ToolPage {
+    /// 列出的工具（序列化后的[`Tool`]）
+    pub tools: Vec<Value>,
+    
+    /// 被省略的工具的游标，没有省略时为`None`
+    pub next_cursor: Option<String>,
+    
+    /// 因超出预算而省略的工具名称，按省略的顺序排列
+    pub omitted: Vec<String>,
+}
+
+/// 截断列表的游标前缀，后面是已经列出的工具数
+const PRIORITY_CURSOR: &str = "priority:";
+
+/// 工具分组
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ToolGroup {
+    /// 分组名称
+    pub name: String,
+    /// 分组描述
+    pub description: String,
+}
+
+/// 缓存的工具列表（或序列化错误）和生成它时的修订号
+type Snapshot = (u64, Result<Arc<Value>, McpError>);
+
 /// 工具管理器
+///
+/// 工具表和分组表在内部加锁，增删查都只需要`&self`，可以在服务器运行时从多个任务并发修改
+/// （例如清单目录监视）。克隆得到的管理器共享同一份表。
 #[derive(Debug, Clone)]
 pub struct ToolManager {
-    tools: HashMap<String, FunctionTool>,
+    tools: Arc<RwLock<HashMap<String, Arc<FunctionTool>>>>,
+    groups: Arc<RwLock<HashMap<String, ToolGroup>>>,
+    /// 修订号，工具表每次变化时加一
+    revision: Arc<AtomicU64>,
+    /// 序列化后的工具列表和生成它时的修订号，修订号变化后重新生成
+    listing: Arc<RwLock<Option<Snapshot>>>,
+    /// 工具模式可以引用的共享定义
+    definitions: Arc<SchemaRegistry>,
+    /// 导入的工具的原始标识符到工具名称的映射
+    source_names: Arc<RwLock<HashMap<String, String>>>,
     duplicate_behavior: DuplicateBehavior,
+    lenient_coercion: bool,
+    serialization_policy: SerializationPolicy,
 }
 
 impl ToolManager {
     /// 创建新的工具管理器
     pub fn new() -> Self {
-        Self {
-            tools: HashMap::new(),
-            duplicate_behavior: DuplicateBehavior::Warn,
-        }
+        Self::with_behavior(DuplicateBehavior::Warn)
     }
     
     /// 创建具有指定重复行为的新工具管理器
     pub fn with_behavior(duplicate_behavior: DuplicateBehavior) -> Self {
         Self {
-            tools: HashMap::new(),
+            tools: Arc::default(),
+            groups: Arc::default(),
+            revision: Arc::default(),
+            listing: Arc::default(),
+            definitions: Arc::default(),
+            source_names: Arc::default(),
             duplicate_behavior,
+            lenient_coercion: false,
+            serialization_policy: SerializationPolicy::default(),
         }
     }
+
+    /// 设置是否在校验前把字符串参数宽松转换为模式声明的类型（默认关闭）
+    ///
+    /// 只做无损转换，例如`"5"`转为整数`5`、`"true"`转为布尔值、去掉枚举值首尾的空白。
+    /// 单个工具可以通过[`FunctionTool::with_lenient_coercion`]覆盖这个设置。
+    pub fn lenient_coercion(mut self, enabled: bool) -> Self {
+        self.lenient_coercion = enabled;
+        self
+    }
+
+    /// 设置是否宽松转换参数
+    pub fn set_lenient_coercion(&mut self, enabled: bool) {
+        self.lenient_coercion = enabled;
+    }
+
+    /// 设置工具列表中某个工具序列化失败时的处理方式，参见[`serialization`](crate::server::serialization)
+    pub fn set_serialization_policy(&mut self, policy: SerializationPolicy) {
+        self.serialization_policy = policy;
+        // 不再与其他策略的克隆共享列表缓存
+        self.listing = Arc::default();
+    }
 }
 
 impl Default for ToolManager {
@@ -185,49 +1246,808 @@ impl Default for ToolManager {
 }
 
 impl ToolManager {
+    /// 添加工具分组
+    pub fn add_group(&self, name: impl Into<String>, description: impl Into<String>) {
+        let name = name.into();
+        self.groups.write().insert(name.clone(), ToolGroup {
+            name,
+            description: description.into(),
+        });
+    }
+
+    /// 列出所有分组
+    pub fn list_groups(&self) -> Vec<ToolGroup> {
+        self.groups.read().values().cloned().collect()
+    }
+
+    /// 禁用分组中的所有工具，返回状态发生变化的工具数量
+    pub fn disable_group(&self, name: &str) -> usize {
+        self.set_group_enabled(name, false)
+    }
+
+    /// 启用分组中的所有工具，返回状态发生变化的工具数量
+    pub fn enable_group(&self, name: &str) -> usize {
+        self.set_group_enabled(name, true)
+    }
+
+    /// 删除分组及其中的所有工具，返回删除的工具数量
+    pub fn remove_group(&self, name: &str) -> usize {
+        self.groups.write().remove(name);
+        let mut tools = self.tools.write();
+        let before = tools.len();
+        tools.retain(|_, tool| tool.group.as_deref() != Some(name));
+        if tools.len() != before {
+            self.touch();
+        }
+        before - tools.len()
+    }
+
+    fn set_group_enabled(&self, name: &str, enabled: bool) -> usize {
+        let mut changed = 0;
+        for tool in self.tools.write().values_mut() {
+            if tool.group.as_deref() == Some(name) && tool.enabled != enabled {
+                Arc::make_mut(tool).enabled = enabled;
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.touch();
+        }
+        changed
+    }
+
+    /// 工具表变化后增大修订号
+    fn touch(&self) {
+        self.revision.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 工具表的修订号，添加、删除、启用或禁用工具后增大
+    pub(crate) fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+
+    /// 注册共享的模式定义，参见[`definitions`](crate::server::definitions)
+    ///
+    /// 注册后重新编译所有工具的输入模式，编译失败的工具保留原来的编译结果并记录警告。
+    pub fn register_schema(&self, name: impl Into<String>, schema: Value) -> Result<(), String> {
+        self.definitions.register(name.into(), schema)?;
+        if let Err(errors) = self.recompile_schemas() {
+            for message in errors {
+                warn!("{}", message);
+            }
+        }
+        self.touch();
+        Ok(())
+    }
+
+    /// 按当前的共享定义重新编译所有工具的输入模式，返回编译失败的工具的错误
+    ///
+    /// 编译失败的工具保留原来的编译结果。共享定义变化或清单重新加载后调用。
+    pub fn recompile_schemas(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        for tool in self.tools.write().values_mut() {
+            match self.compile(tool) {
+                Ok(validator) => Arc::make_mut(tool).validator = validator,
+                Err(message) => errors.push(message),
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// 编译开启了输入校验的工具的输入模式（展开共享定义之后），没有需要编译的模式时返回`None`
+    fn compile(&self, tool: &FunctionTool) -> Result<Option<Arc<CompiledSchema>>, String> {
+        let Some(schema) = tool.input_schema.as_ref().filter(|_| tool.validate_input) else {
+            return Ok(None);
+        };
+        let resolved = self.definitions.resolve(schema).unwrap_or_else(|| schema.clone());
+        let compiled = CompiledSchema::compile_as(resolved, "inputSchema")
+            .map_err(|message| format!("Tool '{}' {}", tool.name, message))?;
+        Ok(Some(Arc::new(compiled)))
+    }
+
+    /// 检查工具模式引用的共享定义都已注册
+    fn check_references(&self, tool: &FunctionTool) -> Result<(), String> {
+        for (field, schema) in [("inputSchema", &tool.input_schema), ("outputSchema", &tool.output_schema)] {
+            if let Some(schema) = schema {
+                self.definitions.check(&format!("Tool '{}' {}", tool.name, field), schema)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 把引用的共享定义打包进工具的模式
+    fn bundled(&self, tool: &FunctionTool) -> FunctionTool {
+        let mut tool = FunctionTool::clone(tool);
+        for schema in [&mut tool.input_schema, &mut tool.output_schema].into_iter().flatten() {
+            if let Some(bundled) = self.definitions.bundle(schema) {
+                *schema = bundled;
+            }
+        }
+        tool
+    }
+
     /// 添加工具
-    #[allow(dead_code)]
-    pub fn add_tool(&mut self, tool: FunctionTool) {
-        if self.tools.contains_key(&tool.name) {
+    ///
+    /// 检查重名和插入在同一次加锁中完成，并发添加同名工具时按重复行为处理，不会互相覆盖而不告警。
+    ///
+    /// # Panics
+    ///
+    /// 重复行为为`Error`且工具已存在时，或模式引用了未注册的共享定义时panic，
+    /// 需要处理错误时使用[`ToolManager::try_add_tool`]。
+    pub fn add_tool(&self, tool: FunctionTool) {
+        if let Err(message) = self.try_add_tool(tool) {
+            panic!("{}", message);
+        }
+    }
+
+    /// 添加工具，重复行为为`Error`且工具已存在时，或模式引用了未注册的共享定义时返回错误
+    ///
+    /// 开启了输入校验的工具在这里编译输入模式，模式结构不正确时返回错误，替换已有工具时使用新工具的编译结果。
+    /// 返回工具是否被添加，重复行为为`Ignore`且工具已存在时返回`Ok(false)`。
+    pub fn try_add_tool(&self, mut tool: FunctionTool) -> Result<bool, String> {
+        self.check_references(&tool)?;
+        tool.validator = self.compile(&tool)?;
+        if let Some(group) = &tool.group {
+            if !self.groups.read().contains_key(group) {
+                warn!("Tool '{}' belongs to undefined group '{}'", tool.name, group);
+            }
+        }
+        let mut tools = self.tools.write();
+        if tools.contains_key(&tool.name) {
             match self.duplicate_behavior {
                 DuplicateBehavior::Warn => {
                     warn!("Tool '{}' already exists, replacing", tool.name);
-                    self.tools.insert(tool.name.clone(), tool);
                 }
                 DuplicateBehavior::Error => {
-                    panic!("Tool '{}' already exists", tool.name);
-                }
-                DuplicateBehavior::Replace => {
-                    self.tools.insert(tool.name.clone(), tool);
+                    return Err(format!("Tool '{}' already exists", tool.name));
                 }
+                DuplicateBehavior::Replace => {}
                 DuplicateBehavior::Ignore => {
                     // 不添加新工具
+                    return Ok(false);
                 }
             }
-        } else {
-            self.tools.insert(tool.name.clone(), tool);
+        }
+        self.record_source_name(&tool);
+        tools.insert(tool.name.clone(), Arc::new(tool));
+        self.touch();
+        Ok(true)
+    }
+
+    /// 添加或替换工具，不按重复行为处理，返回是否替换了已有的工具；输入模式不能编译时返回错误
+    #[cfg_attr(not(feature = "watch"), allow(dead_code))]
+    pub(crate) fn insert_tool(&self, mut tool: FunctionTool) -> Result<bool, String> {
+        if let Err(message) = self.check_references(&tool) {
+            warn!("{}", message);
+        }
+        tool.validator = self.compile(&tool)?;
+        self.record_source_name(&tool);
+        let replaced = self.tools.write().insert(tool.name.clone(), Arc::new(tool)).is_some();
+        self.touch();
+        Ok(replaced)
+    }
+
+    /// 启用或禁用单个工具，工具不存在时返回`None`，否则返回状态是否发生变化
+    pub fn set_tool_enabled(&self, name: &str, enabled: bool) -> Option<bool> {
+        let mut tools = self.tools.write();
+        let tool = tools.get_mut(name)?;
+        if tool.enabled == enabled {
+            return Some(false);
+        }
+        Arc::make_mut(tool).enabled = enabled;
+        self.touch();
+        Some(true)
+    }
+
+    /// 为已注册的工具设置工具函数，替换原来的实现，工具不存在时返回`false`
+    pub fn bind_function<F>(&self, name: &str, function: F) -> bool
+    where
+        F: Fn(&Context, Option<HashMap<String, Value>>) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        self.bind(name, ToolBinding::Bound(Arc::new(Box::new(function))))
+    }
+
+    /// 替换已注册工具的实现，工具不存在时返回`false`
+    pub fn bind(&self, name: &str, binding: ToolBinding) -> bool {
+        let mut tools = self.tools.write();
+        let Some(tool) = tools.get_mut(name) else {
+            return false;
+        };
+        Arc::make_mut(tool).binding = binding;
+        drop(tools);
+        self.touch();
+        true
+    }
+
+    /// 删除工具，返回工具是否存在
+    pub fn remove_tool(&self, name: &str) -> bool {
+        let removed = self.tools.write().remove(name).is_some();
+        if removed {
+            self.source_names.write().retain(|_, target| target != name);
+            self.touch();
+        }
+        removed
+    }
+
+    /// 添加从外部标识符导入的工具，返回注册使用的名称
+    ///
+    /// 原始标识符取[`FunctionTool::source_name`]，没有时取工具名称并记录下来。名称先经过
+    /// [`sanitize_tool_name`]；已经被来自其他标识符的工具占用时附加原始标识符的哈希（参见[`unique_tool_name`]），
+    /// 再次导入同一个标识符时替换原来的工具。之后用原始标识符或注册的名称都可以调用工具，
+    /// 参见[`resolve_tool_name`](Self::resolve_tool_name)。其他错误与[`try_add_tool`](Self::try_add_tool)相同。
+    pub fn add_imported_tool(&self, mut tool: FunctionTool) -> Result<String, String> {
+        let raw = tool.source_name.get_or_insert_with(|| tool.name.clone()).clone();
+        let existing = self.source_names.read().get(&raw).cloned();
+        tool.name = match existing {
+            Some(name) => name,
+            None => {
+                let tools = self.tools.read();
+                let taken = |name: &str| tools.get(name).is_some_and(|other| other.source_name.as_deref() != Some(raw.as_str()));
+                disambiguate(sanitize_tool_name(&tool.name), &raw, taken)
+            }
+        };
+        let name = tool.name.clone();
+        self.try_add_tool(tool)?;
+        Ok(name)
+    }
+
+    /// 把导入的工具的原始标识符解析为注册的名称，已注册的名称和未知的名称原样返回
+    pub fn resolve_tool_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        if self.tools.read().contains_key(name) {
+            return Cow::Borrowed(name);
+        }
+        match self.source_names.read().get(name) {
+            Some(resolved) => Cow::Owned(resolved.clone()),
+            None => Cow::Borrowed(name),
+        }
+    }
+
+    /// 记录原始标识符到工具名称的映射
+    fn record_source_name(&self, tool: &FunctionTool) {
+        if let Some(source_name) = tool.source_name.as_ref().filter(|source_name| **source_name != tool.name) {
+            self.source_names.write().insert(source_name.clone(), tool.name.clone());
         }
     }
 
     /// 获取工具
-    #[allow(dead_code)]
-    pub fn get_tool(&self, name: &str) -> Option<&FunctionTool> {
-        self.tools.get(name)
+    pub fn get_tool(&self, name: &str) -> Option<FunctionTool> {
+        self.tools.read().get(name).map(|tool| FunctionTool::clone(tool))
     }
 
-    /// 列出所有工具
-    #[allow(dead_code)]
-    pub fn list_tools(&self) -> Vec<&FunctionTool> {
-        self.tools.values().collect()
+    /// 列出所有启用的工具的定义，按名称排序
+    pub fn list_tools(&self) -> Vec<Tool> {
+        self.list_function_tools().iter().map(Tool::from).collect()
+    }
+
+    /// 声明了钩子的工具（包括禁用的工具）及其钩子，按名称排序
+    pub(crate) fn list_tool_hooks(&self) -> Vec<(String, ToolHooks)> {
+        let mut hooks: Vec<(String, ToolHooks)> = self
+            .tools
+            .read()
+            .values()
+            .filter(|tool| !tool.tool_hooks.is_empty())
+            .map(|tool| (tool.name.clone(), tool.tool_hooks.clone()))
+            .collect();
+        hooks.sort_by(|a, b| a.0.cmp(&b.0));
+        hooks
+    }
+
+    /// 列出所有启用的工具，按名称排序；模式中引用的共享定义已经打包进去
+    pub(crate) fn list_function_tools(&self) -> Vec<FunctionTool> {
+        let mut tools: Vec<FunctionTool> = self
+            .tools
+            .read()
+            .values()
+            .filter(|tool| tool.enabled)
+            .map(|tool| self.bundled(tool))
+            .collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        tools
+    }
+
+    /// 序列化后的工具列表（JSON数组），内容与[`ToolManager::list_tools`]相同
+    ///
+    /// 结果按修订号缓存，工具表不变时多次调用和并发请求共享同一份。某个工具序列化失败时按
+    /// [`SerializationPolicy`]返回错误或跳过该工具。
+    pub fn tool_listing(&self) -> Result<Arc<Value>, McpError> {
+        // 先读修订号再生成，生成期间发生的变化会让缓存的结果在下一次调用时重新生成
+        let revision = self.revision();
+        if let Some((cached, listing)) = &*self.listing.read() {
+            if *cached == revision {
+                return listing.clone();
+            }
+        }
+        let listing = serialize_items(&self.list_tools(), "tool", |tool| &tool.name, self.serialization_policy)
+            .map(|tools| Arc::new(Value::Array(tools)));
+        *self.listing.write() = Some((revision, listing.clone()));
+        listing
+    }
+
+    /// 序列化后不超过`max_bytes`字节的工具列表
+    ///
+    /// 整个列表放得下时与[`ToolManager::tool_listing`]相同，按名称排序。放不下时按
+    /// [优先级](FunctionTool::with_priority)从高到低、同优先级按名称排列，依次加入工具直到下一个放不下，
+    /// 其余的工具记在`omitted`中，`next_cursor`指向它们。带着这个游标再次列出时返回剩余的工具，
+    /// 不受预算限制，会翻页的客户端仍然能拿到所有工具。字节数按未本地化的紧凑JSON数组计算。
+    ///
+    /// ```rust
+    /// use rustmcp::{FunctionTool, ToolManager};
+    /// use serde_json::json;
+    ///
+    /// let manager = ToolManager::new();
+    /// for (name, priority) in [("alpha", 0), ("beta", 5), ("gamma", 5), ("delta", 9)] {
+    ///     manager.add_tool(FunctionTool::simple(name, "A tool", |_| Ok(json!(null))).with_priority(priority));
+    /// }
+    /// let size = serde_json::to_vec(&manager.list_tools()[0]).unwrap().len();
+    ///
+    /// // 只放得下两个工具：先是优先级最高的delta，再是同优先级中名称在前的beta
+    /// let page = manager.list_tools_within(None, 2 * size + 5).unwrap();
+    /// let names: Vec<&str> = page.tools.iter().map(|tool| tool["name"].as_str().unwrap()).collect();
+    /// assert_eq!(names, ["delta", "beta"]);
+    /// assert_eq!(page.omitted, ["gamma", "alpha"]);
+    ///
+    /// // 游标绕过预算
+    /// let rest = manager.list_tools_within(page.next_cursor.as_deref(), 1).unwrap();
+    /// let names: Vec<&str> = rest.tools.iter().map(|tool| tool["name"].as_str().unwrap()).collect();
+    /// assert_eq!((names, rest.next_cursor), (vec!["gamma", "alpha"], None));
+    ///
+    /// // 放得下时按名称排序，不省略
+    /// let page = manager.list_tools_within(None, 1 << 20).unwrap();
+    /// assert_eq!((page.tools.len(), page.omitted.len()), (4, 0));
+    /// assert_eq!(page.tools[0]["name"], "alpha");
+    /// ```
+    pub fn list_tools_within(&self, cursor: Option<&str>, max_bytes: usize) -> Result<ToolPage, McpError> {
+        let listing = self.tool_listing()?;
+        let tools = listing.as_array().map(Vec::as_slice).unwrap_or_default();
+        let sizes: Vec<usize> = tools.iter().map(|tool| serde_json::to_vec(tool).map_or(0, |bytes| bytes.len())).collect();
+        // 方括号和逗号
+        let total = 2 + sizes.iter().sum::<usize>() + sizes.len().saturating_sub(1);
+        if cursor.is_none() && total <= max_bytes {
+            return Ok(ToolPage { tools: tools.to_vec(), next_cursor: None, omitted: Vec::new() });
+        }
+
+        let priorities: HashMap<String, u8> = self.tools.read().values().map(|tool| (tool.name.clone(), tool.priority)).collect();
+        let name = |index: usize| tools[index]["name"].as_str().unwrap_or_default();
+        // 列表已按名称排序，稳定排序保持同优先级的名称顺序
+        let mut order: Vec<usize> = (0..tools.len()).collect();
+        order.sort_by_key(|&index| std::cmp::Reverse(priorities.get(name(index)).copied().unwrap_or_default()));
+
+        if let Some(cursor) = cursor {
+            let start = cursor
+                .strip_prefix(PRIORITY_CURSOR)
+                .and_then(|listed| listed.parse::<usize>().ok())
+                .filter(|&listed| listed <= order.len())
+                .ok_or_else(|| McpError::invalid_params(format!("Invalid cursor '{}'", cursor), "cursor", "string"))?;
+            return Ok(ToolPage {
+                tools: order[start..].iter().map(|&index| tools[index].clone()).collect(),
+                next_cursor: None,
+                omitted: Vec::new(),
+            });
+        }
+
+        let (mut used, mut listed) = (2, 0);
+        for &index in &order {
+            let size = sizes[index] + usize::from(listed > 0);
+            if used + size > max_bytes {
+                break;
+            }
+            used += size;
+            listed += 1;
+        }
+        Ok(ToolPage {
+            tools: order[..listed].iter().map(|&index| tools[index].clone()).collect(),
+            next_cursor: Some(format!("{}{}", PRIORITY_CURSOR, listed)),
+            omitted: order[listed..].iter().map(|&index| name(index).to_string()).collect(),
+        })
+    }
+
+    /// 列出所有工具，包括禁用的工具
+    pub(crate) fn all_tools(&self) -> Vec<FunctionTool> {
+        self.tools.read().values().map(|tool| FunctionTool::clone(tool)).collect()
+    }
+
+    /// 列出指定分组中启用的工具的定义，按名称排序
+    pub fn list_tools_in_group(&self, group: &str) -> Vec<Tool> {
+        let mut tools: Vec<Tool> = self
+            .tools
+            .read()
+            .values()
+            .filter(|tool| tool.enabled && tool.group.as_deref() == Some(group))
+            .map(|tool| Tool::from(&self.bundled(tool)))
+            .collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        tools
+    }
+
+    /// 设置了配额的工具的当前用量，按名称排序
+    pub fn quota_status(&self) -> Vec<QuotaStatus> {
+        let mut status: Vec<QuotaStatus> = self
+            .tools
+            .read()
+            .values()
+            .filter_map(|tool| tool.quota.as_ref().map(|quota| quota.status("tool", &tool.name)))
+            .collect();
+        status.sort_by(|a, b| a.name.cmp(&b.name));
+        status
+    }
+
+    /// 工具的并发许可，没有设置上限时为`None`
+    pub(crate) fn concurrency_permits(&self, name: &str) -> Option<Arc<Semaphore>> {
+        self.tools.read().get(name).and_then(|tool| tool.permits.clone())
+    }
+
+    /// 工具是否存在且可能修改状态，参见[`FunctionTool::may_mutate`]
+    pub(crate) fn may_mutate(&self, name: &str, unannotated: bool) -> bool {
+        self.tools.read().get(name).is_some_and(|tool| tool.may_mutate(unannotated))
     }
 
     /// 调用工具
-    #[allow(dead_code)]
-    pub fn call_tool(&self, name: &str, args: Option<HashMap<String, Value>>) -> Result<Value, String> {
-        if let Some(tool) = self.get_tool(name) {
-            tool.call(args)
-        } else {
-            Err(format!("Tool '{}' not found", name))
+    ///
+    /// 只在查找工具时持有读锁，工具函数执行期间不持有锁，长时间运行的工具不会阻塞注册。
+    pub fn call_tool(&self, name: &str, args: Option<HashMap<String, Value>>, ctx: &Context) -> Result<Value, McpError> {
+        let name = &*self.resolve_tool_name(name);
+        let tool = {
+            let tools = self.tools.read();
+            match tools.get(name) {
+                Some(tool) => tool.clone(),
+                None => {
+                    return Err(McpError::ToolNotFound {
+                        name: name.to_string(),
+                        suggestions: suggest(name, tools.values().filter(|t| t.enabled).map(|t| &t.name)),
+                    })
+                }
+            }
+        };
+        if !tool.enabled {
+            return Err(McpError::ToolDisabled(name.to_string()));
+        }
+        if !tool.is_bound() {
+            return Err(McpError::ToolNotBound(name.to_string()));
         }
+        let mut args = args;
+        if let (Some(schema), Some(args)) = (&tool.input_schema, args.as_mut()) {
+            if tool.lenient_coercion.unwrap_or(self.lenient_coercion) {
+                // 编译结果中的模式已经展开了共享定义
+                match &tool.validator {
+                    Some(validator) => coerce_arguments(validator.schema(), args),
+                    None => {
+                        let resolved = self.definitions.resolve(schema);
+                        coerce_arguments(resolved.as_ref().unwrap_or(schema), args)
+                    }
+                };
+            }
+        }
+        tool.validate_arguments(args.as_ref())?;
+        if let Some(quota) = &tool.quota {
+            quota.admit("tool", name)?;
+        }
+        let result = match &tool.retry {
+            Some(policy) => call_with_retry(&tool, policy, ctx, args),
+            None => tool.call(ctx, args),
+        };
+        result.map_err(|message| McpError::ToolExecution {
+            name: name.to_string(),
+            message,
+        })
+    }
+}
+
+/// 按重试策略调用工具，并把尝试次数写入结果的`_meta.attempts`
+fn call_with_retry(
+    tool: &FunctionTool,
+    policy: &RetryPolicy,
+    ctx: &Context,
+    args: Option<HashMap<String, Value>>,
+) -> Result<Value, String> {
+    let mut attempts = 0;
+    let mut args = args;
+    let result = loop {
+        attempts += 1;
+        // 处理函数拿走参数，只有还可能重试时才复制
+        let attempt_args = if attempts < policy.max_attempts { args.clone() } else { args.take() };
+        match tool.call(ctx, attempt_args) {
+            Err(message) if attempts < policy.max_attempts && policy.is_retryable(&message) => {
+                let delay = policy.backoff.delay(attempts);
+                // 等待会超过截止时间时不再重试
+                if ctx.remaining().is_some_and(|remaining| remaining <= delay) {
+                    debug!("Tool '{}' failed (attempt {}), not retrying past the deadline: {}", tool.name, attempts, message);
+                    break Err(message);
+                }
+                debug!("Tool '{}' failed (attempt {}), retrying in {:?}: {}", tool.name, attempts, delay, message);
+                if !sleep_unless_cancelled(ctx, delay) {
+                    debug!("Tool '{}' was cancelled while waiting to retry", tool.name);
+                    break Err(message);
+                }
+            }
+            result => break result,
+        }
+    };
+    let mut meta = match ctx.result_meta() {
+        Some(Value::Object(meta)) => meta,
+        _ => serde_json::Map::new(),
+    };
+    meta.insert("attempts".to_string(), Value::from(attempts));
+    ctx.set_result_meta(Value::Object(meta));
+    result
+}
+
+/// 分段等待，请求被取消时提前返回`false`
+fn sleep_unless_cancelled(ctx: &Context, delay: Duration) -> bool {
+    const SLICE: Duration = Duration::from_millis(10);
+    let until = std::time::Instant::now() + delay;
+    loop {
+        if ctx.is_cancelled() {
+            return false;
+        }
+        let left = until.saturating_duration_since(std::time::Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        std::thread::sleep(left.min(SLICE));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::server::test_support::{call, get, initialize, post, request, stdio};
+    use crate::server::{create_app, RustMCP};
+    use crate::Settings;
+
+    fn tool(name: &str, group: Option<&str>) -> FunctionTool {
+        let builder = FunctionTool::builder()
+            .name(name)
+            .description("Test tool")
+            .input_schema(json!({ "type": "object" }))
+            .handler(|_| Ok(json!("ok")));
+        match group {
+            Some(group) => builder.group(group).build(),
+            None => builder.build(),
+        }
+    }
+
+    #[test]
+    fn group_is_serialized_in_meta() {
+        let manager = ToolManager::new();
+        manager.add_tool(tool("add", Some("math")));
+        manager.add_tool(tool("echo", None));
+        manager.add_tool(
+            FunctionTool::builder()
+                .name("mul")
+                .input_schema(json!({ "type": "object" }))
+                .meta(json!({ "cost": 2 }))
+                .group("math")
+                .handler(|_| Ok(json!(0)))
+                .build(),
+        );
+
+        let serialized = |name: &str| serde_json::to_value(Tool::from(&manager.get_tool(name).unwrap())).unwrap();
+        assert_eq!(
+            serialized("add"),
+            json!({ "name": "add", "description": "Test tool", "inputSchema": { "type": "object" }, "_meta": { "group": "math" } })
+        );
+        assert!(serialized("echo").get("_meta").is_none());
+        // 分组与已有的元数据合并
+        assert_eq!(serialized("mul")["_meta"], json!({ "cost": 2, "group": "math" }));
+    }
+
+    #[tokio::test]
+    async fn bulk_disable_and_enable_notify_once() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool_group("math", "Arithmetic");
+        for (name, group) in [("add", Some("math")), ("mul", Some("math")), ("echo", None)] {
+            rustmcp.add_tool(tool(name, group));
+        }
+        let mut notifications = rustmcp.notifier().subscribe();
+        let app = create_app(rustmcp.clone());
+        let listed = |response: Value| {
+            let mut names: Vec<String> = response["result"]["tools"].as_array().unwrap().iter().map(|tool| tool["name"].as_str().unwrap().to_string()).collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(rustmcp.disable_tool_group("math"), 2);
+        assert_eq!(notifications.try_recv().unwrap().method, "notifications/tools/list_changed");
+        assert!(notifications.try_recv().is_err());
+        assert_eq!(listed(call(&app, "tools/list", json!({})).await), ["echo"]);
+        let response = call(&app, "tools/call", json!({ "name": "add" })).await;
+        assert_eq!(response["error"]["data"], json!({ "kind": "tool_disabled", "name": "add" }));
+
+        // 状态没有变化时不通知
+        assert_eq!(rustmcp.disable_tool_group("math"), 0);
+        assert!(notifications.try_recv().is_err());
+
+        assert_eq!(rustmcp.enable_tool_group("math"), 2);
+        assert!(notifications.try_recv().is_ok());
+        assert!(notifications.try_recv().is_err());
+        assert_eq!(listed(call(&app, "tools/list", json!({})).await), ["add", "echo", "mul"]);
+
+        // REST列表可以按分组过滤
+        let (status, _, body) = get(&app, "/mcp/tools?group=math", &[]).await;
+        assert_eq!(status, 200);
+        assert_eq!(body.as_array().unwrap().len(), 2, "{}", body);
+        assert!(body.as_array().unwrap().iter().all(|tool| tool["_meta"]["group"] == "math"));
+        assert_eq!(rustmcp.mcp_list_tool_groups().len(), 1);
+
+        assert_eq!(rustmcp.remove_tool_group("math"), 2);
+        assert!(notifications.try_recv().is_ok());
+        assert!(notifications.try_recv().is_err());
+        assert_eq!(listed(call(&app, "tools/list", json!({})).await), ["echo"]);
+        assert!(rustmcp.mcp_list_tool_groups().is_empty());
+    }
+
+    /// 在后台线程运行`work`，超时视为死锁
+    fn within(limit: std::time::Duration, work: impl FnOnce() + Send + 'static) {
+        let (done, finished) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            work();
+            let _ = done.send(());
+        });
+        finished.recv_timeout(limit).expect("registry operations deadlocked");
+    }
+
+    #[test]
+    fn concurrent_adds_removes_and_calls_keep_listings_consistent() {
+        use crate::server::prompts::{FunctionPrompt, PromptManager, PromptMessage};
+        use crate::server::resources::{FunctionResource, ResourceManager};
+
+        const THREADS: usize = 8;
+        const ROUNDS: usize = 200;
+        let tools = Arc::new(ToolManager::new());
+        let resources = Arc::new(ResourceManager::new());
+        let prompts = Arc::new(PromptManager::new());
+        tools.add_tool(tool("shared", None));
+
+        let (tools_, resources_, prompts_) = (tools.clone(), resources.clone(), prompts.clone());
+        within(std::time::Duration::from_secs(30), move || {
+            std::thread::scope(|scope| {
+                for thread in 0..THREADS {
+                    let (tools, resources, prompts) = (&tools_, &resources_, &prompts_);
+                    scope.spawn(move || {
+                        for round in 0..ROUNDS {
+                            let name = format!("t{}_{}", thread, round);
+                            tools.add_tool(tool(&name, Some("stress")));
+                            resources.add_resource(FunctionResource::simple(format!("memo://{}", name), || Ok(json!("r"))));
+                            prompts.add_prompt(FunctionPrompt::simple(name.clone(), |_| Ok(vec![PromptMessage::user("p")])));
+                            assert_eq!(tools.call_tool("shared", None, &Context::new()).unwrap(), json!("ok"));
+                            assert_eq!(tools.call_tool(&name, None, &Context::new()).unwrap(), json!("ok"));
+                            assert!(resources.read_resource(&format!("memo://{}", name), &Context::new()).is_ok());
+                            assert!(prompts.get_prompt(&name, None, &Context::new()).is_ok());
+                            // 列表中没有重复项，且总是包含共享的工具
+                            let listed = tools.list_tools();
+                            assert!(listed.iter().any(|tool| tool.name == "shared"));
+                            let unique: std::collections::HashSet<_> = listed.iter().map(|tool| &tool.name).collect();
+                            assert_eq!(unique.len(), listed.len());
+                            if round > 0 {
+                                let previous = format!("t{}_{}", thread, round - 1);
+                                assert!(tools.remove_tool(&previous));
+                                assert!(resources.remove_resource(&format!("memo://{}", previous)));
+                                assert!(prompts.remove_prompt(&previous));
+                            }
+                        }
+                    });
+                }
+            });
+        });
+
+        // 每个线程只留下最后一轮的项
+        let mut names: Vec<String> = tools.list_tools().into_iter().map(|tool| tool.name).collect();
+        names.sort();
+        let mut expected: Vec<String> = (0..THREADS).map(|thread| format!("t{}_{}", thread, ROUNDS - 1)).collect();
+        expected.push("shared".to_string());
+        expected.sort();
+        assert_eq!(names, expected);
+        assert_eq!(resources.list_resources().len(), THREADS);
+        assert_eq!(prompts.list_prompts().len(), THREADS);
+        assert_eq!(tools.list_tools_in_group("stress").len(), THREADS);
+    }
+
+    #[test]
+    fn long_running_call_does_not_block_registration() {
+        let tools = Arc::new(ToolManager::new());
+        let (started, running) = std::sync::mpsc::channel();
+        let (release, released) = std::sync::mpsc::channel::<()>();
+        let released = parking_lot::Mutex::new(released);
+        tools.add_tool(FunctionTool::simple("slow", "Blocks until released", move |_| {
+            started.send(()).unwrap();
+            released.lock().recv().unwrap();
+            Ok(json!("done"))
+        }));
+
+        let caller = {
+            let tools = tools.clone();
+            std::thread::spawn(move || tools.call_tool("slow", None, &Context::new()))
+        };
+        running.recv().unwrap();
+        let registry = tools.clone();
+        within(std::time::Duration::from_secs(5), move || {
+            registry.add_tool(tool("added", None));
+            assert!(registry.remove_tool("added"));
+            assert_eq!(registry.list_tools().len(), 1);
+        });
+        release.send(()).unwrap();
+        assert_eq!(caller.join().unwrap().unwrap(), json!("done"));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn omitted_null_and_empty_arguments_are_distinguished() {
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::simple("inspect", "Reports how arguments arrived", |args| {
+            Ok(json!(match args {
+                None => "none".to_string(),
+                Some(args) => format!("{} args", args.len()),
+            }))
+        }));
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("greet")
+                .description("Greets someone")
+                .input_schema(json!({
+                    "type": "object",
+                    "properties": { "name": { "type": "string" } },
+                    "required": ["name"]
+                }))
+                .validate_input(true)
+                .handler(|_| Ok(json!("hello")))
+                .build(),
+        );
+
+        let shapes = [None, Some(Value::Null), Some(json!({}))];
+        let mut messages = vec![request(0, "initialize", json!({}))];
+        for (id, shape) in shapes.iter().enumerate() {
+            for tool in ["inspect", "greet"] {
+                let mut params = json!({ "name": tool });
+                if let Some(shape) = shape {
+                    params["arguments"] = shape.clone();
+                }
+                messages.push(json!({ "jsonrpc": "2.0", "id": format!("{}-{}", tool, id), "method": "tools/call", "params": params }));
+            }
+        }
+        let responses = stdio(&rustmcp, &messages).await;
+        let response = |id: &str| responses.iter().find(|r| r["id"] == id).unwrap().clone();
+        let text = |id: &str| response(id)["result"]["content"][0]["text"].as_str().unwrap().to_string();
+
+        // 省略和null都是None，{}是空表
+        assert!(text("inspect-0").contains("none"));
+        assert!(text("inspect-1").contains("none"));
+        assert!(text("inspect-2").contains("0 args"));
+        // 三种情况都缺少必需参数
+        for id in ["greet-0", "greet-1", "greet-2"] {
+            assert_eq!(response(id)["error"]["code"], -32602);
+            assert_eq!(response(id)["error"]["message"], "Missing required argument 'name'");
+        }
+    }
+
+    #[tokio::test]
+    async fn lists_over_the_byte_budget_keep_high_priority_tools() {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_max_tools_list_bytes(160));
+        for (name, priority) in [("archive", 1), ("search", 9), ("delete", 0), ("fetch", 9), ("notify", 3)] {
+            rustmcp.add_tool(FunctionTool::builder().name(name).description("Does one thing").priority(priority).handler(|_| Ok(json!(null))).build());
+        }
+        let app = create_app(rustmcp.clone());
+        let session = initialize(&app, json!({})).await;
+        let rpc = |method: &'static str, params: Value| {
+            let (app, session) = (app.clone(), session.clone());
+            async move { post(&app, "/mcp", &[("mcp-session-id", &session)], &request(1, method, params)).await.2["result"].take() }
+        };
+        let names = |result: &Value| -> Vec<String> {
+            result["tools"].as_array().unwrap().iter().map(|tool| tool["name"].as_str().unwrap().to_string()).collect()
+        };
+
+        let result = rpc("tools/list", json!({})).await;
+        assert_eq!(names(&result), ["fetch", "search", "notify"]);
+        assert_eq!(result["_meta"]["truncatedCount"], 2);
+        assert!(serde_json::to_vec(&result["tools"]).unwrap().len() <= 160);
+
+        // 会翻页的客户端用nextCursor拿到其余的工具
+        let rest = rpc("tools/list", json!({ "cursor": result["nextCursor"] })).await;
+        assert_eq!(names(&rest), ["archive", "delete"]);
+        assert!(rest.get("nextCursor").is_none() && rest["_meta"].get("truncatedCount").is_none());
+    }
+}
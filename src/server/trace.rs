@@ -0,0 +1,554 @@
+//! 协议跟踪
+//!
+//! 排查与某个客户端的互通问题时，需要看到线上实际收发的消息。开启跟踪后，每条收到和发出的
+//! JSON-RPC消息（HTTP的`POST /mcp`和SSE通知流、WebSocket、标准输入输出）按收发顺序写成一行JSON，
+//! 追加到[`TraceConfig::path`]：
+//!
+//! ```text
+//! {"seq":1,"monotonicUs":12,"timestampMs":1760000000000,"direction":"inbound","transport":"stdio","sessionId":"…","message":{"jsonrpc":"2.0",…}}
+//! ```
+//!
+//! - `message`是消息本身，不是JSON的内容按字符串记录；`params.arguments`中名称像密码、令牌的字段替换为`[REDACTED]`，
+//!   声明式工具读取过的[密钥](crate::server::secrets)的值无论出现在哪里也会被替换；
+//! - `monotonicUs`是开启跟踪以来的微秒数，不受系统时间调整影响；
+//! - [`TraceConfig::per_session`]为`true`时每个会话写到单独的文件，`trace.jsonl`中会话`abc`的消息写到`trace.abc.jsonl`，
+//!   没有会话的消息仍写到`trace.jsonl`。会话ID中文件名不允许的字符替换为`_`并加上ID的哈希，例如`trace.a_b.<哈希>.jsonl`；
+//!   同时最多打开64个会话文件，超过时关闭最久没有写入的，之后再写入时重新打开；
+//! - 写文件在单独的线程中进行，不会阻塞请求处理：消息放入有界队列（[`TraceConfig::queue_depth`]），
+//!   队列满时丢弃并计数（[`RustMCP::dropped_trace_entries`](crate::RustMCP::dropped_trace_entries)）；
+//! - 最近写出的条目保留在内存中，通过[`RustMCP::trace_tail`](crate::RustMCP::trace_tail)或管理接口的
+//!   `GET /trace/tail?lines=N`读取，不需要登录到服务器上查看文件。
+//!
+//! 跟踪可以在运行时通过[`RustMCP::enable_trace`](crate::RustMCP::enable_trace)或管理接口的`PUT /trace`开启，
+//! `rustmcp-serve`在环境变量`RUSTMCP_TRACE`设置了文件路径时启动即开启（[`TraceConfig::from_env`]）。
+//! 跟踪记录完整的消息内容，只应在排查问题时短时间开启。
+//!
+//! ```rust
+//! use rustmcp::{RustMCP, TraceConfig};
+//!
+//! let path = std::env::temp_dir().join(format!("rustmcp-trace-{}.jsonl", std::process::id()));
+//! let rustmcp = RustMCP::new();
+//! rustmcp.enable_trace(TraceConfig::new(&path).per_session(true)).unwrap();
+//! // 排查结束后关闭
+//! rustmcp.disable_trace();
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::server::capture::Redacted;
+use crate::server::context::TransportKind;
+use crate::server::secrets;
+
+/// 设置跟踪文件路径的环境变量
+pub const TRACE_ENV: &str = "RUSTMCP_TRACE";
+
+/// 为真（`1`或`true`）时按会话拆分跟踪文件的环境变量
+pub const TRACE_PER_SESSION_ENV: &str = "RUSTMCP_TRACE_PER_SESSION";
+
+/// 按会话拆分时同时打开的会话文件数上限，超过时关闭最久没有写入的
+const MAX_OPEN_SESSION_FILES: usize = 64;
+
+/// 跟踪设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TraceConfig {
+    /// JSONL文件路径，以追加方式写入
+    pub path: PathBuf,
+    /// 是否每个会话写到单独的文件
+    #[serde(default)]
+    pub per_session: bool,
+    /// 等待写出的条目数上限，队列满时丢弃新条目
+    #[serde(default = "default_queue_depth")]
+    pub queue_depth: usize,
+    /// 在内存中保留的最近条目数，供`trace/tail`读取
+    #[serde(default = "default_tail_capacity")]
+    pub tail_capacity: usize,
+}
+
+fn default_queue_depth() -> usize {
+    1024
+}
+
+fn default_tail_capacity() -> usize {
+    1000
+}
+
+impl TraceConfig {
+    /// 写到`path`，不按会话拆分
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            per_session: false,
+            queue_depth: default_queue_depth(),
+            tail_capacity: default_tail_capacity(),
+        }
+    }
+
+    /// 按环境变量`RUSTMCP_TRACE`和`RUSTMCP_TRACE_PER_SESSION`创建设置，没有设置路径时返回`None`
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var_os(TRACE_ENV).filter(|path| !path.is_empty())?;
+        let per_session = std::env::var(TRACE_PER_SESSION_ENV).is_ok_and(|value| matches!(value.trim(), "1" | "true"));
+        Some(Self::new(path).per_session(per_session))
+    }
+
+    /// 设置是否每个会话写到单独的文件
+    pub fn per_session(mut self, enabled: bool) -> Self {
+        self.per_session = enabled;
+        self
+    }
+
+    /// 设置等待写出的条目数上限
+    pub fn queue_depth(mut self, depth: usize) -> Self {
+        self.queue_depth = depth;
+        self
+    }
+
+    /// 设置在内存中保留的最近条目数
+    pub fn tail_capacity(mut self, capacity: usize) -> Self {
+        self.tail_capacity = capacity;
+        self
+    }
+
+    /// 会话的跟踪文件：`trace.jsonl`变为`trace.<会话>.jsonl`
+    ///
+    /// 会话ID中文件名不允许的字符替换为`_`，并在后面加上`.`和原ID的哈希，
+    /// 替换后相同的两个ID不会写到同一个文件，也不会与不需要替换的ID（其中没有`.`）冲突。
+    fn session_path(&self, session_id: &str) -> PathBuf {
+        let mut session: String = session_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        if session != session_id {
+            let digest = Sha256::digest(session_id.as_bytes());
+            session.push('.');
+            session.extend(digest[..8].iter().map(|byte| format!("{:02x}", byte)));
+        }
+        let stem = self.path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        let name = match self.path.extension() {
+            Some(extension) => format!("{}.{}.{}", stem, session, extension.to_string_lossy()),
+            None => format!("{}.{}", stem, session),
+        };
+        self.path.with_file_name(name)
+    }
+}
+
+/// 消息的方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Direction {
+    /// 客户端发给服务器
+    Inbound,
+    /// 服务器发给客户端
+    Outbound,
+}
+
+/// 一条跟踪记录
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TraceEntry<'a> {
+    seq: u64,
+    monotonic_us: u64,
+    timestamp_ms: u64,
+    direction: Direction,
+    transport: TransportKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session_id: Option<&'a str>,
+    message: Value,
+}
+
+/// 发给写出线程的消息
+enum Message {
+    Line { file: Option<PathBuf>, line: String },
+    Flush(oneshot::Sender<()>),
+}
+
+/// 开启中的跟踪
+struct Active {
+    config: TraceConfig,
+    sender: mpsc::Sender<Message>,
+    started: Instant,
+    seq: AtomicU64,
+}
+
+/// 跟踪开关、写出队列和最近的条目，克隆的[`RustMCP`](crate::RustMCP)共享同一份
+#[derive(Default)]
+pub(crate) struct Tracer {
+    /// 快速判断是否开启，关闭时消息路径上只有一次原子读取
+    enabled: AtomicBool,
+    active: RwLock<Option<Active>>,
+    dropped: AtomicU64,
+    tail: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl Tracer {
+    /// 开启跟踪并替换设置，清空内存中的最近条目；文件无法打开时返回错误
+    ///
+    /// 之前的写出线程写完队列中的条目后结束。
+    pub fn enable(&self, config: TraceConfig) -> std::io::Result<()> {
+        let file = open_append(&config.path)?;
+        let (sender, receiver) = mpsc::channel(config.queue_depth.max(1));
+        let writer = Writer {
+            path: config.path.clone(),
+            file,
+            files: HashMap::new(),
+            writes: 0,
+            tail: self.tail.clone(),
+            tail_capacity: config.tail_capacity,
+        };
+        std::thread::Builder::new()
+            .name("rustmcp-trace".to_string())
+            .spawn(move || writer.run(receiver))?;
+        self.tail.lock().clear();
+        *self.active.write() = Some(Active {
+            config,
+            sender,
+            started: Instant::now(),
+            seq: AtomicU64::new(0),
+        });
+        self.enabled.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// 关闭跟踪，队列中的条目仍会写完，内存中的最近条目保留
+    pub fn disable(&self) {
+        self.enabled.store(false, Ordering::Release);
+        *self.active.write() = None;
+    }
+
+    /// 当前设置，关闭时为`None`
+    pub fn config(&self) -> Option<TraceConfig> {
+        self.active.read().as_ref().map(|active| active.config.clone())
+    }
+
+    /// 记录一条消息，放入队列后立即返回，队列满时丢弃
+    pub fn record(&self, direction: Direction, transport: TransportKind, session_id: Option<&str>, text: &str) {
+        if !self.enabled.load(Ordering::Acquire) {
+            return;
+        }
+        let active = self.active.read();
+        let Some(active) = active.as_ref() else {
+            return;
+        };
+        let entry = TraceEntry {
+            seq: active.seq.fetch_add(1, Ordering::Relaxed) + 1,
+            monotonic_us: u64::try_from(active.started.elapsed().as_micros()).unwrap_or(u64::MAX),
+            timestamp_ms: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0),
+            direction,
+            transport,
+            session_id,
+            message: redacted_message(text),
+        };
+        let line = secrets::redact(&serde_json::to_string(&entry).unwrap_or_default());
+        let file = session_id.filter(|_| active.config.per_session).map(|session| active.config.session_path(session));
+        if active.sender.try_send(Message::Line { file, line }).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed);
+            if dropped.is_multiple_of(1000) {
+                log::warn!("Trace queue is full; {} entries dropped so far", dropped + 1);
+            }
+        }
+    }
+
+    /// 等待之前记录的条目都写到文件，没有开启时立即返回
+    pub async fn flush(&self) {
+        let sender = match self.active.read().as_ref() {
+            Some(active) => active.sender.clone(),
+            None => return,
+        };
+        let (done, wait) = oneshot::channel();
+        if sender.send(Message::Flush(done)).await.is_ok() {
+            let _ = wait.await;
+        }
+    }
+
+    /// 最近写出的`lines`条记录，按时间顺序
+    pub fn tail(&self, lines: usize) -> Vec<Value> {
+        let tail = self.tail.lock();
+        tail.iter()
+            .skip(tail.len().saturating_sub(lines))
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    }
+
+    /// 因队列满而丢弃的条目数
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// 绑定传输和会话的记录入口
+    pub fn tap(self: &Arc<Self>, transport: TransportKind, session_id: Option<&str>) -> TraceTap {
+        TraceTap {
+            tracer: self.clone(),
+            transport,
+            session_id: session_id.map(Arc::from),
+        }
+    }
+}
+
+impl std::fmt::Debug for Tracer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tracer")
+            .field("config", &self.config())
+            .field("dropped", &self.dropped())
+            .finish_non_exhaustive()
+    }
+}
+
+/// 一个连接的记录入口，传输层在收发消息时调用
+#[derive(Debug, Clone)]
+pub(crate) struct TraceTap {
+    tracer: Arc<Tracer>,
+    transport: TransportKind,
+    session_id: Option<Arc<str>>,
+}
+
+impl TraceTap {
+    /// 记录收到的消息
+    pub fn inbound(&self, text: &str) {
+        self.tracer.record(Direction::Inbound, self.transport, self.session_id.as_deref(), text);
+    }
+
+    /// 记录发出的消息
+    pub fn outbound(&self, text: &str) {
+        self.tracer.record(Direction::Outbound, self.transport, self.session_id.as_deref(), text);
+    }
+}
+
+/// 解析消息并脱敏`params.arguments`，不是JSON的消息按字符串记录
+fn redacted_message(text: &str) -> Value {
+    let Ok(mut message) = serde_json::from_str::<Value>(text) else {
+        return Value::String(text.to_string());
+    };
+    if let Some(arguments) = message.get_mut("params").and_then(|params| params.get_mut("arguments")) {
+        *arguments = serde_json::to_value(Redacted::Value(arguments)).unwrap_or_default();
+    }
+    message
+}
+
+/// 写出线程，持有打开的文件
+struct Writer {
+    path: PathBuf,
+    file: File,
+    /// 打开的会话文件和最近一次写入的序号，最多[`MAX_OPEN_SESSION_FILES`]个
+    files: HashMap<PathBuf, (File, u64)>,
+    /// 写入的次数，用于找出最久没有写入的会话文件
+    writes: u64,
+    tail: Arc<Mutex<VecDeque<String>>>,
+    tail_capacity: usize,
+}
+
+impl Writer {
+    /// 依次写出队列中的条目，队列的发送端都释放后结束
+    fn run(mut self, mut receiver: mpsc::Receiver<Message>) {
+        while let Some(message) = receiver.blocking_recv() {
+            match message {
+                Message::Line { file, line } => self.write(file, line),
+                Message::Flush(done) => {
+                    let _ = self.file.flush();
+                    for (file, _) in self.files.values_mut() {
+                        let _ = file.flush();
+                    }
+                    let _ = done.send(());
+                }
+            }
+        }
+    }
+
+    /// 写一行到会话文件，`session_file`为`None`时写到主文件
+    fn write(&mut self, session_file: Option<PathBuf>, mut line: String) {
+        line.push('\n');
+        let written = match &session_file {
+            None => self.file.write_all(line.as_bytes()),
+            Some(path) => self.write_session(path, line.as_bytes()),
+        };
+        if let Err(e) = written {
+            let path = session_file.as_ref().unwrap_or(&self.path);
+            log::warn!("Failed to write trace entry to {}: {}", path.display(), e);
+            return;
+        }
+        line.pop();
+        let mut tail = self.tail.lock();
+        while tail.len() >= self.tail_capacity.max(1) {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    }
+
+    /// 写到会话文件，需要打开新文件并且已达上限时先关闭最久没有写入的
+    fn write_session(&mut self, path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+        self.writes += 1;
+        if let Some((file, used)) = self.files.get_mut(path) {
+            *used = self.writes;
+            return file.write_all(bytes);
+        }
+        let mut file = open_append(path)?;
+        file.write_all(bytes)?;
+        if self.files.len() >= MAX_OPEN_SESSION_FILES {
+            let oldest = self.files.iter().min_by_key(|(_, (_, used))| *used).map(|(path, _)| path.clone());
+            if let Some(oldest) = oldest {
+                self.files.remove(&oldest);
+            }
+        }
+        self.files.insert(path.to_path_buf(), (file, self.writes));
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::server::test_support::{initialize, post, request, stdio};
+    use crate::{create_app, FunctionTool, RustMCP};
+
+    /// 新建一个空的临时目录
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rustmcp-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn read(path: &Path) -> Vec<Value> {
+        let text = std::fs::read_to_string(path).unwrap();
+        text.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn messages_are_traced_in_order_and_redacted() {
+        let dir = temp_dir("trace");
+        let path = dir.join("trace.jsonl");
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(FunctionTool::simple("login", "Signs in", |_| Ok(json!("signed in"))));
+        rustmcp.enable_trace(TraceConfig::new(&path)).unwrap();
+
+        let input = [
+            request(1, "initialize", json!({})),
+            json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+            request(2, "tools/call", json!({ "name": "login", "arguments": { "api_key": "hunter2", "user": "ada" } })),
+        ];
+        // 最后一行不是JSON，不能用`stdio`辅助函数发送
+        let lines: Vec<String> = input.iter().map(Value::to_string).chain(["not json".to_string()]).collect();
+        crate::server::stdio::serve_lines(Arc::new(rustmcp.clone()), lines.join("\n").as_bytes(), tokio::io::sink())
+            .await
+            .unwrap();
+        rustmcp.flush_trace().await;
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(!text.contains("hunter2"));
+        let entries = read(&path);
+        let summary: Vec<(&str, Value)> = entries
+            .iter()
+            .map(|entry| {
+                let message = &entry["message"];
+                let what = if message["method"].is_string() { message["method"].clone() } else { message["id"].clone() };
+                (entry["direction"].as_str().unwrap(), what)
+            })
+            .collect();
+        assert_eq!(summary, [
+            ("inbound", json!("initialize")),
+            ("outbound", json!(1)),
+            ("inbound", json!("notifications/initialized")),
+            ("inbound", json!("tools/call")),
+            ("outbound", json!(2)),
+            ("inbound", json!(null)),
+            ("outbound", json!(null)),
+        ]);
+        assert_eq!(entries[3]["message"]["params"]["arguments"], json!({ "api_key": "[REDACTED]", "user": "ada" }));
+        assert_eq!(entries[5]["message"], "not json");
+        assert!(entries.iter().all(|entry| entry["transport"] == "stdio" && entry["sessionId"].is_string()));
+        let seqs: Vec<u64> = entries.iter().map(|entry| entry["seq"].as_u64().unwrap()).collect();
+        assert_eq!(seqs, (1..=7).collect::<Vec<u64>>());
+        assert!(entries.windows(2).all(|pair| pair[0]["monotonicUs"].as_u64() <= pair[1]["monotonicUs"].as_u64()));
+
+        // 最近的条目也可以直接读取
+        assert_eq!(rustmcp.trace_tail(2), entries[5..]);
+        assert_eq!(rustmcp.dropped_trace_entries(), 0);
+
+        // 关闭后不再记录
+        rustmcp.disable_trace();
+        stdio(&rustmcp, &input).await;
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), text);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn sessions_are_traced_to_separate_files() {
+        let dir = temp_dir("trace-sessions");
+        let rustmcp = RustMCP::new();
+        rustmcp.enable_trace(TraceConfig::new(dir.join("trace.jsonl")).per_session(true)).unwrap();
+        let app = create_app(rustmcp.clone());
+        let alpha = initialize(&app, json!({})).await;
+        let beta = initialize(&app, json!({})).await;
+        post(&app, "/mcp", &[("mcp-session-id", &alpha)], &request(1, "tools/list", json!({}))).await;
+        rustmcp.flush_trace().await;
+
+        let entries = read(&dir.join(format!("trace.{}.jsonl", alpha)));
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[2]["message"]["method"], "tools/list");
+        assert!(entries.iter().all(|entry| entry["transport"] == "http" && entry["sessionId"] == alpha.as_str()));
+        assert_eq!(read(&dir.join(format!("trace.{}.jsonl", beta))).len(), 2);
+        assert!(read(&dir.join("trace.jsonl")).is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sanitized_session_names_do_not_collide() {
+        let config = TraceConfig::new("/var/log/trace.jsonl");
+        assert_eq!(config.session_path("alpha-1"), Path::new("/var/log/trace.alpha-1.jsonl"));
+        let (slash, colon) = (config.session_path("a/b"), config.session_path("a:b"));
+        assert_ne!(slash, colon);
+        assert!(slash.to_str().unwrap().starts_with("/var/log/trace.a_b."), "{}", slash.display());
+        assert_ne!(config.session_path("a_b"), slash);
+        assert_eq!(config.session_path("a/b"), slash);
+    }
+
+    #[test]
+    fn session_files_are_closed_least_recently_used_first() {
+        let dir = temp_dir("trace-open-files");
+        let config = TraceConfig::new(dir.join("trace.jsonl"));
+        let mut writer = Writer {
+            path: config.path.clone(),
+            file: open_append(&config.path).unwrap(),
+            files: HashMap::new(),
+            writes: 0,
+            tail: Arc::default(),
+            tail_capacity: 1,
+        };
+        let line = |session: usize| format!("{{\"session\":{}}}", session);
+        for session in 0..MAX_OPEN_SESSION_FILES {
+            writer.write(Some(config.session_path(&session.to_string())), line(session));
+        }
+        // 会话0最近写过，关闭的是会话1
+        writer.write(Some(config.session_path("0")), line(0));
+        writer.write(Some(config.session_path("new")), line(99));
+        assert_eq!(writer.files.len(), MAX_OPEN_SESSION_FILES);
+        assert!(writer.files.contains_key(&config.session_path("0")));
+        assert!(!writer.files.contains_key(&config.session_path("1")));
+
+        // 关闭的文件再次写入时重新打开并追加
+        writer.write(Some(config.session_path("1")), line(1));
+        assert_eq!(writer.files.len(), MAX_OPEN_SESSION_FILES);
+        assert_eq!(read(&config.session_path("1")), [json!({ "session": 1 }), json!({ "session": 1 })]);
+        assert_eq!(read(&config.session_path("0")).len(), 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
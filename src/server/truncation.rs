@@ -0,0 +1,215 @@
+//! 工具结果的大小限制
+//!
+//! [`Settings::with_max_result_bytes`](crate::Settings::with_max_result_bytes)设置所有工具的上限，
+//! [`FunctionToolBuilder::max_result_bytes`](crate::FunctionToolBuilder::max_result_bytes)覆盖单个工具的上限。
+//! 工具返回值序列化后的字节数超过上限时：
+//! - `content`只保留一个文本块，为结果文本的前`上限`个字节，在UTF-8字符边界处截断；
+//! - 结果的`_meta`中带有`truncated: true`和`originalSize`（序列化后的原始字节数）；
+//! - 工具声明了`outputSchema`时，按[`TruncationPolicy`]丢弃`structuredContent`或返回`isError`结果。
+//!
+//! 大小用计数的写入器测量，截断时只序列化前`上限`个字节，超大的结果不会先生成完整的文本。
+//!
+//! ```rust
+//! use rustmcp::{FunctionTool, RustMCP, Settings, TruncationPolicy};
+//! use serde_json::json;
+//!
+//! let settings = Settings::new().with_max_result_bytes(64 * 1024).with_truncation_policy(TruncationPolicy::Fail);
+//! let rustmcp = RustMCP::new().with_settings(settings);
+//! // 单个工具的上限覆盖全局设置
+//! rustmcp.add_tool(
+//!     FunctionTool::builder()
+//!         .name("dump")
+//!         .max_result_bytes(1024 * 1024)
+//!         .handler(|_| Ok(json!("...")))
+//!         .build(),
+//! );
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{self, Write};
+
+use crate::server::content::Content;
+
+/// 结果超过上限且工具返回了`structuredContent`时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationPolicy {
+    /// 丢弃`structuredContent`，只返回截断的文本
+    #[default]
+    DropStructured,
+    /// 返回`isError: true`的结果，说明结果过大
+    Fail,
+}
+
+/// 超过上限的结果
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Oversize {
+    /// 序列化后的原始字节数
+    pub size: usize,
+    /// 上限
+    pub limit: usize,
+}
+
+impl Oversize {
+    /// 测量`value`序列化后的大小，超过`limit`时返回`Some`
+    pub fn check(value: &Value, limit: usize) -> Option<Self> {
+        let mut counter = Counter(0);
+        serde_json::to_writer(&mut counter, value).ok()?;
+        (counter.0 > limit).then_some(Oversize { size: counter.0, limit })
+    }
+
+    /// 结果文本的前`limit`个字节：内容块合并后的文本，其余值为紧凑的JSON文本
+    pub fn text(&self, value: &Value) -> String {
+        if let Some(content) = Content::from_tool_value(value) {
+            if let Content::Text { mut text } = Content::flatten(&content) {
+                let mut end = self.limit.min(text.len());
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                text.truncate(end);
+                return text;
+            }
+        }
+        let mut prefix = Prefix { buf: Vec::with_capacity(self.limit), limit: self.limit };
+        // 写满后写入器返回错误，序列化在这里停止
+        let _ = serde_json::to_writer(&mut prefix, value);
+        let mut buf = prefix.buf;
+        if let Err(e) = std::str::from_utf8(&buf) {
+            buf.truncate(e.valid_up_to());
+        }
+        String::from_utf8(buf).unwrap_or_default()
+    }
+
+    /// 工具返回了`structuredContent`且策略为[`TruncationPolicy::Fail`]时的错误消息
+    pub fn message(&self, name: &str) -> String {
+        format!(
+            "Result of tool '{}' is {} bytes, above the limit of {} bytes, and structured content cannot be truncated",
+            name, self.size, self.limit
+        )
+    }
+
+    /// 在结果的`_meta`中标记截断
+    pub fn mark(&self, mut result: Value) -> Value {
+        if let Value::Object(map) = &mut result {
+            let meta = map.entry("_meta").or_insert_with(|| serde_json::json!({}));
+            if let Value::Object(meta) = meta {
+                meta.insert("truncated".to_string(), Value::Bool(true));
+                meta.insert("originalSize".to_string(), Value::from(self.size));
+            }
+        }
+        result
+    }
+}
+
+/// 只计数的写入器
+struct Counter(usize);
+
+impl Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 只保留前`limit`个字节的写入器
+struct Prefix {
+    buf: Vec<u8>,
+    limit: usize,
+}
+
+impl Write for Prefix {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let room = self.limit - self.buf.len();
+        if room == 0 {
+            return Err(io::Error::new(io::ErrorKind::WriteZero, "result limit reached"));
+        }
+        let n = room.min(buf.len());
+        self.buf.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::server::test_support::{request, stdio};
+    use crate::{FunctionTool, RustMCP, Settings};
+
+    fn server(policy: TruncationPolicy) -> RustMCP {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_max_result_bytes(16).with_truncation_policy(policy));
+        rustmcp.add_tool(FunctionTool::simple("small", "", |_| Ok(json!("ok"))));
+        rustmcp.add_tool(FunctionTool::simple("dump", "", |_| Ok(json!("日志".repeat(100)))));
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("report")
+                .output_schema(json!({ "type": "object" }))
+                .handler(|_| Ok(json!({ "rows": vec![1; 100] })))
+                .build(),
+        );
+        // 单个工具的上限覆盖全局设置
+        rustmcp.add_tool(
+            FunctionTool::builder()
+                .name("roomy")
+                .max_result_bytes(1024)
+                .handler(|_| Ok(json!("日志".repeat(100))))
+                .build(),
+        );
+        rustmcp
+    }
+
+    async fn call(rustmcp: &RustMCP, name: &str) -> Value {
+        let responses = stdio(rustmcp, &[
+            request(0, "initialize", json!({ "protocolVersion": "2025-06-18" })),
+            request(1, "tools/call", json!({ "name": name })),
+        ])
+        .await;
+        responses[1]["result"].clone()
+    }
+
+    #[tokio::test]
+    async fn oversized_results_are_truncated_on_a_char_boundary() {
+        let rustmcp = server(TruncationPolicy::DropStructured);
+
+        // 没有超过上限时不变
+        let result = call(&rustmcp, "small").await;
+        assert_eq!(result["content"][0]["text"], "\"ok\"");
+        assert!(result.get("_meta").is_none());
+
+        // 超过上限时截断文本，不会切开多字节字符
+        let result = call(&rustmcp, "dump").await;
+        assert_eq!(result["content"][0]["text"], "\"日志日志日");
+        assert_eq!(result["_meta"], json!({ "truncated": true, "originalSize": 602 }));
+        assert_eq!(call(&rustmcp, "roomy").await["content"][0]["text"].as_str().unwrap().len(), 602);
+
+        // 默认丢弃structuredContent
+        let result = call(&rustmcp, "report").await;
+        assert_eq!(result["content"][0]["text"], "{\"rows\":[1,1,1,1");
+        assert!(result.get("structuredContent").is_none());
+        assert_eq!(result["_meta"]["truncated"], true);
+        assert_eq!(result["isError"], false);
+    }
+
+    #[tokio::test]
+    async fn fail_policy_rejects_oversized_structured_results() {
+        let rustmcp = server(TruncationPolicy::Fail);
+        let result = call(&rustmcp, "report").await;
+        assert_eq!(result["isError"], true);
+        assert_eq!(
+            result["content"][0]["text"],
+            "Result of tool 'report' is 210 bytes, above the limit of 16 bytes, and structured content cannot be truncated"
+        );
+        // 没有outputSchema的工具仍然截断
+        assert_eq!(call(&rustmcp, "dump").await["_meta"]["truncated"], true);
+    }
+}
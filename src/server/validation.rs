@@ -0,0 +1,546 @@
+//! 参数校验
+//!
+//! 一个只覆盖工具参数常用子集的JSON Schema校验器：`type`、`enum`、`required`、
+//! `properties`（递归）和`items`。其余关键字会被忽略。
+//! 校验失败时返回[`McpError::InvalidParams`]，`param`为出错参数的路径（例如`options.level`、`tags[1]`）。
+//!
+//! 宽松模式下，校验前先用[`coerce_arguments`]把字符串参数转换为模式声明的类型。
+//!
+//! 工具管理器在注册时把开启了输入校验的工具模式编译为[`CompiledSchema`]，调用时不再逐次解释模式；
+//! 模式结构不正确时注册失败，而不是等到调用时才发现。
+
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::server::error::McpError;
+
+/// 按输入模式校验工具参数，没有参数（`None`）时按空对象校验
+///
+/// 直接在参数表上逐个校验，不把参数复制成一个对象（模式的顶层带`enum`时除外）。
+pub fn validate_arguments(schema: &Value, arguments: Option<&HashMap<String, Value>>) -> Result<(), McpError> {
+    let Some(object) = schema.as_object() else {
+        return Ok(());
+    };
+    if object.contains_key("enum") {
+        let arguments = Value::Object(arguments.map(|args| args.clone().into_iter().collect()).unwrap_or_default());
+        return validate(schema, &arguments, "");
+    }
+    if let Some(expected) = object.get("type").and_then(|t| t.as_str()) {
+        // 参数总是对象，用空对象判断类型
+        if !matches_type(expected, &Value::Object(serde_json::Map::new())) {
+            return Err(McpError::invalid_params(
+                format!("Invalid type for '{}': expected {}, got object", display_path(""), expected),
+                display_path(""),
+                expected,
+            ));
+        }
+    }
+    validate_members(object, "", |name| arguments.and_then(|args| args.get(name)))
+}
+
+/// JSON Schema允许的类型名
+const SCHEMA_TYPES: &[&str] = &["object", "array", "string", "number", "integer", "boolean", "null"];
+
+/// 编译后的模式，校验结果与[`validate_arguments`]相同
+///
+/// 编译时检查模式的结构，展开常用的关键字，之后每次校验只遍历编译结果。
+/// 工具管理器编译的是展开共享定义后的模式，编译结果保存在工具上，克隆的工具共享同一份。
+///
+/// ```rust
+/// use rustmcp::server::validation::CompiledSchema;
+/// use serde_json::json;
+/// use std::collections::HashMap;
+///
+/// let schema = CompiledSchema::compile(&json!({
+///     "type": "object",
+///     "properties": { "level": { "type": "integer" } },
+///     "required": ["level"]
+/// })).unwrap();
+/// let args: HashMap<_, _> = [("level".to_string(), json!("high"))].into();
+/// assert_eq!(
+///     schema.validate_arguments(Some(&args)).unwrap_err().to_string(),
+///     "Invalid type for 'level': expected integer, got string"
+/// );
+///
+/// // 结构不正确的模式不能编译
+/// assert_eq!(
+///     CompiledSchema::compile(&json!({ "properties": { "level": { "type": "int" } } })).unwrap_err(),
+///     "schema.properties.level.type has an unknown type \"int\""
+/// );
+/// ```
+#[derive(Debug)]
+pub struct CompiledSchema {
+    schema: Value,
+    root: Node,
+}
+
+impl CompiledSchema {
+    /// 编译模式，结构不正确时返回错误
+    pub fn compile(schema: &Value) -> Result<Self, String> {
+        Self::compile_as(schema.clone(), "schema")
+    }
+
+    /// 编译模式，错误消息中模式的根路径为`root`
+    pub(crate) fn compile_as(schema: Value, root: &str) -> Result<Self, String> {
+        check_schema(&schema, root)?;
+        let compiled = Node::compile(&schema);
+        Ok(Self { schema, root: compiled })
+    }
+
+    /// 编译时使用的模式
+    pub fn schema(&self) -> &Value {
+        &self.schema
+    }
+
+    /// 校验工具参数，没有参数（`None`）时按空对象校验
+    pub fn validate_arguments(&self, arguments: Option<&HashMap<String, Value>>) -> Result<(), McpError> {
+        let root = &self.root;
+        if root.allowed.is_some() {
+            let arguments = Value::Object(arguments.map(|args| args.clone().into_iter().collect()).unwrap_or_default());
+            return root.validate(&arguments, "");
+        }
+        if let Some(expected) = &root.ty {
+            if !matches_type(expected, &Value::Object(serde_json::Map::new())) {
+                return Err(McpError::invalid_params(
+                    format!("Invalid type for '{}': expected {}, got object", display_path(""), expected),
+                    display_path(""),
+                    expected.as_str(),
+                ));
+            }
+        }
+        root.validate_members("", |name| arguments.and_then(|args| args.get(name)))
+    }
+
+    /// 校验一个值，`path`为该值在参数中的路径，根为空字符串
+    pub fn validate(&self, value: &Value, path: &str) -> Result<(), McpError> {
+        self.root.validate(value, path)
+    }
+}
+
+/// 编译后的一层模式，不是对象的模式编译为接受任意值的空节点
+#[derive(Debug, Default)]
+struct Node {
+    /// `type`为单个类型名时的类型
+    ty: Option<String>,
+    /// `enum`的取值和错误消息中的期望值
+    allowed: Option<(Vec<Value>, String)>,
+    /// 必需的成员和错误消息中的期望类型
+    required: Vec<(String, String)>,
+    properties: Vec<(String, Node)>,
+    items: Option<Box<Node>>,
+}
+
+impl Node {
+    fn compile(schema: &Value) -> Self {
+        let Some(schema) = schema.as_object() else {
+            return Self::default();
+        };
+        let properties = schema.get("properties").and_then(|p| p.as_object());
+        Self {
+            ty: schema.get("type").and_then(|t| t.as_str()).map(str::to_string),
+            allowed: schema
+                .get("enum")
+                .and_then(|e| e.as_array())
+                .map(|allowed| (allowed.clone(), format!("one of {}", Value::Array(allowed.clone())))),
+            required: schema
+                .get("required")
+                .and_then(|r| r.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|r| r.as_str())
+                .map(|name| {
+                    let expected = properties
+                        .and_then(|p| p.get(name))
+                        .and_then(|p| p.get("type"))
+                        .and_then(|t| t.as_str())
+                        .unwrap_or("value");
+                    (name.to_string(), expected.to_string())
+                })
+                .collect(),
+            properties: properties
+                .into_iter()
+                .flatten()
+                .map(|(name, property)| (name.clone(), Node::compile(property)))
+                .collect(),
+            items: schema.get("items").map(|items| Box::new(Node::compile(items))),
+        }
+    }
+
+    fn validate(&self, value: &Value, path: &str) -> Result<(), McpError> {
+        if let Some(expected) = &self.ty {
+            if !matches_type(expected, value) {
+                return Err(McpError::invalid_params(
+                    format!("Invalid type for '{}': expected {}, got {}", display_path(path), expected, type_name(value)),
+                    display_path(path),
+                    expected.as_str(),
+                ));
+            }
+        }
+        if let Some((allowed, expected)) = &self.allowed {
+            if !allowed.contains(value) {
+                return Err(McpError::invalid_params(
+                    format!("Invalid value for '{}': expected {}", display_path(path), expected),
+                    display_path(path),
+                    expected.as_str(),
+                ));
+            }
+        }
+        if let Some(object) = value.as_object() {
+            self.validate_members(path, |name| object.get(name))?;
+        }
+        if let (Some(items), Some(array)) = (&self.items, value.as_array()) {
+            for (index, item) in array.iter().enumerate() {
+                items.validate(item, &format!("{}[{}]", path, index))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_members<'a>(&self, path: &str, get: impl Fn(&str) -> Option<&'a Value>) -> Result<(), McpError> {
+        for (name, expected) in &self.required {
+            if get(name).is_none() {
+                let param = join_path(path, name);
+                return Err(McpError::invalid_params(
+                    format!("Missing required argument '{}'", param),
+                    param,
+                    expected.as_str(),
+                ));
+            }
+        }
+        for (name, property) in &self.properties {
+            if let Some(child) = get(name) {
+                property.validate(child, &join_path(path, name))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 检查模式的结构：`type`、`properties`、`items`、`required`和`enum`的形状是否正确
+pub(crate) fn check_schema(schema: &Value, path: &str) -> Result<(), String> {
+    let object = match schema {
+        Value::Object(object) => object,
+        Value::Bool(_) => return Ok(()),
+        _ => return Err(format!("{} must be an object", path)),
+    };
+    if let Some(ty) = object.get("type") {
+        let valid = match ty {
+            Value::String(name) => SCHEMA_TYPES.contains(&name.as_str()),
+            Value::Array(names) => names.iter().all(|n| n.as_str().is_some_and(|n| SCHEMA_TYPES.contains(&n))),
+            _ => false,
+        };
+        if !valid {
+            return Err(format!("{}.type has an unknown type {}", path, ty));
+        }
+    }
+    if let Some(properties) = object.get("properties") {
+        let properties = properties
+            .as_object()
+            .ok_or_else(|| format!("{}.properties must be an object", path))?;
+        for (name, property) in properties {
+            check_schema(property, &format!("{}.properties.{}", path, name))?;
+        }
+    }
+    if let Some(items) = object.get("items") {
+        check_schema(items, &format!("{}.items", path))?;
+    }
+    if let Some(required) = object.get("required") {
+        if !required.as_array().is_some_and(|names| names.iter().all(Value::is_string)) {
+            return Err(format!("{}.required must be an array of strings", path));
+        }
+    }
+    if object.get("enum").is_some_and(|values| !values.is_array()) {
+        return Err(format!("{}.enum must be an array", path));
+    }
+    Ok(())
+}
+
+/// 按模式校验一个值，`path`为该值在参数中的路径，根为空字符串
+pub fn validate(schema: &Value, value: &Value, path: &str) -> Result<(), McpError> {
+    let schema = match schema.as_object() {
+        Some(schema) => schema,
+        None => return Ok(()),
+    };
+
+    if let Some(expected) = schema.get("type").and_then(|t| t.as_str()) {
+        if !matches_type(expected, value) {
+            return Err(McpError::invalid_params(
+                format!("Invalid type for '{}': expected {}, got {}", display_path(path), expected, type_name(value)),
+                display_path(path),
+                expected,
+            ));
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !allowed.contains(value) {
+            let expected = format!("one of {}", Value::Array(allowed.clone()));
+            return Err(McpError::invalid_params(
+                format!("Invalid value for '{}': expected {}", display_path(path), expected),
+                display_path(path),
+                expected,
+            ));
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        validate_members(schema, path, |name| object.get(name))?;
+    }
+
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (index, item) in array.iter().enumerate() {
+            validate(items, item, &format!("{}[{}]", path, index))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// 校验对象的`required`和`properties`，`get`按名称查找成员
+fn validate_members<'a>(
+    schema: &serde_json::Map<String, Value>,
+    path: &str,
+    get: impl Fn(&str) -> Option<&'a Value>,
+) -> Result<(), McpError> {
+    if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+        for name in required.iter().filter_map(|r| r.as_str()) {
+            if get(name).is_none() {
+                let param = join_path(path, name);
+                let expected = schema
+                    .get("properties")
+                    .and_then(|p| p.get(name))
+                    .and_then(|p| p.get("type"))
+                    .and_then(|t| t.as_str())
+                    .unwrap_or("value")
+                    .to_string();
+                return Err(McpError::invalid_params(
+                    format!("Missing required argument '{}'", param),
+                    param,
+                    expected,
+                ));
+            }
+        }
+    }
+    if let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) {
+        for (name, property) in properties {
+            if let Some(child) = get(name) {
+                validate(property, child, &join_path(path, name))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 按输入模式宽松转换参数，返回发生转换的参数路径
+///
+/// 只进行无损转换：
+/// - 声明为`integer`的字符串，能解析为整数且没有小数部分（`"5"`可以，`"1.5"`和`"05"`不行）；
+/// - 声明为`number`的字符串，是合法的JSON数字字面量；
+/// - 声明为`boolean`的字符串，恰好是`"true"`或`"false"`；
+/// - 带`enum`的字符串，去掉首尾空白后在取值列表中。
+pub fn coerce_arguments(schema: &Value, arguments: &mut HashMap<String, Value>) -> Vec<String> {
+    let mut coerced = Vec::new();
+    if let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) {
+        for (name, property) in properties {
+            if let Some(value) = arguments.get_mut(name) {
+                coerce(property, value, name, &mut coerced);
+            }
+        }
+    }
+    coerced
+}
+
+/// 递归转换一个值
+fn coerce(schema: &Value, value: &mut Value, path: &str, coerced: &mut Vec<String>) {
+    if let Some(replacement) = coerce_scalar(schema, value) {
+        log::debug!("Coerced argument '{}' from {} to {}", path, value, replacement);
+        *value = replacement;
+        coerced.push(path.to_string());
+        return;
+    }
+    match value {
+        Value::Object(object) => {
+            if let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) {
+                for (name, property) in properties {
+                    if let Some(child) = object.get_mut(name) {
+                        coerce(property, child, &join_path(path, name), coerced);
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter_mut().enumerate() {
+                    coerce(item_schema, item, &format!("{}[{}]", path, index), coerced);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 把字符串转换为模式声明的标量类型，不能无损转换时返回`None`
+fn coerce_scalar(schema: &Value, value: &Value) -> Option<Value> {
+    let text = value.as_str()?;
+    let declared = schema.get("type").and_then(|t| t.as_str());
+
+    if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array()) {
+        let trimmed = Value::String(text.trim().to_string());
+        return (trimmed.as_str() != Some(text) && !allowed.contains(value) && allowed.contains(&trimmed)).then_some(trimmed);
+    }
+
+    match declared? {
+        "integer" => {
+            let number: serde_json::Number = serde_json::from_str(text).ok()?;
+            let lossless = (number.is_i64() || number.is_u64()) && number.to_string() == text;
+            lossless.then_some(Value::Number(number))
+        }
+        "number" => {
+            let number: serde_json::Number = serde_json::from_str(text).ok()?;
+            (number.to_string() == text || number.as_f64().is_some_and(|f| f.is_finite() && text.parse::<f64>() == Ok(f)))
+                .then_some(Value::Number(number))
+        }
+        "boolean" => match text {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// 判断值是否符合JSON Schema类型
+fn matches_type(expected: &str, value: &Value) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// 值的JSON类型名称
+pub(crate) fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn join_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn display_path(path: &str) -> String {
+    if path.is_empty() {
+        "arguments".to_string()
+    } else {
+        path.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::server::schema::ToolArgs;
+    use crate::server::{FunctionTool, RustMCP};
+
+    fn schema() -> Value {
+        ToolArgs::new()
+            .integer("count", "Count", true)
+            .number("ratio", "Ratio", false)
+            .boolean("loud", "Loud", false)
+            .enumeration("level", "Level", &["low", "high"], false)
+            .object("options", "Options", ToolArgs::new().integer("depth", "Depth", false), false)
+            .build_schema()
+    }
+
+    fn arguments(value: Value) -> HashMap<String, Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn lossless_strings_are_coerced() {
+        let mut args = arguments(json!({
+            "count": "5", "ratio": "0.25", "loud": "true", "level": "  high ", "options": { "depth": "2" }
+        }));
+        let mut coerced = coerce_arguments(&schema(), &mut args);
+        coerced.sort();
+        assert_eq!(coerced, ["count", "level", "loud", "options.depth", "ratio"]);
+        assert_eq!(Value::Object(args.into_iter().collect()), json!({
+            "count": 5, "ratio": 0.25, "loud": true, "level": "high", "options": { "depth": 2 }
+        }));
+    }
+
+    #[test]
+    fn ambiguous_strings_are_left_alone() {
+        for (name, text) in [
+            ("count", "1.5"),
+            ("count", "05"),
+            ("count", " 5"),
+            ("count", "1e3"),
+            ("ratio", "NaN"),
+            ("ratio", "half"),
+            ("loud", "yes"),
+            ("loud", "True"),
+            ("level", "medium"),
+        ] {
+            let mut args = arguments(json!({ name: text }));
+            assert!(coerce_arguments(&schema(), &mut args).is_empty(), "{} = {:?}", name, text);
+            assert_eq!(args[name], text);
+        }
+        // 已经是正确类型的值和模式中没有的参数不变
+        let mut args = arguments(json!({ "count": 5, "extra": "7" }));
+        assert!(coerce_arguments(&schema(), &mut args).is_empty());
+        assert_eq!(args["extra"], "7");
+    }
+
+    /// 返回收到的参数
+    fn echo(lenient: Option<bool>) -> FunctionTool {
+        let builder = FunctionTool::builder().name("echo").args(ToolArgs::new().integer("count", "Count", true).boolean("loud", "Loud", false));
+        let builder = match lenient {
+            Some(enabled) => builder.lenient_coercion(enabled),
+            None => builder,
+        };
+        builder.handler(|args| Ok(json!(args.unwrap_or_default()))).build()
+    }
+
+    #[tokio::test]
+    async fn coercion_is_opt_in_and_tools_receive_coerced_arguments() {
+        let strings = || Some(arguments(json!({ "count": "3", "loud": "false" })));
+
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(echo(None));
+        let error = rustmcp.mcp_call_tool("echo", strings()).await.unwrap_err();
+        assert!(matches!(error, McpError::InvalidParams { .. }), "{:?}", error);
+
+        let rustmcp = RustMCP::new().with_lenient_coercion(true);
+        rustmcp.add_tool(echo(None));
+        assert_eq!(rustmcp.mcp_call_tool("echo", strings()).await.unwrap(), json!({ "count": 3, "loud": false }));
+        let error = rustmcp.mcp_call_tool("echo", Some(arguments(json!({ "count": "1.5" })))).await.unwrap_err();
+        assert!(matches!(error, McpError::InvalidParams { .. }), "{:?}", error);
+
+        // 工具的设置覆盖工具管理器的设置
+        let rustmcp = RustMCP::new().with_lenient_coercion(true);
+        rustmcp.add_tool(echo(Some(false)));
+        assert!(rustmcp.mcp_call_tool("echo", strings()).await.is_err());
+        let rustmcp = RustMCP::new();
+        rustmcp.add_tool(echo(Some(true)));
+        assert_eq!(rustmcp.mcp_call_tool("echo", strings()).await.unwrap(), json!({ "count": 3, "loud": false }));
+    }
+}
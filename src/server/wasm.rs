@@ -0,0 +1,322 @@
+//! WASM沙箱工具
+//!
+//! 使用wasmtime运行不受信任的工具实现。模块只在创建工具时编译一次，每次调用都在新的
+//! `Store`中实例化，调用之间不共享内存。
+//!
+//! ## 内存ABI
+//!
+//! 模块不能导入任何函数，并且需要导出：
+//! - `memory`: 线性内存；
+//! - `alloc(len: i32) -> i32`: 分配`len`字节，返回起始地址；
+//! - 工具函数`(ptr: i32, len: i32) -> i64`: 参数为JSON编码的工具参数（对象）所在的内存区域，
+//!   返回值的高32位为结果的地址，低32位为结果的长度。
+//!
+//! 结果为JSON时按JSON返回，否则作为字符串返回；`{"error": "..."}`形式的结果会转换为工具错误。
+//! 陷阱（trap）、燃料耗尽、超时和内存超限都会转换为工具错误。返回的结果区域超出线性内存，
+//! 或者长度超过[`WasmLimits::max_result_bytes`]时，在复制结果之前返回工具错误。
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+use wasmtime::{Config, Engine, Instance, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+
+use crate::server::tools::FunctionTool;
+
+/// 纪元计时器的间隔，超时按这个粒度检查
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// 默认燃料上限
+const DEFAULT_FUEL: u64 = 10_000_000;
+
+/// 默认内存上限（16MB）
+const DEFAULT_MAX_MEMORY: usize = 16 * 1024 * 1024;
+
+/// 默认超时
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 默认结果大小上限（4MB）
+const DEFAULT_MAX_RESULT: usize = 4 * 1024 * 1024;
+
+/// WASM工具的资源限制
+#[derive(Debug, Clone)]
+pub struct WasmLimits {
+    /// 每次调用可以消耗的燃料（大致对应执行的指令数），为`None`时不限制
+    pub fuel: Option<u64>,
+    /// 线性内存上限（字节）
+    pub max_memory_bytes: usize,
+    /// 每次调用的最长执行时间，为`None`时不限制
+    pub timeout: Option<Duration>,
+    /// 工具返回结果的最大字节数
+    pub max_result_bytes: usize,
+}
+
+impl WasmLimits {
+    /// 创建默认限制
+    pub fn new() -> Self {
+        Self {
+            fuel: Some(DEFAULT_FUEL),
+            max_memory_bytes: DEFAULT_MAX_MEMORY,
+            timeout: Some(DEFAULT_TIMEOUT),
+            max_result_bytes: DEFAULT_MAX_RESULT,
+        }
+    }
+
+    /// 设置燃料上限
+    pub fn with_fuel(mut self, fuel: Option<u64>) -> Self {
+        self.fuel = fuel;
+        self
+    }
+
+    /// 设置内存上限
+    pub fn with_max_memory(mut self, bytes: usize) -> Self {
+        self.max_memory_bytes = bytes;
+        self
+    }
+
+    /// 设置超时
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 设置结果大小上限
+    pub fn with_max_result(mut self, bytes: usize) -> Self {
+        self.max_result_bytes = bytes;
+        self
+    }
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// WASM工具错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum WasmError {
+    /// 模块编译失败
+    Compile(String),
+    /// 模块缺少ABI要求的导出
+    MissingExport(String),
+}
+
+impl std::fmt::Display for WasmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WasmError::Compile(message) => write!(f, "Failed to compile WASM module: {}", message),
+            WasmError::MissingExport(name) => write!(f, "WASM module does not export '{}'", name),
+        }
+    }
+}
+
+impl std::error::Error for WasmError {}
+
+impl From<WasmError> for String {
+    fn from(e: WasmError) -> Self {
+        e.to_string()
+    }
+}
+
+/// 共享的引擎，开启燃料计量和纪元中断
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        config.epoch_interruption(true);
+        let engine = Engine::new(&config).expect("wasmtime engine configuration is valid");
+
+        // 后台线程定期推进纪元，用于实现超时
+        let ticker = engine.clone();
+        std::thread::Builder::new()
+            .name("rustmcp-wasm-epoch".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(EPOCH_TICK);
+                ticker.increment_epoch();
+            })
+            .expect("failed to spawn wasm epoch thread");
+        engine
+    })
+}
+
+/// 每次调用的存储状态
+struct CallState {
+    limits: StoreLimits,
+}
+
+impl FunctionTool {
+    /// 从WASM模块创建在沙箱中执行的工具
+    ///
+    /// 模块只编译一次；`bytes`可以是二进制模块或WAT文本。工具名称默认为`export_name`，
+    /// 可以在返回后修改`name`、`description`和`input_schema`。
+    pub fn from_wasm_module(bytes: &[u8], export_name: &str, limits: WasmLimits) -> Result<Self, WasmError> {
+        let module = Module::new(engine(), bytes).map_err(|e| WasmError::Compile(e.to_string()))?;
+        for required in ["memory", "alloc", export_name] {
+            if module.get_export(required).is_none() {
+                return Err(WasmError::MissingExport(required.to_string()));
+            }
+        }
+
+        let export_name = export_name.to_string();
+        Ok(FunctionTool::builder()
+            .name(export_name.clone())
+            .handler(move |args: Option<HashMap<String, Value>>| call_module(&module, &export_name, &limits, args))
+            .build())
+    }
+}
+
+/// 实例化模块并调用工具函数
+fn call_module(
+    module: &Module,
+    export_name: &str,
+    limits: &WasmLimits,
+    args: Option<HashMap<String, Value>>,
+) -> Result<Value, String> {
+    let input = serde_json::to_vec(&args.unwrap_or_default()).map_err(|e| e.to_string())?;
+
+    let mut store = Store::new(
+        engine(),
+        CallState {
+            limits: StoreLimitsBuilder::new()
+                .memory_size(limits.max_memory_bytes)
+                .instances(1)
+                .build(),
+        },
+    );
+    store.limiter(|state| &mut state.limits);
+    store.set_fuel(limits.fuel.unwrap_or(u64::MAX)).map_err(|e| e.to_string())?;
+    match limits.timeout {
+        Some(timeout) => store.set_epoch_deadline((timeout.as_millis() / EPOCH_TICK.as_millis()).max(1) as u64 + 1),
+        None => store.set_epoch_deadline(u64::MAX),
+    }
+
+    let linker: Linker<CallState> = Linker::new(engine());
+    let instance = linker.instantiate(&mut store, module).map_err(|e| describe_error(&e, limits))?;
+    run(&mut store, &instance, export_name, &input, limits.max_result_bytes).map_err(|e| describe_error(&e, limits))
+}
+
+/// 按内存ABI传入参数并读取结果
+fn run(store: &mut Store<CallState>, instance: &Instance, export_name: &str, input: &[u8], max_result_bytes: usize) -> wasmtime::Result<Value> {
+    let memory = instance
+        .get_memory(&mut *store, "memory")
+        .ok_or_else(|| wasmtime::Error::msg("module does not export 'memory'"))?;
+    let alloc = instance.get_typed_func::<i32, i32>(&mut *store, "alloc")?;
+    let function = instance.get_typed_func::<(i32, i32), i64>(&mut *store, export_name)?;
+
+    let len = i32::try_from(input.len()).map_err(|_| wasmtime::Error::msg("arguments are too large"))?;
+    let ptr = alloc.call(&mut *store, len)?;
+    memory.write(&mut *store, ptr as u32 as usize, input)?;
+
+    let packed = function.call(&mut *store, (ptr, len))? as u64;
+    let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+    // 地址和长度由模块给出，分配之前先检查
+    if out_ptr.checked_add(out_len).is_none_or(|end| end > memory.data_size(&*store)) {
+        return Err(wasmtime::Error::msg(format!(
+            "result region {}..{} is outside linear memory of {} bytes",
+            out_ptr,
+            out_ptr.saturating_add(out_len),
+            memory.data_size(&*store)
+        )));
+    }
+    if out_len > max_result_bytes {
+        return Err(wasmtime::Error::msg(format!("result of {} bytes exceeds the limit of {} bytes", out_len, max_result_bytes)));
+    }
+    let mut output = vec![0u8; out_len];
+    memory.read(&*store, out_ptr, &mut output)?;
+
+    let value = match serde_json::from_slice::<Value>(&output) {
+        Ok(value) => value,
+        Err(_) => Value::String(String::from_utf8_lossy(&output).into_owned()),
+    };
+    if let Some(message) = value.as_object().filter(|o| o.len() == 1).and_then(|o| o.get("error")) {
+        let message = message.as_str().map(|s| s.to_string()).unwrap_or_else(|| message.to_string());
+        return Err(wasmtime::Error::msg(message));
+    }
+    Ok(value)
+}
+
+/// 把wasmtime错误转换为工具错误信息
+fn describe_error(error: &wasmtime::Error, limits: &WasmLimits) -> String {
+    match error.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => format!("WASM tool ran out of fuel (limit {})", limits.fuel.unwrap_or_default()),
+        Some(Trap::Interrupt) => format!("WASM tool timed out after {:?}", limits.timeout.unwrap_or_default()),
+        Some(trap) => format!("WASM tool trapped: {}", trap),
+        None => format!("WASM tool failed: {}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::server::Context;
+
+    /// 符合内存ABI的模块，`body`是工具函数`run`的函数体，参数为`$ptr`和`$len`
+    fn module(body: &str) -> String {
+        format!(
+            r#"(module
+              (memory (export "memory") 1)
+              (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+              (func (export "run") (param $ptr i32) (param $len i32) (result i64) {})
+              (func (export "echo") (param $ptr i32) (param $len i32) (result i64)
+                (i64.or (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32)) (i64.extend_i32_u (local.get $len)))))"#,
+            body
+        )
+    }
+
+    fn call(body: &str, limits: WasmLimits, export_name: &str) -> Result<Value, String> {
+        let tool = FunctionTool::from_wasm_module(module(body).as_bytes(), export_name, limits).unwrap();
+        let args = HashMap::from([("message".to_string(), json!("hi"))]);
+        tool.call(&Context::new(), Some(args))
+    }
+
+    #[test]
+    fn echo_returns_arguments() {
+        assert_eq!(call("(i64.const 0)", WasmLimits::new(), "echo"), Ok(json!({ "message": "hi" })));
+    }
+
+    #[test]
+    fn missing_exports_are_rejected() {
+        let error = FunctionTool::from_wasm_module(module("(i64.const 0)").as_bytes(), "absent", WasmLimits::new()).unwrap_err();
+        assert_eq!(error, WasmError::MissingExport("absent".to_string()));
+    }
+
+    #[test]
+    fn traps_become_tool_errors() {
+        let error = call("(unreachable)", WasmLimits::new(), "run").unwrap_err();
+        assert!(error.starts_with("WASM tool trapped"), "{}", error);
+    }
+
+    #[test]
+    fn fuel_exhaustion_becomes_a_tool_error() {
+        let limits = WasmLimits::new().with_fuel(Some(10_000)).with_timeout(None);
+        let error = call("(loop $spin (br $spin)) (i64.const 0)", limits, "run").unwrap_err();
+        assert_eq!(error, "WASM tool ran out of fuel (limit 10000)");
+    }
+
+    #[test]
+    fn timeouts_become_tool_errors() {
+        let limits = WasmLimits::new().with_fuel(None).with_timeout(Some(Duration::from_millis(50)));
+        let error = call("(loop $spin (br $spin)) (i64.const 0)", limits, "run").unwrap_err();
+        assert!(error.starts_with("WASM tool timed out"), "{}", error);
+    }
+
+    #[test]
+    fn out_of_bounds_results_are_rejected_before_allocating() {
+        // 长度接近4GB，超出1页内存
+        let error = call("(i64.const 0xffff_ffff)", WasmLimits::new(), "run").unwrap_err();
+        assert!(error.contains("outside linear memory"), "{}", error);
+        // 地址超出内存
+        let error = call("(i64.const 0x0010_0000_0000_0001)", WasmLimits::new(), "run").unwrap_err();
+        assert!(error.contains("outside linear memory"), "{}", error);
+    }
+
+    #[test]
+    fn results_larger_than_the_limit_are_rejected() {
+        let error = call("(i64.const 1000)", WasmLimits::new().with_max_result(100), "run").unwrap_err();
+        assert_eq!(error, "WASM tool failed: result of 1000 bytes exceeds the limit of 100 bytes");
+    }
+}
@@ -0,0 +1,188 @@
+//! 清单目录热加载
+//!
+//! [`RustMCP::watch_manifest_dir`](crate::RustMCP::watch_manifest_dir)加载目录中的`*.json`清单，
+//! 每个清单描述一个执行命令的工具，之后轮询目录：新增或修改的清单重新注册，删除的清单对应的
+//! 工具被移除，工具列表有变化时发送`notifications/tools/list_changed`。
+//! 清单解析失败或输入模式不能编译时记录文件名和错误并跳过，已注册的旧版本保持不变。
+//!
+//! ```json
+//! {
+//!   "name": "greet",
+//!   "description": "Says hello",
+//!   "schema": {
+//!     "type": "object",
+//!     "properties": { "who": { "type": "string" } },
+//!     "required": ["who"]
+//!   },
+//!   "command": ["echo", "hello {who}"]
+//! }
+//! ```
+//!
+//! 命令不经过shell，模板的每个元素单独替换`{参数名}`后作为一个参数传给程序。
+//! 也可以用`proxy`代替`command`，把调用转发到HTTP地址（需要`admin`特性），格式与管理接口相同。
+//! 清单可以执行任意命令，只应监视受信任的目录。
+
+use log::{info, warn};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+use crate::server::notifications::Notifier;
+use crate::server::manifest::ToolManifest;
+use crate::server::tools::{FunctionTool, ToolManager};
+
+/// 轮询目录的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 清单文件的修改标记
+type FileStamp = (Option<SystemTime>, u64);
+
+/// 已加载的清单
+struct LoadedManifest {
+    stamp: FileStamp,
+    /// 清单注册的工具名称，解析一直失败的清单为`None`
+    tool: Option<String>,
+}
+
+/// 清单目录监视器
+///
+/// 丢弃或调用[`ManifestWatcher::stop`]时停止监视，已注册的工具保留。
+#[must_use = "dropping the watcher stops watching the directory"]
+pub struct ManifestWatcher {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ManifestWatcher {
+    /// 停止监视并等待后台线程结束
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for ManifestWatcher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl std::fmt::Debug for ManifestWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ManifestWatcher")
+            .field("running", &self.handle.is_some())
+            .finish()
+    }
+}
+
+/// 加载目录中的清单并启动后台轮询
+pub(crate) fn watch(dir: &Path, tools: ToolManager, notifier: Notifier) -> std::io::Result<ManifestWatcher> {
+    let mut state = DirState {
+        dir: dir.to_path_buf(),
+        tools,
+        loaded: HashMap::new(),
+    };
+    // 目录不存在时直接返回错误，而不是在后台静默失败
+    if state.scan()? {
+        notifier.tools_list_changed();
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_flag = stop.clone();
+    let handle = std::thread::Builder::new()
+        .name("rustmcp-manifest-watch".to_string())
+        .spawn(move || {
+            while !stop_flag.load(Ordering::Relaxed) {
+                std::thread::sleep(POLL_INTERVAL);
+                match state.scan() {
+                    Ok(true) => notifier.tools_list_changed(),
+                    Ok(false) => {}
+                    Err(e) => warn!("Failed to scan manifest directory {}: {}", state.dir.display(), e),
+                }
+            }
+        })?;
+    Ok(ManifestWatcher {
+        stop,
+        handle: Some(handle),
+    })
+}
+
+struct DirState {
+    dir: PathBuf,
+    tools: ToolManager,
+    loaded: HashMap<PathBuf, LoadedManifest>,
+}
+
+impl DirState {
+    /// 对比目录和上次扫描的结果并同步工具，返回工具列表是否变化
+    fn scan(&mut self) -> std::io::Result<bool> {
+        let mut current = HashMap::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") || !path.is_file() {
+                continue;
+            }
+            if let Ok(metadata) = std::fs::metadata(&path) {
+                current.insert(path, (metadata.modified().ok(), metadata.len()));
+            }
+        }
+
+        let mut changed = false;
+        let removed: Vec<PathBuf> = self.loaded.keys().filter(|path| !current.contains_key(*path)).cloned().collect();
+        for path in removed {
+            if let Some(name) = self.loaded.remove(&path).and_then(|manifest| manifest.tool) {
+                if self.tools.remove_tool(&name) {
+                    info!("Removed tool '{}' ({} deleted)", name, path.display());
+                    changed = true;
+                }
+            }
+        }
+
+        for (path, stamp) in current {
+            if self.loaded.get(&path).is_some_and(|manifest| manifest.stamp == stamp) {
+                continue;
+            }
+            let previous = self.loaded.get(&path).and_then(|manifest| manifest.tool.clone());
+            let tool = match load_manifest(&path) {
+                Ok(tool) => tool,
+                Err(message) => {
+                    warn!("Skipping manifest {}: {}", path.display(), message);
+                    self.loaded.insert(path, LoadedManifest { stamp, tool: previous });
+                    continue;
+                }
+            };
+            let name = tool.name.clone();
+            let replaced = match self.tools.insert_tool(tool) {
+                Ok(replaced) => replaced,
+                Err(message) => {
+                    warn!("Skipping manifest {}: {}", path.display(), message);
+                    self.loaded.insert(path, LoadedManifest { stamp, tool: previous });
+                    continue;
+                }
+            };
+            if let Some(previous) = previous.filter(|previous| *previous != name) {
+                self.tools.remove_tool(&previous);
+            }
+            info!("{} tool '{}' from {}", if replaced { "Reloaded" } else { "Loaded" }, name, path.display());
+            self.loaded.insert(path, LoadedManifest { stamp, tool: Some(name) });
+            changed = true;
+        }
+        Ok(changed)
+    }
+}
+
+/// 读取清单并创建工具
+fn load_manifest(path: &Path) -> Result<FunctionTool, String> {
+    let source = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let manifest: ToolManifest = serde_json::from_str(&source).map_err(|e| e.to_string())?;
+    manifest.into_tool()
+}
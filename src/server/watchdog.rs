@@ -0,0 +1,214 @@
+//! 阻塞工具的看门狗（调试用）
+//!
+//! 工具函数是同步的，没有截止时间时直接在异步运行时的工作线程上执行。工具中的`std::thread::sleep`、
+//! 同步网络请求或长时间计算会占住工作线程，表现为服务器"卡死"。
+//! [`Settings::with_blocking_watchdog`](crate::Settings::with_blocking_watchdog)开启看门狗后：
+//! - 每次工具调用登记开始时间，调用返回时注销；
+//! - 后台线程发现调用在工作线程上运行超过阈值仍未返回时，立即记录一条警告，
+//!   指出工具名并建议用`tokio::task::spawn_blocking`执行阻塞操作，或为调用设置执行时限；
+//! - 开启升级时，被警告的工具之后的调用自动放到阻塞线程池执行，不再占住工作线程。
+//!
+//! 已经在阻塞线程池中执行的调用（有截止时间的调用、批量调用）不受监视。
+//! 没有开启时不登记调用，也不启动后台线程。
+//!
+//! ```rust
+//! use rustmcp::{RustMCP, Settings};
+//! use std::time::Duration;
+//!
+//! // 调用在工作线程上超过200毫秒时警告，被警告的工具之后放到阻塞线程池执行
+//! let settings = Settings::new().with_blocking_watchdog(Duration::from_millis(200), true);
+//! let rustmcp = RustMCP::new().with_settings(settings);
+//! ```
+
+use parking_lot::{Condvar, Mutex};
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Once};
+use std::time::{Duration, Instant};
+
+thread_local! {
+    /// 当前线程是否在阻塞线程池中执行调用
+    static OFFLOADED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// 在阻塞线程池中执行`work`，期间的工具调用不受看门狗监视
+pub(crate) fn offloaded<T>(work: impl FnOnce() -> T) -> T {
+    let previous = OFFLOADED.with(|offloaded| offloaded.replace(true));
+    let result = work();
+    OFFLOADED.with(|offloaded| offloaded.set(previous));
+    result
+}
+
+/// 当前线程是否在阻塞线程池中执行调用
+pub(crate) fn is_offloaded() -> bool {
+    OFFLOADED.with(Cell::get)
+}
+
+/// 正在执行的调用
+#[derive(Debug)]
+struct Call {
+    tool: String,
+    started: Instant,
+    warned: bool,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    calls: HashMap<u64, Call>,
+    next_id: u64,
+    /// 之后的调用放到阻塞线程池执行的工具
+    escalated: HashSet<String>,
+    closed: bool,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<State>,
+    wake: Condvar,
+}
+
+/// 监视工具调用的看门狗
+#[derive(Debug)]
+pub(crate) struct Watchdog {
+    threshold: Duration,
+    escalate: bool,
+    shared: Arc<Shared>,
+    started: Once,
+}
+
+impl Watchdog {
+    pub(crate) fn new(threshold: Duration, escalate: bool) -> Self {
+        Self {
+            threshold,
+            escalate,
+            shared: Arc::default(),
+            started: Once::new(),
+        }
+    }
+
+    /// 工具的调用是否已经升级到阻塞线程池
+    pub(crate) fn is_escalated(&self, tool: &str) -> bool {
+        self.shared.state.lock().escalated.contains(tool)
+    }
+
+    /// 登记一次调用，返回的守卫在调用返回时注销；在阻塞线程池中执行时不登记
+    pub(crate) fn watch(&self, tool: &str) -> Option<Watch> {
+        if is_offloaded() {
+            return None;
+        }
+        self.start();
+        let id = {
+            let mut state = self.shared.state.lock();
+            let id = state.next_id;
+            state.next_id += 1;
+            state.calls.insert(id, Call { tool: tool.to_string(), started: Instant::now(), warned: false });
+            id
+        };
+        self.shared.wake.notify_one();
+        Some(Watch { shared: self.shared.clone(), id })
+    }
+
+    /// 第一次登记调用时启动后台线程
+    fn start(&self) {
+        self.started.call_once(|| {
+            let (shared, threshold, escalate) = (self.shared.clone(), self.threshold, self.escalate);
+            let spawned = std::thread::Builder::new()
+                .name("rustmcp-watchdog".to_string())
+                .spawn(move || run(&shared, threshold, escalate));
+            if let Err(e) = spawned {
+                log::warn!("Failed to start the blocking tool watchdog: {}", e);
+            }
+        });
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.shared.state.lock().closed = true;
+        self.shared.wake.notify_one();
+    }
+}
+
+/// 登记中的调用，drop时注销
+#[derive(Debug)]
+pub(crate) struct Watch {
+    shared: Arc<Shared>,
+    id: u64,
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.shared.state.lock().calls.remove(&self.id);
+    }
+}
+
+/// 后台线程：等到最早的调用超过阈值，警告仍未返回的调用
+fn run(shared: &Shared, threshold: Duration, escalate: bool) {
+    let mut state = shared.state.lock();
+    while !state.closed {
+        let now = Instant::now();
+        let State { calls, escalated, .. } = &mut *state;
+        for call in calls.values_mut().filter(|call| !call.warned && now >= call.started + threshold) {
+            call.warned = true;
+            log::warn!(
+                "Tool '{}' has blocked an async runtime worker for over {}ms without returning; \
+                 move blocking I/O or long computation into tokio::task::spawn_blocking, \
+                 or give the call a deadline so it runs on the blocking pool{}",
+                call.tool,
+                threshold.as_millis(),
+                if escalate { "; later calls of this tool will run on the blocking pool" } else { "" },
+            );
+            if escalate {
+                escalated.insert(call.tool.clone());
+            }
+        }
+        match calls.values().filter(|call| !call.warned).map(|call| call.started + threshold).min() {
+            Some(next) => {
+                shared.wake.wait_until(&mut state, next);
+            }
+            None => shared.wake.wait(&mut state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use std::sync::Mutex;
+    use std::thread::{self, ThreadId};
+    use std::time::Duration;
+
+    use crate::server::test_support::{capture_warnings, warnings};
+    use crate::{FunctionTool, RustMCP, Settings};
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn blocking_tools_are_reported_and_offloaded() {
+        static THREADS: Mutex<Vec<ThreadId>> = Mutex::new(Vec::new());
+        capture_warnings();
+        let settings = Settings::new().with_blocking_watchdog(Duration::from_millis(50), true);
+        let rustmcp = RustMCP::new().with_settings(settings);
+        rustmcp.add_tool(FunctionTool::simple("watchdog_report", "Fetches a report", |_| {
+            THREADS.lock().unwrap().push(thread::current().id());
+            // 在异步运行时上同步等待
+            thread::sleep(Duration::from_millis(300));
+            Ok(json!("report"))
+        }));
+        rustmcp.add_tool(FunctionTool::simple("watchdog_ping", "Replies at once", |_| Ok(json!("pong"))));
+
+        assert!(rustmcp.mcp_call_tool("watchdog_ping", None).await.is_ok());
+        assert!(warnings("'watchdog_").is_empty());
+
+        assert!(rustmcp.mcp_call_tool("watchdog_report", None).await.is_ok());
+        let reported = warnings("'watchdog_");
+        assert_eq!(reported.len(), 1);
+        assert!(reported[0].contains("'watchdog_report'"));
+        assert!(reported[0].contains("spawn_blocking"));
+
+        // 升级后之后的调用在阻塞线程池中执行，不再警告
+        assert!(rustmcp.mcp_call_tool("watchdog_report", None).await.is_ok());
+        assert_eq!(warnings("'watchdog_").len(), 1);
+        let threads = THREADS.lock().unwrap().clone();
+        assert_eq!(threads[0], thread::current().id());
+        assert_ne!(threads[1], thread::current().id());
+    }
+}
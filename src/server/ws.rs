@@ -1,65 +1,182 @@
-//! WebSocket和JSON-RPC支持模块
+//! WebSocket支持模块
 //! 实现MCP协议的WebSocket传输层
+//!
+//! 客户端发来的消息不能超过[`Settings::ws_max_message_bytes`](crate::Settings::ws_max_message_bytes)
+//! （默认64 MiB，按重组后的整条消息计算），超过时服务器发送关闭帧`1009`（Message Too Big）并断开连接，
+//! 不会先把整条消息读进内存。
+//!
+//! 客户端提出`permessage-deflate`时服务器默认接受，之后发出的消息都压缩，客户端发来的压缩消息按解压后的大小
+//! 计入上限。[`Settings::ws`](crate::Settings::ws)（[`WsConfig`]）可以关闭压缩，或者要求每条消息单独压缩。
+//!
+//! 服务器在处理请求期间继续读取连接，以便及时发现客户端断开；断开时还没有开始处理的请求被丢弃。
+//! 升级响应的`Mcp-Session-Id`为连接的会话ID。开启了[未送达响应的保留](crate::server::pending)时，
+//! 客户端可以在升级请求中带上之前的会话ID（`Mcp-Session-Id`请求头或`sessionId`查询参数）以同一会话重新连接。
+//!
+//! ```rust
+//! use rustmcp::{create_app, RustMCP, Settings, WsConfig};
+//!
+//! // 客户端的消息解压后最多1 MiB，每条消息单独压缩
+//! let settings = Settings::new()
+//!     .with_ws_max_message_bytes(1024 * 1024)
+//!     .with_ws(WsConfig::new().with_no_context_takeover(true));
+//! let rustmcp = RustMCP::new().with_settings(settings);
+//! let app = create_app(rustmcp);
+//! ```
 
 use axum::{
-    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, State},
-    response::Response,
+    extract::{ws::{close_code, CloseFrame, Message, WebSocketUpgrade}, ConnectInfo, Extension, FromRequestParts, Request, State},
+    http::{HeaderMap, HeaderValue, Uri},
+    response::{IntoResponse, Response},
 };
-use futures::{SinkExt, StreamExt};
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use parking_lot::Mutex as SyncMutex;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
-use crate::server::RustMCP;
-
-/// JSON-RPC请求结构
-#[derive(Serialize, Deserialize, Debug)]
-pub struct JsonRpcRequest {
-    pub jsonrpc: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub id: Option<Value>,
-    pub method: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub params: Option<Value>,
+use crate::server::notifications::{self, Outbox, Push};
+use crate::server::trace::Direction;
+use crate::server::ws_deflate::{self, DeflateUpgrade};
+use crate::server::{dispatch, session_id_header, Identity, Lifecycle, RequestContext, RustMCP, SessionChannel, SessionStore, TransportKind};
+
+use crate::protocol;
+
+// 协议类型已移到`crate::protocol`，这里保留原来的导出路径，使用时产生弃用警告
+
+/// 已移到[`protocol::JsonRpcError`]
+#[deprecated(since = "0.1.0", note = "use `rustmcp::protocol::JsonRpcError` or `rustmcp::prelude`")]
+pub type JsonRpcError = protocol::JsonRpcError;
+/// 已移到[`protocol::JsonRpcNotification`]
+#[deprecated(since = "0.1.0", note = "use `rustmcp::protocol::JsonRpcNotification` or `rustmcp::prelude`")]
+pub type JsonRpcNotification = protocol::JsonRpcNotification;
+/// 已移到[`protocol::JsonRpcRequest`]
+#[deprecated(since = "0.1.0", note = "use `rustmcp::protocol::JsonRpcRequest` or `rustmcp::prelude`")]
+pub type JsonRpcRequest = protocol::JsonRpcRequest;
+/// 已移到[`protocol::JsonRpcResponse`]
+#[deprecated(since = "0.1.0", note = "use `rustmcp::protocol::JsonRpcResponse` or `rustmcp::prelude`")]
+pub type JsonRpcResponse = protocol::JsonRpcResponse;
+
+/// 服务器强制断开时等待关闭帧写出的时间
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 默认的最大消息字节数，与tungstenite的默认值相同
+pub(crate) const DEFAULT_MAX_MESSAGE_BYTES: usize = 64 << 20;
+
+/// 处理一个请求期间最多预先读入的消息数，超过时暂停读取
+const MAX_READ_AHEAD: usize = 64;
+
+/// 连接的写出端，压缩和不压缩的连接共用
+pub(crate) type WsSender = Pin<Box<dyn Sink<Message, Error = axum::Error> + Send>>;
+
+/// 连接的读取端
+pub(crate) type WsReceiver = Pin<Box<dyn Stream<Item = Result<Message, axum::Error>> + Send>>;
+
+/// WebSocket传输的配置
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WsConfig {
+    /// 客户端提出时协商`permessage-deflate`，默认开启
+    #[serde(default = "default_deflate")]
+    pub deflate: bool,
+    /// 双方每条消息单独压缩，不沿用之前消息的压缩上下文；压缩率变低，但客户端不需要在消息之间保留压缩窗口
+    #[serde(default)]
+    pub no_context_takeover: bool,
 }
 
-/// JSON-RPC响应结构
-#[derive(Serialize, Deserialize, Debug)]
-pub struct JsonRpcResponse {
-    pub jsonrpc: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub id: Option<Value>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub result: Option<Value>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub error: Option<JsonRpcError>,
+impl WsConfig {
+    /// 创建默认配置：接受`permessage-deflate`，沿用压缩上下文
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置是否协商`permessage-deflate`
+    pub fn with_deflate(mut self, enabled: bool) -> Self {
+        self.deflate = enabled;
+        self
+    }
+
+    /// 设置是否每条消息单独压缩
+    pub fn with_no_context_takeover(mut self, enabled: bool) -> Self {
+        self.no_context_takeover = enabled;
+        self
+    }
 }
 
-/// JSON-RPC通知结构
-#[derive(Serialize, Deserialize, Debug)]
-pub struct JsonRpcNotification {
-    pub jsonrpc: String,
-    pub method: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub params: Option<Value>,
+impl Default for WsConfig {
+    fn default() -> Self {
+        Self {
+            deflate: default_deflate(),
+            no_context_takeover: false,
+        }
+    }
 }
 
-/// JSON-RPC错误结构
-#[derive(Serialize, Deserialize, Debug)]
-pub struct JsonRpcError {
-    pub code: i32,
-    pub message: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub data: Option<Value>,
+fn default_deflate() -> bool {
+    true
 }
 
 /// WebSocket连接处理函数
 pub async fn ws_handler(
-    ws: WebSocketUpgrade,
     State(state): State<Arc<RustMCP>>,
+    connect_info: Option<ConnectInfo<std::net::SocketAddr>>,
+    identity: Option<Extension<Identity>>,
+    uri: Uri,
+    headers: HeaderMap,
+    request: Request,
 ) -> Response {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+    // 只有开启了未送达响应的保留时才沿用客户端给出的会话ID
+    let session_id = state
+        .parks_responses()
+        .then(|| session_id_header(&headers).or_else(|| session_id_param(&uri)))
+        .flatten()
+        .filter(|id| !id.is_empty() && HeaderValue::from_str(id).is_ok())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let session_header = HeaderValue::from_str(&session_id).ok();
+    // 在升级时记录请求信息，连接上的所有请求共享同一个上下文和会话存储，
+    // 连接关闭时一起丢弃；回传通道和连接信息在连接建立后由handle_socket设置
+    let request_context = RequestContext {
+        transport: TransportKind::Ws,
+        remote_addr: connect_info.map(|ConnectInfo(addr)| addr),
+        headers: RequestContext::collect_headers(&headers),
+        session_id: Some(session_id),
+        identity: identity.map(|Extension(identity)| identity),
+        back_channel: None,
+        session: Some(SessionStore::new(state.settings().session_limits())),
+        lifecycle: Lifecycle::new(),
+        lifecycle_policy: state.settings().lifecycle_policy,
+        connection: None,
+    };
+    // 帧的上限与消息相同，超长的帧在读入负载之前就被拒绝
+    let max_bytes = state.settings().ws_max_message_bytes;
+    let (mut parts, _) = request.into_parts();
+    // 协商了压缩时自己完成握手和分帧，否则交给axum
+    let deflate = ws_deflate::negotiate(&headers, &state.settings().ws).and_then(|params| DeflateUpgrade::from_parts(&mut parts, params));
+    let mut response = match deflate {
+        Some(upgrade) => upgrade.on_upgrade(max_bytes, |sender, receiver| handle_socket(sender, receiver, state, request_context)),
+        None => match WebSocketUpgrade::from_request_parts(&mut parts, &state).await {
+            Ok(ws) => ws.max_message_size(max_bytes).max_frame_size(max_bytes).on_upgrade(|socket| {
+                let (sender, receiver) = socket.split();
+                handle_socket(Box::pin(sender), Box::pin(receiver), state, request_context)
+            }),
+            Err(rejection) => return rejection.into_response(),
+        },
+    };
+    if let Some(value) = session_header {
+        response.headers_mut().insert("mcp-session-id", value);
+    }
+    response
+}
+
+/// 读取升级请求的`sessionId`查询参数，不做URL解码
+fn session_id_param(uri: &Uri) -> Option<String> {
+    uri.query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("sessionId="))
+        .map(str::to_string)
 }
 
 /// 客户端状态
@@ -82,267 +199,242 @@ impl Default for ClientState {
 }
 
 /// 处理WebSocket连接
-async fn handle_socket(socket: WebSocket, state: Arc<RustMCP>) {
+async fn handle_socket(mut sender: WsSender, mut receiver: WsReceiver, state: Arc<RustMCP>, mut request_context: RequestContext) {
     println!("WebSocket connection established");
     
     // 创建客户端状态
     let client_state = Arc::new(Mutex::new(ClientState::new()));
     
-    // 分离读写
-    let (mut sender, mut receiver) = socket.split();
+    // 响应和通知都通过同一个有界队列写出
+    let session_id = request_context.session_id.clone().unwrap_or_default();
+    let outbox = state.outbox(TransportKind::Ws, &session_id);
+    let registration = state.register_connection(&session_id, TransportKind::Ws, outbox.clone());
+    let connection = registration.connection();
+    // 读取端要求关闭连接时设置的关闭帧，由写出端在队列清空后发送
+    let closing: Arc<SyncMutex<Option<CloseFrame<'static>>>> = Arc::default();
+    let writer_outbox = outbox.clone();
+    let writer_connection = connection.clone();
+    let writer_closing = closing.clone();
+    let mut writer_handle = tokio::spawn(async move {
+        while let Some(text) = writer_outbox.next().await {
+            if sender.send(Message::Text(text)).await.is_err() {
+                return;
+            }
+        }
+        let frame = writer_closing.lock().clone().or_else(|| {
+            if writer_connection.is_going_away() {
+                Some(CloseFrame {
+                    code: close_code::AWAY,
+                    reason: "server shutting down".into(),
+                })
+            } else {
+                writer_connection.is_disconnected().then(|| CloseFrame {
+                    code: close_code::NORMAL,
+                    reason: "session closed by server".into(),
+                })
+            }
+        });
+        if let Some(frame) = frame {
+            let _ = sender.send(Message::Close(Some(frame))).await;
+        }
+    });
+    
+    // 重新连接时先发出旧连接未送达的响应
+    if state.settings().pending_redelivery {
+        for (_, text) in state.take_pending(&session_id) {
+            outbox.push(text, false);
+        }
+    }
+    
+    // 处理函数发出的会话通知和响应走同一个队列，保证顺序
+    request_context.back_channel = Some(SessionChannel::outbox(outbox.clone()));
+    request_context.connection = Some(connection.clone());
+    let request_context = Arc::new(request_context);
+    
+    // 转发服务器通知的任务
+    let notification_handle = tokio::spawn(notifications::forward(state.notifier().subscribe(), outbox.clone()));
     
     // 处理接收消息的任务
     let state_clone = state.clone();
     let client_state_clone = client_state.clone();
-    let receiver_handle = tokio::spawn(async move {
-        while let Some(Ok(msg)) = receiver.next().await {
-            if let Message::Text(text) = msg {
-                println!("Received message: {}", text);
-                if let Err(e) = handle_message(text, &state_clone, &mut sender, &client_state_clone).await {
-                    eprintln!("Error handling message: {}", e);
-                    break;
+    let receiver_outbox = outbox.clone();
+    let receiver_closing = closing.clone();
+    let mut receiver_handle = tokio::spawn(async move {
+        // 请求在单独的任务中处理，期间继续读取；客户端断开时设置，请求的响应交给重新连接的客户端或丢弃
+        let lost = Arc::new(AtomicBool::new(false));
+        let mut read_ahead = VecDeque::new();
+        loop {
+            let text = match read_ahead.pop_front() {
+                Some(text) => text,
+                None => match receiver.next().await {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => {
+                        if let Some(frame) = close_frame_for(e) {
+                            *receiver_closing.lock() = Some(frame);
+                        }
+                        break;
+                    }
+                    None => break,
+                },
+            };
+            let mut handling = tokio::spawn({
+                let (state, outbox, client_state) = (state_clone.clone(), receiver_outbox.clone(), client_state_clone.clone());
+                let (request_context, lost) = (request_context.clone(), lost.clone());
+                async move { handle_message(text, &state, &outbox, &client_state, &request_context, &lost).await }
+            });
+            let result = loop {
+                tokio::select! {
+                    joined = &mut handling => break joined.unwrap_or_else(|e| Err(e.into())),
+                    msg = receiver.next(), if !lost.load(Ordering::Acquire) && read_ahead.len() < MAX_READ_AHEAD => match msg {
+                        Some(Ok(Message::Text(text))) => read_ahead.push_back(text),
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => {
+                            if let Some(frame) = close_frame_for(e) {
+                                *receiver_closing.lock() = Some(frame);
+                            }
+                            lost.store(true, Ordering::Release);
+                        }
+                        None => lost.store(true, Ordering::Release),
+                    },
                 }
+            };
+            if let Err(e) = result {
+                eprintln!("Error handling message: {}", e);
+                break;
+            }
+            if lost.load(Ordering::Acquire) {
+                break;
             }
         }
     });
     
-    // 等待任务完成
-    let _ = receiver_handle.await;
+    // 等待客户端断开，或者发送队列溢出、服务器强制断开时放弃连接；
+    // 服务器关闭时写出任务发完消息和关闭帧后结束
+    let written = tokio::select! {
+        _ = &mut receiver_handle => false,
+        _ = outbox.closed() => {
+            receiver_handle.abort();
+            false
+        }
+        _ = &mut writer_handle => {
+            receiver_handle.abort();
+            true
+        }
+    };
+    outbox.close();
+    notification_handle.abort();
+    if !written && (connection.is_disconnected() || closing.lock().is_some()) {
+        let _ = tokio::time::timeout(CLOSE_TIMEOUT, &mut writer_handle).await;
+    }
+    writer_handle.abort();
+    drop(registration);
     println!("WebSocket connection closed");
 }
 
+/// 读取错误对应的关闭帧：消息或帧超过上限时为`1009`，其他错误直接断开
+fn close_frame_for(error: axum::Error) -> Option<CloseFrame<'static>> {
+    use tokio_tungstenite::tungstenite::Error;
+    match error.into_inner().downcast_ref::<Error>() {
+        Some(Error::Capacity(e)) => Some(CloseFrame {
+            code: close_code::SIZE,
+            reason: e.to_string().into(),
+        }),
+        _ => None,
+    }
+}
+
 /// 处理接收到的消息
 async fn handle_message(
     text: String,
     state: &Arc<RustMCP>,
-    sender: &mut futures::stream::SplitSink<WebSocket, Message>,
+    outbox: &Outbox,
     _client_state: &Arc<Mutex<ClientState>>,
+    request_context: &Arc<RequestContext>,
+    lost: &AtomicBool,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    // 解析JSON-RPC请求
-    if let Ok(request) = serde_json::from_str::<JsonRpcRequest>(&text) {
-        let response = match request.method.as_str() {
-            "initialize" => {
-                let result = serde_json::json!({
-                    "protocolVersion": "2024-11-05",
-                    "capabilities": {
-                        "tools": {
-                            "listChanged": true
-                        },
-                        "resources": {
-                            "subscribe": true,
-                            "listChanged": true
-                        },
-                        "prompts": {
-                            "listChanged": true
-                        }
-                    },
-                    "serverInfo": {
-                        "name": "RustMCP-rs",
-                        "version": "0.1.0"
-                    }
-                });
-
-                JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: request.id, // 保持原始ID
-                    result: Some(result),
-                    error: None,
-                }
-            },
-            "notifications/initialized" => {
-                // initialized通知不需要响应
-                println!("Received initialized notification, sending success response");
-                // 对于通知消息，发送一个特殊的成功响应
-                let response = JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: Some(serde_json::Value::Number(serde_json::Number::from(0))),
-                    result: Some(serde_json::json!({})),
-                    error: None,
-                };
-                if let Ok(response_text) = serde_json::to_string(&response) {
-                    let _ = sender.send(Message::Text(response_text)).await;
-                }
-                return Ok(());
-            },
-            _ => {
-                // 转发到HTTP处理器处理其他方法
-                handle_jsonrpc_method(request, state).await
+    state.tracer.record(Direction::Inbound, TransportKind::Ws, request_context.session_id.as_deref(), &text);
+    // 解析JSON-RPC请求，无法解析时返回错误响应
+    let request = match protocol::JsonRpcRequest::parse(&text) {
+        Ok(request) => request,
+        Err(response) => {
+            if let Ok(response_text) = serde_json::to_string(&response) {
+                send_response(outbox, response_text)?;
             }
-        };
+            return Ok(());
+        }
+    };
 
-        // 发送响应
-        if let Ok(response_text) = serde_json::to_string(&response) {
-            sender.send(Message::Text(response_text)).await?;
+    // 没有id的消息是通知，不需要响应
+    if request.is_notification() {
+        return Ok(());
+    }
+    // 转发到共享分发器处理
+    let response = dispatch::dispatch_request(state, request, request_context.clone()).await;
+
+    // 发送响应，连接已经断开时交给重新连接的客户端或保留
+    if let Ok(response_text) = serde_json::to_string(&response) {
+        if lost.load(Ordering::Acquire) {
+            state.park_response(request_context, response.id, response_text);
+        } else if state.parks_responses() {
+            if let Push::Closed = outbox.push(response_text.clone(), false) {
+                state.park_response(request_context, response.id, response_text);
+                return Err("connection closed".into());
+            }
+        } else {
+            send_response(outbox, response_text)?;
         }
     }
-    
+
     Ok(())
 }
 
-/// 处理JSON-RPC方法调用
-async fn handle_jsonrpc_method(request: JsonRpcRequest, state: &Arc<RustMCP>) -> JsonRpcResponse {
-    match request.method.as_str() {
-        "tools/list" => {
-            let tools = state.mcp_list_tools();
-            JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id: request.id.clone(),
-                result: Some(serde_json::json!({
-                    "tools": tools
-                })),
-                error: None,
-            }
-        },
-        "resources/list" => {
-            let resources = state.mcp_list_resources();
-            JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id: request.id.clone(),
-                result: Some(serde_json::json!({
-                    "resources": resources
-                })),
-                error: None,
-            }
-        },
-        "prompts/list" => {
-            let prompts = state.mcp_list_prompts();
-            JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id: request.id.clone(),
-                result: Some(serde_json::json!({
-                    "prompts": prompts
-                })),
-                error: None,
-            }
-        },
-        "tools/call" => {
-            if let Some(params) = request.params {
-                if let Ok(call_params) = serde_json::from_value::<serde_json::Map<String, Value>>(params) {
-                    let name = call_params.get("name").and_then(|v| v.as_str()).unwrap_or("");
-                    let arguments = call_params.get("arguments").cloned();
-                    
-                    // 转换arguments为HashMap
-                    let arguments_map = if let Some(args) = arguments {
-                        serde_json::from_value::<std::collections::HashMap<String, Value>>(args).ok()
-                    } else {
-                        None
-                    };
-                    
-                    match state.mcp_call_tool(name, arguments_map).await {
-                        Ok(result) => JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            id: request.id.clone(),
-                            result: Some(serde_json::json!({
-                                "content": [{
-                                    "type": "text",
-                                    "text": format!("{}", result)
-                                }],
-                                "isError": false
-                            })),
-                            error: None,
-                        },
-                        Err(e) => JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            id: request.id.clone(),
-                            result: Some(serde_json::json!({
-                                "content": [{
-                                    "type": "text",
-                                    "text": e
-                                }],
-                                "isError": true
-                            })),
-                            error: None,
-                        },
-                    }
-                } else {
-                    JsonRpcResponse {
-                        jsonrpc: "2.0".to_string(),
-                        id: request.id,
-                        result: None,
-                        error: Some(JsonRpcError {
-                            code: -32602,
-                            message: "Invalid params".to_string(),
-                            data: None,
-                        }),
-                    }
-                }
-            } else {
-                JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: request.id,
-                    result: None,
-                    error: Some(JsonRpcError {
-                        code: -32602,
-                        message: "Missing params".to_string(),
-                        data: None,
-                    }),
-                }
-            }
-        },
-        "resources/read" => {
-            if let Some(params) = request.params {
-                if let Ok(read_params) = serde_json::from_value::<serde_json::Map<String, Value>>(params) {
-                    let uri = read_params.get("uri").and_then(|v| v.as_str()).unwrap_or("");
-                    
-                    match state.mcp_read_resource(uri) {
-                        Ok(result) => JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            id: request.id,
-                            result: Some(serde_json::json!({
-                                "contents": [{
-                                    "uri": uri,
-                                    "text": result
-                                }]
-                            })),
-                            error: None,
-                        },
-                        Err(e) => JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            id: request.id,
-                            result: None,
-                            error: Some(JsonRpcError {
-                                code: -32000,
-                                message: e,
-                                data: None,
-                            }),
-                        },
-                    }
-                } else {
-                    JsonRpcResponse {
-                        jsonrpc: "2.0".to_string(),
-                        id: request.id,
-                        result: None,
-                        error: Some(JsonRpcError {
-                            code: -32602,
-                            message: "Invalid params".to_string(),
-                            data: None,
-                        }),
-                    }
-                }
-            } else {
-                JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: request.id,
-                    result: None,
-                    error: Some(JsonRpcError {
-                        code: -32602,
-                        message: "Missing params".to_string(),
-                        data: None,
-                    }),
-                }
-            }
-        },
-        _ => {
-            JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id: request.id.clone(),
-                result: None,
-                error: Some(JsonRpcError {
-                    code: -32601,
-                    message: "Method not found".to_string(),
-                    data: None,
-                }),
-            }
+/// 写入响应，连接已断开时返回错误
+fn send_response(outbox: &Outbox, text: String) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    match outbox.push(text, false) {
+        Push::Closed => Err("connection closed".into()),
+        Push::Queued | Push::Dropped => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{SinkExt, StreamExt};
+    use serde_json::{json, Value};
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::tungstenite::Message;
+
+    use crate::server::test_support::{request, spawn_server};
+    use crate::{FunctionTool, RustMCP, Settings, WsConfig};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn disabled_deflate_is_declined_and_oversized_messages_close_the_connection() {
+        let settings = Settings::new().with_ws_max_message_bytes(64 * 1024).with_ws(WsConfig::new().with_deflate(false));
+        let rustmcp = RustMCP::new().with_settings(settings);
+        for i in 0..500 {
+            rustmcp.add_tool(FunctionTool::simple(format!("tool_{}", i), "Does one of many things", |_| Ok(json!("ok"))));
+        }
+        let addr = spawn_server(&rustmcp).await;
+
+        // 关闭了压缩时客户端提出permessage-deflate，服务器不接受，连接不压缩
+        let mut upgrade = format!("ws://{}/mcp/ws", addr).into_client_request().unwrap();
+        upgrade.headers_mut().insert("sec-websocket-extensions", "permessage-deflate; client_max_window_bits".parse().unwrap());
+        let (mut client, response) = tokio_tungstenite::connect_async(upgrade).await.unwrap();
+        assert!(response.headers().get("sec-websocket-extensions").is_none());
+        client.send(Message::text(request(1, "initialize", json!({})).to_string())).await.unwrap();
+        client.next().await.unwrap().unwrap();
+
+        // 限制只作用于客户端发来的消息，大的列表照常返回
+        client.send(Message::text(request(2, "tools/list", json!({})).to_string())).await.unwrap();
+        let listing: Value = serde_json::from_str(client.next().await.unwrap().unwrap().to_text().unwrap()).unwrap();
+        assert_eq!(listing["result"]["tools"].as_array().unwrap().len(), 500);
+
+        // 超过限制的消息让服务器以1009关闭连接
+        let oversized = request(3, "ping", json!({ "padding": "x".repeat(128 * 1024) }));
+        client.send(Message::text(oversized.to_string())).await.unwrap();
+        match client.next().await {
+            Some(Ok(Message::Close(Some(frame)))) => assert_eq!(u16::from(frame.code), 1009),
+            other => panic!("expected a close frame, got {:?}", other),
         }
     }
 }
@@ -0,0 +1,653 @@
+//! WebSocket的`permessage-deflate`扩展（RFC 7692）
+//!
+//! tungstenite不支持WebSocket扩展，客户端在升级请求中提出`permessage-deflate`并且
+//! [`WsConfig::deflate`](crate::WsConfig::deflate)开启时，由这里完成握手和分帧：
+//!
+//! - 按顺序取第一个可以接受的提议。压缩端总是使用15位窗口，要求更小`server_max_window_bits`的提议被拒绝；
+//!   解压端按15位窗口处理，能解开任意窗口大小的数据，`client_max_window_bits`不需要回应。
+//!   没有可以接受的提议时交给axum的[`WebSocketUpgrade`](axum::extract::ws::WebSocketUpgrade)，连接不压缩。
+//! - 服务器发出的文本和二进制消息都压缩（RSV1置位），客户端可以逐条选择是否压缩。
+//! - 解压后的消息同样受[`Settings::ws_max_message_bytes`](crate::Settings::ws_max_message_bytes)限制，
+//!   解压过程中一旦超过就以`1009`关闭连接，压缩炸弹不会被完整解开。
+//! - [`WsConfig::no_context_takeover`](crate::WsConfig::no_context_takeover)开启时双方每条消息单独压缩，
+//!   响应中带上`server_no_context_takeover`和`client_no_context_takeover`；客户端单独提出其中一个时也照办。
+
+use axum::{
+    body::Body,
+    extract::ws::{CloseFrame, Message},
+    http::{header, request::Parts, HeaderMap, HeaderValue, Method, StatusCode},
+    response::Response,
+};
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
+use hyper::upgrade::OnUpgrade;
+use hyper_util::rt::TokioIo;
+use std::future::Future;
+use std::io::Cursor;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::Mutex;
+use tokio_tungstenite::tungstenite::error::{CapacityError, Error, ProtocolError};
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::{Control, Data, OpCode};
+use tokio_tungstenite::tungstenite::protocol::frame::{Frame, FrameHeader};
+
+use crate::server::ws::{WsConfig, WsReceiver, WsSender};
+
+/// 扩展名
+const EXTENSION: &str = "permessage-deflate";
+
+/// 同步刷新在压缩数据末尾产生的空块，发送前去掉，解压前补上
+const TAIL: [u8; 4] = [0, 0, 0xff, 0xff];
+
+/// 每次压缩或解压预留的输出空间
+const CHUNK: usize = 16 * 1024;
+
+/// 协商得到的参数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct DeflateParams {
+    /// 服务器每条消息单独压缩
+    pub(crate) server_no_context_takeover: bool,
+    /// 客户端每条消息单独压缩
+    pub(crate) client_no_context_takeover: bool,
+}
+
+impl DeflateParams {
+    /// 握手响应的`Sec-WebSocket-Extensions`
+    fn response_header(&self) -> String {
+        let mut value = EXTENSION.to_string();
+        if self.server_no_context_takeover {
+            value.push_str("; server_no_context_takeover");
+        }
+        if self.client_no_context_takeover {
+            value.push_str("; client_no_context_takeover");
+        }
+        value
+    }
+}
+
+/// 从升级请求的`Sec-WebSocket-Extensions`中选出第一个可以接受的`permessage-deflate`提议
+pub(crate) fn negotiate(headers: &HeaderMap, config: &WsConfig) -> Option<DeflateParams> {
+    if !config.deflate {
+        return None;
+    }
+    headers
+        .get_all(header::SEC_WEBSOCKET_EXTENSIONS)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|offer| accept(offer, config))
+}
+
+/// 检查一个提议，有重复、未知或无法满足的参数时拒绝
+fn accept(offer: &str, config: &WsConfig) -> Option<DeflateParams> {
+    let mut parts = offer.split(';').map(str::trim);
+    if !parts.next()?.eq_ignore_ascii_case(EXTENSION) {
+        return None;
+    }
+    let mut params = DeflateParams {
+        server_no_context_takeover: config.no_context_takeover,
+        client_no_context_takeover: config.no_context_takeover,
+    };
+    let mut seen = Vec::new();
+    for part in parts {
+        let (name, value) = match part.split_once('=') {
+            Some((name, value)) => (name.trim().to_ascii_lowercase(), Some(value.trim().trim_matches('"'))),
+            None => (part.to_ascii_lowercase(), None),
+        };
+        match (name.as_str(), value) {
+            ("server_no_context_takeover", None) => params.server_no_context_takeover = true,
+            ("client_no_context_takeover", None) => params.client_no_context_takeover = true,
+            ("server_max_window_bits", Some(bits)) if window_bits(bits) == Some(15) => {}
+            ("client_max_window_bits", None) => {}
+            ("client_max_window_bits", Some(bits)) if window_bits(bits).is_some() => {}
+            _ => return None,
+        }
+        if seen.contains(&name) {
+            return None;
+        }
+        seen.push(name);
+    }
+    Some(params)
+}
+
+/// 解析窗口位数，合法范围是8到15
+fn window_bits(value: &str) -> Option<u8> {
+    value.parse().ok().filter(|bits| (8..=15).contains(bits))
+}
+
+/// 协商了压缩的升级请求
+pub(crate) struct DeflateUpgrade {
+    key: HeaderValue,
+    on_upgrade: OnUpgrade,
+    params: DeflateParams,
+}
+
+impl DeflateUpgrade {
+    /// 校验升级请求并取出连接升级；不是有效的WebSocket升级时返回`None`，由axum给出拒绝响应
+    pub(crate) fn from_parts(parts: &mut Parts, params: DeflateParams) -> Option<Self> {
+        let header = |name| parts.headers.get(name).and_then(|value: &HeaderValue| value.to_str().ok());
+        let valid = parts.method == Method::GET
+            && header(header::UPGRADE).is_some_and(|value| value.eq_ignore_ascii_case("websocket"))
+            && header(header::CONNECTION).is_some_and(|value| value.split(',').any(|token| token.trim().eq_ignore_ascii_case("upgrade")))
+            && header(header::SEC_WEBSOCKET_VERSION) == Some("13");
+        let key = parts.headers.get(header::SEC_WEBSOCKET_KEY)?.clone();
+        if !valid {
+            return None;
+        }
+        let on_upgrade = parts.extensions.remove::<OnUpgrade>()?;
+        Some(Self { key, on_upgrade, params })
+    }
+
+    /// 返回`101`响应，连接升级后用压缩的读写端调用`callback`
+    pub(crate) fn on_upgrade<F, Fut>(self, max_bytes: usize, callback: F) -> Response
+    where
+        F: FnOnce(WsSender, WsReceiver) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let Self { key, on_upgrade, params } = self;
+        tokio::spawn(async move {
+            let Ok(upgraded) = on_upgrade.await else {
+                return;
+            };
+            let (sender, receiver) = split(TokioIo::new(upgraded), params, max_bytes);
+            callback(sender, receiver).await;
+        });
+        Response::builder()
+            .status(StatusCode::SWITCHING_PROTOCOLS)
+            .header(header::CONNECTION, "upgrade")
+            .header(header::UPGRADE, "websocket")
+            .header(header::SEC_WEBSOCKET_ACCEPT, derive_accept_key(key.as_bytes()))
+            .header(header::SEC_WEBSOCKET_EXTENSIONS, params.response_header())
+            .body(Body::empty())
+            .unwrap()
+    }
+}
+
+/// 把升级后的连接分成压缩的写出端和读取端
+fn split<S>(io: S, params: DeflateParams, max_bytes: usize) -> (WsSender, WsReceiver)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (read, write) = tokio::io::split(io);
+    let writer = Arc::new(Mutex::new(Writer {
+        io: write,
+        deflater: Deflater::new(params.server_no_context_takeover),
+        closed: false,
+    }));
+    let reader = Reader {
+        io: read,
+        buffer: Vec::new(),
+        inflater: Inflater::new(params.client_no_context_takeover),
+        writer: writer.clone(),
+        max_bytes,
+        partial: None,
+        done: false,
+    };
+    let sender = futures::sink::unfold(writer, |writer, message: Message| async move {
+        writer.lock().await.send(message).await?;
+        Ok::<_, axum::Error>(writer)
+    });
+    let receiver = futures::stream::unfold(reader, |mut reader| async move {
+        let message = reader.next().await?;
+        Some((message, reader))
+    });
+    (Box::pin(sender), Box::pin(receiver))
+}
+
+/// 压缩端，每条消息以同步刷新结束
+struct Deflater {
+    inner: Compress,
+    no_context_takeover: bool,
+}
+
+impl Deflater {
+    fn new(no_context_takeover: bool) -> Self {
+        Self {
+            inner: Compress::new(Compression::default(), false),
+            no_context_takeover,
+        }
+    }
+
+    /// 压缩一条消息，去掉末尾的空块
+    fn deflate(&mut self, mut input: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(input.len() / 2 + TAIL.len());
+        loop {
+            output.reserve(CHUNK);
+            let before = self.inner.total_in();
+            // 输出缓冲区足够，同步刷新不会失败
+            self.inner.compress_vec(input, &mut output, FlushCompress::Sync).expect("deflate");
+            input = &input[(self.inner.total_in() - before) as usize..];
+            if input.is_empty() && output.len() < output.capacity() {
+                break;
+            }
+        }
+        if output.ends_with(&TAIL) {
+            output.truncate(output.len() - TAIL.len());
+        }
+        if self.no_context_takeover {
+            self.inner.reset();
+        }
+        output
+    }
+}
+
+/// 解压端，边解压边检查消息大小
+struct Inflater {
+    inner: Decompress,
+    no_context_takeover: bool,
+}
+
+impl Inflater {
+    fn new(no_context_takeover: bool) -> Self {
+        Self {
+            inner: Decompress::new(false),
+            no_context_takeover,
+        }
+    }
+
+    /// 解压一帧的负载追加到`output`，解压后超过`max_bytes`时返回错误
+    fn inflate(&mut self, mut input: &[u8], output: &mut Vec<u8>, max_bytes: usize) -> Result<(), axum::Error> {
+        loop {
+            output.reserve(CHUNK);
+            let (before_in, before_out) = (self.inner.total_in(), self.inner.total_out());
+            let status = self
+                .inner
+                .decompress_vec(input, output, FlushDecompress::Sync)
+                .map_err(axum::Error::new)?;
+            input = &input[(self.inner.total_in() - before_in) as usize..];
+            if output.len() > max_bytes {
+                return Err(too_long(output.len(), max_bytes));
+            }
+            // 对方结束了压缩流，下一条消息从新的流开始
+            if status == Status::StreamEnd {
+                self.inner.reset(false);
+                return Ok(());
+            }
+            let progressed = self.inner.total_in() != before_in || self.inner.total_out() != before_out;
+            if !progressed || (input.is_empty() && output.len() < output.capacity()) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// 补上末尾的空块，结束一条消息
+    fn finish(&mut self, output: &mut Vec<u8>, max_bytes: usize) -> Result<(), axum::Error> {
+        self.inflate(&TAIL, output, max_bytes)?;
+        if self.no_context_takeover {
+            self.inner.reset(false);
+        }
+        Ok(())
+    }
+}
+
+/// 写出端，读取端回应Ping和关闭帧时也通过它写出
+struct Writer<W> {
+    io: W,
+    deflater: Deflater,
+    closed: bool,
+}
+
+impl<W: AsyncWrite + Unpin> Writer<W> {
+    async fn send(&mut self, message: Message) -> Result<(), axum::Error> {
+        if self.closed {
+            return Err(axum::Error::new(Error::AlreadyClosed));
+        }
+        let (opcode, payload, compressed) = match message {
+            Message::Text(text) => (OpCode::Data(Data::Text), self.deflater.deflate(text.as_bytes()), true),
+            Message::Binary(bytes) => (OpCode::Data(Data::Binary), self.deflater.deflate(&bytes), true),
+            Message::Ping(payload) => (OpCode::Control(Control::Ping), payload, false),
+            Message::Pong(payload) => (OpCode::Control(Control::Pong), payload, false),
+            Message::Close(frame) => {
+                self.closed = true;
+                (OpCode::Control(Control::Close), close_payload(frame), false)
+            }
+        };
+        let header = FrameHeader { is_final: true, rsv1: compressed, opcode, ..FrameHeader::default() };
+        let mut bytes = Vec::with_capacity(payload.len() + 14);
+        Frame::from_payload(header, payload).format(&mut bytes).map_err(axum::Error::new)?;
+        self.io.write_all(&bytes).await.map_err(axum::Error::new)?;
+        self.io.flush().await.map_err(axum::Error::new)?;
+        Ok(())
+    }
+}
+
+/// 关闭帧的负载：大端的关闭码和原因
+fn close_payload(frame: Option<CloseFrame<'static>>) -> Vec<u8> {
+    match frame {
+        Some(frame) => {
+            let mut payload = frame.code.to_be_bytes().to_vec();
+            payload.extend_from_slice(frame.reason.as_bytes());
+            payload
+        }
+        None => Vec::new(),
+    }
+}
+
+/// 协议错误，连接直接断开
+fn protocol(error: ProtocolError) -> axum::Error {
+    axum::Error::new(Error::Protocol(error))
+}
+
+/// 消息或帧超过上限，连接以`1009`关闭
+fn too_long(size: usize, max_size: usize) -> axum::Error {
+    axum::Error::new(Error::Capacity(CapacityError::MessageTooLong { size, max_size }))
+}
+
+/// 正在接收的分片消息
+struct Partial {
+    data: Data,
+    compressed: bool,
+    payload: Vec<u8>,
+}
+
+/// 读取端
+struct Reader<R, W> {
+    io: R,
+    buffer: Vec<u8>,
+    inflater: Inflater,
+    writer: Arc<Mutex<Writer<W>>>,
+    max_bytes: usize,
+    partial: Option<Partial>,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin, W: AsyncWrite + Unpin> Reader<R, W> {
+    /// 下一条消息，连接结束后返回`None`，出错后不再读取
+    async fn next(&mut self) -> Option<Result<Message, axum::Error>> {
+        if self.done {
+            return None;
+        }
+        let message = self.read_message().await;
+        if !matches!(message, Some(Ok(Message::Text(_) | Message::Binary(_) | Message::Ping(_) | Message::Pong(_)))) {
+            self.done = true;
+        }
+        message
+    }
+
+    async fn read_message(&mut self) -> Option<Result<Message, axum::Error>> {
+        loop {
+            let (header, mut payload) = match self.read_frame().await {
+                Ok(Some(frame)) => frame,
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            };
+            let Some(mask) = header.mask else {
+                return Some(Err(protocol(ProtocolError::UnmaskedFrameFromClient)));
+            };
+            for (i, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[i % 4];
+            }
+            if header.rsv2 || header.rsv3 {
+                return Some(Err(protocol(ProtocolError::NonZeroReservedBits)));
+            }
+            match header.opcode {
+                OpCode::Control(control) => {
+                    if !header.is_final {
+                        return Some(Err(protocol(ProtocolError::FragmentedControlFrame)));
+                    }
+                    if payload.len() > 125 {
+                        return Some(Err(protocol(ProtocolError::ControlFrameTooBig)));
+                    }
+                    if header.rsv1 {
+                        return Some(Err(protocol(ProtocolError::NonZeroReservedBits)));
+                    }
+                    match control {
+                        Control::Ping => {
+                            let pong = self.writer.lock().await.send(Message::Pong(payload.clone())).await;
+                            return Some(pong.map(|_| Message::Ping(payload)));
+                        }
+                        Control::Pong => return Some(Ok(Message::Pong(payload))),
+                        Control::Close => {
+                            let frame = match payload.len() {
+                                0 => None,
+                                1 => return Some(Err(protocol(ProtocolError::InvalidCloseSequence))),
+                                _ => match String::from_utf8(payload[2..].to_vec()) {
+                                    Ok(reason) => Some(CloseFrame { code: u16::from_be_bytes([payload[0], payload[1]]), reason: reason.into() }),
+                                    Err(_) => return Some(Err(axum::Error::new(Error::Utf8))),
+                                },
+                            };
+                            // 回应同样的关闭码，服务器已经先发出关闭帧时忽略
+                            let _ = self.writer.lock().await.send(Message::Close(frame.clone())).await;
+                            return Some(Ok(Message::Close(frame)));
+                        }
+                        Control::Reserved(code) => return Some(Err(protocol(ProtocolError::UnknownControlFrameType(code)))),
+                    }
+                }
+                OpCode::Data(Data::Continue) => {
+                    if header.rsv1 {
+                        return Some(Err(protocol(ProtocolError::NonZeroReservedBits)));
+                    }
+                    if self.partial.is_none() {
+                        return Some(Err(protocol(ProtocolError::UnexpectedContinueFrame)));
+                    }
+                }
+                OpCode::Data(data) => {
+                    if self.partial.is_some() {
+                        return Some(Err(protocol(ProtocolError::ExpectedFragment(data))));
+                    }
+                    self.partial = Some(Partial { data, compressed: header.rsv1, payload: Vec::new() });
+                }
+            }
+            if let Err(e) = self.append(&payload, header.is_final) {
+                return Some(Err(e));
+            }
+            if header.is_final {
+                let partial = self.partial.take()?;
+                return Some(match partial.data {
+                    Data::Text => String::from_utf8(partial.payload).map(Message::Text).map_err(|_| axum::Error::new(Error::Utf8)),
+                    _ => Ok(Message::Binary(partial.payload)),
+                });
+            }
+        }
+    }
+
+    /// 把一帧的负载加入正在接收的消息，压缩的消息边解压边检查大小
+    fn append(&mut self, payload: &[u8], is_final: bool) -> Result<(), axum::Error> {
+        let Some(partial) = self.partial.as_mut() else {
+            return Ok(());
+        };
+        if partial.compressed {
+            self.inflater.inflate(payload, &mut partial.payload, self.max_bytes)?;
+            if is_final {
+                self.inflater.finish(&mut partial.payload, self.max_bytes)?;
+            }
+        } else {
+            partial.payload.extend_from_slice(payload);
+        }
+        if partial.payload.len() > self.max_bytes {
+            return Err(too_long(partial.payload.len(), self.max_bytes));
+        }
+        Ok(())
+    }
+
+    /// 读取一帧，超过上限的帧在读入负载之前就被拒绝
+    async fn read_frame(&mut self) -> Result<Option<(FrameHeader, Vec<u8>)>, axum::Error> {
+        loop {
+            let mut cursor = Cursor::new(self.buffer.as_slice());
+            if let Some((header, length)) = FrameHeader::parse(&mut cursor).map_err(axum::Error::new)? {
+                let length = usize::try_from(length).unwrap_or(usize::MAX);
+                if length > self.max_bytes {
+                    return Err(too_long(length, self.max_bytes));
+                }
+                let start = cursor.position() as usize;
+                if self.buffer.len() >= start + length {
+                    let payload = self.buffer[start..start + length].to_vec();
+                    self.buffer.drain(..start + length);
+                    return Ok(Some((header, payload)));
+                }
+            }
+            if self.io.read_buf(&mut self.buffer).await.map_err(axum::Error::new)? == 0 {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderMap;
+    use serde_json::{json, Value};
+    use std::net::SocketAddr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio_tungstenite::tungstenite::protocol::frame::coding::{Data, OpCode};
+    use tokio_tungstenite::tungstenite::protocol::frame::{Frame, FrameHeader};
+
+    use super::*;
+    use crate::server::test_support::{request, spawn_server};
+    use crate::{FunctionTool, RustMCP, Settings};
+
+    fn offer(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("sec-websocket-extensions", value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn negotiation_takes_the_first_acceptable_offer() {
+        let config = WsConfig::new();
+        let headers = offer("x-webkit-deflate-frame, permessage-deflate; server_max_window_bits=10, permessage-deflate; client_max_window_bits");
+        assert_eq!(negotiate(&headers, &config), Some(DeflateParams::default()));
+        let params = negotiate(&offer("permessage-deflate; server_max_window_bits=15; client_no_context_takeover"), &config).unwrap();
+        assert_eq!(params.response_header(), "permessage-deflate; client_no_context_takeover");
+
+        for rejected in [
+            "permessage-deflate; client_no_context_takeover; client_no_context_takeover",
+            "permessage-deflate; unknown_param",
+            "permessage-deflate; client_max_window_bits=16",
+            "permessage-deflate; server_no_context_takeover=1",
+            "x-webkit-deflate-frame",
+        ] {
+            assert_eq!(negotiate(&offer(rejected), &config), None, "{}", rejected);
+        }
+        assert_eq!(negotiate(&HeaderMap::new(), &config), None);
+        assert_eq!(negotiate(&offer("permessage-deflate"), &WsConfig::new().with_deflate(false)), None);
+
+        let params = negotiate(&offer("permessage-deflate"), &WsConfig::new().with_no_context_takeover(true)).unwrap();
+        assert_eq!(params.response_header(), "permessage-deflate; server_no_context_takeover; client_no_context_takeover");
+    }
+
+    /// 手写的压缩WebSocket客户端
+    struct Client {
+        stream: TcpStream,
+        buffer: Vec<u8>,
+        deflater: Deflater,
+        inflater: Inflater,
+    }
+
+    impl Client {
+        /// 提出`extensions`完成握手，返回客户端和响应头
+        async fn connect(addr: SocketAddr, extensions: &str, no_context_takeover: bool) -> (Self, String) {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            let handshake = format!(
+                "GET /mcp/ws HTTP/1.1\r\nhost: {}\r\nupgrade: websocket\r\nconnection: Upgrade\r\nsec-websocket-version: 13\r\nsec-websocket-key: dGhlIHNhbXBsZSBub25jZQ==\r\nsec-websocket-extensions: {}\r\n\r\n",
+                addr, extensions
+            );
+            stream.write_all(handshake.as_bytes()).await.unwrap();
+            let mut buffer = Vec::new();
+            let end = loop {
+                assert!(stream.read_buf(&mut buffer).await.unwrap() > 0);
+                if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                    break end + 4;
+                }
+            };
+            let head = String::from_utf8(buffer.drain(..end).collect()).unwrap();
+            let client = Self { stream, buffer, deflater: Deflater::new(no_context_takeover), inflater: Inflater::new(no_context_takeover) };
+            (client, head)
+        }
+
+        async fn write_frame(&mut self, header: FrameHeader, payload: Vec<u8>) {
+            let mut bytes = Vec::new();
+            Frame::from_payload(FrameHeader { mask: Some([7, 1, 9, 3]), ..header }, payload).format(&mut bytes).unwrap();
+            self.stream.write_all(&bytes).await.unwrap();
+        }
+
+        /// 压缩后分成`fragments`帧发送
+        async fn send(&mut self, message: &Value, fragments: usize) {
+            let compressed = self.deflater.deflate(message.to_string().as_bytes());
+            let size = compressed.len().div_ceil(fragments);
+            let chunks: Vec<Vec<u8>> = compressed.chunks(size).map(<[u8]>::to_vec).collect();
+            let last = chunks.len() - 1;
+            for (i, chunk) in chunks.into_iter().enumerate() {
+                let opcode = if i == 0 { OpCode::Data(Data::Text) } else { OpCode::Data(Data::Continue) };
+                self.write_frame(FrameHeader { is_final: i == last, rsv1: i == 0, opcode, ..FrameHeader::default() }, chunk).await;
+            }
+        }
+
+        async fn read_frame(&mut self) -> (FrameHeader, Vec<u8>) {
+            loop {
+                let mut cursor = Cursor::new(self.buffer.as_slice());
+                if let Some((header, length)) = FrameHeader::parse(&mut cursor).unwrap() {
+                    let start = cursor.position() as usize;
+                    let end = start + length as usize;
+                    if self.buffer.len() >= end {
+                        let payload = self.buffer[start..end].to_vec();
+                        self.buffer.drain(..end);
+                        return (header, payload);
+                    }
+                }
+                let read = tokio::time::timeout(std::time::Duration::from_secs(10), self.stream.read_buf(&mut self.buffer)).await.unwrap().unwrap();
+                assert!(read > 0, "connection closed");
+            }
+        }
+
+        /// 接收一条压缩的文本消息，返回解压后的JSON和压缩后的字节数
+        async fn recv(&mut self) -> (Value, usize) {
+            let (header, payload) = self.read_frame().await;
+            assert!(header.rsv1 && header.is_final && header.opcode == OpCode::Data(Data::Text), "{:?}", header);
+            let mut text = Vec::new();
+            self.inflater.inflate(&payload, &mut text, usize::MAX).unwrap();
+            self.inflater.finish(&mut text, usize::MAX).unwrap();
+            (serde_json::from_slice(&text).unwrap(), payload.len())
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn negotiated_connections_compress_listings_and_close_oversized_decompressed_messages() {
+        let rustmcp = RustMCP::new().with_settings(Settings::new().with_ws_max_message_bytes(64 * 1024));
+        for i in 0..1000 {
+            rustmcp.add_tool(FunctionTool::simple(format!("tool_{}", i), "Does one of many things and explains which one in a longer description", |_| Ok(json!("ok"))));
+        }
+        let addr = spawn_server(&rustmcp).await;
+
+        let (mut client, head) = Client::connect(addr, "permessage-deflate; client_max_window_bits", false).await;
+        assert!(head.starts_with("HTTP/1.1 101"), "{}", head);
+        assert!(head.to_ascii_lowercase().contains("sec-websocket-extensions: permessage-deflate\r\n"), "{}", head);
+        assert!(head.contains("mcp-session-id: "), "{}", head);
+
+        // 分片发送的压缩消息
+        client.send(&request(1, "initialize", json!({})), 3).await;
+        assert!(client.recv().await.0["result"].is_object());
+
+        // 大的列表压缩后返回，并且超过了客户端消息的上限
+        client.send(&request(2, "tools/list", json!({})), 1).await;
+        let (listing, compressed) = client.recv().await;
+        assert_eq!(listing["result"]["tools"].as_array().unwrap().len(), 1000);
+        assert!(listing.to_string().len() > 64 * 1024);
+        assert!(compressed * 4 < listing.to_string().len(), "{} bytes compressed", compressed);
+
+        // 压缩后只有几KB，解压后超过上限，服务器以1009关闭连接
+        client.send(&request(3, "ping", json!({ "padding": "x".repeat(1024 * 1024) })), 1).await;
+        let (header, payload) = client.read_frame().await;
+        assert_eq!(header.opcode, OpCode::Control(Control::Close));
+        assert_eq!(u16::from_be_bytes([payload[0], payload[1]]), 1009);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn no_context_takeover_compresses_each_message_on_its_own() {
+        let settings = Settings::new().with_ws(WsConfig::new().with_no_context_takeover(true));
+        let addr = spawn_server(&RustMCP::new().with_settings(settings)).await;
+
+        let (mut client, head) = Client::connect(addr, "permessage-deflate", true).await;
+        assert!(head.contains("server_no_context_takeover; client_no_context_takeover"), "{}", head);
+        client.send(&request(1, "initialize", json!({})), 1).await;
+        client.recv().await;
+        // 两条相同的响应压缩结果相同，第二条没有引用第一条
+        client.send(&request(2, "ping", json!({})), 1).await;
+        let (first, first_size) = client.recv().await;
+        client.send(&request(2, "ping", json!({})), 1).await;
+        let (second, second_size) = client.recv().await;
+        assert_eq!(first, second);
+        assert_eq!(first_size, second_size);
+    }
+}
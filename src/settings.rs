@@ -1,4 +1,19 @@
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::server::admission::AdmissionLimits;
+use crate::server::content::ResultFormat;
+use crate::server::http_client::HttpClientConfig;
+use crate::server::lifecycle::LifecyclePolicy;
+use crate::server::policy::MethodPolicy;
+use crate::server::prompts::TurnPolicy;
+use crate::server::pending::PendingLimits;
+use crate::server::sse::{ReplayLimits, DEFAULT_REPLAY_BYTES, DEFAULT_REPLAY_EVENTS};
+use crate::server::session::{SessionEviction, SessionLimits, DEFAULT_MAX_BYTES, DEFAULT_MAX_SESSIONS, DEFAULT_TTL};
+use crate::server::serialization::SerializationPolicy;
+use crate::server::truncation::TruncationPolicy;
+use crate::server::ws::WsConfig;
 
 /// 应用设置
 #[derive(Debug, Clone, Deserialize)]
@@ -12,6 +27,192 @@ pub struct Settings {
     #[serde(default = "default_resource_prefix_format")]
     #[allow(dead_code)]
     pub resource_prefix_format: String,
+    /// 请求限流设置，为`None`时不限流
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitSettings>,
+    /// 在`initialize`响应的`serverInfo`中报告的服务器名称
+    #[serde(default = "default_server_name")]
+    pub server_name: String,
+    /// 在`initialize`响应的`serverInfo`中报告的服务器版本
+    #[serde(default = "default_server_version")]
+    pub server_version: String,
+    /// 严格解析请求参数：`name`、`uri`、`arguments`存在但类型不对时返回`-32602`，
+    /// 而不是当作没有传入。默认开启
+    #[serde(default = "default_strict_params")]
+    pub strict_params: bool,
+    /// 请求`params`允许的最大嵌套层数，`params`对象本身为第1层
+    #[serde(default = "default_max_params_depth")]
+    pub max_params_depth: usize,
+    /// 请求`params`序列化后允许的最大字节数
+    #[serde(default = "default_max_params_bytes")]
+    pub max_params_bytes: usize,
+    /// `POST /mcp`和`POST /mcp/call-tool`请求体的最大字节数，参见[`body`](crate::server::body)
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize,
+    /// 每个会话存储的最大字节数
+    #[serde(default = "default_session_max_bytes")]
+    pub session_max_bytes: usize,
+    /// 会话存储的空闲超时（秒），为`None`时不过期
+    #[serde(default = "default_session_ttl_secs")]
+    pub session_ttl_secs: Option<u64>,
+    /// 同时保留的HTTP会话数上限，默认10000，为`None`时不限制
+    #[serde(default = "default_max_sessions")]
+    pub max_sessions: Option<usize>,
+    /// 会话数达到上限时的淘汰策略，默认淘汰最久没有请求的会话
+    #[serde(default)]
+    pub session_eviction: SessionEviction,
+    /// 后台清理过期HTTP会话的间隔（毫秒）
+    #[serde(default = "default_session_reap_interval_ms")]
+    pub session_reap_interval_ms: u64,
+    /// 每个HTTP会话通知流重放缓冲区的通知数，参见[`sse`](crate::server::sse)
+    #[serde(default = "default_sse_replay_events")]
+    pub sse_replay_events: usize,
+    /// 每个HTTP会话通知流重放缓冲区的字节数
+    #[serde(default = "default_sse_replay_bytes")]
+    pub sse_replay_bytes: usize,
+    /// 通知流空闲时发送保活注释的间隔（毫秒），为0时不发送
+    #[serde(default = "default_sse_keep_alive_ms")]
+    pub sse_keep_alive_ms: u64,
+    /// 通知流`retry:`字段建议的重新连接等待毫秒数，为0时不发送
+    #[serde(default = "default_sse_retry_ms")]
+    pub sse_retry_ms: u64,
+    /// 在`initialize`之前到达的请求的处理策略，默认为`auto`
+    #[serde(default)]
+    pub lifecycle_policy: LifecyclePolicy,
+    /// 启动校验发现错误时拒绝启动，默认只记录日志
+    #[serde(default)]
+    pub strict_validation: bool,
+    /// 注册带有未登记标签的工具、资源或提示时失败，参见[`tags`](crate::server::tags)
+    #[serde(default)]
+    pub strict_tags: bool,
+    /// 启动校验时按当前的模式检查工具和提示的示例，参见[`RustMCP::validate_examples`](crate::RustMCP::validate_examples)
+    #[serde(default)]
+    pub validate_examples: bool,
+    /// 只读模式：拒绝调用可能修改状态的工具，运行时可以通过`RustMCP::set_read_only`切换
+    #[serde(default)]
+    pub read_only: bool,
+    /// 只读模式下允许调用没有注解的工具，默认把它们当作可能修改状态
+    #[serde(default)]
+    pub read_only_allow_unannotated: bool,
+    /// 规范化JSON：对外输出的所有JSON对象的键按字典序排列，相同的注册项总是得到相同的字节
+    #[serde(default)]
+    pub canonical_json: bool,
+    /// 每个WebSocket或stdio连接的发送队列长度，参见[`notifications`](crate::server::notifications)
+    #[serde(default = "default_outbound_queue_depth")]
+    pub outbound_queue_depth: usize,
+    /// 审计记录队列长度，队列满时丢弃记录，参见[`audit`](crate::server::audit)
+    #[serde(default = "default_audit_queue_depth")]
+    pub audit_queue_depth: usize,
+    /// 带幂等键的`tools/call`结果保存的毫秒数，参见[`idempotency`](crate::server::idempotency)
+    #[serde(default = "default_idempotency_ttl_ms")]
+    pub idempotency_ttl_ms: u64,
+    /// `tools/call`、`resources/read`和`prompts/get`的最长执行时间（毫秒），为`None`时不限制；
+    /// 客户端在`_meta.timeoutMs`中给出更短的时间时使用客户端的时间
+    #[serde(default)]
+    pub request_timeout_ms: Option<u64>,
+    /// 客户端`_meta.timeoutMs`的下限（毫秒），更小的值被提高到这个值并在结果中警告
+    #[serde(default = "default_min_timeout_hint_ms")]
+    pub min_timeout_hint_ms: u64,
+    /// `x-rustmcp/tools/call_batch`中同时执行的调用数，参见[`batch`](crate::server::batch)
+    #[serde(default = "default_batch_concurrency")]
+    pub batch_concurrency: usize,
+    /// JSON资源的文本是否缩进，参见[`ResourceContents`](crate::ResourceContents)
+    #[serde(default)]
+    pub resource_json_pretty: bool,
+    /// 流式资源每块的字节数，参见[`streaming`](crate::server::streaming)
+    #[serde(default = "default_resource_chunk_bytes")]
+    pub resource_chunk_bytes: usize,
+    /// 每次资源探测的最长时间（毫秒），参见[`probe`](crate::server::probe)
+    #[serde(default = "default_resource_probe_timeout_ms")]
+    pub resource_probe_timeout_ms: u64,
+    /// 连续探测失败多少次后停用资源，为`None`时不停用
+    #[serde(default)]
+    pub resource_disable_after: Option<u32>,
+    /// 管理接口的Bearer令牌，设置后`create_app`在`/admin`下挂载管理接口（需要`admin`特性）
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    /// `tools/call`结果的格式，为`None`时按会话协商的协议版本选择，参见[`ResultFormat`]
+    #[serde(default)]
+    pub result_format: Option<ResultFormat>,
+    /// 工具结果序列化后的最大字节数，超过时截断，为`None`时不限制，参见[`truncation`](crate::server::truncation)
+    #[serde(default)]
+    pub max_result_bytes: Option<usize>,
+    /// 截断的结果带有`structuredContent`时的处理方式
+    #[serde(default)]
+    pub truncation_policy: TruncationPolicy,
+    /// 工具错误文本的最大字节数，超过时截断，为0时不限制，参见[`error_shaping`](crate::server::error_shaping)
+    #[serde(default = "default_max_error_bytes")]
+    pub max_error_bytes: usize,
+    /// 从工具错误文本中去掉的片段的正则表达式
+    #[serde(default)]
+    pub error_scrubbers: Vec<String>,
+    /// JSON-RPC方法的允许/拒绝列表，为`None`时允许所有方法，参见[`policy`](crate::server::policy)
+    #[serde(default)]
+    pub method_policy: Option<MethodPolicy>,
+    /// `GET /mcp/info`要求`Authorization: Bearer <admin_token>`，参见[`info`](crate::server::info)
+    #[serde(default)]
+    pub protect_info: bool,
+    /// 按`Accept-Encoding`用gzip或deflate压缩HTTP响应，默认关闭，参见[`compression`](crate::server::compression)
+    #[serde(default)]
+    pub compression: bool,
+    /// 压缩的最小响应字节数，更小的响应原样发送
+    #[serde(default = "default_compression_min_bytes")]
+    pub compression_min_bytes: u16,
+    /// `prompts/list`每页的提示数，为`None`时不分页，一次返回所有提示
+    #[serde(default)]
+    pub prompt_page_size: Option<usize>,
+    /// `tools/list`结果中工具列表序列化后的最大字节数，超过时按优先级截断，为`None`时不限制，
+    /// 参见[`ToolManager::list_tools_within`](crate::ToolManager::list_tools_within)
+    #[serde(default)]
+    pub max_tools_list_bytes: Option<usize>,
+    /// 列表中的工具、资源或提示序列化失败时的处理方式，参见[`serialization`](crate::server::serialization)
+    #[serde(default)]
+    pub serialization_policy: SerializationPolicy,
+    /// 提示消息的轮次检查，默认记录警告，参见[`PromptBuilder`](crate::PromptBuilder)
+    #[serde(default)]
+    pub prompt_turn_policy: TurnPolicy,
+    /// 优雅关闭时每个工具关闭钩子的最长等待毫秒数，参见[`tool_hooks`](crate::server::tool_hooks)
+    #[serde(default = "default_tool_shutdown_timeout_ms")]
+    pub tool_shutdown_timeout_ms: u64,
+    /// WebSocket消息的最大字节数，超过时以`1009`关闭连接，参见[`ws`](crate::server::ws)
+    #[serde(default = "default_ws_max_message_bytes")]
+    pub ws_max_message_bytes: usize,
+    /// WebSocket的压缩设置，参见[`WsConfig`]
+    #[serde(default)]
+    pub ws: WsConfig,
+    /// 连接断开后保留未送达响应的毫秒数，为`None`时不保留，参见[`pending`](crate::server::pending)
+    #[serde(default)]
+    pub pending_results_ttl_ms: Option<u64>,
+    /// 每个会话最多保留的未送达响应数
+    #[serde(default = "default_pending_results_max")]
+    pub pending_results_max: usize,
+    /// 每个会话保留的未送达响应的最大总字节数
+    #[serde(default = "default_pending_results_max_bytes")]
+    pub pending_results_max_bytes: usize,
+    /// 客户端以同一会话重新连接WebSocket时自动重发保留的响应
+    #[serde(default = "default_pending_redelivery")]
+    pub pending_redelivery: bool,
+    /// 出站HTTP客户端的配置，参见[`http_client`](crate::server::http_client)
+    #[serde(default)]
+    pub http_client: HttpClientConfig,
+    /// 同时处理的JSON-RPC请求数上限，为`None`时不限制，参见[`admission`](crate::server::admission)
+    #[serde(default)]
+    pub max_in_flight: Option<usize>,
+    /// 每个会话（没有会话时按远端IP）同时处理的请求数上限，为`None`时不限制
+    #[serde(default)]
+    pub max_in_flight_per_session: Option<usize>,
+    /// 不计入并发请求数、也不会因过载被拒绝的方法
+    #[serde(default)]
+    pub load_shedding_exempt: Vec<String>,
+    /// 因过载拒绝请求时建议的重试等待毫秒数
+    #[serde(default = "default_overload_retry_after_ms")]
+    pub overload_retry_after_ms: u64,
+    /// 工具调用占住异步运行时超过多少毫秒时警告，为`None`时不监视，参见[`watchdog`](crate::server::watchdog)
+    #[serde(default)]
+    pub blocking_watchdog_ms: Option<u64>,
+    /// 被警告的工具之后的调用自动放到阻塞线程池执行
+    #[serde(default)]
+    pub blocking_escalation: bool,
 }
 
 impl Settings {
@@ -22,6 +223,67 @@ impl Settings {
             port: 8000,
             debug: false,
             resource_prefix_format: default_resource_prefix_format(),
+            rate_limit: None,
+            server_name: default_server_name(),
+            server_version: default_server_version(),
+            strict_params: default_strict_params(),
+            max_params_depth: default_max_params_depth(),
+            max_params_bytes: default_max_params_bytes(),
+            max_body_bytes: default_max_body_bytes(),
+            session_max_bytes: default_session_max_bytes(),
+            session_ttl_secs: default_session_ttl_secs(),
+            max_sessions: default_max_sessions(),
+            session_eviction: SessionEviction::default(),
+            session_reap_interval_ms: default_session_reap_interval_ms(),
+            sse_replay_events: default_sse_replay_events(),
+            sse_replay_bytes: default_sse_replay_bytes(),
+            sse_keep_alive_ms: default_sse_keep_alive_ms(),
+            sse_retry_ms: default_sse_retry_ms(),
+            lifecycle_policy: LifecyclePolicy::default(),
+            strict_validation: false,
+            strict_tags: false,
+            validate_examples: false,
+            read_only: false,
+            read_only_allow_unannotated: false,
+            canonical_json: false,
+            outbound_queue_depth: default_outbound_queue_depth(),
+            audit_queue_depth: default_audit_queue_depth(),
+            idempotency_ttl_ms: default_idempotency_ttl_ms(),
+            request_timeout_ms: None,
+            min_timeout_hint_ms: default_min_timeout_hint_ms(),
+            batch_concurrency: default_batch_concurrency(),
+            resource_json_pretty: false,
+            resource_chunk_bytes: default_resource_chunk_bytes(),
+            resource_probe_timeout_ms: default_resource_probe_timeout_ms(),
+            resource_disable_after: None,
+            admin_token: None,
+            result_format: None,
+            max_result_bytes: None,
+            truncation_policy: TruncationPolicy::default(),
+            max_error_bytes: default_max_error_bytes(),
+            error_scrubbers: Vec::new(),
+            method_policy: None,
+            protect_info: false,
+            compression: false,
+            compression_min_bytes: default_compression_min_bytes(),
+            prompt_page_size: None,
+            max_tools_list_bytes: None,
+            serialization_policy: SerializationPolicy::default(),
+            prompt_turn_policy: TurnPolicy::default(),
+            tool_shutdown_timeout_ms: default_tool_shutdown_timeout_ms(),
+            ws_max_message_bytes: default_ws_max_message_bytes(),
+            ws: WsConfig::default(),
+            pending_results_ttl_ms: None,
+            pending_results_max: default_pending_results_max(),
+            pending_results_max_bytes: default_pending_results_max_bytes(),
+            pending_redelivery: default_pending_redelivery(),
+            http_client: HttpClientConfig::default(),
+            max_in_flight: None,
+            max_in_flight_per_session: None,
+            load_shedding_exempt: Vec::new(),
+            overload_retry_after_ms: default_overload_retry_after_ms(),
+            blocking_watchdog_ms: None,
+            blocking_escalation: false,
         }
     }
     
@@ -31,6 +293,398 @@ impl Settings {
         self.debug
     }
     
+    /// 设置请求限流
+    ///
+    /// 适用于所有JSON-RPC请求；REST的`/mcp/call-tool`和`/mcp/tools/{name}/call`按`tools/call`计数。
+    pub fn with_rate_limit(mut self, rate_limit: RateLimitSettings) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+    
+    /// 设置服务器名称和版本
+    pub fn with_server_info(mut self, name: impl Into<String>, version: impl Into<String>) -> Self {
+        self.server_name = name.into();
+        self.server_version = version.into();
+        self
+    }
+    
+    /// 设置是否严格解析请求参数，关闭后恢复旧的宽松行为：类型不对的参数被当作没有传入
+    pub fn with_strict_params(mut self, strict: bool) -> Self {
+        self.strict_params = strict;
+        self
+    }
+    
+    /// 设置请求`params`的最大嵌套层数和最大字节数
+    pub fn with_params_limits(mut self, max_depth: usize, max_bytes: usize) -> Self {
+        self.max_params_depth = max_depth;
+        self.max_params_bytes = max_bytes;
+        self
+    }
+    
+    /// 设置`POST /mcp`和`POST /mcp/call-tool`请求体的最大字节数（默认2 MiB），超过时返回`413`和`-32600`错误
+    pub fn with_max_body_bytes(mut self, bytes: usize) -> Self {
+        self.max_body_bytes = bytes;
+        self
+    }
+    
+    /// 设置会话存储的大小上限和空闲超时
+    pub fn with_session_limits(mut self, max_bytes: usize, ttl: Option<Duration>) -> Self {
+        self.session_max_bytes = max_bytes;
+        self.session_ttl_secs = ttl.map(|ttl| ttl.as_secs());
+        self
+    }
+    
+    /// 获取会话存储的限制
+    pub fn session_limits(&self) -> SessionLimits {
+        SessionLimits::new(self.session_max_bytes, self.session_ttl_secs.map(Duration::from_secs))
+    }
+    
+    /// 设置同时保留的HTTP会话数上限和淘汰策略，上限为0时按1处理
+    pub fn with_max_sessions(mut self, max_sessions: usize, eviction: SessionEviction) -> Self {
+        self.max_sessions = Some(max_sessions);
+        self.session_eviction = eviction;
+        self
+    }
+    
+    /// 设置后台清理过期HTTP会话的间隔（默认1分钟）
+    pub fn with_session_reap_interval(mut self, interval: Duration) -> Self {
+        self.session_reap_interval_ms = u64::try_from(interval.as_millis()).unwrap_or(u64::MAX);
+        self
+    }
+    
+    /// 设置HTTP会话通知流重放缓冲区保留的通知数和字节数（默认256条、1 MiB），
+    /// 最新的一条通知总是保留
+    pub fn with_sse_replay(mut self, events: usize, bytes: usize) -> Self {
+        self.sse_replay_events = events;
+        self.sse_replay_bytes = bytes;
+        self
+    }
+    
+    /// 获取通知流重放缓冲区的限制
+    pub(crate) fn replay_limits(&self) -> ReplayLimits {
+        ReplayLimits {
+            events: self.sse_replay_events,
+            bytes: self.sse_replay_bytes,
+        }
+    }
+    
+    /// 设置通知流空闲时发送保活注释（`: keepalive`）的间隔（默认25秒），为0时不发送
+    ///
+    /// 代理常在连接空闲一段时间后静默断开，保活让连接保持活跃，也让断开的连接尽早被发现。
+    pub fn with_sse_keep_alive(mut self, interval: Duration) -> Self {
+        self.sse_keep_alive_ms = u64::try_from(interval.as_millis()).unwrap_or(u64::MAX);
+        self
+    }
+    
+    /// 设置通知流`retry:`字段建议客户端断线后等待多久再重新连接（默认3秒），为0时不发送
+    pub fn with_sse_retry(mut self, delay: Duration) -> Self {
+        self.sse_retry_ms = u64::try_from(delay.as_millis()).unwrap_or(u64::MAX);
+        self
+    }
+    
+    /// 通知流的保活间隔，不发送时为`None`
+    pub(crate) fn sse_keep_alive(&self) -> Option<Duration> {
+        (self.sse_keep_alive_ms > 0).then(|| Duration::from_millis(self.sse_keep_alive_ms))
+    }
+    
+    /// 通知流建议的重新连接等待时间，不发送时为`None`
+    pub(crate) fn sse_retry(&self) -> Option<Duration> {
+        (self.sse_retry_ms > 0).then(|| Duration::from_millis(self.sse_retry_ms))
+    }
+    
+    /// 设置在`initialize`之前到达的请求的处理策略
+    pub fn with_lifecycle_policy(mut self, policy: LifecyclePolicy) -> Self {
+        self.lifecycle_policy = policy;
+        self
+    }
+    
+    /// 设置启动校验发现错误时是否拒绝启动
+    pub fn with_strict_validation(mut self, strict: bool) -> Self {
+        self.strict_validation = strict;
+        self
+    }
+    
+    /// 设置是否只接受通过`RustMCP::define_tag`登记的标签
+    pub fn with_strict_tags(mut self, strict: bool) -> Self {
+        self.strict_tags = strict;
+        self
+    }
+    
+    /// 设置启动校验时是否检查工具和提示的示例
+    pub fn with_validate_examples(mut self, enabled: bool) -> Self {
+        self.validate_examples = enabled;
+        self
+    }
+    
+    /// 设置是否以只读模式启动
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+    
+    /// 设置只读模式下是否允许调用没有注解的工具
+    pub fn with_read_only_allow_unannotated(mut self, allow: bool) -> Self {
+        self.read_only_allow_unannotated = allow;
+        self
+    }
+    
+    /// 设置是否输出规范化JSON
+    ///
+    /// 开启后JSON-RPC响应、REST接口的列表和调用结果以及`/mcp/openapi.json`中，
+    /// 所有对象的键都按字典序排列，不受`HashMap`的随机哈希种子和`serde_json`的`preserve_order`特性影响。
+    /// 只改变键的顺序，不改变内容。
+    ///
+    /// ```rust
+    /// use rustmcp::{RustMCP, Settings};
+    ///
+    /// let rustmcp = RustMCP::new().with_settings(Settings::new().with_canonical_json(true));
+    /// ```
+    pub fn with_canonical_json(mut self, canonical: bool) -> Self {
+        self.canonical_json = canonical;
+        self
+    }
+    
+    /// 设置每个连接的发送队列长度（默认256），0按1处理
+    pub fn with_outbound_queue_depth(mut self, depth: usize) -> Self {
+        self.outbound_queue_depth = depth;
+        self
+    }
+    
+    /// 设置审计记录队列长度（默认1024），0按1处理；需要在设置审计接收端之前设置
+    pub fn with_audit_queue_depth(mut self, depth: usize) -> Self {
+        self.audit_queue_depth = depth;
+        self
+    }
+    
+    /// 设置带幂等键的`tools/call`结果的保存时间（默认10分钟）
+    pub fn with_idempotency_ttl(mut self, ttl: Duration) -> Self {
+        self.idempotency_ttl_ms = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+        self
+    }
+    
+    /// 设置`tools/call`、`resources/read`和`prompts/get`的最长执行时间
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout_ms = Some(u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX));
+        self
+    }
+    
+    /// 设置客户端`_meta.timeoutMs`的下限
+    pub fn with_min_timeout_hint(mut self, floor: Duration) -> Self {
+        self.min_timeout_hint_ms = u64::try_from(floor.as_millis()).unwrap_or(u64::MAX);
+        self
+    }
+    
+    /// 设置`x-rustmcp/tools/call_batch`中同时执行的调用数（默认8，为0时按1处理）
+    pub fn with_batch_concurrency(mut self, limit: usize) -> Self {
+        self.batch_concurrency = limit;
+        self
+    }
+    
+    /// 设置JSON资源的文本是否缩进（默认不缩进）
+    pub fn with_resource_json_pretty(mut self, enabled: bool) -> Self {
+        self.resource_json_pretty = enabled;
+        self
+    }
+    
+    /// 设置流式资源每块的字节数（默认1 MiB），0按1处理
+    pub fn with_resource_chunk_bytes(mut self, bytes: usize) -> Self {
+        self.resource_chunk_bytes = bytes;
+        self
+    }
+    
+    /// 设置每次资源探测的最长时间（默认5秒），以及连续失败多少次后停用资源（默认不停用）
+    pub fn with_resource_probes(mut self, timeout: Duration, disable_after: Option<u32>) -> Self {
+        self.resource_probe_timeout_ms = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+        self.resource_disable_after = disable_after;
+        self
+    }
+    
+    /// 资源探测的时限
+    pub fn resource_probe_timeout(&self) -> Duration {
+        Duration::from_millis(self.resource_probe_timeout_ms)
+    }
+    
+    /// 设置管理接口的Bearer令牌，参见[`admin`](crate::server::admin)
+    pub fn with_admin_token(mut self, token: impl Into<String>) -> Self {
+        self.admin_token = Some(token.into());
+        self
+    }
+    
+    /// 所有会话使用指定的`tools/call`结果格式，不再按协议版本选择
+    pub fn with_result_format(mut self, format: ResultFormat) -> Self {
+        self.result_format = Some(format);
+        self
+    }
+    
+    /// 设置工具结果序列化后的最大字节数，单个工具可以用[`FunctionToolBuilder::max_result_bytes`](crate::FunctionToolBuilder::max_result_bytes)覆盖
+    pub fn with_max_result_bytes(mut self, bytes: usize) -> Self {
+        self.max_result_bytes = Some(bytes);
+        self
+    }
+    
+    /// 设置工具错误文本的最大字节数（默认4096），为0时不限制
+    pub fn with_max_error_bytes(mut self, bytes: usize) -> Self {
+        self.max_error_bytes = bytes;
+        self
+    }
+    
+    /// 添加从工具错误文本中去掉的片段的正则表达式，匹配的片段替换为`[REDACTED]`
+    pub fn with_error_scrubber(mut self, pattern: impl Into<String>) -> Self {
+        self.error_scrubbers.push(pattern.into());
+        self
+    }
+    
+    /// 设置截断的结果带有`structuredContent`时的处理方式（默认丢弃`structuredContent`）
+    pub fn with_truncation_policy(mut self, policy: TruncationPolicy) -> Self {
+        self.truncation_policy = policy;
+        self
+    }
+    
+    /// 设置JSON-RPC方法的允许/拒绝列表
+    pub fn with_method_policy(mut self, policy: MethodPolicy) -> Self {
+        self.method_policy = Some(policy);
+        self
+    }
+    
+    /// 设置`GET /mcp/info`是否要求管理令牌（默认不要求）
+    pub fn with_protect_info(mut self, enabled: bool) -> Self {
+        self.protect_info = enabled;
+        self
+    }
+    
+    /// 开启HTTP响应压缩，只压缩不小于`min_bytes`的响应
+    pub fn with_compression(mut self, min_bytes: u16) -> Self {
+        self.compression = true;
+        self.compression_min_bytes = min_bytes;
+        self
+    }
+    
+    /// 设置`prompts/list`每页的提示数，客户端用结果中的`nextCursor`翻页
+    pub fn with_prompt_page_size(mut self, page_size: usize) -> Self {
+        self.prompt_page_size = Some(page_size);
+        self
+    }
+    
+    /// 设置`tools/list`中工具列表的字节预算，超过时按[`FunctionToolBuilder::priority`](crate::FunctionToolBuilder::priority)截断
+    pub fn with_max_tools_list_bytes(mut self, bytes: usize) -> Self {
+        self.max_tools_list_bytes = Some(bytes);
+        self
+    }
+    
+    /// 设置列表中的项序列化失败时的处理方式（默认[`SerializationPolicy::FailClosed`]）
+    pub fn with_serialization_policy(mut self, policy: SerializationPolicy) -> Self {
+        self.serialization_policy = policy;
+        self
+    }
+    
+    /// 设置提示消息的轮次检查：角色只能是`user`或`assistant`，并且交替出现（默认[`TurnPolicy::Warn`]）
+    pub fn with_prompt_turn_policy(mut self, policy: TurnPolicy) -> Self {
+        self.prompt_turn_policy = policy;
+        self
+    }
+    
+    /// 设置优雅关闭时每个工具关闭钩子的最长等待时间（默认5秒）
+    pub fn with_tool_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.tool_shutdown_timeout_ms = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+        self
+    }
+    
+    /// 每个工具关闭钩子的最长等待时间
+    pub(crate) fn tool_shutdown_timeout(&self) -> Duration {
+        Duration::from_millis(self.tool_shutdown_timeout_ms)
+    }
+    
+    /// 设置WebSocket消息的最大字节数（默认64 MiB），超过时以`1009`关闭连接
+    pub fn with_ws_max_message_bytes(mut self, bytes: usize) -> Self {
+        self.ws_max_message_bytes = bytes;
+        self
+    }
+    
+    /// 设置WebSocket是否协商`permessage-deflate`以及是否每条消息单独压缩，参见[`ws`](crate::server::ws)
+    pub fn with_ws(mut self, config: WsConfig) -> Self {
+        self.ws = config;
+        self
+    }
+    
+    /// 连接断开时保留未送达的响应`ttl`，客户端以同一会话重新连接后取回，参见[`pending`](crate::server::pending)
+    pub fn with_pending_results(mut self, ttl: Duration) -> Self {
+        self.pending_results_ttl_ms = Some(u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX));
+        self
+    }
+    
+    /// 设置每个会话最多保留的未送达响应数（默认16）和总字节数（默认1 MiB）
+    pub fn with_pending_results_limits(mut self, max_results: usize, max_bytes: usize) -> Self {
+        self.pending_results_max = max_results;
+        self.pending_results_max_bytes = max_bytes;
+        self
+    }
+    
+    /// 设置重新连接时是否自动重发保留的响应（默认重发），不重发时客户端用`x-rustmcp/session/pending`取回
+    pub fn with_pending_redelivery(mut self, enabled: bool) -> Self {
+        self.pending_redelivery = enabled;
+        self
+    }
+    
+    /// 设置转发工具、URL资源和OpenAPI导入共用的出站HTTP客户端，参见[`http_client`](crate::server::http_client)
+    pub fn with_http_client(mut self, config: HttpClientConfig) -> Self {
+        self.http_client = config;
+        self
+    }
+    
+    /// 设置全局和每个会话同时处理的请求数上限，超过时拒绝新的请求，参见[`admission`](crate::server::admission)
+    pub fn with_in_flight_limits(mut self, max_in_flight: Option<usize>, per_session: Option<usize>) -> Self {
+        self.max_in_flight = max_in_flight;
+        self.max_in_flight_per_session = per_session;
+        self
+    }
+    
+    /// 设置不计入并发请求数的方法，例如开销很小的`tools/list`
+    pub fn with_load_shedding_exempt<I, S>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.load_shedding_exempt = methods.into_iter().map(Into::into).collect();
+        self
+    }
+    
+    /// 设置因过载拒绝请求时建议的重试等待时间（默认1秒）
+    pub fn with_overload_retry_after(mut self, retry_after: Duration) -> Self {
+        self.overload_retry_after_ms = u64::try_from(retry_after.as_millis()).unwrap_or(u64::MAX);
+        self
+    }
+    
+    /// 获取并发请求数上限
+    pub(crate) fn admission_limits(&self) -> AdmissionLimits {
+        AdmissionLimits {
+            max_in_flight: self.max_in_flight,
+            max_in_flight_per_session: self.max_in_flight_per_session,
+            retry_after: Duration::from_millis(self.overload_retry_after_ms),
+        }
+    }
+    
+    /// 获取保留未送达响应的限制，未开启时为`None`
+    pub(crate) fn pending_limits(&self) -> Option<PendingLimits> {
+        self.pending_results_ttl_ms.map(|ttl| PendingLimits {
+            ttl: Duration::from_millis(ttl),
+            max_results: self.pending_results_max,
+            max_bytes: self.pending_results_max_bytes,
+        })
+    }
+    
+    /// 开启阻塞工具的看门狗（调试用）：工具调用在异步运行时上超过`threshold`仍未返回时记录警告，
+    /// `escalate`为`true`时该工具之后的调用放到阻塞线程池执行，参见[`watchdog`](crate::server::watchdog)
+    pub fn with_blocking_watchdog(mut self, threshold: Duration, escalate: bool) -> Self {
+        self.blocking_watchdog_ms = Some(u64::try_from(threshold.as_millis()).unwrap_or(u64::MAX));
+        self.blocking_escalation = escalate;
+        self
+    }
+    
+    /// 获取看门狗的警告阈值，未开启时为`None`
+    pub(crate) fn blocking_watchdog(&self) -> Option<Duration> {
+        self.blocking_watchdog_ms.map(Duration::from_millis)
+    }
+    
     /// 获取资源前缀格式
     #[allow(dead_code)]
     pub fn resource_prefix_format(&self) -> &str {
@@ -46,4 +700,219 @@ impl Default for Settings {
 
 fn default_resource_prefix_format() -> String {
     "resource://".to_string()
-}
\ No newline at end of file
+}
+
+fn default_server_name() -> String {
+    "RustMCP-rs".to_string()
+}
+
+fn default_server_version() -> String {
+    "0.1.0".to_string()
+}
+
+fn default_strict_params() -> bool {
+    true
+}
+
+fn default_max_params_depth() -> usize {
+    32
+}
+
+fn default_max_params_bytes() -> usize {
+    4 * 1024 * 1024
+}
+
+fn default_session_max_bytes() -> usize {
+    DEFAULT_MAX_BYTES
+}
+
+fn default_session_ttl_secs() -> Option<u64> {
+    Some(DEFAULT_TTL.as_secs())
+}
+
+fn default_max_sessions() -> Option<usize> {
+    Some(DEFAULT_MAX_SESSIONS)
+}
+
+fn default_session_reap_interval_ms() -> u64 {
+    60 * 1000
+}
+
+fn default_sse_replay_events() -> usize {
+    DEFAULT_REPLAY_EVENTS
+}
+
+fn default_sse_replay_bytes() -> usize {
+    DEFAULT_REPLAY_BYTES
+}
+
+fn default_max_error_bytes() -> usize {
+    4096
+}
+
+fn default_sse_keep_alive_ms() -> u64 {
+    25 * 1000
+}
+
+fn default_sse_retry_ms() -> u64 {
+    3 * 1000
+}
+
+fn default_outbound_queue_depth() -> usize {
+    256
+}
+
+fn default_audit_queue_depth() -> usize {
+    1024
+}
+
+fn default_idempotency_ttl_ms() -> u64 {
+    10 * 60 * 1000
+}
+
+fn default_min_timeout_hint_ms() -> u64 {
+    50
+}
+
+fn default_batch_concurrency() -> usize {
+    8
+}
+
+fn default_resource_chunk_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_resource_probe_timeout_ms() -> u64 {
+    5_000
+}
+
+fn default_compression_min_bytes() -> u16 {
+    crate::server::compression::DEFAULT_MIN_BYTES
+}
+
+fn default_tool_shutdown_timeout_ms() -> u64 {
+    5_000
+}
+
+fn default_max_body_bytes() -> usize {
+    crate::server::body::DEFAULT_MAX_BODY_BYTES
+}
+
+fn default_ws_max_message_bytes() -> usize {
+    crate::server::ws::DEFAULT_MAX_MESSAGE_BYTES
+}
+
+fn default_pending_results_max() -> usize {
+    16
+}
+
+fn default_pending_results_max_bytes() -> usize {
+    1 << 20
+}
+
+fn default_pending_redelivery() -> bool {
+    true
+}
+
+fn default_overload_retry_after_ms() -> u64 {
+    1000
+}
+
+/// 令牌桶限流参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimit {
+    /// 每秒补充的请求数
+    pub requests_per_second: f64,
+    /// 桶容量（允许的突发请求数）
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// 创建新的限流参数
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        Self {
+            requests_per_second,
+            burst,
+        }
+    }
+}
+
+/// 按会话（或无状态HTTP的远端IP）进行的请求限流设置
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitSettings {
+    /// 默认限流参数，适用于未单独配置的方法
+    pub default: RateLimit,
+    /// 按方法单独配置的限流参数，例如为`tools/call`设置更严格的限制
+    #[serde(default)]
+    pub methods: HashMap<String, RateLimit>,
+}
+
+impl RateLimitSettings {
+    /// 使用默认限流参数创建设置
+    pub fn new(default: RateLimit) -> Self {
+        Self {
+            default,
+            methods: HashMap::new(),
+        }
+    }
+    
+    /// 为指定方法设置单独的限流参数
+    pub fn with_method_limit(mut self, method: impl Into<String>, limit: RateLimit) -> Self {
+        self.methods.insert(method.into(), limit);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FunctionResource, RustMCP, Settings};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn canonical_json_output_does_not_depend_on_hash_seeds() {
+        // 在新线程中创建注册项，其中的HashMap使用该线程的随机哈希种子
+        fn registry() -> RustMCP {
+            std::thread::spawn(|| {
+                let rustmcp = RustMCP::new().with_settings(Settings::new().with_canonical_json(true));
+                let meta: HashMap<String, serde_json::Value> =
+                    (0..32).map(|i| (format!("key{}", i), serde_json::json!(i))).collect();
+                rustmcp.add_resource(FunctionResource::from_function(
+                    || Ok(serde_json::json!("hello")),
+                    "config://app".to_string(),
+                    None,
+                    Some("App config".to_string()),
+                    None,
+                    None,
+                    None,
+                    Some(meta),
+                ));
+                rustmcp
+            })
+            .join()
+            .unwrap()
+        }
+
+        async fn respond(rustmcp: RustMCP) -> Vec<u8> {
+            let input = [
+                r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+                r#"{"jsonrpc":"2.0","id":2,"method":"resources/list"}"#,
+            ]
+            .join("\n");
+            let (writer, mut reader) = tokio::io::duplex(64 * 1024);
+            crate::server::stdio::serve_lines(Arc::new(rustmcp), input.as_bytes(), writer).await.unwrap();
+            let mut output = Vec::new();
+            reader.read_to_end(&mut output).await.unwrap();
+            output
+        }
+
+        let first = respond(registry()).await;
+        let second = respond(registry()).await;
+        assert_eq!(first, second);
+
+        let text = String::from_utf8(first).unwrap();
+        assert!(text.find(r#""key0""#).unwrap() < text.find(r#""key1""#).unwrap());
+        assert!(text.find(r#""key10""#).unwrap() < text.find(r#""key2""#).unwrap());
+    }
+}
@@ -0,0 +1,83 @@
+//! 统计堆内存，确认工具调用路径上不复制参数
+//!
+//! 替换了全局分配器，只有一个测试，避免并行的测试互相影响计数。
+
+use rustmcp::{FunctionTool, MemoryAuditSink, RustMCP, Settings, ToolArgs};
+use serde_json::Value;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+
+/// 统计当前和峰值的堆内存
+struct Counting;
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let live = LIVE.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK.fetch_max(live, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// 重置峰值，返回当前的堆内存
+fn mark() -> usize {
+    let live = LIVE.load(Ordering::SeqCst);
+    PEAK.store(live, Ordering::SeqCst);
+    live
+}
+
+const SIZE: usize = 8 * 1024 * 1024;
+
+#[tokio::test]
+async fn tool_arguments_are_not_copied() {
+    let settings = Settings::new().with_params_limits(32, 64 * 1024 * 1024);
+    let rustmcp = RustMCP::new().with_settings(settings).with_audit_sink(MemoryAuditSink::new(10));
+    rustmcp.add_tool(
+        FunctionTool::builder()
+            .name("upload")
+            .args(ToolArgs::new().string("document", "Base64 document", true))
+            .handler(|args| {
+                let document = args.as_ref().and_then(|args| args.get("document")).and_then(Value::as_str);
+                Ok(serde_json::json!(document.map_or(0, str::len)))
+            })
+            .build(),
+    );
+
+    // 直接调用：校验和审计都不复制参数，返回后参数已经释放
+    let arguments = HashMap::from([("document".to_string(), Value::String("QUJD".repeat(SIZE / 4)))]);
+    let before = mark();
+    assert_eq!(rustmcp.mcp_call_tool("upload", Some(arguments)).await.unwrap(), SIZE);
+    let peak = PEAK.load(Ordering::SeqCst) - before;
+    assert!(peak < SIZE / 4, "peak {} bytes above the arguments", peak);
+    assert!(LIVE.load(Ordering::SeqCst) + SIZE / 2 < before);
+
+    // JSON-RPC：读入的一行和解析出的参数之外没有副本，处理完后服务器不保留参数
+    let request = serde_json::json!({
+        "jsonrpc": "2.0", "id": 2, "method": "tools/call",
+        "params": { "name": "upload", "arguments": { "document": "QUJD".repeat(SIZE / 4) } }
+    });
+    let input = format!("{}\n{}\n", r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#, request);
+    drop(request);
+    let (writer, mut reader) = tokio::io::duplex(64 * 1024);
+    let before = mark();
+    rustmcp::server::stdio::serve_lines(Arc::new(rustmcp.clone()), input.as_bytes(), writer).await.unwrap();
+    let peak = PEAK.load(Ordering::SeqCst) - before;
+    let mut output = String::new();
+    reader.read_to_string(&mut output).await.unwrap();
+    assert!(output.contains(&format!(r#""text":"{}""#, SIZE)));
+    // 读入的一行按倍数扩容，最多约为参数的2倍，加上解析出的参数共约3倍
+    assert!(peak < SIZE * 7 / 2, "peak {} bytes", peak);
+    assert!(LIVE.load(Ordering::SeqCst) < before + SIZE / 4);
+}
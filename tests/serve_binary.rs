@@ -0,0 +1,98 @@
+//! 启动`rustmcp-serve`，用临时配置文件通过stdio调用工具
+#![cfg(feature = "cli")]
+
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const CONFIG: &str = r#"
+[server]
+allowed_commands = ["echo"]
+
+[[tools]]
+name = "echo"
+description = "Echoes back the provided message"
+command = ["echo", "{message}"]
+
+[tools.schema]
+type = "object"
+required = ["message"]
+
+[tools.schema.properties.message]
+type = "string"
+
+[[resources]]
+uri = "file:///welcome"
+name = "welcome"
+text = "Welcome"
+"#;
+
+/// 在临时目录中写入配置文件
+fn write_config(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rustmcp-serve-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+/// 以stdio传输运行，发送所有消息后关闭标准输入，返回输出的每一行
+fn run_stdio(config: &Path, messages: &[Value]) -> Vec<Value> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rustmcp-serve"))
+        .args(["--config", config.to_str().unwrap(), "--transport", "stdio"])
+        .env("RUST_LOG", "warn")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    for message in messages {
+        writeln!(stdin, "{}", message).unwrap();
+    }
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{:?}", output.status);
+    String::from_utf8(output.stdout).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+#[test]
+fn serves_configured_tool_over_stdio() {
+    let config = write_config("server.toml", CONFIG);
+    let lines = run_stdio(
+        &config,
+        &[
+            request(1, "initialize", json!({ "protocolVersion": "2025-06-18", "clientInfo": { "name": "test", "version": "1" } })),
+            json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+            request(2, "tools/list", json!({})),
+            request(3, "tools/call", json!({ "name": "echo", "arguments": { "message": "hello from the binary" } })),
+            request(4, "resources/read", json!({ "uri": "file:///welcome" })),
+        ],
+    );
+    let response = |id: u64| lines.iter().find(|line| line["id"] == id).unwrap_or_else(|| panic!("no response {} in {:?}", id, lines));
+
+    assert!(response(1)["result"]["serverInfo"]["name"].is_string());
+    assert_eq!(response(2)["result"]["tools"][0]["name"], "echo");
+    let result = &response(3)["result"];
+    assert_eq!(result["isError"], false, "{}", result);
+    assert!(result["content"][0]["text"].as_str().unwrap().contains("hello from the binary"), "{}", result);
+    assert_eq!(response(4)["result"]["contents"][0]["text"], "Welcome");
+}
+
+#[test]
+fn invalid_config_reports_file_line_and_key() {
+    let config = write_config("broken.toml", "[server]\nport = \"not a number\"\n");
+    let output = Command::new(env!("CARGO_BIN_EXE_rustmcp-serve"))
+        .args(["--config", config.to_str().unwrap(), "--transport", "stdio"])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("broken.toml:2: server.port:"), "{}", stderr);
+}